@@ -10,13 +10,26 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run
-    Run { file: std::path::PathBuf },
+    Run {
+        file: std::path::PathBuf,
+        /// Arguments passed to the script as `args`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Compile to bytecode, which `run` runs without compiling it again
+    Compile {
+        file: std::path::PathBuf,
+        /// Where to write the bytecode (default: the file with the extension `licoc`)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Run { file } => run::start(file),
+        Commands::Run { file, args } => run::start(file, args),
+        Commands::Compile { file, output } => run::compile(file, output.as_deref()),
     }
 }
@@ -1,83 +1,94 @@
 use lico_core::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub fn start(file: &PathBuf) {
-    let buf = std::fs::read_to_string(file).unwrap();
+pub fn start(file: &PathBuf, args: &[String]) {
+    let bytes = std::fs::read(file).unwrap();
+    // Bytecode made by `compile` is run as is, anything else is taken as source.
+    let (buf, program) = match bytes.starts_with(&vm::bytecode::MAGIC) {
+        true => match vm::bytecode::Program::deserialize(&bytes) {
+            // Runtime errors are shown in the source the bytecode was compiled from.
+            Ok(program) => (program.source.clone(), Some(program)),
+            Err(e) => {
+                eprintln!("{}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        },
+        false => (String::from_utf8(bytes).unwrap(), None),
+    };
     let buf_str = buf.as_str();
 
-    let (tokens, err) = lexer::parse(buf_str);
-    if !err.is_empty() {
-        for e in err {
-            println!("{e:?}");
+    let mut engine = Engine::new();
+
+    // The first Ctrl-C interrupts the script so that it can clean up. A second one, before the
+    // script has noticed the first, means it is stuck, so the process exits right away.
+    let interrupt = engine.interrupt_handle();
+    let _ = ctrlc::set_handler(move || {
+        if interrupt.is_interrupted() {
+            std::process::exit(130);
         }
-        return;
-    }
+        interrupt.interrupt();
+    });
 
-    let (tree, err) = parser::parse(&tokens);
-    if !err.is_empty() {
-        for e in err {
-            println!("{e:?}");
+    if program.is_none() {
+        // Errors are reported by `eval` below.
+        let file = file.display().to_string();
+        for warning in Engine::check(buf_str).unwrap_or_default() {
+            eprintln!("{}", Diagnostic::from(&warning).display_in(&file, buf_str));
         }
-        return;
     }
 
-    let code = match compiler::compile(&tree) {
-        Ok(x) => x,
+    engine.runtime.args = args.to_vec();
+    // `import` resolves relative paths against the script, and other modules in its directory
+    // and then in the ones listed in `LICO_PATH`.
+    let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+    engine.runtime.modules.root = Some(dir.clone());
+    engine.runtime.modules.search_path.push(dir);
+    if let Some(paths) = std::env::var_os("LICO_PATH") {
+        engine
+            .runtime
+            .modules
+            .search_path
+            .extend(std::env::split_paths(&paths));
+    }
+    let result = match &program {
+        Some(program) => engine.run_program(program),
+        None => engine.eval(buf_str),
+    };
+    match result {
+        Ok(outcome) => {
+            if outcome.exit_code != 0 {
+                engine.runtime.stdio.flush();
+                engine.runtime.stdio.flush_err();
+                std::process::exit(outcome.exit_code);
+            }
+        }
         Err(e) => {
-            println!("Compilation error: {:?}", e);
-            let (start, end) = match get_line_column_range(buf_str, e.span.to_range()) {
-                Some(x) => x,
-                None => {
-                    println!("Invalid span");
-                    return;
-                }
-            };
-            println!("Positon: {}:{} ~ {}:{}", start.0, start.1, end.0, end.1);
-            return;
+            let diagnostics = e.diagnostics();
+            eprint!(
+                "{}",
+                render(&diagnostics, &file.display().to_string(), buf_str)
+            );
+            std::process::exit(1);
         }
-    };
-
-    let mut runtime = vm::runtime::Runtime::new();
-    vm::execute(&code, &mut runtime).unwrap();
+    }
 }
 
-fn get_line_column_range(
-    source: &str,
-    span: std::ops::Range<u32>,
-) -> Option<((u32, u32), (u32, u32))> {
-    let source_len = {
-        let len = source.chars().count();
-        if len > u32::MAX as usize {
-            return None;
+pub fn compile(file: &Path, output: Option<&Path>) {
+    let source = std::fs::read_to_string(file).unwrap();
+    let bytecode = match Engine::compile(&source) {
+        Ok(bytecode) => bytecode,
+        Err(e) => {
+            let diagnostics = e.diagnostics();
+            eprint!(
+                "{}",
+                render(&diagnostics, &file.display().to_string(), &source)
+            );
+            std::process::exit(1);
         }
-        len as u32
     };
-
-    // Return None if the start and end positions of the range are outside the document range
-    if span.start >= source_len || span.end > source_len || span.start > span.end {
-        return None;
+    let output = output.map_or_else(|| file.with_extension("licoc"), Path::to_path_buf);
+    if let Err(e) = std::fs::write(&output, bytecode) {
+        eprintln!("{}: {}", output.display(), e);
+        std::process::exit(1);
     }
-
-    let line_start_offsets = {
-        let mut offsets = vec![0];
-        for (i, c) in source.char_indices() {
-            if c == '\n' {
-                offsets.push(i + 1);
-            }
-        }
-        offsets
-    };
-
-    let calc_line_column = |offset: u32| {
-        let line = line_start_offsets
-            .iter()
-            .position(|&line_start_offset| line_start_offset as u32 > offset)
-            .unwrap_or(line_start_offsets.len() - 1);
-        let column = offset - line_start_offsets[line - 1] as u32;
-        (line as u32, column)
-    };
-
-    let start = calc_line_column(span.start);
-    let end = calc_line_column(span.end);
-    Some((start, end))
 }
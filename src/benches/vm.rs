@@ -0,0 +1,124 @@
+//! Benchmarks for the compiler and the VM.
+//!
+//! Run `cargo bench` from `src/`. To compare a change against a baseline:
+//!
+//! ```sh
+//! git checkout main && cargo bench -- --save-baseline main
+//! git checkout my-branch && cargo bench -- --baseline main
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use lico_core::{compiler, lexer, parser, vm};
+
+fn compile(source: &str) -> Vec<vm::code::Code> {
+    let (tokens, errors) = lexer::parse(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (program, errors) = parser::parse(&tokens);
+    assert!(errors.is_empty(), "{:?}", errors);
+    compiler::compile(&program).unwrap()
+}
+
+const ARITHMETIC_LOOP: &str = r#"
+var sum = 0
+var i = 0
+while i < 100000 do
+    sum = sum + i * 2 % 7 - 1
+    i = i + 1
+end
+return sum
+"#;
+
+const TABLE_CHURN: &str = r#"
+var total = 0
+for i in 1->upto(10000) do
+    var t = { id = i, name = "item", value = i * 2 }
+    t.value = t.value + 1
+    t["extra"] = t.id
+    total = total + t.value + t.extra
+end
+return total
+"#;
+
+const CLOSURE_CREATION: &str = r#"
+var total = 0
+for i in 1->upto(10000) do
+    var add = func(x) return x + i end
+    total = add(total)
+end
+return total
+"#;
+
+const STRING_CONCAT: &str = r#"
+var s = ""
+for i in 1->upto(2000) do
+    s = s .. "x" .. i
+end
+return s
+"#;
+
+const RECURSIVE_CALL: &str = r#"
+func fib(n)
+    if n < 2 then
+        return n
+    end
+    return fib(n - 1) + fib(n - 2)
+end
+return fib(20)
+"#;
+
+fn large_source() -> String {
+    (0..500)
+        .map(|i| {
+            format!(
+                r#"
+func fizz_buzz_{i}(n)
+    var m3 = n % 3 == 0
+    var m5 = n % 5 == 0
+    if m3 and m5 then
+        return "FizzBuzz"
+    elif m3 then
+        return "Fizz"
+    elif m5 then
+        return "Buzz"
+    else
+        return n
+    end
+end
+var table_{i} = {{ key = [1, 2, 3], value = fizz_buzz_{i}({i}) }}
+"#
+            )
+        })
+        .collect()
+}
+
+fn bench_vm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vm");
+    let cases = [
+        ("arithmetic_loop", ARITHMETIC_LOOP),
+        ("table_churn", TABLE_CHURN),
+        ("closure_creation", CLOSURE_CREATION),
+        ("string_concat", STRING_CONCAT),
+        ("recursive_call", RECURSIVE_CALL),
+    ];
+    for (name, source) in cases {
+        let code = compile(source);
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(
+                vm::runtime::Runtime::new,
+                |runtime| vm::execute(black_box(&code), runtime).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_compiler(c: &mut Criterion) {
+    let source = large_source();
+    c.bench_function("compiler/parse_and_compile_large_file", |b| {
+        b.iter(|| compile(black_box(&source)))
+    });
+}
+
+criterion_group!(benches, bench_vm, bench_compiler);
+criterion_main!(benches);
@@ -2,47 +2,461 @@ use super::*;
 
 mod block;
 mod expression;
+mod fold;
+mod pattern;
 mod statement;
 mod util;
 
+/// Restrictions on what a compiled program may use, and how it is compiled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Only the builtins without side effects on the outside world are defined, so that the
+    /// program can't do I/O, run commands, or exit the process.
+    pub pure: bool,
+    /// Arithmetic and comparisons on locals and integer constants are compiled to register
+    /// instructions (`Code::Binary`, `Code::BinaryTo` and `Code::Move`), which run the same
+    /// program in fewer instructions than the stack ones.
+    pub registers: bool,
+}
+
+// The builtins defined when `Options::pure` is set.
+const PURE_BUILTINS: &[&str] = &[
+    "partial",
+    "compose",
+    "memoize",
+    "inspect",
+    "format",
+    "glob_match",
+    "hash",
+    "compress",
+    "decompress",
+    "error",
+    "coroutine",
+];
+
 pub fn compile<'src>(program: &'src Program<'src>) -> Result<Vec<vm::code::Code>> {
+    compile_with(program, Options::default())
+}
+
+/// Compiles `program` under the restrictions of `options`.
+///
+/// The output depends only on the program and the options: locals get `LocalId`s in the order
+/// they are defined, and captures are visited sorted by name, so no hash map order leaks into it.
+/// Compiling the same source always yields identical code, which caches rely on.
+pub fn compile_with<'src>(
+    program: &'src Program<'src>,
+    options: Options,
+) -> Result<Vec<vm::code::Code>> {
+    compile_full(program, options).map(|compiled| compiled.code)
+}
+
+/// A compiled program, with what the compiler found out about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Compiled {
+    pub code: Vec<vm::code::Code>,
+    /// Where each instruction of `code` came from in the source, by index. Functions keep theirs
+    /// in `FunctionPrototype::spans`.
+    pub spans: Vec<Option<TextSpan>>,
+    /// The warnings about the program, in the order they were found.
+    pub warnings: Vec<Warning>,
+}
+
+/// Like [`compile_with`], but also returns the spans of the instructions and the warnings.
+pub fn compile_full<'src>(program: &'src Program<'src>, options: Options) -> Result<Compiled> {
     use vm::code::{ArgumentKind, BuiltinInstr};
 
     let mut fragment = Fragment::new();
-    let mut context = Context::new();
+    let mut context = Context::with_options(options);
     for (capture, span) in program.body.captures.iter() {
+        if options.pure && !PURE_BUILTINS.contains(capture) {
+            return Err(Error::undefined_variable(capture.to_string(), *span));
+        }
         match *capture {
-            "print" => {
-                context.add_variable("print");
-                fragment.append_many([
-                    ICode::BeginFuncCreation,
-                    ICode::AddArgument(ArgumentKind::Auto),
+            name @ ("print" | "println" | "eprint" | "eprintln") => {
+                // func(...values) write(format_values(values) [.. "\n"]) flush() end
+                let (write, flush) = match name.starts_with('e') {
+                    true => (BuiltinInstr::WriteError, BuiltinInstr::FlushError),
+                    false => (BuiltinInstr::Write, BuiltinInstr::Flush),
+                };
+                let mut code = vec![
+                    ICode::LoadRustFunction(vm::builtin::format_values),
                     ICode::LoadLocal(VariableId::new_manual(0)),
-                    ICode::Builtin(BuiltinInstr::Write, 1),
-                    ICode::Builtin(BuiltinInstr::Flush, 0),
-                    ICode::LoadNil,
-                    ICode::Return,
-                    ICode::EndFuncCreation,
+                    ICode::Call(1, *span),
+                ];
+                if name.ends_with("ln") {
+                    code.extend([
+                        ICode::LoadString("\n".to_string()),
+                        ICode::Builtin(write, 2),
+                    ]);
+                } else {
+                    code.push(ICode::Builtin(write, 1));
+                }
+                code.extend([ICode::Builtin(flush, 0), ICode::LoadNil, ICode::Return]);
+                context.add_variable(name);
+                fragment.append_many([
+                    ICode::MakeClosure {
+                        name: None,
+                        captures: Vec::new(),
+                        args: vec![ArgumentKind::Rest],
+                        code,
+                    },
                     ICode::MakeLocal,
                 ]);
             }
-            "println" => {
-                context.add_variable("println");
+            name @ ("read_line" | "read") => {
+                let instr = match name {
+                    "read_line" => BuiltinInstr::ReadLine,
+                    _ => BuiltinInstr::Read,
+                };
+                context.add_variable(name);
+                fragment.append_many([builtin_closure(instr, 1, true), ICode::MakeLocal]);
+            }
+            "read_all" => {
+                context.add_variable("read_all");
                 fragment.append_many([
-                    ICode::BeginFuncCreation,
-                    ICode::AddArgument(ArgumentKind::Auto),
-                    ICode::LoadLocal(VariableId::new_manual(0)),
-                    ICode::LoadString("\n".to_string()),
-                    ICode::Builtin(BuiltinInstr::Write, 2),
-                    ICode::Builtin(BuiltinInstr::Flush, 0),
-                    ICode::LoadNil,
-                    ICode::Return,
-                    ICode::EndFuncCreation,
+                    builtin_closure(BuiltinInstr::ReadAll, 0, true),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "partial" => {
+                context.add_variable("partial");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::partial),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "compose" => {
+                context.add_variable("compose");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::compose),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "memoize" => {
+                context.add_variable("memoize");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::memoize),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "inspect" => {
+                context.add_variable("inspect");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::inspect),
                     ICode::MakeLocal,
                 ]);
             }
-            "require" => {
-                unimplemented!("require")
+            "format" => {
+                context.add_variable("format");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::format),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "log" => {
+                // log = { debug = func(msg, fields), info = .., warn = .., error = ..,
+                //         enabled = func(level) }
+                context.add_variable("log");
+                let levels = ["debug", "info", "warn", "error"];
+                for (level, name) in levels.iter().enumerate() {
+                    fragment.append_many([
+                        ICode::MakeClosure {
+                            name: None,
+                            captures: Vec::new(),
+                            args: vec![ArgumentKind::Auto, ArgumentKind::Auto],
+                            code: vec![
+                                ICode::LoadInt(level as i64),
+                                ICode::LoadLocal(VariableId::new_manual(0)),
+                                ICode::LoadLocal(VariableId::new_manual(1)),
+                                ICode::Builtin(BuiltinInstr::Log, 3),
+                                ICode::LoadNil,
+                                ICode::Return,
+                            ],
+                        },
+                        ICode::LoadString(name.to_string()),
+                        ICode::MakeNamed,
+                    ]);
+                }
+                fragment.append_many([
+                    builtin_closure(BuiltinInstr::LogEnabled, 1, true),
+                    ICode::LoadString("enabled".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(levels.len() as u32 + 1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "time" => {
+                // time = { sleep = func(seconds), monotonic = <rust function> }
+                context.add_variable("time");
+                fragment.append_many([
+                    ICode::MakeClosure {
+                        name: None,
+                        captures: Vec::new(),
+                        args: vec![ArgumentKind::Auto],
+                        code: vec![
+                            ICode::LoadLocal(VariableId::new_manual(0)),
+                            ICode::Builtin(BuiltinInstr::Sleep, 1),
+                            ICode::LoadNil,
+                            ICode::Return,
+                        ],
+                    },
+                    ICode::LoadString("sleep".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::monotonic),
+                    ICode::LoadString("monotonic".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(2),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "exit" => {
+                context.add_variable("exit");
+                fragment.append_many([
+                    builtin_closure(BuiltinInstr::Exit, 1, false),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "error" => {
+                context.add_variable("error");
+                fragment.append_many([
+                    builtin_closure(BuiltinInstr::Error, 1, false),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "coroutine" => {
+                // coroutine = { create = <rust function>, resume = func(co, value), status = .. }
+                context.add_variable("coroutine");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::coroutine_create),
+                    ICode::LoadString("create".to_string()),
+                    ICode::MakeNamed,
+                    builtin_closure(BuiltinInstr::Resume, 2, true),
+                    ICode::LoadString("resume".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::coroutine_status),
+                    ICode::LoadString("status".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(3),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "on_interrupt" => {
+                context.add_variable("on_interrupt");
+                fragment.append_many([
+                    builtin_closure(BuiltinInstr::OnInterrupt, 1, false),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "args" => {
+                context.add_variable("args");
+                fragment.append_many([ICode::Builtin(BuiltinInstr::Args, 0), ICode::MakeLocal]);
+            }
+            "os" => {
+                // os = { args = <the script arguments> }
+                context.add_variable("os");
+                fragment.append_many([
+                    ICode::Builtin(BuiltinInstr::Args, 0),
+                    ICode::LoadString("args".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "clock" => {
+                context.add_variable("clock");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::clock),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "glob_match" => {
+                context.add_variable("glob_match");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::glob_match),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "fs" => {
+                context.add_variable("fs");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::fs_glob),
+                    ICode::LoadString("glob".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fs_lines),
+                    ICode::LoadString("lines".to_string()),
+                    ICode::MakeNamed,
+                    builtin_closure(BuiltinInstr::ReadFile, 2, true),
+                    ICode::LoadString("read".to_string()),
+                    ICode::MakeNamed,
+                    builtin_closure(BuiltinInstr::WriteFile, 2, false),
+                    ICode::LoadString("write".to_string()),
+                    ICode::MakeNamed,
+                    builtin_closure(BuiltinInstr::ReadBytes, 1, true),
+                    ICode::LoadString("read_bytes".to_string()),
+                    ICode::MakeNamed,
+                    builtin_closure(BuiltinInstr::WriteBytes, 2, false),
+                    ICode::LoadString("write_bytes".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fs_open),
+                    ICode::LoadString("open".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fs_walk),
+                    ICode::LoadString("walk".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fs_write_atomic),
+                    ICode::LoadString("write_atomic".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fs_lock),
+                    ICode::LoadString("lock".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(10),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "io" => {
+                context.add_variable("io");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::io_lines),
+                    ICode::LoadString("lines".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "term" => {
+                context.add_variable("term");
+                #[cfg_attr(not(feature = "term"), allow(unused_mut))]
+                let mut fields = vec![
+                    ("color", ICode::LoadRustFunction(vm::builtin::term_color)),
+                    (
+                        "is_terminal",
+                        builtin_closure(BuiltinInstr::IsTerminal, 0, true),
+                    ),
+                    ("size", builtin_closure(BuiltinInstr::TerminalSize, 0, true)),
+                    (
+                        "clear",
+                        builtin_closure(BuiltinInstr::ClearScreen, 0, false),
+                    ),
+                    (
+                        "move_to",
+                        builtin_closure(BuiltinInstr::MoveCursor, 2, false),
+                    ),
+                    (
+                        "show_cursor",
+                        builtin_closure(BuiltinInstr::ShowCursor, 1, false),
+                    ),
+                ];
+                #[cfg(feature = "term")]
+                fields.push(("read_key", builtin_closure(BuiltinInstr::ReadKey, 0, true)));
+                let len = fields.len() as u32;
+                for (name, value) in fields {
+                    fragment.append_many([
+                        value,
+                        ICode::LoadString(name.to_string()),
+                        ICode::MakeNamed,
+                    ]);
+                }
+                fragment.append_many([ICode::MakeTable(len), ICode::MakeLocal]);
+            }
+            "subprocess" => {
+                context.add_variable("subprocess");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::subprocess_spawn),
+                    ICode::LoadString("spawn".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            "env" => {
+                context.add_variable("env");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::env_load),
+                    ICode::LoadString("load".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "plugin")]
+            "plugin" => {
+                context.add_variable("plugin");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::plugin::plugin_load),
+                    ICode::LoadString("load".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "ffi")]
+            "ffi" => {
+                context.add_variable("ffi");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::ffi_open),
+                    ICode::LoadString("open".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "random")]
+            "uuid" => {
+                context.add_variable("uuid");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::uuid_v4),
+                    ICode::LoadString("v4".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "random")]
+            "random_id" => {
+                context.add_variable("random_id");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::random_id),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "hash")]
+            "hash" => {
+                context.add_variable("hash");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::sha256),
+                    ICode::LoadString("sha256".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::sha1),
+                    ICode::LoadString("sha1".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::md5),
+                    ICode::LoadString("md5".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::crc32),
+                    ICode::LoadString("crc32".to_string()),
+                    ICode::MakeNamed,
+                    ICode::LoadRustFunction(vm::builtin::fnv),
+                    ICode::LoadString("fnv".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(5),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "compress")]
+            "compress" => {
+                context.add_variable("compress");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::compress),
+                    ICode::MakeLocal,
+                ]);
+            }
+            #[cfg(feature = "compress")]
+            "decompress" => {
+                context.add_variable("decompress");
+                fragment.append_many([
+                    ICode::LoadRustFunction(vm::builtin::decompress),
+                    ICode::MakeLocal,
+                ]);
             }
             name => {
                 return Err(Error::undefined_variable(name.to_string(), *span));
@@ -50,9 +464,38 @@ pub fn compile<'src>(program: &'src Program<'src>) -> Result<Vec<vm::code::Code>
         }
     }
     fragment.append_compile(&program.body.block, &mut context)?;
-    if !matches!(fragment.last(), Some(ICode::Return)) {
+    if !util::always_returns(&program.body.block) {
         fragment.append_many([ICode::LoadNil, ICode::Return]);
     }
 
-    Ok(fragment.into_code())
+    let (code, spans) = fragment.into_code();
+    let (code, spans) = optimize::optimize(&code, &spans);
+    let (code, spans) = match options.registers {
+        true => registers::translate(&code, &spans),
+        false => (code, spans),
+    };
+    Ok(Compiled {
+        code,
+        spans,
+        warnings: context.take_warnings(),
+    })
+}
+
+// func(arg1, ..., argN) return <instr>(arg1, ..., argN) end
+// If the builtin returns nothing, the function returns nil.
+fn builtin_closure(instr: vm::code::BuiltinInstr, args_len: u8, returns: bool) -> ICode {
+    let mut code = (0..args_len)
+        .map(|i| ICode::LoadLocal(VariableId::new_manual(i as usize)))
+        .collect::<Vec<_>>();
+    code.push(ICode::Builtin(instr, args_len));
+    if !returns {
+        code.push(ICode::LoadNil);
+    }
+    code.push(ICode::Return);
+    ICode::MakeClosure {
+        name: None,
+        captures: Vec::new(),
+        args: vec![vm::code::ArgumentKind::Auto; args_len as usize],
+        code,
+    }
 }
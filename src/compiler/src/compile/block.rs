@@ -3,10 +3,22 @@ use super::*;
 impl<'node, 'src: 'node> Compilable<'node, 'src> for Block<'src> {
     fn compile(&'node self, fragment: &mut Fragment, context: &mut Context<'src>) -> Result<()> {
         context.begin_block();
-        for statement in self.iter() {
+        for (i, statement) in self.iter().enumerate() {
             statement.compile(fragment, context)?;
+            if let Statement::Return { .. }
+            | Statement::ReturnMany { .. }
+            | Statement::Break
+            | Statement::Continue = statement.0
+            {
+                if let Some(span) = util::statements_span(&self[i + 1..]) {
+                    context.warn(Warning::unreachable_code(span));
+                }
+                break;
+            }
         }
-        if !matches!(fragment.last(), Some(ICode::Return)) {
+        // The code may end with a return of a nested block that others jump past, e.g. the
+        // `else` of an `if`, which still needs the locals dropped.
+        if !util::always_returns(self) {
             let drop_count = context.get_block_local_count();
             if drop_count > 0 {
                 fragment.append(ICode::DropLocal(drop_count));
@@ -24,6 +24,12 @@ fn compile<'node, 'src: 'node>(
     fragment: &mut Fragment,
     context: &mut Context<'src>,
 ) -> Result<()> {
+    if let Expression::Unary { .. } | Expression::Binary { .. } = expr {
+        if let Some(value) = fold::constant(expr) {
+            fragment.append(fold::load(value));
+            return Ok(());
+        }
+    }
     match expr {
         Expression::Unary { op, expr } => match op {
             UnaryOp::Neg => {
@@ -33,18 +39,7 @@ fn compile<'node, 'src: 'node>(
                 Ok(())
             }
             UnaryOp::Not => {
-                // 0: eval expr
-                // 1: jump_if_true 4
-                // 2: load false
-                // 3: jump 5
-                // 4: load true
-                // 5: ..
-                fragment.append_compile(expr, context)?.append_many([
-                    ICode::JumpIfTrue(3),
-                    ICode::LoadBool(false),
-                    ICode::Jump(2),
-                    ICode::LoadBool(true),
-                ]);
+                fragment.append_compile(expr, context)?.append(ICode::Not);
                 Ok(())
             }
             UnaryOp::BNot => {
@@ -90,6 +85,20 @@ fn compile<'node, 'src: 'node>(
                     .append(ICode::Mod(span));
                 Ok(())
             }
+            BinaryOp::Pow => {
+                fragment
+                    .append_compile(lhs, context)?
+                    .append_compile(rhs, context)?
+                    .append(ICode::Pow(span));
+                Ok(())
+            }
+            BinaryOp::FloorDiv => {
+                fragment
+                    .append_compile(lhs, context)?
+                    .append_compile(rhs, context)?
+                    .append(ICode::FloorDiv(span));
+                Ok(())
+            }
             BinaryOp::Eq => {
                 fragment
                     .append_compile(lhs, context)?
@@ -132,38 +141,53 @@ fn compile<'node, 'src: 'node>(
                     .append(ICode::GreaterEq(span));
                 Ok(())
             }
+            BinaryOp::In => {
+                fragment
+                    .append_compile(lhs, context)?
+                    .append_compile(rhs, context)?
+                    .append(ICode::In(span));
+                Ok(())
+            }
             BinaryOp::And => {
-                // If lhs is true, then evaluate rhs
+                // If lhs is truthy, then evaluate rhs, or else lhs is the result
                 //   0: eval lhs
-                //   1: jump_if_false 4
+                //   1: jump_if_false_or_pop 3
                 //   2: eval rhs
-                //   3: jump 5
-                //   4: push false
-                //   5: ...
+                //   3: ...
                 let lhs_fragment = Fragment::with_compile(lhs, context)?;
                 let rhs_fragment = Fragment::with_compile(rhs, context)?;
                 fragment
                     .append_fragment(lhs_fragment)
-                    .append(ICode::JumpIfFalse(rhs_fragment.len() as isize + 2))
-                    .append_fragment(rhs_fragment)
-                    .append_many([ICode::Jump(2), ICode::LoadBool(false)]);
+                    .append(ICode::JumpIfFalseOrPop(rhs_fragment.len() as isize + 1))
+                    .append_fragment(rhs_fragment);
                 Ok(())
             }
             BinaryOp::Or => {
-                // If lhs is false, then evaluate rhs
+                // If lhs is falsy, then evaluate rhs, or else lhs is the result
                 //   0: eval lhs
-                //   1: jump_if_true 4
+                //   1: jump_if_true_or_pop 3
                 //   2: eval rhs
-                //   3: jump 5
-                //   4: push true
-                //   5: ...
+                //   3: ...
                 let lhs_fragment = Fragment::with_compile(lhs, context)?;
                 let rhs_fragment = Fragment::with_compile(rhs, context)?;
                 fragment
                     .append_fragment(lhs_fragment)
-                    .append(ICode::JumpIfTrue(rhs_fragment.len() as isize + 2))
-                    .append_fragment(rhs_fragment)
-                    .append_many([ICode::Jump(2), ICode::LoadBool(true)]);
+                    .append(ICode::JumpIfTrueOrPop(rhs_fragment.len() as isize + 1))
+                    .append_fragment(rhs_fragment);
+                Ok(())
+            }
+            BinaryOp::NilCoalesce => {
+                // If lhs is nil, then evaluate rhs
+                //   0: eval lhs
+                //   1: jump_if_not_nil 3
+                //   2: eval rhs
+                //   3: ...
+                let lhs_fragment = Fragment::with_compile(lhs, context)?;
+                let rhs_fragment = Fragment::with_compile(rhs, context)?;
+                fragment
+                    .append_fragment(lhs_fragment)
+                    .append(ICode::JumpIfNotNil(rhs_fragment.len() as isize + 1))
+                    .append_fragment(rhs_fragment);
                 Ok(())
             }
             BinaryOp::BitAnd => {
@@ -216,7 +240,50 @@ fn compile<'node, 'src: 'node>(
                     .append(ICode::Concat(span));
                 Ok(())
             }
+
+            // `x |> f(a)` is compiled as `f(x, a)`, and `x |> t->m(a)` as `t->m(x, a)`.
+            BinaryOp::Pipeline => {
+                let rhs_span = rhs.1;
+                match rhs.0.as_ref() {
+                    Expression::Call { expr, args } => {
+                        fragment
+                            .append_compile(expr, context)?
+                            .append_compile(lhs, context)?;
+                        let call = match append_args(fragment, 1, args, rhs_span, context)? {
+                            Some(count) => ICode::Call(count, rhs_span),
+                            None => ICode::CallSpread(rhs_span),
+                        };
+                        fragment.append(call);
+                    }
+                    Expression::MethodCall {
+                        expr,
+                        name: (name, _),
+                        args,
+                    } => {
+                        fragment
+                            .append_compile(expr, context)?
+                            .append_compile(lhs, context)?;
+                        let name = name.to_string().into();
+                        let call = match append_args(fragment, 1, args, rhs_span, context)? {
+                            Some(count) => ICode::CallMethod(name, count, rhs_span),
+                            None => ICode::CallMethodSpread(name, rhs_span),
+                        };
+                        fragment.append(call);
+                    }
+                    _ => {
+                        fragment
+                            .append_compile(rhs, context)?
+                            .append_compile(lhs, context)?
+                            .append(ICode::Call(1, span));
+                    }
+                }
+                Ok(())
+            }
         },
+        Expression::Local(name, _) if context.is_global(name) => {
+            fragment.append(ICode::LoadGlobal(name.to_string()));
+            Ok(())
+        }
         Expression::Local(name, _) => {
             let id = context
                 .resolve_variable(name)
@@ -263,56 +330,223 @@ fn compile<'node, 'src: 'node>(
             Ok(())
         }
         Expression::ArrayObject(array) => {
-            fragment
-                .append_compile_many(array.iter(), context)?
-                .append(ICode::MakeArray(array.len() as u32));
+            if has_spread(array) {
+                append_spread_array(fragment, 0, array, span, context)?;
+            } else {
+                fragment
+                    .append_compile_many(array.iter(), context)?
+                    .append(ICode::MakeArray(array.len() as u32));
+            }
             Ok(())
         }
+        Expression::Spread { .. } => Err(Error::misplaced_spread(span)),
         Expression::FunctionObject(function) => {
-            util::append_func_creation_fragment(fragment, &function.body, &function.args, context)?;
+            util::append_func_creation_fragment(
+                fragment,
+                None,
+                &function.body,
+                &function.args,
+                None,
+                context,
+            )?;
             Ok(())
         }
-        Expression::Call { expr, args } => {
-            fragment
-                .append_compile(expr, context)?
-                .append_compile_many(args.iter(), context)?
-                .append(ICode::Call(args.len() as u8, span));
+        Expression::Call { .. }
+        | Expression::MethodCall { .. }
+        | Expression::IndexAccess { .. }
+        | Expression::DotAccess { .. }
+        | Expression::SliceAccess { .. }
+        | Expression::Optional { .. } => {
+            let mut exits = Vec::new();
+            compile_chain(expr, span, fragment, context, &mut exits)?;
+            fragment.patch_nil_jumps(exits);
+            Ok(())
+        }
+        Expression::If {
+            cond,
+            then,
+            elifs,
+            else_,
+        } => {
+            // Lowered as the `If` statement: each arm is [cond] [jump if false] [value] [jump],
+            // where the last jump is to the end, after the [value] of `else_`.
+            let mut arms = Vec::new();
+            let conds = std::iter::once(((&*cond.0, cond.1), (&*then.0, then.1))).chain(
+                elifs.iter().map(|((cond, cond_span), (then, then_span))| {
+                    ((cond, *cond_span), (then, *then_span))
+                }),
+            );
+            for (cond, then) in conds {
+                let then = Fragment::with_compile(&then, context)?;
+                let mut arm = Fragment::with_compile(&cond, context)?;
+                arm.append(ICode::JumpIfFalse(then.len() as isize + 2))
+                    .append_fragment(then);
+                arms.push(arm);
+            }
+            arms.push(Fragment::with_compile(else_, context)?);
+            let mut jump_dist = arms.last().unwrap().len() + 1;
+            for arm in arms.iter_mut().rev().skip(1) {
+                arm.append(ICode::Jump(jump_dist as isize));
+                jump_dist += arm.len();
+            }
+            fragment.append_fragment_many(arms);
             Ok(())
         }
+        Expression::Yield { value } => {
+            match value {
+                Some(value) => fragment.append_compile(value, context)?,
+                None => fragment.append(ICode::LoadNil),
+            };
+            fragment.append(ICode::Yield(span));
+            Ok(())
+        }
+        Expression::Import { path: (path, _) } => {
+            // Importing runs other files, which pure code must not do.
+            if context.options().pure {
+                return Err(Error::import_not_allowed(span));
+            }
+            fragment.append_many([ICode::LoadString(path.to_string()), ICode::Import(span)]);
+            Ok(())
+        }
+        Expression::Error => todo!(),
+    }
+}
+
+/// Compiles `expr`, the last link of a chain of calls and accesses such as `a?.b.c()`, after the
+/// links before it. The receiver of a `?.` or `?->` is followed by a placeholder, whose position is
+/// added to `exits`, for a jump over the rest of the chain when it is nil.
+pub(super) fn compile_chain<'node, 'src: 'node>(
+    expr: &'node Expression<'src>,
+    span: TextSpan,
+    fragment: &mut Fragment,
+    context: &mut Context<'src>,
+    exits: &mut Vec<usize>,
+) -> Result<()> {
+    match expr {
+        Expression::Call {
+            expr: (callee, callee_span),
+            args,
+        } => {
+            compile_chain(callee, *callee_span, fragment, context, exits)?;
+            let call = match append_args(fragment, 0, args, span, context)? {
+                Some(count) => ICode::Call(count, span),
+                None => ICode::CallSpread(span),
+            };
+            fragment.append(call);
+        }
         Expression::MethodCall {
-            expr,
+            expr: (receiver, receiver_span),
             name: (name, _),
             args,
         } => {
-            fragment
-                .append_compile(expr, context)?
-                .append_compile_many(args.iter(), context)?
-                .append(ICode::CallMethod(
-                    name.to_string().into(),
-                    args.len() as u8,
-                    span,
-                ));
-            Ok(())
+            compile_chain(receiver, *receiver_span, fragment, context, exits)?;
+            let name = name.to_string().into();
+            let call = match append_args(fragment, 0, args, span, context)? {
+                Some(count) => ICode::CallMethod(name, count, span),
+                None => ICode::CallMethodSpread(name, span),
+            };
+            fragment.append(call);
         }
-        Expression::IndexAccess { expr, accessor } => {
+        Expression::IndexAccess {
+            expr: (table, table_span),
+            accessor,
+        } => {
+            compile_chain(table, *table_span, fragment, context, exits)?;
             fragment
-                .append_compile(expr, context)?
                 .append_compile(accessor, context)?
                 .append(ICode::GetItem(span));
-            Ok(())
         }
         Expression::DotAccess {
-            expr,
+            expr: (table, table_span),
             accessor: (accessor, _),
         } => {
+            compile_chain(table, *table_span, fragment, context, exits)?;
             fragment
-                .append_compile(expr, context)?
                 .append(ICode::LoadString(accessor.to_string()))
                 .append(ICode::GetItem(span));
-            Ok(())
         }
-        Expression::Error => todo!(),
+        Expression::SliceAccess {
+            expr: (target, target_span),
+            start,
+            end,
+        } => {
+            compile_chain(target, *target_span, fragment, context, exits)?;
+            for bound in [start, end] {
+                match bound {
+                    Some(bound) => fragment.append_compile(bound, context)?,
+                    None => fragment.append(ICode::LoadNil),
+                };
+            }
+            fragment.append(ICode::GetSlice(span));
+        }
+        Expression::Optional {
+            expr: (receiver, receiver_span),
+        } => {
+            compile_chain(receiver, *receiver_span, fragment, context, exits)?;
+            exits.push(fragment.len());
+            fragment.append(ICode::Placeholder);
+        }
+        expr => compile(expr, span, fragment, context)?,
     }
+    Ok(())
+}
+
+/// Appends `args` of the call at `span` after the `pushed` arguments already pushed, and returns
+/// the count of them all. If some are spread, they are all gathered into one array instead, and
+/// `None` is returned.
+pub(super) fn append_args<'node, 'src: 'node>(
+    fragment: &mut Fragment,
+    pushed: usize,
+    args: &'node [(Expression<'src>, TextSpan)],
+    span: TextSpan,
+    context: &mut Context<'src>,
+) -> Result<Option<u8>> {
+    if !has_spread(args) {
+        fragment.append_compile_many(args.iter(), context)?;
+        return Ok(Some((pushed + args.len()) as u8));
+    }
+    append_spread_array(fragment, pushed, args, span, context)?;
+    Ok(None)
+}
+
+fn has_spread(items: &[(Expression, TextSpan)]) -> bool {
+    items
+        .iter()
+        .any(|(item, _)| matches!(item, Expression::Spread { .. }))
+}
+
+// Pushes an array of the `pushed` items already pushed and `items`, with the spread ones
+// flattened, by making arrays of the items between them:
+//   [a, b, ...c, d] → [a, b] c [d] make_array_spread 3
+fn append_spread_array<'node, 'src: 'node>(
+    fragment: &mut Fragment,
+    pushed: usize,
+    items: &'node [(Expression<'src>, TextSpan)],
+    span: TextSpan,
+    context: &mut Context<'src>,
+) -> Result<()> {
+    let mut arrays = 0;
+    let mut pending = pushed;
+    for item in items {
+        if let Expression::Spread { expr } = &item.0 {
+            if pending > 0 {
+                fragment.append(ICode::MakeArray(pending as u32));
+                arrays += 1;
+                pending = 0;
+            }
+            fragment.append_compile(expr, context)?;
+            arrays += 1;
+        } else {
+            fragment.append_compile(item, context)?;
+            pending += 1;
+        }
+    }
+    if pending > 0 {
+        fragment.append(ICode::MakeArray(pending as u32));
+        arrays += 1;
+    }
+    fragment.append(ICode::MakeArraySpread(arrays, span));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -340,13 +574,11 @@ mod tests {
             &mut context,
         );
         assert_eq!(
-            fragment.unwrap().into_code(),
+            fragment.unwrap().into_code().0,
             vec![
                 Code::LoadLocal(LocalId(0)),
-                Code::JumpIfFalse(3),
+                Code::JumpIfFalseOrPop(2),
                 Code::LoadLocal(LocalId(1)),
-                Code::Jump(2),
-                Code::LoadBool(false)
             ]
         );
     }
@@ -370,14 +602,34 @@ mod tests {
             &mut context,
         );
         assert_eq!(
-            fragment.unwrap().into_code(),
+            fragment.unwrap().into_code().0,
             vec![
                 Code::LoadLocal(LocalId(0)),
-                Code::JumpIfTrue(3),
+                Code::JumpIfTrueOrPop(2),
                 Code::LoadLocal(LocalId(1)),
-                Code::Jump(2),
-                Code::LoadBool(true)
             ]
         );
     }
+
+    #[test]
+    fn not() {
+        let mut context = Context::new();
+        context.begin_block();
+        context.add_variable("a");
+        let dummy_span = TextSpan::new(0, 0);
+        let fragment = Fragment::with_compile(
+            &(
+                Expression::Unary {
+                    op: UnaryOp::Not,
+                    expr: (Box::new(Expression::Local("a", dummy_span)), dummy_span),
+                },
+                dummy_span,
+            ),
+            &mut context,
+        );
+        assert_eq!(
+            fragment.unwrap().into_code().0,
+            vec![Code::LoadLocal(LocalId(0)), Code::Not]
+        );
+    }
 }
@@ -0,0 +1,97 @@
+use super::*;
+use vm::runtime::Object;
+
+/// Evaluates `expr` at compile time, if it is made only of literals and of operators that
+/// always give the same result for them. Operations that fail or overflow are left to the runtime,
+/// so that they fail when the program runs.
+pub fn constant(expr: &Expression) -> Option<Object> {
+    match expr {
+        Expression::Primitive(primitive, _) => Some(match primitive {
+            Primitive::Int(x) => Object::Int(*x),
+            Primitive::Float(x) => Object::Float(*x),
+            Primitive::String(x) => Object::new_string(x.to_string()),
+            Primitive::Bool(x) => Object::Bool(*x),
+            Primitive::Nil => Object::Nil,
+        }),
+        Expression::Unary { op, expr } => match (op, constant(&expr.0)?) {
+            (UnaryOp::Neg, Object::Int(x)) => x.checked_neg().map(Object::Int),
+            (UnaryOp::Neg, Object::Float(x)) => Some(Object::Float(-x)),
+            (UnaryOp::Not, x) => Some(Object::Bool(!x.is_truthy())),
+            (UnaryOp::BNot, Object::Int(x)) => Some(Object::Int(!x)),
+            _ => None,
+        },
+        Expression::Binary {
+            op:
+                op @ (BinaryOp::BitAnd
+                | BinaryOp::BitOr
+                | BinaryOp::BitXor
+                | BinaryOp::ShiftLeft
+                | BinaryOp::ShiftRight),
+            lhs,
+            rhs,
+        } => {
+            let (Object::Int(lhs), Object::Int(rhs)) = (constant(&lhs.0)?, constant(&rhs.0)?)
+            else {
+                return None;
+            };
+            let shift = u32::try_from(rhs).ok().filter(|bits| *bits < 64);
+            let value = match op {
+                BinaryOp::BitAnd => lhs & rhs,
+                BinaryOp::BitOr => lhs | rhs,
+                BinaryOp::BitXor => lhs ^ rhs,
+                BinaryOp::ShiftLeft => lhs << shift?,
+                _ => lhs >> shift?,
+            };
+            Some(Object::Int(value))
+        }
+        Expression::Binary { op, lhs, rhs } => {
+            use vm::code::BinaryOp as Op;
+            let op = match op {
+                BinaryOp::Add => Op::Add,
+                BinaryOp::Sub => Op::Sub,
+                BinaryOp::Mul => Op::Mul,
+                BinaryOp::Div => Op::Div,
+                BinaryOp::Mod => Op::Mod,
+                BinaryOp::Pow => Op::Pow,
+                BinaryOp::FloorDiv => Op::FloorDiv,
+                BinaryOp::Eq => Op::Eq,
+                BinaryOp::NotEq => Op::NotEq,
+                BinaryOp::Less => Op::Less,
+                BinaryOp::LessEq => Op::LessEq,
+                BinaryOp::Greater => Op::Greater,
+                BinaryOp::GreaterEq => Op::GreaterEq,
+                BinaryOp::Concat => Op::Concat,
+                _ => return None,
+            };
+            let lhs = constant(&lhs.0)?;
+            let rhs = constant(&rhs.0)?;
+            if let (Object::Int(lhs), Object::Int(rhs)) = (&lhs, &rhs) {
+                let fits = match op {
+                    Op::Add => lhs.checked_add(*rhs).is_some(),
+                    Op::Sub => lhs.checked_sub(*rhs).is_some(),
+                    Op::Mul => lhs.checked_mul(*rhs).is_some(),
+                    Op::Div => lhs.checked_div(*rhs).is_some(),
+                    Op::Mod => lhs.checked_rem(*rhs).is_some(),
+                    _ => true,
+                };
+                if !fits {
+                    return None;
+                }
+            }
+            op.apply(lhs, rhs).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Loads `value`, which [`constant`] computed.
+pub fn load(value: Object) -> ICode {
+    match value {
+        Object::Int(x) => ICode::LoadInt(x),
+        Object::Float(x) => ICode::LoadFloat(x),
+        Object::Bool(x) => ICode::LoadBool(x),
+        Object::String(x) => ICode::LoadString(x.to_string()),
+        Object::Nil => ICode::LoadNil,
+        value => unreachable!("{} is not a constant", value.typename()),
+    }
+}
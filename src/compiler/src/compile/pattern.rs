@@ -0,0 +1,143 @@
+use super::*;
+use vm::code::BuiltinInstr;
+
+/// The code matching a pattern against the value in a local.
+pub struct PatternCode<'src> {
+    /// Pushes whether the value matches, or is empty if every value does.
+    pub test: Fragment,
+    /// Makes a local for each of `names`, in order, from the parts of the value.
+    pub bind: Fragment,
+    pub names: Vec<(&'src str, TextSpan)>,
+}
+
+// How a part of the value is reached from it.
+#[derive(Clone, Copy)]
+enum Access<'src> {
+    Index(usize),
+    Key(&'src str),
+}
+
+/// Compiles `pattern` matched against the value in the local `value`. The caller defines the
+/// bound names after `bind`, as variables or as parameters.
+pub fn compile_pattern<'src>(
+    pattern: &Pattern<'src>,
+    span: TextSpan,
+    value: VariableId,
+    context: &mut Context<'src>,
+) -> Result<PatternCode<'src>> {
+    let mut tests = Vec::new();
+    let mut code = PatternCode {
+        test: Fragment::new(),
+        bind: Fragment::new(),
+        names: Vec::new(),
+    };
+    let mut path = Vec::new();
+    append_pattern(
+        pattern, span, value, &mut path, &mut tests, &mut code, context,
+    )?;
+
+    // The tests are joined as with `and`, so that a part is only looked into once the tests
+    // before it, e.g. on its type or length, have passed.
+    if let Some(mut test) = tests.pop() {
+        while let Some(mut previous) = tests.pop() {
+            previous
+                .append(ICode::JumpIfFalseOrPop(test.len() as isize + 1))
+                .append_fragment(test);
+            test = previous;
+        }
+        code.test = test;
+    }
+    Ok(code)
+}
+
+fn append_pattern<'src>(
+    pattern: &Pattern<'src>,
+    span: TextSpan,
+    value: VariableId,
+    path: &mut Vec<Access<'src>>,
+    tests: &mut Vec<Fragment>,
+    code: &mut PatternCode<'src>,
+    context: &mut Context<'src>,
+) -> Result<()> {
+    match pattern {
+        Pattern::Literal(primitive) => {
+            let literal = (Expression::Primitive(primitive.clone(), span), span);
+            let mut test = load(value, path, span);
+            test.append_compile(&literal, context)?
+                .append(ICode::Eq(span));
+            tests.push(test);
+        }
+        Pattern::Ident(name) => {
+            code.bind
+                .append_fragment(load(value, path, span))
+                .append(ICode::MakeLocal);
+            code.names.push((name, span));
+        }
+        Pattern::Table(fields) => {
+            tests.push(test_type(value, path, "table", span));
+            for ((key, _), (pattern, pattern_span)) in fields {
+                path.push(Access::Key(key));
+                append_pattern(pattern, *pattern_span, value, path, tests, code, context)?;
+                path.pop();
+            }
+        }
+        Pattern::Array { elements, rest } => {
+            tests.push(test_type(value, path, "array", span));
+            // [a, b] matches an array of exactly 2 items, and [a, b, ...c] one of at least 2.
+            let mut test = load(value, path, span);
+            test.append_many([
+                ICode::CallMethod("len".into(), 0, span),
+                ICode::LoadInt(elements.len() as i64),
+                match rest {
+                    Some(_) => ICode::GreaterEq(span),
+                    None => ICode::Eq(span),
+                },
+            ]);
+            tests.push(test);
+            for (index, (pattern, pattern_span)) in elements.iter().enumerate() {
+                path.push(Access::Index(index));
+                append_pattern(pattern, *pattern_span, value, path, tests, code, context)?;
+                path.pop();
+            }
+            if let Some((name, name_span)) = rest {
+                code.bind
+                    .append_fragment(load(value, path, span))
+                    .append(ICode::LoadInt(elements.len() as i64))
+                    .append_fragment(load(value, path, span))
+                    .append_many([
+                        ICode::CallMethod("len".into(), 0, span),
+                        ICode::CallMethod("slice".into(), 2, span),
+                        ICode::MakeLocal,
+                    ]);
+                code.names.push((name, *name_span));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Pushes the part of the value at `path`.
+fn load(value: VariableId, path: &[Access], span: TextSpan) -> Fragment {
+    let mut fragment = Fragment::with_code(vec![ICode::LoadLocal(value)]);
+    for access in path {
+        fragment.append_many([
+            match access {
+                Access::Index(index) => ICode::LoadInt(*index as i64),
+                Access::Key(key) => ICode::LoadString(key.to_string()),
+            },
+            ICode::GetItem(span),
+        ]);
+    }
+    fragment
+}
+
+// Pushes whether the part of the value at `path` is of the type `typename`.
+fn test_type(value: VariableId, path: &[Access], typename: &str, span: TextSpan) -> Fragment {
+    let mut fragment = load(value, path, span);
+    fragment.append_many([
+        ICode::Builtin(BuiltinInstr::TypeName, 1),
+        ICode::LoadString(typename.to_string()),
+        ICode::Eq(span),
+    ]);
+    fragment
+}
@@ -1,4 +1,5 @@
 use super::*;
+use vm::runtime::Object;
 
 impl<'node, 'src: 'node> Compilable<'node, 'src> for (Statement<'src>, TextSpan) {
     fn compile(&'node self, fragment: &mut Fragment, context: &mut Context<'src>) -> Result<()> {
@@ -16,22 +17,72 @@ fn compile<'node, 'src: 'node>(
     match statement {
         // var [name] = [expr]
         Statement::Var {
-            name: (name, _),
+            name: (name, name_span),
+            expr,
+        } => {
+            fragment
+                .append_compile(expr, context)?
+                .append(ICode::MakeLocal);
+            context.define_variable(name, *name_span);
+        }
+
+        // var [pattern] = [expr]
+        //  ↓
+        // var <>var = [expr]
+        // var [name] = <>var[..] for each name in [pattern]
+        Statement::DestructuringVar {
+            pattern: (pattern, pattern_span),
             expr,
         } => {
             fragment
                 .append_compile(expr, context)?
                 .append(ICode::MakeLocal);
-            context.add_variable(name);
+            let id = context.add_variable("<>var");
+            let code = pattern::compile_pattern(pattern, *pattern_span, id, context)?;
+            fragment.append_fragment(code.bind);
+            for (name, name_span) in code.names {
+                context.define_variable(name, name_span);
+            }
+        }
+
+        // var [names] = [exprs]
+        Statement::ParallelVar { names, exprs } => {
+            fragment.append_compile_many(exprs.iter(), context)?;
+            util::append_unpack(fragment, names.len(), exprs.len());
+            // The last value is on the top of the stack, so the locals are made from the last
+            // name. Their ids don't have to follow the order of the names.
+            for (name, name_span) in names.iter().rev() {
+                fragment.append(ICode::MakeLocal);
+                context.define_variable(name, *name_span);
+            }
+        }
+
+        // global [name]
+        // global [name] = [expr]
+        Statement::Global {
+            name: (name, _),
+            expr,
+        } => {
+            // Globals outlive the evaluation, which pure code must not depend on.
+            if context.options().pure {
+                return Err(Error::global_not_allowed(span));
+            }
+            if let Some(expr) = expr {
+                fragment
+                    .append_compile(expr, context)?
+                    .append(ICode::SetGlobal(name.to_string()));
+            }
+            context.declare_global(name);
         }
 
         // func [name]([args])
         //     [body]
         // end
         Statement::Func {
-            name: (name, _),
+            name: (name, name_span),
             args,
             body,
+            doc: _,
         } => {
             // NOTE: `body.captures` is sorted.
             let is_recusive = body
@@ -40,15 +91,22 @@ fn compile<'node, 'src: 'node>(
                 .is_ok();
             if is_recusive {
                 fragment.append_many([ICode::LoadNil, ICode::MakeLocal]);
-                context.add_variable(name);
+                context.define_variable(name, *name_span);
             }
-            util::append_func_creation_fragment(fragment, body, args, context)?;
+            util::append_func_creation_fragment(
+                fragment,
+                Some(name.to_string()),
+                body,
+                args,
+                None,
+                context,
+            )?;
             if is_recusive {
-                let id = context.resolve_variable(name).unwrap();
+                let id = context.resolve_assignment(name).unwrap();
                 fragment.append(ICode::SetLocal(id));
             } else {
                 fragment.append(ICode::MakeLocal);
-                context.add_variable(name);
+                context.define_variable(name, *name_span);
             }
         }
 
@@ -56,12 +114,26 @@ fn compile<'node, 'src: 'node>(
         //     [body]
         // end
         Statement::FieldFunc {
-            table: (_, table_span),
+            table: (table, table_span),
             fields,
             args,
             body,
+            doc: _,
         } => {
-            util::append_func_creation_fragment(fragment, body, args, context)?;
+            let name = std::iter::once(*table)
+                .chain(fields.iter().map(|(field, _)| *field))
+                .collect::<Vec<_>>()
+                .join(".");
+            let load_table = match context.is_global(table) {
+                true => ICode::LoadGlobal(table.to_string()),
+                false => ICode::LoadLocal(
+                    context
+                        .resolve_variable(table)
+                        .ok_or_else(|| Error::undefined_variable(table.to_string(), *table_span))?,
+                ),
+            };
+            util::append_func_creation_fragment(fragment, Some(name), body, args, None, context)?;
+            fragment.append(load_table);
             let mut prev_span_start = table_span.start();
             for (field, field_span) in fields.iter().take(fields.len() - 1) {
                 let span = TextSpan::new(prev_span_start, field_span.end());
@@ -78,18 +150,136 @@ fn compile<'node, 'src: 'node>(
             ]);
         }
 
+        // class [name] < [parent]
+        //     func [method]([args]) [body] end
+        //     func init(self, [args]) [init body] end
+        // end
+        //  ↓
+        // var [name] = { __index = [parent] }
+        // [name].[method] = func([args]) [body] end
+        // [name].init = func(self, [args]) [init body] end
+        // [name].new = func(<>class, [args])
+        //     var self = { __index = <>class }
+        //     [init body]
+        //     return self
+        // end
+        //
+        // Without `init`, a class inherits `new` from its parent, or has one that only makes the
+        // instance if it has no parent.
+        Statement::Class {
+            name: (name, name_span),
+            parent,
+            methods,
+        } => {
+            if let Some(parent) = parent {
+                fragment.append_compile(parent, context)?.append_many([
+                    ICode::LoadString("__index".to_string()),
+                    ICode::MakeNamed,
+                    ICode::MakeTable(1),
+                ]);
+            } else {
+                fragment.append(ICode::MakeTable(0));
+            }
+            fragment.append(ICode::MakeLocal);
+            let class_id = context.define_variable(name, *name_span);
+            for ((method, method_span), function) in methods {
+                util::append_func_creation_fragment(
+                    fragment,
+                    Some(format!("{}.{}", name, method)),
+                    &function.body,
+                    &function.args,
+                    None,
+                    context,
+                )?;
+                fragment.append_many([
+                    ICode::LoadLocal(class_id),
+                    ICode::LoadString(method.to_string()),
+                    ICode::SetItem(*method_span),
+                ]);
+            }
+            let init = methods.iter().find(|((method, _), _)| *method == "init");
+            let empty_body = Chunk {
+                captures: vec![],
+                block: Block(vec![]),
+            };
+            let (args, body, instance, span) = match init {
+                Some(((_, init_span), init)) => {
+                    let Some((_, Pattern::Ident(instance), instance_span, _)) = init.args.first()
+                    else {
+                        unreachable!("[BUG] `init` must take `self` as its first parameter.")
+                    };
+                    (
+                        &init.args[1..],
+                        &init.body,
+                        (*instance, *instance_span),
+                        *init_span,
+                    )
+                }
+                None if parent.is_none() => {
+                    (&[][..], &empty_body, ("self", *name_span), *name_span)
+                }
+                None => return Ok(()),
+            };
+            let args = std::iter::once((
+                FunctArgAnnotation::None,
+                Pattern::Ident("<>class"),
+                span,
+                None,
+            ))
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>();
+            util::append_func_creation_fragment(
+                fragment,
+                Some(format!("{}.new", name)),
+                body,
+                &args,
+                Some(instance),
+                context,
+            )?;
+            fragment.append_many([
+                ICode::LoadLocal(class_id),
+                ICode::LoadString("new".to_string()),
+                ICode::SetItem(span),
+            ]);
+        }
+
         // [name] = [expr]
+        Statement::Assign {
+            name: (name, _),
+            expr,
+        } if context.is_global(name) => {
+            fragment
+                .append_compile(expr, context)?
+                .append(ICode::SetGlobal(name.to_string()));
+        }
         Statement::Assign {
             name: (name, name_span),
             expr,
         } => {
             fragment.append_compile(expr, context)?;
             let id = context
-                .resolve_variable(name)
+                .resolve_assignment(name)
                 .ok_or_else(|| Error::undefined_variable(name.to_string(), *name_span))?;
             fragment.append(ICode::SetLocal(id));
         }
 
+        // [names] = [exprs]
+        Statement::ParallelAssign { names, exprs } => {
+            // All [exprs] are evaluated before any store, so `a, b = b, a` swaps.
+            fragment.append_compile_many(exprs.iter(), context)?;
+            util::append_unpack(fragment, names.len(), exprs.len());
+            for (name, name_span) in names.iter().rev() {
+                if context.is_global(name) {
+                    fragment.append(ICode::SetGlobal(name.to_string()));
+                    continue;
+                }
+                let id = context
+                    .resolve_assignment(name)
+                    .ok_or_else(|| Error::undefined_variable(name.to_string(), *name_span))?;
+                fragment.append(ICode::SetLocal(id));
+            }
+        }
+
         // [target].[accessor] = [expr]
         Statement::FieldAssign {
             table: target,
@@ -103,6 +293,75 @@ fn compile<'node, 'src: 'node>(
                 .append(ICode::SetItem(span));
         }
 
+        // [target] ??= [expr]
+        // [target] ||= [expr]
+        Statement::ConditionalAssign {
+            op,
+            target: (target, target_span),
+            expr,
+        } => {
+            // `ConditionalAssign`: [setup]
+            //                      [test]  // if the current value is kept, jump to [teardown]
+            //                      [expr]
+            //                      [store]
+            //                      [teardown]
+            let (load_fragment, store_fragment, local_count) =
+                compile_assign_target(target, *target_span, span, fragment, context)?;
+
+            let mut assign_fragment = Fragment::with_compile(expr, context)?;
+            assign_fragment.append_fragment(store_fragment);
+            match op {
+                ConditionalAssignOp::NilCoalesce => {
+                    fragment
+                        .append_fragment(load_fragment)
+                        .append_many([ICode::LoadNil, ICode::Eq(span)]);
+                }
+                ConditionalAssignOp::Or => {
+                    fragment.append_fragment(load_fragment).append(ICode::Not);
+                }
+            }
+            fragment
+                .append(ICode::JumpIfFalse(assign_fragment.len() as isize + 1))
+                .append_fragment(assign_fragment);
+            if local_count > 0 {
+                fragment.append(ICode::DropLocal(local_count));
+                context.drop_variable(local_count);
+            }
+        }
+
+        // [target] += [expr]
+        // [target] ..= [expr]
+        Statement::CompoundAssign {
+            op,
+            target: (target, target_span),
+            expr,
+        } => {
+            // `CompoundAssign`: [setup]
+            //                   [load]
+            //                   [expr]
+            //                   [op]
+            //                   [store]
+            //                   [teardown]
+            let (load_fragment, store_fragment, local_count) =
+                compile_assign_target(target, *target_span, span, fragment, context)?;
+            fragment
+                .append_fragment(load_fragment)
+                .append_compile(expr, context)?
+                .append(match op {
+                    CompoundAssignOp::Add => ICode::Add(span),
+                    CompoundAssignOp::Sub => ICode::Sub(span),
+                    CompoundAssignOp::Mul => ICode::Mul(span),
+                    CompoundAssignOp::Div => ICode::Div(span),
+                    CompoundAssignOp::Mod => ICode::Mod(span),
+                    CompoundAssignOp::Concat => ICode::Concat(span),
+                })
+                .append_fragment(store_fragment);
+            if local_count > 0 {
+                fragment.append(ICode::DropLocal(local_count));
+                context.drop_variable(local_count);
+            }
+        }
+
         // if [cond] then
         //     [body]
         // elif [elifs.0] then
@@ -129,35 +388,55 @@ fn compile<'node, 'src: 'node>(
             //            ...
             //           = `else_`
 
-            let mut new_fragments = {
-                // `make_snip` creates [cond] ~ [body]
-                let mut make_snip = |cond: &(Expression<'src>, TextSpan), body: &Block<'src>| {
-                    let cond_fagment = Fragment::with_compile(cond, context)?;
-                    let body_fragment = Fragment::with_compile(body, context)?;
-                    let mut fragment = Fragment::new();
-                    fragment
-                        .append_fragment(cond_fagment)
-                        .append(ICode::JumpIfFalse(body_fragment.len() as isize + 2))
-                        .append_fragment(body_fragment);
-                    Ok(fragment)
-                };
-
-                // Applay `make_snip` to (`cond`, `body`) pair, and `elifs`.`
-                let mut res = Vec::new();
-                res.push(make_snip(cond, body)?);
-                for (cond, body) in elifs.iter() {
-                    res.push(make_snip(cond, body)?);
-                }
-
-                // Append `else_` block
-                if let Some(body) = else_ {
-                    res.push(Fragment::with_compile(body, context)?);
-                } else {
-                    res.push(Fragment::with_code(vec![ICode::Nop]));
+            // Arms whose condition is a constant are resolved here: `if false` arms are dropped,
+            // and an `if true` arm takes the place of `else_` and drops the arms after it.
+            let arms = std::iter::once((cond, body))
+                .chain(elifs.iter().map(|(cond, body)| (cond, body)))
+                .collect::<Vec<_>>();
+            let arm_span = |(cond, body): &(&(Expression<'src>, TextSpan), &Block<'src>)| {
+                let end = util::statements_span(body).map_or(cond.1.end(), |span| span.end());
+                TextSpan::new(cond.1.start(), end)
+            };
+            let mut new_fragments = Vec::new();
+            let mut always = None;
+            for (i, arm @ (cond, body)) in arms.iter().enumerate() {
+                match fold::constant(&cond.0) {
+                    Some(Object::Bool(false)) => {
+                        context.warn(Warning::unreachable_code(arm_span(arm)));
+                    }
+                    Some(Object::Bool(true)) => {
+                        always = Some(Fragment::with_compile(*body, context)?);
+                        let rest = arms[i + 1..]
+                            .iter()
+                            .map(arm_span)
+                            .chain(else_.as_ref().and_then(|body| util::statements_span(body)))
+                            .collect::<Vec<_>>();
+                        if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+                            let span = TextSpan::new(first.start(), last.end());
+                            context.warn(Warning::unreachable_code(span));
+                        }
+                        break;
+                    }
+                    _ => {
+                        // `Set`: [cond] ~ [body]
+                        let cond_fagment = Fragment::with_compile(*cond, context)?;
+                        let body_fragment = Fragment::with_compile(*body, context)?;
+                        let mut fragment = Fragment::new();
+                        fragment
+                            .append_fragment(cond_fagment)
+                            .append(ICode::JumpIfFalse(body_fragment.len() as isize + 2))
+                            .append_fragment(body_fragment);
+                        new_fragments.push(fragment);
+                    }
                 }
+            }
 
-                res
-            };
+            // Append `else_` block
+            new_fragments.push(match (always, else_) {
+                (Some(fragment), _) => fragment,
+                (None, Some(body)) => Fragment::with_compile(body, context)?,
+                (None, None) => Fragment::with_code(vec![ICode::Nop]),
+            });
 
             // Add last [jump] of `Set`
             let mut jump_dist = new_fragments.last().unwrap().len() + 1;
@@ -173,34 +452,35 @@ fn compile<'node, 'src: 'node>(
         //   [body]
         // end
         //  ↓
-        // var <>iter = [iter]->__get_iterator()
+        // var <>iter = iterator of [iter]
         // var [value] = Nil
-        // while <>iter->__move_next() do
-        //     [value] = <>iter->__current()
+        // while <>iter has next do
+        //     [value] = next of <>iter
         //     [body]
         // end
         // delete [value], <>iter
         Statement::For {
-            value: (value, _),
+            value: (value, value_span),
             iter,
             body,
         } => {
-            //            0: make_local    <>iter = [iter]->__get_iterator()
-            //            1: make_local    [value] = Nil
-            // (continue) 2: eval          <>iter->__move_next()
-            //            3: jump_if_false 7
-            //            4: set_local     [value] = <>iter->__current()
-            //            5: eval          [body]
-            //            6: jump          2
-            //   (break)  7: delete        [value], <>iter (= drop_local 2)
-            //            8: ...
+            //            0: iter_start
+            //            1: make_local    <>iter
+            //            2: make_local    [value] = Nil
+            // (continue) 3: iter_next     <>iter, 8
+            //            4: iter_end      8
+            //            5: set_local     [value]
+            //            6: eval          [body]
+            //            7: jump          3
+            //   (break)  8: delete        [value], <>iter (= drop_local 2)
+            //            9: ...
 
             let iter_fragment = Fragment::with_compile(iter, context)?;
             let loop_fragment = {
                 let iter_span = iter.1;
 
                 let iter_id = context.add_variable("<>iter");
-                let value_id = context.add_variable(value);
+                let value_id = context.define_variable(value, *value_span);
                 context.begin_loop();
                 let body_fragment = Fragment::with_compile(body, context)?; // 6
                 let body_fragment_len = body_fragment.len() as isize;
@@ -210,24 +490,21 @@ fn compile<'node, 'src: 'node>(
                 let mut fragment = Fragment::new();
                 fragment
                     .append_many([
-                        ICode::CallMethod("__get_iterator".into(), 0, iter_span), // 0
-                        ICode::MakeLocal,                                         // |
-                        ICode::LoadNil,                                           // 1
-                        ICode::MakeLocal,                                         // |
-                        ICode::LoadLocal(iter_id),                                // 2
-                        ICode::CallMethod("__move_next".into(), 0, iter_span),    // |
-                        ICode::JumpIfFalse(3 + body_fragment_len + 2),            // 3
-                        ICode::LoadLocal(iter_id),                                // 4
-                        ICode::CallMethod("__current".into(), 0, iter_span),      // |
-                        ICode::SetLocal(value_id),                                // |
+                        ICode::IterStart(iter_span),                                // 0
+                        ICode::MakeLocal,                                           // 1
+                        ICode::LoadNil,                                             // 2
+                        ICode::MakeLocal,                                           // |
+                        ICode::IterNext(iter_id, 4 + body_fragment_len, iter_span), // 3
+                        ICode::IterEnd(3 + body_fragment_len),                      // 4
+                        ICode::SetLocal(value_id),                                  // 5
                     ])
-                    .append_fragment(body_fragment) // 5
+                    .append_fragment(body_fragment) // 6
                     .append_many([
-                        ICode::Jump(-(body_fragment_len + 6)), //  6
-                        ICode::DropLocal(2),                   //  7
+                        ICode::Jump(-(body_fragment_len + 3)), // 7
+                        ICode::DropLocal(2),                   // 8
                     ]);
-                fragment.patch_backward_jump(3); // to 2
-                fragment.patch_forward_jump(-1); // to 7
+                fragment.patch_backward_jump(4); // to 3
+                fragment.patch_forward_jump(0); // to 8
                 fragment
             };
             fragment
@@ -235,10 +512,79 @@ fn compile<'node, 'src: 'node>(
                 .append_fragment(loop_fragment);
         }
 
+        // for [value] = [start], [stop], [step] do
+        //   [body]
+        // end
+        Statement::NumericFor {
+            value: (value, value_span),
+            start,
+            stop,
+            step,
+            body,
+        } => {
+            //            0: make_local    <>index = [start]
+            //            1: make_local    <>stop = [stop]
+            //            2: make_local    <>step = [step] (or 1)
+            //            3: make_local    [value] = Nil
+            //            4: for_prep      <>index, 7
+            //            5: eval          [body]
+            // (continue) 6: for_loop      <>index, 5
+            //   (break)  7: delete        [value], <>step, <>stop, <>index (= drop_local 4)
+            //            8: ...
+
+            let bounds_span = TextSpan::new(
+                start.1.start(),
+                step.as_ref().map_or(stop.1, |step| step.1).end(),
+            );
+            fragment
+                .append_compile(start, context)?
+                .append(ICode::MakeLocal);
+            let index_id = context.add_variable("<>index");
+            fragment
+                .append_compile(stop, context)?
+                .append(ICode::MakeLocal);
+            context.add_variable("<>stop");
+            match step {
+                Some(step) => fragment.append_compile(step, context)?,
+                None => fragment.append(ICode::LoadInt(1)),
+            };
+            fragment.append(ICode::MakeLocal);
+            context.add_variable("<>step");
+
+            context.define_variable(value, *value_span);
+            context.begin_loop();
+            let body_fragment = Fragment::with_compile(body, context)?; // 5
+            let body_fragment_len = body_fragment.len() as isize;
+            context.end_loop();
+            context.drop_variable(4);
+
+            let mut loop_fragment = Fragment::new();
+            loop_fragment
+                .append_many([
+                    ICode::LoadNil,                                               // 3
+                    ICode::MakeLocal,                                             // |
+                    ICode::ForPrep(index_id, 2 + body_fragment_len, bounds_span), // 4
+                ])
+                .append_fragment(body_fragment) // 5
+                .append_many([
+                    ICode::ForLoop(index_id, -body_fragment_len), // 6
+                    ICode::DropLocal(4),                          // 7
+                ]);
+            loop_fragment.patch_backward_jump(3 + body_fragment_len); // to 6
+            loop_fragment.patch_forward_jump(0); // to 7
+            fragment.append_fragment(loop_fragment);
+        }
+
         // while [cond] do
         //     [body]
         // end
         Statement::While { cond, body } => {
+            if let Some(Object::Bool(false)) = fold::constant(&cond.0) {
+                if let Some(span) = util::statements_span(body) {
+                    context.warn(Warning::unreachable_code(span));
+                }
+                return Ok(());
+            }
             let while_fragment = {
                 let cond_fragment = Fragment::with_compile(cond, context)?;
                 let cond_fragment_len = cond_fragment.len() as isize;
@@ -269,20 +615,210 @@ fn compile<'node, 'src: 'node>(
             fragment.append_compile(body, context)?;
         }
 
+        // match [expr]
+        // case [pattern] if [guard] then
+        //     [body]
+        // ...
+        // end
+        Statement::Match { expr, arms } => {
+            // `Arm`: [test]  // types, lengths and literals of the pattern, if any
+            //        [jump]  // if test is false, jump to next top of `Arm`
+            //        [bind]  // make_local for each name bound by the pattern
+            //        [guard]
+            //        [jump]  // if guard is false, jump to (fail)
+            //        [body]
+            //        drop_local [bind count]
+            //        [jump]  // [body] is executed, so jump to end of `Match`
+            // (fail) drop_local [bind count]
+            //
+            // `Match` = make_local <>match = [expr]
+            //         = `Arm`
+            //          ...
+            //         = drop_local 1 (<>match)
+
+            fragment
+                .append_compile(expr, context)?
+                .append(ICode::MakeLocal);
+            let match_id = context.add_variable("<>match");
+
+            let mut new_fragments = Vec::with_capacity(arms.len());
+            for ((pattern, pattern_span), guard, body) in arms.iter() {
+                let pattern::PatternCode {
+                    test: test_fragment,
+                    bind: bind_fragment,
+                    names,
+                } = pattern::compile_pattern(pattern, *pattern_span, match_id, context)?;
+                let bind_count = names.len();
+                for (name, span) in names {
+                    context.define_variable(name, span);
+                }
+                let guard_fragment = match guard {
+                    Some(guard) => Some(Fragment::with_compile(guard, context)?),
+                    None => None,
+                };
+                let body_fragment = Fragment::with_compile(body, context)?;
+                context.drop_variable(bind_count);
+
+                let drop_fragment = if bind_count > 0 {
+                    Fragment::with_code(vec![ICode::DropLocal(bind_count)])
+                } else {
+                    Fragment::new()
+                };
+                let mut inner_fragment = bind_fragment;
+                let fail_fragment = match guard_fragment {
+                    Some(guard_fragment) => {
+                        let jump = body_fragment.len() + drop_fragment.len() + 2;
+                        inner_fragment
+                            .append_fragment(guard_fragment)
+                            .append(ICode::JumpIfFalse(jump as isize));
+                        drop_fragment.clone()
+                    }
+                    None => Fragment::new(),
+                };
+                inner_fragment
+                    .append_fragment(body_fragment)
+                    .append_fragment(drop_fragment);
+
+                let mut arm_fragment = test_fragment;
+                if !arm_fragment.is_empty() {
+                    let jump = inner_fragment.len() + fail_fragment.len() + 2;
+                    arm_fragment.append(ICode::JumpIfFalse(jump as isize));
+                }
+                arm_fragment.append_fragment(inner_fragment);
+                new_fragments.push((arm_fragment, fail_fragment));
+            }
+
+            // Add [jump] to end of `Match` for each `Arm`
+            let mut jump_dist = 1;
+            for (arm_fragment, fail_fragment) in new_fragments.iter_mut().rev() {
+                jump_dist += fail_fragment.len();
+                arm_fragment.append(ICode::Jump(jump_dist as isize));
+                jump_dist += arm_fragment.len();
+            }
+            for (arm_fragment, fail_fragment) in new_fragments {
+                fragment
+                    .append_fragment(arm_fragment)
+                    .append_fragment(fail_fragment);
+            }
+
+            fragment.append(ICode::DropLocal(1));
+            context.drop_variable(1);
+        }
+
+        // with [expr] as [name] do
+        //     [body]
+        // end
+        Statement::With {
+            expr,
+            name: (name, name_span),
+            body,
+        } => {
+            fragment
+                .append_compile(expr, context)?
+                .append(ICode::MakeLocal);
+            let id = context.define_variable(name, *name_span);
+            // It is closed at the end of the block.
+            context.use_variable(id);
+            fragment.append_many([ICode::LoadLocal(id), ICode::EnterWith(expr.1)]);
+            context.enter_resource();
+            fragment
+                .append_compile(body, context)?
+                .append_many([ICode::ExitWith, ICode::DropLocal(1)]);
+            context.exit_resource();
+            context.drop_variable(1);
+        }
+
+        // try
+        //     [body]
+        // catch [name]
+        //     [handler]
+        // end
+        //  ↓
+        //     Try(catch)
+        //     [body]
+        //     EndTry
+        //     Jump(end)
+        // catch:
+        //     var [name] = <the error>
+        //     [handler]
+        // end:
+        Statement::Try {
+            body,
+            name: (name, name_span),
+            handler,
+        } => {
+            context.enter_handler();
+            let mut body_fragment = Fragment::with_compile(body, context)?;
+            context.exit_handler();
+            body_fragment.append(ICode::EndTry);
+
+            let mut handler_fragment = Fragment::with_code(vec![ICode::MakeLocal]);
+            context.define_variable(name, *name_span);
+            handler_fragment
+                .append_compile(handler, context)?
+                .append(ICode::DropLocal(1));
+            context.drop_variable(1);
+
+            fragment
+                .append(ICode::Try(body_fragment.len() as isize + 2))
+                .append_fragment(body_fragment)
+                .append(ICode::Jump(handler_fragment.len() as isize + 1))
+                .append_fragment(handler_fragment);
+        }
+
+        // assert([cond], [message])
+        Statement::Assert { cond, message } => {
+            let mut failure_fragment = Fragment::with_code(vec![ICode::LoadString(format!(
+                "Assertion failed: `{}`",
+                util::expression_to_source(&cond.0),
+            ))]);
+            if let Some(message) = message {
+                failure_fragment
+                    .append_many([ICode::LoadString(": ".to_string()), ICode::Concat(span)])
+                    .append_compile(message, context)?
+                    .append(ICode::Concat(span));
+            }
+            failure_fragment.append(ICode::Raise(cond.1));
+            fragment
+                .append_compile(cond, context)?
+                .append(ICode::JumpIfTrue(failure_fragment.len() as isize + 1))
+                .append_fragment(failure_fragment);
+        }
+
         // return [value]
         Statement::Return { value } => {
             if let Some(value) = value {
                 fragment.append_compile(value, context)?;
+                // The result of a call is returned as is, so the callee can run in place of this
+                // function.
+                if let Some(&ICode::Call(args_len, span)) = fragment.last() {
+                    *fragment.last_mut().unwrap() = ICode::TailCall(args_len, span);
+                }
+            } else if let Some(instance) = context.instance() {
+                fragment.append(ICode::LoadLocal(instance));
             } else {
                 fragment.append(ICode::LoadNil);
             }
             fragment.append(ICode::Return);
         }
 
+        // return [values]
+        Statement::ReturnMany { values } => {
+            fragment
+                .append_compile_many(values.iter(), context)?
+                .append(ICode::ReturnMany(values.len() as u8));
+        }
+
         // continue
         Statement::Continue => {
             let drop_count = context.get_loop_vars_count();
             if let Some(drop_count) = drop_count {
+                for _ in 0..context.get_loop_resources_count().unwrap_or(0) {
+                    fragment.append(ICode::ExitWith);
+                }
+                for _ in 0..context.get_loop_handlers_count().unwrap_or(0) {
+                    fragment.append(ICode::EndTry);
+                }
                 fragment
                     .append(ICode::DropLocal(drop_count))
                     .append_backward_jump();
@@ -295,6 +831,12 @@ fn compile<'node, 'src: 'node>(
         Statement::Break => {
             let drop_count = context.get_loop_vars_count();
             if let Some(drop_count) = drop_count {
+                for _ in 0..context.get_loop_resources_count().unwrap_or(0) {
+                    fragment.append(ICode::ExitWith);
+                }
+                for _ in 0..context.get_loop_handlers_count().unwrap_or(0) {
+                    fragment.append(ICode::EndTry);
+                }
                 fragment
                     .append(ICode::DropLocal(drop_count))
                     .append_forward_jump();
@@ -304,30 +846,69 @@ fn compile<'node, 'src: 'node>(
         }
 
         // [expr]([args])
-        Statement::Call { expr, args } => {
-            fragment
-                .append_compile(expr, context)?
-                .append_compile_many(args.iter(), context)?
-                .append_many([ICode::Call(args.len() as u8, span), ICode::UnloadTop]);
+        Statement::Call {
+            expr: (callee, callee_span),
+            args,
+        } => {
+            let mut exits = Vec::new();
+            expression::compile_chain(callee, *callee_span, fragment, context, &mut exits)?;
+            let call = match expression::append_args(fragment, 0, args, span, context)? {
+                Some(count) => ICode::Call(count, span),
+                None => ICode::CallSpread(span),
+            };
+            fragment.append(call);
+            // A nil receiver of `?.` or `?->` skips the call, leaving nil to drop.
+            fragment.patch_nil_jumps(exits);
+            fragment.append(ICode::UnloadTop);
+        }
+
+        // yield [value]
+        Statement::Yield { value } => {
+            match value {
+                Some(value) => fragment.append_compile(value, context)?,
+                None => fragment.append(ICode::LoadNil),
+            };
+            fragment.append_many([ICode::Yield(span), ICode::UnloadTop]);
+        }
+
+        // import [path]
+        Statement::Import { path: (path, _) } => {
+            if context.options().pure {
+                return Err(Error::import_not_allowed(span));
+            }
+            fragment.append_many([
+                ICode::LoadString(path.to_string()),
+                ICode::Import(span),
+                ICode::UnloadTop,
+            ]);
         }
 
         // [expr]->[name]([args])
         Statement::MethodCall {
-            expr,
+            expr: (receiver, receiver_span),
             name: (name, span),
             args,
         } => {
-            fragment
-                .append_compile(expr, context)?
-                .append_compile_many(args.iter(), context)?
-                .append_many([
-                    ICode::CallMethod(name.to_string().into(), args.len() as u8, *span),
-                    ICode::UnloadTop,
-                ]);
+            let mut exits = Vec::new();
+            expression::compile_chain(receiver, *receiver_span, fragment, context, &mut exits)?;
+            let name = name.to_string().into();
+            let call = match expression::append_args(fragment, 0, args, *span, context)? {
+                Some(count) => ICode::CallMethod(name, count, *span),
+                None => ICode::CallMethodSpread(name, *span),
+            };
+            fragment.append(call);
+            fragment.patch_nil_jumps(exits);
+            fragment.append(ICode::UnloadTop);
         }
 
         // @[name]([args])
-        Statement::Attribute { name: _, args: _ } => todo!("compile attribute_statement"),
+        // No attribute means anything to the compiler yet, so they are only reported.
+        Statement::Attribute {
+            name: (name, name_span),
+            args: _,
+        } => {
+            context.warn(Warning::unknown_attribute(name.to_string(), *name_span));
+        }
 
         Statement::Error => {
             panic!("found error");
@@ -336,11 +917,68 @@ fn compile<'node, 'src: 'node>(
     Ok(())
 }
 
+/// Compiles the setup of an assignment to `target`, a local or an item, and returns the code that
+/// loads it, the code that stores the value on the stack to it, and the number of hidden locals
+/// the setup made.
+fn compile_assign_target<'node, 'src: 'node>(
+    target: &'node Expression<'src>,
+    target_span: TextSpan,
+    span: TextSpan,
+    fragment: &mut Fragment,
+    context: &mut Context<'src>,
+) -> Result<(Fragment, Fragment, usize)> {
+    let mut load_fragment = Fragment::new();
+    let mut store_fragment = Fragment::new();
+    let mut local_count = 0;
+    match target {
+        Expression::Local(name, _) if context.is_global(name) => {
+            load_fragment.append(ICode::LoadGlobal(name.to_string()));
+            store_fragment.append(ICode::SetGlobal(name.to_string()));
+        }
+        Expression::Local(name, name_span) => {
+            let id = context
+                .resolve_variable(name)
+                .ok_or_else(|| Error::undefined_variable(name.to_string(), *name_span))?;
+            load_fragment.append(ICode::LoadLocal(id));
+            store_fragment.append(ICode::SetLocal(id));
+        }
+        Expression::IndexAccess {
+            expr: table,
+            accessor: field,
+        } => {
+            // Evaluate [table] and [field] only once.
+            fragment
+                .append_compile(&(table.0.as_ref(), table.1), context)?
+                .append(ICode::MakeLocal);
+            let table_id = context.add_variable("<>table");
+            fragment
+                .append_compile(&(field.0.as_ref(), field.1), context)?
+                .append(ICode::MakeLocal);
+            let field_id = context.add_variable("<>field");
+            load_fragment.append_many([
+                ICode::LoadLocal(table_id),
+                ICode::LoadLocal(field_id),
+                ICode::GetItem(target_span),
+            ]);
+            store_fragment.append_many([
+                ICode::LoadLocal(table_id),
+                ICode::LoadLocal(field_id),
+                ICode::SetItem(span),
+            ]);
+            local_count = 2;
+        }
+        _ => {
+            unreachable!("[BUG] target of an assignment must be Local or IndexAccess.")
+        }
+    }
+    Ok((load_fragment, store_fragment, local_count))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     pub use pretty_assertions::assert_eq;
-    use vm::code::{Code, LocalId};
+    use vm::code::{BuiltinInstr, Code, LocalId};
 
     #[test]
     fn r#if() {
@@ -366,7 +1004,7 @@ mod tests {
         );
         let fragment = Fragment::with_compile(&statement, &mut context);
         assert_eq!(
-            fragment.unwrap().into_code(),
+            fragment.unwrap().into_code().0,
             vec![
                 Code::LoadLocal(LocalId(1)), // a
                 Code::JumpIfFalse(5),
@@ -403,7 +1041,7 @@ mod tests {
         );
         let fragment = Fragment::with_compile(&statement, &mut context);
         assert_eq!(
-            fragment.unwrap().into_code(),
+            fragment.unwrap().into_code().0,
             vec![
                 Code::LoadLocal(LocalId(1)), // a
                 Code::JumpIfFalse(4),
@@ -445,7 +1083,7 @@ mod tests {
         );
         let fragment = Fragment::with_compile(&statement, &mut context);
         assert_eq!(
-            fragment.unwrap().into_code(),
+            fragment.unwrap().into_code().0,
             vec![
                 Code::LoadLocal(LocalId(1)), // a
                 Code::JumpIfFalse(4),
@@ -462,4 +1100,79 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn assert_with_message() {
+        let mut context = Context::new();
+        context.begin_block();
+        context.add_variable("a");
+        let dummy_span = TextSpan::new(0, 0);
+        let statement = (
+            Statement::Assert {
+                cond: (
+                    Expression::Binary {
+                        op: BinaryOp::Greater,
+                        lhs: (Box::new(Expression::Local("a", dummy_span)), dummy_span),
+                        rhs: (
+                            Box::new(Expression::Primitive(Primitive::Int(1), dummy_span)),
+                            dummy_span,
+                        ),
+                    },
+                    TextSpan::new(7, 12),
+                ),
+                message: Some((
+                    Expression::Primitive(Primitive::String("too small".into()), dummy_span),
+                    dummy_span,
+                )),
+            },
+            dummy_span,
+        );
+        let fragment = Fragment::with_compile(&statement, &mut context);
+        assert_eq!(
+            fragment.unwrap().into_code().0,
+            vec![
+                Code::LoadLocal(LocalId(0)), // a
+                Code::LoadInt(1),
+                Code::Greater,
+                Code::JumpIfTrue(7),
+                Code::LoadString("Assertion failed: `a > 1`".into()),
+                Code::LoadString(": ".into()),
+                Code::Concat,
+                Code::LoadString("too small".into()),
+                Code::Concat,
+                Code::Builtin(BuiltinInstr::Error, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn return_call() {
+        let mut context = Context::new();
+        context.begin_block();
+        context.add_variable("f");
+        let dummy_span = TextSpan::new(0, 0);
+        let call = Expression::Call {
+            expr: (Box::new(Expression::Local("f", dummy_span)), dummy_span),
+            args: vec![(
+                Expression::Primitive(Primitive::Int(1), dummy_span),
+                dummy_span,
+            )],
+        };
+        let statement = (
+            Statement::Return {
+                value: Some((call, dummy_span)),
+            },
+            dummy_span,
+        );
+        let fragment = Fragment::with_compile(&statement, &mut context);
+        assert_eq!(
+            fragment.unwrap().into_code().0,
+            vec![
+                Code::LoadLocal(LocalId(0)), // f
+                Code::LoadInt(1),
+                Code::TailCall(1),
+                Code::Return,
+            ]
+        );
+    }
 }
@@ -2,39 +2,321 @@ use super::*;
 
 use vm::code::ArgumentKind;
 
+/// Spreads the results of the call that is the last of `exprs_len` values over the names left
+/// without a value, if `names_len` is more.
+pub fn append_unpack(fragment: &mut Fragment, names_len: usize, exprs_len: usize) {
+    if names_len > exprs_len {
+        fragment.append(ICode::Unpack((names_len - exprs_len + 1) as u8));
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn append_func_creation_fragment<'node, 'src: 'node>(
     fragment: &mut Fragment,
+    name: Option<String>,
     chunk: &'node Chunk<'src>,
-    args: &'node [(FunctArgAnnotation, &'src str, TextSpan)],
+    args: &'node [(
+        FunctArgAnnotation,
+        Pattern<'src>,
+        TextSpan,
+        Option<(Expression<'src>, TextSpan)>,
+    )],
+    constructs: Option<(&'src str, TextSpan)>,
     context: &mut Context<'src>,
 ) -> Result<()> {
-    let add_capture = chunk
+    // Globals aren't captured, the function reads them from the runtime.
+    let chunk_captures = chunk
         .captures
+        .iter()
+        .filter(|(name, _)| !context.is_global(name))
+        .collect::<Vec<_>>();
+    let captures = chunk_captures
         .iter()
         .map(|(name, span)| {
             let id = context
                 .resolve_variable(name)
                 .ok_or_else(|| Error::undefined_variable(name.to_string(), *span))?;
-            Ok(ICode::AddCapture(id))
+            Ok(id)
         })
         .collect::<Result<Vec<_>>>()?;
-    let add_argument = args.iter().map(|_| ICode::AddArgument(ArgumentKind::Copy));
+    let warnings;
     let block_fragment = {
-        let mut context = Context::new();
+        let mut context = context.nested();
         context.begin_block();
-        context.add_variable_many(chunk.captures.iter().map(|(name, _)| *name));
-        context.add_variable_many(args.iter().map(|(_, name, _)| *name));
-        let mut fragment = Fragment::with_compile(&chunk.block, &mut context)?;
-        if !matches!(fragment.last(), Some(ICode::Return)) {
-            fragment.append_many([ICode::LoadNil, ICode::Return]);
+        context.add_variable_many(chunk_captures.iter().map(|(name, _)| *name));
+        let arg_ids = args
+            .iter()
+            .map(|(_, pattern, span, _)| match pattern {
+                Pattern::Ident(name) => context.define_parameter(name, *span),
+                _ => context.add_variable("<>arg"),
+            })
+            .collect::<Vec<_>>();
+
+        // func f(a = [default]) ... end
+        //  ↓
+        // func f(a)
+        //     if a == nil then a = [default] end
+        //     ...
+        // end
+        let mut fragment = Fragment::new();
+        for ((_, _, span, default), id) in args.iter().zip(&arg_ids) {
+            let Some(default) = default else {
+                continue;
+            };
+            let mut default_fragment = Fragment::with_compile(default, &mut context)?;
+            default_fragment.append(ICode::SetLocal(*id));
+            fragment
+                .append_many([ICode::LoadLocal(*id), ICode::LoadNil, ICode::Eq(*span)])
+                .append(ICode::JumpIfFalse(default_fragment.len() as isize + 1))
+                .append_fragment(default_fragment);
+        }
+
+        // func f({a, b}) ... end
+        //  ↓
+        // func f(<>arg)
+        //     var a = <>arg.a
+        //     var b = <>arg.b
+        //     ...
+        // end
+        for ((_, pattern, span, _), id) in args.iter().zip(&arg_ids) {
+            if let Pattern::Ident(_) = pattern {
+                continue;
+            }
+            let code = pattern::compile_pattern(pattern, *span, *id, &mut context)?;
+            fragment.append_fragment(code.bind);
+            for (name, name_span) in code.names {
+                context.define_parameter(name, name_span);
+            }
         }
+
+        // A constructor makes the instance from the class passed as its first argument, and
+        // returns it at the end.
+        if let Some((instance, span)) = constructs {
+            fragment.append_many([
+                ICode::LoadLocal(arg_ids[0]),
+                ICode::LoadString("__index".to_string()),
+                ICode::MakeNamed,
+                ICode::MakeTable(1),
+                ICode::MakeLocal,
+            ]);
+            let id = context.define_variable(instance, span);
+            context.set_instance(id);
+        }
+        fragment.append_compile(&chunk.block, &mut context)?;
+        if !always_returns(&chunk.block) {
+            match context.instance() {
+                Some(id) => fragment.append_many([ICode::LoadLocal(id), ICode::Return]),
+                None => fragment.append_many([ICode::LoadNil, ICode::Return]),
+            };
+        }
+        // Reports the unused parameters.
+        context.end_block();
+        warnings = context.take_warnings();
         fragment
     };
-    fragment
-        .append(ICode::BeginFuncCreation)
-        .append_many(add_capture)
-        .append_many(add_argument)
-        .append_fragment(block_fragment)
-        .append(ICode::EndFuncCreation);
+    // The body of a constructor is the one of `init`, which is already compiled and warned about.
+    if constructs.is_none() {
+        for warning in warnings {
+            context.warn(warning);
+        }
+    }
+    fragment.append(ICode::MakeClosure {
+        name,
+        captures,
+        args: args
+            .iter()
+            .map(|(annotation, _, _, _)| match annotation {
+                FunctArgAnnotation::Rest => ArgumentKind::Rest,
+                _ => ArgumentKind::Copy,
+            })
+            .collect(),
+        code: block_fragment.into_icode(),
+    });
     Ok(())
 }
+
+/// Renders `expr` back to (approximate) source code, e.g. for `assert` failure messages.
+pub fn expression_to_source(expr: &Expression<'_>) -> String {
+    fn join(exprs: &[(Expression<'_>, TextSpan)]) -> String {
+        exprs
+            .iter()
+            .map(|(expr, _)| expression_to_source(expr))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    fn operand(expr: &Expression<'_>) -> String {
+        match expr {
+            Expression::Binary { .. } => format!("({})", expression_to_source(expr)),
+            _ => expression_to_source(expr),
+        }
+    }
+
+    match expr {
+        Expression::Unary {
+            op,
+            expr: (expr, _),
+        } => {
+            let op = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Not => "not ",
+                UnaryOp::BNot => "~",
+            };
+            format!("{}{}", op, operand(expr))
+        }
+        Expression::Binary {
+            op,
+            lhs: (lhs, _),
+            rhs: (rhs, _),
+        } => {
+            let op = match op {
+                BinaryOp::Add => "+",
+                BinaryOp::Sub => "-",
+                BinaryOp::Mul => "*",
+                BinaryOp::Div => "/",
+                BinaryOp::Mod => "%",
+                BinaryOp::Pow => "**",
+                BinaryOp::FloorDiv => "//",
+                BinaryOp::Eq => "==",
+                BinaryOp::NotEq => "!=",
+                BinaryOp::Less => "<",
+                BinaryOp::LessEq => "<=",
+                BinaryOp::Greater => ">",
+                BinaryOp::GreaterEq => ">=",
+                BinaryOp::In => "in",
+                BinaryOp::And => "and",
+                BinaryOp::Or => "or",
+                BinaryOp::NilCoalesce => "??",
+                BinaryOp::BitAnd => "&",
+                BinaryOp::BitOr => "|",
+                BinaryOp::BitXor => "^",
+                BinaryOp::BitNot => "~",
+                BinaryOp::ShiftLeft => "<<",
+                BinaryOp::ShiftRight => ">>",
+                BinaryOp::Concat => "..",
+                BinaryOp::Pipeline => "|>",
+            };
+            format!("{} {} {}", operand(lhs), op, operand(rhs))
+        }
+        Expression::Local(name, _) => name.to_string(),
+        Expression::Primitive(primitive, _) => match primitive {
+            Primitive::Int(x) => x.to_string(),
+            Primitive::Float(x) => x.to_string(),
+            Primitive::String(x) => format!(r#""{}""#, x),
+            Primitive::Bool(x) => x.to_string(),
+            Primitive::Nil => "nil".to_string(),
+        },
+        Expression::TableObject(table) => {
+            let fields = table
+                .iter()
+                .map(|(key, (value, _))| match key {
+                    TableFieldKey::Ident(name, _) => {
+                        format!("{} = {}", name, expression_to_source(value))
+                    }
+                    TableFieldKey::Expr(key, _) => format!(
+                        "[{}] = {}",
+                        expression_to_source(key),
+                        expression_to_source(value)
+                    ),
+                })
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Expression::ArrayObject(array) => format!("[{}]", join(array)),
+        Expression::FunctionObject(function) => {
+            let args = function
+                .args
+                .iter()
+                .map(|(_, pattern, _, default)| match default {
+                    Some((default, _)) => {
+                        format!("{} = {}", pattern, expression_to_source(default))
+                    }
+                    None => pattern.to_string(),
+                })
+                .collect::<Vec<_>>();
+            format!("func({}) ... end", args.join(", "))
+        }
+        Expression::Call {
+            expr: (expr, _),
+            args,
+        } => format!("{}({})", operand(expr), join(args)),
+        Expression::MethodCall {
+            expr: (expr, _),
+            name: (name, _),
+            args,
+        } => format!("{}->{}({})", operand(expr), name, join(args)),
+        Expression::IndexAccess {
+            expr: (expr, _),
+            accessor: (accessor, _),
+        } => format!("{}[{}]", operand(expr), expression_to_source(accessor)),
+        Expression::DotAccess {
+            expr: (expr, _),
+            accessor: (accessor, _),
+        } => format!("{}.{}", operand(expr), accessor),
+        Expression::SliceAccess {
+            expr: (expr, _),
+            start,
+            end,
+        } => {
+            let bound = |bound: &Option<(Box<Expression<'_>>, TextSpan)>| match bound {
+                Some((bound, _)) => expression_to_source(bound),
+                None => String::new(),
+            };
+            format!("{}[{}:{}]", operand(expr), bound(start), bound(end))
+        }
+        // The `.` or `->` after it completes the operator.
+        Expression::Optional { expr: (expr, _) } => format!("{}?", operand(expr)),
+        Expression::Spread { expr: (expr, _) } => format!("...{}", operand(expr)),
+        Expression::If {
+            cond: (cond, _),
+            then: (then, _),
+            elifs,
+            else_: (else_, _),
+        } => {
+            let mut source = format!(
+                "if {} then {}",
+                expression_to_source(cond),
+                expression_to_source(then)
+            );
+            for ((cond, _), (then, _)) in elifs {
+                source += &format!(
+                    " elif {} then {}",
+                    expression_to_source(cond),
+                    expression_to_source(then)
+                );
+            }
+            source + &format!(" else {} end", expression_to_source(else_))
+        }
+        Expression::Yield { value: None } => "yield".to_string(),
+        Expression::Yield {
+            value: Some((value, _)),
+        } => format!("yield {}", expression_to_source(value)),
+        Expression::Import { path: (path, _) } => format!(r#"import "{}""#, path),
+        Expression::Error => "<error>".to_string(),
+    }
+}
+
+/// Whether `statements` always return, by a `return` of their own. A nested block that returns,
+/// e.g. in an `else`, doesn't count, as other paths may go on after it.
+pub fn always_returns(statements: &[(Statement<'_>, TextSpan)]) -> bool {
+    let last = statements.iter().find(|(statement, _)| {
+        matches!(
+            statement,
+            Statement::Return { .. }
+                | Statement::ReturnMany { .. }
+                | Statement::Break
+                | Statement::Continue
+        )
+    });
+    matches!(
+        last,
+        Some((Statement::Return { .. } | Statement::ReturnMany { .. }, _))
+    )
+}
+
+/// The span from the start of the first statement to the end of the last one, or `None` if there
+/// are none.
+pub fn statements_span(statements: &[(Statement<'_>, TextSpan)]) -> Option<TextSpan> {
+    let (_, first) = statements.first()?;
+    let (_, last) = statements.last()?;
+    Some(TextSpan::new(first.start(), last.end()))
+}
@@ -1,4 +1,5 @@
 use foundation::TextSpan;
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Error {
@@ -11,6 +12,9 @@ pub enum ErrorKind {
     NoLoopToBreak,
     NoLoopToContinue,
     UndefinedVariable(String),
+    ImportNotAllowed,
+    GlobalNotAllowed,
+    MisplacedSpread,
 }
 
 impl Error {
@@ -34,4 +38,41 @@ impl Error {
             span,
         }
     }
+
+    pub fn import_not_allowed(span: TextSpan) -> Self {
+        Self {
+            kind: ErrorKind::ImportNotAllowed,
+            span,
+        }
+    }
+
+    pub fn global_not_allowed(span: TextSpan) -> Self {
+        Self {
+            kind: ErrorKind::GlobalNotAllowed,
+            span,
+        }
+    }
+
+    pub fn misplaced_spread(span: TextSpan) -> Self {
+        Self {
+            kind: ErrorKind::MisplacedSpread,
+            span,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::NoLoopToBreak => write!(f, "`break` outside of a loop"),
+            ErrorKind::NoLoopToContinue => write!(f, "`continue` outside of a loop"),
+            ErrorKind::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+            ErrorKind::ImportNotAllowed => write!(f, "`import` is not allowed here"),
+            ErrorKind::GlobalNotAllowed => write!(f, "`global` is not allowed here"),
+            ErrorKind::MisplacedSpread => write!(
+                f,
+                "`...` is only allowed in array literals and the arguments of calls"
+            ),
+        }
+    }
 }
@@ -3,10 +3,17 @@ use foundation::{ast::*, TextSpan};
 type Result<T> = std::result::Result<T, Error>;
 
 mod error;
-pub use error::Error;
+pub use error::{Error, ErrorKind};
+
+mod warning;
+pub use warning::{Warning, WarningKind};
 
 mod tools;
 use tools::*;
 
 mod compile;
-pub use compile::compile;
+pub use compile::{compile, compile_full, compile_with, Compiled, Options};
+
+mod optimize;
+
+mod registers;
@@ -0,0 +1,321 @@
+//! Peephole optimization of the compiled code: instructions whose effect is undone right away,
+//! jumps to jumps, conditional jumps on a constant, and `Nop`s are removed.
+
+use foundation::TextSpan;
+use std::rc::Rc;
+use vm::code::{Code, FunctionPrototype};
+
+/// Optimizes `code` and the functions it creates. The instructions that stay keep their `spans`.
+pub(crate) fn optimize(
+    code: &[Code],
+    spans: &[Option<TextSpan>],
+) -> (Vec<Code>, Vec<Option<TextSpan>>) {
+    let mut code = code
+        .iter()
+        .map(|code| match code {
+            Code::MakeClosure(proto) => {
+                let (code, spans) = optimize(&proto.code, &proto.spans);
+                Code::MakeClosure(Rc::new(FunctionPrototype {
+                    code: code.into(),
+                    spans: spans.into(),
+                    ..FunctionPrototype::clone(proto)
+                }))
+            }
+            code => code.clone(),
+        })
+        .collect::<Vec<_>>();
+    let mut spans = spans.to_vec();
+    spans.resize(code.len(), None);
+
+    // Removed instructions are first replaced with `Nop`, so that jumps keep their offsets until
+    // the end.
+    let targets = jump_targets(&code);
+    for pc in 0..code.len().saturating_sub(1) {
+        // Both instructions must run together, which a jump to the second one breaks.
+        if targets[pc + 1] {
+            continue;
+        }
+        match (&code[pc], &code[pc + 1]) {
+            (load, Code::UnloadTop) if is_pure_load(load) => {
+                code[pc] = Code::Nop;
+                code[pc + 1] = Code::Nop;
+            }
+            (Code::LoadBool(value), Code::JumpIfTrue(offset) | Code::JumpIfFalse(offset)) => {
+                let jumps = *value == matches!(code[pc + 1], Code::JumpIfTrue(_));
+                code[pc + 1] = match jumps {
+                    true => Code::Jump(*offset),
+                    false => Code::Nop,
+                };
+                code[pc] = Code::Nop;
+            }
+            _ => {}
+        }
+    }
+
+    for pc in 0..code.len() {
+        let (Code::Jump(offset)
+        | Code::JumpIfTrue(offset)
+        | Code::JumpIfFalse(offset)
+        | Code::JumpIfNil(offset)
+        | Code::JumpIfNotNil(offset)
+        | Code::JumpIfTrueOrPop(offset)
+        | Code::JumpIfFalseOrPop(offset)
+        | Code::ForPrep(_, offset)
+        | Code::ForLoop(_, offset)
+        | Code::IterNext(_, offset)
+        | Code::IterEnd(offset)
+        | Code::Try(offset)) = code[pc]
+        else {
+            continue;
+        };
+        let target = final_target(&code, pc.wrapping_add_signed(offset));
+        // A jump over nothing but `Nop`s does nothing.
+        let skips_nothing = target > pc && code[pc + 1..target].iter().all(is_nop);
+        match &mut code[pc] {
+            Code::Jump(_) if skips_nothing => code[pc] = Code::Nop,
+            Code::Jump(offset)
+            | Code::JumpIfTrue(offset)
+            | Code::JumpIfFalse(offset)
+            | Code::JumpIfNil(offset)
+            | Code::JumpIfNotNil(offset)
+            | Code::JumpIfTrueOrPop(offset)
+            | Code::JumpIfFalseOrPop(offset)
+            | Code::ForPrep(_, offset)
+            | Code::ForLoop(_, offset)
+            | Code::IterNext(_, offset)
+            | Code::IterEnd(offset)
+            | Code::Try(offset) => {
+                *offset = target as isize - pc as isize;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    strip_nops(code, spans)
+}
+
+fn jump_targets(code: &[Code]) -> Vec<bool> {
+    let mut targets = vec![false; code.len() + 1];
+    for (pc, code) in code.iter().enumerate() {
+        match code {
+            Code::Jump(offset)
+            | Code::JumpIfTrue(offset)
+            | Code::JumpIfFalse(offset)
+            | Code::JumpIfNil(offset)
+            | Code::JumpIfNotNil(offset)
+            | Code::JumpIfTrueOrPop(offset)
+            | Code::JumpIfFalseOrPop(offset)
+            | Code::ForPrep(_, offset)
+            | Code::ForLoop(_, offset)
+            | Code::IterEnd(offset)
+            | Code::Try(offset) => targets[pc.wrapping_add_signed(*offset)] = true,
+            // The item is also pushed for the instruction after the `IterEnd`.
+            Code::IterNext(_, offset) => {
+                targets[pc.wrapping_add_signed(*offset)] = true;
+                targets[pc + 2] = true;
+            }
+            _ => {}
+        }
+    }
+    targets
+}
+
+/// Where a jump to `target` ends up, after the `Nop`s and unconditional jumps found there.
+fn final_target(code: &[Code], mut target: usize) -> usize {
+    // Bounded, as the jumps may make a loop.
+    for _ in 0..code.len() {
+        match code.get(target) {
+            Some(Code::Nop) => target += 1,
+            Some(Code::Jump(offset)) if *offset != 0 => {
+                target = target.wrapping_add_signed(*offset);
+            }
+            _ => break,
+        }
+    }
+    target
+}
+
+fn is_pure_load(code: &Code) -> bool {
+    matches!(
+        code,
+        Code::LoadInt(_)
+            | Code::LoadFloat(_)
+            | Code::LoadBool(_)
+            | Code::LoadString(_)
+            | Code::LoadNil
+            | Code::LoadLocal(_)
+            | Code::LoadRustFunction(_)
+    )
+}
+
+fn is_nop(code: &Code) -> bool {
+    matches!(code, Code::Nop)
+}
+
+fn strip_nops(code: Vec<Code>, spans: Vec<Option<TextSpan>>) -> (Vec<Code>, Vec<Option<TextSpan>>) {
+    // The last instruction stays, so that jumps to it still land in the code.
+    let len = code.len();
+    let keep = |pc: usize, code: &Code| !is_nop(code) || pc + 1 == len;
+
+    // The new position of each instruction, and of the end of the code. A `Nop` takes the
+    // position of the instruction after it.
+    let mut positions = Vec::with_capacity(len + 1);
+    let mut position = 0;
+    for (pc, code) in code.iter().enumerate() {
+        positions.push(position);
+        position += keep(pc, code) as usize;
+    }
+    positions.push(position);
+
+    code.into_iter()
+        .zip(spans)
+        .enumerate()
+        .filter(|(pc, (code, _))| keep(*pc, code))
+        .map(|(pc, (code, span))| {
+            let code = match code {
+                Code::Jump(offset) => Code::Jump(relocate(&positions, pc, offset)),
+                Code::JumpIfTrue(offset) => Code::JumpIfTrue(relocate(&positions, pc, offset)),
+                Code::JumpIfFalse(offset) => Code::JumpIfFalse(relocate(&positions, pc, offset)),
+                Code::JumpIfNil(offset) => Code::JumpIfNil(relocate(&positions, pc, offset)),
+                Code::JumpIfNotNil(offset) => Code::JumpIfNotNil(relocate(&positions, pc, offset)),
+                Code::JumpIfTrueOrPop(offset) => {
+                    Code::JumpIfTrueOrPop(relocate(&positions, pc, offset))
+                }
+                Code::JumpIfFalseOrPop(offset) => {
+                    Code::JumpIfFalseOrPop(relocate(&positions, pc, offset))
+                }
+                Code::ForPrep(id, offset) => Code::ForPrep(id, relocate(&positions, pc, offset)),
+                Code::ForLoop(id, offset) => Code::ForLoop(id, relocate(&positions, pc, offset)),
+                Code::IterNext(id, offset) => Code::IterNext(id, relocate(&positions, pc, offset)),
+                Code::IterEnd(offset) => Code::IterEnd(relocate(&positions, pc, offset)),
+                Code::Try(offset) => Code::Try(relocate(&positions, pc, offset)),
+                code => code,
+            };
+            (code, span)
+        })
+        .unzip()
+}
+
+fn relocate(positions: &[usize], pc: usize, offset: isize) -> isize {
+    positions[pc.wrapping_add_signed(offset)] as isize - positions[pc] as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use vm::code::LocalId;
+
+    #[test]
+    fn removes_unused_loads() {
+        let code = [
+            Code::LoadLocal(LocalId(0)),
+            Code::UnloadTop,
+            Code::LoadInt(1),
+            Code::Return,
+        ];
+        assert_eq!(optimize(&code, &[]).0, vec![Code::LoadInt(1), Code::Return]);
+    }
+
+    #[test]
+    fn keeps_spans() {
+        let add = TextSpan::new(4, 5);
+        let code = [
+            Code::LoadInt(1),
+            Code::UnloadTop,
+            Code::LoadLocal(LocalId(0)),
+            Code::LoadInt(1),
+            Code::Add,
+            Code::Return,
+        ];
+        let spans = [None, None, None, None, Some(add), None];
+        assert_eq!(
+            optimize(&code, &spans),
+            (code[2..].to_vec(), vec![None, None, Some(add), None])
+        );
+    }
+
+    #[test]
+    fn folds_constant_conditions() {
+        // while true do x() end
+        let code = [
+            Code::LoadBool(true),
+            Code::JumpIfFalse(5),
+            Code::LoadLocal(LocalId(0)),
+            Code::Call(0),
+            Code::UnloadTop,
+            Code::Jump(-5),
+            Code::LoadNil,
+            Code::Return,
+        ];
+        assert_eq!(
+            optimize(&code, &[]).0,
+            vec![
+                Code::LoadLocal(LocalId(0)),
+                Code::Call(0),
+                Code::UnloadTop,
+                Code::Jump(-3),
+                Code::LoadNil,
+                Code::Return,
+            ]
+        );
+
+        let code = [
+            Code::LoadBool(false),
+            Code::JumpIfFalse(2),
+            Code::Exit,
+            Code::Return,
+        ];
+        assert_eq!(
+            optimize(&code, &[]).0,
+            vec![Code::Jump(2), Code::Exit, Code::Return]
+        );
+    }
+
+    #[test]
+    fn collapses_jump_chains() {
+        let code = [
+            Code::LoadLocal(LocalId(0)),
+            Code::JumpIfTrue(3),
+            Code::LoadNil,
+            Code::Return,
+            Code::Jump(2),
+            Code::Return,
+            Code::LoadInt(1),
+            Code::Return,
+        ];
+        let mut expected = code.to_vec();
+        expected[1] = Code::JumpIfTrue(5);
+        assert_eq!(optimize(&code, &[]).0, expected);
+
+        // An endless loop stays one.
+        let code = [Code::Jump(1), Code::Jump(-1)];
+        assert_eq!(optimize(&code, &[]).0, vec![Code::Jump(0)]);
+    }
+
+    #[test]
+    fn strips_nops() {
+        // if x then y = 1 end
+        let code = [
+            Code::LoadLocal(LocalId(0)),
+            Code::JumpIfFalse(4),
+            Code::LoadInt(1),
+            Code::SetLocal(LocalId(1)),
+            Code::Jump(1),
+            Code::Nop,
+            Code::LoadNil,
+            Code::Return,
+        ];
+        assert_eq!(
+            optimize(&code, &[]).0,
+            vec![
+                Code::LoadLocal(LocalId(0)),
+                Code::JumpIfFalse(3),
+                Code::LoadInt(1),
+                Code::SetLocal(LocalId(1)),
+                Code::LoadNil,
+                Code::Return,
+            ]
+        );
+    }
+}
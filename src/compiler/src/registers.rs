@@ -0,0 +1,217 @@
+//! Translation of stack code into register code, which reads the operands of arithmetic and
+//! comparisons straight from locals and constants, and writes results straight into locals. Locals
+//! are the registers: `a = b + 1` becomes a single `BinaryTo` instead of loading both operands,
+//! adding them and storing the result.
+
+use foundation::TextSpan;
+use std::rc::Rc;
+use vm::code::{BinaryOp, Code, FunctionPrototype, Operand};
+
+/// Translates `code` and the functions it creates. The result does the same as `code`, in the
+/// same order, with fewer instructions, which keep the `spans` of those they came from.
+pub(crate) fn translate(
+    code: &[Code],
+    spans: &[Option<TextSpan>],
+) -> (Vec<Code>, Vec<Option<TextSpan>>) {
+    // Where jumps land, which must stay the start of an instruction.
+    let mut targets = vec![false; code.len() + 1];
+    for (pc, code) in code.iter().enumerate() {
+        match code {
+            Code::Jump(offset)
+            | Code::JumpIfTrue(offset)
+            | Code::JumpIfFalse(offset)
+            | Code::JumpIfNil(offset)
+            | Code::JumpIfNotNil(offset)
+            | Code::JumpIfTrueOrPop(offset)
+            | Code::JumpIfFalseOrPop(offset)
+            | Code::ForPrep(_, offset)
+            | Code::ForLoop(_, offset)
+            | Code::IterEnd(offset)
+            | Code::Try(offset) => targets[pc.wrapping_add_signed(*offset)] = true,
+            // The item is also pushed for the instruction after the `IterEnd`.
+            Code::IterNext(_, offset) => {
+                targets[pc.wrapping_add_signed(*offset)] = true;
+                targets[pc + 2] = true;
+            }
+            _ => {}
+        }
+    }
+
+    let mut translator = Translator::default();
+    // The new position of each instruction, and of the end of the code.
+    let mut positions = Vec::with_capacity(code.len() + 1);
+    for (pc, code) in code.iter().enumerate() {
+        if targets[pc] {
+            translator.flush();
+            translator.barrier = translator.out.len();
+        }
+        positions.push(translator.out.len());
+        translator.span = spans.get(pc).copied().flatten();
+        translator.translate(pc, code);
+    }
+    translator.flush();
+    positions.push(translator.out.len());
+
+    let mut out = translator.out;
+    for (at, target) in translator.jumps {
+        let offset = positions[target] as isize - at as isize;
+        match &mut out[at] {
+            Code::Jump(x)
+            | Code::JumpIfTrue(x)
+            | Code::JumpIfFalse(x)
+            | Code::JumpIfNil(x)
+            | Code::JumpIfNotNil(x)
+            | Code::JumpIfTrueOrPop(x)
+            | Code::JumpIfFalseOrPop(x)
+            | Code::ForPrep(_, x)
+            | Code::ForLoop(_, x)
+            | Code::IterNext(_, x)
+            | Code::IterEnd(x)
+            | Code::Try(x) => *x = offset,
+            _ => unreachable!(),
+        }
+    }
+    // A binary operator on two stack values is no shorter as a register instruction.
+    for code in out.iter_mut() {
+        if let Code::Binary(op, Operand::Stack, Operand::Stack) = code {
+            *code = stack_instruction(*op);
+        }
+    }
+    (out, translator.spans)
+}
+
+#[derive(Default)]
+struct Translator {
+    out: Vec<Code>,
+    /// The span of each instruction in `out`.
+    spans: Vec<Option<TextSpan>>,
+    /// The span of the instruction being translated.
+    span: Option<TextSpan>,
+    /// Locals and constants loaded by the stack code and not pushed yet, as they may become the
+    /// operands of the next instructions instead. They are the top of the stack, in order.
+    pending: Vec<Operand>,
+    /// Instructions before this one may not be merged with the following ones, as a jump lands
+    /// here.
+    barrier: usize,
+    /// The jumps, by their new position, with the old position of their target.
+    jumps: Vec<(usize, usize)>,
+}
+
+impl Translator {
+    fn translate(&mut self, pc: usize, code: &Code) {
+        match code {
+            Code::LoadLocal(id) => self.pending.push(Operand::Local(*id)),
+            Code::LoadInt(x) => self.pending.push(Operand::Int(*x)),
+            Code::SetLocal(dst) => match self.pending.pop() {
+                Some(src) => {
+                    // The local may be one of the values still to be pushed.
+                    self.flush();
+                    self.push(Code::Move(*dst, src));
+                }
+                None => match self.out.last() {
+                    Some(&Code::Binary(op, lhs, rhs)) if self.out.len() > self.barrier => {
+                        *self.out.last_mut().unwrap() = Code::BinaryTo(*dst, op, lhs, rhs);
+                    }
+                    _ => self.push(code.clone()),
+                },
+            },
+            Code::Jump(offset)
+            | Code::JumpIfTrue(offset)
+            | Code::JumpIfFalse(offset)
+            | Code::JumpIfNil(offset)
+            | Code::JumpIfNotNil(offset)
+            | Code::JumpIfTrueOrPop(offset)
+            | Code::JumpIfFalseOrPop(offset)
+            | Code::ForPrep(_, offset)
+            | Code::ForLoop(_, offset)
+            | Code::IterNext(_, offset)
+            | Code::IterEnd(offset)
+            | Code::Try(offset) => {
+                self.flush();
+                self.jumps
+                    .push((self.out.len(), pc.wrapping_add_signed(*offset)));
+                self.push(code.clone());
+            }
+            Code::MakeClosure(proto) => {
+                self.flush();
+                let (code, spans) = translate(&proto.code, &proto.spans);
+                self.push(Code::MakeClosure(Rc::new(FunctionPrototype {
+                    code: code.into(),
+                    spans: spans.into(),
+                    ..FunctionPrototype::clone(proto)
+                })));
+            }
+            code => match binary_op(code) {
+                Some(op) => {
+                    // Operands already on the stack are below the pending ones.
+                    let rhs = self.pending.pop().unwrap_or(Operand::Stack);
+                    let lhs = self.pending.pop().unwrap_or(Operand::Stack);
+                    self.flush();
+                    self.push(Code::Binary(op, lhs, rhs));
+                }
+                None => {
+                    self.flush();
+                    self.push(code.clone());
+                }
+            },
+        }
+    }
+
+    /// Outputs `code`, which came from the instruction being translated.
+    fn push(&mut self, code: Code) {
+        self.out.push(code);
+        self.spans.push(self.span);
+    }
+
+    /// Pushes the pending values.
+    fn flush(&mut self) {
+        for operand in self.pending.drain(..) {
+            self.out.push(match operand {
+                Operand::Local(id) => Code::LoadLocal(id),
+                Operand::Int(x) => Code::LoadInt(x),
+                Operand::Stack => unreachable!(),
+            });
+            // Loads can't fail.
+            self.spans.push(None);
+        }
+    }
+}
+
+fn binary_op(code: &Code) -> Option<BinaryOp> {
+    Some(match code {
+        Code::Add => BinaryOp::Add,
+        Code::Sub => BinaryOp::Sub,
+        Code::Mul => BinaryOp::Mul,
+        Code::Div => BinaryOp::Div,
+        Code::Mod => BinaryOp::Mod,
+        Code::Pow => BinaryOp::Pow,
+        Code::FloorDiv => BinaryOp::FloorDiv,
+        Code::Eq => BinaryOp::Eq,
+        Code::NotEq => BinaryOp::NotEq,
+        Code::Less => BinaryOp::Less,
+        Code::LessEq => BinaryOp::LessEq,
+        Code::Greater => BinaryOp::Greater,
+        Code::GreaterEq => BinaryOp::GreaterEq,
+        Code::Concat => BinaryOp::Concat,
+        _ => return None,
+    })
+}
+
+fn stack_instruction(op: BinaryOp) -> Code {
+    match op {
+        BinaryOp::Add => Code::Add,
+        BinaryOp::Sub => Code::Sub,
+        BinaryOp::Mul => Code::Mul,
+        BinaryOp::Div => Code::Div,
+        BinaryOp::Mod => Code::Mod,
+        BinaryOp::Pow => Code::Pow,
+        BinaryOp::FloorDiv => Code::FloorDiv,
+        BinaryOp::Eq => Code::Eq,
+        BinaryOp::NotEq => Code::NotEq,
+        BinaryOp::Less => Code::Less,
+        BinaryOp::LessEq => Code::LessEq,
+        BinaryOp::Greater => Code::Greater,
+        BinaryOp::GreaterEq => Code::GreaterEq,
+        BinaryOp::Concat => Code::Concat,
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{Options, Warning};
+use foundation::TextSpan;
 use std::ops::Deref;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,34 +24,129 @@ impl Deref for VariableId {
 pub struct Context<'src> {
     block_vars_count: internal::NestedCounter,
     loop_vars_count: internal::NestedCounter,
+    loop_resources_count: internal::NestedCounter,
+    loop_handlers_count: internal::NestedCounter,
     id_generator: internal::VariableIdGenerator<'src>,
+    options: Options,
+    warnings: Vec<Warning>,
+    /// The variables named in the source, by their id, to warn about the unused ones. Variables
+    /// made by the compiler are `None`.
+    definitions: Vec<Option<Definition<'src>>>,
+    /// The instance a constructor returns, also by a `return` without a value.
+    instance: Option<VariableId>,
+    /// The names declared with `global`, each with the number of variables when it was, since
+    /// the variables defined after it shadow it.
+    globals: Vec<(&'src str, usize)>,
+    /// The number of globals declared outside each block.
+    global_blocks: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Definition<'src> {
+    name: &'src str,
+    span: TextSpan,
+    parameter: bool,
+    used: bool,
 }
 
 impl<'src> Context<'src> {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_options(Options::default())
+    }
+
+    pub fn with_options(options: Options) -> Self {
         Self {
             block_vars_count: internal::NestedCounter::new(),
             loop_vars_count: internal::NestedCounter::new(),
+            loop_resources_count: internal::NestedCounter::new(),
+            loop_handlers_count: internal::NestedCounter::new(),
             id_generator: internal::VariableIdGenerator::new(),
+            options,
+            warnings: Vec::new(),
+            definitions: Vec::new(),
+            instance: None,
+            globals: Vec::new(),
+            global_blocks: Vec::new(),
         }
     }
 
+    /// Makes the context of a function defined in this one, which sees the same globals.
+    pub fn nested(&self) -> Self {
+        let mut context = Self::with_options(self.options);
+        context.globals = self
+            .globals
+            .iter()
+            .filter(|(name, _)| self.is_global(name))
+            .map(|(name, _)| (*name, 0))
+            .collect();
+        context
+    }
+
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    pub fn warn(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Takes the warnings reported so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn set_instance(&mut self, id: VariableId) {
+        self.instance = Some(id);
+    }
+
+    pub fn instance(&self) -> Option<VariableId> {
+        self.instance
+    }
+
     pub fn begin_block(&mut self) {
         self.block_vars_count.start_section();
+        self.global_blocks.push(self.globals.len());
     }
 
     pub fn end_block(&mut self) {
+        let globals_len = self.global_blocks.pop().unwrap_or_default();
+        self.globals.truncate(globals_len);
         let block_cnt = self.block_vars_count.end_section();
-        self.id_generator.drop_variable(block_cnt);
+        self.forget_variables(block_cnt);
         self.loop_vars_count.decrement(block_cnt);
     }
 
     pub fn begin_loop(&mut self) {
         self.loop_vars_count.start_section();
+        self.loop_resources_count.start_section();
+        self.loop_handlers_count.start_section();
     }
 
     pub fn end_loop(&mut self) {
         self.loop_vars_count.end_section();
+        self.loop_resources_count.end_section();
+        self.loop_handlers_count.end_section();
+    }
+
+    #[inline]
+    pub fn enter_resource(&mut self) {
+        self.loop_resources_count.increment(1);
+    }
+
+    #[inline]
+    pub fn exit_resource(&mut self) {
+        self.loop_resources_count.decrement(1);
+    }
+
+    #[inline]
+    pub fn enter_handler(&mut self) {
+        self.loop_handlers_count.increment(1);
+    }
+
+    #[inline]
+    pub fn exit_handler(&mut self) {
+        self.loop_handlers_count.decrement(1);
     }
 
     /// Returns the number of locals in the current loop section.
@@ -59,6 +156,20 @@ impl<'src> Context<'src> {
         self.loop_vars_count.get_current_count()
     }
 
+    /// Returns the number of `with` resources entered in the current loop section.
+    /// Returns [`None`] if there is no current loop section.
+    #[inline]
+    pub fn get_loop_resources_count(&self) -> Option<usize> {
+        self.loop_resources_count.get_current_count()
+    }
+
+    /// Returns the number of `try` blocks entered in the current loop section.
+    /// Returns [`None`] if there is no current loop section.
+    #[inline]
+    pub fn get_loop_handlers_count(&self) -> Option<usize> {
+        self.loop_handlers_count.get_current_count()
+    }
+
     #[inline]
     pub fn get_block_local_count(&self) -> usize {
         self.block_vars_count
@@ -66,13 +177,40 @@ impl<'src> Context<'src> {
             .expect("[BUG] This should be called after `Context::begin_block()` is called.")
     }
 
+    /// Adds a variable made by the compiler, e.g. for a builtin or a hidden temporary.
     #[inline]
     pub fn add_variable(&mut self, name: &'src str) -> VariableId {
         self.block_vars_count.increment(1);
         self.loop_vars_count.increment(1);
+        self.definitions.push(None);
         self.id_generator.add_variable(name)
     }
 
+    /// Adds a variable defined in the source at `span`, which is warned about if it is never read
+    /// or if it shadows another one. Names starting with `_` are exempt.
+    pub fn define_variable(&mut self, name: &'src str, span: TextSpan) -> VariableId {
+        self.define(name, span, false)
+    }
+
+    /// Like [`Context::define_variable`], for a parameter of a function.
+    pub fn define_parameter(&mut self, name: &'src str, span: TextSpan) -> VariableId {
+        self.define(name, span, true)
+    }
+
+    fn define(&mut self, name: &'src str, span: TextSpan, parameter: bool) -> VariableId {
+        if !name.starts_with('_') && self.id_generator.resolve_variable(name).is_some() {
+            self.warn(Warning::shadowing(name.to_string(), span));
+        }
+        let id = self.add_variable(name);
+        *self.definitions.last_mut().unwrap() = Some(Definition {
+            name,
+            span,
+            parameter,
+            used: false,
+        });
+        id
+    }
+
     pub fn add_variable_many(&mut self, names: impl IntoIterator<Item = &'src str>) {
         for name in names.into_iter() {
             self.add_variable(name);
@@ -81,13 +219,69 @@ impl<'src> Context<'src> {
 
     #[inline]
     pub fn drop_variable(&mut self, count: usize) {
-        self.id_generator.drop_variable(count);
+        self.forget_variables(count);
         self.block_vars_count.decrement(count);
         self.loop_vars_count.decrement(count);
     }
 
+    // Warns about the variables going out of scope that were never read.
+    fn forget_variables(&mut self, count: usize) {
+        self.id_generator.drop_variable(count);
+        let dropped = self.definitions.len() - count;
+        for definition in self.definitions.drain(dropped..).flatten() {
+            // `self` is there for the method call syntax, even if the method doesn't read it.
+            let is_self = definition.parameter && definition.name == "self";
+            if definition.used || is_self || definition.name.starts_with('_') {
+                continue;
+            }
+            let name = definition.name.to_string();
+            self.warnings.push(match definition.parameter {
+                true => Warning::unused_parameter(name, definition.span),
+                false => Warning::unused_variable(name, definition.span),
+            });
+        }
+    }
+
+    /// Resolves a variable that is read.
+    #[inline]
+    pub fn resolve_variable(&mut self, name: &'src str) -> Option<VariableId> {
+        let id = self.id_generator.resolve_variable(name)?;
+        self.use_variable(id);
+        Some(id)
+    }
+
+    /// Marks a variable as read by the compiled code itself.
+    #[inline]
+    pub fn use_variable(&mut self, id: VariableId) {
+        if let Some(definition) = &mut self.definitions[*id] {
+            definition.used = true;
+        }
+    }
+
+    /// Declares a global, which `name` refers to until the end of the block or a variable of the
+    /// same name is defined.
+    pub fn declare_global(&mut self, name: &'src str) {
+        self.globals.push((name, self.definitions.len()));
+    }
+
+    /// Whether `name` refers to a global rather than a variable.
+    pub fn is_global(&self, name: &'src str) -> bool {
+        let Some((_, count)) = self
+            .globals
+            .iter()
+            .rev()
+            .find(|(global, _)| *global == name)
+        else {
+            return false;
+        };
+        self.id_generator
+            .resolve_variable(name)
+            .is_none_or(|id| *id < *count)
+    }
+
+    /// Resolves a variable that is assigned to, which doesn't count as using it.
     #[inline]
-    pub fn resolve_variable(&self, name: &'src str) -> Option<VariableId> {
+    pub fn resolve_assignment(&self, name: &'src str) -> Option<VariableId> {
         self.id_generator.resolve_variable(name)
     }
 }
@@ -1,4 +1,6 @@
 use super::*;
+use std::{collections::HashMap, rc::Rc};
+use vm::runtime::StringObject;
 
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct Fragment {
@@ -52,11 +54,26 @@ impl<'node, 'src: 'node> Fragment {
         self.backward_jump_pos.clear();
     }
 
+    /// Sets the placeholders at `positions` to jump to the end of the fragment if the value on the
+    /// stack is nil, e.g. to skip the rest of `a?.b.c` when `a` is nil.
+    pub fn patch_nil_jumps(&mut self, positions: impl IntoIterator<Item = usize>) {
+        let len = self.icode.len();
+        for pos in positions {
+            debug_assert!(matches!(self.icode[pos], ICode::Placeholder));
+            self.icode[pos] = ICode::JumpIfNil((len - pos) as isize);
+        }
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.icode.len()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.icode.is_empty()
+    }
+
     #[inline]
     pub fn append(&mut self, code: ICode) -> &mut Self {
         self.icode.push(code);
@@ -138,46 +155,120 @@ impl<'node, 'src: 'node> Fragment {
     }
 
     #[inline]
-    pub fn into_code(self) -> Vec<vm::code::Code> {
-        use std::rc::Rc;
-        use vm::code::{Code, LocalId};
+    pub fn last_mut(&mut self) -> Option<&mut ICode> {
+        self.icode.last_mut()
+    }
 
-        #[allow(unused_variables)]
+    /// Takes the intermediate code. All jumps must have been patched.
+    pub fn into_icode(self) -> Vec<ICode> {
+        debug_assert!(self.forward_jump_pos.is_empty() && self.backward_jump_pos.is_empty());
         self.icode
-            .into_iter()
-            .map(|icode| match icode {
+    }
+
+    /// Lowers the intermediate code to the VM's, with the source span of each instruction.
+    #[inline]
+    pub fn into_code(self) -> (Vec<vm::code::Code>, Vec<Option<TextSpan>>) {
+        lower(self.icode, &mut Constants::default())
+    }
+}
+
+/// The strings of a program, so that every use of the same literal or method name, in any of its
+/// functions, shares one allocation. Shared strings also compare equal by pointer.
+#[derive(Default)]
+struct Constants {
+    strings: HashMap<String, StringObject>,
+    names: HashMap<String, Rc<str>>,
+}
+
+impl Constants {
+    fn string(&mut self, string: String) -> StringObject {
+        self.strings
+            .entry(string)
+            .or_insert_with_key(|string| StringObject::new(string.clone()))
+            .clone()
+    }
+
+    fn name(&mut self, name: &str) -> Rc<str> {
+        if let Some(name) = self.names.get(name) {
+            return Rc::clone(name);
+        }
+        let rc: Rc<str> = name.into();
+        self.names.insert(name.to_string(), Rc::clone(&rc));
+        rc
+    }
+}
+
+fn lower(
+    icode: Vec<ICode>,
+    constants: &mut Constants,
+) -> (Vec<vm::code::Code>, Vec<Option<TextSpan>>) {
+    use vm::code::{BuiltinInstr, BuiltinMethod, Code, FunctionPrototype, LocalId, MethodSite};
+
+    #[allow(unused_variables)]
+    icode
+        .into_iter()
+        .map(|icode| {
+            let span = icode.span();
+            let code = match icode {
                 ICode::LoadInt(x) => Code::LoadInt(x),
                 ICode::LoadFloat(x) => Code::LoadFloat(x),
                 ICode::LoadBool(x) => Code::LoadBool(x),
-                ICode::LoadString(x) => Code::LoadString(Rc::new(x)),
+                ICode::LoadString(x) => Code::LoadString(constants.string(x)),
                 ICode::LoadNil => Code::LoadNil,
                 ICode::LoadLocal(id) => Code::LoadLocal(LocalId(*id)),
+                ICode::LoadGlobal(name) => Code::LoadGlobal(constants.string(name)),
+                ICode::LoadRustFunction(func) => Code::LoadRustFunction(func),
                 ICode::UnloadTop => Code::UnloadTop,
                 ICode::SetLocal(id) => Code::SetLocal(LocalId(*id)),
+                ICode::SetGlobal(name) => Code::SetGlobal(constants.string(name)),
                 ICode::MakeLocal => Code::MakeLocal,
                 ICode::MakeArray(len) => Code::MakeArray(len),
+                ICode::MakeArraySpread(len, span) => Code::MakeArraySpread(len),
                 ICode::MakeNamed => Code::MakeNamed,
                 ICode::MakeTable(len) => Code::MakeTable(len),
                 ICode::DropLocal(count) => Code::DropLocal(count),
                 ICode::Jump(x) => Code::Jump(x),
                 ICode::JumpIfTrue(x) => Code::JumpIfTrue(x),
+                ICode::JumpIfNil(x) => Code::JumpIfNil(x),
+                ICode::JumpIfNotNil(x) => Code::JumpIfNotNil(x),
+                ICode::JumpIfTrueOrPop(x) => Code::JumpIfTrueOrPop(x),
+                ICode::JumpIfFalseOrPop(x) => Code::JumpIfFalseOrPop(x),
                 ICode::JumpIfFalse(x) => Code::JumpIfFalse(x),
-                ICode::CallMethod(name, arg_count, span) => Code::CallMethod(name, arg_count),
+                ICode::ForPrep(id, x, span) => Code::ForPrep(LocalId(*id), x),
+                ICode::ForLoop(id, x) => Code::ForLoop(LocalId(*id), x),
+                ICode::IterStart(span) => Code::IterStart,
+                ICode::IterNext(id, x, span) => Code::IterNext(LocalId(*id), x),
+                ICode::IterEnd(x) => Code::IterEnd(x),
+                ICode::CallMethod(name, arg_count, span) => match BuiltinMethod::from_name(&name) {
+                    Some(method) => Code::CallBuiltinMethod(method, arg_count),
+                    None => Code::CallMethod(MethodSite::new(constants.name(&name)), arg_count),
+                },
+                ICode::CallMethodSpread(name, span) => {
+                    Code::CallMethodSpread(MethodSite::new(constants.name(&name)))
+                }
                 ICode::Call(arg_count, span) => Code::Call(arg_count),
+                ICode::CallSpread(span) => Code::CallSpread,
+                ICode::TailCall(arg_count, span) => Code::TailCall(arg_count),
+                ICode::Unpack(count) => Code::Unpack(count),
                 ICode::SetItem(span) => Code::SetItem,
                 ICode::GetItem(span) => Code::GetItem,
+                ICode::GetSlice(span) => Code::GetSlice,
                 ICode::Add(span) => Code::Add,
                 ICode::Sub(span) => Code::Sub,
                 ICode::Mul(span) => Code::Mul,
                 ICode::Div(span) => Code::Div,
                 ICode::Mod(span) => Code::Mod,
+                ICode::Pow(span) => Code::Pow,
+                ICode::FloorDiv(span) => Code::FloorDiv,
                 ICode::Unm(span) => Code::Unm,
+                ICode::Not => Code::Not,
                 ICode::Eq(span) => Code::Eq,
                 ICode::NotEq(span) => Code::NotEq,
                 ICode::Less(span) => Code::Less,
                 ICode::LessEq(span) => Code::LessEq,
                 ICode::Greater(span) => Code::Greater,
                 ICode::GreaterEq(span) => Code::GreaterEq,
+                ICode::In(span) => Code::In,
                 ICode::Concat(span) => Code::Concat,
                 ICode::BitAnd(span) => Code::BitAnd,
                 ICode::BitOr(span) => Code::BitOr,
@@ -186,16 +277,37 @@ impl<'node, 'src: 'node> Fragment {
                 ICode::ShiftL(span) => Code::ShiftL,
                 ICode::ShiftR(span) => Code::ShiftR,
                 ICode::Builtin(instr, arg_count) => Code::Builtin(instr, arg_count),
-                ICode::BeginFuncCreation => Code::BeginFuncCreation,
-                ICode::AddCapture(id) => Code::AddCapture(LocalId(*id)),
-                ICode::AddArgument(x) => Code::AddArgument(x),
-                ICode::EndFuncCreation => Code::EndFuncCreation,
+                ICode::Raise(span) => Code::Builtin(BuiltinInstr::Error, 1),
+                ICode::MakeClosure {
+                    name,
+                    captures,
+                    args,
+                    code,
+                } => {
+                    let (code, spans) = lower(code, constants);
+                    Code::MakeClosure(Rc::new(FunctionPrototype {
+                        name,
+                        captures: captures.into_iter().map(|id| LocalId(*id)).collect(),
+                        args,
+                        code: code.into(),
+                        spans: spans.into(),
+                        file: None,
+                    }))
+                }
+                ICode::EnterWith(span) => Code::EnterWith,
+                ICode::ExitWith => Code::ExitWith,
+                ICode::Try(x) => Code::Try(x),
+                ICode::EndTry => Code::EndTry,
+                ICode::Yield(span) => Code::Yield,
+                ICode::Import(span) => Code::Import,
                 ICode::Placeholder => panic!("Placeholder should not be in the final code."),
                 ICode::Nop => Code::Nop,
                 ICode::Return => Code::Return,
-            })
-            .collect()
-    }
+                ICode::ReturnMany(count) => Code::ReturnMany(count),
+            };
+            (code, span)
+        })
+        .unzip()
 }
 
 #[cfg(test)]
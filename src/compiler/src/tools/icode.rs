@@ -1,7 +1,12 @@
 use super::*;
 use std::borrow::Cow;
-use vm::code::{ArgumentKind, BuiltinInstr};
+use vm::{
+    code::{ArgumentKind, BuiltinInstr},
+    runtime::Object,
+    RuntimeError,
+};
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum ICode {
     LoadInt(i64),
@@ -10,11 +15,15 @@ pub enum ICode {
     LoadString(String),
     LoadNil,
     LoadLocal(VariableId),
+    LoadGlobal(String),
+    LoadRustFunction(fn(&[Object]) -> std::result::Result<Object, RuntimeError>),
     UnloadTop,
 
     SetLocal(VariableId),
+    SetGlobal(String),
     MakeLocal,
     MakeArray(u32),
+    MakeArraySpread(u32, TextSpan),
     MakeNamed,
     MakeTable(u32),
     DropLocal(usize),
@@ -22,23 +31,43 @@ pub enum ICode {
     Jump(isize),
     JumpIfTrue(isize),
     JumpIfFalse(isize),
+    JumpIfNil(isize),
+    JumpIfNotNil(isize),
+    JumpIfTrueOrPop(isize),
+    JumpIfFalseOrPop(isize),
+
+    ForPrep(VariableId, isize, TextSpan),
+    ForLoop(VariableId, isize),
+
+    IterStart(TextSpan),
+    IterNext(VariableId, isize, TextSpan),
+    IterEnd(isize),
 
     CallMethod(Cow<'static, str>, u8, TextSpan),
+    CallMethodSpread(Cow<'static, str>, TextSpan),
     Call(u8, TextSpan),
+    CallSpread(TextSpan),
+    TailCall(u8, TextSpan),
+    Unpack(u8),
     SetItem(TextSpan),
     GetItem(TextSpan),
+    GetSlice(TextSpan),
     Add(TextSpan),       // +
     Sub(TextSpan),       // -
     Mul(TextSpan),       // *
     Div(TextSpan),       // /
     Mod(TextSpan),       // %
+    Pow(TextSpan),       // **
+    FloorDiv(TextSpan),  // //
     Unm(TextSpan),       // - (unary)
+    Not,                 // not
     Eq(TextSpan),        // ==
     NotEq(TextSpan),     // !=
     Less(TextSpan),      // <
     LessEq(TextSpan),    // <=
     Greater(TextSpan),   // >
     GreaterEq(TextSpan), // >=
+    In(TextSpan),        // in
     Concat(TextSpan),    // ..
     BitAnd(TextSpan),    // &
     BitOr(TextSpan),     // |
@@ -48,14 +77,75 @@ pub enum ICode {
     ShiftR(TextSpan),    // >>
 
     Builtin(BuiltinInstr, u8),
+    // Raises the value on top of the stack as an error, for a statement that fails, e.g. `assert`.
+    Raise(TextSpan),
+
+    MakeClosure {
+        name: Option<String>,
+        captures: Vec<VariableId>,
+        args: Vec<ArgumentKind>,
+        code: Vec<ICode>,
+    },
+
+    EnterWith(TextSpan),
+    ExitWith,
 
-    BeginFuncCreation,
-    AddCapture(VariableId),
-    AddArgument(ArgumentKind),
-    EndFuncCreation,
+    Try(isize),
+    EndTry,
+
+    Yield(TextSpan),
+    Import(TextSpan),
 
     Placeholder,
 
     Nop,
     Return,
+    ReturnMany(u8),
+}
+
+impl ICode {
+    /// Where the instruction came from in the source, for the instructions that may fail.
+    pub fn span(&self) -> Option<TextSpan> {
+        match self {
+            ICode::ForPrep(_, _, span)
+            | ICode::IterStart(span)
+            | ICode::MakeArraySpread(_, span)
+            | ICode::IterNext(_, _, span)
+            | ICode::CallMethod(_, _, span)
+            | ICode::CallMethodSpread(_, span)
+            | ICode::Call(_, span)
+            | ICode::CallSpread(span)
+            | ICode::TailCall(_, span)
+            | ICode::SetItem(span)
+            | ICode::GetItem(span)
+            | ICode::GetSlice(span)
+            | ICode::Add(span)
+            | ICode::Sub(span)
+            | ICode::Mul(span)
+            | ICode::Div(span)
+            | ICode::Mod(span)
+            | ICode::Pow(span)
+            | ICode::FloorDiv(span)
+            | ICode::Unm(span)
+            | ICode::Eq(span)
+            | ICode::NotEq(span)
+            | ICode::Less(span)
+            | ICode::LessEq(span)
+            | ICode::Greater(span)
+            | ICode::GreaterEq(span)
+            | ICode::In(span)
+            | ICode::Concat(span)
+            | ICode::BitAnd(span)
+            | ICode::BitOr(span)
+            | ICode::BitXor(span)
+            | ICode::BitNot(span)
+            | ICode::ShiftL(span)
+            | ICode::ShiftR(span)
+            | ICode::EnterWith(span)
+            | ICode::Raise(span)
+            | ICode::Yield(span)
+            | ICode::Import(span) => Some(*span),
+            _ => None,
+        }
+    }
 }
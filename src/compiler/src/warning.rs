@@ -0,0 +1,75 @@
+use foundation::TextSpan;
+use std::fmt;
+
+/// Something in the program that is likely a mistake, but doesn't keep it from being compiled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub span: TextSpan,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// Statements that can never run, e.g. after `return` or in `if false`. They are not
+    /// compiled.
+    UnreachableCode,
+    /// A variable that is never read. Assigning to it doesn't count.
+    UnusedVariable(String),
+    /// A parameter of a function that is never read.
+    UnusedParameter(String),
+    /// A variable defined with the name of another one in scope, which can't be reached anymore.
+    Shadowing(String),
+    /// An attribute, e.g. `@inline`, that the compiler doesn't know. It is ignored.
+    UnknownAttribute(String),
+}
+
+impl Warning {
+    pub fn unreachable_code(span: TextSpan) -> Self {
+        Self {
+            kind: WarningKind::UnreachableCode,
+            span,
+        }
+    }
+
+    pub fn unused_variable(name: String, span: TextSpan) -> Self {
+        Self {
+            kind: WarningKind::UnusedVariable(name),
+            span,
+        }
+    }
+
+    pub fn unused_parameter(name: String, span: TextSpan) -> Self {
+        Self {
+            kind: WarningKind::UnusedParameter(name),
+            span,
+        }
+    }
+
+    pub fn shadowing(name: String, span: TextSpan) -> Self {
+        Self {
+            kind: WarningKind::Shadowing(name),
+            span,
+        }
+    }
+
+    pub fn unknown_attribute(name: String, span: TextSpan) -> Self {
+        Self {
+            kind: WarningKind::UnknownAttribute(name),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            WarningKind::UnreachableCode => write!(f, "unreachable code"),
+            WarningKind::UnusedVariable(name) => write!(f, "unused variable `{}`", name),
+            WarningKind::UnusedParameter(name) => write!(f, "unused parameter `{}`", name),
+            WarningKind::Shadowing(name) => {
+                write!(f, "`{}` shadows a variable of the same name", name)
+            }
+            WarningKind::UnknownAttribute(name) => write!(f, "unknown attribute `@{}`", name),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use super::*;
+use compiler::{Warning, WarningKind};
+use foundation::TextSpan;
+use std::fmt;
+use vm::RuntimeError;
+
+/// An error or a warning about a script, with the parts of the source it is about.
+///
+/// [`Diagnostic::display_in`] shows it with those parts of the source underlined:
+///
+/// ```text
+/// error: Expected then, found `print`
+///  --> main.lico:1:6
+///   |
+/// 1 | if x print(1) end
+///   |      ^^^^^ expected then
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// The parts of the source the diagnostic is about. The first one is where it happened, and
+    /// the others add context to it.
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    /// A suggestion on how to fix it.
+    pub help: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A span of the source, shown underlined with the message next to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub span: TextSpan,
+    /// May be empty, to only underline the span.
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, span: TextSpan, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Shows the diagnostic with the labeled parts of `source`, which `file` holds, underlined.
+    /// The output ends with a newline.
+    pub fn display_in<'a>(&'a self, file: &'a str, source: &'a str) -> impl fmt::Display + 'a {
+        DisplayIn {
+            diagnostic: self,
+            file,
+            source,
+        }
+    }
+}
+
+/// Renders `diagnostics` about `source`, which `file` holds, one after another with a blank line
+/// between them.
+pub fn render(diagnostics: &[Diagnostic], file: &str, source: &str) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.display_in(file, source).to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Error {
+    /// The diagnostics for this error: one for each syntax error, one otherwise.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Error::Lex(errors) => errors.iter().map(Diagnostic::from).collect(),
+            Error::Parse(errors) => errors.iter().map(Diagnostic::from).collect(),
+            Error::Compile(error) => vec![Diagnostic::from(error)],
+            Error::Load(error) => vec![Diagnostic::error(error.to_string())],
+            Error::Runtime(error) => vec![Diagnostic::from(error)],
+        }
+    }
+}
+
+impl From<&lexer::Error> for Diagnostic {
+    fn from(error: &lexer::Error) -> Self {
+        use lexer::Error::*;
+        let label = match error {
+            MissingClosingDelimiter { expected, .. } => format!("expected `{}`", expected),
+            UnterminatedBlockComment(_) => "expected `*/`".to_string(),
+            InvalidEscapeSequence { reason, .. } => reason.clone(),
+            UnexpectedCharInEscapeSequence { expected, .. } => {
+                let expected = expected
+                    .iter()
+                    .map(|range| format!("`{}`..=`{}`", range.start(), range.end()))
+                    .collect::<Vec<_>>();
+                format!("expected {}", expected.join(" or "))
+            }
+            InvalidInputSequence(..)
+            | UnsupportedOperator(..)
+            | UnknownNumberLiteral(..)
+            | InvalidFloatLiteral { .. }
+            | InvalidIntLiteral { .. } => String::new(),
+        };
+        Diagnostic::error(error.to_string()).with_label(error.span(), label)
+    }
+}
+
+impl From<&parser::Error> for Diagnostic {
+    fn from(error: &parser::Error) -> Self {
+        use parser::Error::*;
+        let label = match error {
+            UnexpectedSymbol(..) => "unexpected here".to_string(),
+            UnexpectedEof(expected, _) => format!("expected `{}`", expected),
+            ExpectedFound { expected, .. } => format!("expected {}", expected),
+            MissingRequiredElement(element, _) => format!("expected {}", element),
+            MissingClosingSymbol { expected, .. } => format!("expected `{}`", expected),
+            InvalidStatement { .. } | Contextual(..) => String::new(),
+        };
+        Diagnostic::error(error.to_string()).with_label(error.span(), label)
+    }
+}
+
+impl From<&compiler::Error> for Diagnostic {
+    fn from(error: &compiler::Error) -> Self {
+        use compiler::ErrorKind::*;
+        let diagnostic = Diagnostic::error(error.to_string()).with_label(error.span, "");
+        match &error.kind {
+            UndefinedVariable(name) if name == "require" => {
+                diagnostic.with_help("load a module with `import \"name\"`")
+            }
+            UndefinedVariable(_) => diagnostic.with_help("define it with `var` before using it"),
+            ImportNotAllowed => diagnostic
+                .with_note("importing a module runs its file, which may have side effects"),
+            GlobalNotAllowed => diagnostic
+                .with_note("globals are shared by all the code run, which may change them"),
+            NoLoopToBreak | NoLoopToContinue | MisplacedSpread => diagnostic,
+        }
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        let diagnostic = Diagnostic::warning(warning.to_string()).with_label(warning.span, "");
+        match &warning.kind {
+            WarningKind::UnreachableCode => diagnostic,
+            WarningKind::UnknownAttribute(_) => {
+                diagnostic.with_help("it has no effect on the program")
+            }
+            WarningKind::UnusedVariable(name) | WarningKind::UnusedParameter(name) => {
+                diagnostic.with_help(format!("if this is intended, name it `_{}`", name))
+            }
+            WarningKind::Shadowing(name) => diagnostic.with_help(format!(
+                "rename it, or name it `_{}` if this is intended",
+                name
+            )),
+        }
+    }
+}
+
+/// Labels the failing instruction, and the calls the error unwound through that are in the
+/// source. Those in other files, e.g. modules loaded by `import`, are located in notes.
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        let mut diagnostic = Diagnostic::error(error.message.clone());
+        let mut callee = None::<&str>;
+        for frame in &error.traceback {
+            match (frame.span, &frame.file) {
+                (Some(span), None) => {
+                    // Recursion passes through the same call many times, which is labeled once.
+                    if diagnostic.labels.iter().all(|label| label.span != span) {
+                        let message = match callee {
+                            Some(callee) => format!("called `{}` here", callee),
+                            None => String::new(),
+                        };
+                        diagnostic = diagnostic.with_label(span, message);
+                    }
+                }
+                (Some(span), Some(file)) => {
+                    let (line, column) = file.line_column(span.start());
+                    let location = format!("{}:{}:{}", file.path, line, column);
+                    let note = match callee {
+                        Some(callee) => format!("called `{}` at {}", callee, location),
+                        None => format!("raised at {}", location),
+                    };
+                    if !diagnostic.notes.contains(&note) {
+                        diagnostic = diagnostic.with_note(note);
+                    }
+                }
+                (None, _) => {}
+            }
+            callee = Some(&frame.function);
+        }
+        diagnostic
+    }
+}
+
+struct DisplayIn<'a> {
+    diagnostic: &'a Diagnostic,
+    file: &'a str,
+    source: &'a str,
+}
+
+impl fmt::Display for DisplayIn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Diagnostic {
+            severity,
+            message,
+            labels,
+            notes,
+            help,
+        } = self.diagnostic;
+        let severity = match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        writeln!(f, "{}: {}", severity, message)?;
+
+        let mut lines = labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                (
+                    SourceLine::at(self.source, label.span.start()),
+                    i == 0,
+                    label,
+                )
+            })
+            .collect::<Vec<_>>();
+        let width = (lines.iter())
+            .map(|(line, ..)| line.number.to_string().len())
+            .max()
+            .unwrap_or(1);
+        if let Some((line, ..)) = lines.first() {
+            writeln!(
+                f,
+                "{:width$}--> {}:{}:{}",
+                "", self.file, line.number, line.column
+            )?;
+            writeln!(f, "{:width$} |", "")?;
+        }
+        lines.sort_by_key(|(line, ..)| (line.number, line.column));
+
+        let mut previous = None;
+        for (line, primary, label) in &lines {
+            if previous != Some(line.number) {
+                if previous.is_some_and(|previous| previous + 1 < line.number) {
+                    writeln!(f, "...")?;
+                }
+                let row = format!(
+                    "{:>width$} | {}",
+                    line.number,
+                    line.text.replace('\t', "    ")
+                );
+                writeln!(f, "{}", row.trim_end())?;
+                previous = Some(line.number);
+            }
+            // The part of the span on this line, or at least one character at its start.
+            let end = (label.span.end() as usize).clamp(line.offset, line.end);
+            let underlined = self.source.get(line.offset..end).unwrap_or("");
+            let mark = if *primary { "^" } else { "-" };
+            let underline = mark.repeat(display_width(underlined).max(1));
+            let indent = display_width(&line.text[..line.offset - line.start]);
+            let row = format!("{:indent$}{} {}", "", underline, label.message);
+            writeln!(f, "{:width$} | {}", "", row.trim_end())?;
+        }
+
+        if !lines.is_empty() && (!notes.is_empty() || help.is_some()) {
+            writeln!(f, "{:width$} |", "")?;
+        }
+        for note in notes {
+            writeln!(f, "{:width$} = note: {}", "", note)?;
+        }
+        if let Some(help) = help {
+            writeln!(f, "{:width$} = help: {}", "", help)?;
+        }
+        Ok(())
+    }
+}
+
+/// The line of the source a span starts on.
+struct SourceLine<'a> {
+    /// The line and column of the span, counting from 1.
+    number: usize,
+    column: usize,
+    text: &'a str,
+    /// The byte offsets of the line and of the span in the source, and of the end of the line.
+    start: usize,
+    offset: usize,
+    end: usize,
+}
+
+impl<'a> SourceLine<'a> {
+    fn at(source: &'a str, offset: u32) -> Self {
+        // Spans at the end of input point just past the last character.
+        let mut offset = (offset as usize).min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        let text = source[start..end].trim_end_matches('\r');
+        Self {
+            number: source[..offset].matches('\n').count() + 1,
+            column: source[start..offset].chars().count() + 1,
+            text,
+            start,
+            offset,
+            end: start + text.len(),
+        }
+    }
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
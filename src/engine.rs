@@ -0,0 +1,222 @@
+use super::*;
+use compiler::{Compiled, Warning};
+use foundation::TextSpan;
+use vm::{
+    bytecode::{LoadError, Program},
+    code::Code,
+    runtime::{CompiledModule, InterruptHandle, Object, Runtime},
+    RuntimeError,
+};
+
+/// Compiles and runs Lico source code on a [`Runtime`] that persists across evaluations.
+#[derive(Debug)]
+pub struct Engine {
+    pub runtime: Runtime,
+}
+
+/// The result of a script that ran to completion or called `exit`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionOutcome {
+    /// The value returned by the script, or nil if it exited.
+    pub value: Object,
+    /// The code passed to `exit`, or 0 if the script didn't call it.
+    pub exit_code: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Lex(Vec<lexer::Error>),
+    Parse(Vec<parser::Error>),
+    Compile(compiler::Error),
+    /// The bytecode given to [`Engine::run_bytecode`] can't be run by this build.
+    Load(LoadError),
+    Runtime(RuntimeError),
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    /// The number of instructions [`Engine::eval_expr`] and [`Engine::eval_config`] may run.
+    pub const BOUNDED_FUEL: u64 = 10_000_000;
+
+    pub fn new() -> Self {
+        let mut runtime = Runtime::new();
+        runtime.modules.set_compiler(compile_module);
+        Self { runtime }
+    }
+
+    pub fn eval(&mut self, source: &str) -> Result<ExecutionOutcome, Error> {
+        let compiled = compile_source(source)?;
+        self.run(&compiled.code, &compiled.spans)
+    }
+
+    /// Compiles `source` to bytecode, which [`Engine::run_bytecode`] runs later without parsing
+    /// it again, e.g. from a cache on disk. The source is kept in the bytecode, to show where
+    /// runtime errors happened.
+    pub fn compile(source: &str) -> Result<Vec<u8>, Error> {
+        let compiled = compile_source(source)?;
+        let program = Program {
+            code: compiled.code,
+            spans: compiled.spans,
+            source: source.to_string(),
+        };
+        Ok(program.serialize())
+    }
+
+    /// Compiles `source` without running it, and returns the warnings about it, e.g. code that
+    /// can never run.
+    pub fn check(source: &str) -> Result<Vec<Warning>, Error> {
+        compile_source(source).map(|compiled| compiled.warnings)
+    }
+
+    /// Runs bytecode made by [`Engine::compile`] with the same version of Lico.
+    pub fn run_bytecode(&mut self, bytecode: &[u8]) -> Result<ExecutionOutcome, Error> {
+        let program = Program::deserialize(bytecode).map_err(Error::Load)?;
+        self.run_program(&program)
+    }
+
+    /// Runs a program loaded from bytecode, whose runtime errors are located in its
+    /// [`Program::source`].
+    pub fn run_program(&mut self, program: &Program) -> Result<ExecutionOutcome, Error> {
+        self.run(&program.code, &program.spans)
+    }
+
+    /// Evaluates a single expression, e.g. a formula entered by a user or a value in a config
+    /// file. Only builtins without side effects are available, so it can't do I/O or exit, and it
+    /// runs on at most [`Engine::BOUNDED_FUEL`] instructions, so it can't hang.
+    pub fn eval_expr(&mut self, source: &str) -> Result<Object, Error> {
+        let (tokens, errors) = lexer::parse(source);
+        if !errors.is_empty() {
+            return Err(Error::Lex(errors));
+        }
+        let (program, errors) = parser::parse_expression(&tokens);
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+        let options = compiler::Options {
+            pure: true,
+            ..Default::default()
+        };
+        let compiled = compiler::compile_full(&program, options).map_err(Error::Compile)?;
+        self.run_bounded(&compiled.code, &compiled.spans)
+            .map(|outcome| outcome.value)
+    }
+
+    /// Evaluates a config file: a script that computes a table, which is returned. Only builtins
+    /// without side effects are available and it runs on at most [`Engine::BOUNDED_FUEL`]
+    /// instructions, so that loading the config can't do I/O or hang.
+    pub fn eval_config(&mut self, source: &str) -> Result<Object, Error> {
+        let (tokens, errors) = lexer::parse(source);
+        if !errors.is_empty() {
+            return Err(Error::Lex(errors));
+        }
+        let (program, errors) = parser::parse(&tokens);
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+        let options = compiler::Options {
+            pure: true,
+            ..Default::default()
+        };
+        let compiled = compiler::compile_full(&program, options).map_err(Error::Compile)?;
+        match self.run_bounded(&compiled.code, &compiled.spans)?.value {
+            value @ Object::Table(_) => Ok(value),
+            value => Err(Error::Runtime(RuntimeError::type_error(format!(
+                "Config must evaluate to a table, got {}",
+                value.typename()
+            )))),
+        }
+    }
+
+    /// Runs `code` on at most [`Engine::BOUNDED_FUEL`] instructions, or on the fuel left in the
+    /// runtime if it is less, which the run then uses up as usual.
+    fn run_bounded(
+        &mut self,
+        code: &[Code],
+        spans: &[Option<TextSpan>],
+    ) -> Result<ExecutionOutcome, Error> {
+        let fuel = self.runtime.fuel();
+        let budget = fuel.map_or(Self::BOUNDED_FUEL, |fuel| fuel.min(Self::BOUNDED_FUEL));
+        self.runtime.set_fuel(Some(budget));
+        let result = self.run(code, spans);
+        let used = budget - self.runtime.fuel().unwrap_or(0);
+        self.runtime.set_fuel(fuel.map(|fuel| fuel - used));
+        result
+    }
+
+    fn run(
+        &mut self,
+        code: &[Code],
+        spans: &[Option<TextSpan>],
+    ) -> Result<ExecutionOutcome, Error> {
+        // Each evaluation gets its own scope, so that locals left by an earlier one (e.g. after an
+        // error) don't shift the `LocalId`s of this one.
+        let stack_len = self.runtime.stack.len();
+        self.runtime.variable_table.push_scope();
+        let result = vm::execute_with_spans(code, spans, &mut self.runtime);
+        self.runtime.variable_table.pop_scope();
+        self.runtime.stack.truncate(stack_len);
+        self.runtime.shrink();
+
+        match (result, self.runtime.exit_code.take()) {
+            (_, Some(exit_code)) => Ok(ExecutionOutcome {
+                value: Object::Nil,
+                exit_code,
+            }),
+            (Ok(value), None) => Ok(ExecutionOutcome {
+                value,
+                exit_code: 0,
+            }),
+            (Err(err), None) => Err(Error::Runtime(err)),
+        }
+    }
+
+    /// Returns a handle that interrupts the running script from another thread, e.g. on Ctrl-C.
+    /// The script stops with an "Interrupted" error, or runs its `on_interrupt` handler.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.runtime.interrupt.clone()
+    }
+
+    /// Evaluates `source` with `args` available to the script as `args` and `os.args`.
+    pub fn eval_with_args(
+        &mut self,
+        source: &str,
+        args: &[&str],
+    ) -> Result<ExecutionOutcome, Error> {
+        self.runtime.args = args.iter().map(|arg| arg.to_string()).collect();
+        self.eval(source)
+    }
+}
+
+/// Compiles a file loaded by `import`, whose errors and warnings are rendered as they would be for
+/// a script.
+fn compile_module(path: &str, source: &str) -> Result<CompiledModule, String> {
+    let compiled =
+        compile_source(source).map_err(|err| render(&err.diagnostics(), path, source))?;
+    let warnings = compiled
+        .warnings
+        .iter()
+        .map(Diagnostic::from)
+        .collect::<Vec<_>>();
+    Ok(CompiledModule {
+        code: compiled.code,
+        spans: compiled.spans,
+        warnings: render(&warnings, path, source),
+    })
+}
+
+fn compile_source(source: &str) -> Result<Compiled, Error> {
+    let (tokens, errors) = lexer::parse(source);
+    if !errors.is_empty() {
+        return Err(Error::Lex(errors));
+    }
+    let (program, errors) = parser::parse(&tokens);
+    if !errors.is_empty() {
+        return Err(Error::Parse(errors));
+    }
+    compiler::compile_full(&program, compiler::Options::default()).map_err(Error::Compile)
+}
@@ -15,6 +15,41 @@ macro_rules! impl_display {
 }
 impl_display!(Program<'_>, Chunk<'_>, Block<'_>, Expression<'_>);
 
+impl ::std::fmt::Display for Pattern<'_> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            Pattern::Literal(primitive) => match primitive {
+                Primitive::Int(x) => write!(f, "{}", x),
+                Primitive::Float(x) => write!(f, "{:.8}", x),
+                Primitive::String(x) => write!(f, r#""{}""#, x),
+                Primitive::Bool(x) => write!(f, "{}", x),
+                Primitive::Nil => write!(f, "nil"),
+            },
+            Pattern::Ident(name) => write!(f, "{}", name),
+            Pattern::Table(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|((key, _), (pattern, _))| match pattern {
+                        Pattern::Ident(name) if name == key => key.to_string(),
+                        pattern => format!("{}: {}", key, pattern),
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Pattern::Array { elements, rest } => {
+                let mut elements = elements
+                    .iter()
+                    .map(|(pattern, _)| pattern.to_string())
+                    .collect::<Vec<_>>();
+                if let Some((rest, _)) = rest {
+                    elements.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", elements.join(", "))
+            }
+        }
+    }
+}
+
 trait PrettyPrint {
     fn pretty_print(&self, builder: &mut PrettyPrintBuilder);
 }
@@ -190,29 +225,88 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, expr);
             }
 
+            // DestructuringVar (s) @1..2
+            //   pattern: [pattern] @1..2
+            //   expr
+            //     [expr]
+            Statement::DestructuringVar { pattern, expr } => {
+                builder.append(0, format!("DestructuringVar (s) @{}", span));
+                builder.append(2, format!("pattern: {} @{}", pattern.0, pattern.1));
+                builder.append(2, "expr");
+                builder.nest(4, expr);
+            }
+
+            // ParallelVar (s) @1..2
+            //   names
+            //     [name] @1..2
+            //   exprs
+            //     [expr]
+            Statement::ParallelVar { names, exprs } => {
+                builder.append(0, format!("ParallelVar (s) @{}", span));
+                builder.append(2, "names");
+                for (name, name_span) in names {
+                    builder.append(4, format!("{} @{}", name, name_span));
+                }
+                builder.append(2, "exprs");
+                for expr in exprs {
+                    builder.nest(4, expr);
+                }
+            }
+
+            // Global (s) @1..2
+            //   name: [name] @1..2
+            //   expr
+            //     [expr]
+            Statement::Global { name, expr } => {
+                builder.append(0, format!("Global (s) @{}", span));
+                builder.append(2, format!("name: {} @{}", name.0, name.1));
+                if let Some(expr) = expr {
+                    builder.append(2, "expr");
+                    builder.nest(4, expr);
+                }
+            }
+
             // Func (s) @1..2
             //   name: [name] @1..2
             //   args
             //     [annotation] name @1..2
+            //       [default]
             //   body
-            Statement::Func { name, args, body } => {
+            //   doc
+            //     [line] @1..2
+            Statement::Func {
+                name,
+                args,
+                body,
+                doc,
+            } => {
                 builder.append(0, format!("Func (s) @{}", span));
                 builder.append(2, format!("name: {} @{}", name.0, name.1));
                 if args.is_empty() {
                     builder.append(2, "args: None");
                 } else {
                     builder.append(2, "args");
-                    for (annotation, name, span) in args {
+                    for (annotation, name, span, default) in args {
                         let annotation = match annotation {
                             FunctArgAnnotation::None => "",
                             FunctArgAnnotation::Ref => "[ref] ",
                             FunctArgAnnotation::In => "[in] ",
+                            FunctArgAnnotation::Rest => "[rest] ",
                         };
-                        builder.append(4, &format!("{}{} @{}", annotation, name, span));
+                        builder.append(4, format!("{}{} @{}", annotation, name, span));
+                        if let Some(default) = default {
+                            builder.nest(6, default);
+                        }
                     }
                 }
                 builder.append(2, "body");
                 builder.nest(4, body);
+                if !doc.is_empty() {
+                    builder.append(2, "doc");
+                    for (line, span) in doc {
+                        builder.append(4, format!("{:?} @{}", line, span));
+                    }
+                }
             }
 
             // FieldFunc (s) @1..2
@@ -221,12 +315,16 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
             //     name @1..2
             //   args
             //     [annotation] name @1
+            //       [default]
             //   body
+            //   doc
+            //     [line] @1..2
             Statement::FieldFunc {
                 table,
                 fields,
                 args,
                 body,
+                doc,
             } => {
                 builder.append(0, format!("FieldFunc (s) @{}", span));
                 builder.append(2, format!("table: {} @{}", table.0, table.1));
@@ -242,17 +340,79 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                     builder.append(2, "args: None");
                 } else {
                     builder.append(2, "args");
-                    for (annotation, name, span) in args {
+                    for (annotation, name, span, default) in args {
                         let annotation = match annotation {
                             FunctArgAnnotation::None => "",
                             FunctArgAnnotation::Ref => "[ref]",
                             FunctArgAnnotation::In => "[in]",
+                            FunctArgAnnotation::Rest => "[rest]",
                         };
                         builder.append(4, format!("{} {} @{}", annotation, name, span));
+                        if let Some(default) = default {
+                            builder.nest(6, default);
+                        }
                     }
                 }
                 builder.append(2, "body");
                 builder.nest(4, body);
+                if !doc.is_empty() {
+                    builder.append(2, "doc");
+                    for (line, span) in doc {
+                        builder.append(4, format!("{:?} @{}", line, span));
+                    }
+                }
+            }
+
+            // Class (s) @1..2
+            //   name: [name] @1..2
+            //   parent
+            //     [expr]
+            //   methods
+            //     [name] @1..2
+            //       args
+            //         [annotation] name @1..2
+            //           [default]
+            //       body
+            Statement::Class {
+                name,
+                parent,
+                methods,
+            } => {
+                builder.append(0, format!("Class (s) @{}", span));
+                builder.append(2, format!("name: {} @{}", name.0, name.1));
+                if let Some(parent) = parent {
+                    builder.append(2, "parent");
+                    builder.nest(4, parent);
+                } else {
+                    builder.append(2, "parent: None");
+                }
+                if methods.is_empty() {
+                    builder.append(2, "methods: None");
+                } else {
+                    builder.append(2, "methods");
+                }
+                for ((name, name_span), method) in methods {
+                    builder.append(4, format!("{} @{}", name, name_span));
+                    if method.args.is_empty() {
+                        builder.append(6, "args: None");
+                    } else {
+                        builder.append(6, "args");
+                        for (annotation, name, span, default) in &method.args {
+                            let annotation = match annotation {
+                                FunctArgAnnotation::None => "",
+                                FunctArgAnnotation::Ref => "[ref] ",
+                                FunctArgAnnotation::In => "[in] ",
+                                FunctArgAnnotation::Rest => "[rest] ",
+                            };
+                            builder.append(8, format!("{}{} @{}", annotation, name, span));
+                            if let Some(default) = default {
+                                builder.nest(10, default);
+                            }
+                        }
+                    }
+                    builder.append(6, "body");
+                    builder.nest(8, &method.body);
+                }
             }
 
             // Assign (s) @1..2
@@ -266,6 +426,23 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, expr);
             }
 
+            // ParallelAssign (s) @1..2
+            //   names
+            //     [name] @1..2
+            //   exprs
+            //     [expr]
+            Statement::ParallelAssign { names, exprs } => {
+                builder.append(0, format!("ParallelAssign (s) @{}", span));
+                builder.append(2, "names");
+                for (name, name_span) in names {
+                    builder.append(4, format!("{} @{}", name, name_span));
+                }
+                builder.append(2, "exprs");
+                for expr in exprs {
+                    builder.nest(4, expr);
+                }
+            }
+
             // FieldAssign (s) @1..2
             //   table
             //     [expr]
@@ -283,6 +460,48 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, expr);
             }
 
+            // ConditionalAssign (s) @1..2
+            //   op: [op]
+            //   target
+            //     [expr]
+            //   expr
+            //     [expr]
+            Statement::ConditionalAssign { op, target, expr } => {
+                let op = match op {
+                    ConditionalAssignOp::NilCoalesce => "??=",
+                    ConditionalAssignOp::Or => "||=",
+                };
+                builder.append(0, format!("ConditionalAssign (s) @{}", span));
+                builder.append(2, format!("op: {}", op));
+                builder.append(2, "target");
+                builder.nest(4, target);
+                builder.append(2, "expr");
+                builder.nest(4, expr);
+            }
+
+            // CompoundAssign (s) @1..2
+            //   op: [op]
+            //   target
+            //     [expr]
+            //   expr
+            //     [expr]
+            Statement::CompoundAssign { op, target, expr } => {
+                let op = match op {
+                    CompoundAssignOp::Add => "+=",
+                    CompoundAssignOp::Sub => "-=",
+                    CompoundAssignOp::Mul => "*=",
+                    CompoundAssignOp::Div => "/=",
+                    CompoundAssignOp::Mod => "%=",
+                    CompoundAssignOp::Concat => "..=",
+                };
+                builder.append(0, format!("CompoundAssign (s) @{}", span));
+                builder.append(2, format!("op: {}", op));
+                builder.append(2, "target");
+                builder.nest(4, target);
+                builder.append(2, "expr");
+                builder.nest(4, expr);
+            }
+
             // If (s) @1..2
             //   cond
             //     [expr]
@@ -336,6 +555,37 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, body);
             }
 
+            // NumericFor (s) @1..2
+            //   value: [name] @1..2
+            //   start
+            //     [expr]
+            //   stop
+            //     [expr]
+            //   step
+            //     [expr]
+            //   body
+            //     [block]
+            Statement::NumericFor {
+                value,
+                start,
+                stop,
+                step,
+                body,
+            } => {
+                builder.append(0, format!("NumericFor (s) @{}", span));
+                builder.append(2, format!("value: {} @{}", value.0, value.1));
+                builder.append(2, "start");
+                builder.nest(4, start);
+                builder.append(2, "stop");
+                builder.nest(4, stop);
+                if let Some(step) = step {
+                    builder.append(2, "step");
+                    builder.nest(4, step);
+                }
+                builder.append(2, "body");
+                builder.nest(4, body);
+            }
+
             // While (s) @1..2
             //   cond
             //     [expr]
@@ -349,6 +599,29 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, body);
             }
 
+            // Match (s) @1..2
+            //   expr
+            //     [expr]
+            //   case [pattern] @1..2
+            //     guard
+            //       [expr]
+            //     body
+            //       [block]
+            Statement::Match { expr, arms } => {
+                builder.append(0, format!("Match (s) @{}", span));
+                builder.append(2, "expr");
+                builder.nest(4, expr);
+                for ((pattern, pattern_span), guard, body) in arms {
+                    builder.append(2, format!("case {} @{}", pattern, pattern_span));
+                    if let Some(guard) = guard {
+                        builder.append(4, "guard");
+                        builder.nest(6, guard);
+                    }
+                    builder.append(4, "body");
+                    builder.nest(6, body);
+                }
+            }
+
             // Do (s) @1..2
             //   body
             //     [block]
@@ -358,6 +631,59 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 builder.nest(4, body);
             }
 
+            // With (s) @1..2
+            //   expr
+            //     [expr]
+            //   name: [name] @1..2
+            //   body
+            //     [block]
+            Statement::With {
+                expr,
+                name: (name, name_span),
+                body,
+            } => {
+                builder.append(0, format!("With (s) @{}", span));
+                builder.append(2, "expr");
+                builder.nest(4, expr);
+                builder.append(2, format!("name: {} @{}", name, name_span));
+                builder.append(2, "body");
+                builder.nest(4, body);
+            }
+
+            // Try (s) @1..2
+            //   body
+            //     [block]
+            //   name: [name] @1..2
+            //   handler
+            //     [block]
+            Statement::Try {
+                body,
+                name: (name, name_span),
+                handler,
+            } => {
+                builder.append(0, format!("Try (s) @{}", span));
+                builder.append(2, "body");
+                builder.nest(4, body);
+                builder.append(2, format!("name: {} @{}", name, name_span));
+                builder.append(2, "handler");
+                builder.nest(4, handler);
+            }
+
+            // Assert (s) @1..2
+            //   cond
+            //     [expr]
+            //   message
+            //     [expr]
+            Statement::Assert { cond, message } => {
+                builder.append(0, format!("Assert (s) @{}", span));
+                builder.append(2, "cond");
+                builder.nest(4, cond);
+                if let Some(message) = message {
+                    builder.append(2, "message");
+                    builder.nest(4, message);
+                }
+            }
+
             // Return (s) @1..2
             //   value
             //     [expr]
@@ -369,6 +695,35 @@ impl PrettyPrint for (Statement<'_>, TextSpan) {
                 }
             }
 
+            // ReturnMany (s) @1..2
+            //   values
+            //     [expr]
+            Statement::ReturnMany { values } => {
+                builder.append(0, format!("ReturnMany (s) @{}", span));
+                builder.append(2, "values");
+                for value in values {
+                    builder.nest(4, value);
+                }
+            }
+
+            // Yield (s) @1..2
+            //   value
+            //     [expr]
+            Statement::Yield { value } => {
+                builder.append(0, format!("Yield (s) @{}", span));
+                if let Some(value) = value {
+                    builder.append(2, "value");
+                    builder.nest(4, value);
+                }
+            }
+
+            // Import (s) @1..2
+            //   path: [string] @1
+            Statement::Import { path } => {
+                builder.append(0, format!("Import (s) @{}", span));
+                builder.append(2, format!("path: {:?} @{}", path.0, path.1));
+            }
+
             // Continue (s) @1..2
             Statement::Continue => {
                 builder.append(0, format!("Continue (s) @{}", span));
@@ -459,6 +814,12 @@ fn expression_name(expr: &Expression<'_>) -> &'static str {
         Expression::MethodCall { .. } => "MethodCall (e)",
         Expression::IndexAccess { .. } => "IndexAccess (e)",
         Expression::DotAccess { .. } => "DotAccess (e)",
+        Expression::SliceAccess { .. } => "SliceAccess (e)",
+        Expression::Optional { .. } => "Optional (e)",
+        Expression::Spread { .. } => "Spread (e)",
+        Expression::If { .. } => "If (e)",
+        Expression::Yield { .. } => "Yield (e)",
+        Expression::Import { .. } => "Import (e)",
         Expression::Error => "Error (e)",
     }
 }
@@ -492,14 +853,18 @@ fn expression_pretty_print_inner(builder: &mut PrettyPrintBuilder, expr: &Expres
                 BinaryOp::Mul => "*",
                 BinaryOp::Div => "/",
                 BinaryOp::Mod => "%",
+                BinaryOp::Pow => "**",
+                BinaryOp::FloorDiv => "//",
                 BinaryOp::And => "and",
                 BinaryOp::Or => "or",
+                BinaryOp::NilCoalesce => "??",
                 BinaryOp::Eq => "==",
                 BinaryOp::NotEq => "!=",
                 BinaryOp::Less => "<",
                 BinaryOp::LessEq => "<=",
                 BinaryOp::Greater => ">",
                 BinaryOp::GreaterEq => ">=",
+                BinaryOp::In => "in",
                 BinaryOp::BitAnd => "&",
                 BinaryOp::BitOr => "|",
                 BinaryOp::BitXor => "^",
@@ -507,6 +872,7 @@ fn expression_pretty_print_inner(builder: &mut PrettyPrintBuilder, expr: &Expres
                 BinaryOp::ShiftLeft => "<<",
                 BinaryOp::ShiftRight => ">>",
                 BinaryOp::Concat => "..",
+                BinaryOp::Pipeline => "|>",
             };
             builder.append(2, format!("op: {}", op));
             builder.append(2, "lhs");
@@ -564,6 +930,7 @@ fn expression_pretty_print_inner(builder: &mut PrettyPrintBuilder, expr: &Expres
         // FunctionObject (e) @1..2
         //   args
         //     [annotation] name @1
+        //       [default]
         //   body
         //     [block]
         Expression::FunctionObject(function) => {
@@ -571,13 +938,17 @@ fn expression_pretty_print_inner(builder: &mut PrettyPrintBuilder, expr: &Expres
                 builder.append(2, "args: None");
             } else {
                 builder.append(2, "args");
-                for (annotation, name, span) in &function.args {
+                for (annotation, name, span, default) in &function.args {
                     let annotation = match annotation {
                         FunctArgAnnotation::None => "",
                         FunctArgAnnotation::Ref => " [ref]",
                         FunctArgAnnotation::In => " [in]",
+                        FunctArgAnnotation::Rest => " [rest]",
                     };
                     builder.append(4, format!("{}{} @{}", name, annotation, span));
+                    if let Some(default) = default {
+                        builder.nest(6, default);
+                    }
                 }
             }
             builder.append(2, "body");
@@ -644,6 +1015,91 @@ fn expression_pretty_print_inner(builder: &mut PrettyPrintBuilder, expr: &Expres
             builder.append(2, format!("accessor: {} @{}", accessor.0, accessor.1));
         }
 
+        // SliceAccess (e) @1..2
+        //   expr
+        //     [expr]
+        //   start
+        //     [expr]
+        //   end
+        //     [expr]
+        Expression::SliceAccess { expr, start, end } => {
+            builder.append(2, "expr");
+            builder.nest(4, expr);
+            if let Some(start) = start {
+                builder.append(2, "start");
+                builder.nest(4, start);
+            }
+            if let Some(end) = end {
+                builder.append(2, "end");
+                builder.nest(4, end);
+            }
+        }
+
+        // Optional (e) @1..2
+        //   expr
+        //     [expr]
+        Expression::Optional { expr } => {
+            builder.append(2, "expr");
+            builder.nest(4, expr);
+        }
+
+        // Spread (e) @1..2
+        //   expr
+        //     [expr]
+        Expression::Spread { expr } => {
+            builder.append(2, "expr");
+            builder.nest(4, expr);
+        }
+
+        // If (e) @1..2
+        //   cond
+        //     [expr]
+        //   then
+        //     [expr]
+        //   elif
+        //     cond
+        //       [expr]
+        //     then
+        //       [expr]
+        //   else
+        //     [expr]
+        Expression::If {
+            cond,
+            then,
+            elifs,
+            else_,
+        } => {
+            builder.append(2, "cond");
+            builder.nest(4, cond);
+            builder.append(2, "then");
+            builder.nest(4, then);
+            for (cond, then) in elifs {
+                builder.append(2, "elif");
+                builder.append(4, "cond");
+                builder.nest(6, cond);
+                builder.append(4, "then");
+                builder.nest(6, then);
+            }
+            builder.append(2, "else");
+            builder.nest(4, else_);
+        }
+
+        // Yield (e) @1..2
+        //   value
+        //     [expr]
+        Expression::Yield { value } => {
+            if let Some(value) = value {
+                builder.append(2, "value");
+                builder.nest(4, value);
+            }
+        }
+
+        // Import (e) @1..2
+        //   path: [string] @1
+        Expression::Import { path } => {
+            builder.append(2, format!("path: {:?} @{}", path.0, path.1));
+        }
+
         // Error (e) @1..2
         Expression::Error => {}
     }
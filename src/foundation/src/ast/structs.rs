@@ -39,32 +39,78 @@ pub struct Chunk<'src> {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::type_complexity)]
 pub enum Statement<'src> {
     // variable
     Var {
         name: (&'src str, TextSpan),
         expr: (Expression<'src>, TextSpan),
     },
+    ParallelVar {
+        names: Vec<(&'src str, TextSpan)>,
+        exprs: Vec<(Expression<'src>, TextSpan)>,
+    },
+    DestructuringVar {
+        pattern: (Pattern<'src>, TextSpan),
+        expr: (Expression<'src>, TextSpan),
+    },
+    Global {
+        name: (&'src str, TextSpan),
+        expr: Option<(Expression<'src>, TextSpan)>,
+    },
     Func {
         name: (&'src str, TextSpan),
-        args: Vec<(FunctArgAnnotation, &'src str, TextSpan)>,
+        args: Vec<(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )>,
         body: Chunk<'src>,
+        /// The lines of the `##` doc comment right before the definition, without the `##`.
+        doc: Vec<(&'src str, TextSpan)>,
     },
     FieldFunc {
         table: (&'src str, TextSpan),
         fields: Vec<(&'src str, TextSpan)>,
-        args: Vec<(FunctArgAnnotation, &'src str, TextSpan)>,
+        args: Vec<(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )>,
         body: Chunk<'src>,
+        /// Likewise for the definition of a field function.
+        doc: Vec<(&'src str, TextSpan)>,
+    },
+    Class {
+        name: (&'src str, TextSpan),
+        parent: Option<(Expression<'src>, TextSpan)>,
+        methods: Vec<((&'src str, TextSpan), FunctionObject<'src>)>,
     },
     Assign {
         name: (&'src str, TextSpan),
         expr: (Expression<'src>, TextSpan),
     },
+    ParallelAssign {
+        names: Vec<(&'src str, TextSpan)>,
+        exprs: Vec<(Expression<'src>, TextSpan)>,
+    },
     FieldAssign {
         table: (Expression<'src>, TextSpan),
         field: (Expression<'src>, TextSpan),
         expr: (Expression<'src>, TextSpan),
     },
+    ConditionalAssign {
+        op: ConditionalAssignOp,
+        target: (Expression<'src>, TextSpan), // Local or IndexAccess
+        expr: (Expression<'src>, TextSpan),
+    },
+    CompoundAssign {
+        op: CompoundAssignOp,
+        target: (Expression<'src>, TextSpan), // Local or IndexAccess
+        expr: (Expression<'src>, TextSpan),
+    },
 
     // control
     If {
@@ -78,6 +124,13 @@ pub enum Statement<'src> {
         iter: (Expression<'src>, TextSpan),
         body: Block<'src>,
     },
+    NumericFor {
+        value: (&'src str, TextSpan),
+        start: (Expression<'src>, TextSpan),
+        stop: (Expression<'src>, TextSpan),
+        step: Option<(Expression<'src>, TextSpan)>,
+        body: Block<'src>,
+    },
     While {
         cond: (Expression<'src>, TextSpan),
         body: Block<'src>,
@@ -85,9 +138,41 @@ pub enum Statement<'src> {
     Do {
         body: Block<'src>,
     },
+    Match {
+        expr: (Expression<'src>, TextSpan),
+        #[allow(clippy::type_complexity)]
+        arms: Vec<(
+            (Pattern<'src>, TextSpan),
+            Option<(Expression<'src>, TextSpan)>,
+            Block<'src>,
+        )>,
+    },
+    With {
+        expr: (Expression<'src>, TextSpan),
+        name: (&'src str, TextSpan),
+        body: Block<'src>,
+    },
+    Try {
+        body: Block<'src>,
+        name: (&'src str, TextSpan),
+        handler: Block<'src>,
+    },
+    Assert {
+        cond: (Expression<'src>, TextSpan),
+        message: Option<(Expression<'src>, TextSpan)>,
+    },
     Return {
         value: Option<(Expression<'src>, TextSpan)>,
     },
+    ReturnMany {
+        values: Vec<(Expression<'src>, TextSpan)>,
+    },
+    Yield {
+        value: Option<(Expression<'src>, TextSpan)>,
+    },
+    Import {
+        path: (EcoString, TextSpan),
+    },
     Continue,
     Break,
 
@@ -144,6 +229,35 @@ pub enum Expression<'src> {
         expr: (Box<Expression<'src>>, TextSpan),
         accessor: (&'src str, TextSpan),
     },
+    /// `expr[start:end]`, where either bound may be left out.
+    SliceAccess {
+        expr: (Box<Expression<'src>>, TextSpan),
+        start: Option<(Box<Expression<'src>>, TextSpan)>,
+        end: Option<(Box<Expression<'src>>, TextSpan)>,
+    },
+    /// The receiver of `?.` or `?->`: when it is nil, the whole chain of accesses and calls after
+    /// it is nil too.
+    Optional {
+        expr: (Box<Expression<'src>>, TextSpan),
+    },
+    /// `...expr` in an array literal or the arguments of a call, which are given the items of the
+    /// array in its place.
+    Spread {
+        expr: (Box<Expression<'src>>, TextSpan),
+    },
+    /// `if cond then a elif cond then b else c end`, whose value is that of the arm taken.
+    If {
+        cond: (Box<Expression<'src>>, TextSpan),
+        then: (Box<Expression<'src>>, TextSpan),
+        elifs: Vec<((Expression<'src>, TextSpan), (Expression<'src>, TextSpan))>,
+        else_: (Box<Expression<'src>>, TextSpan),
+    },
+    Yield {
+        value: Option<(Box<Expression<'src>>, TextSpan)>,
+    },
+    Import {
+        path: (EcoString, TextSpan),
+    },
     Error,
 }
 
@@ -157,11 +271,13 @@ pub enum UnaryOp {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum BinaryOp {
     // arithmetic
-    Add, // +
-    Sub, // -
-    Mul, // *
-    Div, // /
-    Mod, // %
+    Add,      // +
+    Sub,      // -
+    Mul,      // *
+    Div,      // /
+    Mod,      // %
+    Pow,      // **
+    FloorDiv, // //
 
     // comparison
     Eq,        // ==
@@ -170,10 +286,12 @@ pub enum BinaryOp {
     LessEq,    // <=
     Greater,   // >
     GreaterEq, // >=
+    In,        // in
 
     // logical
-    And, // and
-    Or,  // or
+    And,         // and
+    Or,          // or
+    NilCoalesce, // ??
 
     // bitwise
     BitAnd,     // &
@@ -184,7 +302,24 @@ pub enum BinaryOp {
     ShiftRight, // >>
 
     // other
-    Concat, // ..
+    Concat,   // ..
+    Pipeline, // |>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConditionalAssignOp {
+    NilCoalesce, // ??=
+    Or,          // ||=
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CompoundAssignOp {
+    Add,    // +=
+    Sub,    // -=
+    Mul,    // *=
+    Div,    // /=
+    Mod,    // %=
+    Concat, // ..=
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -215,13 +350,32 @@ unit_object!(
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct FunctionObject<'src> {
-    pub args: Vec<(FunctArgAnnotation, &'src str, TextSpan)>,
+    #[allow(clippy::type_complexity)]
+    pub args: Vec<(
+        FunctArgAnnotation,
+        Pattern<'src>,
+        TextSpan,
+        Option<(Expression<'src>, TextSpan)>,
+    )>,
     pub body: Chunk<'src>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern<'src> {
+    Literal(Primitive),
+    Ident(&'src str),
+    #[allow(clippy::type_complexity)]
+    Table(Vec<((&'src str, TextSpan), (Pattern<'src>, TextSpan))>), // {a, b: c}
+    Array {
+        elements: Vec<(Pattern<'src>, TextSpan)>,
+        rest: Option<(&'src str, TextSpan)>,
+    }, // [a, b, ...c]
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FunctArgAnnotation {
     None,
     Ref,
     In,
+    Rest, // ...name, the arguments after the others as an array
 }
@@ -25,29 +25,56 @@ pub enum Token<'src> {
     Return,
     Break,
     Continue,
+    Match,
+    Case,
+    With,
+    As,
+    Assert,
+    Class,
+    Try,
+    Catch,
+    Yield,
+    Import,
+    Global,
 
     // operators
-    Plus,      // +
-    Minus,     // -
-    Star,      // *
-    Slash,     // /
-    Mod,       // %
-    Amp,       // &
-    Pipe,      // |
-    Caret,     // ^
-    Tilde,     // ~
-    Eq,        // ==
-    NotEq,     // !=
-    Less,      // <
-    LessEq,    // <=
-    Less2,     // <<
-    Greater,   // >
-    GreaterEq, // >=
-    Greater2,  // >>
-    Dot,       // .
-    Arrow,     // ->
-    Dot2,      // ..
-    Assign,    // =
+    Plus,            // +
+    Minus,           // -
+    Star,            // *
+    Star2,           // **
+    Slash,           // /
+    Slash2,          // //
+    Mod,             // %
+    Amp,             // &
+    Pipe,            // |
+    Pipe2,           // ||
+    Caret,           // ^
+    Tilde,           // ~
+    Eq,              // ==
+    NotEq,           // !=
+    Less,            // <
+    LessEq,          // <=
+    Less2,           // <<
+    Greater,         // >
+    GreaterEq,       // >=
+    Greater2,        // >>
+    Dot,             // .
+    Arrow,           // ->
+    Pipeline,        // |>
+    Dot2,            // ..
+    Dot3,            // ...
+    Question2,       // ??
+    QuestionDot,     // ?.
+    QuestionArrow,   // ?->
+    Assign,          // =
+    Question2Assign, // ??=
+    Pipe2Assign,     // ||=
+    PlusAssign,      // +=
+    MinusAssign,     // -=
+    StarAssign,      // *=
+    SlashAssign,     // /=
+    ModAssign,       // %=
+    Dot2Assign,      // ..=
 
     // keyword operators
     And,
@@ -68,6 +95,7 @@ pub enum Token<'src> {
     Ident(&'src str),
     Attribute(&'src str),
     Comment(&'src str),
+    DocComment(&'src str),
     Error(&'src str),
 }
 
@@ -94,13 +122,27 @@ impl std::fmt::Display for Token<'_> {
             Token::Return => write!(f, "return"),
             Token::Break => write!(f, "break"),
             Token::Continue => write!(f, "continue"),
+            Token::Match => write!(f, "match"),
+            Token::Case => write!(f, "case"),
+            Token::With => write!(f, "with"),
+            Token::As => write!(f, "as"),
+            Token::Assert => write!(f, "assert"),
+            Token::Class => write!(f, "class"),
+            Token::Try => write!(f, "try"),
+            Token::Catch => write!(f, "catch"),
+            Token::Yield => write!(f, "yield"),
+            Token::Import => write!(f, "import"),
+            Token::Global => write!(f, "global"),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
             Token::Star => write!(f, "*"),
+            Token::Star2 => write!(f, "**"),
             Token::Slash => write!(f, "/"),
+            Token::Slash2 => write!(f, "//"),
             Token::Mod => write!(f, "%"),
             Token::Amp => write!(f, "&"),
             Token::Pipe => write!(f, "|"),
+            Token::Pipe2 => write!(f, "||"),
             Token::Caret => write!(f, "^"),
             Token::Tilde => write!(f, "~"),
             Token::Eq => write!(f, "=="),
@@ -113,8 +155,21 @@ impl std::fmt::Display for Token<'_> {
             Token::Greater2 => write!(f, ">>"),
             Token::Dot => write!(f, "."),
             Token::Arrow => write!(f, "->"),
+            Token::Pipeline => write!(f, "|>"),
             Token::Dot2 => write!(f, ".."),
+            Token::Dot3 => write!(f, "..."),
+            Token::Question2 => write!(f, "??"),
+            Token::QuestionDot => write!(f, "?."),
+            Token::QuestionArrow => write!(f, "?->"),
             Token::Assign => write!(f, "="),
+            Token::Question2Assign => write!(f, "??="),
+            Token::Pipe2Assign => write!(f, "||="),
+            Token::PlusAssign => write!(f, "+="),
+            Token::MinusAssign => write!(f, "-="),
+            Token::StarAssign => write!(f, "*="),
+            Token::SlashAssign => write!(f, "/="),
+            Token::ModAssign => write!(f, "%="),
+            Token::Dot2Assign => write!(f, "..="),
             Token::And => write!(f, "and"),
             Token::Or => write!(f, "or"),
             Token::Not => write!(f, "not"),
@@ -129,6 +184,7 @@ impl std::fmt::Display for Token<'_> {
             Token::Ident(x) => write!(f, "{}", x),
             Token::Attribute(x) => write!(f, "@{}", x),
             Token::Comment(x) => write!(f, "#{}", x),
+            Token::DocComment(x) => write!(f, "##{}", x),
             Token::Error(c) => write!(f, "Error('{}')", c),
         }
     }
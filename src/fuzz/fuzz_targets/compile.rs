@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let (tokens, errors) = lexer::parse(source);
+    if !errors.is_empty() {
+        return;
+    }
+    let (program, errors) = parser::parse(&tokens);
+    if errors.is_empty() {
+        let _ = compiler::compile(&program);
+    }
+});
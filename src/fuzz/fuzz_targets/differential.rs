@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use reference::differential::{self, Outcome};
+
+// The input only seeds the generator: arbitrary source would rarely get past the parser, and
+// could loop forever on the VM.
+fuzz_target!(|seed: u64| {
+    let source = reference::generate::program(seed);
+    if let Outcome::Diverged { vm, reference } = differential::run(&source) {
+        panic!(
+            "The VM and the reference interpreter diverge on\n{}\nvm: {:?}\nreference: {:?}",
+            source, vm, reference
+        );
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let (tokens, errors) = lexer::parse(source);
+    if errors.is_empty() {
+        let _ = parser::parse(&tokens);
+    }
+});
@@ -32,6 +32,9 @@ pub enum Error {
         expected: char,
     },
 
+    #[error("Unterminated block comment")]
+    UnterminatedBlockComment(TextSpan),
+
     #[error("Invalid escape sequence")]
     InvalidEscapeSequence {
         info: (String, TextSpan),
@@ -55,6 +58,7 @@ impl Error {
             InvalidFloatLiteral { info: (x, _), .. } => Some(x.clone()),
             InvalidIntLiteral { info: (x, _), .. } => Some(x.clone()),
             MissingClosingDelimiter { info: (x, _), .. } => x.map(|x| x.to_string()),
+            UnterminatedBlockComment(_) => None,
             InvalidEscapeSequence { info: (x, _), .. } => Some(x.clone()),
             UnexpectedCharInEscapeSequence { info: (x, _), .. } => Some(x.to_string()),
         }
@@ -69,6 +73,7 @@ impl Error {
             InvalidFloatLiteral { info: (_, x), .. } => *x,
             InvalidIntLiteral { info: (_, x), .. } => *x,
             MissingClosingDelimiter { info: (_, x), .. } => *x,
+            UnterminatedBlockComment(x) => *x,
             InvalidEscapeSequence { info: (_, x), .. } => *x,
             UnexpectedCharInEscapeSequence { info: (_, x), .. } => *x,
         }
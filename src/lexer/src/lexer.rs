@@ -42,6 +42,11 @@ impl<'src> Lexer<'src> {
         self.chars.peek().map(|(_, c)| *c)
     }
 
+    /// Looks `n` chars ahead without consuming them, so that `peek_nth(0)` is `peek()`.
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n).map(|(_, c)| c)
+    }
+
     pub fn consume_ws(&mut self) {
         assert!(
             self.start_pos.is_none(),
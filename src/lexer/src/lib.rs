@@ -9,7 +9,7 @@ use tokenize::tokenize;
 
 pub use error::Error;
 
-pub fn parse(source: &str) -> (Vec<(Token, TextSpan)>, Vec<Error>) {
+pub fn parse(source: &str) -> (Vec<(Token<'_>, TextSpan)>, Vec<Error>) {
     let mut lexer = Lexer::new(source);
     tokenize(&mut lexer);
     lexer.into_tokens_errors()
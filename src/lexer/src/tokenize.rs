@@ -10,6 +10,9 @@ pub fn tokenize(lexer: &mut Lexer) {
         match c {
             '0'..='9' => tokenize_number(lexer, c),
             _ if is_xid_start(c) || c == '_' => tokenize_identifier(lexer),
+            '"' | '\'' if lexer.peek() == Some(c) && lexer.peek_nth(1) == Some(c) => {
+                tokenize_raw_string(lexer, c)
+            }
             '"' => tokenize_string(lexer, '"'),
             '\'' => tokenize_string(lexer, '\''),
             '@' => tokenize_attribute(lexer),
@@ -22,6 +25,10 @@ pub fn tokenize(lexer: &mut Lexer) {
                     lexer.report(|span| Error::UnsupportedOperator("++", span));
                     lexer.bump(Token::Error("++"));
                 }
+                Some('=') => {
+                    lexer.next();
+                    lexer.bump(Token::PlusAssign);
+                }
                 _ => lexer.bump(Token::Plus),
             },
             '-' => match lexer.peek() {
@@ -34,25 +41,42 @@ pub fn tokenize(lexer: &mut Lexer) {
                     lexer.report(|span| Error::UnsupportedOperator("--", span));
                     lexer.bump(Token::Error("--"));
                 }
+                Some('=') => {
+                    lexer.next();
+                    lexer.bump(Token::MinusAssign);
+                }
                 _ => lexer.bump(Token::Minus),
             },
             '*' => match lexer.peek() {
                 Some('*') => {
                     lexer.next();
-                    lexer.report(|span| Error::UnsupportedOperator("**", span));
-                    lexer.bump(Token::Error("**"));
+                    lexer.bump(Token::Star2);
+                }
+                Some('=') => {
+                    lexer.next();
+                    lexer.bump(Token::StarAssign);
                 }
                 _ => lexer.bump(Token::Star),
             },
             '/' => match lexer.peek() {
+                Some('*') => tokenize_block_comment(lexer),
                 Some('/') => {
                     lexer.next();
-                    lexer.report(|span| Error::UnsupportedOperator("//", span));
-                    lexer.bump(Token::Error("//"));
+                    lexer.bump(Token::Slash2);
+                }
+                Some('=') => {
+                    lexer.next();
+                    lexer.bump(Token::SlashAssign);
                 }
                 _ => lexer.bump(Token::Slash),
             },
-            '%' => lexer.bump(Token::Mod),
+            '%' => match lexer.peek() {
+                Some('=') => {
+                    lexer.next();
+                    lexer.bump(Token::ModAssign);
+                }
+                _ => lexer.bump(Token::Mod),
+            },
             '&' => match lexer.peek() {
                 Some('&') => {
                     lexer.next();
@@ -64,13 +88,16 @@ pub fn tokenize(lexer: &mut Lexer) {
             '|' => match lexer.peek() {
                 Some('|') => {
                     lexer.next();
-                    lexer.report(|span| Error::UnsupportedOperator("||", span));
-                    lexer.bump(Token::Error("||"));
+                    if lexer.peek() == Some('=') {
+                        lexer.next();
+                        lexer.bump(Token::Pipe2Assign);
+                    } else {
+                        lexer.bump(Token::Pipe2);
+                    }
                 }
                 Some('>') => {
                     lexer.next();
-                    lexer.report(|span| Error::UnsupportedOperator("|>", span));
-                    lexer.bump(Token::Error("|>"));
+                    lexer.bump(Token::Pipeline);
                 }
                 _ => lexer.bump(Token::Pipe),
             },
@@ -127,10 +154,44 @@ pub fn tokenize(lexer: &mut Lexer) {
                 }
                 _ => lexer.bump(Token::Greater),
             },
+            '?' => match lexer.peek() {
+                Some('?') => {
+                    lexer.next();
+                    if lexer.peek() == Some('=') {
+                        lexer.next();
+                        lexer.bump(Token::Question2Assign);
+                    } else {
+                        lexer.bump(Token::Question2);
+                    }
+                }
+                Some('.') => {
+                    lexer.next();
+                    lexer.bump(Token::QuestionDot);
+                }
+                Some('-') if lexer.peek_nth(1) == Some('>') => {
+                    lexer.next();
+                    lexer.next();
+                    lexer.bump(Token::QuestionArrow);
+                }
+                _ => {
+                    lexer.report(|span| Error::UnsupportedOperator("?", span));
+                    lexer.bump(Token::Error("?"));
+                }
+            },
             '.' => match lexer.peek() {
                 Some('.') => {
                     lexer.next();
-                    lexer.bump(Token::Dot2);
+                    match lexer.peek() {
+                        Some('.') => {
+                            lexer.next();
+                            lexer.bump(Token::Dot3);
+                        }
+                        Some('=') => {
+                            lexer.next();
+                            lexer.bump(Token::Dot2Assign);
+                        }
+                        _ => lexer.bump(Token::Dot2),
+                    }
                 }
                 _ => lexer.bump(Token::Dot),
             },
@@ -170,8 +231,9 @@ fn tokenize_number(lexer: &mut Lexer, start: char) {
                 lexer.next();
                 16
             }
-            Some('0'..='9' | '.') => 10,
-            Some(c) if is_xid_start(c) || c == '_' => {
+            Some('0'..='9' | '.' | '_') => 10,
+            Some('e' | 'E') if has_exponent(lexer) => 10,
+            Some(c) if is_xid_start(c) => {
                 lexer.take_until(|c| c.is_whitespace());
                 let slice = lexer.get_slice();
                 lexer.report(|span| Error::UnknownNumberLiteral(slice.into(), span));
@@ -190,55 +252,81 @@ fn tokenize_number(lexer: &mut Lexer, start: char) {
     } else {
         10
     };
-    lexer.take_while(|c| c.is_digit(radix));
+    // Digits may be separated by `_`, e.g. `1_000_000`.
+    lexer.take_while(|c| c.is_digit(radix) || c == '_');
+    let mut is_float = false;
     if lexer.peek() == Some('.') {
         lexer.next();
-        if radix != 10 {
-            lexer.take_while(|c| c.is_digit(radix));
-            let slice = lexer.get_slice();
-            lexer.report(|span| Error::InvalidFloatLiteral {
-                info: (slice.into(), span),
-                reason: format!(
-                    "{} float literal is not supported",
-                    match radix {
-                        2 => "Binary",
-                        8 => "Octal",
-                        16 => "Hexadecimal",
-                        _ => unreachable!(),
-                    }
-                ),
-            });
-        } else {
-            lexer.take_while(|c| c.is_ascii_digit()); // U+0030 '0' ..= U+0039 '9'
-            let slice = lexer.get_slice();
-            match slice.parse() {
-                Ok(x) => lexer.bump(Token::Float(x)),
-                Err(err) => {
-                    lexer.report(|span| Error::InvalidFloatLiteral {
-                        info: (slice.into(), span),
-                        reason: format!("Invalid float literal: {}", err),
-                    });
+        lexer.take_while(|c| c.is_digit(radix) || c == '_');
+        is_float = true;
+    }
+    if radix == 10 && has_exponent(lexer) {
+        lexer.next(); // 'e' or 'E'
+        if let Some('+' | '-') = lexer.peek() {
+            lexer.next();
+        }
+        lexer.take_while(|c| c.is_ascii_digit() || c == '_');
+        is_float = true;
+    }
+    let slice = lexer.get_slice();
+    let digits = match radix {
+        10 => slice,
+        _ => &slice[2..], // skip '0b', '0o', '0x'
+    }
+    .replace('_', "");
+    if is_float && radix != 10 {
+        lexer.report(|span| Error::InvalidFloatLiteral {
+            info: (slice.into(), span),
+            reason: format!(
+                "{} float literal is not supported",
+                match radix {
+                    2 => "Binary",
+                    8 => "Octal",
+                    16 => "Hexadecimal",
+                    _ => unreachable!(),
                 }
+            ),
+        });
+        lexer.bump(Token::Error(slice));
+    } else if is_float {
+        match digits.parse() {
+            Ok(x) => lexer.bump(Token::Float(x)),
+            Err(err) => {
+                lexer.report(|span| Error::InvalidFloatLiteral {
+                    info: (slice.into(), span),
+                    reason: format!("Invalid float literal: {}", err),
+                });
+                lexer.bump(Token::Error(slice));
             }
         }
     } else {
-        let slice = if radix == 10 {
-            lexer.get_slice()
-        } else {
-            &lexer.get_slice()[2..] // skip '0b', '0o', '0x'
+        // Binary, octal and hexadecimal literals are bit patterns, so that `0xFFFFFFFFFFFFFFFF`
+        // is -1.
+        let int = match radix {
+            10 => digits.parse::<i64>(),
+            _ => u64::from_str_radix(&digits, radix).map(|x| x as i64),
         };
-        match i64::from_str_radix(slice, radix) {
+        match int {
             Ok(x) => lexer.bump(Token::Int(x)),
             Err(err) => {
                 lexer.report(|span| Error::InvalidIntLiteral {
                     info: (slice.into(), span),
                     source: err,
                 });
+                lexer.bump(Token::Error(slice));
             }
         }
     }
 }
 
+// Whether an exponent, e.g. `e3`, `E-3` or `e+3`, follows.
+fn has_exponent(lexer: &Lexer) -> bool {
+    matches!(
+        (lexer.peek_nth(0), lexer.peek_nth(1), lexer.peek_nth(2)),
+        (Some('e' | 'E'), Some('0'..='9'), _) | (Some('e' | 'E'), Some('+' | '-'), Some('0'..='9'))
+    )
+}
+
 fn tokenize_identifier(lexer: &mut Lexer<'_>) {
     lexer.take_while(is_xid_continue);
     let slice = lexer.get_slice();
@@ -258,6 +346,17 @@ fn tokenize_identifier(lexer: &mut Lexer<'_>) {
         "return" => Token::Return,
         "break" => Token::Break,
         "continue" => Token::Continue,
+        "match" => Token::Match,
+        "case" => Token::Case,
+        "with" => Token::With,
+        "as" => Token::As,
+        "assert" => Token::Assert,
+        "class" => Token::Class,
+        "try" => Token::Try,
+        "catch" => Token::Catch,
+        "yield" => Token::Yield,
+        "import" => Token::Import,
+        "global" => Token::Global,
         "true" => Token::Bool(true),
         "false" => Token::Bool(false),
         "nil" => Token::Nil,
@@ -332,7 +431,7 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                             }
                             Some(c) if c == start => {
                                 lexer.report(|span| Error::InvalidEscapeSequence {
-                                    info: (r"\x".to_string(), TextSpan::at(span.end() - 2, 2)),
+                                    info: (r"\x".to_string(), TextSpan::at(span.end() - 3, 2)),
                                     reason: r"`\x` escape sequence requires 2 hex digits".into(),
                                 });
                                 lexer.bump(Token::String((content + r"\x").into()));
@@ -362,7 +461,7 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                             }
                             Some(c) if c == start => {
                                 lexer.report(|span| Error::InvalidEscapeSequence {
-                                    info: (format!(r"\x{}", o), TextSpan::at(span.end() - 3, 3)),
+                                    info: (format!(r"\x{}", o), TextSpan::at(span.end() - 4, 3)),
                                     reason: r"`\x` escape sequence requires 2 hex digits".into(),
                                 });
                                 lexer.bump(Token::String(content.into()));
@@ -393,7 +492,7 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                             }
                             Some(c) if c == start => {
                                 lexer.report(|span| Error::InvalidEscapeSequence {
-                                    info: (r"\u{}".to_string(), TextSpan::at(span.end() - 2, 2)),
+                                    info: (r"\u".to_string(), TextSpan::at(span.end() - 3, 2)),
                                     reason: r"`\u` escape sequence format is `\u{...}`".into(),
                                 });
                                 lexer.bump(Token::String(content.into()));
@@ -415,25 +514,31 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                         loop {
                             match lexer.next() {
                                 Some('}') => {
-                                    let char = std::char::from_u32(
-                                        // `codepoint` should be valid hex
-                                        u32::from_str_radix(&codepoint, 16).unwrap(),
-                                    );
-                                    if let Some(char) = char {
-                                        content.push(char);
-                                    } else {
-                                        let esc_string = r"\u{".to_owned() + &codepoint + "}";
-                                        lexer.report(|span| {
-                                            let esc_len = esc_string.len() as u32;
-                                            let span = TextSpan::at(span.end() - esc_len, esc_len);
-                                            Error::InvalidEscapeSequence {
-                                                info: (esc_string, span),
-                                                reason: "invalid unicode codepoint".into(),
-                                            }
-                                        });
-                                        content.push_str(r"\u{");
-                                        content.push_str(&codepoint);
-                                        content.push('}');
+                                    // `codepoint` is hex, but may be empty or too large.
+                                    let char = match codepoint.len() {
+                                        1..=6 => u32::from_str_radix(&codepoint, 16)
+                                            .ok()
+                                            .and_then(std::char::from_u32)
+                                            .ok_or("invalid unicode codepoint"),
+                                        _ => Err(r"`\u{...}` requires 1 to 6 hex digits"),
+                                    };
+                                    match char {
+                                        Ok(char) => content.push(char),
+                                        Err(reason) => {
+                                            let esc_string = r"\u{".to_owned() + &codepoint + "}";
+                                            lexer.report(|span| {
+                                                let esc_len = esc_string.len() as u32;
+                                                let span =
+                                                    TextSpan::at(span.end() - esc_len, esc_len);
+                                                Error::InvalidEscapeSequence {
+                                                    info: (esc_string, span),
+                                                    reason: reason.into(),
+                                                }
+                                            });
+                                            content.push_str(r"\u{");
+                                            content.push_str(&codepoint);
+                                            content.push('}');
+                                        }
                                     }
                                     break;
                                 }
@@ -444,8 +549,9 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                                 Some(c) if c == start => {
                                     let esc_string = r"\u{".to_owned() + &codepoint;
                                     lexer.report(|span| {
+                                        // The span ends before the closing quote.
                                         let esc_len = esc_string.len() as u32;
-                                        let span = TextSpan::at(span.end() - esc_len, esc_len);
+                                        let span = TextSpan::at(span.end() - 1 - esc_len, esc_len);
                                         Error::InvalidEscapeSequence {
                                             info: (esc_string, span),
                                             reason: r"`\u` escape sequence format is `\u{...}`"
@@ -484,8 +590,10 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
                     Some(c) => {
                         lexer.report(|span| {
                             let c_len = c.len_utf8() as u32;
+                            // The span includes the backslash.
+                            let span = TextSpan::at(span.end() - c_len - 1, c_len + 1);
                             Error::InvalidEscapeSequence {
-                                info: (format!(r"\{}", c), TextSpan::at(span.end() - c_len, c_len)),
+                                info: (format!(r"\{}", c), span),
                                 reason: "unknown escape sequence".into(),
                             }
                         });
@@ -503,6 +611,34 @@ fn tokenize_string(lexer: &mut Lexer<'_>, start: char) {
     }
 }
 
+// A raw string is enclosed in three quotes, may span lines and has no escape sequences. A newline
+// right after the opening quotes isn't part of it, so that it can start on the next line.
+fn tokenize_raw_string(lexer: &mut Lexer<'_>, quote: char) {
+    lexer.next();
+    lexer.next();
+    let mut quotes = 0;
+    while quotes < 3 {
+        match lexer.next() {
+            Some(c) if c == quote => quotes += 1,
+            Some(_) => quotes = 0,
+            None => {
+                lexer.report(|span| Error::MissingClosingDelimiter {
+                    info: (None, TextSpan::at(span.end(), 0)),
+                    expected: quote,
+                });
+                break;
+            }
+        }
+    }
+    let slice = lexer.get_slice();
+    let content = &slice[3..slice.len() - quotes];
+    let content = content
+        .strip_prefix('\n')
+        .or_else(|| content.strip_prefix("\r\n"))
+        .unwrap_or(content);
+    lexer.bump(Token::String(content.into()));
+}
+
 fn tokenize_attribute(lexer: &mut Lexer<'_>) {
     // NOTE: is_ascii_alphanumeric() == a..=z | A..=Z | 0..=9
     lexer.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
@@ -510,18 +646,50 @@ fn tokenize_attribute(lexer: &mut Lexer<'_>) {
     lexer.bump(Token::Attribute(slice));
 }
 
-fn tokenize_comment(lexer: &mut Lexer<'_>) {
+fn tokenize_comment<'src>(lexer: &mut Lexer<'src>) {
+    // `##` starts a doc comment.
+    let (prefix_len, token): (_, fn(&'src str) -> Token<'src>) = match lexer.peek() {
+        Some('#') => (2, Token::DocComment),
+        _ => (1, Token::Comment),
+    };
     loop {
         let Some(c) = lexer.next() else {
-            let slice = &lexer.get_slice()[1..]; // skip '#'
-            lexer.bump(Token::Comment(slice));
+            let slice = &lexer.get_slice()[prefix_len..]; // skip '#' or "##"
+            lexer.bump(token(slice));
             return;
         };
         if ['\n', '\r'].contains(&c) {
             break;
         }
     }
-    let slice = &lexer.get_slice()[1..]; // skip '#'
+    let slice = &lexer.get_slice()[prefix_len..]; // skip '#' or "##"
     let line = &slice[..slice.len() - 1]; // skip last newline
-    lexer.bump(Token::Comment(line));
+    lexer.bump(token(line));
+}
+
+// `/* ... */`, which may be nested.
+fn tokenize_block_comment(lexer: &mut Lexer<'_>) {
+    lexer.next(); // '*'
+    let mut depth = 1;
+    while depth > 0 {
+        match lexer.next() {
+            Some('/') if lexer.peek() == Some('*') => {
+                lexer.next();
+                depth += 1;
+            }
+            Some('*') if lexer.peek() == Some('/') => {
+                lexer.next();
+                depth -= 1;
+            }
+            Some(_) => {}
+            None => {
+                lexer.report(Error::UnterminatedBlockComment);
+                let slice = &lexer.get_slice()[2..]; // skip "/*"
+                lexer.bump(Token::Comment(slice));
+                return;
+            }
+        }
+    }
+    let slice = lexer.get_slice();
+    lexer.bump(Token::Comment(&slice[2..slice.len() - 2])); // skip "/*" and "*/"
 }
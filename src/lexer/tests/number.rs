@@ -62,3 +62,52 @@ fn float() {
     assert_eq!(parse_float("123.456"), (123.456, 0..7));
     assert_eq!(parse_float("1."), (1., 0..2));
 }
+
+#[test]
+fn int_underscore() {
+    assert_eq!(parse_int("1_000_000"), (1_000_000, 0..9));
+    assert_eq!(parse_int("0_1"), (1, 0..3));
+    assert_eq!(parse_int("0b1010_1010"), (0b1010_1010, 0..11));
+    assert_eq!(parse_int("0xFF_FF"), (0xFFFF, 0..7));
+}
+
+#[test]
+fn int_bit_pattern() {
+    assert_eq!(parse_int("0xFF"), (255, 0..4));
+    assert_eq!(parse_int("0xFFFFFFFFFFFFFFFF"), (-1, 0..18));
+    assert_eq!(parse_int("0x8000000000000000"), (i64::MIN, 0..18));
+    assert_eq!(parse_int("9223372036854775807"), (i64::MAX, 0..19));
+}
+
+#[test]
+fn float_exponent() {
+    assert_eq!(parse_float("1e3"), (1000.0, 0..3));
+    assert_eq!(parse_float("1E3"), (1000.0, 0..3));
+    assert_eq!(parse_float("1e-3"), (0.001, 0..4));
+    assert_eq!(parse_float("2.5e+2"), (250.0, 0..6));
+    assert_eq!(parse_float("0e0"), (0.0, 0..3));
+    assert_eq!(parse_float("1_000.000_1"), (1000.0001, 0..11));
+    assert_eq!(
+        parse_ok("1e x"),
+        vec![
+            (Token::Int(1), 0..1),
+            (Token::Ident("e"), 1..2),
+            (Token::Ident("x"), 3..4)
+        ]
+    );
+}
+
+#[test]
+fn number_error() {
+    for source in [
+        "9223372036854775808",
+        "0x1_0000_0000_0000_0000",
+        "0x1.5",
+        "0b",
+    ] {
+        let (tokens, errors) = lexer::parse(source);
+        assert_eq!(errors.len(), 1, "{}", source);
+        assert_eq!(tokens.len(), 1, "{}", source);
+        assert!(matches!(tokens[0].0, Token::Error(_)), "{}", source);
+    }
+}
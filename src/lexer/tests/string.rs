@@ -1,5 +1,6 @@
 mod common;
 use common::*;
+use foundation::TextSpan;
 pub use pretty_assertions::assert_eq;
 
 fn parse_comment(s: &str) -> String {
@@ -41,6 +42,33 @@ fn comment() {
     );
 }
 
+#[test]
+fn block_and_doc_comment() {
+    assert_eq!(parse_comment("/* a\n b */"), " a\n b ");
+    assert_eq!(parse_comment("/* a /* b */ c */"), " a /* b */ c ");
+    assert_eq!(
+        parse_ok("a /**/ / b ## doc\nc"),
+        vec![
+            (Token::Ident("a"), 0..1),
+            (Token::Comment(""), 2..6),
+            (Token::Slash, 7..8),
+            (Token::Ident("b"), 9..10),
+            (Token::DocComment(" doc"), 11..18),
+            (Token::Ident("c"), 18..19)
+        ]
+    );
+
+    let (tokens, errors) = lexer::parse("/* a /* b */");
+    assert_eq!(
+        tokens,
+        vec![(Token::Comment(" a /* b */"), TextSpan::new(0, 12))]
+    );
+    assert_eq!(
+        errors,
+        vec![lexer::Error::UnterminatedBlockComment(TextSpan::new(0, 12))]
+    );
+}
+
 #[test]
 fn string() {
     test_parse_string("abc de f", "abc de f");
@@ -67,3 +95,55 @@ fn string_escape() {
     test_parse_string(r#"\'"#, "'");
     test_parse_string(r#"\""#, "\"");
 }
+
+#[test]
+fn raw_string() {
+    assert_eq!(
+        parse_ok(r#""""a "b" \n""""#),
+        vec![(Token::String(r#"a "b" \n"#.into()), 0..14)]
+    );
+    assert_eq!(
+        parse_ok("'''\nfirst\n  second\n'''"),
+        vec![(Token::String("first\n  second\n".into()), 0..22)]
+    );
+    assert_eq!(
+        parse_ok(r#""""""" x"#),
+        vec![(Token::String("".into()), 0..6), (Token::Ident("x"), 7..8)]
+    );
+
+    let (tokens, errors) = lexer::parse(r#""""abc"#);
+    assert_eq!(
+        tokens,
+        vec![(Token::String("abc".into()), TextSpan::new(0, 6))]
+    );
+    assert_eq!(
+        errors,
+        vec![lexer::Error::MissingClosingDelimiter {
+            info: (None, TextSpan::at(6, 0)),
+            expected: '"',
+        }]
+    );
+}
+
+#[test]
+fn string_escape_error() {
+    fn error_span(s: &str) -> (Option<String>, std::ops::Range<u32>) {
+        let (_, errors) = lexer::parse(s);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        (errors[0].text(), errors[0].span().into_range())
+    }
+    assert_eq!(error_span(r#""a\qb""#), (Some(r"\q".into()), 2..4));
+    assert_eq!(error_span(r#""\x""#), (Some(r"\x".into()), 1..3));
+    assert_eq!(error_span(r#""\x4""#), (Some(r"\x4".into()), 1..4));
+    assert_eq!(error_span(r#""\u""#), (Some(r"\u".into()), 1..3));
+    assert_eq!(error_span(r#""\u{12""#), (Some(r"\u{12".into()), 1..6));
+    assert_eq!(error_span(r#""\u{}""#), (Some(r"\u{}".into()), 1..5));
+    assert_eq!(
+        error_span(r#""\u{1234567}""#),
+        (Some(r"\u{1234567}".into()), 1..12)
+    );
+    assert_eq!(
+        error_span(r#""\u{d800}""#),
+        (Some(r"\u{d800}".into()), 1..9)
+    );
+}
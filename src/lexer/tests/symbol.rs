@@ -30,6 +30,17 @@ fn keyword() {
     assert_eq!(parse_ok("return"), vec![(Token::Return, 0..6)]);
     assert_eq!(parse_ok("break"), vec![(Token::Break, 0..5)]);
     assert_eq!(parse_ok("continue"), vec![(Token::Continue, 0..8)]);
+    assert_eq!(parse_ok("match"), vec![(Token::Match, 0..5)]);
+    assert_eq!(parse_ok("case"), vec![(Token::Case, 0..4)]);
+    assert_eq!(parse_ok("with"), vec![(Token::With, 0..4)]);
+    assert_eq!(parse_ok("as"), vec![(Token::As, 0..2)]);
+    assert_eq!(parse_ok("assert"), vec![(Token::Assert, 0..6)]);
+    assert_eq!(parse_ok("class"), vec![(Token::Class, 0..5)]);
+    assert_eq!(parse_ok("try"), vec![(Token::Try, 0..3)]);
+    assert_eq!(parse_ok("catch"), vec![(Token::Catch, 0..5)]);
+    assert_eq!(parse_ok("yield"), vec![(Token::Yield, 0..5)]);
+    assert_eq!(parse_ok("import"), vec![(Token::Import, 0..6)]);
+    assert_eq!(parse_ok("global"), vec![(Token::Global, 0..6)]);
 }
 
 #[test]
@@ -37,7 +48,9 @@ fn operator() {
     assert_eq!(parse_ok("+"), vec![(Token::Plus, 0..1)]);
     assert_eq!(parse_ok("-"), vec![(Token::Minus, 0..1)]);
     assert_eq!(parse_ok("*"), vec![(Token::Star, 0..1)]);
+    assert_eq!(parse_ok("**"), vec![(Token::Star2, 0..2)]);
     assert_eq!(parse_ok("/"), vec![(Token::Slash, 0..1)]);
+    assert_eq!(parse_ok("//"), vec![(Token::Slash2, 0..2)]);
     assert_eq!(parse_ok("%"), vec![(Token::Mod, 0..1)]);
     assert_eq!(parse_ok("&"), vec![(Token::Amp, 0..1)]);
     assert_eq!(parse_ok("|"), vec![(Token::Pipe, 0..1)]);
@@ -53,8 +66,22 @@ fn operator() {
     assert_eq!(parse_ok(">>"), vec![(Token::Greater2, 0..2)]);
     assert_eq!(parse_ok("."), vec![(Token::Dot, 0..1)]);
     assert_eq!(parse_ok("->"), vec![(Token::Arrow, 0..2)]);
+    assert_eq!(parse_ok("|>"), vec![(Token::Pipeline, 0..2)]);
     assert_eq!(parse_ok(".."), vec![(Token::Dot2, 0..2)]);
+    assert_eq!(parse_ok("..."), vec![(Token::Dot3, 0..3)]);
     assert_eq!(parse_ok("="), vec![(Token::Assign, 0..1)]);
+    assert_eq!(parse_ok("??="), vec![(Token::Question2Assign, 0..3)]);
+    assert_eq!(parse_ok("??"), vec![(Token::Question2, 0..2)]);
+    assert_eq!(parse_ok("?."), vec![(Token::QuestionDot, 0..2)]);
+    assert_eq!(parse_ok("?->"), vec![(Token::QuestionArrow, 0..3)]);
+    assert_eq!(parse_ok("||="), vec![(Token::Pipe2Assign, 0..3)]);
+    assert_eq!(parse_ok("||"), vec![(Token::Pipe2, 0..2)]);
+    assert_eq!(parse_ok("+="), vec![(Token::PlusAssign, 0..2)]);
+    assert_eq!(parse_ok("-="), vec![(Token::MinusAssign, 0..2)]);
+    assert_eq!(parse_ok("*="), vec![(Token::StarAssign, 0..2)]);
+    assert_eq!(parse_ok("/="), vec![(Token::SlashAssign, 0..2)]);
+    assert_eq!(parse_ok("%="), vec![(Token::ModAssign, 0..2)]);
+    assert_eq!(parse_ok("..="), vec![(Token::Dot2Assign, 0..3)]);
 }
 
 #[test]
@@ -4,3 +4,8 @@ pub use compiler;
 pub use lexer;
 pub use parser;
 pub use vm;
+
+mod diagnostic;
+mod engine;
+pub use diagnostic::*;
+pub use engine::*;
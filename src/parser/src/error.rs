@@ -33,3 +33,18 @@ pub enum Error {
     #[error("{0}")]
     Contextual(String, TextSpan),
 }
+
+impl Error {
+    pub fn span(&self) -> TextSpan {
+        use Error::*;
+        match self {
+            UnexpectedSymbol(_, x) => *x,
+            UnexpectedEof(_, x) => *x,
+            ExpectedFound { found: (_, x), .. } => *x,
+            MissingRequiredElement(_, x) => *x,
+            MissingClosingSymbol { info: (_, x), .. } => *x,
+            InvalidStatement { info: (_, x), .. } => *x,
+            Contextual(_, x) => *x,
+        }
+    }
+}
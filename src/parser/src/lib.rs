@@ -10,12 +10,23 @@ pub fn parse<'tokens, 'src: 'tokens>(
     tokens: &'tokens [(Token<'src>, TextSpan)],
 ) -> (Program<'src>, Vec<Error>) {
     let (mut program, errors) = parser::parse(tokens);
+    resolve(&mut program);
+    (program, errors)
+}
 
+/// Parses a single expression, as a program that returns its value.
+pub fn parse_expression<'tokens, 'src: 'tokens>(
+    tokens: &'tokens [(Token<'src>, TextSpan)],
+) -> (Program<'src>, Vec<Error>) {
+    let (mut program, errors) = parser::parse_expression(tokens);
+    resolve(&mut program);
+    (program, errors)
+}
+
+fn resolve(program: &mut Program) {
     let mut walker = walker::Walker::new();
     walker.go(&mut program.body.block);
     let mut result = walker.finish();
     program.body.captures = result.captures();
     program.attributes = result.take_attributes();
-
-    (program, errors)
 }
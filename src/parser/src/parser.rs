@@ -14,6 +14,15 @@ pub fn parse<'tokens, 'src: 'tokens>(
     (program, errors)
 }
 
+pub fn parse_expression<'tokens, 'src: 'tokens>(
+    tokens: &'tokens [(Token<'src>, TextSpan)],
+) -> (Program<'src>, Vec<Error>) {
+    let mut parser = Parser(internal::ParserCore::new(tokens));
+    let program = parser.expression_program();
+    let errors = parser.done();
+    (program, errors)
+}
+
 struct Parser<'tokens, 'src: 'tokens>(internal::ParserCore<'tokens, 'src>);
 
 impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
@@ -24,6 +33,42 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             body: chunk,
         }
     }
+
+    pub fn expression_program(&mut self) -> Program<'src> {
+        self.skip_comments();
+        let statement = match self.expression() {
+            Some(expr) => {
+                let span = expr.1;
+                (Statement::Return { value: Some(expr) }, span)
+            }
+            None => {
+                let span = self.look(0).map_or(self.eoi_span(), |(_, span)| *span);
+                self.report(Error::MissingRequiredElement("expression", span));
+                (Statement::Error, span)
+            }
+        };
+        self.skip_comments();
+        if let Some((token, span)) = self.look(0) {
+            let found = (token.to_string(), *span);
+            self.report(Error::ExpectedFound {
+                expected: "end of expression",
+                found,
+            });
+        }
+        Program {
+            attributes: vec![],
+            body: Chunk {
+                captures: vec![],
+                block: Block(vec![statement]),
+            },
+        }
+    }
+
+    fn skip_comments(&mut self) {
+        while let Some((Token::Comment(_) | Token::DocComment(_), _)) = self.look(0) {
+            self.move_next();
+        }
+    }
 }
 
 mod internal {
@@ -54,7 +99,9 @@ mod internal {
         #[inline]
         #[must_use]
         pub fn done(self) -> Vec<Error> {
-            self.0.errors
+            let mut errors = self.0.errors;
+            errors.sort_by_key(|error| error.span().start());
+            errors
         }
 
         #[inline]
@@ -87,9 +134,76 @@ mod internal {
             self.0.index -= 1;
         }
 
-        #[inline]
+        /// Records a syntax error. One at the same place as the previous error is most likely
+        /// caused by it, so it is dropped.
         pub fn report(&mut self, error: Error) {
-            self.0.errors.push(error);
+            let same_place = (self.0.errors.last())
+                .is_some_and(|last| last.span().start() == error.span().start());
+            if !same_place {
+                self.0.errors.push(error);
+            }
+        }
+
+        #[inline]
+        pub fn error_count(&self) -> usize {
+            self.0.errors.len()
+        }
+
+        /// Skips tokens after a syntax error, up to one that `stop` accepts or that may start a
+        /// statement or end a block, so that parsing goes on from there.
+        pub fn synchronize_to(&mut self, stop: impl Fn(&Token) -> bool) {
+            while let Some((token, _)) = self.0.tokens.get(self.0.index) {
+                if stop(token) || self.at_statement_boundary() {
+                    break;
+                }
+                self.0.index += 1;
+            }
+        }
+
+        fn at_statement_boundary(&self) -> bool {
+            match &self.0.tokens[self.0.index].0 {
+                Token::Var
+                | Token::Func
+                | Token::If
+                | Token::Elif
+                | Token::Else
+                | Token::For
+                | Token::While
+                | Token::Do
+                | Token::End
+                | Token::Return
+                | Token::Break
+                | Token::Continue
+                | Token::Match
+                | Token::Case
+                | Token::With
+                | Token::Assert
+                | Token::Class
+                | Token::Try
+                | Token::Catch
+                | Token::Yield
+                | Token::Import
+                | Token::Global
+                | Token::Attribute(_) => true,
+                // Two operands in a row can't be one expression, so the second one starts a
+                // statement.
+                Token::Ident(_) => self.0.index.checked_sub(1).is_some_and(|prev| {
+                    matches!(
+                        self.0.tokens[prev].0,
+                        Token::Int(_)
+                            | Token::Float(_)
+                            | Token::String(_)
+                            | Token::Bool(_)
+                            | Token::Nil
+                            | Token::Ident(_)
+                            | Token::CloseParen
+                            | Token::CloseBracket
+                            | Token::CloseBrace
+                            | Token::End
+                    )
+                }),
+                _ => false,
+            }
         }
 
         #[inline]
@@ -27,11 +27,17 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 Some((Token::End, span)) => break span,
                 Some((token, span)) => {
                     let Some(statement) = self.statement_with(token, span) else {
-                        todo!("implement error recovery");
+                        let eoi_span = self.eoi_span();
+                        self.report(Error::UnexpectedEof("end", eoi_span));
+                        break eoi_span;
                     };
                     statements.push(statement);
                 }
-                None => todo!("implement error recovery"),
+                None => {
+                    let eoi_span = self.eoi_span();
+                    self.report(Error::UnexpectedEof("end", eoi_span));
+                    break eoi_span;
+                }
             }
         };
         (Block(statements), end_span)
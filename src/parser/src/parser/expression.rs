@@ -3,9 +3,7 @@ use super::*;
 impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
     // None = the next token cannot be parsed as an expression.
     pub fn expression(&mut self) -> Option<(Expression<'src>, TextSpan)> {
-        let Some((token, _)) = self.look(0) else {
-            return None;
-        };
+        let (token, _) = self.look(0)?;
 
         match token {
             Token::Int(_) | Token::Float(_) | Token::String(_) | Token::Bool(_) | Token::Nil => {
@@ -15,7 +13,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             // keywords
             Token::Var => None,
             Token::Func => Some(self.expr_bp(0)),
-            Token::If => None,
+            Token::If => Some(self.expr_bp(0)),
             Token::Then => None,
             Token::Elif => None,
             Token::Else => None,
@@ -28,15 +26,29 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             Token::Return => None,
             Token::Break => None,
             Token::Continue => None,
+            Token::Match => None,
+            Token::Case => None,
+            Token::With => None,
+            Token::As => None,
+            Token::Assert => None,
+            Token::Class => None,
+            Token::Try => None,
+            Token::Catch => None,
+            Token::Yield => Some(self.expr_bp(0)),
+            Token::Import => Some(self.expr_bp(0)),
+            Token::Global => None,
 
             // operators
             Token::Plus
             | Token::Minus
             | Token::Star
+            | Token::Star2
             | Token::Slash
+            | Token::Slash2
             | Token::Mod
             | Token::Amp
             | Token::Pipe
+            | Token::Pipe2
             | Token::Caret
             | Token::Tilde
             | Token::Eq
@@ -49,8 +61,21 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             | Token::Greater2
             | Token::Dot
             | Token::Arrow
+            | Token::Pipeline
             | Token::Dot2
-            | Token::Assign => Some(self.expr_bp(0)),
+            | Token::Dot3
+            | Token::Question2
+            | Token::QuestionDot
+            | Token::QuestionArrow
+            | Token::Assign
+            | Token::Question2Assign
+            | Token::Pipe2Assign
+            | Token::PlusAssign
+            | Token::MinusAssign
+            | Token::StarAssign
+            | Token::SlashAssign
+            | Token::ModAssign
+            | Token::Dot2Assign => Some(self.expr_bp(0)),
 
             // keyword operators
             Token::And | Token::Or | Token::Not => Some(self.expr_bp(0)),
@@ -68,16 +93,19 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             // other
             Token::Ident(_) => Some(self.expr_bp(0)),
             Token::Attribute(_) => None,
-            Token::Comment(_) => {
+            Token::Comment(_) | Token::DocComment(_) => {
                 loop {
                     self.move_next();
-                    if !matches!(self.look(0), Some((Token::Comment(_), _))) {
+                    if !matches!(
+                        self.look(0),
+                        Some((Token::Comment(_) | Token::DocComment(_), _))
+                    ) {
                         break;
                     }
                 }
                 self.expression()
             }
-            Token::Error(_) => todo!(),
+            Token::Error(_) => Some(self.expr_bp(0)),
         }
     }
 
@@ -139,8 +167,10 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                             self.move_next();
                             self.func_def_args()
                         }
-                        Some(_) => todo!("implement error recovery"),
-                        None => todo!("implement error recovery"),
+                        _ => {
+                            self.expected("(");
+                            (Vec::new(), current_span)
+                        }
                     };
                     let (body, end_span) = self.block_until_end_token();
                     (
@@ -154,6 +184,48 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         TextSpan::new(current_span.start(), end_span.end()),
                     )
                 }
+                Token::If => self.if_expression(current_span),
+                // yield [expr]
+                Token::Yield => match self.expression() {
+                    Some((value, value_span)) => (
+                        Expression::Yield {
+                            value: Some((Box::new(value), value_span)),
+                        },
+                        TextSpan::new(current_span.start(), value_span.end()),
+                    ),
+                    None => (Expression::Yield { value: None }, current_span),
+                },
+                // import "path"
+                Token::Import => match self.look(0) {
+                    Some((Token::String(path), path_span)) => {
+                        let path = (path.clone(), *path_span);
+                        self.move_next();
+                        let span = TextSpan::new(current_span.start(), path.1.end());
+                        (Expression::Import { path }, span)
+                    }
+                    _ => {
+                        self.expected("<string>");
+                        (Expression::Error, current_span)
+                    }
+                },
+                // |x, y| x + y, or || x without arguments
+                Token::Pipe | Token::Pipe2 => {
+                    let (args, _) = self.lambda_args();
+                    let (body, body_span) = self.expr_bp(0);
+                    let return_ = Statement::Return {
+                        value: Some((body, body_span)),
+                    };
+                    (
+                        Expression::FunctionObject(FunctionObject {
+                            args,
+                            body: Chunk {
+                                captures: vec![],
+                                block: Block(vec![(return_, body_span)]),
+                            },
+                        }),
+                        TextSpan::new(current_span.start(), body_span.end()),
+                    )
+                }
                 Token::OpenBrace => {
                     let (fields, close_span) = {
                         if let Some((Token::CloseBrace, span)) = self.look(0) {
@@ -165,22 +237,39 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                             let close_span = loop {
                                 let field = match self.next() {
                                     Some((Token::Ident(name), name_span)) => {
-                                        if let Some((Token::Assign, _)) = self.look(0) {
+                                        let expr = self.table_field_value();
+                                        (TableFieldKey::Ident(name, name_span), expr)
+                                    }
+                                    Some((Token::OpenBracket, _)) => {
+                                        let (key, key_span) = match self.expression() {
+                                            Some(key) => key,
+                                            None => (Expression::Error, self.expected("<expr>")),
+                                        };
+                                        if let Some((Token::CloseBracket, _)) = self.look(0) {
                                             self.move_next();
-                                            let Some(expr) = self.expression() else {
-                                                todo!("implement error recovery");
-                                            };
-                                            (TableFieldKey::Ident(name, name_span), expr)
                                         } else {
-                                            todo!("implement error recovery");
+                                            self.expected("]");
                                         }
-                                    }
-                                    Some((Token::OpenBracket, _)) => {
-                                        todo!("implement [expr] key")
+                                        let expr = self.table_field_value();
+                                        (TableFieldKey::Expr(key, key_span), expr)
                                     }
                                     // Some((Token::Func, func_span)) => {}
-                                    Some(_) => todo!("implement error recovery"),
-                                    None => todo!("implement error recovery"),
+                                    Some((token, span)) => {
+                                        self.report(Error::ExpectedFound {
+                                            expected: "<field>",
+                                            found: (token.to_string(), span),
+                                        });
+                                        self.move_prev();
+                                        break self.recover_to_close(
+                                            |token| matches!(token, Token::CloseBrace),
+                                            span,
+                                        );
+                                    }
+                                    None => {
+                                        let eoi_span = self.eoi_span();
+                                        self.report(Error::UnexpectedEof("}", eoi_span));
+                                        break eoi_span;
+                                    }
                                 };
                                 fields.push(field);
                                 let Some((delim, delim_span)) = self.look(0) else {
@@ -202,7 +291,13 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                                         self.move_next();
                                         break close_span;
                                     }
-                                    _ => todo!("implement error recovery"),
+                                    _ => {
+                                        let span = self.expected("}");
+                                        break self.recover_to_close(
+                                            |token| matches!(token, Token::CloseBrace),
+                                            span,
+                                        );
+                                    }
                                 }
                             };
                             (fields, close_span)
@@ -222,7 +317,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         } else {
                             let mut exprs = Vec::new();
                             let close_span = loop {
-                                let Some((expr, expr_span)) = self.expression() else {
+                                let Some((expr, expr_span)) = self.element() else {
                                     match self.look(0) {
                                         Some((Token::Comma, span)) => {
                                             self.report(Error::UnexpectedSymbol(",", *span));
@@ -230,7 +325,11 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                                             continue;
                                         }
                                         Some(_) => {
-                                            todo!("implement error recovery");
+                                            let span = self.expected("<expr>");
+                                            break self.recover_to_close(
+                                                |token| matches!(token, Token::CloseBracket),
+                                                span,
+                                            );
                                         }
                                         None => {
                                             let eoi_span = self.eoi_span();
@@ -259,7 +358,13 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                                         self.move_next();
                                         break close_span;
                                     }
-                                    _ => todo!("implement error recovery"),
+                                    _ => {
+                                        let span = self.expected("]");
+                                        break self.recover_to_close(
+                                            |token| matches!(token, Token::CloseBracket),
+                                            span,
+                                        );
+                                    }
                                 }
                             };
                             (exprs, close_span)
@@ -271,28 +376,37 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     )
                 }
                 Token::OpenParen => {
-                    let (expr, _) = self.expr_bp(0);
-                    let close_span = match self.next() {
-                        Some((Token::CloseParen, close_span)) => close_span,
-                        Some(_) => todo!("implement error recovery"),
-                        None => {
-                            let eoi_span = self.eoi_span();
-                            self.report(Error::UnexpectedEof(")", eoi_span));
-                            eoi_span
+                    let (expr, expr_span) = self.expr_bp(0);
+                    let end = match self.look(0) {
+                        Some((Token::CloseParen, close_span)) => {
+                            let end = close_span.end();
+                            self.move_next();
+                            end
+                        }
+                        _ => {
+                            self.expected(")");
+                            expr_span.end()
                         }
                     };
-                    let span = TextSpan::new(current_span.start(), close_span.end());
+                    let span = TextSpan::new(current_span.start(), end);
                     (expr, span)
                 }
-                Token::Comment(_) => {
+                Token::Comment(_) | Token::DocComment(_) => {
                     loop {
                         self.move_next();
-                        if !matches!(self.look(0), Some((Token::Comment(_), _))) {
+                        if !matches!(
+                            self.look(0),
+                            Some((Token::Comment(_) | Token::DocComment(_), _))
+                        ) {
                             break;
                         }
                     }
                     return self.expr_bp(min_bp);
                 }
+                // The lexer has already reported it.
+                Token::Error(_) if binding_power::infix_op(current).is_none() => {
+                    (Expression::Error, current_span)
+                }
                 _ => {
                     let missing_expr_span = {
                         let prev_span = self
@@ -329,16 +443,18 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             },
         };
 
-        loop {
-            let Some((current, _)) = self.look(0) else {
-                break;
-            };
-
+        while let Some((current, op_span)) = self.look(0) {
+            let op_span = *op_span;
             if let Some(l_bp) = binding_power::postfix_op(current) {
                 if l_bp < min_bp {
                     break;
                 }
                 let (current, _) = unsafe { self.next().unwrap_unchecked() }; // SAFETY: this is checked to be Some() by above self.look(0)
+                if matches!(current, Token::QuestionDot | Token::QuestionArrow) {
+                    lhs = Expression::Optional {
+                        expr: (Box::new(lhs), lhs_span),
+                    };
+                }
                 (lhs, lhs_span) = match current {
                     Token::OpenParen => {
                         let (args, close_span) = self.func_call_args();
@@ -350,60 +466,104 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                             TextSpan::new(lhs_span.start(), close_span.end()),
                         )
                     },
-                    Token::Arrow => {
-                        let (name, name_span) = match self.next() {
-                            Some((Token::Ident(x), span)) => (*x, span),
-                            _ => {
-                                self.move_prev();
-                                todo!("implement error recovery")
-                            }
-                        };
-                        match self.next() {
-                            Some((Token::OpenParen, _)) => {},
-                            _ => todo!("implement error recovery"),
+                    Token::Arrow | Token::QuestionArrow => match self.look(0) {
+                        Some((Token::Ident(_), _)) => {
+                            // SAFETY: `self.look(0)` is `Token::Ident`.
+                            let (name, name_span) = unsafe { self.next_ident_unchecked() };
+                            let (args, close_span) = match self.look(0) {
+                                Some((Token::OpenParen, _)) => {
+                                    self.move_next();
+                                    self.func_call_args()
+                                }
+                                _ => {
+                                    self.expected("(");
+                                    (Vec::new(), name_span)
+                                }
+                            };
+                            (
+                                Expression::MethodCall {
+                                    expr: (Box::new(lhs), lhs_span),
+                                    name: (name, name_span),
+                                    args,
+                                },
+                                TextSpan::new(lhs_span.start(), close_span.end()),
+                            )
+                        }
+                        _ => {
+                            self.expected("<name>");
+                            (Expression::Error, lhs_span)
                         }
-                        let (args, close_span) = self.func_call_args();
-                        (
-                            Expression::MethodCall {
-                                expr: (Box::new(lhs), lhs_span),
-                                name: (name, name_span),
-                                args,
-                            },
-                            TextSpan::new(lhs_span.start(), close_span.end()),
-                        )
                     },
-                    Token::Dot => {
-                        let (name, name_span) = match self.next() {
-                            Some((Token::Ident(x), span)) => (*x, span),
+                    Token::Dot | Token::QuestionDot => match self.look(0) {
+                        Some((Token::Ident(_), _)) => {
+                            // SAFETY: `self.look(0)` is `Token::Ident`.
+                            let (name, name_span) = unsafe { self.next_ident_unchecked() };
+                            (
+                                Expression::DotAccess {
+                                    expr: (Box::new(lhs), lhs_span),
+                                    accessor: (name, name_span),
+                                },
+                                TextSpan::new(lhs_span.start(), name_span.end()),
+                            )
+                        }
+                        _ => {
+                            self.expected("<name>");
+                            (Expression::Error, lhs_span)
+                        }
+                    },
+                    Token::OpenBracket => {
+                        // `[:` starts a slice without its start bound.
+                        let start = match self.look(0) {
+                            Some((Token::Colon, _)) => None,
+                            _ => Some(match self.expression() {
+                                Some(expr) => expr,
+                                None => (Expression::Error, self.expected("<expr>")),
+                            }),
+                        };
+                        let slice = match self.look(0) {
+                            Some(&(Token::Colon, colon_span)) => {
+                                self.move_next();
+                                let end = match self.look(0) {
+                                    Some((Token::CloseBracket, _)) => None,
+                                    _ => Some(match self.expression() {
+                                        Some(expr) => expr,
+                                        None => (Expression::Error, self.expected("<expr>")),
+                                    }),
+                                };
+                                Some((colon_span, end))
+                            }
+                            _ => None,
+                        };
+                        let last_end = match (&start, &slice) {
+                            (_, Some((_, Some((_, end_span))))) => end_span.end(),
+                            (_, Some((colon_span, None))) => colon_span.end(),
+                            (Some((_, start_span)), None) => start_span.end(),
+                            (None, None) => unreachable!("a slice without its start has a `:`"),
+                        };
+                        let end = match self.look(0) {
+                            Some((Token::CloseBracket, close_span)) => {
+                                let end = close_span.end();
+                                self.move_next();
+                                end
+                            }
                             _ => {
-                                self.move_prev();
-                                todo!("implement error recovery")
+                                self.expected("]");
+                                last_end
                             }
                         };
-                        (
-                            Expression::DotAccess {
+                        let expr = match (start, slice) {
+                            (start, Some((_, end))) => Expression::SliceAccess {
                                 expr: (Box::new(lhs), lhs_span),
-                                accessor: (name, name_span),
+                                start: start.map(|(expr, span)| (Box::new(expr), span)),
+                                end: end.map(|(expr, span)| (Box::new(expr), span)),
                             },
-                            TextSpan::new(lhs_span.start(), name_span.end()),
-                        )
-                    },
-                    Token::OpenBracket => {
-                        let Some((expr, expr_span)) = self.expression() else {
-                            todo!("implement error recovery");
+                            (Some((expr, expr_span)), None) => Expression::IndexAccess {
+                                expr: (Box::new(lhs), lhs_span),
+                                accessor: (Box::new(expr), expr_span)
+                            },
+                            (None, None) => unreachable!("a slice without its start has a `:`"),
                         };
-                        if let Some((Token::CloseBracket, close_span)) = self.next() {
-                            (
-                                Expression::IndexAccess {
-                                    expr: (Box::new(lhs), lhs_span),
-                                    accessor: (Box::new(expr), expr_span)
-                                },
-                                TextSpan::new(lhs_span.start(), close_span.end()),
-                            )
-                        } else {
-                            self.move_prev();
-                            todo!("implement error recovery");
-                        }
+                        (expr, TextSpan::new(lhs_span.start(), end))
                     },
                     _ => unreachable!(
                         "binding_power::postfix_op() should only return Some() for valid postfix operators"
@@ -414,7 +574,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
 
             if let Some((op, (l_bp, r_bp), err)) = binding_power::infix_op(current) {
                 if let Some(err) = err {
-                    self.report(Error::Contextual(err, current_span));
+                    self.report(Error::Contextual(err, op_span));
                 }
                 if l_bp < min_bp {
                     break;
@@ -436,39 +596,107 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
         }
         (lhs, lhs_span)
     }
+
+    // if <expr> then <expr> [elif <expr> then <expr>]* else <expr> end
+    fn if_expression(&mut self, start_span: TextSpan) -> (Expression<'src>, TextSpan) {
+        let cond = self.if_expression_operand();
+        self.expect_keyword("then", |token| matches!(token, Token::Then));
+        let then = self.if_expression_operand();
+        let mut elifs = Vec::new();
+        while let Some((Token::Elif, _)) = self.look(0) {
+            self.move_next();
+            let cond = self.if_expression_operand();
+            self.expect_keyword("then", |token| matches!(token, Token::Then));
+            elifs.push((cond, self.if_expression_operand()));
+        }
+        // Unlike the statement, the expression must have a value whichever arm is taken.
+        let (else_, else_span) = match self.look(0) {
+            Some((Token::Else, _)) => {
+                self.move_next();
+                self.if_expression_operand()
+            }
+            _ => (Expression::Error, self.expected("else")),
+        };
+        let end = match self.look(0) {
+            Some((Token::End, end_span)) => {
+                let end = end_span.end();
+                self.move_next();
+                end
+            }
+            _ => {
+                self.expected("end");
+                else_span.end()
+            }
+        };
+        (
+            Expression::If {
+                cond: (Box::new(cond.0), cond.1),
+                then: (Box::new(then.0), then.1),
+                elifs,
+                else_: (Box::new(else_), else_span),
+            },
+            TextSpan::new(start_span.start(), end),
+        )
+    }
+
+    fn if_expression_operand(&mut self) -> (Expression<'src>, TextSpan) {
+        match self.expression() {
+            Some(expr) => expr,
+            None => (Expression::Error, self.expected("<expr>")),
+        }
+    }
+
+    // = <expr>
+    fn table_field_value(&mut self) -> (Expression<'src>, TextSpan) {
+        if let Some((Token::Assign, _)) = self.look(0) {
+            self.move_next();
+            match self.expression() {
+                Some(expr) => expr,
+                None => (Expression::Error, self.expected("<expr>")),
+            }
+        } else {
+            (Expression::Error, self.expected("="))
+        }
+    }
 }
 
-/// |        Precedence        | Associativity |     Operators     |
-/// | -----------------------  | ------------- | ----------------- |
-/// | 12: Unary Postfix        |    postfix    | .x, [], (), ->x() |
-/// | 11: Unary Prefix         |    prefix     | +, -, not         |
-/// | 10: Multiplicative       |   left infix  | *, /, %           |
-/// |  9: Additive             |   left infix  | +, -              |
-/// |  8: String concatenation |  right infix  | ..                |
-/// |  7: Shift                |   left infix  | <<, >>            |
-/// |  6: Relational           |   left infix  | <, <=, >, >=      |
-/// |  5: Equality             |   left infix  | ==, !=            |
-/// |  4: Boolean-AND          |   left infix  | &                 |
-/// |  3: Boolean-XOR          |   left infix  | ^                 |
-/// |  2: Boolean-OR           |   left infix  | |                 |
-/// |  1: Logical-AND          |   left infix  | and               |
-/// |  0: Logical-OR           |   left infix  | or                |
+/// |        Precedence        | Associativity |           Operators            |
+/// | -----------------------  | ------------- | ------------------------------ |
+/// | 15: Unary Postfix        |    postfix    | .x, [], (), ->x(), ?.x, ?->x() |
+/// | 14: Exponent             |  right infix  | **                             |
+/// | 13: Unary Prefix         |    prefix     | -, not, ~                      |
+/// | 12: Multiplicative       |   left infix  | *, /, //, %                    |
+/// | 11: Additive             |   left infix  | +, -                           |
+/// | 10: String concatenation |  right infix  | ..                             |
+/// |  9: Shift                |   left infix  | <<, >>                         |
+/// |  8: Relational           |   left infix  | <, <=, >, >=, in               |
+/// |  7: Equality             |   left infix  | ==, !=                         |
+/// |  6: Bitwise-AND          |   left infix  | &                              |
+/// |  5: Bitwise-XOR          |   left infix  | ^                              |
+/// |  4: Bitwise-OR           |   left infix  | |                              |
+/// |  3: Logical-AND          |   left infix  | and                            |
+/// |  2: Logical-OR           |   left infix  | or                             |
+/// |  1: Nil-coalescing       |  right infix  | ??                             |
+/// |  0: Pipeline             |   left infix  | |>                             |
 mod binding_power {
     use super::*;
 
-    const UNARY_POSTFIX: u8 = 12;
-    const UNARY_PREFIX: u8 = 11;
-    const MULTIPLICATIVE: u8 = 10;
-    const ADDITIVE: u8 = 9;
-    const STRING_CONCAT: u8 = 8;
-    const SHIFT: u8 = 7;
-    const RELATIONAL: u8 = 6;
-    const EQUALITY: u8 = 5;
-    const BIT_AND: u8 = 4;
-    const BIT_XOR: u8 = 3;
-    const BIT_OR: u8 = 2;
-    const LOGICAL_AND: u8 = 1;
-    const LOGICAL_OR: u8 = 0;
+    const UNARY_POSTFIX: u8 = 15;
+    const EXPONENT: u8 = 14;
+    const UNARY_PREFIX: u8 = 13;
+    const MULTIPLICATIVE: u8 = 12;
+    const ADDITIVE: u8 = 11;
+    const STRING_CONCAT: u8 = 10;
+    const SHIFT: u8 = 9;
+    const RELATIONAL: u8 = 8;
+    const EQUALITY: u8 = 7;
+    const BIT_AND: u8 = 6;
+    const BIT_XOR: u8 = 5;
+    const BIT_OR: u8 = 4;
+    const LOGICAL_AND: u8 = 3;
+    const LOGICAL_OR: u8 = 2;
+    const NIL_COALESCE: u8 = 1;
+    const PIPELINE: u8 = 0;
 
     pub fn prefix_op(token: &Token) -> Option<(UnaryOp, u8, Option<String>)> {
         #[rustfmt::skip]
@@ -489,6 +717,8 @@ mod binding_power {
             | Token::OpenBracket // [] (indexing)
             | Token::OpenParen   // () (function call)
             | Token::Arrow // ->x() (method call)
+            | Token::QuestionDot // ?.x (optional dot access)
+            | Token::QuestionArrow // ?->x() (optional method call)
         ) {
             return None;
         }
@@ -505,8 +735,10 @@ mod binding_power {
 
         #[rustfmt::skip]
         let (bp, op, err) = match token {
+            Token::Star2     => (right(EXPONENT),      BinaryOp::Pow,        None),
             Token::Star      => (left(MULTIPLICATIVE), BinaryOp::Mul,        None),
             Token::Slash     => (left(MULTIPLICATIVE), BinaryOp::Div,        None),
+            Token::Slash2    => (left(MULTIPLICATIVE), BinaryOp::FloorDiv,   None),
             Token::Mod       => (left(MULTIPLICATIVE), BinaryOp::Mod,        None),
             Token::Plus      => (left(ADDITIVE),       BinaryOp::Add,        None),
             Token::Minus     => (left(ADDITIVE),       BinaryOp::Sub,        None),
@@ -517,6 +749,7 @@ mod binding_power {
             Token::LessEq    => (left(RELATIONAL),     BinaryOp::LessEq,     None),
             Token::Greater   => (left(RELATIONAL),     BinaryOp::Greater,    None),
             Token::GreaterEq => (left(RELATIONAL),     BinaryOp::GreaterEq,  None),
+            Token::In        => (left(RELATIONAL),     BinaryOp::In,         None),
             Token::Eq        => (left(EQUALITY),       BinaryOp::Eq,         None),
             Token::NotEq     => (left(EQUALITY),       BinaryOp::NotEq,      None),
             Token::Amp       => (left(BIT_AND),        BinaryOp::BitAnd,     None),
@@ -524,6 +757,9 @@ mod binding_power {
             Token::Pipe      => (left(BIT_OR),         BinaryOp::BitOr,      None),
             Token::And       => (left(LOGICAL_AND),    BinaryOp::And,        None),
             Token::Or        => (left(LOGICAL_OR),     BinaryOp::Or,         None),
+            Token::Pipe2     => (left(LOGICAL_OR),     BinaryOp::Or,         Some("Should use `or` for logical or")),
+            Token::Question2 => (right(NIL_COALESCE),  BinaryOp::NilCoalesce, None),
+            Token::Pipeline  => (left(PIPELINE),       BinaryOp::Pipeline,   None),
             // Token::Assign    => {
             //     let err = "Should use `==` for equal".to_string();
             //     (left(EQUALITY), Some(err), BinaryOp::Eq)
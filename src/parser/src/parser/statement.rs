@@ -1,43 +1,94 @@
 use super::*;
 
 impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
-    /// None = (Token::Error | Token::Comment)* EOF
+    /// None = (Token::Error | Token::Comment | Token::DocComment)* EOF
     pub fn statement(&mut self) -> Option<(Statement<'src>, TextSpan)> {
-        let Some((token, span)) = self.next() else {
-            return None;
-        };
+        let (token, span) = self.next()?;
         self.statement_with(token, span)
     }
 
-    /// None = (Token::Error | Token::Comment)* EOF
+    /// None = (Token::Error | Token::Comment | Token::DocComment)* EOF
     pub fn statement_with(
         &mut self,
         token: &'tokens Token<'src>,
         span: TextSpan,
+    ) -> Option<(Statement<'src>, TextSpan)> {
+        let error_count = self.error_count();
+        let statement = self.statement_kind(token, span);
+        // The rest of a statement with a syntax error would only cause more errors.
+        if self.error_count() > error_count {
+            self.synchronize_to(|_| false);
+        }
+        statement
+    }
+
+    fn statement_kind(
+        &mut self,
+        token: &'tokens Token<'src>,
+        span: TextSpan,
     ) -> Option<(Statement<'src>, TextSpan)> {
         match token {
-            Token::Int(_) => todo!(),
-            Token::Float(_) => todo!(),
-            Token::String(_) => todo!(),
-            Token::Bool(_) => todo!(),
-            Token::Nil => todo!(),
+            Token::Int(_) | Token::Float(_) | Token::String(_) | Token::Bool(_) | Token::Nil => {
+                self.move_prev();
+                // SAFETY: A literal starts an expression, so `self.expression()` returns Some.
+                let (expr, expr_span) = unsafe { self.expression().unwrap_unchecked() };
+                Some(self.expr_to_statement(expr, expr_span))
+            }
 
             // keywords
             Token::Var => Some(self.var_statement(span)),
             Token::Func => Some(self.func_statement(span)),
             Token::If => Some(self.if_statement(span)),
-            Token::Then => todo!(),
-            Token::Elif => todo!(),
-            Token::Else => todo!(),
+            Token::Then => {
+                self.report(Error::UnexpectedSymbol("then", span));
+                Some((Statement::Error, span))
+            }
+            Token::Elif => {
+                self.report(Error::UnexpectedSymbol("elif", span));
+                Some((Statement::Error, span))
+            }
+            Token::Else => {
+                self.report(Error::UnexpectedSymbol("else", span));
+                Some((Statement::Error, span))
+            }
             Token::For => Some(self.for_statement(span)),
             Token::While => Some(self.while_statement(span)),
-            Token::In => todo!(),
-            Token::Ref => todo!(),
+            Token::In => {
+                self.report(Error::UnexpectedSymbol("in", span));
+                Some((Statement::Error, span))
+            }
+            Token::Ref => {
+                self.report(Error::UnexpectedSymbol("ref", span));
+                Some((Statement::Error, span))
+            }
             Token::Do => Some(self.do_statement(span)),
-            Token::End => todo!(),
+            Token::End => {
+                self.report(Error::UnexpectedSymbol("end", span));
+                Some((Statement::Error, span))
+            }
             Token::Return => Some(self.return_statement(span)),
             Token::Break => Some((Statement::Break, span)),
             Token::Continue => Some((Statement::Continue, span)),
+            Token::Match => Some(self.match_statement(span)),
+            Token::Case => {
+                self.report(Error::UnexpectedSymbol("case", span));
+                Some((Statement::Error, span))
+            }
+            Token::With => Some(self.with_statement(span)),
+            Token::As => {
+                self.report(Error::UnexpectedSymbol("as", span));
+                Some((Statement::Error, span))
+            }
+            Token::Assert => Some(self.assert_statement(span)),
+            Token::Class => Some(self.class_statement(span)),
+            Token::Try => Some(self.try_statement(span)),
+            Token::Yield => Some(self.yield_statement(span)),
+            Token::Import => Some(self.import_statement(span)),
+            Token::Global => Some(self.global_statement(span)),
+            Token::Catch => {
+                self.report(Error::UnexpectedSymbol("catch", span));
+                Some((Statement::Error, span))
+            }
 
             // operators
             Token::Plus => {
@@ -52,10 +103,18 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 self.report(Error::UnexpectedSymbol("*", span));
                 Some((Statement::Error, span))
             }
+            Token::Star2 => {
+                self.report(Error::UnexpectedSymbol("**", span));
+                Some((Statement::Error, span))
+            }
             Token::Slash => {
                 self.report(Error::UnexpectedSymbol("/", span));
                 Some((Statement::Error, span))
             }
+            Token::Slash2 => {
+                self.report(Error::UnexpectedSymbol("//", span));
+                Some((Statement::Error, span))
+            }
             Token::Mod => {
                 self.report(Error::UnexpectedSymbol("%", span));
                 Some((Statement::Error, span))
@@ -68,6 +127,10 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 self.report(Error::UnexpectedSymbol("|", span));
                 Some((Statement::Error, span))
             }
+            Token::Pipe2 => {
+                self.report(Error::UnexpectedSymbol("||", span));
+                Some((Statement::Error, span))
+            }
             Token::Caret => {
                 self.report(Error::UnexpectedSymbol("^", span));
                 Some((Statement::Error, span))
@@ -116,14 +179,66 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 self.report(Error::UnexpectedSymbol("->", span));
                 Some((Statement::Error, span))
             }
+            Token::Pipeline => {
+                self.report(Error::UnexpectedSymbol("|>", span));
+                Some((Statement::Error, span))
+            }
             Token::Dot2 => {
                 self.report(Error::UnexpectedSymbol("..", span));
                 Some((Statement::Error, span))
             }
+            Token::Dot3 => {
+                self.report(Error::UnexpectedSymbol("...", span));
+                Some((Statement::Error, span))
+            }
+            Token::Question2 => {
+                self.report(Error::UnexpectedSymbol("??", span));
+                Some((Statement::Error, span))
+            }
+            Token::QuestionDot => {
+                self.report(Error::UnexpectedSymbol("?.", span));
+                Some((Statement::Error, span))
+            }
+            Token::QuestionArrow => {
+                self.report(Error::UnexpectedSymbol("?->", span));
+                Some((Statement::Error, span))
+            }
             Token::Assign => {
                 self.report(Error::UnexpectedSymbol("=", span));
                 Some((Statement::Error, span))
             }
+            Token::Question2Assign => {
+                self.report(Error::UnexpectedSymbol("??=", span));
+                Some((Statement::Error, span))
+            }
+            Token::Pipe2Assign => {
+                self.report(Error::UnexpectedSymbol("||=", span));
+                Some((Statement::Error, span))
+            }
+            Token::PlusAssign => {
+                self.report(Error::UnexpectedSymbol("+=", span));
+                Some((Statement::Error, span))
+            }
+            Token::MinusAssign => {
+                self.report(Error::UnexpectedSymbol("-=", span));
+                Some((Statement::Error, span))
+            }
+            Token::StarAssign => {
+                self.report(Error::UnexpectedSymbol("*=", span));
+                Some((Statement::Error, span))
+            }
+            Token::SlashAssign => {
+                self.report(Error::UnexpectedSymbol("/=", span));
+                Some((Statement::Error, span))
+            }
+            Token::ModAssign => {
+                self.report(Error::UnexpectedSymbol("%=", span));
+                Some((Statement::Error, span))
+            }
+            Token::Dot2Assign => {
+                self.report(Error::UnexpectedSymbol("..=", span));
+                Some((Statement::Error, span))
+            }
 
             // keyword operators
             Token::And => {
@@ -185,6 +300,23 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             // other
             Token::Ident(name) => Some(self.assign_or_call_statement(name, span)),
             Token::Attribute(name) => Some(self.attribute_statement(name, span)),
+            Token::DocComment(line) => {
+                let mut doc = vec![(*line, span)];
+                while let Some((Token::DocComment(_), _)) = self.look(0) {
+                    if let Some((Token::DocComment(line), span)) = self.next() {
+                        doc.push((*line, span));
+                    }
+                }
+                // The doc comment belongs to a following function definition, and is skipped like
+                // any other comment before anything else.
+                let (mut statement, span) = self.statement()?;
+                if let Statement::Func { doc: func_doc, .. }
+                | Statement::FieldFunc { doc: func_doc, .. } = &mut statement
+                {
+                    *func_doc = doc;
+                }
+                Some((statement, span))
+            }
             Token::Comment(_) | Token::Error(_) => {
                 // skip
                 self.statement()
@@ -193,9 +325,15 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
     }
 
     // var [name] = [expr]
+    // var [name], [name], ... = [expr], [expr], ...
+    // var [pattern] = [expr]
     fn var_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
         let (name, name_span) = match self.next() {
             Some((Token::Ident(name), span)) => (*name, span),
+            Some((Token::OpenBracket | Token::OpenBrace, _)) => {
+                self.move_prev();
+                return self.destructuring_var(start_span);
+            }
             Some((Token::Assign, assign_span)) => {
                 let span = TextSpan::new(start_span.end(), assign_span.start());
                 self.report(Error::MissingRequiredElement("<name>", span));
@@ -214,6 +352,12 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 return (Statement::Error, span);
             }
         };
+        if let Some((Token::Comma, _)) = self.look(0) {
+            return match self.parallel_names_and_exprs((name, name_span), start_span) {
+                Ok((names, exprs, span)) => (Statement::ParallelVar { names, exprs }, span),
+                Err(span) => (Statement::Error, span),
+            };
+        }
         if let Some((Token::Assign, _)) = self.look(0) {
             self.move_next();
         } else {
@@ -244,6 +388,78 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
         )
     }
 
+    fn destructuring_var(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        let (pattern, pattern_span) = self.pattern();
+        if let Some((Token::Assign, _)) = self.look(0) {
+            self.move_next();
+        } else {
+            let span = TextSpan::new(start_span.start(), pattern_span.end());
+            self.report(Error::MissingRequiredElement("= <expr>", span));
+            return (
+                Statement::DestructuringVar {
+                    pattern: (pattern, pattern_span),
+                    expr: (Expression::Error, TextSpan::at(pattern_span.end(), 0)),
+                },
+                span,
+            );
+        }
+        let (expr, expr_span) = match self.expression() {
+            Some((expr, span)) => (expr, span),
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("<expr>", span));
+                return (Statement::Error, span);
+            }
+        };
+        (
+            Statement::DestructuringVar {
+                pattern: (pattern, pattern_span),
+                expr: (expr, expr_span),
+            },
+            TextSpan::new(start_span.start(), expr_span.end()),
+        )
+    }
+
+    // global [name]
+    // global [name] = [expr]
+    fn global_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        if !matches!(self.look(0), Some((Token::Ident(_), _))) {
+            self.expected("<name>");
+            return (Statement::Error, start_span);
+        }
+        let Some((Token::Ident(name), name_span)) = self.next() else {
+            unreachable!("[BUG] The name is checked above.");
+        };
+        let (name, name_span) = (*name, name_span);
+        if let Some((Token::Assign, _)) = self.look(0) {
+            self.move_next();
+        } else {
+            let span = TextSpan::new(start_span.start(), name_span.end());
+            return (
+                Statement::Global {
+                    name: (name, name_span),
+                    expr: None,
+                },
+                span,
+            );
+        }
+        let (expr, expr_span) = match self.expression() {
+            Some((expr, span)) => (expr, span),
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("<expr>", span));
+                return (Statement::Error, span);
+            }
+        };
+        (
+            Statement::Global {
+                name: (name, name_span),
+                expr: Some((expr, expr_span)),
+            },
+            TextSpan::new(start_span.start(), expr_span.end()),
+        )
+    }
+
     // func [name]([args])
     //     [block]
     // end
@@ -266,7 +482,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                                     let (name, span) = unsafe { self.next_ident_unchecked() };
                                     fields.push((name, span));
                                 }
-                                _ => todo!("implement error recovery"),
+                                _ => {
+                                    self.expected("<name>");
+                                }
                             }
                         }
                         Some((token, span)) => {
@@ -276,7 +494,11 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                             });
                             break ((*name, name_span), Vec::new());
                         }
-                        _ => todo!("implement error recovery"),
+                        None => {
+                            let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                            self.report(Error::UnexpectedEof("(", span));
+                            return (Statement::Error, span);
+                        }
                     }
                 };
                 (name, fields, args)
@@ -287,7 +509,16 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 let (args, _) = self.func_def_args();
                 (("$dummy", name_span), vec![], args)
             }
-            _ => todo!("implement error recovery"),
+            next => {
+                if next.is_some() {
+                    self.move_prev();
+                }
+                let span = self.expected("<name>");
+                return (
+                    Statement::Error,
+                    TextSpan::new(start_span.start(), span.end()),
+                );
+            }
         };
         let (body, end_span) = self.block_until_end_token();
         if fields.is_empty() {
@@ -299,6 +530,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         captures: vec![],
                         block: body,
                     },
+                    doc: vec![],
                 },
                 TextSpan::new(start_span.start(), end_span.end()),
             )
@@ -312,6 +544,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         captures: vec![],
                         block: body,
                     },
+                    doc: vec![],
                 },
                 TextSpan::new(start_span.start(), end_span.end()),
             )
@@ -339,7 +572,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     (Expression::Error, span)
                 }
                 Some(_) => {
-                    todo!("implement error recovery");
+                    let span = self.expected("<expr>");
+                    self.synchronize_to(|token| matches!(token, Token::Then));
+                    (Expression::Error, span)
                 }
                 None => {
                     let expr_span = TextSpan::new(start_span.end(), self.eoi_span().end());
@@ -367,9 +602,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 });
                 self.move_next();
             }
-            Some(_) => {
-                todo!("implement error recovery");
-            }
+            Some(_) => self.expect_keyword("then", |token| matches!(token, Token::Then)),
             None => {
                 let err_span = TextSpan::new(cond_span.end(), self.eoi_span().end());
                 self.report(Error::UnexpectedEof("then", err_span));
@@ -458,7 +691,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         (Expression::Error, span)
                     }
                     Some(_) => {
-                        todo!("implement error recovery");
+                        let span = self.expected("<expr>");
+                        self.synchronize_to(|token| matches!(token, Token::Then));
+                        (Expression::Error, span)
                     }
                     None => {
                         let expr_span = TextSpan::new(start_span.end(), self.eoi_span().end());
@@ -486,9 +721,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     });
                     self.move_next();
                 }
-                Some(_) => {
-                    todo!("implement error recovery");
-                }
+                Some(_) => self.expect_keyword("then", |token| matches!(token, Token::Then)),
                 None => {
                     self.report(Error::UnexpectedEof("then", self.eoi_span()));
                     return (
@@ -583,7 +816,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 ("$dummy", span)
             }
             Some(_) => {
-                todo!("implement error recovery");
+                let span = self.expected("<name>");
+                self.synchronize_to(|token| matches!(token, Token::In));
+                ("$dummy", span)
             }
             None => {
                 let err_span = TextSpan::new(start_span.end(), self.eoi_span().end());
@@ -594,13 +829,19 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 );
             }
         };
+        if let Some((Token::Assign, _)) = self.look(0) {
+            self.move_next();
+            return self.numeric_for_statement(start_span, (name, name_span));
+        }
         let (expr, expr_span) = match self.look(0) {
             Some((Token::In, _)) => {
                 self.move_next();
                 match self.expression() {
                     Some(e) => e,
                     None => {
-                        todo!("implement error recovery")
+                        let span = self.expected("<expr>");
+                        self.synchronize_to(|token| matches!(token, Token::Do));
+                        (Expression::Error, span)
                     }
                 }
             }
@@ -616,7 +857,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 )
             }
             Some(_) => {
-                todo!("implement error recovery");
+                let span = self.expected("in <expr>");
+                self.synchronize_to(|token| matches!(token, Token::Do));
+                (Expression::Error, span)
             }
             None => {
                 let err_span = TextSpan::new(name_span.end(), self.eoi_span().end());
@@ -631,9 +874,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
             Some((Token::Do, _)) => {
                 self.move_next();
             }
-            Some(_) => {
-                todo!("implement error recovery");
-            }
+            Some(_) => self.expect_keyword("do", |token| matches!(token, Token::Do)),
             None => {
                 let err_span = TextSpan::new(expr_span.end(), self.eoi_span().end());
                 self.report(Error::UnexpectedEof("do", err_span));
@@ -658,6 +899,79 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
         )
     }
 
+    // for [name] = [expr], [expr], [expr] do
+    //     [block]
+    // end
+    // (the second `, [expr]`, the step, is optional)
+    fn numeric_for_statement(
+        &mut self,
+        start_span: TextSpan,
+        value: (&'src str, TextSpan),
+    ) -> (Statement<'src>, TextSpan) {
+        debug_assert!(
+            matches!(self.look(-1), Some((Token::Assign, _))),
+            "self.numeric_for_statement() must be called after `=`. self.look(-1) is {:?}",
+            self.look(-1)
+        );
+
+        let start = self.numeric_for_bound();
+        let stop = match self.look(0) {
+            Some((Token::Comma, _)) => {
+                self.move_next();
+                self.numeric_for_bound()
+            }
+            _ => {
+                let span = self.expected(", <expr>");
+                self.synchronize_to(|token| matches!(token, Token::Do));
+                (Expression::Error, span)
+            }
+        };
+        let step = match self.look(0) {
+            Some((Token::Comma, _)) => {
+                self.move_next();
+                Some(self.numeric_for_bound())
+            }
+            _ => None,
+        };
+        let Some(_) = self.look(0) else {
+            let err_span = TextSpan::new(start_span.start(), self.eoi_span().end());
+            self.report(Error::UnexpectedEof("do", err_span));
+            return (
+                Statement::NumericFor {
+                    value,
+                    start,
+                    stop,
+                    step,
+                    body: Block(vec![]),
+                },
+                err_span,
+            );
+        };
+        self.expect_keyword("do", |token| matches!(token, Token::Do));
+        let (body, end_span) = self.block_until_end_token();
+        (
+            Statement::NumericFor {
+                value,
+                start,
+                stop,
+                step,
+                body,
+            },
+            TextSpan::new(start_span.start(), end_span.end()),
+        )
+    }
+
+    fn numeric_for_bound(&mut self) -> (Expression<'src>, TextSpan) {
+        match self.expression() {
+            Some(bound) => bound,
+            None => {
+                let span = self.expected("<expr>");
+                self.synchronize_to(|token| matches!(token, Token::Comma | Token::Do));
+                (Expression::Error, span)
+            }
+        }
+    }
+
     // while [expr] do
     //     [block]
     // end
@@ -681,9 +995,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 });
                 self.move_next();
             }
-            Some(_) => {
-                todo!("implement error recovery");
-            }
+            Some(_) => self.expect_keyword("do", |token| matches!(token, Token::Do)),
             None => {
                 let span = TextSpan::new(start_span.start(), self.eoi_span().end());
                 self.report(Error::UnexpectedEof("do", span));
@@ -717,18 +1029,382 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
         )
     }
 
+    // match [expr]
+    // case [pattern] if [expr] then
+    //     [block]
+    // case [pattern] then
+    //     [block]
+    // end
+    fn match_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        let Some(expr) = self.expression() else {
+            let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+            self.report(Error::UnexpectedEof("<expr>", span));
+            return (Statement::Error, span);
+        };
+        let mut arms = Vec::new();
+        let end_span = loop {
+            match self.next() {
+                Some((Token::Case, _)) => {}
+                Some((Token::End, end_span)) => break end_span,
+                Some((token, span)) => {
+                    let found = (token.to_string(), span);
+                    self.report(Error::ExpectedFound {
+                        expected: "case",
+                        found,
+                    });
+                    self.synchronize_to(|token| matches!(token, Token::Case | Token::End));
+                    continue;
+                }
+                None => {
+                    let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                    self.report(Error::UnexpectedEof("end", span));
+                    return (Statement::Match { expr, arms }, span);
+                }
+            }
+            let pattern = self.pattern();
+            let guard = if let Some((Token::If, _)) = self.look(0) {
+                self.move_next();
+                match self.expression() {
+                    Some(guard) => Some(guard),
+                    None => Some((Expression::Error, self.expected("<expr>"))),
+                }
+            } else {
+                None
+            };
+            self.expect_keyword("then", |token| matches!(token, Token::Then));
+            let mut stmts = Vec::new();
+            while !matches!(self.look(0), Some((Token::Case | Token::End, _)) | None) {
+                let Some(stmt) = self.statement() else {
+                    break;
+                };
+                stmts.push(stmt);
+            }
+            arms.push((pattern, guard, Block(stmts)));
+        };
+        (
+            Statement::Match { expr, arms },
+            TextSpan::new(start_span.start(), end_span.end()),
+        )
+    }
+
+    // try
+    //     [block]
+    // catch [name]
+    //     [block]
+    // end
+    fn try_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        let mut statements = Vec::new();
+        loop {
+            match self.next() {
+                Some((Token::Catch, _)) => break,
+                Some((Token::End, end_span)) => {
+                    self.report(Error::ExpectedFound {
+                        expected: "catch",
+                        found: ("end".to_string(), end_span),
+                    });
+                    return (
+                        Statement::Try {
+                            body: Block(statements),
+                            name: ("$dummy", end_span),
+                            handler: Block(vec![]),
+                        },
+                        TextSpan::new(start_span.start(), end_span.end()),
+                    );
+                }
+                Some((token, span)) => {
+                    if let Some(statement) = self.statement_with(token, span) {
+                        statements.push(statement);
+                        continue;
+                    }
+                    let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                    self.report(Error::UnexpectedEof("catch", span));
+                    return (Statement::Error, span);
+                }
+                None => {
+                    let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                    self.report(Error::UnexpectedEof("catch", span));
+                    return (Statement::Error, span);
+                }
+            }
+        }
+        let body = Block(statements);
+        let name = match self.look(0) {
+            Some((Token::Ident(_), _)) => {
+                // SAFETY: `self.look(0)` is `Token::Ident`.
+                unsafe { self.next_ident_unchecked() }
+            }
+            Some(_) => ("$dummy", self.expected("<name>")),
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("<name>", span));
+                return (Statement::Error, span);
+            }
+        };
+        let (handler, end_span) = self.block_until_end_token();
+        (
+            Statement::Try {
+                body,
+                name,
+                handler,
+            },
+            TextSpan::new(start_span.start(), end_span.end()),
+        )
+    }
+
+    // with [expr] as [name] do
+    //     [block]
+    // end
+    fn with_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        let Some((expr, expr_span)) = self.expression() else {
+            let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+            self.report(Error::UnexpectedEof("<expr>", span));
+            return (Statement::Error, span);
+        };
+        match self.next() {
+            Some((Token::As, _)) => {}
+            Some(_) => {
+                self.move_prev();
+                self.expect_keyword("as", |token| matches!(token, Token::As));
+            }
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("as", span));
+                return (Statement::Error, span);
+            }
+        }
+        let (name, name_span) = match self.look(0) {
+            Some((Token::Ident(_), _)) => {
+                // SAFETY: `self.look(0)` is `Token::Ident`.
+                unsafe { self.next_ident_unchecked() }
+            }
+            Some(_) => ("$dummy", self.expected("<name>")),
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("<name>", span));
+                return (Statement::Error, span);
+            }
+        };
+        match self.next() {
+            Some((Token::Do, _)) => {}
+            Some(_) => {
+                self.move_prev();
+                self.expect_keyword("do", |token| matches!(token, Token::Do));
+            }
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("do", span));
+                return (
+                    Statement::With {
+                        expr: (expr, expr_span),
+                        name: (name, name_span),
+                        body: Block(vec![]),
+                    },
+                    span,
+                );
+            }
+        }
+        let (body, end_span) = self.block_until_end_token();
+        (
+            Statement::With {
+                expr: (expr, expr_span),
+                name: (name, name_span),
+                body,
+            },
+            TextSpan::new(start_span.start(), end_span.end()),
+        )
+    }
+
+    // class [name] < [expr]
+    //     func [name]([args])
+    //         [block]
+    //     end
+    //     ...
+    // end
+    fn class_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        let name = match self.look(0) {
+            Some((Token::Ident(_), _)) => {
+                // SAFETY: `self.look(0)` is `Token::Ident`.
+                unsafe { self.next_ident_unchecked() }
+            }
+            Some(_) => ("$dummy", self.expected("<name>")),
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("<name>", span));
+                return (Statement::Error, span);
+            }
+        };
+        let parent = match self.look(0) {
+            Some((Token::Less, _)) => {
+                self.move_next();
+                match self.expression() {
+                    Some(expr) => Some(expr),
+                    None => Some((Expression::Error, self.expected("<expr>"))),
+                }
+            }
+            _ => None,
+        };
+        let mut methods = Vec::new();
+        let end_span = loop {
+            match self.next() {
+                Some((Token::End, span)) => break span,
+                Some((Token::Func, _)) => {
+                    let Some((Token::Ident(_), _)) = self.look(0) else {
+                        self.expected("<name>");
+                        self.synchronize_to(|token| matches!(token, Token::Func | Token::End));
+                        continue;
+                    };
+                    // SAFETY: `self.look(0)` is `Token::Ident`.
+                    let name = unsafe { self.next_ident_unchecked() };
+                    let args = match self.look(0) {
+                        Some((Token::OpenParen, _)) => {
+                            self.move_next();
+                            self.func_def_args().0
+                        }
+                        _ => {
+                            self.expected("(");
+                            Vec::new()
+                        }
+                    };
+                    if name.0 == "init"
+                        && !matches!(
+                            args.first(),
+                            Some((FunctArgAnnotation::None, Pattern::Ident(_), _, None))
+                        )
+                    {
+                        self.report(Error::InvalidStatement {
+                            info: ("init".to_string(), name.1),
+                            reason: "`init` must take `self` as its first parameter".to_string(),
+                        });
+                    }
+                    let (body, _) = self.block_until_end_token();
+                    let body = Chunk {
+                        captures: vec![],
+                        block: body,
+                    };
+                    methods.push((name, FunctionObject { args, body }));
+                }
+                Some((Token::Comment(_) | Token::DocComment(_), _)) => {}
+                Some((token, span)) => {
+                    self.report(Error::ExpectedFound {
+                        expected: "func",
+                        found: (token.to_string(), span),
+                    });
+                    self.synchronize_to(|token| matches!(token, Token::Func | Token::End));
+                }
+                None => {
+                    let eoi_span = self.eoi_span();
+                    self.report(Error::UnexpectedEof("end", eoi_span));
+                    break eoi_span;
+                }
+            }
+        };
+        (
+            Statement::Class {
+                name,
+                parent,
+                methods,
+            },
+            TextSpan::new(start_span.start(), end_span.end()),
+        )
+    }
+
+    // assert([expr])
+    // assert([expr], [expr])
+    fn assert_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        match self.next() {
+            Some((Token::OpenParen, _)) => {}
+            Some(_) => {
+                self.move_prev();
+                let span = self.expected("(");
+                return (
+                    Statement::Error,
+                    TextSpan::new(start_span.start(), span.end()),
+                );
+            }
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("(", span));
+                return (Statement::Error, span);
+            }
+        }
+        let (args, close_span) = self.func_call_args();
+        let span = TextSpan::new(start_span.start(), close_span.end());
+        let mut args = args.into_iter();
+        let Some(cond) = args.next() else {
+            self.report(Error::MissingRequiredElement("<expr>", span));
+            return (Statement::Error, span);
+        };
+        let message = args.next();
+        if let Some((_, extra_span)) = args.next() {
+            self.report(Error::InvalidStatement {
+                info: ("assert".to_string(), extra_span),
+                reason: "`assert` takes at most 2 arguments".to_string(),
+            });
+        }
+        (Statement::Assert { cond, message }, span)
+    }
+
     // return
     // return [expr]
+    // return [expr], [expr], ...
     fn return_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
-        if let Some((expr, expr_span)) = self.expression() {
-            (
+        let Some((expr, expr_span)) = self.expression() else {
+            return (Statement::Return { value: None }, start_span);
+        };
+        if !matches!(self.look(0), Some((Token::Comma, _))) {
+            return (
                 Statement::Return {
                     value: Some((expr, expr_span)),
                 },
                 TextSpan::new(start_span.start(), expr_span.end()),
-            )
-        } else {
-            (Statement::Return { value: None }, start_span)
+            );
+        }
+        let mut values = vec![(expr, expr_span)];
+        while let Some((Token::Comma, _)) = self.look(0) {
+            self.move_next();
+            match self.expression() {
+                Some(value) => values.push(value),
+                None => {
+                    values.push((Expression::Error, self.expected("<expr>")));
+                    break;
+                }
+            }
+        }
+        // SAFETY: `values` has at least one element, the first value.
+        let end = unsafe { values.last().unwrap_unchecked() }.1.end();
+        (
+            Statement::ReturnMany { values },
+            TextSpan::new(start_span.start(), end),
+        )
+    }
+
+    // yield
+    // yield [expr]
+    fn yield_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        match self.expression() {
+            Some((expr, expr_span)) => (
+                Statement::Yield {
+                    value: Some((expr, expr_span)),
+                },
+                TextSpan::new(start_span.start(), expr_span.end()),
+            ),
+            None => (Statement::Yield { value: None }, start_span),
+        }
+    }
+
+    // import [string]
+    fn import_statement(&mut self, start_span: TextSpan) -> (Statement<'src>, TextSpan) {
+        match self.look(0) {
+            Some((Token::String(path), path_span)) => {
+                let path = (path.clone(), *path_span);
+                self.move_next();
+                let span = TextSpan::new(start_span.start(), path.1.end());
+                (Statement::Import { path }, span)
+            }
+            _ => {
+                self.expected("<string>");
+                (Statement::Error, start_span)
+            }
         }
     }
 
@@ -745,8 +1421,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
     ) -> (Statement<'src>, TextSpan) {
         if let Some((Token::Assign, _)) = self.look(0) {
             self.move_next();
-            let Some((expr, expr_span)) = self.expression() else {
-                todo!("implement error recovery");
+            let (expr, expr_span) = match self.expression() {
+                Some(expr) => expr,
+                None => (Expression::Error, self.expected("<expr>")),
             };
             return (
                 Statement::Assign {
@@ -756,6 +1433,17 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 TextSpan::new(ident_span.start(), expr_span.end()),
             );
         }
+        if let Some(op) = self.conditional_assign_op() {
+            let target = (Expression::Local(ident, ident_span), ident_span);
+            return self.make_conditional_assign_statement(op, target);
+        }
+        if let Some(op) = self.compound_assign_op() {
+            let target = (Expression::Local(ident, ident_span), ident_span);
+            return self.make_compound_assign_statement(op, target);
+        }
+        if let Some((Token::Comma, _)) = self.look(0) {
+            return self.parallel_assign_statement(ident, ident_span);
+        }
         // NOTE: From here, we have to evaluate the expression. (since we already checked that next token is not Assign, which means it's not `ident = [expr]`).
         //       To call `self.expression()` we have to move the cursor back to the ident.
         self.move_prev();
@@ -765,6 +1453,102 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
         self.expr_to_statement(base_expr, base_expr_span)
     }
 
+    // ident, ident, ... = [expr], [expr], ...
+    fn parallel_assign_statement(
+        &mut self,
+        ident: &'src str,
+        ident_span: TextSpan,
+    ) -> (Statement<'src>, TextSpan) {
+        match self.parallel_names_and_exprs((ident, ident_span), ident_span) {
+            Ok((names, exprs, span)) => (Statement::ParallelAssign { names, exprs }, span),
+            Err(span) => (Statement::Error, span),
+        }
+    }
+
+    // , ident, ... = [expr], [expr], ...
+    // The first name has been read already. The last expression may be a call with fewer
+    // expressions than names, whose results go to the rest of the names.
+    #[allow(clippy::type_complexity)]
+    fn parallel_names_and_exprs(
+        &mut self,
+        first: (&'src str, TextSpan),
+        start_span: TextSpan,
+    ) -> Result<
+        (
+            Vec<(&'src str, TextSpan)>,
+            Vec<(Expression<'src>, TextSpan)>,
+            TextSpan,
+        ),
+        TextSpan,
+    > {
+        let mut names = vec![first];
+        while let Some((Token::Comma, _)) = self.look(0) {
+            self.move_next();
+            match self.look(0) {
+                Some((Token::Ident(_), _)) => {
+                    // SAFETY: `self.look(0)` is `Token::Ident`.
+                    names.push(unsafe { self.next_ident_unchecked() });
+                }
+                Some(_) => {
+                    let span = self.expected("<name>");
+                    return Err(TextSpan::new(start_span.start(), span.end()));
+                }
+                None => {
+                    let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                    self.report(Error::UnexpectedEof("<name>", span));
+                    return Err(span);
+                }
+            }
+        }
+        match self.next() {
+            Some((Token::Assign, _)) => {}
+            Some(_) => {
+                self.move_prev();
+                let span = self.expected("=");
+                return Err(TextSpan::new(start_span.start(), span.end()));
+            }
+            None => {
+                let span = TextSpan::new(start_span.start(), self.eoi_span().end());
+                self.report(Error::UnexpectedEof("=", span));
+                return Err(span);
+            }
+        }
+        let mut exprs = Vec::with_capacity(names.len());
+        loop {
+            let Some(expr) = self.expression() else {
+                exprs.push((Expression::Error, self.expected("<expr>")));
+                break;
+            };
+            exprs.push(expr);
+            if let Some((Token::Comma, _)) = self.look(0) {
+                self.move_next();
+            } else {
+                break;
+            }
+        }
+        // SAFETY: `exprs` has at least one element because the loop above pushes before breaking.
+        let (last, last_span) = unsafe { exprs.last().unwrap_unchecked() };
+        let span = TextSpan::new(start_span.start(), last_span.end());
+        let spreads = exprs.len() < names.len()
+            && matches!(
+                last,
+                Expression::Call { .. } | Expression::MethodCall { .. }
+            );
+        // A missing value has been reported already.
+        let missing = matches!(last, Expression::Error);
+        if names.len() != exprs.len() && !spreads && !missing {
+            self.report(Error::InvalidStatement {
+                info: ("=".to_string(), span),
+                reason: format!(
+                    "Expected {} values on the right-hand side, found {}",
+                    names.len(),
+                    exprs.len()
+                ),
+            });
+        }
+        Ok((names, exprs, span))
+    }
+
     fn expr_to_statement(
         &mut self,
         base_expr: Expression<'src>,
@@ -804,7 +1588,13 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 },
                 base_expr_span,
             ),
-            _ => todo!("implement error recovery"),
+            _ => {
+                self.report(Error::InvalidStatement {
+                    info: ("<expr>".to_string(), base_expr_span),
+                    reason: "Expected a call or an assignment, found an expression".to_string(),
+                });
+                (Statement::Error, base_expr_span)
+            }
         }
     }
 
@@ -815,8 +1605,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
     ) -> (Statement<'src>, TextSpan) {
         if let Some((Token::Assign, _)) = self.look(0) {
             self.move_next();
-            let Some((expr, expr_span)) = self.expression() else {
-                todo!("implement error recovery");
+            let (expr, expr_span) = match self.expression() {
+                Some(expr) => expr,
+                None => (Expression::Error, self.expected("<expr>")),
             };
             (
                 Statement::FieldAssign {
@@ -826,16 +1617,151 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                 },
                 TextSpan::new(table_span.start(), expr_span.end()),
             )
+        } else if let Some(op) = self.conditional_assign_op() {
+            let target_span = TextSpan::new(table_span.start(), field_span.end());
+            let target = Expression::IndexAccess {
+                expr: (Box::new(table), table_span),
+                accessor: (Box::new(field), field_span),
+            };
+            self.make_conditional_assign_statement(op, (target, target_span))
+        } else if let Some(op) = self.compound_assign_op() {
+            let target_span = TextSpan::new(table_span.start(), field_span.end());
+            let target = Expression::IndexAccess {
+                expr: (Box::new(table), table_span),
+                accessor: (Box::new(field), field_span),
+            };
+            self.make_compound_assign_statement(op, (target, target_span))
         } else {
-            todo!("implement error recovery");
+            let span = TextSpan::new(table_span.start(), field_span.end());
+            self.report(Error::InvalidStatement {
+                info: ("<expr>".to_string(), span),
+                reason: "Expected a call or an assignment, found an expression".to_string(),
+            });
+            (Statement::Error, span)
         }
     }
 
+    fn conditional_assign_op(&mut self) -> Option<ConditionalAssignOp> {
+        let op = match self.look(0) {
+            Some((Token::Question2Assign, _)) => ConditionalAssignOp::NilCoalesce,
+            Some((Token::Pipe2Assign, _)) => ConditionalAssignOp::Or,
+            _ => return None,
+        };
+        self.move_next();
+        Some(op)
+    }
+
+    fn make_conditional_assign_statement(
+        &mut self,
+        op: ConditionalAssignOp,
+        (target, target_span): (Expression<'src>, TextSpan),
+    ) -> (Statement<'src>, TextSpan) {
+        let (expr, expr_span) = match self.expression() {
+            Some(expr) => expr,
+            None => (Expression::Error, self.expected("<expr>")),
+        };
+        (
+            Statement::ConditionalAssign {
+                op,
+                target: (target, target_span),
+                expr: (expr, expr_span),
+            },
+            TextSpan::new(target_span.start(), expr_span.end()),
+        )
+    }
+
+    fn compound_assign_op(&mut self) -> Option<CompoundAssignOp> {
+        let op = match self.look(0) {
+            Some((Token::PlusAssign, _)) => CompoundAssignOp::Add,
+            Some((Token::MinusAssign, _)) => CompoundAssignOp::Sub,
+            Some((Token::StarAssign, _)) => CompoundAssignOp::Mul,
+            Some((Token::SlashAssign, _)) => CompoundAssignOp::Div,
+            Some((Token::ModAssign, _)) => CompoundAssignOp::Mod,
+            Some((Token::Dot2Assign, _)) => CompoundAssignOp::Concat,
+            _ => return None,
+        };
+        self.move_next();
+        Some(op)
+    }
+
+    fn make_compound_assign_statement(
+        &mut self,
+        op: CompoundAssignOp,
+        (target, target_span): (Expression<'src>, TextSpan),
+    ) -> (Statement<'src>, TextSpan) {
+        let (expr, expr_span) = match self.expression() {
+            Some(expr) => expr,
+            None => (Expression::Error, self.expected("<expr>")),
+        };
+        (
+            Statement::CompoundAssign {
+                op,
+                target: (target, target_span),
+                expr: (expr, expr_span),
+            },
+            TextSpan::new(target_span.start(), expr_span.end()),
+        )
+    }
+
+    // @name
+    // @name(<name>, ...)
     fn attribute_statement(
         &mut self,
-        _name: &'src str,
-        _start_span: TextSpan,
+        name: &'src str,
+        start_span: TextSpan,
     ) -> (Statement<'src>, TextSpan) {
-        todo!()
+        // The argument list must follow the attribute name without any space.
+        let has_args = matches!(
+            self.look(0),
+            Some((Token::OpenParen, span)) if span.start() == start_span.end()
+        );
+        if !has_args {
+            return (
+                Statement::Attribute {
+                    name: (name, start_span),
+                    args: None,
+                },
+                start_span,
+            );
+        }
+        self.move_next();
+
+        let mut args = Vec::new();
+        let close_span = loop {
+            match self.look(0) {
+                Some((Token::Ident(_), _)) => {
+                    // SAFETY: `self.look(0)` is `Token::Ident`.
+                    args.push(unsafe { self.next_ident_unchecked() });
+                }
+                Some((Token::CloseParen, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected("<name>");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
+            }
+            match self.look(0) {
+                Some((Token::Comma, _)) => self.move_next(),
+                Some((Token::CloseParen, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected(")");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
+            }
+        };
+        (
+            Statement::Attribute {
+                name: (name, start_span),
+                args: Some(args),
+            },
+            TextSpan::new(start_span.start(), close_span.end()),
+        )
     }
 }
@@ -1,14 +1,30 @@
 use super::*;
 
 impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
-    pub fn func_def_args(&mut self) -> (Vec<(FunctArgAnnotation, &'src str, TextSpan)>, TextSpan) {
+    #[allow(clippy::type_complexity)]
+    pub fn func_def_args(
+        &mut self,
+    ) -> (
+        Vec<(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )>,
+        TextSpan,
+    ) {
         debug_assert!(
             matches!(self.look(-1), Some((Token::OpenParen, _))),
             "self.func_def_args() must be called after `(`. self.look(-1) is {:?}",
             self.look(-1)
         );
 
-        let mut args = Vec::<(FunctArgAnnotation, &'src str, TextSpan)>::new();
+        let mut args = Vec::<(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )>::new();
         let mut annotation = None;
         let close_span = loop {
             let Some((peek, peek_span)) = self.look(0) else {
@@ -21,7 +37,17 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     // SAFETY: `peek` is self.look(0) and it is `Token::Ident`.
                     let (name, name_span) = unsafe { self.next_ident_unchecked() };
                     let annotation = annotation.take().unwrap_or(FunctArgAnnotation::None);
-                    args.push((annotation, name, name_span));
+                    let default = self.func_def_arg_default();
+                    args.push((annotation, Pattern::Ident(name), name_span, default));
+                }
+                Token::OpenBrace => {
+                    let open_span = *peek_span;
+                    self.move_next();
+                    let (fields, close_span) = self.table_pattern_fields();
+                    let annotation = annotation.take().unwrap_or(FunctArgAnnotation::None);
+                    let span = TextSpan::new(open_span.start(), close_span.end());
+                    let default = self.func_def_arg_default();
+                    args.push((annotation, Pattern::Table(fields), span, default));
                 }
                 tok @ (Token::In | Token::Ref) => {
                     if annotation.is_some() {
@@ -39,12 +65,45 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     self.move_next();
                     continue;
                 }
+                Token::Dot3 => {
+                    if annotation.take().is_some() {
+                        self.report(Error::ExpectedFound {
+                            expected: "<name>",
+                            found: (peek.to_string(), *peek_span),
+                        });
+                    }
+                    self.move_next();
+                    let Some((Token::Ident(_), _)) = self.look(0) else {
+                        let span = self.expected("<name>");
+                        break self
+                            .recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                    };
+                    // SAFETY: `self.look(0)` is `Token::Ident`.
+                    let (name, name_span) = unsafe { self.next_ident_unchecked() };
+                    args.push((
+                        FunctArgAnnotation::Rest,
+                        Pattern::Ident(name),
+                        name_span,
+                        None,
+                    ));
+                    // The rest parameter takes all the arguments left, so nothing may follow it.
+                    if let Some((Token::CloseParen, span)) = self.look(0) {
+                        let close_span = *span;
+                        self.move_next();
+                        break close_span;
+                    }
+                    let span = self.expected(")");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
                 Token::CloseParen => {
                     let close_span = *peek_span;
                     self.move_next();
                     break close_span;
                 }
-                _ => todo!("implement error recovery"),
+                _ => {
+                    let span = self.expected("<name>");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
             }
             match self.look(0) {
                 Some((Token::Comma, _)) => {
@@ -55,12 +114,329 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     self.move_next();
                     break close_span;
                 }
-                _ => todo!("implement error recovery"),
+                _ => {
+                    let span = self.expected(")");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
             }
         };
         (args, close_span)
     }
 
+    // (= <expr>)?
+    fn func_def_arg_default(&mut self) -> Option<(Expression<'src>, TextSpan)> {
+        let Some((Token::Assign, _)) = self.look(0) else {
+            return None;
+        };
+        self.move_next();
+        match self.expression() {
+            Some(expr) => Some(expr),
+            None => Some((Expression::Error, self.expected("<expr>"))),
+        }
+    }
+
+    /// Parses `a, {b, c}, ...d |` of the lambda expression `|a, {b, c}, ...d| <expr>`. After the
+    /// `||` of a lambda without arguments, `|| <expr>`, there is nothing to parse.
+    #[allow(clippy::type_complexity)]
+    pub fn lambda_args(
+        &mut self,
+    ) -> (
+        Vec<(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )>,
+        TextSpan,
+    ) {
+        if let Some((Token::Pipe2, span)) = self.look(-1) {
+            return (Vec::new(), *span);
+        }
+        debug_assert!(
+            matches!(self.look(-1), Some((Token::Pipe, _))),
+            "self.lambda_args() must be called after `|` or `||`. self.look(-1) is {:?}",
+            self.look(-1)
+        );
+
+        let mut args = Vec::new();
+        let close_span = loop {
+            let Some((peek, peek_span)) = self.look(0) else {
+                let eoi_span = self.eoi_span();
+                self.report(Error::UnexpectedEof("|", eoi_span));
+                break eoi_span;
+            };
+            match peek {
+                Token::Ident(_) => {
+                    // SAFETY: `peek` is self.look(0) and it is `Token::Ident`.
+                    let (name, name_span) = unsafe { self.next_ident_unchecked() };
+                    args.push((
+                        FunctArgAnnotation::None,
+                        Pattern::Ident(name),
+                        name_span,
+                        None,
+                    ));
+                }
+                Token::OpenBrace => {
+                    let open_span = *peek_span;
+                    self.move_next();
+                    let (fields, close_span) = self.table_pattern_fields();
+                    let span = TextSpan::new(open_span.start(), close_span.end());
+                    args.push((FunctArgAnnotation::None, Pattern::Table(fields), span, None));
+                }
+                Token::Dot3 => {
+                    self.move_next();
+                    let Some((Token::Ident(_), _)) = self.look(0) else {
+                        let span = self.expected("<name>");
+                        break self.recover_to_close(|token| matches!(token, Token::Pipe), span);
+                    };
+                    // SAFETY: `self.look(0)` is `Token::Ident`.
+                    let (name, name_span) = unsafe { self.next_ident_unchecked() };
+                    args.push((
+                        FunctArgAnnotation::Rest,
+                        Pattern::Ident(name),
+                        name_span,
+                        None,
+                    ));
+                    if let Some((Token::Pipe, span)) = self.look(0) {
+                        let close_span = *span;
+                        self.move_next();
+                        break close_span;
+                    }
+                    let span = self.expected("|");
+                    break self.recover_to_close(|token| matches!(token, Token::Pipe), span);
+                }
+                Token::Pipe => {
+                    let close_span = *peek_span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected("<name>");
+                    break self.recover_to_close(|token| matches!(token, Token::Pipe), span);
+                }
+            }
+            match self.look(0) {
+                Some((Token::Comma, _)) => {
+                    self.move_next();
+                }
+                Some((Token::Pipe, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected("|");
+                    break self.recover_to_close(|token| matches!(token, Token::Pipe), span);
+                }
+            }
+        };
+        (args, close_span)
+    }
+
+    /// Parses `a, b, c }` of the table pattern `{a, b, c}`.
+    pub fn pattern(&mut self) -> (Pattern<'src>, TextSpan) {
+        let Some((token, span)) = self.next() else {
+            let eoi_span = self.eoi_span();
+            self.report(Error::UnexpectedEof("<pattern>", eoi_span));
+            return (Pattern::Ident("_"), eoi_span);
+        };
+        let pattern = match token {
+            Token::Int(x) => Pattern::Literal(Primitive::Int(*x)),
+            Token::Float(x) => Pattern::Literal(Primitive::Float(*x)),
+            Token::String(x) => Pattern::Literal(Primitive::String(x.clone())),
+            Token::Bool(x) => Pattern::Literal(Primitive::Bool(*x)),
+            Token::Nil => Pattern::Literal(Primitive::Nil),
+            Token::Minus => {
+                let pattern = match self.next() {
+                    Some((Token::Int(x), _)) => Pattern::Literal(Primitive::Int(-x)),
+                    Some((Token::Float(x), _)) => Pattern::Literal(Primitive::Float(-x)),
+                    next => {
+                        if next.is_some() {
+                            self.move_prev();
+                        }
+                        let span = self.expected("<number>");
+                        return (Pattern::Ident("_"), span);
+                    }
+                };
+                let span = TextSpan::new(span.start(), self.look(-1).unwrap().1.end());
+                return (pattern, span);
+            }
+            Token::Ident(_) => {
+                self.move_prev();
+                // SAFETY: `token` is `Token::Ident`.
+                let (name, _) = unsafe { self.next_ident_unchecked() };
+                Pattern::Ident(name)
+            }
+            Token::OpenBrace => {
+                let (fields, close_span) = self.table_pattern_fields();
+                return (
+                    Pattern::Table(fields),
+                    TextSpan::new(span.start(), close_span.end()),
+                );
+            }
+            Token::OpenBracket => {
+                let (elements, rest, close_span) = self.array_pattern_elements();
+                return (
+                    Pattern::Array { elements, rest },
+                    TextSpan::new(span.start(), close_span.end()),
+                );
+            }
+            _ => {
+                self.move_prev();
+                let span = self.expected("<pattern>");
+                return (Pattern::Ident("_"), span);
+            }
+        };
+        (pattern, span)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn table_pattern_fields(
+        &mut self,
+    ) -> (
+        Vec<((&'src str, TextSpan), (Pattern<'src>, TextSpan))>,
+        TextSpan,
+    ) {
+        debug_assert!(
+            matches!(self.look(-1), Some((Token::OpenBrace, _))),
+            "self.table_pattern_fields() must be called after `{{`. self.look(-1) is {:?}",
+            self.look(-1)
+        );
+
+        let mut fields = Vec::new();
+        let close_span = loop {
+            match self.look(0) {
+                Some((Token::Ident(_), _)) => {
+                    // SAFETY: self.look(0) is `Token::Ident`.
+                    let (key, key_span) = unsafe { self.next_ident_unchecked() };
+                    let pattern = if let Some((Token::Colon, _)) = self.look(0) {
+                        self.move_next();
+                        self.pattern()
+                    } else {
+                        (Pattern::Ident(key), key_span)
+                    };
+                    fields.push(((key, key_span), pattern));
+                }
+                Some((Token::CloseBrace, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                Some(_) => {
+                    let span = self.expected("<name>");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseBrace), span);
+                }
+                None => {
+                    let eoi_span = self.eoi_span();
+                    self.report(Error::UnexpectedEof("}", eoi_span));
+                    break eoi_span;
+                }
+            }
+            match self.look(0) {
+                Some((Token::Comma, _)) => {
+                    self.move_next();
+                }
+                Some((Token::CloseBrace, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected("}");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseBrace), span);
+                }
+            }
+        };
+        (fields, close_span)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn array_pattern_elements(
+        &mut self,
+    ) -> (
+        Vec<(Pattern<'src>, TextSpan)>,
+        Option<(&'src str, TextSpan)>,
+        TextSpan,
+    ) {
+        debug_assert!(
+            matches!(self.look(-1), Some((Token::OpenBracket, _))),
+            "self.array_pattern_elements() must be called after `[`. self.look(-1) is {:?}",
+            self.look(-1)
+        );
+
+        let mut elements = Vec::new();
+        let mut rest = None;
+        let close_span = loop {
+            match self.look(0) {
+                Some((Token::CloseBracket, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                Some((Token::Dot3, _)) => {
+                    self.move_next();
+                    let Some((Token::Ident(_), _)) = self.look(0) else {
+                        let span = self.expected("<name>");
+                        break self
+                            .recover_to_close(|token| matches!(token, Token::CloseBracket), span);
+                    };
+                    // SAFETY: self.look(0) is `Token::Ident`.
+                    rest = Some(unsafe { self.next_ident_unchecked() });
+                    // The rest is the last element.
+                    if let Some((Token::CloseBracket, span)) = self.look(0) {
+                        let close_span = *span;
+                        self.move_next();
+                        break close_span;
+                    }
+                    let span = self.expected("]");
+                    break self
+                        .recover_to_close(|token| matches!(token, Token::CloseBracket), span);
+                }
+                Some(_) => elements.push(self.pattern()),
+                None => {
+                    let eoi_span = self.eoi_span();
+                    self.report(Error::UnexpectedEof("]", eoi_span));
+                    break eoi_span;
+                }
+            }
+            match self.look(0) {
+                Some((Token::Comma, _)) => {
+                    self.move_next();
+                }
+                Some((Token::CloseBracket, span)) => {
+                    let close_span = *span;
+                    self.move_next();
+                    break close_span;
+                }
+                _ => {
+                    let span = self.expected("]");
+                    break self
+                        .recover_to_close(|token| matches!(token, Token::CloseBracket), span);
+                }
+            }
+        };
+        (elements, rest, close_span)
+    }
+
+    // [expr] or ...[expr], in an array literal or the arguments of a call
+    pub fn element(&mut self) -> Option<(Expression<'src>, TextSpan)> {
+        let Some((Token::Dot3, dot3_span)) = self.look(0) else {
+            return self.expression();
+        };
+        let start = dot3_span.start();
+        self.move_next();
+        let (expr, span) = match self.expression() {
+            Some(expr) => expr,
+            None => (Expression::Error, self.expected("<expr>")),
+        };
+        Some((
+            Expression::Spread {
+                expr: (Box::new(expr), span),
+            },
+            TextSpan::new(start, span.end()),
+        ))
+    }
+
     pub fn func_call_args(&mut self) -> (Vec<(Expression<'src>, TextSpan)>, TextSpan) {
         debug_assert!(
             matches!(self.look(-1), Some((Token::OpenParen, _))),
@@ -75,7 +451,7 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
 
         let mut args = Vec::new();
         let close_span = loop {
-            let Some((arg, arg_span)) = self.expression() else {
+            let Some((arg, arg_span)) = self.element() else {
                 match self.look(0) {
                     Some((Token::Comma, span)) => {
                         self.report(Error::UnexpectedSymbol(",", *span));
@@ -83,7 +459,9 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                         continue;
                     }
                     Some(_) => {
-                        todo!("implement error recovery");
+                        let span = self.expected("<expr>");
+                        break self
+                            .recover_to_close(|token| matches!(token, Token::CloseParen), span);
                     }
                     None => {
                         let eoi_span = self.eoi_span();
@@ -112,12 +490,93 @@ impl<'tokens, 'src: 'tokens> Parser<'tokens, 'src> {
                     self.move_next();
                     break close_span;
                 }
-                _ => todo!("implement error recovery"),
+                _ => {
+                    let span = self.expected(")");
+                    break self.recover_to_close(|token| matches!(token, Token::CloseParen), span);
+                }
             }
         };
         (args, close_span)
     }
 
+    /// Reports that `expected` is missing before the next token, which is left for the caller.
+    /// Returns the span of the error.
+    pub fn expected(&mut self, expected: &'static str) -> TextSpan {
+        match self.look(0) {
+            Some((token, span)) => {
+                let span = *span;
+                let found = (token.to_string(), span);
+                self.report(Error::ExpectedFound { expected, found });
+                span
+            }
+            None => {
+                let span = self.eoi_span();
+                self.report(Error::UnexpectedEof(expected, span));
+                span
+            }
+        }
+    }
+
+    /// Moves past the keyword `name`, which `is_keyword` accepts. If it is missing, reports it and
+    /// skips to it, or to where the next statement may start.
+    pub fn expect_keyword(&mut self, name: &'static str, is_keyword: fn(&Token) -> bool) {
+        if !self.look(0).is_some_and(|(token, _)| is_keyword(token)) {
+            self.expected(name);
+            self.synchronize_to(is_keyword);
+        }
+        if self.look(0).is_some_and(|(token, _)| is_keyword(token)) {
+            self.move_next();
+        }
+    }
+
+    /// Skips the rest of a list after a syntax error, up to the closing delimiter `is_close`
+    /// accepts, and moves past it. Nested delimiters are skipped whole. Returns the span of the
+    /// closing delimiter, or `error_span` if a keyword that can't be in an expression comes first.
+    pub fn recover_to_close(
+        &mut self,
+        is_close: fn(&Token) -> bool,
+        error_span: TextSpan,
+    ) -> TextSpan {
+        let mut depth = 0usize;
+        while let Some((token, span)) = self.look(0) {
+            match token {
+                Token::OpenParen | Token::OpenBrace | Token::OpenBracket => depth += 1,
+                Token::CloseParen | Token::CloseBrace | Token::CloseBracket if depth > 0 => {
+                    depth -= 1;
+                }
+                token if depth == 0 && is_close(token) => {
+                    let span = *span;
+                    self.move_next();
+                    return span;
+                }
+                Token::Var
+                | Token::If
+                | Token::Elif
+                | Token::Else
+                | Token::For
+                | Token::While
+                | Token::Do
+                | Token::End
+                | Token::Return
+                | Token::Break
+                | Token::Continue
+                | Token::Match
+                | Token::Case
+                | Token::With
+                | Token::Assert
+                | Token::Class
+                | Token::Try
+                | Token::Catch
+                | Token::Yield
+                | Token::Import
+                | Token::Global => break,
+                _ => {}
+            }
+            self.move_next();
+        }
+        error_span
+    }
+
     /// This function is needed to avoid a lifetime error.
     ///
     /// ```ignore
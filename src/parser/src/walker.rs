@@ -49,6 +49,26 @@ impl<'walker, 'src: 'walker> Walker<'walker, 'src> {
         self.defs.insert(name);
     }
 
+    pub fn record_pattern_definition(&mut self, pattern: &Pattern<'src>) {
+        match pattern {
+            Pattern::Literal(_) => {}
+            Pattern::Ident(name) => self.record_variable_definition(name),
+            Pattern::Table(fields) => {
+                for (_, (pattern, _)) in fields {
+                    self.record_pattern_definition(pattern);
+                }
+            }
+            Pattern::Array { elements, rest } => {
+                for (pattern, _) in elements {
+                    self.record_pattern_definition(pattern);
+                }
+                if let Some((name, _)) = rest {
+                    self.record_variable_definition(name);
+                }
+            }
+        }
+    }
+
     pub fn record_attribute(&mut self, name: &'src str, span: TextSpan) {
         self.attrs.borrow_mut().entry(name).or_default().push(span);
     }
@@ -79,10 +99,8 @@ impl<'walker, 'src: 'walker> Walker<'walker, 'src> {
                 if self.defs.contains(name) {
                     continue;
                 }
-                for defs in self.master_defs.iter().rev() {
-                    if defs.contains(name) {
-                        continue;
-                    }
+                if self.master_defs.iter().any(|defs| defs.contains(name)) {
+                    continue;
                 }
                 self.caps.borrow_mut().entry(name).or_insert(span);
             }
@@ -146,16 +164,46 @@ mod walkable_impl {
                     walker.record_variable_definition(name);
                     walker.go(expr);
                 }
+                Statement::DestructuringVar {
+                    pattern: (pattern, _),
+                    expr: (expr, _),
+                } => {
+                    walker.record_pattern_definition(pattern);
+                    walker.go(expr);
+                }
+                Statement::ParallelVar { names, exprs } => {
+                    for (name, _) in names {
+                        walker.record_variable_definition(name);
+                    }
+                    for (expr, _) in exprs {
+                        walker.go(expr);
+                    }
+                }
+                Statement::Global {
+                    name: (name, _),
+                    expr,
+                } => {
+                    if let Some((expr, _)) = expr {
+                        walker.go(expr);
+                    }
+                    walker.record_variable_definition(name);
+                }
                 Statement::Func {
                     name: (name, _),
                     args,
                     body,
+                    doc: _,
                 } => {
                     walker.record_variable_definition(name);
                     let result = {
                         let mut walker = Walker::new();
-                        for (_, arg, _) in args {
-                            walker.record_variable_definition(arg);
+                        for (_, arg, _, _) in args.iter() {
+                            walker.record_pattern_definition(arg);
+                        }
+                        for (_, _, _, default) in args.iter_mut() {
+                            if let Some((default, _)) = default {
+                                walker.go(default);
+                            }
                         }
                         walker.go(&mut body.block);
                         let result = walker.finish();
@@ -169,12 +217,18 @@ mod walkable_impl {
                     fields: _,
                     args,
                     body,
+                    doc: _,
                 } => {
                     walker.record_variable_usage(table, *table_span);
                     let result = {
                         let mut walker = Walker::new();
-                        for (_, arg, _) in args {
-                            walker.record_variable_definition(arg);
+                        for (_, arg, _, _) in args.iter() {
+                            walker.record_pattern_definition(arg);
+                        }
+                        for (_, _, _, default) in args.iter_mut() {
+                            if let Some((default, _)) = default {
+                                walker.go(default);
+                            }
                         }
                         walker.go(&mut body.block);
                         let result = walker.finish();
@@ -183,6 +237,34 @@ mod walkable_impl {
                     };
                     walker.merge(result);
                 }
+                Statement::Class {
+                    name: (name, _),
+                    parent,
+                    methods,
+                } => {
+                    if let Some((parent, _)) = parent {
+                        walker.go(parent);
+                    }
+                    walker.record_variable_definition(name);
+                    for (_, method) in methods.iter_mut() {
+                        let result = {
+                            let mut walker = Walker::new();
+                            for (_, arg, _, _) in method.args.iter() {
+                                walker.record_pattern_definition(arg);
+                            }
+                            for (_, _, _, default) in method.args.iter_mut() {
+                                if let Some((default, _)) = default {
+                                    walker.go(default);
+                                }
+                            }
+                            walker.go(&mut method.body.block);
+                            let result = walker.finish();
+                            method.body.captures = result.captures();
+                            result
+                        };
+                        walker.merge(result);
+                    }
+                }
                 Statement::Assign {
                     name: (name, name_span),
                     expr: (expr, _),
@@ -190,6 +272,14 @@ mod walkable_impl {
                     walker.go(expr);
                     walker.record_variable_usage(name, *name_span);
                 }
+                Statement::ParallelAssign { names, exprs } => {
+                    for (expr, _) in exprs {
+                        walker.go(expr);
+                    }
+                    for (name, name_span) in names {
+                        walker.record_variable_usage(name, *name_span);
+                    }
+                }
                 Statement::FieldAssign {
                     table: (table, _),
                     field: (field, _),
@@ -199,6 +289,19 @@ mod walkable_impl {
                     walker.go(table);
                     walker.go(field);
                 }
+                Statement::ConditionalAssign {
+                    op: _,
+                    target: (target, _),
+                    expr: (expr, _),
+                }
+                | Statement::CompoundAssign {
+                    op: _,
+                    target: (target, _),
+                    expr: (expr, _),
+                } => {
+                    walker.go(target);
+                    walker.go(expr);
+                }
                 Statement::If {
                     cond: (cond, _),
                     body,
@@ -224,6 +327,21 @@ mod walkable_impl {
                     walker.record_variable_definition(value);
                     walker.fork().go(body);
                 }
+                Statement::NumericFor {
+                    value: (value, _),
+                    start: (start, _),
+                    stop: (stop, _),
+                    step,
+                    body,
+                } => {
+                    walker.go(start);
+                    walker.go(stop);
+                    if let Some((step, _)) = step {
+                        walker.go(step);
+                    }
+                    walker.record_variable_definition(value);
+                    walker.fork().go(body);
+                }
                 Statement::While {
                     cond: (cond, _),
                     body,
@@ -234,11 +352,65 @@ mod walkable_impl {
                 Statement::Do { body } => {
                     walker.fork().go(body);
                 }
+                Statement::Match {
+                    expr: (expr, _),
+                    arms,
+                } => {
+                    walker.go(expr);
+                    for ((pattern, _), guard, body) in arms.iter_mut() {
+                        let mut walker = walker.fork();
+                        walker.record_pattern_definition(pattern);
+                        if let Some((guard, _)) = guard {
+                            walker.go(guard);
+                        }
+                        walker.go(body);
+                    }
+                }
+                Statement::With {
+                    expr: (expr, _),
+                    name: (name, _),
+                    body,
+                } => {
+                    walker.go(expr);
+                    let mut walker = walker.fork();
+                    walker.record_variable_definition(name);
+                    walker.go(body);
+                }
+                Statement::Try {
+                    body,
+                    name: (name, _),
+                    handler,
+                } => {
+                    walker.fork().go(body);
+                    let mut walker = walker.fork();
+                    walker.record_variable_definition(name);
+                    walker.go(handler);
+                }
+                Statement::Assert {
+                    cond: (cond, _),
+                    message,
+                } => {
+                    walker.go(cond);
+                    if let Some((message, _)) = message {
+                        walker.go(message);
+                    }
+                }
                 Statement::Return { value } => {
                     if let Some((value, _)) = value {
                         walker.go(value);
                     }
                 }
+                Statement::ReturnMany { values } => {
+                    for (value, _) in values {
+                        walker.go(value);
+                    }
+                }
+                Statement::Yield { value } => {
+                    if let Some((value, _)) = value {
+                        walker.go(value);
+                    }
+                }
+                Statement::Import { path: _ } => {}
                 Statement::Continue => {}
                 Statement::Break => {}
                 Statement::Call {
@@ -323,8 +495,13 @@ mod walkable_impl {
             Expression::FunctionObject(func) => {
                 let result = {
                     let mut waker = Walker::new();
-                    for (_, arg, _) in func.args.iter() {
-                        waker.record_variable_definition(arg);
+                    for (_, arg, _, _) in func.args.iter() {
+                        waker.record_pattern_definition(arg);
+                    }
+                    for (_, _, _, default) in func.args.iter_mut() {
+                        if let Some((default, _)) = default {
+                            waker.go(default);
+                        }
                     }
                     waker.go(&mut func.body.block);
                     let result = waker.finish();
@@ -365,7 +542,40 @@ mod walkable_impl {
             } => {
                 walker.go(expr);
             }
-            Expression::Error => panic!("Error expression found."),
+            Expression::SliceAccess {
+                expr: (expr, _),
+                start,
+                end,
+            } => {
+                walker.go(expr);
+                for (bound, _) in start.iter_mut().chain(end) {
+                    walker.go(bound);
+                }
+            }
+            Expression::Optional { expr: (expr, _) } | Expression::Spread { expr: (expr, _) } => {
+                walker.go(expr);
+            }
+            Expression::If {
+                cond: (cond, _),
+                then: (then, _),
+                elifs,
+                else_: (else_, _),
+            } => {
+                walker.go(cond);
+                walker.go(then);
+                for ((cond, _), (then, _)) in elifs {
+                    walker.go(cond);
+                    walker.go(then);
+                }
+                walker.go(else_);
+            }
+            Expression::Yield { value } => {
+                if let Some((value, _)) = value {
+                    walker.go(value);
+                }
+            }
+            Expression::Import { path: _ } => {}
+            Expression::Error => {}
         }
     }
 }
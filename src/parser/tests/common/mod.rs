@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use foundation::ast::Program;
 
 pub fn parse_program(src: &str) -> Program<'_> {
@@ -5,3 +7,8 @@ pub fn parse_program(src: &str) -> Program<'_> {
     let (program, _) = parser::parse(&tokens);
     program
 }
+
+pub fn parse_program_with_errors(src: &str) -> (Program<'_>, Vec<parser::Error>) {
+    let (tokens, _) = lexer::parse(src);
+    parser::parse(&tokens)
+}
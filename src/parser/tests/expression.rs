@@ -79,6 +79,46 @@ expression_test! {
     ]
 }
 
+expression_test! {
+    name = lambda_with_args,
+    source = "|a, {b}, ...c| a + b",
+    expected = [
+        "FunctionObject (e)"
+        "  args"
+        "    a @1..2"
+        "    {b} @4..7"
+        "    c [rest] @12..13"
+        "  body"
+        "    Chunk"
+        "      captures: None"
+        "      block"
+        "        Return (s) @15..20"
+        "          value"
+        "            Binary (e) @15..20"
+        "              op: +"
+        "              lhs"
+        "                Local (e) a @15..16"
+        "              rhs"
+        "                Local (e) b @19..20"
+    ]
+}
+
+expression_test! {
+    name = lambda_without_args,
+    source = "|| 42",
+    expected = [
+        "FunctionObject (e)"
+        "  args: None"
+        "  body"
+        "    Chunk"
+        "      captures: None"
+        "      block"
+        "        Return (s) @3..5"
+        "          value"
+        "            Primitive (e) 42 @3..5"
+    ]
+}
+
 expression_test! {
     name = empty_array_object,
     source = "[]",
@@ -145,6 +185,27 @@ expression_test! {
     ]
 }
 
+expression_test! {
+    name = table_object_with_expr_key,
+    source = "{[1] = 2, [\"a\" .. b] = 3}",
+    expected = [
+        "TableObject (e)"
+        "  key"
+        "    Primitive (e) 1 @2..3"
+        "  value"
+        "    Primitive (e) 2 @7..8"
+        "  key"
+        "    Binary (e) @11..19"
+        "      op: .."
+        "      lhs"
+        "        Primitive (e) \"a\" @11..14"
+        "      rhs"
+        "        Local (e) b @18..19"
+        "  value"
+        "    Primitive (e) 3 @23..24"
+    ]
+}
+
 expression_test! {
     name = complicated_func_with_trailing_comma,
     source = "f(g(),)",
@@ -422,6 +483,32 @@ expression_test! {
     ]
 }
 
+expression_test! {
+    name = pow_and_floor_div,
+    source = "-a ** b ** 2 // c",
+    expected = [
+        "Binary (e)"
+        "  op: //"
+        "  lhs"
+        "    Unary (e) @0..12"
+        "      op: -"
+        "      expr"
+        "        Binary (e) @1..12"
+        "          op: **"
+        "          lhs"
+        "            Local (e) a @1..2"
+        "          rhs"
+        "            Binary (e) @6..12"
+        "              op: **"
+        "              lhs"
+        "                Local (e) b @6..7"
+        "              rhs"
+        "                Primitive (e) 2 @11..12"
+        "  rhs"
+        "    Local (e) c @16..17"
+    ]
+}
+
 expression_test! {
     name = string_concat,
     source = "'a' .. 4 + 1 == 'a5'",
@@ -489,3 +576,162 @@ expression_test! {
         "    Primitive (e) 2 @34..35"
     ]
 }
+
+expression_test! {
+    name = pipeline,
+    source = "x or y |> f |> g(1)", // ((x or y) |> f) |> g(1)
+    expected = [
+        "Binary (e)"
+        "  op: |>"
+        "  lhs"
+        "    Binary (e) @0..11"
+        "      op: |>"
+        "      lhs"
+        "        Binary (e) @0..6"
+        "          op: or"
+        "          lhs"
+        "            Local (e) x @0..1"
+        "          rhs"
+        "            Local (e) y @5..6"
+        "      rhs"
+        "        Local (e) f @10..11"
+        "  rhs"
+        "    Call (e) @15..19"
+        "      expr"
+        "        Local (e) g @15..16"
+        "      args"
+        "        Primitive (e) 1 @17..18"
+    ]
+}
+
+expression_test! {
+    name = nil_coalesce_and_optional_chain,
+    source = "a ?? b?.c.d?->m() ?? e",
+    expected = [
+        "Binary (e)"
+        "  op: ??"
+        "  lhs"
+        "    Local (e) a @0..1"
+        "  rhs"
+        "    Binary (e) @5..22"
+        "      op: ??"
+        "      lhs"
+        "        MethodCall (e) @5..17"
+        "          expr"
+        "            Optional (e) @5..11"
+        "              expr"
+        "                DotAccess (e) @5..11"
+        "                  expr"
+        "                    DotAccess (e) @5..9"
+        "                      expr"
+        "                        Optional (e) @5..6"
+        "                          expr"
+        "                            Local (e) b @5..6"
+        "                      accessor: c @8..9"
+        "                  accessor: d @10..11"
+        "          name: m @14..15"
+        "          args: None"
+        "      rhs"
+        "        Local (e) e @21..22"
+    ]
+}
+
+expression_test! {
+    name = spread,
+    source = "f(a, ...[b, ...c])",
+    expected = [
+        "Call (e)"
+        "  expr"
+        "    Local (e) f @0..1"
+        "  args"
+        "    Local (e) a @2..3"
+        "    Spread (e) @5..17"
+        "      expr"
+        "        ArrayObject (e) @8..17"
+        "          000"
+        "            Local (e) b @9..10"
+        "          001"
+        "            Spread (e) @12..16"
+        "              expr"
+        "                Local (e) c @15..16"
+    ]
+}
+
+expression_test! {
+    name = slice_access,
+    source = "a[1:-1][:n][i:]",
+    expected = [
+        "SliceAccess (e)"
+        "  expr"
+        "    SliceAccess (e) @0..11"
+        "      expr"
+        "        SliceAccess (e) @0..7"
+        "          expr"
+        "            Local (e) a @0..1"
+        "          start"
+        "            Primitive (e) 1 @2..3"
+        "          end"
+        "            Primitive (e) -1 @4..6"
+        "      end"
+        "        Local (e) n @9..10"
+        "  start"
+        "    Local (e) i @12..13"
+    ]
+}
+
+expression_test! {
+    name = in_operator,
+    source = "a + 1 in b and k in t",
+    expected = [
+        "Binary (e)"
+        "  op: and"
+        "  lhs"
+        "    Binary (e) @0..10"
+        "      op: in"
+        "      lhs"
+        "        Binary (e) @0..5"
+        "          op: +"
+        "          lhs"
+        "            Local (e) a @0..1"
+        "          rhs"
+        "            Primitive (e) 1 @4..5"
+        "      rhs"
+        "        Local (e) b @9..10"
+        "  rhs"
+        "    Binary (e) @15..21"
+        "      op: in"
+        "      lhs"
+        "        Local (e) k @15..16"
+        "      rhs"
+        "        Local (e) t @20..21"
+    ]
+}
+
+expression_test! {
+    name = if_expression,
+    source = "if a then 1 elif b then f(x) else if c then 2 else 3 end end",
+    expected = [
+        "If (e)"
+        "  cond"
+        "    Local (e) a @3..4"
+        "  then"
+        "    Primitive (e) 1 @10..11"
+        "  elif"
+        "    cond"
+        "      Local (e) b @17..18"
+        "    then"
+        "      Call (e) @24..28"
+        "        expr"
+        "          Local (e) f @24..25"
+        "        args"
+        "          Local (e) x @26..27"
+        "  else"
+        "    If (e) @34..56"
+        "      cond"
+        "        Local (e) c @37..38"
+        "      then"
+        "        Primitive (e) 2 @44..45"
+        "      else"
+        "        Primitive (e) 3 @51..52"
+    ]
+}
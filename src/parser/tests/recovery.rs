@@ -0,0 +1,170 @@
+mod common;
+use pretty_assertions::assert_eq;
+
+// `errors` are the reported errors with their spans, and `statements` are the top-level
+// statements parsed after recovering from them.
+macro_rules! recovery_test {
+    (
+        name = $name:ident,
+        source = $source:expr,
+        errors = [$($errors:expr),* $(,)?],
+        statements = [$($statements:expr),* $(,)?] $(,)?
+    ) => {
+        #[test]
+        fn $name() {
+            let (program, errors) = common::parse_program_with_errors($source);
+            let errors = errors
+                .iter()
+                .map(|error| format!("{} @{}", error, error.span()))
+                .collect::<Vec<_>>();
+            assert_eq!(errors, vec![$($errors),*] as Vec<&str>);
+            let statements = program
+                .body
+                .to_string()
+                .lines()
+                .filter(|line| line.starts_with("    ") && !line.starts_with("     "))
+                .filter(|line| line.contains(" (s) @"))
+                .map(|line| line.trim().to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(statements, vec![$($statements),*] as Vec<&str>);
+        }
+    };
+}
+
+recovery_test! {
+    name = missing_then,
+    source = "if x print(1) end\nprint(2)",
+    errors = ["Expected then, found `print` @5..10"],
+    statements = ["If (s) @0..17", "Call (s) @18..26"],
+}
+
+recovery_test! {
+    name = missing_do,
+    source = "for i 1 do end\nwhile true print(1) end\nprint(2)",
+    errors = [
+        "Expected in <expr>, found `1` @6..7",
+        "Expected do, found `print` @26..31"
+    ],
+    statements = ["For (s) @0..14", "While (s) @15..38", "Call (s) @39..47"],
+}
+
+recovery_test! {
+    name = broken_numeric_for,
+    source = "for i = 1 do end\nfor j = 1, do end\nprint(2)",
+    errors = [
+        "Expected , <expr>, found `do` @10..12",
+        "Expected <expr>, found `do` @28..30"
+    ],
+    statements = ["NumericFor (s) @0..16", "NumericFor (s) @17..34", "Call (s) @35..43"],
+}
+
+recovery_test! {
+    name = broken_table_and_array,
+    source = "var t = {a 1, b = 2}\nvar a = [1, 2 3]\nvar u = {1}\nf(t, a, u)",
+    errors = [
+        "Expected =, found `1` @11..12",
+        "Expected ], found `3` @35..36",
+        "Expected <field>, found `1` @47..48"
+    ],
+    statements = [
+        "Var (s) @0..20",
+        "Var (s) @21..37",
+        "Var (s) @38..49",
+        "Call (s) @50..60"
+    ],
+}
+
+recovery_test! {
+    name = broken_call_and_paren,
+    source = "f(1,, 2)\nvar y = (1 + 2\ng(y)",
+    errors = [
+        "Unexpected symbol `,` @4..5",
+        "Expected ), found `g` @24..25"
+    ],
+    statements = ["Call (s) @0..8", "Var (s) @9..23", "Call (s) @24..28"],
+}
+
+recovery_test! {
+    name = broken_postfix,
+    source = "a->b\nc[1\nd = 2",
+    errors = [
+        "Expected (, found `c` @5..6",
+        "Expected a call or an assignment, found an expression @5..8",
+        "Expected ], found `d` @9..10"
+    ],
+    statements = ["MethodCall (s) @0..4", "Error (s) @5..8", "Assign (s) @9..14"],
+}
+
+recovery_test! {
+    name = expression_statement,
+    source = "t.x\nx = 1\n1 + 2\nf()",
+    errors = [
+        "Expected a call or an assignment, found an expression @0..3",
+        "Expected a call or an assignment, found an expression @10..15"
+    ],
+    statements = [
+        "Error (s) @0..3",
+        "Assign (s) @4..9",
+        "Error (s) @10..15",
+        "Call (s) @16..19"
+    ],
+}
+
+recovery_test! {
+    name = stray_keyword,
+    source = "then f()\nend\ng()",
+    errors = [
+        "Unexpected symbol `then` @0..4",
+        "Unexpected symbol `end` @9..12"
+    ],
+    statements = [
+        "Error (s) @0..4",
+        "Error (s) @9..12",
+        "Call (s) @13..16"
+    ],
+}
+
+recovery_test! {
+    name = stray_token_in_match,
+    source = "match x case 1 then f() foo case 2 then g() end\nh()",
+    errors = ["Expected a call or an assignment, found an expression @24..27"],
+    statements = ["Match (s) @0..47", "Call (s) @48..51"],
+}
+
+recovery_test! {
+    name = attribute,
+    source = "@foo(a b)\n@bar\nf()",
+    errors = ["Expected ), found `b` @7..8"],
+    statements = ["Attribute (s) @0..9", "Attribute (s) @10..14", "Call (s) @15..18"],
+}
+
+recovery_test! {
+    name = missing_end,
+    source = "func f()\n  g()\n",
+    errors = ["Missing symbol `end` @14..14"],
+    statements = ["Func (s) @0..14"],
+}
+
+recovery_test! {
+    name = too_few_values_for_names,
+    source = "var a, b = 1\nvar c, d = f()\nreturn c,",
+    errors = [
+        "Expected 2 values on the right-hand side, found 1 @0..12",
+        "Missing symbol `<expr>` @37..37",
+    ],
+    statements = ["ParallelVar (s) @0..12", "ParallelVar (s) @13..27", "ReturnMany (s) @28..37"],
+}
+
+recovery_test! {
+    name = args_after_rest_arg,
+    source = "func f(...a, b) end\nfunc g(...) end",
+    errors = ["Expected ), found `,` @11..12", "Expected <name>, found `)` @30..31"],
+    statements = ["Func (s) @0..19", "Func (s) @20..35"],
+}
+
+recovery_test! {
+    name = if_expression_without_else,
+    source = "var x = if a then 1 end\nprint(x)",
+    errors = ["Expected else, found `end` @20..23"],
+    statements = ["Var (s) @0..23", "Call (s) @24..32"],
+}
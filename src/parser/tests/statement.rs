@@ -27,6 +27,35 @@ chunk_test! {
     ]
 }
 
+chunk_test! {
+    name = closure_in_block_captures_outer_variable,
+    source = "var s = 1 do var f = func() return s end end",
+    expected = [
+        "Chunk"
+        "  captures: None"
+        "  block"
+        "    Var (s) @0..9"
+        "      name: s @4..5"
+        "      expr"
+        "        Primitive (e) 1 @8..9"
+        "    Do (s) @10..44"
+        "      body"
+        "        Block"
+        "          Var (s) @13..40"
+        "            name: f @17..18"
+        "            expr"
+        "              FunctionObject (e) @21..40"
+        "                args: None"
+        "                body"
+        "                  Chunk"
+        "                    captures: s @35..36"
+        "                    block"
+        "                      Return (s) @28..36"
+        "                        value"
+        "                          Local (e) s @35..36"
+    ]
+}
+
 chunk_test! {
     name = assign_variable,
     source = "x = true",
@@ -101,6 +130,28 @@ chunk_test! {
     ]
 }
 
+chunk_test! {
+    name = define_function_with_table_pattern_arg,
+    source = "func f({a, b}, c) return a end",
+    expected = [
+        "Chunk"
+        "  captures: None"
+        "  block"
+        "    Func (s) @0..30"
+        "      name: f @5..6"
+        "      args"
+        "        {a, b} @7..13"
+        "        c @15..16"
+        "      body"
+        "        Chunk"
+        "          captures: None"
+        "          block"
+        "            Return (s) @18..26"
+        "              value"
+        "                Local (e) a @25..26"
+    ]
+}
+
 chunk_test! {
     name = define_function_with_trailing_comma,
     source = "func f(a,) end",
@@ -119,6 +170,45 @@ chunk_test! {
     ]
 }
 
+chunk_test! {
+    name = define_function_with_rest_arg,
+    source = "func f(a, ...rest) end",
+    expected = [
+        "Chunk"
+        "  captures: None"
+        "  block"
+        "    Func (s) @0..22"
+        "      name: f @5..6"
+        "      args"
+        "        a @7..8"
+        "        [rest] rest @13..17"
+        "      body"
+        "        Chunk"
+        "          captures: None"
+        "          block: None"
+    ]
+}
+
+chunk_test! {
+    name = define_function_with_default_arg,
+    source = r#"func greet(name, punct = "!") end"#,
+    expected = [
+        "Chunk"
+        "  captures: None"
+        "  block"
+        "    Func (s) @0..33"
+        "      name: greet @5..10"
+        "      args"
+        "        name @11..15"
+        "        punct @17..22"
+        "          Primitive (e) \"!\" @25..28"
+        "      body"
+        "        Chunk"
+        "          captures: None"
+        "          block: None"
+    ]
+}
+
 chunk_test! {
     name = define_table_field_function,
     source = "func t.a.b() end",
@@ -139,6 +229,128 @@ chunk_test! {
     ]
 }
 
+chunk_test! {
+    name = define_class,
+    source = "class B < A func init(self, x) self.x = x end end",
+    expected = [
+        "Chunk"
+        "  captures: A @10..11"
+        "  block"
+        "    Class (s) @0..49"
+        "      name: B @6..7"
+        "      parent"
+        "        Local (e) A @10..11"
+        "      methods"
+        "        init @17..21"
+        "          args"
+        "            self @22..26"
+        "            x @28..29"
+        "          body"
+        "            Chunk"
+        "              captures: None"
+        "              block"
+        "                FieldAssign (s) @31..41"
+        "                  table"
+        "                    Local (e) self @31..35"
+        "                  field"
+        "                    Primitive (e) \"x\" @36..37"
+        "                  expr"
+        "                    Local (e) x @40..41"
+    ]
+}
+
+chunk_test! {
+    name = try_catch,
+    source = "try f() catch e g(e) end",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    f @4..5"
+        "    g @16..17"
+        "  block"
+        "    Try (s) @0..24"
+        "      body"
+        "        Block"
+        "          Call (s) @4..7"
+        "            expr"
+        "              Local (e) f @4..5"
+        "            args: None"
+        "      name: e @14..15"
+        "      handler"
+        "        Block"
+        "          Call (s) @16..20"
+        "            expr"
+        "              Local (e) g @16..17"
+        "            args"
+        "              Local (e) e @18..19"
+    ]
+}
+
+chunk_test! {
+    name = yield_values,
+    source = "yield yield x yield",
+    expected = [
+        "Chunk"
+        "  captures: x @12..13"
+        "  block"
+        "    Yield (s) @0..13"
+        "      value"
+        "        Yield (e) @6..13"
+        "          value"
+        "            Local (e) x @12..13"
+        "    Yield (s) @14..19"
+    ]
+}
+
+chunk_test! {
+    name = import_module,
+    source = r#"import "a" var b = (import "b").c"#,
+    expected = [
+        "Chunk"
+        "  captures: None"
+        "  block"
+        "    Import (s) @0..10"
+        "      path: \"a\" @7..10"
+        "    Var (s) @11..33"
+        "      name: b @15..16"
+        "      expr"
+        "        DotAccess (e) @19..33"
+        "          expr"
+        "            Import (e) @19..31"
+        "              path: \"b\" @27..30"
+        "          accessor: c @32..33"
+    ]
+}
+
+chunk_test! {
+    name = global_variable,
+    source = "global a global b = a f = func() b = 1 end",
+    expected = [
+        "Chunk"
+        "  captures: f @22..23"
+        "  block"
+        "    Global (s) @0..8"
+        "      name: a @7..8"
+        "    Global (s) @9..21"
+        "      name: b @16..17"
+        "      expr"
+        "        Local (e) a @20..21"
+        "    Assign (s) @22..42"
+        "      name: f @22..23"
+        "      expr"
+        "        FunctionObject (e) @26..42"
+        "          args: None"
+        "          body"
+        "            Chunk"
+        "              captures: b @33..34"
+        "              block"
+        "                Assign (s) @33..38"
+        "                  name: b @33..34"
+        "                  expr"
+        "                    Primitive (e) 1 @37..38"
+    ]
+}
+
 chunk_test! {
     name = call_function_without_args,
     source = "f()",
@@ -366,6 +578,65 @@ chunk_test! {
     ]
 }
 
+chunk_test! {
+    name = for_in_with_continue,
+    source = "for i in xs do continue end",
+    expected = [
+        "Chunk"
+        "  captures: xs @9..11"
+        "  block"
+        "    For (s) @0..27"
+        "      value: i @4..5"
+        "      iter"
+        "        Local (e) xs @9..11"
+        "      body"
+        "        Block"
+        "          Continue (s) @15..23"
+    ]
+}
+
+chunk_test! {
+    name = numeric_for,
+    source = "for i = 1, n do end",
+    expected = [
+        "Chunk"
+        "  captures: n @11..12"
+        "  block"
+        "    NumericFor (s) @0..19"
+        "      value: i @4..5"
+        "      start"
+        "        Primitive (e) 1 @8..9"
+        "      stop"
+        "        Local (e) n @11..12"
+        "      body"
+        "        Block"
+    ]
+}
+
+chunk_test! {
+    name = numeric_for_with_step,
+    source = "for i = 10, 1, -2 do a = i end",
+    expected = [
+        "Chunk"
+        "  captures: a @21..22"
+        "  block"
+        "    NumericFor (s) @0..30"
+        "      value: i @4..5"
+        "      start"
+        "        Primitive (e) 10 @8..10"
+        "      stop"
+        "        Primitive (e) 1 @12..13"
+        "      step"
+        "        Primitive (e) -2 @15..17"
+        "      body"
+        "        Block"
+        "          Assign (s) @21..26"
+        "            name: a @21..22"
+        "            expr"
+        "              Local (e) i @25..26"
+    ]
+}
+
 chunk_test! {
     name = for_in_expr_with_body,
     source = "for i in 1->upto(10) do a = a + i end",
@@ -438,3 +709,278 @@ chunk_test! {
         "    Return (s) @0..6"
     ]
 }
+
+chunk_test! {
+    name = match_statement_with_guard,
+    source = "match x case 1 then f() case y if y > 1 then case _ then end",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    f @20..21"
+        "    x @6..7"
+        "  block"
+        "    Match (s) @0..60"
+        "      expr"
+        "        Local (e) x @6..7"
+        "      case 1 @13..14"
+        "        body"
+        "          Block"
+        "            Call (s) @20..23"
+        "              expr"
+        "                Local (e) f @20..21"
+        "              args: None"
+        "      case y @29..30"
+        "        guard"
+        "          Binary (e) @34..39"
+        "            op: >"
+        "            lhs"
+        "              Local (e) y @34..35"
+        "            rhs"
+        "              Primitive (e) 1 @38..39"
+        "        body"
+        "          Block"
+        "      case _ @50..51"
+        "        body"
+        "          Block"
+    ]
+}
+
+chunk_test! {
+    name = match_statement_with_destructuring,
+    source = "match v case [x, 0, ...rest] then case {name: [n], age} then case [] then end",
+    expected = [
+        "Chunk"
+        "  captures: v @6..7"
+        "  block"
+        "    Match (s) @0..77"
+        "      expr"
+        "        Local (e) v @6..7"
+        "      case [x, 0, ...rest] @13..28"
+        "        body"
+        "          Block"
+        "      case {name: [n], age} @39..55"
+        "        body"
+        "          Block"
+        "      case [] @66..68"
+        "        body"
+        "          Block"
+    ]
+}
+
+chunk_test! {
+    name = with_statement,
+    source = "with open(path) as f do f->read() end",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    open @5..9"
+        "    path @10..14"
+        "  block"
+        "    With (s) @0..37"
+        "      expr"
+        "        Call (e) @5..15"
+        "          expr"
+        "            Local (e) open @5..9"
+        "          args"
+        "            Local (e) path @10..14"
+        "      name: f @19..20"
+        "      body"
+        "        Block"
+        "          MethodCall (s) @24..33"
+        "            expr"
+        "              Local (e) f @24..25"
+        "            name: read @27..31"
+        "            args: None"
+    ]
+}
+
+chunk_test! {
+    name = assert_statement_with_message,
+    source = "assert(x == 1, \"x must be 1\")",
+    expected = [
+        "Chunk"
+        "  captures: x @7..8"
+        "  block"
+        "    Assert (s) @0..29"
+        "      cond"
+        "        Binary (e) @7..13"
+        "          op: =="
+        "          lhs"
+        "            Local (e) x @7..8"
+        "          rhs"
+        "            Primitive (e) 1 @12..13"
+        "      message"
+        "        Primitive (e) \"x must be 1\" @15..28"
+    ]
+}
+
+chunk_test! {
+    name = conditional_assign,
+    source = "x ??= 1 t.y ||= x",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    t @8..9"
+        "    x @0..1"
+        "  block"
+        "    ConditionalAssign (s) @0..7"
+        "      op: ??="
+        "      target"
+        "        Local (e) x @0..1"
+        "      expr"
+        "        Primitive (e) 1 @6..7"
+        "    ConditionalAssign (s) @8..17"
+        "      op: ||="
+        "      target"
+        "        IndexAccess (e) @8..11"
+        "          expr"
+        "            Local (e) t @8..9"
+        "          accessor"
+        "            Primitive (e) \"y\" @10..11"
+        "      expr"
+        "        Local (e) x @16..17"
+    ]
+}
+
+chunk_test! {
+    name = compound_assign,
+    source = "a += 1 t.x ..= \"s\" t[a] %= 2",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    a @0..1"
+        "    t @7..8"
+        "  block"
+        "    CompoundAssign (s) @0..6"
+        "      op: +="
+        "      target"
+        "        Local (e) a @0..1"
+        "      expr"
+        "        Primitive (e) 1 @5..6"
+        "    CompoundAssign (s) @7..18"
+        "      op: ..="
+        "      target"
+        "        IndexAccess (e) @7..10"
+        "          expr"
+        "            Local (e) t @7..8"
+        "          accessor"
+        "            Primitive (e) \"x\" @9..10"
+        "      expr"
+        "        Primitive (e) \"s\" @15..18"
+        "    CompoundAssign (s) @19..28"
+        "      op: %="
+        "      target"
+        "        IndexAccess (e) @19..22"
+        "          expr"
+        "            Local (e) t @19..20"
+        "          accessor"
+        "            Local (e) a @21..22"
+        "      expr"
+        "        Primitive (e) 2 @27..28"
+    ]
+}
+
+chunk_test! {
+    name = parallel_assign,
+    source = "a, b = b, a",
+    expected = [
+        "Chunk"
+        "  captures"
+        "    a @10..11"
+        "    b @7..8"
+        "  block"
+        "    ParallelAssign (s) @0..11"
+        "      names"
+        "        a @0..1"
+        "        b @3..4"
+        "      exprs"
+        "        Local (e) b @7..8"
+        "        Local (e) a @10..11"
+    ]
+}
+
+chunk_test! {
+    name = destructuring_var,
+    source = "var [a, {x, y: b}, ...rest] = f()",
+    expected = [
+        "Chunk"
+        "  captures: f @30..31"
+        "  block"
+        "    DestructuringVar (s) @0..33"
+        "      pattern: [a, {x, y: b}, ...rest] @4..27"
+        "      expr"
+        "        Call (e) @30..33"
+        "          expr"
+        "            Local (e) f @30..31"
+        "          args: None"
+    ]
+}
+
+chunk_test! {
+    name = parallel_var_from_call,
+    source = "var q, r = divmod(7, 2)",
+    expected = [
+        "Chunk"
+        "  captures: divmod @11..17"
+        "  block"
+        "    ParallelVar (s) @0..23"
+        "      names"
+        "        q @4..5"
+        "        r @7..8"
+        "      exprs"
+        "        Call (e) @11..23"
+        "          expr"
+        "            Local (e) divmod @11..17"
+        "          args"
+        "            Primitive (e) 7 @18..19"
+        "            Primitive (e) 2 @21..22"
+    ]
+}
+
+chunk_test! {
+    name = return_many,
+    source = "return x, 1",
+    expected = [
+        "Chunk"
+        "  captures: x @7..8"
+        "  block"
+        "    ReturnMany (s) @0..11"
+        "      values"
+        "        Local (e) x @7..8"
+        "        Primitive (e) 1 @10..11"
+    ]
+}
+
+chunk_test! {
+    name = doc_comment,
+    source = "## Adds them.\n## Returns the sum.\nfunc add(a) end\n## skip\nvar x = 1 func t.f() end",
+    expected = [
+        "Chunk"
+        "  captures: t @73..74"
+        "  block"
+        "    Func (s) @34..49"
+        "      name: add @39..42"
+        "      args"
+        "        a @43..44"
+        "      body"
+        "        Chunk"
+        "          captures: None"
+        "          block: None"
+        "      doc"
+        "        \" Adds them.\" @0..14"
+        "        \" Returns the sum.\" @14..34"
+        "    Var (s) @58..67"
+        "      name: x @62..63"
+        "      expr"
+        "        Primitive (e) 1 @66..67"
+        "    FieldFunc (s) @68..82"
+        "      table: t @73..74"
+        "      fields"
+        "        f @75..76"
+        "      args: None"
+        "      body"
+        "        Chunk"
+        "          captures: None"
+        "          block: None"
+    ]
+}
@@ -0,0 +1,85 @@
+//! Runs a program on both the VM and the reference interpreter, and compares the results.
+
+use super::*;
+use vm::runtime::{Object, Runtime, TableKey};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// Both engines returned the same value, or both failed.
+    Agree,
+    /// The result says nothing, e.g. the program doesn't parse or uses something the reference
+    /// interpreter doesn't model.
+    Skipped(String),
+    /// The engines disagree. Each result is the rendered value or the error message.
+    Diverged {
+        vm: Result<String, String>,
+        reference: Result<String, String>,
+    },
+}
+
+pub fn run(source: &str) -> Outcome {
+    let (tokens, errors) = lexer::parse(source);
+    if !errors.is_empty() {
+        return Outcome::Skipped(format!("lex errors: {:?}", errors));
+    }
+    let (program, errors) = parser::parse(&tokens);
+    if !errors.is_empty() {
+        return Outcome::Skipped(format!("parse errors: {:?}", errors));
+    }
+
+    // The reference interpreter runs first, so that the VM never runs a program whose behavior
+    // isn't settled, like an integer overflow, which panics in debug builds.
+    let reference = match eval(&program) {
+        Ok(value) => Ok(value.render()),
+        Err(Error::Runtime(message)) => Err(message),
+        Err(Error::Unsupported(what)) => return Outcome::Skipped(what),
+    };
+    let vm = compiler::compile(&program)
+        .map_err(|err| format!("{:?}", err))
+        .and_then(|code| vm::execute(&code, &mut Runtime::new()).map_err(|err| err.to_string()))
+        .map(|value| render(&value));
+
+    match (&vm, &reference) {
+        (Ok(vm), Ok(reference)) if vm == reference => Outcome::Agree,
+        (Err(_), Err(_)) => Outcome::Agree,
+        _ => Outcome::Diverged { vm, reference },
+    }
+}
+
+/// Renders a VM object in the same form as [`Value::render`].
+pub fn render(object: &Object) -> String {
+    match object {
+        Object::Int(x) => x.to_string(),
+        Object::Float(x) => format!("{:?}", x),
+        Object::String(x) => format!("{:?}", x.as_str()),
+        Object::Bool(x) => x.to_string(),
+        Object::Nil => "nil".to_string(),
+        Object::Array(array) => {
+            let items = array.borrow().iter().map(render).collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        Object::Table(table) => {
+            let mut fields = table
+                .borrow()
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        TableKey::String(string) => format!("{:?}", string),
+                        TableKey::Int(int) => int.to_string(),
+                        TableKey::Bool(boolean) => boolean.to_string(),
+                    };
+                    format!("{} = {}", key, render(value))
+                })
+                .collect::<Vec<_>>();
+            fields.sort();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Object::Function(_)
+        | Object::BoundFunction(_)
+        | Object::MemoizedFunction(_)
+        | Object::RustFunction(_) => "<function>".to_string(),
+        Object::Bytes(bytes) => format!("<bytes ({})>", bytes.len()),
+        Object::Error(error) => format!("<error {:?}>", error.error().message),
+        Object::Userdata(userdata) => format!("<userdata {}>", userdata.name()),
+    }
+}
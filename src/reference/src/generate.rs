@@ -0,0 +1,501 @@
+//! Generates random programs for differential testing.
+//!
+//! Programs are well typed, so that most of them run to the end instead of stopping at the first
+//! type error, and every loop is bounded. The value of each top-level variable is returned at the
+//! end, in an array, so that the result covers the whole run.
+//!
+//! Constructs that are known to be broken in the VM are not generated yet: `break`/`continue`,
+//! `for`, and functions declared inside blocks.
+
+use std::fmt::Write;
+
+/// Generates the program for `seed`. The same seed always gives the same program.
+pub fn program(seed: u64) -> String {
+    let mut generator = Generator {
+        rng: Rng(seed),
+        source: String::new(),
+        indent: 0,
+        scopes: vec![Vec::new()],
+        functions: Vec::new(),
+        next_id: 0,
+        returns: None,
+    };
+    generator.top_level();
+    generator.source
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Type {
+    Int,
+    Float,
+    Bool,
+    Str,
+}
+
+const TYPES: [Type; 4] = [Type::Int, Type::Float, Type::Bool, Type::Str];
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Scalar(Type),
+    // Loop counters are read but never assigned, so that every loop ends.
+    Counter,
+    // Arrays of Int, which never change their length.
+    Array(usize),
+    Table(Vec<Type>),
+}
+
+#[derive(Clone, Debug)]
+struct Var {
+    name: String,
+    kind: Kind,
+}
+
+struct Function {
+    name: String,
+    params: Vec<Type>,
+    ret: Type,
+}
+
+// SplitMix64, which is enough for picking constructs and keeps programs stable across platforms.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+struct Generator {
+    rng: Rng,
+    source: String,
+    indent: usize,
+    scopes: Vec<Vec<Var>>,
+    functions: Vec<Function>,
+    next_id: usize,
+    // The return type of the function being generated, if any.
+    returns: Option<Type>,
+}
+
+impl Generator {
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{}", prefix, self.next_id)
+    }
+
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.indent {
+            self.source.push_str("    ");
+        }
+        self.source.push_str(line);
+        self.source.push('\n');
+    }
+
+    fn declare(&mut self, name: String, kind: Kind) {
+        self.scopes.last_mut().unwrap().push(Var { name, kind });
+    }
+
+    fn vars(&self) -> impl Iterator<Item = &Var> {
+        self.scopes.iter().flatten()
+    }
+
+    fn scalars(&self, ty: Type) -> Vec<String> {
+        self.vars()
+            .filter(|var| match var.kind {
+                Kind::Scalar(var_ty) => var_ty == ty,
+                Kind::Counter => ty == Type::Int,
+                _ => false,
+            })
+            .map(|var| var.name.clone())
+            .collect()
+    }
+
+    fn assignable(&self) -> Vec<(String, Type)> {
+        self.vars()
+            .filter_map(|var| match var.kind {
+                Kind::Scalar(ty) => Some((var.name.clone(), ty)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn arrays(&self) -> Vec<(String, usize)> {
+        self.vars()
+            .filter_map(|var| match var.kind {
+                Kind::Array(len) => Some((var.name.clone(), len)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn table_fields(&self, ty: Type) -> Vec<(String, usize)> {
+        self.vars()
+            .flat_map(|var| match &var.kind {
+                Kind::Table(fields) => fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, field)| **field == ty)
+                    .map(|(index, _)| (var.name.clone(), index))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    fn top_level(&mut self) {
+        let count = 4 + self.rng.below(10);
+        for _ in 0..count {
+            match self.rng.below(10) {
+                0 | 1 => self.function(),
+                2 => self.array(),
+                3 => self.table(),
+                _ => self.statement(2),
+            }
+        }
+        let results = self.scopes[0]
+            .iter()
+            .map(|var| var.name.clone())
+            .collect::<Vec<_>>();
+        self.line(&format!("return [{}]", results.join(", ")));
+    }
+
+    fn function(&mut self) {
+        let name = self.fresh("f");
+        let params = (0..self.rng.below(3))
+            .map(|_| *self.rng.pick(&TYPES))
+            .collect::<Vec<_>>();
+        let ret = *self.rng.pick(&TYPES);
+        let param_names = params.iter().map(|_| self.fresh("p")).collect::<Vec<_>>();
+
+        // Half are anonymous functions assigned to a variable, which compile differently.
+        let anonymous = self.rng.chance(50);
+        if anonymous {
+            self.line(&format!("var {} = func({})", name, param_names.join(", ")));
+        } else {
+            self.line(&format!("func {}({})", name, param_names.join(", ")));
+        }
+        self.indent += 1;
+        self.scopes.push(Vec::new());
+        for (param, ty) in param_names.into_iter().zip(params.iter()) {
+            self.declare(param, Kind::Scalar(*ty));
+        }
+        self.returns = Some(ret);
+        for _ in 0..1 + self.rng.below(4) {
+            self.statement(2);
+        }
+        let value = self.expression(ret, 3);
+        self.line(&format!("return {}", value));
+        self.returns = None;
+        self.scopes.pop();
+        self.indent -= 1;
+        self.line("end");
+        self.functions.push(Function { name, params, ret });
+    }
+
+    fn array(&mut self) {
+        let name = self.fresh("a");
+        let len = 1 + self.rng.below(5);
+        let items = (0..len)
+            .map(|_| self.expression(Type::Int, 2))
+            .collect::<Vec<_>>();
+        self.line(&format!("var {} = [{}]", name, items.join(", ")));
+        self.declare(name, Kind::Array(len));
+    }
+
+    fn table(&mut self) {
+        let name = self.fresh("t");
+        let fields = (0..1 + self.rng.below(3))
+            .map(|_| *self.rng.pick(&TYPES))
+            .collect::<Vec<_>>();
+        let values = fields
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| format!("k{} = {}", index, self.expression(*ty, 2)))
+            .collect::<Vec<_>>();
+        self.line(&format!("var {} = {{ {} }}", name, values.join(", ")));
+        self.declare(name, Kind::Table(fields));
+    }
+
+    fn block(&mut self, depth: usize) {
+        self.indent += 1;
+        self.scopes.push(Vec::new());
+        for _ in 0..1 + self.rng.below(3) {
+            self.statement(depth);
+        }
+        if let Some(ret) = self.returns {
+            if self.rng.chance(15) {
+                let value = self.expression(ret, 2);
+                self.line(&format!("return {}", value));
+            }
+        }
+        self.scopes.pop();
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, depth: usize) {
+        let choice = self.rng.below(if depth == 0 { 6 } else { 10 });
+        match choice {
+            0 | 1 => {
+                let name = self.fresh("v");
+                let ty = *self.rng.pick(&TYPES);
+                let value = self.expression(ty, 3);
+                self.line(&format!("var {} = {}", name, value));
+                self.declare(name, Kind::Scalar(ty));
+            }
+            2 | 3 => {
+                let targets = self.assignable();
+                if targets.is_empty() {
+                    return self.statement(depth);
+                }
+                if targets.len() >= 2 && self.rng.chance(20) {
+                    let (lhs, lhs_ty) = self.rng.pick(&targets).clone();
+                    let (rhs, rhs_ty) = self.rng.pick(&targets).clone();
+                    if lhs != rhs {
+                        let lhs_value = self.expression(lhs_ty, 2);
+                        let rhs_value = self.expression(rhs_ty, 2);
+                        self.line(&format!("{}, {} = {}, {}", lhs, rhs, lhs_value, rhs_value));
+                        return;
+                    }
+                }
+                let (name, ty) = self.rng.pick(&targets).clone();
+                if ty == Type::Bool && self.rng.chance(20) {
+                    let value = self.expression(ty, 2);
+                    self.line(&format!("{} ||= {}", name, value));
+                } else {
+                    let value = self.expression(ty, 3);
+                    self.line(&format!("{} = {}", name, value));
+                }
+            }
+            4 => {
+                let arrays = self.arrays();
+                if arrays.is_empty() {
+                    return self.statement(depth);
+                }
+                let (name, len) = self.rng.pick(&arrays).clone();
+                let index = self.rng.below(len);
+                let value = self.expression(Type::Int, 3);
+                self.line(&format!("{}[{}] = {}", name, index, value));
+            }
+            5 => {
+                let ty = *self.rng.pick(&TYPES);
+                let fields = self.table_fields(ty);
+                if fields.is_empty() {
+                    return self.statement(depth);
+                }
+                let (name, index) = self.rng.pick(&fields).clone();
+                let value = self.expression(ty, 3);
+                if self.rng.chance(50) {
+                    self.line(&format!("{}.k{} = {}", name, index, value));
+                } else {
+                    self.line(&format!("{}[\"k{}\"] = {}", name, index, value));
+                }
+            }
+            6 | 7 => {
+                let cond = self.expression(Type::Bool, 3);
+                self.line(&format!("if {} then", cond));
+                self.block(depth - 1);
+                for _ in 0..self.rng.below(3) {
+                    let cond = self.expression(Type::Bool, 3);
+                    self.line(&format!("elif {} then", cond));
+                    self.block(depth - 1);
+                }
+                if self.rng.chance(50) {
+                    self.line("else");
+                    self.block(depth - 1);
+                }
+                self.line("end");
+            }
+            8 => {
+                let counter = self.fresh("i");
+                let limit = 1 + self.rng.below(5);
+                self.line(&format!("var {} = 0", counter));
+                self.line(&format!("while {} < {} do", counter, limit));
+                self.indent += 1;
+                self.scopes.push(Vec::new());
+                self.declare(counter.clone(), Kind::Counter);
+                for _ in 0..1 + self.rng.below(3) {
+                    self.statement(depth - 1);
+                }
+                self.line(&format!("{} = {} + 1", counter, counter));
+                self.scopes.pop();
+                self.indent -= 1;
+                self.line("end");
+                // The counter stays in scope after the loop, but only for reading.
+                self.declare(counter, Kind::Counter);
+            }
+            _ => {
+                self.line("do");
+                self.block(depth - 1);
+                self.line("end");
+            }
+        }
+    }
+
+    fn literal(&mut self, ty: Type) -> String {
+        match ty {
+            Type::Int => self.rng.below(21).to_string(),
+            Type::Float => format!("{}.{}", self.rng.below(10), self.rng.below(100)),
+            Type::Bool => self.rng.chance(50).to_string(),
+            Type::Str => format!("\"s{}\"", self.rng.below(10)),
+        }
+    }
+
+    fn expression(&mut self, ty: Type, depth: usize) -> String {
+        if depth == 0 || self.rng.chance(25) {
+            return self.leaf(ty);
+        }
+        let depth = depth - 1;
+        match ty {
+            Type::Int => match self.rng.below(8) {
+                0 => {
+                    let op = *self.rng.pick(&["+", "-", "*", "&", "|", "^"]);
+                    let lhs = self.expression(Type::Int, depth);
+                    let rhs = self.expression(Type::Int, depth);
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                1 => {
+                    // Mostly nonzero literal divisors, so that few programs stop there.
+                    let op = *self.rng.pick(&["/", "%"]);
+                    let lhs = self.expression(Type::Int, depth);
+                    let rhs = if self.rng.chance(90) {
+                        (1 + self.rng.below(7)).to_string()
+                    } else {
+                        self.expression(Type::Int, depth)
+                    };
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                2 => {
+                    let op = *self.rng.pick(&["<<", ">>"]);
+                    let lhs = self.expression(Type::Int, depth);
+                    format!("({} {} {})", lhs, op, self.rng.below(8))
+                }
+                3 => {
+                    let op = *self.rng.pick(&["-", "~"]);
+                    format!("({}{})", op, self.expression(Type::Int, depth))
+                }
+                4 => self.call(Type::Int, depth).unwrap_or_else(|| self.leaf(ty)),
+                5 => {
+                    let arrays = self.arrays();
+                    if arrays.is_empty() {
+                        return self.leaf(ty);
+                    }
+                    let (name, len) = self.rng.pick(&arrays).clone();
+                    // One past the end reads nil, which is not an Int, so stay in bounds.
+                    format!("{}[{}]", name, self.rng.below(len))
+                }
+                _ => {
+                    let lhs = self.expression(Type::Int, depth);
+                    let rhs = self.expression(Type::Int, depth);
+                    format!("({} + {})", lhs, rhs)
+                }
+            },
+            Type::Float => match self.rng.below(4) {
+                0 | 1 => {
+                    let op = *self.rng.pick(&["+", "-", "*", "/"]);
+                    let (lhs_ty, rhs_ty) = *self.rng.pick(&[
+                        (Type::Float, Type::Float),
+                        (Type::Int, Type::Float),
+                        (Type::Float, Type::Int),
+                    ]);
+                    let lhs = self.expression(lhs_ty, depth);
+                    let rhs = self.expression(rhs_ty, depth);
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                2 => format!("(-{})", self.expression(Type::Float, depth)),
+                _ => self
+                    .call(Type::Float, depth)
+                    .unwrap_or_else(|| self.leaf(ty)),
+            },
+            Type::Bool => match self.rng.below(6) {
+                0 | 1 => {
+                    let op = *self.rng.pick(&["<", "<=", ">", ">=", "==", "!="]);
+                    let lhs_ty = *self.rng.pick(&[Type::Int, Type::Float]);
+                    let rhs_ty = *self.rng.pick(&[Type::Int, Type::Float]);
+                    let lhs = self.expression(lhs_ty, depth);
+                    let rhs = self.expression(rhs_ty, depth);
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                2 => {
+                    let op = *self.rng.pick(&["and", "or"]);
+                    let lhs = self.expression(Type::Bool, depth);
+                    let rhs = self.expression(Type::Bool, depth);
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                3 => format!("(not {})", self.expression(Type::Bool, depth)),
+                4 => {
+                    let op = *self.rng.pick(&["==", "!="]);
+                    let lhs = self.expression(Type::Str, depth);
+                    let rhs = self.expression(Type::Str, depth);
+                    format!("({} {} {})", lhs, op, rhs)
+                }
+                _ => self
+                    .call(Type::Bool, depth)
+                    .unwrap_or_else(|| self.leaf(ty)),
+            },
+            Type::Str => match self.rng.below(3) {
+                0 | 1 => {
+                    let rhs_ty = *self.rng.pick(&TYPES);
+                    let lhs = self.expression(Type::Str, depth);
+                    let rhs = self.expression(rhs_ty, depth);
+                    format!("({} .. {})", lhs, rhs)
+                }
+                _ => self.call(Type::Str, depth).unwrap_or_else(|| self.leaf(ty)),
+            },
+        }
+    }
+
+    fn leaf(&mut self, ty: Type) -> String {
+        let mut candidates = self.scalars(ty);
+        for (name, index) in self.table_fields(ty) {
+            candidates.push(format!("{}.k{}", name, index));
+        }
+        if candidates.is_empty() || self.rng.chance(30) {
+            self.literal(ty)
+        } else {
+            self.rng.pick(&candidates).clone()
+        }
+    }
+
+    fn call(&mut self, ty: Type, depth: usize) -> Option<String> {
+        let candidates = self
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|(_, function)| function.ret == ty)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = *self.rng.pick(&candidates);
+        let params = self.functions[index].params.clone();
+        let args = params
+            .iter()
+            .map(|param| self.expression(*param, depth))
+            .collect::<Vec<_>>();
+        let name = &self.functions[index].name;
+        let mut call = String::new();
+        // A call with arguments is sometimes written as a pipeline.
+        if !args.is_empty() && self.rng.chance(20) {
+            write!(call, "({} |> {}({}))", args[0], name, args[1..].join(", ")).unwrap();
+        } else {
+            write!(call, "{}({})", name, args.join(", ")).unwrap();
+        }
+        Some(call)
+    }
+}
@@ -0,0 +1,916 @@
+use super::*;
+use crate::value::{Cell, Closure};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+// Programs are generated without knowing whether they terminate, so the interpreter gives up on
+// ones that run too long or recurse too deep rather than hanging or overflowing the stack.
+const MAX_STEPS: usize = 100_000;
+const MAX_DEPTH: usize = 100;
+
+/// Runs `program` and returns the value it returns, or nil.
+pub fn eval<'src>(program: &'src Program<'src>) -> Result<Value<'src>, Error> {
+    // The top level captures only names that are not declared anywhere, i.e. builtins or
+    // undefined variables, which the compiler rejects even in code that is never run.
+    if let Some((name, _)) = program.body.captures.first() {
+        return Err(Error::Unsupported(format!(
+            "builtin or undefined `{}`",
+            name
+        )));
+    }
+    let mut interpreter = Interpreter { steps: 0, depth: 0 };
+    let mut frame = Frame { vars: Vec::new() };
+    match interpreter.exec_block(&mut frame, &program.body.block)? {
+        Flow::Normal => Ok(Value::Nil),
+        Flow::Return(value) => Ok(value),
+        Flow::Break | Flow::Continue => Err(Error::Runtime("No loop to exit".to_string())),
+    }
+}
+
+struct Interpreter {
+    steps: usize,
+    depth: usize,
+}
+
+// The variables visible in a function body, the innermost last. A block truncates it back to
+// where it began when it ends.
+struct Frame<'src> {
+    vars: Vec<(&'src str, Cell<'src>)>,
+}
+
+impl<'src> Frame<'src> {
+    fn declare(&mut self, name: &'src str, value: Value<'src>) {
+        self.vars.push((name, Rc::new(RefCell::new(value))));
+    }
+
+    fn lookup(&self, name: &str) -> Result<&Cell<'src>, Error> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|(var, _)| *var == name)
+            .map(|(_, cell)| cell)
+            .ok_or_else(|| Error::Unsupported(format!("builtin or undefined `{}`", name)))
+    }
+}
+
+enum Flow<'src> {
+    Normal,
+    Return(Value<'src>),
+    Break,
+    Continue,
+}
+
+fn unsupported<T>(what: &str) -> Result<T, Error> {
+    Err(Error::Unsupported(what.to_string()))
+}
+
+fn runtime<T>(message: String) -> Result<T, Error> {
+    Err(Error::Runtime(message))
+}
+
+impl Interpreter {
+    fn step(&mut self) -> Result<(), Error> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return unsupported("step limit exceeded");
+        }
+        Ok(())
+    }
+
+    fn exec_block<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        block: &'src Block<'src>,
+    ) -> Result<Flow<'src>, Error> {
+        let base = frame.vars.len();
+        let mut flow = Flow::Normal;
+        for (statement, _) in block.iter() {
+            flow = self.exec_statement(frame, statement)?;
+            if !matches!(flow, Flow::Normal) {
+                break;
+            }
+        }
+        frame.vars.truncate(base);
+        Ok(flow)
+    }
+
+    fn exec_statement<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        statement: &'src Statement<'src>,
+    ) -> Result<Flow<'src>, Error> {
+        self.step()?;
+        match statement {
+            Statement::Var {
+                name: (name, _),
+                expr: (expr, _),
+            } => {
+                let value = self.eval_expression(frame, expr)?;
+                frame.declare(name, value);
+            }
+            Statement::DestructuringVar { .. } => return unsupported("destructuring"),
+            Statement::ParallelVar { names, exprs } => {
+                if names.len() != exprs.len() {
+                    return unsupported("spreading multiple return values");
+                }
+                let mut values = Vec::with_capacity(exprs.len());
+                for (expr, _) in exprs.iter() {
+                    values.push(self.eval_expression(frame, expr)?);
+                }
+                for ((name, _), value) in names.iter().zip(values) {
+                    frame.declare(name, value);
+                }
+            }
+            Statement::Func {
+                name: (name, _),
+                args,
+                body,
+                doc: _,
+            } => {
+                // As in the compiler, the function sees its own name only if it refers to it.
+                let is_recursive = body
+                    .captures
+                    .binary_search_by_key(name, |(name, _)| name)
+                    .is_ok();
+                if is_recursive {
+                    frame.declare(name, Value::Nil);
+                    let closure = self.make_closure(frame, args, body)?;
+                    *frame.lookup(name)?.borrow_mut() = closure;
+                } else {
+                    let closure = self.make_closure(frame, args, body)?;
+                    frame.declare(name, closure);
+                }
+            }
+            Statement::Assign {
+                name: (name, _),
+                expr: (expr, _),
+            } => {
+                let value = self.eval_expression(frame, expr)?;
+                *frame.lookup(name)?.borrow_mut() = value;
+            }
+            Statement::ParallelAssign { names, exprs } => {
+                if names.len() != exprs.len() {
+                    return unsupported("parallel assignment of different lengths");
+                }
+                let mut values = Vec::with_capacity(exprs.len());
+                for (expr, _) in exprs.iter() {
+                    values.push(self.eval_expression(frame, expr)?);
+                }
+                for ((name, _), value) in names.iter().zip(values) {
+                    *frame.lookup(name)?.borrow_mut() = value;
+                }
+            }
+            Statement::FieldAssign {
+                table: (table, _),
+                field: (field, _),
+                expr: (expr, _),
+            } => {
+                let value = self.eval_expression(frame, expr)?;
+                let table = self.eval_expression(frame, table)?;
+                let field = self.eval_expression(frame, field)?;
+                set_item(table, field, value)?;
+            }
+            Statement::ConditionalAssign {
+                op,
+                target: (target, _),
+                expr: (expr, _),
+            } => {
+                let place = self.eval_place(frame, target)?;
+                let current = place.get()?;
+                let assign = match op {
+                    ConditionalAssignOp::NilCoalesce => matches!(current, Value::Nil),
+                    ConditionalAssignOp::Or => !current.is_truthy(),
+                };
+                if assign {
+                    let value = self.eval_expression(frame, expr)?;
+                    place.set(value)?;
+                }
+            }
+            Statement::CompoundAssign {
+                op,
+                target: (target, _),
+                expr: (expr, _),
+            } => {
+                let place = self.eval_place(frame, target)?;
+                let current = place.get()?;
+                let value = self.eval_expression(frame, expr)?;
+                let op = match op {
+                    CompoundAssignOp::Add => BinaryOp::Add,
+                    CompoundAssignOp::Sub => BinaryOp::Sub,
+                    CompoundAssignOp::Mul => BinaryOp::Mul,
+                    CompoundAssignOp::Div => BinaryOp::Div,
+                    CompoundAssignOp::Mod => BinaryOp::Mod,
+                    CompoundAssignOp::Concat => BinaryOp::Concat,
+                };
+                place.set(binary(&op, current, value)?)?;
+            }
+            Statement::If {
+                cond: (cond, _),
+                body,
+                elifs,
+                else_,
+            } => {
+                if self.eval_condition(frame, cond)? {
+                    return self.exec_block(frame, body);
+                }
+                for ((cond, _), body) in elifs.iter() {
+                    if self.eval_condition(frame, cond)? {
+                        return self.exec_block(frame, body);
+                    }
+                }
+                if let Some(body) = else_ {
+                    return self.exec_block(frame, body);
+                }
+            }
+            Statement::While {
+                cond: (cond, _),
+                body,
+            } => {
+                while self.eval_condition(frame, cond)? {
+                    self.step()?;
+                    match self.exec_block(frame, body)? {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
+                }
+            }
+            Statement::For {
+                value: (value, _),
+                iter: (iter, _),
+                body,
+            } => {
+                let items = match self.eval_expression(frame, iter)? {
+                    // The VM fails if the array is modified in the loop, which is not modeled.
+                    Value::Array(array) => array.borrow().clone(),
+                    Value::String(string) => (string.chars())
+                        .map(|char| Value::String(char.to_string()))
+                        .collect(),
+                    Value::Table(_) => return unsupported("iterating tables"),
+                    value => return runtime(format!("{} is not iterable", value.typename())),
+                };
+                let base = frame.vars.len();
+                frame.declare(value, Value::Nil);
+                for item in items {
+                    self.step()?;
+                    *frame.vars[base].1.borrow_mut() = item;
+                    match self.exec_block(frame, body)? {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow @ Flow::Return(_) => {
+                            frame.vars.truncate(base);
+                            return Ok(flow);
+                        }
+                    }
+                }
+                frame.vars.truncate(base);
+            }
+            Statement::NumericFor {
+                value: (value, _),
+                start: (start, _),
+                stop: (stop, _),
+                step,
+                body,
+            } => {
+                let mut index = ensure_int(self.eval_expression(frame, start)?)?;
+                let stop = ensure_int(self.eval_expression(frame, stop)?)?;
+                let step = match step {
+                    Some((step, _)) => ensure_int(self.eval_expression(frame, step)?)?,
+                    None => 1,
+                };
+                if step == 0 {
+                    return runtime("The step of a numeric for must not be 0".to_string());
+                }
+                let base = frame.vars.len();
+                frame.declare(value, Value::Nil);
+                while (step > 0 && index <= stop) || (step < 0 && index >= stop) {
+                    self.step()?;
+                    *frame.vars[base].1.borrow_mut() = Value::Int(index);
+                    match self.exec_block(frame, body)? {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow @ Flow::Return(_) => {
+                            frame.vars.truncate(base);
+                            return Ok(flow);
+                        }
+                    }
+                    // Counting past the end of the integers ends the loop.
+                    let Some(next) = index.checked_add(step) else {
+                        break;
+                    };
+                    index = next;
+                }
+                frame.vars.truncate(base);
+            }
+            Statement::Do { body } => return self.exec_block(frame, body),
+            Statement::Assert {
+                cond: (cond, _),
+                message,
+            } => {
+                if !self.eval_condition(frame, cond)? {
+                    if let Some((message, _)) = message {
+                        // The message is concatenated to the failure, which may fail first.
+                        let message = self.eval_expression(frame, message)?;
+                        concat(Value::String(String::new()), message)?;
+                    }
+                    return runtime("Assertion failed".to_string());
+                }
+            }
+            Statement::Return { value } => {
+                let value = match value {
+                    Some((value, _)) => self.eval_expression(frame, value)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Call {
+                expr: (expr, _),
+                args,
+            } => {
+                let func = self.eval_expression(frame, expr)?;
+                let args = self.eval_arguments(frame, None, args)?;
+                self.call(func, args)?;
+            }
+            Statement::ReturnMany { .. } => return unsupported("multiple return values"),
+            Statement::FieldFunc { .. } => return unsupported("field functions"),
+            Statement::Match { .. } => return unsupported("match"),
+            Statement::With { .. } => return unsupported("with"),
+            Statement::MethodCall { .. } => return unsupported("method calls"),
+            Statement::Attribute { .. } => return unsupported("attributes"),
+            Statement::Class { .. } => return unsupported("classes"),
+            Statement::Try { .. } => return unsupported("try"),
+            Statement::Yield { .. } => return unsupported("coroutines"),
+            Statement::Import { .. } => return unsupported("import"),
+            Statement::Global { .. } => return unsupported("global"),
+            Statement::Error => return unsupported("syntax errors"),
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn eval_condition<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        expr: &'src Expression<'src>,
+    ) -> Result<bool, Error> {
+        Ok(self.eval_expression(frame, expr)?.is_truthy())
+    }
+
+    fn eval_arguments<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        first: Option<Value<'src>>,
+        args: &'src [(Expression<'src>, TextSpan)],
+    ) -> Result<Vec<Value<'src>>, Error> {
+        let mut values = Vec::with_capacity(args.len() + 1);
+        values.extend(first);
+        for (arg, _) in args.iter() {
+            values.push(self.eval_expression(frame, arg)?);
+        }
+        Ok(values)
+    }
+
+    // The target of a conditional or compound assignment.
+    fn eval_place<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        target: &'src Expression<'src>,
+    ) -> Result<Place<'src>, Error> {
+        match target {
+            Expression::Local(name, _) => Ok(Place::Local(frame.lookup(name)?.clone())),
+            Expression::IndexAccess {
+                expr: (table, _),
+                accessor: (field, _),
+            } => {
+                let table = self.eval_expression(frame, table)?;
+                let field = self.eval_expression(frame, field)?;
+                Ok(Place::Item(table, field))
+            }
+            _ => unsupported("assignment to this target"),
+        }
+    }
+
+    fn eval_expression<'src>(
+        &mut self,
+        frame: &mut Frame<'src>,
+        expr: &'src Expression<'src>,
+    ) -> Result<Value<'src>, Error> {
+        match expr {
+            Expression::Unary {
+                op,
+                expr: (expr, _),
+            } => {
+                let value = self.eval_expression(frame, expr)?;
+                match (op, value) {
+                    (UnaryOp::Neg, Value::Int(x)) => match x.checked_neg() {
+                        Some(x) => Ok(Value::Int(x)),
+                        None => unsupported("integer overflow"),
+                    },
+                    (UnaryOp::Neg, Value::Float(x)) => Ok(Value::Float(-x)),
+                    (UnaryOp::Not, value) => Ok(Value::Bool(!value.is_truthy())),
+                    (UnaryOp::BNot, Value::Int(x)) => Ok(Value::Int(!x)),
+                    (_, value) => runtime(format!("Unexpected operand: {}", value.typename())),
+                }
+            }
+            Expression::Binary {
+                op: BinaryOp::And,
+                lhs: (lhs, _),
+                rhs: (rhs, _),
+            } => match self.eval_expression(frame, lhs)? {
+                value if value.is_truthy() => self.eval_expression(frame, rhs),
+                value => Ok(value),
+            },
+            Expression::Binary {
+                op: BinaryOp::Or,
+                lhs: (lhs, _),
+                rhs: (rhs, _),
+            } => match self.eval_expression(frame, lhs)? {
+                value if value.is_truthy() => Ok(value),
+                _ => self.eval_expression(frame, rhs),
+            },
+            Expression::Binary {
+                op: BinaryOp::NilCoalesce,
+                lhs: (lhs, _),
+                rhs: (rhs, _),
+            } => match self.eval_expression(frame, lhs)? {
+                Value::Nil => self.eval_expression(frame, rhs),
+                value => Ok(value),
+            },
+            Expression::Binary {
+                op: BinaryOp::Pipeline,
+                lhs: (lhs, _),
+                rhs: (rhs, _),
+            } => match rhs.as_ref() {
+                Expression::Call {
+                    expr: (func, _),
+                    args,
+                } => {
+                    let func = self.eval_expression(frame, func)?;
+                    let lhs = self.eval_expression(frame, lhs)?;
+                    let args = self.eval_arguments(frame, Some(lhs), args)?;
+                    self.call(func, args)
+                }
+                Expression::MethodCall { .. } => unsupported("method calls"),
+                func => {
+                    let func = self.eval_expression(frame, func)?;
+                    let lhs = self.eval_expression(frame, lhs)?;
+                    self.call(func, vec![lhs])
+                }
+            },
+            Expression::Binary {
+                op,
+                lhs: (lhs, _),
+                rhs: (rhs, _),
+            } => {
+                let lhs = self.eval_expression(frame, lhs)?;
+                let rhs = self.eval_expression(frame, rhs)?;
+                binary(op, lhs, rhs)
+            }
+            Expression::Local(name, _) => Ok(frame.lookup(name)?.borrow().clone()),
+            Expression::Primitive(primitive, _) => Ok(match primitive {
+                Primitive::Int(x) => Value::Int(*x),
+                Primitive::Float(x) => Value::Float(*x),
+                Primitive::String(x) => Value::String(x.to_string()),
+                Primitive::Bool(x) => Value::Bool(*x),
+                Primitive::Nil => Value::Nil,
+            }),
+            Expression::TableObject(table) => {
+                let mut fields = BTreeMap::new();
+                for (key, (value, _)) in table.iter() {
+                    // The value is evaluated before the key, as in the compiler.
+                    let value = self.eval_expression(frame, value)?;
+                    let key = match key {
+                        TableFieldKey::Ident(key, _) => Key::String(key.to_string()),
+                        TableFieldKey::Expr(key, _) => {
+                            Key::from_value(self.eval_expression(frame, key)?)?
+                        }
+                    };
+                    fields.insert(key, value);
+                }
+                Ok(Value::Table(Rc::new(RefCell::new(fields))))
+            }
+            Expression::ArrayObject(array) => {
+                let mut items = Vec::with_capacity(array.len());
+                for (item, _) in array.iter() {
+                    items.push(self.eval_expression(frame, item)?);
+                }
+                Ok(Value::Array(Rc::new(RefCell::new(items))))
+            }
+            Expression::FunctionObject(function) => {
+                self.make_closure(frame, &function.args, &function.body)
+            }
+            Expression::Call {
+                expr: (expr, _),
+                args,
+            } => {
+                let func = self.eval_expression(frame, expr)?;
+                let args = self.eval_arguments(frame, None, args)?;
+                self.call(func, args)
+            }
+            Expression::IndexAccess {
+                expr: (expr, _),
+                accessor: (accessor, _),
+            } => {
+                let target = self.eval_expression(frame, expr)?;
+                let accessor = self.eval_expression(frame, accessor)?;
+                get_item(target, accessor)
+            }
+            Expression::DotAccess {
+                expr: (expr, _),
+                accessor: (accessor, _),
+            } => {
+                let target = self.eval_expression(frame, expr)?;
+                get_item(target, Value::String(accessor.to_string()))
+            }
+            Expression::SliceAccess {
+                expr: (expr, _),
+                start,
+                end,
+            } => {
+                let target = self.eval_expression(frame, expr)?;
+                let mut bound = |bound: &'src Option<(Box<Expression<'src>>, TextSpan)>| match bound
+                {
+                    Some((bound, _)) => self.eval_expression(frame, bound),
+                    None => Ok(Value::Nil),
+                };
+                let (start, end) = (bound(start)?, bound(end)?);
+                get_slice(target, start, end)
+            }
+            Expression::If {
+                cond: (cond, _),
+                then: (then, _),
+                elifs,
+                else_: (else_, _),
+            } => {
+                let arms = std::iter::once((&**cond, &**then))
+                    .chain(elifs.iter().map(|((cond, _), (then, _))| (cond, then)));
+                for (cond, then) in arms {
+                    if self.eval_condition(frame, cond)? {
+                        return self.eval_expression(frame, then);
+                    }
+                }
+                self.eval_expression(frame, else_)
+            }
+            Expression::MethodCall { .. } => unsupported("method calls"),
+            Expression::Optional { .. } => unsupported("optional chaining"),
+            Expression::Spread { .. } => unsupported("spread"),
+            Expression::Yield { .. } => unsupported("coroutines"),
+            Expression::Import { .. } => unsupported("import"),
+            Expression::Error => unsupported("syntax errors"),
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn make_closure<'src>(
+        &mut self,
+        frame: &Frame<'src>,
+        args: &'src [(
+            FunctArgAnnotation,
+            Pattern<'src>,
+            TextSpan,
+            Option<(Expression<'src>, TextSpan)>,
+        )],
+        body: &'src Chunk<'src>,
+    ) -> Result<Value<'src>, Error> {
+        // Captures are resolved when the closure is made, like the `LocalId`s the compiler
+        // resolves, so a variable declared later with the same name doesn't change them.
+        let mut captures = Vec::with_capacity(body.captures.len());
+        for (name, _) in body.captures.iter() {
+            captures.push((*name, frame.lookup(name)?.clone()));
+        }
+        Ok(Value::Function(Rc::new(Closure {
+            args,
+            body,
+            captures,
+        })))
+    }
+
+    fn call<'src>(
+        &mut self,
+        func: Value<'src>,
+        args: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, Error> {
+        let Value::Function(closure) = func else {
+            return runtime(format!("Expected callable, got {}", func.typename()));
+        };
+        if closure.args.len() != args.len() {
+            return unsupported("calls with a wrong number of arguments");
+        }
+        if self.depth >= MAX_DEPTH {
+            return unsupported("recursion limit exceeded");
+        }
+        let mut frame = Frame {
+            vars: closure.captures.clone(),
+        };
+        for ((annotation, pattern, _, default), value) in closure.args.iter().zip(args) {
+            match (annotation, pattern, default) {
+                (FunctArgAnnotation::None, Pattern::Ident(name), None) => {
+                    frame.declare(name, value)
+                }
+                (_, _, Some(_)) => return unsupported("parameters with default values"),
+                _ => return unsupported("annotated or destructuring parameters"),
+            }
+        }
+        self.depth += 1;
+        let flow = self.exec_block(&mut frame, &closure.body.block);
+        self.depth -= 1;
+        match flow? {
+            Flow::Normal => Ok(Value::Nil),
+            Flow::Return(value) => Ok(value),
+            Flow::Break | Flow::Continue => runtime("No loop to exit".to_string()),
+        }
+    }
+}
+
+// The target of a conditional assignment, evaluated once.
+enum Place<'src> {
+    Local(Cell<'src>),
+    Item(Value<'src>, Value<'src>),
+}
+
+impl<'src> Place<'src> {
+    fn get(&self) -> Result<Value<'src>, Error> {
+        match self {
+            Place::Local(cell) => Ok(cell.borrow().clone()),
+            Place::Item(table, field) => get_item(table.clone(), field.clone()),
+        }
+    }
+
+    fn set(self, value: Value<'src>) -> Result<(), Error> {
+        match self {
+            Place::Local(cell) => {
+                *cell.borrow_mut() = value;
+                Ok(())
+            }
+            Place::Item(table, field) => set_item(table, field, value),
+        }
+    }
+}
+
+fn ensure_int(value: Value) -> Result<i64, Error> {
+    match value {
+        Value::Int(int) => Ok(int),
+        value => runtime(format!("Expected `int`, got `{}`", value.typename())),
+    }
+}
+
+fn get_item<'src>(target: Value<'src>, accessor: Value<'src>) -> Result<Value<'src>, Error> {
+    match (target, accessor) {
+        (Value::Array(array), Value::Int(index)) => {
+            let array = array.borrow();
+            let item = item_index(index, array.len()).and_then(|index| array.get(index));
+            Ok(item.cloned().unwrap_or(Value::Nil))
+        }
+        (Value::Table(table), key) => {
+            let key = Key::from_value(key)?;
+            Ok(table.borrow().get(&key).cloned().unwrap_or(Value::Nil))
+        }
+        (Value::String(string), Value::Int(index)) => {
+            let chars = string.chars().collect::<Vec<_>>();
+            let char = item_index(index, chars.len()).and_then(|index| chars.get(index));
+            Ok(char.map_or(Value::Nil, |char| Value::String(char.to_string())))
+        }
+        (target, accessor) => runtime(format!(
+            "Cannot index {} with {}",
+            target.typename(),
+            accessor.typename()
+        )),
+    }
+}
+
+// The position of the item at `index`, where a negative one counts from the end.
+fn item_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as i64)?
+    } else {
+        index
+    };
+    usize::try_from(index).ok()
+}
+
+fn get_slice<'src>(
+    target: Value<'src>,
+    start: Value<'src>,
+    end: Value<'src>,
+) -> Result<Value<'src>, Error> {
+    let range = |len: usize| {
+        let bound = |bound: Value, default: usize| match bound {
+            Value::Nil => Ok(default),
+            Value::Int(index) if index < 0 => Ok(index.saturating_add(len as i64).max(0) as usize),
+            Value::Int(index) => Ok((index as u64).min(len as u64) as usize),
+            bound => runtime(format!("Cannot slice with {}", bound.typename())),
+        };
+        let (start, end) = (bound(start, 0)?, bound(end, len)?);
+        Ok(start..end.max(start))
+    };
+    match target {
+        Value::Array(array) => {
+            let items = array.borrow();
+            let items = items[range(items.len())?].to_vec();
+            Ok(Value::Array(Rc::new(RefCell::new(items))))
+        }
+        Value::String(string) => {
+            let chars = string.chars().collect::<Vec<_>>();
+            Ok(Value::String(chars[range(chars.len())?].iter().collect()))
+        }
+        target => runtime(format!("Cannot slice {}", target.typename())),
+    }
+}
+
+fn set_item<'src>(
+    target: Value<'src>,
+    accessor: Value<'src>,
+    value: Value<'src>,
+) -> Result<(), Error> {
+    match (target, accessor) {
+        (Value::Array(array), Value::Int(index)) => {
+            let mut array = array.borrow_mut();
+            // The VM doesn't check the bounds yet, and panics.
+            match usize::try_from(index)
+                .ok()
+                .and_then(|index| array.get_mut(index))
+            {
+                Some(item) => *item = value,
+                None => return unsupported("storing out of array bounds"),
+            }
+        }
+        (Value::Table(table), key) => {
+            table.borrow_mut().insert(Key::from_value(key)?, value);
+        }
+        (target, accessor) => {
+            return runtime(format!(
+                "Cannot index {} with {}",
+                target.typename(),
+                accessor.typename()
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn binary<'src>(op: &BinaryOp, lhs: Value<'src>, rhs: Value<'src>) -> Result<Value<'src>, Error> {
+    match op {
+        BinaryOp::Add => arithmetic(lhs, rhs, i64::checked_add, |lhs, rhs| lhs + rhs),
+        BinaryOp::Sub => arithmetic(lhs, rhs, i64::checked_sub, |lhs, rhs| lhs - rhs),
+        BinaryOp::Mul => arithmetic(lhs, rhs, i64::checked_mul, |lhs, rhs| lhs * rhs),
+        BinaryOp::Div | BinaryOp::Mod | BinaryOp::FloorDiv => {
+            if matches!((&lhs, &rhs), (Value::Int(_), Value::Int(0))) {
+                return runtime("Divided by zero.".to_string());
+            }
+            match op {
+                BinaryOp::Div => arithmetic(lhs, rhs, i64::checked_div, |lhs, rhs| lhs / rhs),
+                BinaryOp::Mod => arithmetic(lhs, rhs, i64::checked_rem, |lhs, rhs| lhs % rhs),
+                _ => arithmetic(lhs, rhs, floor_div, |lhs, rhs| (lhs / rhs).floor()),
+            }
+        }
+        BinaryOp::Pow => match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) if rhs < 0 => {
+                Ok(Value::Float((lhs as f64).powf(rhs as f64)))
+            }
+            (lhs, rhs) => arithmetic(lhs, rhs, pow, f64::powf),
+        },
+        BinaryOp::Eq => Ok(Value::Bool(lhs.equals(&rhs)?)),
+        BinaryOp::NotEq => Ok(Value::Bool(!lhs.equals(&rhs)?)),
+        BinaryOp::Less => compare(lhs, rhs, |ordering| ordering.is_lt()),
+        BinaryOp::LessEq => compare(lhs, rhs, |ordering| ordering.is_le()),
+        BinaryOp::Greater => compare(lhs, rhs, |ordering| ordering.is_gt()),
+        BinaryOp::GreaterEq => compare(lhs, rhs, |ordering| ordering.is_ge()),
+        BinaryOp::In => contains(rhs, lhs),
+        BinaryOp::BitAnd => bitwise(lhs, rhs, |lhs, rhs| Some(lhs & rhs)),
+        BinaryOp::BitOr => bitwise(lhs, rhs, |lhs, rhs| Some(lhs | rhs)),
+        BinaryOp::BitXor => bitwise(lhs, rhs, |lhs, rhs| Some(lhs ^ rhs)),
+        BinaryOp::ShiftLeft => bitwise(lhs, rhs, |lhs, rhs| {
+            (0..64).contains(&rhs).then(|| lhs << rhs)
+        }),
+        BinaryOp::ShiftRight => bitwise(lhs, rhs, |lhs, rhs| {
+            (0..64).contains(&rhs).then(|| lhs >> rhs)
+        }),
+        BinaryOp::Concat => concat(lhs, rhs),
+        BinaryOp::BitNot => unsupported("binary `~`"),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::NilCoalesce | BinaryOp::Pipeline => {
+            unreachable!("[BUG] short-circuit and pipeline operators are evaluated by the caller.")
+        }
+    }
+}
+
+fn contains<'src>(container: Value<'src>, item: Value<'src>) -> Result<Value<'src>, Error> {
+    let contains = match (container, item) {
+        (Value::Array(array), item) => {
+            for x in array.borrow().iter() {
+                if x.equals(&item)? {
+                    return Ok(Value::Bool(true));
+                }
+            }
+            false
+        }
+        (Value::Table(table), key) => table.borrow().contains_key(&Key::from_value(key)?),
+        (Value::String(string), Value::String(item)) => string.contains(&item),
+        (container, item) => {
+            return runtime(format!(
+                "Cannot search {} for {}",
+                container.typename(),
+                item.typename()
+            ))
+        }
+    };
+    Ok(Value::Bool(contains))
+}
+
+fn floor_div(lhs: i64, rhs: i64) -> Option<i64> {
+    let quotient = lhs.checked_div(rhs)?;
+    if lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+fn pow(base: i64, exp: i64) -> Option<i64> {
+    match u32::try_from(exp) {
+        Ok(exp) => base.checked_pow(exp),
+        Err(_) => match base {
+            0 | 1 => Some(base),
+            -1 => Some(if exp % 2 == 0 { 1 } else { -1 }),
+            _ => None,
+        },
+    }
+}
+
+fn arithmetic<'src>(
+    lhs: Value<'src>,
+    rhs: Value<'src>,
+    int: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Result<Value<'src>, Error> {
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => match int(lhs, rhs) {
+            Some(result) => Ok(Value::Int(result)),
+            None => unsupported("integer overflow"),
+        },
+        (Value::Int(lhs), Value::Float(rhs)) => Ok(Value::Float(float(lhs as f64, rhs))),
+        (Value::Float(lhs), Value::Int(rhs)) => Ok(Value::Float(float(lhs, rhs as f64))),
+        (Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(float(lhs, rhs))),
+        (lhs, rhs) => runtime(format!(
+            "Expected Int or Float, but got {} and {}",
+            lhs.typename(),
+            rhs.typename()
+        )),
+    }
+}
+
+fn compare<'src>(
+    lhs: Value<'src>,
+    rhs: Value<'src>,
+    test: fn(std::cmp::Ordering) -> bool,
+) -> Result<Value<'src>, Error> {
+    let ordering = match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(&rhs)),
+        (Value::Int(lhs), Value::Float(rhs)) => (lhs as f64).partial_cmp(&rhs),
+        (Value::Float(lhs), Value::Int(rhs)) => lhs.partial_cmp(&(rhs as f64)),
+        (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(&rhs),
+        (lhs, rhs) => {
+            return runtime(format!(
+                "Expected Int or Float, but got {} and {}",
+                lhs.typename(),
+                rhs.typename()
+            ))
+        }
+    };
+    // Every comparison with NaN is false.
+    Ok(Value::Bool(ordering.is_some_and(test)))
+}
+
+fn bitwise<'src>(
+    lhs: Value<'src>,
+    rhs: Value<'src>,
+    op: fn(i64, i64) -> Option<i64>,
+) -> Result<Value<'src>, Error> {
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => match op(lhs, rhs) {
+            Some(result) => Ok(Value::Int(result)),
+            None => unsupported("shift out of range"),
+        },
+        (lhs, rhs) => runtime(format!(
+            "Expected Int, but got {} and {}",
+            lhs.typename(),
+            rhs.typename()
+        )),
+    }
+}
+
+fn concat<'src>(lhs: Value<'src>, rhs: Value<'src>) -> Result<Value<'src>, Error> {
+    fn to_string(value: Value) -> Result<String, Error> {
+        match value {
+            Value::Int(x) => Ok(x.to_string()),
+            Value::Float(x) => Ok(x.to_string()),
+            Value::String(x) => Ok(x),
+            Value::Bool(x) => Ok(x.to_string()),
+            Value::Nil => Ok("nil".to_string()),
+            value => runtime(format!(
+                "Expected String or Stringable Object, but got {}",
+                value.typename()
+            )),
+        }
+    }
+    Ok(Value::String(to_string(lhs)? + &to_string(rhs)?))
+}
@@ -0,0 +1,35 @@
+//! A slow tree-walking interpreter over the AST, used as an oracle for the compiler and the VM.
+//!
+//! It follows the semantics of the VM as directly as possible, without any of its machinery
+//! (locals slots, jumps, stack), so that a program giving different results on both points to a
+//! bug in one of them. Where the VM's behavior is not settled yet (e.g. integer overflow, which
+//! panics in debug builds), the interpreter gives up with [`Error::Unsupported`] instead of
+//! guessing.
+
+use foundation::{ast::*, TextSpan};
+
+mod value;
+pub use value::{Key, Value};
+
+mod interpreter;
+pub use interpreter::eval;
+
+pub mod differential;
+pub mod generate;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The program failed, as it would on the VM (with a different message).
+    Runtime(String),
+    /// The program uses something the interpreter doesn't model, so its result says nothing.
+    Unsupported(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Runtime(message) => write!(f, "{}", message),
+            Error::Unsupported(message) => write!(f, "Unsupported: {}", message),
+        }
+    }
+}
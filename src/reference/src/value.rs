@@ -0,0 +1,146 @@
+use super::*;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+#[derive(Clone, Debug)]
+pub enum Value<'src> {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    Nil,
+    Array(Rc<RefCell<Vec<Value<'src>>>>),
+    Table(Rc<RefCell<BTreeMap<Key, Value<'src>>>>),
+    Function(Rc<Closure<'src>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    String(String),
+    Int(i64),
+    Bool(bool),
+}
+
+/// A variable, shared between the scope that declares it and the closures that capture it.
+pub(crate) type Cell<'src> = Rc<RefCell<Value<'src>>>;
+
+#[derive(Debug)]
+pub struct Closure<'src> {
+    #[allow(clippy::type_complexity)]
+    pub(crate) args: &'src [(
+        FunctArgAnnotation,
+        Pattern<'src>,
+        TextSpan,
+        Option<(Expression<'src>, TextSpan)>,
+    )],
+    pub(crate) body: &'src Chunk<'src>,
+    pub(crate) captures: Vec<(&'src str, Cell<'src>)>,
+}
+
+impl<'src> Value<'src> {
+    pub fn typename(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Nil => "nil",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+            Value::Function(_) => "function",
+        }
+    }
+
+    /// Truthiness as the VM's conditions: everything but nil and false is true.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    /// Equality as the VM's `==`: structural for arrays and tables, and an Int is never equal
+    /// to a Float.
+    pub fn equals(&self, other: &Value<'src>) -> Result<bool, Error> {
+        let equals = match (self, other) {
+            (Value::Int(lhs), Value::Int(rhs)) => lhs == rhs,
+            (Value::Float(lhs), Value::Float(rhs)) => lhs == rhs,
+            (Value::String(lhs), Value::String(rhs)) => lhs == rhs,
+            (Value::Bool(lhs), Value::Bool(rhs)) => lhs == rhs,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+                for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+                    if !lhs.equals(rhs)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            (Value::Table(lhs), Value::Table(rhs)) => {
+                let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+                for ((lhs_key, lhs), (rhs_key, rhs)) in lhs.iter().zip(rhs.iter()) {
+                    if lhs_key != rhs_key || !lhs.equals(rhs)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            (Value::Function(_), _) | (_, Value::Function(_)) => {
+                return Err(Error::Unsupported("comparing functions".to_string()))
+            }
+            _ => false,
+        };
+        Ok(equals)
+    }
+
+    /// Renders the value in the form that [`differential::render`] gives to VM objects, so that
+    /// results of both engines can be compared as strings.
+    pub fn render(&self) -> String {
+        match self {
+            Value::Int(x) => x.to_string(),
+            Value::Float(x) => format!("{:?}", x),
+            Value::String(x) => format!("{:?}", x),
+            Value::Bool(x) => x.to_string(),
+            Value::Nil => "nil".to_string(),
+            Value::Array(array) => {
+                let items = array.borrow().iter().map(Value::render).collect::<Vec<_>>();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Table(table) => {
+                let mut fields = table
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", key.render(), value.render()))
+                    .collect::<Vec<_>>();
+                fields.sort();
+                format!("{{{}}}", fields.join(", "))
+            }
+            Value::Function(_) => "<function>".to_string(),
+        }
+    }
+}
+
+impl Key {
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::String(string) => Ok(Key::String(string)),
+            Value::Int(int) => Ok(Key::Int(int)),
+            Value::Bool(boolean) => Ok(Key::Bool(boolean)),
+            value => Err(Error::Runtime(format!(
+                "Expected `string`, `int` or `bool` as table key, got `{}`",
+                value.typename()
+            ))),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            Key::String(string) => format!("{:?}", string),
+            Key::Int(int) => int.to_string(),
+            Key::Bool(boolean) => boolean.to_string(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use reference::{
+    differential::{self, Outcome},
+    generate,
+};
+
+fn assert_agree(source: &str) -> bool {
+    match differential::run(source) {
+        Outcome::Agree => true,
+        Outcome::Skipped(_) => false,
+        Outcome::Diverged { vm, reference } => panic!(
+            "The VM and the reference interpreter diverge on\n{}\nvm: {:?}\nreference: {:?}",
+            source, vm, reference
+        ),
+    }
+}
+
+#[test]
+fn handwritten_programs() {
+    let programs = [
+        "return [not true, not false, true and false, false or true]",
+        "return [7 / 2, -7 / 2, -7 % 3, 7.0 / 2, 1 == 1.0, \"a\" .. 1.5 .. nil]",
+        "var a = 1 func f() a = a + 10 return a end return [f(), f(), a]",
+        "var a = 1 func f() return a end var a = 2 return [f(), a]",
+        "func fib(n) if n < 2 then return n end return fib(n - 1) + fib(n - 2) end return fib(15)",
+        "var t = { x = 1 } t.y = \"s\" t[\"x\"] ??= 2 t.z ??= true return t",
+        "var x = 1 var y = 2 x, y = y, x return [x, y]",
+        "var s = 0 for x in [1, 2, 3] do s = s * 10 + x end return s",
+        "var s = \"\" for c in \"abc\" do s = c .. s end return s",
+        "for x in 1 do end return 0",
+        "var s = 0 for i = 10, 1, -3 do s = s * 100 + i end for i = 1, 0 do s = 0 end return s",
+        "for i = 1, 2, 0 do end return 0",
+        "for i = 1, 2.5 do end return 0",
+        "return 1 + true",
+        "return 1 / 0",
+    ];
+    for source in programs {
+        assert_agree(source);
+    }
+}
+
+#[test]
+fn generated_programs() {
+    let compared = (0..500)
+        .filter(|seed| assert_agree(&generate::program(*seed)))
+        .count();
+    // Programs using something the reference interpreter doesn't model are skipped, but most of
+    // them must be compared for the test to mean anything.
+    assert!(
+        compared >= 400,
+        "only {} of 500 programs were compared",
+        compared
+    );
+}
@@ -0,0 +1,430 @@
+use lico_core::{compiler, lexer, parser, vm};
+
+fn compile(source: &str) -> String {
+    let (tokens, errors) = lexer::parse(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (program, errors) = parser::parse(&tokens);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let code = compiler::compile(&program).unwrap();
+    format!("{:?}", code)
+}
+
+#[test]
+fn output_is_deterministic() {
+    // Many captured builtins and nested closures, whose captures are collected in hash maps.
+    let source = r#"
+        var config = { name = "app", ports = [1, 2, 3], nested = { a = 1, b = 2 } }
+        func make(base)
+            var zeta = 1
+            var alpha = 2
+            var mid = 3
+            return func(x)
+                var total = base + x + zeta + alpha + mid
+                for port in config.ports do
+                    total = total + port
+                end
+                return total
+            end
+        end
+        var add = make(10)
+        var f = compose(partial(add, 1), inspect)
+        var g = memoize(add)
+        with { close = func(self) end } as r do
+            println(f(2), g(3), glob_match("*.txt", "a.txt"))
+        end
+        eprint(read_line, exit, args, os, time, clock, fs, io, env, term)
+    "#;
+    let first = compile(source);
+    for _ in 0..20 {
+        assert_eq!(compile(source), first);
+    }
+}
+
+#[test]
+fn string_constants_are_shared() {
+    use vm::code::Code;
+
+    fn strings(code: &[Code], out: &mut Vec<vm::runtime::StringObject>) {
+        for code in code {
+            match code {
+                Code::LoadString(string) if string.as_str() == "shared" => out.push(string.clone()),
+                Code::MakeClosure(proto) => strings(&proto.code, out),
+                _ => {}
+            }
+        }
+    }
+
+    let source = r#"
+        var a = "shared"
+        var f = func() return "shared" end
+        return a == f() and { shared = 1 }.shared == 1
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    let code = compiler::compile(&program).unwrap();
+    let mut found = Vec::new();
+    strings(&code, &mut found);
+    assert!(found.len() >= 3, "{:?}", found);
+    assert!(found.iter().all(|string| string.ptr_eq(&found[0])));
+
+    // Loading bytecode shares them the same way.
+    let code = Code::deserialize(&Code::serialize(&code)).unwrap();
+    let mut loaded = Vec::new();
+    strings(&code, &mut loaded);
+    assert_eq!(loaded.len(), found.len());
+    assert!(loaded.iter().all(|string| string.ptr_eq(&loaded[0])));
+}
+
+#[test]
+fn constant_folding() {
+    let code = compile(
+        r#"
+        var seconds = 2 * 60 * 60
+        var name = "a" .. "b" .. 1
+        var check = not (1.5 < 2) != (-3 == 0 - 3)
+        var fails = 1 / 0
+        var overflows = 9223372036854775807 + 1
+        var flags = (1 << 4 | 0b11) & ~1
+        var shifts = 1 << 64
+        var powers = 2 ** 10 // 3
+        var huge = 2 ** 64
+        "#,
+    );
+    assert!(code.contains("LoadInt(7200), MakeLocal"), "{}", code);
+    assert!(code.contains(r#"LoadString(StringObject("ab1")), MakeLocal"#));
+    assert!(code.contains("LoadBool(true), MakeLocal"));
+    // Left to fail when the program runs.
+    assert!(code.contains("LoadInt(1), LoadInt(0), Div"));
+    assert!(code.contains("LoadInt(9223372036854775807), LoadInt(1), Add"));
+    assert!(code.contains("LoadInt(18), MakeLocal"));
+    assert!(code.contains("LoadInt(1), LoadInt(64), ShiftL"));
+    assert!(code.contains("LoadInt(341), MakeLocal"));
+    assert!(code.contains("LoadInt(2), LoadInt(64), Pow"));
+}
+
+#[test]
+fn numeric_literals() {
+    let code =
+        compile("var mask = 0xFF_00 var bits = 0b1010 var big = 1_000_000 var small = 1.5e-3");
+    assert!(code.contains("LoadInt(65280), MakeLocal"), "{}", code);
+    assert!(code.contains("LoadInt(10), MakeLocal"), "{}", code);
+    assert!(code.contains("LoadInt(1000000), MakeLocal"), "{}", code);
+    assert!(code.contains("LoadFloat(0.0015), MakeLocal"), "{}", code);
+}
+
+#[test]
+fn register_code() {
+    use vm::{
+        code::Code,
+        runtime::{Object, Runtime},
+    };
+
+    fn run(source: &str, registers: bool) -> (Vec<Code>, Object, u64) {
+        let (tokens, _) = lexer::parse(source);
+        let (program, _) = parser::parse(&tokens);
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        (code, value, runtime.stats.instructions)
+    }
+
+    let source = r#"
+        var sum = 0
+        var i = 0
+        while i < 1000 do
+            if i % 3 == 0 then
+                sum = sum + i * 2
+            else
+                sum = sum - 1
+            end
+            i = i + 1
+        end
+        var f = func(x) return x * x + sum end
+        return f(i)
+    "#;
+    let (stack_code, stack_value, stack_count) = run(source, false);
+    let (register_code, register_value, register_count) = run(source, true);
+    assert_eq!(stack_value, Object::Int(1333000));
+    assert_eq!(register_value, stack_value);
+    assert!(!stack_code
+        .iter()
+        .any(|code| matches!(code, Code::BinaryTo(..))));
+    assert!(register_code
+        .iter()
+        .any(|code| matches!(code, Code::BinaryTo(..))));
+    assert!(
+        register_count * 2 < stack_count,
+        "{} instructions with registers, {} without",
+        register_count,
+        stack_count
+    );
+}
+
+#[test]
+fn for_in_with_registers() {
+    let source = r#"
+        var sum = 0
+        for x in [1, 2, 3] do
+            for i in x->upto(3) do
+                sum = sum + x * i
+            end
+        end
+        return sum
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    for registers in [false, true] {
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = vm::runtime::Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        assert_eq!(
+            value,
+            vm::runtime::Object::Int(25),
+            "registers: {}",
+            registers
+        );
+    }
+}
+
+#[test]
+fn break_and_continue_with_registers() {
+    let source = r#"
+        var sum = 0
+        var n = 0
+        while true do
+            n = n + 1
+            var m = n * 2
+            if m > 20 then break end
+            for i = 1, n do
+                var k = i
+                if k % 2 == 0 then continue end
+                if k > 5 then break end
+                sum = sum + k
+            end
+        end
+        return sum
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    for registers in [false, true] {
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = vm::runtime::Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        assert_eq!(
+            value,
+            vm::runtime::Object::Int(64),
+            "registers: {}",
+            registers
+        );
+    }
+}
+
+#[test]
+fn multiple_return_values_with_registers() {
+    let source = r#"
+        func minmax(a, b)
+            if a < b then return a, b end
+            return b, a
+        end
+        var sum = 0
+        var i = 0
+        while i < 10 do
+            var lo, hi = minmax(i, 5)
+            sum = sum + hi * 10 + lo
+            i = i + 1
+        end
+        return sum
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    for registers in [false, true] {
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = vm::runtime::Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        assert_eq!(
+            value,
+            vm::runtime::Object::Int(635),
+            "registers: {}",
+            registers
+        );
+    }
+}
+
+#[test]
+fn try_catch_with_registers() {
+    let source = r#"
+        var total = 0
+        var i = 0
+        while i < 4 do
+            i = i + 1
+            try
+                total = total + i
+                if i % 2 == 0 then error("even") end
+                total = total + 10
+            catch _
+                total = total - 1
+            end
+        end
+        return total
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    for registers in [false, true] {
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        assert_eq!(vm::verify(&code), Ok(()), "registers: {}", registers);
+        let mut runtime = vm::runtime::Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        assert_eq!(
+            value,
+            vm::runtime::Object::Int(28),
+            "registers: {}",
+            registers
+        );
+    }
+}
+
+#[test]
+fn metamethods_with_registers() {
+    let source = r#"
+        var a = { n = 2, __add = func(a, b) return a.n + b end, __eq = func(a, b) return true end }
+        var sum = 0
+        var i = 0
+        while i < 3 do
+            sum = a + sum
+            if a != { n = 0 } then break end
+            i = i + 1
+        end
+        return sum
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    for registers in [false, true] {
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = vm::runtime::Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        assert_eq!(
+            value,
+            vm::runtime::Object::Int(6),
+            "registers: {}",
+            registers
+        );
+    }
+}
+
+#[test]
+fn numeric_for() {
+    use vm::runtime::{Object, Runtime};
+
+    fn run(source: &str, registers: bool) -> (Object, u64) {
+        let (tokens, _) = lexer::parse(source);
+        let (program, _) = parser::parse(&tokens);
+        let options = compiler::Options {
+            registers,
+            ..Default::default()
+        };
+        let code = compiler::compile_with(&program, options).unwrap();
+        let mut runtime = Runtime::new();
+        let value = vm::execute(&code, &mut runtime).unwrap();
+        (value, runtime.stats.instructions)
+    }
+
+    let numeric_for = r#"
+        var sum = 0
+        for i = 1, 1000 do
+            sum = sum + i
+        end
+        for i = 10, 1, -3 do
+            sum = sum * 100 + i
+        end
+        return sum
+    "#;
+    let while_loop = r#"
+        var sum = 0
+        var i = 1
+        while i <= 1000 do
+            sum = sum + i
+            i = i + 1
+        end
+        i = 10
+        while i >= 1 do
+            sum = sum * 100 + i
+            i = i - 3
+        end
+        return sum
+    "#;
+    for registers in [false, true] {
+        let (value, count) = run(numeric_for, registers);
+        let (while_value, while_count) = run(while_loop, registers);
+        assert_eq!(
+            value,
+            Object::Int(50050010070401),
+            "registers: {}",
+            registers
+        );
+        assert_eq!(while_value, value);
+        // The counter is compared and advanced by a single instruction.
+        assert!(
+            count * 3 < while_count * 2,
+            "{} instructions for numeric for, {} for while",
+            count,
+            while_count
+        );
+    }
+}
+
+#[test]
+fn block_ending_in_nested_return() {
+    // The `then` ends with an `if` whose `else` returns, but its other branch goes on, so the
+    // locals of the `then` are still dropped and the function still returns at the end.
+    let source = r#"
+        func f(p, q)
+            if p then
+                var a = 1
+                if q then
+                    a = 2
+                else
+                    var b = 3
+                    return b
+                end
+            end
+            var c = 4
+            if p then
+                c = 5
+            else
+                return c
+            end
+        end
+        return [f(true, true), f(true, false), f(false, false)]
+    "#;
+    let (tokens, _) = lexer::parse(source);
+    let (program, _) = parser::parse(&tokens);
+    let code = compiler::compile(&program).unwrap();
+    assert_eq!(vm::verify(&code), Ok(()));
+    let mut runtime = vm::runtime::Runtime::new();
+    let value = vm::execute(&code, &mut runtime).unwrap();
+    assert_eq!(value.to_string(), "[nil, 3, 4]");
+}
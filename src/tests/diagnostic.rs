@@ -0,0 +1,172 @@
+use lico_core::{render, Diagnostic, Engine, TextSpan};
+
+fn diagnostics(source: &str) -> String {
+    let diagnostics = match Engine::new().eval(source) {
+        Ok(_) => Vec::new(),
+        Err(err) => err.diagnostics(),
+    };
+    render(&diagnostics, "main.lico", source)
+}
+
+#[test]
+fn syntax_errors() {
+    let source = "if x print(1) end\nvar t = {a 1}\n";
+    assert_eq!(
+        diagnostics(source),
+        [
+            "error: Expected then, found `print`",
+            " --> main.lico:1:6",
+            "  |",
+            "1 | if x print(1) end",
+            "  |      ^^^^^ expected then",
+            "",
+            "error: Expected =, found `1`",
+            " --> main.lico:2:12",
+            "  |",
+            "2 | var t = {a 1}",
+            "  |            ^ expected =",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn misused_operator() {
+    // `||` is the empty parameter list of a lambda, not the logical or.
+    assert_eq!(
+        diagnostics("var x = true || false"),
+        [
+            "error: Should use `or` for logical or",
+            " --> main.lico:1:14",
+            "  |",
+            "1 | var x = true || false",
+            "  |              ^^",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn compile_error_with_help() {
+    assert_eq!(
+        diagnostics("var x = 1\nprint(x + y)"),
+        [
+            "error: undefined variable `y`",
+            " --> main.lico:2:11",
+            "  |",
+            "2 | print(x + y)",
+            "  |           ^",
+            "  |",
+            "  = help: define it with `var` before using it",
+            "",
+        ]
+        .join("\n")
+    );
+    assert_eq!(
+        diagnostics("var m = require(\"m\")"),
+        [
+            "error: undefined variable `require`",
+            " --> main.lico:1:9",
+            "  |",
+            "1 | var m = require(\"m\")",
+            "  |         ^^^^^^^",
+            "  |",
+            "  = help: load a module with `import \"name\"`",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn runtime_error_labels_calls() {
+    let source = "func f(n)\n  return n + \"a\"\nend\nvar x = f(1)\n";
+    assert_eq!(
+        diagnostics(source),
+        [
+            "error: Expected Int or Float, but got Int(1) and String(StringObject(\"a\"))",
+            " --> main.lico:2:10",
+            "  |",
+            "2 |   return n + \"a\"",
+            "  |          ^^^^^^^",
+            "...",
+            "4 | var x = f(1)",
+            "  |         ---- called `f` here",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn warning() {
+    let source = "func f(a)\n  return 1\nend\nf(1)";
+    let warnings = Engine::check(source).unwrap();
+    let diagnostics = warnings.iter().map(Diagnostic::from).collect::<Vec<_>>();
+    assert_eq!(
+        render(&diagnostics, "main.lico", source),
+        [
+            "warning: unused parameter `a`",
+            " --> main.lico:1:8",
+            "  |",
+            "1 | func f(a)",
+            "  |        ^",
+            "  |",
+            "  = help: if this is intended, name it `_a`",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn multiple_labels_notes_and_wide_gutter() {
+    let source = "a\nb\nc\nd\ne\nf\ng\nh\ni\nfoo(bar)\tbaz";
+    let diagnostic = Diagnostic::error("something is wrong")
+        .with_label(TextSpan::new(22, 25), "here")
+        .with_label(TextSpan::new(18, 21), "")
+        .with_label(TextSpan::new(27, 30), "and here")
+        .with_label(TextSpan::new(0, 1), "because of this")
+        .with_note("a note")
+        .with_help("a hint");
+    assert_eq!(
+        diagnostic.display_in("main.lico", source).to_string(),
+        [
+            "error: something is wrong",
+            "  --> main.lico:10:5",
+            "   |",
+            " 1 | a",
+            "   | - because of this",
+            "...",
+            "10 | foo(bar)    baz",
+            "   | ---",
+            "   |     ^^^ here",
+            "   |             --- and here",
+            "   |",
+            "   = note: a note",
+            "   = help: a hint",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn span_at_end_of_input() {
+    let source = "func f()\n";
+    let diagnostic = Diagnostic::error("Missing symbol `end`").with_label(TextSpan::new(9, 9), "");
+    assert_eq!(
+        diagnostic.display_in("main.lico", source).to_string(),
+        [
+            "error: Missing symbol `end`",
+            " --> main.lico:2:1",
+            "  |",
+            "2 |",
+            "  | ^",
+            "",
+        ]
+        .join("\n")
+    );
+}
@@ -0,0 +1,2241 @@
+use lico_core::{
+    render,
+    vm::{
+        bytecode::Program,
+        code::{Code, FunctionPrototype, LocalId},
+        runtime::{FileAccess, IntOverflow, MemoryFileSystem, Object, Permissions, Runtime},
+        ErrorKind, RuntimeError,
+    },
+    Engine, Error, ExecutionOutcome,
+};
+use std::rc::Rc;
+
+fn value(value: Object) -> Result<ExecutionOutcome, Error> {
+    Ok(ExecutionOutcome {
+        value,
+        exit_code: 0,
+    })
+}
+
+#[test]
+fn eval() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval("return 1 + 2"), value(Object::Int(3)));
+    assert_eq!(
+        engine.eval("var x = 10 return x * 2"),
+        value(Object::Int(20))
+    );
+}
+
+#[test]
+fn eval_after_error() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval("var a = 1 return a"), value(Object::Int(1)));
+    assert!(engine.eval("var b = 2 return b + 'x'").is_err());
+    assert_eq!(engine.eval("var c = 3 return c"), value(Object::Int(3)));
+}
+
+#[test]
+fn eval_with_args() {
+    let mut engine = Engine::new();
+    let res = engine.eval_with_args(
+        "return args[1] .. ' ' .. os.args->len()->to_string()",
+        &["input.txt", "--flag"],
+    );
+    assert_eq!(res, value(Object::new_string("--flag 2".to_string())));
+}
+
+#[test]
+fn eval_errors() {
+    let mut engine = Engine::new();
+    assert!(matches!(engine.eval("return )"), Err(Error::Parse(_))));
+    assert!(matches!(engine.eval("return y"), Err(Error::Compile(_))));
+    assert!(matches!(
+        engine.eval("return 1 + 'a'"),
+        Err(Error::Runtime(_))
+    ));
+}
+
+#[test]
+fn runtime_error_kinds() {
+    let kind = |source: &str| match Engine::new().eval(source) {
+        Err(Error::Runtime(err)) => {
+            assert!(err.pc.is_some(), "no pc for {:?}", source);
+            err.kind
+        }
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(kind("return 1 + 'a'"), ErrorKind::Type);
+    assert_eq!(kind("return 'abc'->len(1)"), ErrorKind::Arity);
+    assert_eq!(kind("return 1 / 0"), ErrorKind::DivisionByZero);
+    assert_eq!(kind("return fs.open('/no/such/file')"), ErrorKind::Io);
+    assert_eq!(
+        kind("return decompress('plain'->to_bytes())"),
+        ErrorKind::Io
+    );
+    assert_eq!(kind("assert(1 > 2)"), ErrorKind::User);
+
+    // `Display` gives the message alone, as before the errors had a kind.
+    let res = Engine::new().eval("return 1 + 'a'");
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.to_string() == err.message && err.message.starts_with("Expected Int or Float")
+    ));
+}
+
+#[test]
+fn array_store_index() {
+    // A negative index counts from the end, as when reading.
+    let res = Engine::new().eval("var a = [1, 2, 3] a[-1] = 9 a[0] = 7 return [a, a[-1]]");
+    assert_eq!(res.unwrap().value.to_string(), "[[7, 2, 9], 9]");
+
+    for source in [
+        "var a = [1, 2, 3] a[5] = 1",
+        "var a = [1, 2, 3] a[3] = 1",
+        "var a = [1, 2, 3] a[-4] = 1",
+        "var a = [] a[0] = 1",
+    ] {
+        let res = Engine::new().eval(source);
+        assert!(
+            matches!(&res, Err(Error::Runtime(err)) if err.kind == ErrorKind::IndexOutOfRange),
+            "{}: {:?}",
+            source,
+            res
+        );
+    }
+    let res = Engine::new().eval("var a = [1, 2, 3] a[-4] = 1");
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.message == "Index -4 is out of range for an array of length 3"
+    ));
+    let res = Engine::new()
+        .eval("var a = [1] try a[1] = 2 catch e return e.kind end")
+        .unwrap();
+    assert_eq!(res.value.to_string(), "index_out_of_range");
+}
+
+#[test]
+fn bitwise() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var a, b, n = 0b1100, 0b1010, 2
+        return [a & b, a | b, a ^ b, ~a, a << n, -a >> n, 1 << 63]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            0b1000,
+            0b1110,
+            0b0110,
+            !0b1100,
+            0b110000,
+            -3,
+            i64::MIN
+        ]))
+    );
+    let mut kind = |source: &str| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.kind,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(kind("var x = 1.5 return x & 1"), ErrorKind::Type);
+    assert_eq!(kind("var x = 'a' return ~x"), ErrorKind::Type);
+    assert_eq!(kind("var x = 64 return 1 << x"), ErrorKind::Other);
+    assert_eq!(kind("var x = -1 return 1 >> x"), ErrorKind::Other);
+}
+
+#[test]
+fn pow_and_floor_div() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var a, b = 2, 7
+        return [a ** 10, -a ** 2, a ** 3 ** 2, a ** -1, 4 ** 0.5, 3->pow(4)]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(1024),
+            Object::Int(-4),
+            Object::Int(512),
+            Object::Float(0.5),
+            Object::Float(2.0),
+            Object::Int(81),
+        ]))
+    );
+    let source = r#"
+        var a, b = 7, 2
+        return [a // b, -a // b, a // -b, -a // -b, a / -b, 7.5 // b]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(3),
+            Object::Int(-4),
+            Object::Int(-4),
+            Object::Int(3),
+            Object::Int(-3),
+            Object::Float(3.0),
+        ]))
+    );
+    let mut kind = |source: &str| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.kind,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(kind("var x = 3 return x ** 40"), ErrorKind::Overflow);
+    assert_eq!(kind("var x = 0 return 1 // x"), ErrorKind::DivisionByZero);
+    assert_eq!(kind("var x = 'a' return x ** 2"), ErrorKind::Type);
+
+    engine.runtime.int_overflow = IntOverflow::Float;
+    assert_eq!(
+        engine.eval("var x = 2 return x ** 64"),
+        value(Object::Float(18446744073709551616.0))
+    );
+}
+
+#[test]
+fn nil_coalescing_and_optional_chaining() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var calls = 0
+        var count = func() calls = calls + 1 return calls end
+        var t = { a = { b = 1 }, list = [1, 2, 3] }
+        var none = nil
+        return [
+            none ?? count(), t.a.b ?? count(), false ?? 1, none ?? nil ?? 3,
+            none?.a.b, t?.a.b, t.x?.b.c, none?->len(), t.list?->len(), t?.list[1],
+            calls,
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(1),
+            Object::Int(1),
+            Object::Bool(false),
+            Object::Int(3),
+            Object::Nil,
+            Object::Int(1),
+            Object::Nil,
+            Object::Nil,
+            Object::Int(3),
+            Object::Int(2),
+            Object::Int(1),
+        ]))
+    );
+    let source = r#"
+        var log = []
+        var none = nil
+        none?->push(1)
+        log?->push(2)
+        none?.f(3)
+        return [log->len(), log[0]]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![Object::Int(1), Object::Int(2)]))
+    );
+}
+
+#[test]
+fn logical_operators_with_truthiness() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var calls = 0
+        var count = func() calls = calls + 1 return calls end
+        var none = nil
+        var a = 1 and 'x'
+        var b = none and count()
+        var c = false or none
+        var d = none or count()
+        var e = 'y' or count()
+        var f = [not none, not 0, not false]
+        var g = 0
+        if '' then g = g + 1 end
+        if none then g = g + 10 end
+        var h = false
+        h ||= 'set'
+        return [a, b, c, d, e, f[0], f[1], f[2], g, h, calls]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::new_string("x".to_string()),
+            Object::Nil,
+            Object::Nil,
+            Object::Int(1),
+            Object::new_string("y".to_string()),
+            Object::Bool(true),
+            Object::Bool(false),
+            Object::Bool(true),
+            Object::Int(1),
+            Object::new_string("set".to_string()),
+            Object::Int(1),
+        ]))
+    );
+}
+
+#[test]
+fn spread() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var rest = [2, 3]
+        var sum = func(...xs) var s = 0 for x in xs do s = s + x end return s end
+        var t = { mul = func(self, x, y) return x * y end }
+        var copy = [...rest]
+        copy->push(4)
+        return [
+            [0, ...rest, ...[], 9]->len(), sum(...rest), sum(1, ...rest, 10), t->mul(...rest),
+            5 |> sum(...rest), rest->len(),
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(4),
+            Object::Int(5),
+            Object::Int(16),
+            Object::Int(6),
+            Object::Int(10),
+            Object::Int(2),
+        ]))
+    );
+    match engine.eval("var x = 1 return [...x]") {
+        Err(Error::Runtime(err)) => assert_eq!(err.kind, ErrorKind::Type),
+        res => panic!("expected a type error, got {:?}", res),
+    }
+}
+
+#[test]
+fn if_expression() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var sign = func(x) return if x < 0 then -1 elif x == 0 then 0 else 1 end end
+        var calls = 0
+        var count = func() calls += 1 return calls end
+        var picked = if nil then count() else if 0 then "zero" else count() end end
+        return [sign(-5), sign(0), sign(7), picked, calls, (if true then 1 else 2 end) + 1]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(-1),
+            Object::Int(0),
+            Object::Int(1),
+            Object::new_string("zero".to_string()),
+            Object::Int(0),
+            Object::Int(2),
+        ]))
+    );
+}
+
+#[test]
+fn in_operator() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var arr = [1, "two", [3]]
+        var t = { name = "lico", [2] = true }
+        return [
+            1 in arr, "two" in arr, [3] in arr, 4 in arr,
+            "name" in t, 2 in t, "age" in t,
+            "ic" in "lico", "x" in "lico", "" in "",
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Bool(true),
+            Object::Bool(true),
+            Object::Bool(true),
+            Object::Bool(false),
+            Object::Bool(true),
+            Object::Bool(true),
+            Object::Bool(false),
+            Object::Bool(true),
+            Object::Bool(false),
+            Object::Bool(true),
+        ]))
+    );
+    for source in ["return 1 in 2", "return 1 in \"abc\""] {
+        match engine.eval(source) {
+            Err(Error::Runtime(err)) => assert_eq!(err.kind, ErrorKind::Type),
+            res => panic!("expected a type error, got {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn slice_and_negative_index() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var arr = [1, 2, 3, 4, 5]
+        var s = "héllo"
+        return [
+            arr[1:4]->len(), arr[1:4][0], arr[:2][1], arr[-2:][0], arr[3:1]->len(),
+            arr[-1], arr[-5], arr[-6], arr[10:]->len(),
+            s[1:3], s[:-1], s[-3:], s[-1], s[0],
+        ]
+    "#;
+    let string = |s: &str| Object::new_string(s.to_string());
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::Int(3),
+            Object::Int(2),
+            Object::Int(2),
+            Object::Int(4),
+            Object::Int(0),
+            Object::Int(5),
+            Object::Int(1),
+            Object::Nil,
+            Object::Int(0),
+            string("él"),
+            string("héll"),
+            string("llo"),
+            string("o"),
+            string("h"),
+        ]))
+    );
+    match engine.eval("return [1, 2][\"a\":]") {
+        Err(Error::Runtime(err)) => assert_eq!(err.kind, ErrorKind::Type),
+        res => panic!("expected a type error, got {:?}", res),
+    }
+}
+
+#[test]
+fn int_overflow() {
+    let mut engine = Engine::new();
+    let mut kind = |source: &str| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.kind,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(
+        kind("var x = 9223372036854775807 return x + 1"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = -9223372036854775807 return x - 2"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = 4611686018427387904 return x * 2"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = -9223372036854775807 - 1 return x / -1"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = -9223372036854775807 - 1 return x % -1"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = -9223372036854775807 - 1 return -x"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(
+        kind("var x = -9223372036854775807 - 1 return x->abs()"),
+        ErrorKind::Overflow
+    );
+    assert_eq!(kind("var x = 0 return 1 % x"), ErrorKind::DivisionByZero);
+
+    // The errors are catchable.
+    let source = r#"
+        try
+            var x = 9223372036854775807
+            return x + 1
+        catch err
+            return err.kind
+        end
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::new_string("overflow".to_string()))
+    );
+
+    engine.runtime.int_overflow = IntOverflow::Float;
+    assert_eq!(
+        engine.eval("var x = 9223372036854775807 return [x + 1, x * 2, x - 1]"),
+        value(Object::from(vec![
+            Object::Float(9223372036854775808.0),
+            Object::Float(18446744073709551614.0),
+            Object::Int(9223372036854775806),
+        ]))
+    );
+    assert_eq!(
+        engine.eval("var x = -9223372036854775807 - 1 return [-x, x->abs()]"),
+        value(Object::from(vec![
+            Object::Float(9223372036854775808.0),
+            Object::Float(9223372036854775808.0),
+        ]))
+    );
+}
+
+#[test]
+fn runtime_error_traceback() {
+    let source = r#"
+        func inner(x)
+            return x + "a"
+        end
+        func outer(x)
+            var y = inner(x)
+            return y
+        end
+        var f = func() outer(1) end
+        return f()
+    "#;
+    let Err(Error::Runtime(err)) = Engine::new().eval(source) else {
+        panic!("expected a runtime error");
+    };
+    let functions = err
+        .traceback
+        .iter()
+        .map(|frame| frame.function.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(functions, ["inner", "outer", "<anonymous>", "<main>"]);
+    assert_eq!(err.pc, Some(err.traceback[0].pc));
+
+    assert_eq!(err.to_string(), err.message);
+    let report = format!("{:#}", err);
+    assert!(report.starts_with(&format!(
+        "{}\nTraceback (most recent call last):\n  <main> at pc ",
+        err.message
+    )));
+    assert!(report.ends_with(&format!("\n  inner at pc {}", err.traceback[0].pc)));
+}
+
+#[test]
+fn runtime_error_locations() {
+    let source = "func inner(x)
+    return x + \"a\"
+end
+var y = inner(1)
+return y";
+    let Err(Error::Runtime(err)) = Engine::new().eval(source) else {
+        panic!("expected a runtime error");
+    };
+    let spans = err
+        .traceback
+        .iter()
+        .map(|frame| {
+            let range = frame.span.unwrap().to_range();
+            &source[range.start as usize..range.end as usize]
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(spans, ["x + \"a\"", "inner(1)"]);
+    assert_eq!(
+        err.display_in("main.lico", source).to_string(),
+        format!(
+            "{}\nTraceback (most recent call last):\n  <main> at main.lico:4:9\n  inner at main.lico:2:12",
+            err.message
+        )
+    );
+
+    // Bytecode keeps the locations, and the source they are in.
+    let bytecode = Engine::compile(source).unwrap();
+    let program = Program::deserialize(&bytecode).unwrap();
+    assert_eq!(program.source, source);
+    let Err(Error::Runtime(loaded)) = Engine::new().run_program(&program) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(loaded.traceback, err.traceback);
+}
+
+#[test]
+fn assert_location() {
+    let source = "func check(n)
+    assert(n > 0, \"positive\")
+end
+check(0)";
+    let Err(Error::Runtime(err)) = Engine::new().eval(source) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.kind, ErrorKind::User);
+    assert_eq!(err.message, "Assertion failed: `n > 0`: positive");
+    let range = err.traceback[0].span.unwrap().to_range();
+    assert_eq!(&source[range.start as usize..range.end as usize], "n > 0");
+}
+
+#[test]
+fn deep_recursion() {
+    // Far deeper than the native stack of a test thread would allow if calls recursed on it.
+    let source = r#"
+        func sum(n)
+            if n == 0 then
+                return 0
+            end
+            return n + sum(n - 1)
+        end
+        var count = nil
+        count = memoize(func(n)
+            if n == 0 then
+                return 0
+            end
+            return count(n - 1) + 1
+        end)
+        return [sum(50000), count(50000)]
+    "#;
+    let res = Engine::new().eval(source).unwrap().value;
+    assert_eq!(res.to_string(), "[1250025000, 50000]");
+
+    let mut engine = Engine::new();
+    engine.runtime.max_call_depth = 100;
+    let res = engine.eval("func f(n) return 1 + f(n + 1) end return f(0)");
+    let Err(Error::Runtime(err)) = res else {
+        panic!("expected a runtime error, got {:?}", res);
+    };
+    assert_eq!(err.kind, ErrorKind::StackOverflow);
+    // The 100 frames of `f` are kept as one.
+    let frames = err
+        .traceback
+        .iter()
+        .map(|frame| (frame.function.as_str(), frame.repeated))
+        .collect::<Vec<_>>();
+    assert_eq!(frames, [("f", 99), ("<main>", 0)]);
+    assert!(format!("{:#}", err).ends_with("\n  [previous frame repeated 99 more times]"));
+    assert!(engine.runtime.call_stack.is_empty());
+
+    // Frames that don't repeat one after another are kept up to a limit.
+    engine.runtime.max_call_depth = 1000;
+    let source = "var b = nil
+func a(n) return 1 + b(n) end
+b = func(n) return 1 + a(n) end
+return a(0)";
+    let res = engine.eval(source);
+    let Err(Error::Runtime(err)) = res else {
+        panic!("expected a runtime error, got {:?}", res);
+    };
+    assert_eq!(err.traceback.len(), RuntimeError::MAX_TRACEBACK_LEN);
+    assert_eq!(err.omitted_frames, 1001 - RuntimeError::MAX_TRACEBACK_LEN);
+    assert!(format!("{:#}", err).contains("\n  [901 more frames]\n"));
+    assert_eq!(
+        engine.eval("func f(n) return n end return f(1)"),
+        value(Object::Int(1))
+    );
+
+    // Calls made from native code still use the native stack, but fail before it overflows.
+    let source = r#"
+        var count = nil
+        count = memoize(memoize(func(n)
+            if n == 0 then
+                return 0
+            end
+            return count(n - 1) + 1
+        end))
+        return count(50000)
+    "#;
+    assert!(matches!(
+        Engine::new().eval(source),
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::StackOverflow
+    ));
+
+    // The limit is the same in every build, and the host may change it.
+    let source = r#"
+        var count = nil
+        count = memoize(memoize(func(n)
+            if n == 0 then
+                return 0
+            end
+            return count(n - 1) + 1
+        end))
+        return count(10)
+    "#;
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval(source), value(Object::Int(10)));
+    engine.runtime.max_native_depth = 5;
+    assert!(matches!(
+        engine.eval(source),
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::StackOverflow
+    ));
+}
+
+#[test]
+fn tail_call() {
+    // Tail calls reuse the frame, so they don't count towards the depth limit.
+    let mut engine = Engine::new();
+    engine.runtime.max_call_depth = 10;
+    let source = r#"
+        func sum(n, acc)
+            if n == 0 then
+                return acc
+            end
+            return sum(n - 1, acc + n)
+        end
+        return sum(100000, 0)
+    "#;
+    assert_eq!(engine.eval(source), value(Object::Int(5000050000)));
+
+    // A frame with an open `with` has to close it after the callee returns, so it stays.
+    let source = r#"
+        var log = []
+        func g()
+            log->push("g")
+        end
+        func f()
+            with { close = func(self) log->push("close") end } as r do
+                return g()
+            end
+        end
+        f()
+        log->push("after")
+        return log
+    "#;
+    let res = Engine::new().eval(source).unwrap().value;
+    assert_eq!(res.to_string(), r#"["g", "close", "after"]"#);
+}
+
+#[test]
+fn fuel() {
+    let mut engine = Engine {
+        runtime: Runtime::with_fuel(1000),
+    };
+    let res = engine.eval("while true do end");
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::FuelExhausted
+    ));
+    assert_eq!(engine.runtime.fuel(), Some(0));
+
+    engine.runtime.set_fuel(Some(1000));
+    assert_eq!(engine.eval("return 1 + 2"), value(Object::Int(3)));
+    let left = engine.runtime.fuel().unwrap();
+    assert!(left < 1000 && left > 900, "{} left", left);
+
+    engine.runtime.set_fuel(None);
+    assert_eq!(engine.eval("return 1 + 2"), value(Object::Int(3)));
+    assert_eq!(engine.runtime.fuel(), None);
+}
+
+#[test]
+fn bytecode() {
+    let source = r#"
+        func fib(n)
+            if n < 2 then return n end
+            return fib(n - 1) + fib(n - 2)
+        end
+        var words = ["b", "a"]
+        return { fib = fib(10), len = words->len(), joined = partial(func(a, b) return a .. b end, "x")("y") }
+    "#;
+    let bytecode = Engine::compile(source).unwrap();
+    let res = Engine::new().run_bytecode(&bytecode).unwrap().value;
+    assert_eq!(res, Engine::new().eval(source).unwrap().value);
+    assert_eq!(
+        res.to_string(),
+        Engine::new().eval(source).unwrap().value.to_string()
+    );
+
+    assert!(matches!(
+        Engine::compile("return y"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        Engine::new().run_bytecode(b"return 1"),
+        Err(Error::Load(_))
+    ));
+}
+
+#[test]
+fn corrupt_bytecode() {
+    let source = r#"
+        var words = { greeting = "hello", names = ["a", "b"] }
+        func greet(name) return words.greeting .. " " .. name end
+        return words.names->map(greet)
+    "#;
+    let bytecode = Engine::compile(source).unwrap();
+    for len in 0..bytecode.len() {
+        let res = Engine::new().run_bytecode(&bytecode[..len]);
+        assert!(
+            matches!(res, Err(Error::Load(_))),
+            "{} bytes: {:?}",
+            len,
+            res
+        );
+    }
+    for i in 0..bytecode.len() {
+        for bit in 0..8 {
+            let mut corrupt = bytecode.clone();
+            corrupt[i] ^= 1 << bit;
+            let res = Engine::new().run_bytecode(&corrupt);
+            assert!(
+                matches!(res, Err(Error::Load(_))),
+                "bit {} of byte {}: {:?}",
+                bit,
+                i,
+                res
+            );
+        }
+    }
+}
+
+/// The code made by breaking one instruction of `code` in each way that upsets the locals or the
+/// stack, in it or in the functions it creates.
+fn mutate(code: &[Code]) -> Vec<Vec<Code>> {
+    let mut mutants = Vec::new();
+    for (i, instr) in code.iter().enumerate() {
+        let mut replacements = vec![
+            Code::Nop,
+            Code::UnloadTop,
+            Code::MakeLocal,
+            Code::DropLocal(1),
+            Code::LoadLocal(LocalId(i)),
+            Code::MakeNamed,
+            Code::MakeTable(1),
+        ];
+        match instr {
+            Code::LoadLocal(id) => replacements.push(Code::LoadLocal(LocalId(id.0 + 1))),
+            Code::SetLocal(id) => replacements.push(Code::SetLocal(LocalId(id.0 + 1))),
+            Code::IterNext(id, offset) => {
+                replacements.push(Code::IterNext(LocalId(id.0 + 1), *offset))
+            }
+            Code::ForPrep(id, offset) => {
+                replacements.push(Code::ForPrep(LocalId(id.0 + 1), *offset))
+            }
+            Code::DropLocal(count) => replacements.push(Code::DropLocal(count + 1)),
+            Code::MakeTable(len) => replacements.push(Code::MakeTable(len + 1)),
+            Code::MakeClosure(proto) => {
+                for code in mutate(&proto.code) {
+                    replacements.push(Code::MakeClosure(Rc::new(FunctionPrototype {
+                        code: code.into(),
+                        spans: Rc::new([]),
+                        ..(**proto).clone()
+                    })));
+                }
+            }
+            _ => {}
+        }
+        for replacement in replacements {
+            let mut mutant = code.to_vec();
+            mutant[i] = replacement;
+            mutants.push(mutant);
+        }
+        let mut mutant = code.to_vec();
+        mutant.remove(i);
+        mutants.push(mutant);
+    }
+    mutants
+}
+
+#[test]
+fn mutated_bytecode() {
+    let source = r#"
+        var total = 0
+        for i = 1, 3 do total = total + i end
+        for x in [4, 5] do total = total + x end
+        var point = { x = 1, y = total }
+        func add(a, ...rest)
+            var sum = a
+            for r in rest do sum = sum + r end
+            return sum + point.x
+        end
+        try
+            var t = { a = add(1, 2, 3) }
+            error(t.a)
+        catch e
+            total = total + e.message->len()
+        end
+        return [total, point.y, add(total)]
+    "#;
+    let bytecode = Engine::compile(source).unwrap();
+    let code = Code::deserialize(&bytecode).unwrap();
+    let broken = mutate(&code);
+    assert!(broken.len() > 500, "{}", broken.len());
+    // Each either fails to load or run, or runs; none may panic.
+    for mutant in broken {
+        let mut engine = Engine::new();
+        engine.runtime.set_fuel(Some(10_000));
+        let _ = engine.run_bytecode(&Code::serialize(&mutant));
+    }
+}
+
+#[test]
+fn eval_expr() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval_expr("1 + 2 * 3"), Ok(Object::Int(7)));
+    assert_eq!(
+        engine.eval_expr("# a comment\n{ a = [1, 2], b = 'x' .. 'y' }.b"),
+        Ok(Object::new_string("xy".to_string()))
+    );
+    assert_eq!(
+        engine.eval_expr("func(x) return x * 2 end(21)"),
+        Ok(Object::Int(42))
+    );
+    assert_eq!(
+        engine.eval_expr("compose(func(x) return x + 1 end)(1)"),
+        Ok(Object::Int(2))
+    );
+
+    // Statements, I/O builtins, and trailing input are rejected.
+    assert!(matches!(
+        engine.eval_expr("var x = 1"),
+        Err(Error::Parse(_))
+    ));
+    assert!(matches!(engine.eval_expr("1 2"), Err(Error::Parse(_))));
+    assert!(matches!(engine.eval_expr(""), Err(Error::Parse(_))));
+    assert!(matches!(
+        engine.eval_expr("exit(1)"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        engine.eval_expr("fs.read('/etc/passwd')"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        engine.eval_expr("1 + nil"),
+        Err(Error::Runtime(_))
+    ));
+}
+
+#[test]
+fn eval_config() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var base = { host = "localhost", port = 8000 }
+        var servers = []
+        for i in 1->upto(3) do
+            servers->push({ host = base.host, port = base.port + i })
+        end
+        return { name = "app", servers = servers }
+    "#;
+    let config = engine.eval_config(source).unwrap();
+    let Object::Table(config) = config else {
+        panic!("config should be a table");
+    };
+    let Some(Object::Array(servers)) = config.borrow().get("servers").cloned() else {
+        panic!("servers should be an array");
+    };
+    let Object::Table(last) = servers.borrow()[2].clone() else {
+        panic!("server should be a table");
+    };
+    assert_eq!(last.borrow().get("port"), Some(&Object::Int(8003)));
+
+    // Loops and recursion that never end run out of fuel.
+    let fuel_exhausted = |res: Result<Object, Error>| matches!(res, Err(Error::Runtime(err)) if err.kind == ErrorKind::FuelExhausted);
+    assert!(fuel_exhausted(
+        engine.eval_config("while true do end return {}")
+    ));
+    assert!(fuel_exhausted(
+        engine.eval_config("func f(n) return f(n) end return {x = f(1)}")
+    ));
+    assert!(fuel_exhausted(
+        engine.eval_config("for i = 0, 9223372036854775807 do end return {}")
+    ));
+    assert!(fuel_exhausted(
+        engine.eval_expr("func() while true do end end()")
+    ));
+    assert_eq!(engine.runtime.fuel(), None);
+    assert!(matches!(
+        engine.eval_config("return {id = uuid()}"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        engine.eval_config("println(1) return {}"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        engine.eval_config("return 1"),
+        Err(Error::Runtime(_))
+    ));
+}
+
+#[test]
+fn for_in() {
+    let mut engine = Engine::new();
+    let string = |s: &str| value(Object::new_string(s.to_string()));
+    assert_eq!(
+        engine.eval(
+            r#"
+            var s = ""
+            for x in [1, 2, 3] do s = s .. x end
+            for c in "héy" do s = s .. "," .. c end
+            for x in [] do s = s .. "never" end
+            return s
+            "#
+        ),
+        string("123,h,é,y")
+    );
+    assert_eq!(
+        engine
+            .eval(
+                r#"
+            var keys = 0
+            var sum = 0
+            for pair in { a = 1, b = 2, c = 3 } do
+                keys = keys + pair->len() - 1
+                sum = sum + pair[1]
+            end
+            return [keys, sum]
+            "#
+            )
+            .unwrap()
+            .value,
+        Object::new_array(vm::runtime::ArrayObject::new(vec![
+            Object::Int(3),
+            Object::Int(6)
+        ]))
+    );
+    assert_eq!(
+        engine.eval(
+            r#"
+            var s = ""
+            var down = 10->downto(1)
+            down->step(-4)
+            for i in down do s = s .. i .. " " end
+            for i in 1->upto(0) do s = s .. "never" end
+            return s
+            "#
+        ),
+        string("10 6 2 ")
+    );
+
+    // Tables drive the iteration with `__iter` and `__next`, until `__next` returns nil.
+    assert_eq!(
+        engine.eval(
+            r#"
+            var n = 0
+            var counter = {
+                __next = func(self)
+                    n = n + 1
+                    if n > 3 then return nil end
+                    return n * 10
+                end,
+            }
+            var bag = { items = ["x", "y"], __iter = func(self) return self.items end }
+            var s = ""
+            for x in counter do s = s .. x .. " " end
+            for x in bag do s = s .. x end
+            return s
+            "#
+        ),
+        string("10 20 30 xy")
+    );
+
+    let mut message = |source| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.message,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(message("for x in 1 do end"), "int is not iterable");
+    assert_eq!(
+        message("for i = 1, 3, 0 do end"),
+        "The step of a numeric for must not be 0"
+    );
+    assert_eq!(
+        message("var a = [1, 2] for x in a do a->push(x) end"),
+        "array modified during iteration"
+    );
+}
+
+#[test]
+fn break_and_continue() {
+    let mut engine = Engine::new();
+    let string = |s: &str| value(Object::new_string(s.to_string()));
+    assert_eq!(
+        engine.eval(
+            r#"
+            var s = ""
+            var n = 0
+            while true do
+                n = n + 1
+                if n % 2 == 0 then continue end
+                if n > 7 then break end
+                s = s .. n
+            end
+            for x in [1, 2, 3, 4] do
+                if x == 2 then continue end
+                if x == 4 then break end
+                s = s .. "," .. x
+            end
+            for i = 1, 10 do
+                if i == 3 then continue end
+                if i == 5 then break end
+                s = s .. ";" .. i
+            end
+            return s
+            "#
+        ),
+        string("1357,1,3;1;2;4")
+    );
+
+    // Locals made inside the body are dropped on the way out, at any depth and in nested loops.
+    assert_eq!(
+        engine.eval(
+            r#"
+            var s = ""
+            for i = 1, 3 do
+                var a = i
+                for j = 1, 3 do
+                    var b = a * 10 + j
+                    if j == 2 then
+                        var c = b
+                        do
+                            var d = c
+                            continue
+                        end
+                    end
+                    if i == 3 then
+                        var e = b
+                        break
+                    end
+                    s = s .. b .. " "
+                end
+                var f = a
+                s = s .. "| "
+            end
+            return s
+            "#
+        ),
+        string("11 13 | 21 23 | | ")
+    );
+
+    // Leaving a `with` block early still closes its resource.
+    assert_eq!(
+        engine.eval(
+            r#"
+            var closed = 0
+            for i = 1, 3 do
+                with { close = func(self) closed = closed + 1 end } as r do
+                    if i == 2 then continue end
+                    if i == 3 then break end
+                end
+            end
+            return closed
+            "#
+        ),
+        value(Object::Int(3))
+    );
+
+    assert!(matches!(engine.eval("break"), Err(Error::Compile(_))));
+    assert!(matches!(
+        engine.eval("if true then continue end"),
+        Err(Error::Compile(_))
+    ));
+    assert!(matches!(
+        engine.eval("while true do func f() break end end"),
+        Err(Error::Compile(_))
+    ));
+}
+
+#[test]
+fn multiple_return_values() {
+    let mut engine = Engine::new();
+    let mut eval = |source| engine.eval(source).unwrap().value.to_string();
+    let source = r#"
+        func divmod(a, b) return (a - a % b) / b, a % b end
+        func none() end
+        var q, r = divmod(17, 5)
+        var x, y, z = divmod(9, 4)
+        var first = divmod(9, 4)
+        var n, m = none()
+        var p, o, l = 1, divmod(7, 2)
+        var values = [q, r, x, y, z, n, m, p, o, l]
+        q, r = divmod(1, 1)
+        return [values, first, q, r]
+    "#;
+    assert_eq!(
+        eval(source),
+        "[[3, 2, 2, 1, nil, nil, nil, 1, 3, 1], 2, 1, 0]"
+    );
+
+    // The values go on through `return f()`, even where the frame is kept to close a resource.
+    let source = r#"
+        func pair() return "a", "b" end
+        func tail() return pair() end
+        func kept()
+            with { close = func(self) end } as r do
+                return pair()
+            end
+        end
+        var a, b = tail()
+        var c, d = kept()
+        var e, f = "x"->len()
+        return [a, b, c, d, e, f]
+    "#;
+    assert_eq!(eval(source), r#"["a", "b", "a", "b", 1, nil]"#);
+}
+
+#[test]
+fn variadic_functions() {
+    let mut engine = Engine::new();
+    let source = r#"
+        func count(...xs) return xs->len() end
+        func head(first, ...rest) return [first, rest] end
+        var t = { nested = {} }
+        func t.nested.pack(...xs) return xs end
+        var sum = func(...xs)
+            var total = 0
+            for x in xs do total = total + x end
+            return total
+        end
+        return [count(), count(1, nil, 3), head(1, 2, 3), head(), t.nested.pack(4, 5), sum(1, 2, 3)]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        "[0, 3, [1, [2, 3]], [nil, []], [4, 5], 6]"
+    );
+}
+
+#[test]
+fn default_parameters() {
+    let mut engine = Engine::new();
+    let source = r#"
+        func greet(name, punct = "!") return "Hello, " .. name .. punct end
+        func area(w, h = w, {x, y} = { x = 1, y = 2 }) return w * h + x + y end
+        var base = 100
+        var add = func(a, b = base + a) return a + b end
+        var t = {}
+        func t.scale(v, k = 2) return v * k end
+        return [
+            greet("Alice"), greet("Bob", "?"), greet("Carol", nil),
+            area(3), area(3, 4, { x = 10, y = 20 }),
+            add(1), add(1, 2), t.scale(5), t.scale(5, 3),
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"["Hello, Alice!", "Hello, Bob?", "Hello, Carol!", 12, 42, 102, 3, 10, 15]"#
+    );
+}
+
+#[test]
+fn lambda_expressions() {
+    let mut engine = Engine::new();
+    let source = r#"
+        func map(array, f)
+            var result = []
+            for x in array do result->push(f(x)) end
+            return result
+        end
+        var offset = 10
+        var add = |a, b| a + b + offset
+        var curry = |x| |y| x * y
+        var name_and_count = |{name}, ...rest| name .. rest->len()
+        var answer = || 42
+        return [
+            map([1, 2, 3], |x| x * 2), add(1, 2), curry(3)(4),
+            name_and_count({ name = "n" }, 1, 2), (|x| x > 1 and x < 3)(2),
+            answer(), map([1, 2], || 1),
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"[[2, 4, 6], 13, 12, "n2", true, 42, [1, 1]]"#
+    );
+}
+
+#[test]
+fn metamethods() {
+    let mut engine = Engine::new();
+    let source = r#"
+        func money(cents)
+            return {
+                cents = cents,
+                __add = func(a, b) return money(a.cents + b.cents) end,
+                __mul = func(a, k) return money(a.cents * k) end,
+                __unm = func(a) return money(-a.cents) end,
+                __eq = func(a, b) return a.cents == b.cents end,
+                __lt = func(a, b) return a.cents < b.cents end,
+                __concat = func(a, b) return a.cents .. "c" .. b end,
+            }
+        end
+        var a = money(150)
+        var b = money(50)
+        return [
+            (a + b).cents, (a * 2).cents, (-a).cents, a == money(150), a != b,
+            a < b, a > b, a .. "!",
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"[200, 300, -150, true, true, false, true, "150c!"]"#
+    );
+
+    let mut message = |source| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.message,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    // Without the metamethod, the operator fails as before.
+    assert!(message("return {} + 1").starts_with("Expected Int or Float"));
+    assert_eq!(
+        message("return { __eq = func(a, b) return 1 end } != {}"),
+        "Expected `bool`, got `int`"
+    );
+}
+
+#[test]
+fn index_metamethods() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var base = { kind = "base", greet = func(self) return "hi " .. self.name end }
+        var middle = { __index = base }
+        var object = { name = "obj", __index = middle }
+        var computed = { n = 3, __index = func(self, key) return key .. self.n end }
+        var stored = {}
+        var proxy = { seen = 0, __newindex = func(self, key, value)
+            self.seen = self.seen + 1
+            stored[key] = value
+        end }
+        proxy.a = 1
+        proxy.seen = 10
+        var target = { kept = 0 }
+        var forward = { __newindex = target }
+        forward.kept = 1
+        forward.added = 2
+        return [
+            object.greet(object), object.kind, object.missing, computed.x, computed[1],
+            stored.a, proxy.a, proxy.seen, target.kept, target.added,
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"["hi obj", "base", nil, "x3", "13", 1, nil, 10, 1, 2]"#
+    );
+
+    let err = engine
+        .eval("var t = {} t.__index = t return t.x")
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message == "`__index` chain is too long"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn classes() {
+    let mut engine = Engine::new();
+    let source = r#"
+        class Shape
+            func init(self, name = "shape")
+                self.name = name
+                if name == "" then
+                    self.name = "unnamed"
+                    return
+                end
+                self.sides = 0
+            end
+            func describe(self) return self.name .. "/" .. self.sides end
+            func area(self) return 0 end
+        end
+        class Square < Shape
+            func init(self, size)
+                self.name = "square"
+                self.sides = 4
+                self.size = size
+            end
+            func area(self) return self.size * self.size end
+            func __lt(a, b) return a->area() < b->area() end
+        end
+        class Unit < Square end
+        class Empty end
+        var square = Square->new(3)
+        var unit = Unit->new(1)
+        return [
+            Shape->new()->describe(), Shape->new("").name, square->describe(), square->area(),
+            unit->area(), unit < square, Empty->new().__index == Empty,
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"["shape/0", "unnamed", "square/4", 9, 1, true, true]"#
+    );
+
+    let err = engine.eval("class C func init() end end").unwrap_err();
+    assert!(matches!(err, Error::Parse(_)), "{:?}", err);
+}
+
+#[test]
+fn try_catch() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var log = []
+        func fail(n)
+            if n > 1 then
+                error("failed at " .. n)
+            end
+            return n
+        end
+        func resource(name)
+            return { close = func(self) log->push("closed " .. name) end }
+        end
+        func guarded(n)
+            try
+                with resource("inner") as r do
+                    return [1, fail(n)]
+                end
+            catch err
+                return err
+            end
+        end
+        var ok = guarded(1)
+        var err = guarded(2)
+        for i in 1->upto(3) do
+            try
+                if i == 2 then break end
+            catch _
+            end
+        end
+        var kind = nil
+        try
+            var a = 1
+            var b = a + "x"
+        catch e
+            kind = e.kind
+        end
+        return [ok, err.message, err.kind, err.traceback, log, kind]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"[[1, 1], "failed at 2", "user", ["guarded", "fail", "<anonymous>"], ["closed inner", "closed inner"], "type"]"#
+    );
+
+    // A `try` block that was left by `break` or `return` catches nothing any more.
+    let source = r#"
+        func f() try return 1 catch _ end end
+        f()
+        while true do try break catch _ end end
+        error("uncaught")
+    "#;
+    let err = engine.eval(source).unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message == "uncaught"),
+        "{:?}",
+        err
+    );
+
+    // A caught error can be raised again.
+    let err = engine
+        .eval(r#"try error("first") catch e error(e) end"#)
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message == "first" && err.kind == ErrorKind::User),
+        "{:?}",
+        err
+    );
+
+    // `exit` and running out of fuel stop the script anyway.
+    assert_eq!(
+        engine.eval("try exit(2) catch _ end return 1"),
+        Ok(ExecutionOutcome {
+            value: Object::Nil,
+            exit_code: 2,
+        })
+    );
+    let mut engine = Engine {
+        runtime: Runtime::with_fuel(1000),
+    };
+    let res = engine.eval("try while true do end catch _ end return 1");
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::FuelExhausted
+    ));
+}
+
+#[test]
+fn coroutines() {
+    let mut engine = Engine::new();
+    let source = r#"
+        var log = []
+        func resource(name)
+            return { close = func(self) log->push("closed " .. name) end }
+        end
+        func inner(x)
+            var got = yield x * 10
+            return got
+        end
+        var co = coroutine.create(func(start)
+            with resource("r") as r do
+                var a = inner(start)
+                try
+                    var b = yield a
+                    error("oops " .. b)
+                catch e
+                    log->push(e.message)
+                end
+            end
+            return "end"
+        end)
+        var values = []
+        values->push(coroutine.resume(co, 1))
+        values->push(coroutine.status(co))
+        values->push(coroutine.resume(co, 2))
+        values->push(coroutine.resume(co, 3))
+        values->push(coroutine.status(co))
+        return [values, log]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"[[10, "suspended", 2, "end", "dead"], ["oops 3", "closed r"]]"#
+    );
+
+    // The values left on the stack below a `yield` are kept while suspended.
+    let source = r#"
+        var co = coroutine.create(func() return [1, yield 2, 3] end)
+        var first = coroutine.resume(co, nil)
+        return [first, coroutine.resume(co, 20)]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        "[2, [1, 20, 3]]"
+    );
+
+    // Only the functions run by a coroutine may yield.
+    let err = engine.eval("yield 1").unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message.starts_with("Cannot yield outside")),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn exit() {
+    let mut engine = Engine::new();
+    let source = r#"
+        func check(x)
+            if x > 2 then
+                exit(3)
+            end
+        end
+        for i in 1->upto(5) do
+            check(i)
+        end
+        return "unreachable"
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        Ok(ExecutionOutcome {
+            value: Object::Nil,
+            exit_code: 3,
+        })
+    );
+    assert_eq!(
+        engine.eval("exit()"),
+        Ok(ExecutionOutcome {
+            value: Object::Nil,
+            exit_code: 0,
+        })
+    );
+    assert_eq!(engine.eval("return 5"), value(Object::Int(5)));
+}
+
+#[test]
+fn fs_lines() {
+    let path = std::env::temp_dir().join(format!("lico-fs-lines-{}.txt", std::process::id()));
+    std::fs::write(&path, "one\ntwo\r\n\nfour").unwrap();
+    let source = format!(
+        r#"
+        var lines = []
+        for line in fs.lines({:?}) do
+            lines->push(line)
+        end
+        return lines
+        "#,
+        path.to_str().unwrap()
+    );
+    let res = Engine::new().eval(&source).unwrap().value;
+    assert_eq!(res.to_string(), r#"["one", "two", "", "four"]"#);
+    std::fs::remove_file(&path).unwrap();
+
+    let res = Engine::new().eval(&source);
+    assert!(matches!(res, Err(Error::Runtime(_))));
+}
+
+#[test]
+fn fs_read_write() {
+    let path = std::env::temp_dir().join(format!("lico-fs-read-write-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut engine = Engine::new();
+
+    let source = format!(r#"fs.write({path:?}, "héllo") return fs.read({path:?})"#);
+    let res = engine.eval(&source).unwrap().value;
+    assert_eq!(res, Object::new_string("héllo".to_string()));
+
+    let source = format!(r#"return fs.read_bytes({path:?})->to_array()"#);
+    let res = engine.eval(&source).unwrap().value;
+    assert_eq!(res.to_string(), "[104, 195, 169, 108, 108, 111]");
+
+    let source = format!(
+        r#"
+        fs.write_bytes({path:?}, "a"->to_bytes())
+        return fs.read_bytes({path:?})->len()
+        "#
+    );
+    assert_eq!(engine.eval(&source).unwrap().value, Object::Int(1));
+
+    // "\xC3" alone is invalid UTF-8.
+    std::fs::write(path, b"a\xC3b").unwrap();
+    let res = engine.eval(&format!("return fs.read({path:?})"));
+    assert!(matches!(res, Err(Error::Runtime(_))));
+    let res = engine.eval(&format!("return fs.read({path:?}, true)"));
+    assert_eq!(
+        res.unwrap().value,
+        Object::new_string("a\u{FFFD}b".to_string())
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn fs_open() {
+    let path = std::env::temp_dir().join(format!("lico-fs-open-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    let source = format!(
+        r#"
+        var path = {path:?}
+        with fs.open(path, "w") as f do
+            f->write("first\n")
+            f->write("sécond\n")
+        end
+        with fs.open(path, "a") as f do
+            f->write("third"->to_bytes())
+        end
+
+        var result = []
+        with fs.open(path, "r+") as f do
+            result->push(f->read_line())
+            result->push(f->read(2))
+            f->seek(-5, "end")
+            f->write("THIRD")
+            result->push(f->seek(0, "cur"))
+            f->seek(6)
+            result->push(f->read_bytes(3)->len())
+            result->push(f->read())
+            result->push(f->read())
+        end
+        return result
+        "#
+    );
+    let res = Engine::new().eval(&source).unwrap().value;
+    assert_eq!(
+        res.to_string(),
+        r#"["first", "sé", 19, 3, "cond\nTHIRD", nil]"#
+    );
+
+    let source = format!(
+        r#"
+        var f = fs.open({path:?})
+        f->close()
+        f->close()
+        return f->read()
+        "#
+    );
+    let res = Engine::new().eval(&source);
+    assert!(matches!(res, Err(Error::Runtime(err)) if err.to_string() == "File is already closed"));
+
+    // Reading characters stops at a byte that can't start one, instead of taking it as the lead
+    // of a longer character.
+    std::fs::write(path, b"a\x80bc").unwrap();
+    let source = format!(
+        r#"
+        var f = fs.open({path:?})
+        var first = f->read(1)
+        try
+            f->read(2)
+        catch e
+            return [first, e.kind, e.message]
+        end
+        "#
+    );
+    let res = Engine::new().eval(&source).unwrap().value;
+    assert_eq!(
+        res.to_string(),
+        r#"["a", "io", "Invalid UTF-8 sequence: [80]"]"#
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn import() {
+    let dir = std::env::temp_dir().join(format!("lico-import-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("lib/counter.lico"),
+        "var count = 0 return { next = func() count = count + 1 return count end }",
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib/user.lico"), r#"return import "./counter""#).unwrap();
+    std::fs::write(dir.join("a.lico"), r#"import "b""#).unwrap();
+    std::fs::write(dir.join("b.lico"), r#"import "a""#).unwrap();
+    std::fs::write(dir.join("bad.lico"), "var = 1").unwrap();
+    std::fs::write(
+        dir.join("lib/fail.lico"),
+        "func half(n)\n    return n / 0\nend\nreturn { half = half }",
+    )
+    .unwrap();
+
+    let mut engine = Engine::new();
+    engine.runtime.modules.search_path.push(dir.clone());
+    engine.runtime.modules.register("answer", Object::Int(42));
+    let source = r#"
+        var counter = import "lib/counter"
+        counter.next()
+        var again = import "lib/user"
+        return [import "answer", again.next()]
+    "#;
+    assert_eq!(engine.eval(source), value(Object::from(vec![42, 2])));
+    // The cache outlives the evaluation.
+    assert_eq!(
+        engine.eval(r#"return (import "lib/counter").next()"#),
+        value(Object::Int(3))
+    );
+
+    let err = engine.eval(r#"import "a""#).unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message.starts_with("Circular import of")),
+        "{:?}",
+        err
+    );
+    let err = engine.eval(r#"import "bad""#).unwrap_err();
+    assert!(
+        matches!(&err, Error::Runtime(err) if err.message.starts_with("Failed to compile module")),
+        "{:?}",
+        err
+    );
+
+    // Errors in a module are located in its file.
+    let source = "var lib = import \"lib/fail\"\nreturn lib.half(1)";
+    let Err(Error::Runtime(err)) = engine.eval(source) else {
+        panic!("expected a runtime error");
+    };
+    let module = dir.join("lib/fail.lico").canonicalize().unwrap();
+    assert_eq!(
+        err.display_in("main.lico", source).to_string(),
+        format!(
+            "{}\nTraceback (most recent call last):\n  <main> at main.lico:2:8\n  half at {}:2:12",
+            err.message,
+            module.display()
+        )
+    );
+    assert_eq!(
+        render(&Error::Runtime(err).diagnostics(), "main.lico", source),
+        format!(
+            "error: Divided by zero.\n --> main.lico:2:8\n  |\n2 | return lib.half(1)\n  |        ^^^^^^^^^^^ called `half` here\n  |\n  = note: raised at {}:2:12\n",
+            module.display()
+        )
+    );
+
+    // Pure code can't run other files.
+    let err = engine.eval_expr(r#"import "answer""#).unwrap_err();
+    assert!(matches!(err, Error::Compile(_)), "{:?}", err);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn globals() {
+    let mut engine = Engine::new();
+    engine.runtime.global.set("limit", Object::Int(3));
+    let source = r#"
+        global count = 0
+        global limit
+        func bump()
+            count = count + 1
+            return count < limit
+        end
+        while bump() do end
+        var count = "local"
+        return count
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::new_string("local".to_string()))
+    );
+    assert_eq!(engine.runtime.global.get("count"), Object::Int(3));
+    // The globals outlive the evaluation.
+    assert_eq!(
+        engine.eval("global count count = count + 1 return count"),
+        value(Object::Int(4))
+    );
+    // A global read before it is set is nil.
+    assert_eq!(engine.eval("global unset return unset"), value(Object::Nil));
+    // Without a declaration, the name isn't a global.
+    assert!(matches!(
+        engine.eval("return count"),
+        Err(Error::Compile(_))
+    ));
+
+    // Pure code can't depend on state shared between evaluations.
+    let err = engine
+        .eval_expr("(func() global count return count end)()")
+        .unwrap_err();
+    assert!(matches!(err, Error::Compile(_)), "{:?}", err);
+}
+
+#[test]
+fn fs_walk() {
+    let dir = std::env::temp_dir().join(format!("lico-fs-walk-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("b/c")).unwrap();
+    std::fs::write(dir.join("a.txt"), "12345").unwrap();
+    std::fs::write(dir.join("b/d.md"), "").unwrap();
+    std::fs::write(dir.join("b/c/e.txt"), "").unwrap();
+    let walk = |options: &str| {
+        let source = format!(
+            r#"
+            var root = {:?}
+            var entries = []
+            for entry in fs.walk(root{options}) do
+                entries->push([entry.depth, entry.is_dir, entry.size])
+            end
+            return entries
+            "#,
+            dir.to_str().unwrap()
+        );
+        let res = Engine::new().eval(&source).unwrap().value;
+        res.to_string()
+    };
+    let paths = |options: &str| {
+        let source = format!(
+            r#"
+            var entries = []
+            for entry in fs.walk({:?}{options}) do
+                entries->push(entry.path)
+            end
+            return entries
+            "#,
+            dir.to_str().unwrap()
+        );
+        let res = Engine::new().eval(&source).unwrap().value;
+        res.to_string().replace(dir.to_str().unwrap(), "")
+    };
+
+    assert_eq!(
+        paths(""),
+        r#"["/a.txt", "/b", "/b/c", "/b/c/e.txt", "/b/d.md"]"#
+    );
+    assert_eq!(paths(", { max_depth = 1 }"), r#"["/a.txt", "/b"]"#);
+    assert_eq!(
+        paths(r#", { pattern = "**/*.txt" }"#),
+        r#"["/a.txt", "/b/c/e.txt"]"#
+    );
+    assert_eq!(
+        paths(", { dirs = false }"),
+        r#"["/a.txt", "/b/c/e.txt", "/b/d.md"]"#
+    );
+    assert!(walk("").starts_with("[[1, false, 5], [1, true, "));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fs_write_atomic() {
+    let dir = std::env::temp_dir().join(format!("lico-fs-write-atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state.json");
+    std::fs::write(&path, "old").unwrap();
+    let source = format!(
+        r#"fs.write_atomic({0:?}, "new") return fs.read({0:?})"#,
+        path.to_str().unwrap()
+    );
+    let res = Engine::new().eval(&source).unwrap().value;
+    assert_eq!(res, Object::new_string("new".to_string()));
+    // Only the target is left, the temporary file is renamed over it.
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fs_lock() {
+    let path = std::env::temp_dir().join(format!("lico-fs-lock-{}", std::process::id()));
+    let try_lock = || {
+        let file = std::fs::File::open(&path).unwrap();
+        file.try_lock().is_ok()
+    };
+    let mut engine = Engine::new();
+
+    let source = format!(r#"return fs.lock({:?})"#, path.to_str().unwrap());
+    let lock = engine.eval(&source).unwrap().value;
+    assert!(!try_lock());
+    drop(lock);
+    assert!(try_lock());
+
+    let source = format!(
+        r#"
+        with fs.lock({0:?}) as lock do end
+        var lock = fs.lock({0:?})
+        lock->unlock()
+        lock->unlock()
+        "#,
+        path.to_str().unwrap()
+    );
+    engine.eval(&source).unwrap();
+    assert!(try_lock());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn memory_filesystem() {
+    let files = MemoryFileSystem::new();
+    files.insert("in.txt", "héllo");
+    let mut engine = Engine::new();
+    engine.runtime.filesystem = Rc::new(files.clone());
+
+    let source = r#"
+        fs.write("out.txt", fs.read("in.txt") .. "!")
+        fs.write_bytes("out.bin", fs.read_bytes("in.txt"))
+        return fs.read("out.txt")
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value,
+        Object::new_string("héllo!".to_string())
+    );
+    assert_eq!(files.get("out.bin"), Some("héllo".as_bytes().to_vec()));
+    assert!(!std::path::Path::new("out.txt").exists());
+
+    let res = engine.eval(r#"return fs.read("missing.txt")"#);
+    assert!(matches!(res, Err(Error::Runtime(err)) if err.kind == ErrorKind::Io));
+
+    // The other builtins that touch files use it too.
+    files.insert("app.env", "NAME=lico\n");
+    files.insert("lib/answer.lico", "return { value = 42 }");
+    let source = r#"
+        with fs.open("log.txt", "w") as f do
+            f->write("one\n")
+        end
+        with fs.open("log.txt", "a+") as f do
+            f->write("two\n")
+            f->seek(0)
+            assert(f->read_line() == "one")
+        end
+        var lines = []
+        for line in fs.lines("log.txt") do
+            lines->push(line)
+        end
+        fs.write_atomic("atomic.txt", "done")
+        with fs.lock("lock.txt") as lock do end
+        return [lines, env.load("app.env").NAME, (import "./lib/answer").value, fs.glob("**/*.lico")]
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"[["one", "two"], "lico", 42, ["lib/answer.lico"]]"#
+    );
+    assert_eq!(files.get("log.txt"), Some(b"one\ntwo\n".to_vec()));
+    assert_eq!(files.get("atomic.txt"), Some(b"done".to_vec()));
+    assert!(!std::path::Path::new("log.txt").exists());
+
+    let res = engine.eval(r#"var lock = fs.lock("held.txt") return fs.lock("held.txt")"#);
+    assert!(matches!(res, Err(Error::Runtime(err)) if err.kind == ErrorKind::Io));
+
+    let source = r#"
+        var paths = []
+        for entry in fs.walk("lib") do
+            paths->push(entry.path .. ":" .. entry.size)
+        end
+        return paths
+    "#;
+    assert_eq!(
+        engine.eval(source).unwrap().value.to_string(),
+        r#"["lib/answer.lico:21"]"#
+    );
+}
+
+#[test]
+fn file_permissions() {
+    let dir = std::env::temp_dir().join(format!("lico-permissions-{}", std::process::id()));
+    let allowed = dir.join("allowed");
+    std::fs::create_dir_all(&allowed).unwrap();
+    std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+    let dir_path = dir.to_str().unwrap();
+    let denied = |res: Result<ExecutionOutcome, Error>| matches!(res, Err(Error::Runtime(err)) if err.kind == ErrorKind::PermissionDenied);
+
+    let mut engine = Engine::new();
+    engine.runtime.set_permissions(Permissions::no_files());
+    let secret = format!("{dir_path}/secret.txt");
+    assert!(denied(engine.eval(&format!("return fs.read({secret:?})"))));
+    assert!(denied(
+        engine.eval(&format!("return fs.read_bytes({secret:?})"))
+    ));
+    assert!(denied(engine.eval(&format!("fs.write({secret:?}, 'x')"))));
+    assert!(denied(engine.eval(&format!("return fs.lines({secret:?})"))));
+    assert!(denied(engine.eval(&format!("return fs.open({secret:?})"))));
+    assert!(denied(
+        engine.eval(&format!("return fs.walk({dir_path:?})"))
+    ));
+    assert!(denied(
+        engine.eval(&format!("return fs.glob('{dir_path}/*')"))
+    ));
+    // Nor can another process read it.
+    assert!(denied(
+        engine.eval(&format!("return subprocess.spawn('cat', [{secret:?}])"))
+    ));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("secret.txt")).unwrap(),
+        "secret"
+    );
+
+    // Reads are allowed everywhere, writes only under `allowed`, even through `..`.
+    engine.runtime.set_permissions(Permissions {
+        read: FileAccess::All,
+        write: FileAccess::Only(vec![allowed.clone()]),
+        ..Permissions::default()
+    });
+    let inside = format!("{dir_path}/allowed/new.txt");
+    let escaped = format!("{dir_path}/allowed/../secret.txt");
+    let source = format!("fs.write({inside:?}, 'ok') return fs.read({inside:?})");
+    assert_eq!(
+        engine.eval(&source).unwrap().value,
+        Object::new_string("ok".to_string())
+    );
+    assert!(denied(engine.eval(&format!("fs.write({escaped:?}, 'x')"))));
+    assert!(denied(
+        engine.eval(&format!("return fs.open({escaped:?}, 'a')"))
+    ));
+    assert!(denied(engine.eval(&format!("return fs.lock({escaped:?})"))));
+    assert!(engine.eval(&format!("return fs.read({escaped:?})")).is_ok());
+    assert!(engine.eval(&format!("return fs.open({escaped:?})")).is_ok());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("secret.txt")).unwrap(),
+        "secret"
+    );
+
+    // The permissions only apply while the runtime executes.
+    assert!(Permissions::current().check_write(&escaped).is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sleep_permission() {
+    let mut engine = Engine::new();
+    engine.runtime.set_permissions(Permissions {
+        sleep: false,
+        ..Permissions::default()
+    });
+    let start = std::time::Instant::now();
+    let res = engine.eval("time.sleep(10)");
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::PermissionDenied
+    ));
+    assert!(start.elapsed().as_secs() < 1);
+
+    engine.runtime.set_permissions(Permissions::default());
+    assert!(engine.eval("time.sleep(0.01)").is_ok());
+}
+
+#[test]
+fn interrupt() {
+    let mut engine = Engine::new();
+    let handle = engine.interrupt_handle();
+    let interrupter = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        handle.interrupt();
+    });
+    let res = engine.eval("while true do end");
+    interrupter.join().unwrap();
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::Interrupted && err.message == "Interrupted"
+    ));
+
+    let handle = engine.interrupt_handle();
+    let interrupter = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        handle.interrupt();
+    });
+    let res = engine.eval(
+        r#"
+        var state = { stop = false }
+        on_interrupt(func() state.stop = true end)
+        var count = 0
+        while not state.stop do
+            count = count + 1
+        end
+        on_interrupt(nil)
+        return "cleaned up"
+        "#,
+    );
+    interrupter.join().unwrap();
+    assert_eq!(res, value(Object::new_string("cleaned up".to_string())));
+}
+
+#[cfg(unix)]
+#[test]
+fn subprocess_spawn() {
+    let res = Engine::new().eval(
+        r#"
+        var result = []
+        var cat = subprocess.spawn("cat", [], { stderr = "null" })
+        cat.stdin->write("one\n")
+        cat.stdin->write("two"->to_bytes())
+        cat.stdin->close()
+        for line in cat.stdout do
+            result->push(line)
+        end
+        result->push(cat->wait())
+
+        var sh = subprocess.spawn("sh", ["-c", "echo err >&2; exit 3"], { stdout = "null" })
+        for line in sh.stderr do
+            result->push(line)
+        end
+        result->push(sh->wait())
+        result->push(sh.stdout)
+
+        var sleep = subprocess.spawn("sleep", ["10"])
+        result->push(sleep->wait(0.05))
+        sleep->kill()
+        result->push(sleep->wait())
+        return result
+        "#,
+    );
+    assert_eq!(
+        res.unwrap().value.to_string(),
+        r#"["one", "two", 0, "err", 3, nil, nil, 137]"#
+    );
+
+    let res = Engine::new().eval(r#"subprocess.spawn("lico-no-such-program")"#);
+    assert!(matches!(res, Err(Error::Runtime(_))));
+}
+
+#[cfg(all(feature = "ffi", target_os = "linux"))]
+#[test]
+fn ffi() {
+    let res = Engine::new().eval(
+        r#"
+        var libc = ffi.open("libc.so.6")
+        var libm = ffi.open("libm.so.6")
+        var abs = libc->function("abs", ["int"], "int")
+        var strlen = libc->function("strlen", ["string"], "int64")
+        var strnlen = libc->function("strnlen", ["bytes", "int64"], "int64")
+        var getenv = libc->function("getenv", ["string"], "string")
+        var pow = libm->function("pow", ["double", "double"], "double")
+        var ldexp = libm->function("ldexp", ["double", "int"], "double")
+        return [
+            abs(-3), strlen("héllo"), strnlen("abcdef"->to_bytes(), 4),
+            getenv("LICO_NO_SUCH_VARIABLE"), pow(2, 0.5), ldexp(1.5, 3)
+        ]
+        "#,
+    );
+    assert_eq!(
+        res.unwrap().value.to_string(),
+        r#"[3, 6, 4, nil, 1.4142135623730951, 12]"#
+    );
+
+    let res = Engine::new()
+        .eval(r#"var abs = ffi.open("libc.so.6")->function("abs", ["int"], "int") abs("x")"#);
+    assert!(matches!(res, Err(Error::Runtime(_))));
+    let res = Engine::new().eval(r#"ffi.open("lico-no-such-library.so")"#);
+    assert!(matches!(res, Err(Error::Runtime(_))));
+
+    let mut engine = Engine::new();
+    engine.runtime.set_permissions(Permissions {
+        native_code: false,
+        ..Permissions::default()
+    });
+    let res = engine.eval(r#"ffi.open("libc.so.6")"#);
+    assert!(matches!(
+        res,
+        Err(Error::Runtime(err)) if err.kind == ErrorKind::PermissionDenied
+    ));
+}
+
+#[test]
+fn unreachable_code() {
+    use lico_core::compiler::{Warning, WarningKind};
+
+    let source = "var x = 1
+if false then x = 2 end
+while false do x = 3 end
+if x == 1 then x = 4 elif true then x = 5 else x = 6 end
+return x
+x = 7";
+    let spans = Engine::check(source)
+        .unwrap()
+        .into_iter()
+        .map(|Warning { kind, span }| {
+            assert_eq!(kind, WarningKind::UnreachableCode);
+            &source[span.to_range().start as usize..span.to_range().end as usize]
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(spans, ["false then x = 2", "x = 3", "x = 6", "x = 7"]);
+
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval(source), value(Object::Int(4)));
+}
+
+#[test]
+fn unused_variables_and_shadowing() {
+    use lico_core::compiler::{Warning, WarningKind};
+
+    let source = "var a = 1
+var unused = 2
+var _ignored = 3
+a = 4
+func f(p, q, _r)
+    return q
+end
+if true then
+    var a = f(1, 2, 3)
+    return a
+end";
+    let warnings = Engine::check(source)
+        .unwrap()
+        .into_iter()
+        .map(|Warning { kind, span }| {
+            let text = &source[span.to_range().start as usize..span.to_range().end as usize];
+            (kind, text)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            (WarningKind::UnusedParameter("p".to_string()), "p"),
+            (WarningKind::Shadowing("a".to_string()), "a"),
+            (WarningKind::UnusedVariable("a".to_string()), "a"),
+            (WarningKind::UnusedVariable("unused".to_string()), "unused"),
+        ]
+    );
+}
+
+#[test]
+fn format_builtin() {
+    let mut engine = Engine::new();
+    let source = r#"
+        return [
+            format("x=%d y=%.2f name=%s", 3, 1.005, "lico"),
+            format("[%5d|%-5d|%05d|%+d]", 42, 42, -42, 7),
+            format("[%8.3f|%-8.1f|%f]", 3.14159, -2.5, 1),
+            format("[%6s|%-6s|%.2s|%s %s]", "ab", "ab", "abc", true, [1, "a"]),
+            format("%x%% %04x", 255, 10),
+        ]
+    "#;
+    assert_eq!(
+        engine.eval(source),
+        value(Object::from(vec![
+            Object::new_string("x=3 y=1.00 name=lico".to_string()),
+            Object::new_string("[   42|42   |-0042|+7]".to_string()),
+            Object::new_string("[   3.142|-2.5    |1.000000]".to_string()),
+            Object::new_string("[    ab|ab    |ab|true [1, \"a\"]]".to_string()),
+            Object::new_string("ff% 000a".to_string()),
+        ]))
+    );
+    let mut kind = |source: &str| match engine.eval(source) {
+        Err(Error::Runtime(err)) => err.kind,
+        res => panic!("expected a runtime error for {:?}, got {:?}", source, res),
+    };
+    assert_eq!(kind("return format('%d', 1.5)"), ErrorKind::Type);
+    assert_eq!(kind("return format('%f', 'a')"), ErrorKind::Type);
+    assert_eq!(kind("return format('%d %d', 1)"), ErrorKind::Arity);
+    assert_eq!(kind("return format('%q', 1)"), ErrorKind::Other);
+    assert_eq!(kind("return format('50%')"), ErrorKind::Other);
+    assert_eq!(
+        kind("return format('%.99999999999f', 1.0)"),
+        ErrorKind::Other
+    );
+    assert_eq!(kind("return format('%999999999999d', 1)"), ErrorKind::Other);
+}
+
+#[test]
+fn unknown_attribute() {
+    use lico_core::compiler::{Warning, WarningKind};
+
+    let source = "@inline
+func f(x) return x + 1 end
+return f(1)";
+    let warnings = Engine::check(source).unwrap();
+    assert_eq!(
+        warnings,
+        [Warning {
+            kind: WarningKind::UnknownAttribute("inline".to_string()),
+            span: warnings[0].span,
+        }]
+    );
+    assert_eq!(
+        &source
+            [warnings[0].span.to_range().start as usize..warnings[0].span.to_range().end as usize],
+        "@inline"
+    );
+    assert_eq!(Engine::new().eval(source), value(Object::Int(2)));
+}
@@ -0,0 +1,340 @@
+use super::*;
+use std::{cell::RefCell, rc::Rc};
+
+mod coroutine;
+pub use coroutine::*;
+
+mod dotenv;
+pub use dotenv::*;
+
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "ffi")]
+pub use ffi::*;
+
+mod file;
+pub use file::*;
+
+mod format;
+pub use format::*;
+
+mod glob;
+pub use glob::*;
+
+mod iterator;
+pub use iterator::*;
+
+mod lines;
+pub use lines::*;
+
+mod subprocess;
+pub use subprocess::*;
+
+mod term;
+pub use term::*;
+
+mod walk;
+pub use walk::*;
+
+#[cfg(feature = "random")]
+mod random;
+#[cfg(feature = "random")]
+pub use random::*;
+
+#[cfg(feature = "hash")]
+mod hash;
+#[cfg(feature = "hash")]
+pub use hash::*;
+
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(feature = "compress")]
+pub use compress::*;
+
+// partial(func: Function, ...args: Any) -> Function
+pub fn partial(args: &[Object]) -> Result<Object, RuntimeError> {
+    let Some((func, bound)) = args.split_last() else {
+        return Err(RuntimeError::arity("at least 1", 0));
+    };
+    ensure_callable(func)?;
+    Ok(Object::new_bound_function(BoundFunctionObject {
+        func: func.clone(),
+        args: bound.iter().rev().cloned().collect(),
+    }))
+}
+
+// compose(...funcs: Function) -> Function
+pub fn compose(args: &[Object]) -> Result<Object, RuntimeError> {
+    if args.is_empty() {
+        return Err(RuntimeError::arity("at least 1", 0));
+    }
+    for func in args {
+        ensure_callable(func)?;
+    }
+
+    // The composed function captures `funcs` as locals 0..N, and takes one argument as local N.
+    //   for each func:
+    //     LoadLocal(i), LoadLocal(N), Call(1), SetLocal(N)
+    //   LoadLocal(N), Return
+    let env = args
+        .iter()
+        .rev()
+        .map(|func| Rc::new(RefCell::new(func.clone())))
+        .collect::<Vec<_>>();
+    let arg = LocalId(env.len());
+    let mut code = Vec::with_capacity(env.len() * 4 + 2);
+    for i in 0..env.len() {
+        code.extend([
+            Code::LoadLocal(LocalId(i)),
+            Code::LoadLocal(arg),
+            Code::Call(1),
+            Code::SetLocal(arg),
+        ]);
+    }
+    code.extend([Code::LoadLocal(arg), Code::Return]);
+
+    let proto = FunctionPrototype {
+        name: None,
+        captures: Vec::new(),
+        args: vec![ArgumentKind::Auto],
+        code: code.into(),
+        spans: Rc::new([]),
+        file: None,
+    };
+    Ok(Object::new_function(FunctionObject {
+        proto: Rc::new(proto),
+        env,
+    }))
+}
+
+// memoize(func: Function) -> Function
+pub fn memoize(args: &[Object]) -> Result<Object, RuntimeError> {
+    let [func] = args else {
+        return Err(RuntimeError::arity(1, args.len()));
+    };
+    ensure_callable(func)?;
+    Ok(Object::new_memoized_function(MemoizedFunctionObject::new(
+        func.clone(),
+    )))
+}
+
+// inspect(value: Any, max_depth?: Int) -> String
+pub fn inspect(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (value, max_depth) = match args {
+        [value] => (value, 8),
+        [max_depth, value] => (value, max_depth.clone().ensure_int()?.max(0) as usize),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let mut inspector = Inspector::new(max_depth, false);
+    inspector.write(value, 0);
+    Ok(Object::new_string(inspector.buf))
+}
+
+// format_values(values: Array) -> String
+//
+// Joins `values` with spaces for `print` and friends. Strings are written as is, and arrays and
+// tables are rendered on one line by the rules of `inspect`.
+pub fn format_values(args: &[Object]) -> Result<Object, RuntimeError> {
+    let values = extract_argument!(args, [Array]);
+    let mut inspector = Inspector::new(8, true);
+    for (i, value) in values.borrow().iter().enumerate() {
+        if i > 0 {
+            inspector.buf.push(' ');
+        }
+        match value {
+            Object::String(string) => inspector.buf.push_str(string.as_str()),
+            value => inspector.write(value, 0),
+        }
+    }
+    Ok(Object::new_string(inspector.buf))
+}
+
+struct Inspector {
+    max_depth: usize,
+    // Whether to render everything on one line, e.g. `{a = 1, b = [2, 3]}`.
+    compact: bool,
+    // Addresses of the arrays/tables being rendered, to detect cycles.
+    visiting: Vec<*const ()>,
+    buf: String,
+}
+
+impl Inspector {
+    const INDENT: usize = 2;
+
+    fn new(max_depth: usize, compact: bool) -> Self {
+        Self {
+            max_depth,
+            compact,
+            visiting: Vec::new(),
+            buf: String::new(),
+        }
+    }
+
+    fn write(&mut self, value: &Object, depth: usize) {
+        match value {
+            Object::String(string) => self.buf.push_str(&quote_string(string.as_str())),
+            Object::Array(array) => {
+                let ptr = Rc::as_ptr(array) as *const ();
+                let array = array.borrow();
+                if array.is_empty() {
+                    self.buf.push_str("[]");
+                } else if self.visiting.contains(&ptr) {
+                    self.buf.push_str("[<cycle>]");
+                } else if depth >= self.max_depth {
+                    self.buf.push_str("[...]");
+                } else {
+                    self.visiting.push(ptr);
+                    self.buf.push('[');
+                    for (i, value) in array.iter().enumerate() {
+                        self.begin_item(i, depth + 1);
+                        self.write(value, depth + 1);
+                    }
+                    self.end_items(depth);
+                    self.buf.push(']');
+                    self.visiting.pop();
+                }
+            }
+            Object::Table(table) => {
+                let ptr = Rc::as_ptr(table) as *const ();
+                let table = table.borrow();
+                if table.is_empty() {
+                    self.buf.push_str("{}");
+                } else if self.visiting.contains(&ptr) {
+                    self.buf.push_str("{<cycle>}");
+                } else if depth >= self.max_depth {
+                    self.buf.push_str("{...}");
+                } else {
+                    self.visiting.push(ptr);
+                    self.buf.push('{');
+                    let mut fields = table.iter().collect::<Vec<_>>();
+                    fields.sort_by(|(lhs, _), (rhs, _)| compare_keys(lhs, rhs));
+                    for (i, (key, value)) in fields.into_iter().enumerate() {
+                        self.begin_item(i, depth + 1);
+                        match key {
+                            TableKey::String(key) if is_identifier(key) => self.buf.push_str(key),
+                            TableKey::String(key) => {
+                                self.buf.push('[');
+                                self.buf.push_str(&quote_string(key));
+                                self.buf.push(']');
+                            }
+                            key => {
+                                self.buf.push('[');
+                                self.buf.push_str(&key.to_string());
+                                self.buf.push(']');
+                            }
+                        }
+                        self.buf.push_str(" = ");
+                        self.write(value, depth + 1);
+                    }
+                    self.end_items(depth);
+                    self.buf.push('}');
+                    self.visiting.pop();
+                }
+            }
+            value => self.buf.push_str(&value.to_string()),
+        }
+    }
+
+    // Writes what goes before the `index`-th element of an array or a table.
+    fn begin_item(&mut self, index: usize, depth: usize) {
+        if self.compact {
+            if index > 0 {
+                self.buf.push_str(", ");
+            }
+        } else {
+            if index > 0 {
+                self.buf.push(',');
+            }
+            self.buf.push('\n');
+            self.indent(depth);
+        }
+    }
+
+    // Writes what goes after the last element of an array or a table.
+    fn end_items(&mut self, depth: usize) {
+        if !self.compact {
+            self.buf.push_str(",\n");
+            self.indent(depth);
+        }
+    }
+
+    fn indent(&mut self, depth: usize) {
+        self.buf
+            .extend(std::iter::repeat_n(' ', depth * Self::INDENT));
+    }
+}
+
+fn compare_keys(lhs: &TableKey, rhs: &TableKey) -> std::cmp::Ordering {
+    fn rank(key: &TableKey) -> u8 {
+        match key {
+            TableKey::Int(_) => 0,
+            TableKey::Bool(_) => 1,
+            TableKey::String(_) => 2,
+        }
+    }
+    match (lhs, rhs) {
+        (TableKey::Int(lhs), TableKey::Int(rhs)) => lhs.cmp(rhs),
+        (TableKey::Bool(lhs), TableKey::Bool(rhs)) => lhs.cmp(rhs),
+        (TableKey::String(lhs), TableKey::String(rhs)) => lhs.cmp(rhs),
+        (lhs, rhs) => rank(lhs).cmp(&rank(rhs)),
+    }
+}
+
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// time.monotonic() -> Float
+pub fn monotonic(args: &[Object]) -> Result<Object, RuntimeError> {
+    use std::{sync::OnceLock, time::Instant};
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+
+    if !args.is_empty() {
+        return Err(RuntimeError::arity(0, args.len()));
+    }
+    let origin = ORIGIN.get_or_init(Instant::now);
+    Ok(Object::Float(origin.elapsed().as_secs_f64()))
+}
+
+// clock() -> Float
+pub fn clock(args: &[Object]) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::arity(0, args.len()));
+    }
+    Ok(Object::Float(process_cpu_time()))
+}
+
+// The CPU time consumed by this process in seconds, which is always 0 off unix.
+#[cfg(unix)]
+fn process_cpu_time() -> f64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid pointer to `timespec`.
+    let res = unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut time) };
+    if res != 0 {
+        return 0.0;
+    }
+    time.tv_sec as f64 + time.tv_nsec as f64 / 1e9
+}
+
+#[cfg(not(unix))]
+fn process_cpu_time() -> f64 {
+    0.0
+}
+
+pub(crate) fn ensure_callable(func: &Object) -> Result<(), RuntimeError> {
+    match func {
+        Object::Function(_)
+        | Object::BoundFunction(_)
+        | Object::MemoizedFunction(_)
+        | Object::RustFunction(_) => Ok(()),
+        x => Err(RuntimeError::type_error(format!(
+            "Mismatched argument type: expected function, got {}",
+            x.typename()
+        ))),
+    }
+}
@@ -0,0 +1,115 @@
+use super::*;
+use std::io::{Read, Write};
+
+enum Algorithm {
+    Gzip,
+    Zlib,
+    Zstd,
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Result<Self, RuntimeError> {
+        match name {
+            "gzip" => Ok(Algorithm::Gzip),
+            "zlib" => Ok(Algorithm::Zlib),
+            "zstd" => Ok(Algorithm::Zstd),
+            _ => Err(format!("Unknown algorithm: {}, expected gzip, zlib or zstd", name).into()),
+        }
+    }
+
+    fn default_level(&self) -> i64 {
+        match self {
+            Algorithm::Gzip | Algorithm::Zlib => 6,
+            Algorithm::Zstd => 3,
+        }
+    }
+
+    fn level_range(&self) -> std::ops::RangeInclusive<i64> {
+        match self {
+            Algorithm::Gzip | Algorithm::Zlib => 0..=9,
+            Algorithm::Zstd => 1..=22,
+        }
+    }
+}
+
+fn extract_data(data: &Object) -> Result<&[u8], RuntimeError> {
+    match data {
+        Object::String(x) => Ok(x.as_str().as_bytes()),
+        Object::Bytes(x) => Ok(x),
+        x => Err(format!("Expected String or Bytes, but got {}", x.typename()).into()),
+    }
+}
+
+// compress(data: String | Bytes, algorithm?: String, level?: Int) -> Bytes
+pub fn compress(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (data, algorithm, level) = match args {
+        [data] => (data, None, None),
+        [algorithm, data] => (data, Some(algorithm), None),
+        [level, algorithm, data] => (data, Some(algorithm), Some(level)),
+        _ => return Err(RuntimeError::arity("1 to 3", args.len())),
+    };
+    let data = extract_data(data)?;
+    let algorithm = match algorithm {
+        Some(x) => Algorithm::from_name(x.clone().ensure_string()?.as_str())?,
+        None => Algorithm::Gzip,
+    };
+    let level = match level {
+        Some(x) => x.clone().ensure_int()?,
+        None => algorithm.default_level(),
+    };
+    let range = algorithm.level_range();
+    if !range.contains(&level) {
+        return Err(format!(
+            "Compression level must be in {}..={}, got {}",
+            range.start(),
+            range.end(),
+            level
+        )
+        .into());
+    }
+
+    let compressed = match algorithm {
+        Algorithm::Gzip => {
+            let level = flate2::Compression::new(level as u32);
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        Algorithm::Zlib => {
+            let level = flate2::Compression::new(level as u32);
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        Algorithm::Zstd => zstd::encode_all(data, level as i32)?,
+    };
+    Ok(Object::new_bytes(compressed))
+}
+
+// decompress(data: Bytes, algorithm?: String) -> Bytes
+pub fn decompress(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (data, algorithm) = match args {
+        [data] => (data, None),
+        [algorithm, data] => (data, Some(algorithm)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let data = extract_data(data)?;
+    let algorithm = match algorithm {
+        Some(x) => Algorithm::from_name(x.clone().ensure_string()?.as_str())?,
+        None => Algorithm::Gzip,
+    };
+
+    let mut decompressed = Vec::new();
+    match algorithm {
+        Algorithm::Gzip => {
+            flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+        Algorithm::Zlib => {
+            flate2::read::ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+        Algorithm::Zstd => {
+            decompressed = zstd::decode_all(data)?;
+        }
+    }
+    Ok(Object::new_bytes(decompressed))
+}
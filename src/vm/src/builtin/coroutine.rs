@@ -0,0 +1,58 @@
+use super::*;
+use crate::execute::Suspension;
+
+/// A function run a step at a time, kept in a `coroutine` userdata. `resume` runs it until it
+/// yields a value or returns, and a `for-in` loop over it takes the yielded values as its items.
+pub(crate) enum Coroutine {
+    /// Not resumed yet, so the first `resume` calls the function.
+    Created(Rc<FunctionObject>),
+    /// Stopped at a `yield`, where the next `resume` goes on.
+    Suspended(Box<Suspension>),
+    Running,
+    /// Returned, or stopped by an error.
+    Dead,
+}
+
+impl Coroutine {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Coroutine::Created(_) | Coroutine::Suspended(_) => "suspended",
+            Coroutine::Running => "running",
+            Coroutine::Dead => "dead",
+        }
+    }
+}
+
+/// The `coroutine` userdata in `arg`, or a type error.
+pub(crate) fn ensure_coroutine(arg: &Object) -> Result<Rc<UserdataObject>, RuntimeError> {
+    match arg {
+        Object::Userdata(userdata) if userdata.name() == "coroutine" => Ok(Rc::clone(userdata)),
+        other => Err(RuntimeError::type_error(format!(
+            "Mismatched argument type: expected coroutine, got {}",
+            other.typename()
+        ))),
+    }
+}
+
+// coroutine_create(func: Function) -> Coroutine
+pub fn coroutine_create(args: &[Object]) -> Result<Object, RuntimeError> {
+    let func = extract_argument!(args, [Function]);
+    let coroutine = Coroutine::Created(func);
+    Ok(Object::Userdata(Rc::new(UserdataObject::new(
+        "coroutine",
+        coroutine,
+    ))))
+}
+
+// coroutine_status(coroutine: Coroutine) -> String
+//
+// "suspended" before the first `resume` and at a `yield`, "running" while it runs, and "dead"
+// once it has returned or failed.
+pub fn coroutine_status(args: &[Object]) -> Result<Object, RuntimeError> {
+    let [coroutine] = args else {
+        return Err(RuntimeError::arity(1, args.len()));
+    };
+    let coroutine = ensure_coroutine(coroutine)?;
+    let status = coroutine.borrow_mut::<Coroutine>()?.status();
+    Ok(Object::new_string(status.to_string()))
+}
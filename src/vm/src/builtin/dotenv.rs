@@ -0,0 +1,205 @@
+use super::*;
+use hashbrown::HashMap;
+use std::{iter::Peekable, str::Chars};
+
+// env.load(path: String, export?: Bool) -> Table
+//
+// Loads a `.env` file as a table of strings. When `export` is true, the variables are also set to
+// the process environment unless they are already set.
+pub fn env_load(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (path, export) = match args {
+        [path] => (path, false),
+        [export, path] => (path, export.clone().ensure_bool()?),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let path = path.clone().ensure_string()?;
+    Permissions::current().check_read(path.as_str())?;
+    let source = current_filesystem()
+        .read(path.as_str())
+        .map_err(|e| RuntimeError::io(&path, e))?;
+    let source = String::from_utf8(source).map_err(|e| RuntimeError::io(&path, e))?;
+    let vars = parse_dotenv(&source).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut table = TableObject::new(HashMap::default());
+    for (key, value) in vars {
+        if export && std::env::var_os(&key).is_none() {
+            std::env::set_var(&key, &value);
+        }
+        table.insert(TableKey::String(key.into()), Object::new_string(value));
+    }
+    Ok(Object::new_table(table))
+}
+
+/// Parses the content of a `.env` file.
+///
+/// - `KEY=VALUE` per line, optionally prefixed with `export`.
+/// - Lines starting with `#` and blank lines are ignored.
+/// - Unquoted values are trimmed, and ` #` starts an inline comment.
+/// - Single-quoted values are taken literally.
+/// - Double-quoted values may span multiple lines and support `\n`, `\r`, `\t`, `\"`, `\\` and `\$`.
+/// - `$NAME` and `${NAME}` in unquoted or double-quoted values are expanded with the variables
+///   defined above, or the process environment.
+pub fn parse_dotenv(source: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    loop {
+        skip_while(&mut chars, &mut line, |c| c.is_whitespace());
+        match chars.peek() {
+            None => break,
+            Some('#') => {
+                skip_while(&mut chars, &mut line, |c| c != '\n');
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        let mut key = take_key(&mut chars);
+        if key == "export" && matches!(chars.peek(), Some(' ' | '\t')) {
+            skip_while(&mut chars, &mut line, |c| c == ' ' || c == '\t');
+            key = take_key(&mut chars);
+        }
+        if key.is_empty() {
+            return Err(format!("line {}: expected a variable name", line));
+        }
+        skip_while(&mut chars, &mut line, |c| c == ' ' || c == '\t');
+        if chars.next() != Some('=') {
+            return Err(format!("line {}: expected `=` after `{}`", line, key));
+        }
+        skip_while(&mut chars, &mut line, |c| c == ' ' || c == '\t');
+
+        let lookup = |name: &str| {
+            vars.iter()
+                .rev()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+                .or_else(|| std::env::var(name).ok())
+                .unwrap_or_default()
+        };
+        let start_line = line;
+        let value = match chars.peek() {
+            Some('\'') => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => {
+                            line += (c == '\n') as usize;
+                            value.push(c);
+                        }
+                        None => return Err(format!("line {}: unterminated `'`", start_line)),
+                    }
+                }
+                value
+            }
+            Some('"') => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('r') => value.push('\r'),
+                            Some('t') => value.push('\t'),
+                            Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                            Some(c) => {
+                                value.push('\\');
+                                value.push(c);
+                            }
+                            None => return Err(format!("line {}: unterminated `\"`", start_line)),
+                        },
+                        Some('$') => expand(&mut chars, &mut value, lookup),
+                        Some(c) => {
+                            line += (c == '\n') as usize;
+                            value.push(c);
+                        }
+                        None => return Err(format!("line {}: unterminated `\"`", start_line)),
+                    }
+                }
+                value
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' || (c == '#' && value.ends_with([' ', '\t'])) {
+                        break;
+                    }
+                    chars.next();
+                    if c == '$' {
+                        expand(&mut chars, &mut value, lookup);
+                    } else {
+                        value.push(c);
+                    }
+                }
+                value.trim_end().to_string()
+            }
+        };
+
+        // Only a comment may follow the value.
+        skip_while(&mut chars, &mut line, |c| {
+            c == ' ' || c == '\t' || c == '\r'
+        });
+        match chars.peek() {
+            None | Some('\n') => {}
+            Some('#') => skip_while(&mut chars, &mut line, |c| c != '\n'),
+            Some(c) => return Err(format!("line {}: unexpected `{}` after value", line, c)),
+        }
+
+        match vars.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => vars.push((key, value)),
+        }
+    }
+    Ok(vars)
+}
+
+fn skip_while(chars: &mut Peekable<Chars>, line: &mut usize, f: impl Fn(char) -> bool) {
+    while let Some(&c) = chars.peek() {
+        if !f(c) {
+            break;
+        }
+        *line += (c == '\n') as usize;
+        chars.next();
+    }
+}
+
+fn take_key(chars: &mut Peekable<Chars>) -> String {
+    let mut key = String::new();
+    while let Some(&c) = chars.peek() {
+        if !(c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+            break;
+        }
+        key.push(c);
+        chars.next();
+    }
+    key
+}
+
+// Expands `$NAME` or `${NAME}`, the leading `$` is already consumed.
+fn expand(chars: &mut Peekable<Chars>, value: &mut String, lookup: impl Fn(&str) -> String) {
+    let braced = chars.peek() == Some(&'{');
+    if braced {
+        chars.next();
+    }
+    let name = take_key(chars);
+    if braced {
+        if chars.peek() == Some(&'}') {
+            chars.next();
+        } else {
+            value.push_str("${");
+            value.push_str(&name);
+            return;
+        }
+    }
+    if name.is_empty() {
+        value.push('$');
+        if braced {
+            value.push_str("{}");
+        }
+    } else {
+        value.push_str(&lookup(&name));
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+use std::ffi::{c_char, c_void, CStr, CString};
+
+// ffi.open(path: String) -> Library
+//
+// Opens a shared library. Its C functions are made callable with
+//   library->function(name: String, args: Array<String>, ret: String) -> Function
+// where the types are one of
+//   "int" (C int), "int64", "double", "string" (a NUL-terminated `const char *`),
+//   "bytes" (a read-only `const void *`, argument only), and "void" (return only).
+//
+// Arguments are passed in registers, so a function takes at most 6 arguments of integer and
+// pointer types and 8 of "double". Nothing checks that the declared signature is right, and a
+// wrong one crashes the process, which is why this module is behind the `ffi` feature.
+pub fn ffi_open(args: &[Object]) -> Result<Object, RuntimeError> {
+    let path = extract_argument!(args, [String]);
+    let permissions = Permissions::current();
+    permissions.check_native_code(path.as_str())?;
+    permissions.check_read(path.as_str())?;
+    if !cfg!(all(
+        any(target_arch = "x86_64", target_arch = "aarch64"),
+        not(windows)
+    )) {
+        return Err("FFI is not supported on this platform".into());
+    }
+    // SAFETY: Opening a library runs its initializers, which scripts using FFI have to trust.
+    let library = unsafe { libloading::Library::new(path.as_str()) }
+        .map_err(|e| RuntimeError::io(&path, e))?;
+    let library = UserdataObject::with_methods("library", Rc::new(library), LIBRARY_METHODS);
+    Ok(Object::Userdata(Rc::new(library)))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CType {
+    Int,
+    Int64,
+    Double,
+    String,
+    Bytes,
+    Void,
+}
+
+impl CType {
+    fn from_name(name: &str) -> Result<Self, RuntimeError> {
+        match name {
+            "int" => Ok(CType::Int),
+            "int64" => Ok(CType::Int64),
+            "double" => Ok(CType::Double),
+            "string" => Ok(CType::String),
+            "bytes" => Ok(CType::Bytes),
+            "void" => Ok(CType::Void),
+            _ => Err(format!("Unknown C type: {}", name).into()),
+        }
+    }
+}
+
+struct CFunction {
+    // Keeps the library loaded while the function can be called.
+    _library: Rc<libloading::Library>,
+    ptr: *const c_void,
+    args: Vec<CType>,
+    ret: CType,
+}
+
+const LIBRARY_METHODS: &[(&str, UserdataMethod)] = &[("function", library_function)];
+
+// function(name: String, args: Array<String>, ret: String) -> Function
+fn library_function(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let (name, arg_types, ret) = extract_argument!(args, [String, Array, String]);
+    let arg_types = arg_types
+        .borrow()
+        .iter()
+        .map(|name| CType::from_name(name.clone().ensure_string()?.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if arg_types.contains(&CType::Void) {
+        return Err("void is not an argument type".into());
+    }
+    let ret = CType::from_name(ret.as_str())?;
+    if ret == CType::Bytes {
+        return Err("bytes is not a return type".into());
+    }
+    let doubles = arg_types.iter().filter(|ty| **ty == CType::Double).count();
+    if doubles > FLOAT_REGS || arg_types.len() - doubles > INT_REGS {
+        return Err(format!(
+            "{}: too many arguments, at most {} integers and {} doubles are supported",
+            name, INT_REGS, FLOAT_REGS
+        )
+        .into());
+    }
+    let library = Rc::clone(&*this.borrow_mut::<Rc<libloading::Library>>()?);
+    // SAFETY: The symbol is only called through `ffi_call`, with the signature the script gave.
+    let ptr = unsafe { library.get::<*const c_void>(name.as_str().as_bytes()) }
+        .map(|symbol| *symbol)
+        .map_err(|e| RuntimeError::io(&name, e))?;
+    if ptr.is_null() {
+        return Err(format!("{}: symbol is null", name).into());
+    }
+    let func = CFunction {
+        _library: library,
+        ptr,
+        args: arg_types,
+        ret,
+    };
+    let func = UserdataObject::new("c_function", func);
+    Ok(Object::new_bound_function(BoundFunctionObject {
+        func: Object::RustFunction(ffi_call),
+        args: vec![Object::Userdata(Rc::new(func))],
+    }))
+}
+
+const INT_REGS: usize = 6;
+const FLOAT_REGS: usize = 8;
+
+// The C calling conventions of x86-64 (System V) and AArch64 assign integer and floating-point
+// arguments to two separate sequences of registers. So any signature within the limits can be
+// called as a function taking all of those registers, with each argument put in the next slot of
+// its kind; the callee reads the registers it expects and ignores the rest.
+#[rustfmt::skip]
+type IntReturn = unsafe extern "C" fn(
+    i64, i64, i64, i64, i64, i64,
+    f64, f64, f64, f64, f64, f64, f64, f64,
+) -> i64;
+#[rustfmt::skip]
+type FloatReturn = unsafe extern "C" fn(
+    i64, i64, i64, i64, i64, i64,
+    f64, f64, f64, f64, f64, f64, f64, f64,
+) -> f64;
+
+// Called with the `CFunction` bound as the first argument, i.e. the last of `args`.
+fn ffi_call(args: &[Object]) -> Result<Object, RuntimeError> {
+    let Some((Object::Userdata(func), args)) = args.split_last() else {
+        unreachable!("ffi_call should be bound to a c_function")
+    };
+    let func = func.borrow_mut::<CFunction>()?;
+    if args.len() != func.args.len() {
+        return Err(format!(
+            "Wrong number of arguments: expected {}, got {}",
+            func.args.len(),
+            args.len()
+        )
+        .into());
+    }
+
+    let mut ints = [0i64; INT_REGS];
+    let mut floats = [0f64; FLOAT_REGS];
+    let (mut int_count, mut float_count) = (0, 0);
+    // The strings passed to C, which must live until the call returns.
+    let mut strings = Vec::new();
+    for (ty, arg) in func.args.iter().zip(args.iter().rev()) {
+        let int = match (ty, arg) {
+            (CType::Int, Object::Int(int)) => i64::from(
+                i32::try_from(*int).map_err(|_| format!("{} does not fit in a C int", int))?,
+            ),
+            (CType::Int64, Object::Int(int)) => *int,
+            (CType::Double, arg) => {
+                floats[float_count] = match arg {
+                    Object::Int(int) => *int as f64,
+                    Object::Float(float) => *float,
+                    arg => return Err(mismatched_type("double", arg)),
+                };
+                float_count += 1;
+                continue;
+            }
+            (CType::String, Object::String(string)) => {
+                let string = CString::new(string.as_str())
+                    .map_err(|_| "A string passed to C must not contain NUL".to_string())?;
+                let ptr = string.as_ptr() as i64;
+                strings.push(string);
+                ptr
+            }
+            (CType::Bytes, Object::Bytes(bytes)) => bytes.as_ptr() as i64,
+            (CType::String | CType::Bytes, Object::Nil) => 0,
+            (ty, arg) => return Err(mismatched_type(&format!("{:?}", ty).to_lowercase(), arg)),
+        };
+        ints[int_count] = int;
+        int_count += 1;
+    }
+
+    let [i0, i1, i2, i3, i4, i5] = ints;
+    let [f0, f1, f2, f3, f4, f5, f6, f7] = floats;
+    // SAFETY: The script declared the signature of the function, which is all FFI can go by.
+    let ret = unsafe {
+        if func.ret == CType::Double {
+            let call = std::mem::transmute::<*const c_void, FloatReturn>(func.ptr);
+            let ret = call(i0, i1, i2, i3, i4, i5, f0, f1, f2, f3, f4, f5, f6, f7);
+            return Ok(Object::Float(ret));
+        }
+        let call = std::mem::transmute::<*const c_void, IntReturn>(func.ptr);
+        call(i0, i1, i2, i3, i4, i5, f0, f1, f2, f3, f4, f5, f6, f7)
+    };
+    drop(strings);
+    match func.ret {
+        CType::Int => Ok(Object::Int(ret as i32 as i64)),
+        CType::Int64 => Ok(Object::Int(ret)),
+        CType::String if ret == 0 => Ok(Object::Nil),
+        CType::String => {
+            // SAFETY: The function is declared to return a NUL-terminated string.
+            let string = unsafe { CStr::from_ptr(ret as *const c_char) };
+            Ok(Object::new_string(string.to_string_lossy().into_owned()))
+        }
+        CType::Void => Ok(Object::Nil),
+        CType::Double | CType::Bytes => unreachable!(),
+    }
+}
+
+fn mismatched_type(expected: &str, arg: &Object) -> RuntimeError {
+    RuntimeError::type_error(format!(
+        "Mismatched argument type: expected {}, got {}",
+        expected,
+        arg.typename()
+    ))
+}
@@ -0,0 +1,296 @@
+use super::*;
+use crate::utf8::read_char;
+use std::{
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+// fs.open(path: String, mode?: String) -> File
+//
+// Opens a file in one of the modes of C's `fopen`: "r" (default), "w", "a", "r+", "w+", "a+".
+// The handle has the methods below, and is closed at the end of `with`.
+pub fn fs_open(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (path, mode) = match args {
+        [path] => (path, None),
+        [mode, path] => (path, Some(mode.clone().ensure_string()?)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let path = path.clone().ensure_string()?;
+    let mode = mode.as_ref().map_or("r", |mode| mode.as_str());
+    let permissions = Permissions::current();
+    if mode.starts_with('r') || mode.ends_with('+') {
+        permissions.check_read(path.as_str())?;
+    }
+    if mode != "r" {
+        permissions.check_write(path.as_str())?;
+    }
+    let (read, write, append, create) = match mode {
+        "r" => (true, false, false, false),
+        "w" => (false, true, false, true),
+        "a" => (false, false, true, true),
+        "r+" => (true, true, false, false),
+        "w+" => (true, true, false, true),
+        "a+" => (true, false, true, true),
+        mode => return Err(format!("Invalid file mode: {}", mode).into()),
+    };
+    let options = OpenOptions {
+        read,
+        write,
+        append,
+        create,
+        truncate: mode.starts_with('w'),
+    };
+    let file = current_filesystem()
+        .open(path.as_str(), &options)
+        .map_err(|e| RuntimeError::io(&path, e))?;
+    let handle = FileHandle(Some(BufReader::new(file)));
+    let userdata = UserdataObject::with_methods("file", handle, FILE_METHODS);
+    Ok(Object::Userdata(Rc::new(userdata)))
+}
+
+// Reads go through the buffer, so it is discarded before writing or seeking to keep the position
+// of the file where the script expects it. `None` means the file is closed.
+struct FileHandle(Option<BufReader<Box<dyn OpenFile>>>);
+
+impl FileHandle {
+    fn get(
+        this: &UserdataObject,
+    ) -> Result<std::cell::RefMut<'_, BufReader<Box<dyn OpenFile>>>, RuntimeError> {
+        let handle = this.borrow_mut::<FileHandle>()?;
+        std::cell::RefMut::filter_map(handle, |handle| handle.0.as_mut())
+            .map_err(|_| "File is already closed".into())
+    }
+}
+
+const FILE_METHODS: &[(&str, UserdataMethod)] = &[
+    ("read", file_read),
+    ("read_bytes", file_read_bytes),
+    ("read_line", file_read_line),
+    ("write", file_write),
+    ("seek", file_seek),
+    ("flush", file_flush),
+    ("close", file_close),
+];
+
+// Returns the optional `count` argument, or None to read everything.
+fn read_count(args: &[Object]) -> Result<Option<usize>, RuntimeError> {
+    match args {
+        [] => Ok(None),
+        [count] => match count.clone().ensure_int()? {
+            count if count < 0 => Err(format!("Count must not be negative, got {}", count).into()),
+            count => Ok(Some(count as usize)),
+        },
+        _ => Err(RuntimeError::arity("0 or 1", args.len())),
+    }
+}
+
+// read(count?: Int) -> String | Nil
+//
+// Reads up to `count` characters, or everything remaining. Returns nil at the end of the file.
+fn file_read(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let count = read_count(args)?;
+    let mut file = FileHandle::get(this)?;
+    let mut string = String::new();
+    match count {
+        None => {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            string = String::from_utf8(buf)
+                .map_err(|e| RuntimeError::new(ErrorKind::Io, e.to_string()))?;
+        }
+        Some(count) => {
+            let mut bytes = file.by_ref().bytes();
+            let mut next_byte = || bytes.next().transpose().map_err(RuntimeError::from);
+            for _ in 0..count {
+                match read_char(&mut next_byte)? {
+                    Some(char) => string.push(char),
+                    None => break,
+                }
+            }
+        }
+    }
+    if string.is_empty() && count != Some(0) {
+        return Ok(Object::Nil);
+    }
+    Ok(Object::new_string(string))
+}
+
+// read_bytes(count?: Int) -> Bytes | Nil
+//
+// Reads up to `count` bytes, or everything remaining. Returns nil at the end of the file.
+fn file_read_bytes(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let count = read_count(args)?;
+    let mut file = FileHandle::get(this)?;
+    let mut buf = Vec::new();
+    let result = match count {
+        None => file.read_to_end(&mut buf),
+        Some(count) => file.by_ref().take(count as u64).read_to_end(&mut buf),
+    };
+    result?;
+    if buf.is_empty() && count != Some(0) {
+        return Ok(Object::Nil);
+    }
+    Ok(Object::new_bytes(buf))
+}
+
+// read_line() -> String | Nil
+//
+// Reads a line without its line ending. Returns nil at the end of the file.
+fn file_read_line(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    let mut file = FileHandle::get(this)?;
+    let mut line = String::new();
+    if file.read_line(&mut line)? == 0 {
+        return Ok(Object::Nil);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Object::new_string(line))
+}
+
+// write(data: String | Bytes) -> Nil
+fn file_write(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let [data] = args else {
+        return Err(RuntimeError::arity(1, args.len()));
+    };
+    let mut file = FileHandle::get(this)?;
+    file.seek(SeekFrom::Current(0))?;
+    let result = match data {
+        Object::String(string) => file.get_mut().write_all(string.as_str().as_bytes()),
+        Object::Bytes(bytes) => file.get_mut().write_all(bytes),
+        data => {
+            return Err(RuntimeError::type_error(format!(
+                "Mismatched argument type: expected string or bytes, got {}",
+                data.typename()
+            )))
+        }
+    };
+    result?;
+    Ok(Object::Nil)
+}
+
+// seek(offset: Int, whence?: "set" | "cur" | "end") -> Int
+//
+// Moves to `offset` bytes from the start (default), the current position, or the end, and returns
+// the new position from the start.
+fn file_seek(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let (offset, whence) = match args {
+        [offset] => (offset, None),
+        [whence, offset] => (offset, Some(whence.clone().ensure_string()?)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let offset = offset.clone().ensure_int()?;
+    let pos = match whence.as_ref().map_or("set", |whence| whence.as_str()) {
+        "set" if offset < 0 => return Err(format!("Invalid offset: {}", offset).into()),
+        "set" => SeekFrom::Start(offset as u64),
+        "cur" => SeekFrom::Current(offset),
+        "end" => SeekFrom::End(offset),
+        whence => return Err(format!("Invalid whence: {}", whence).into()),
+    };
+    let pos = FileHandle::get(this)?.seek(pos)?;
+    Ok(Object::Int(pos as i64))
+}
+
+// flush() -> Nil
+fn file_flush(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    let mut file = FileHandle::get(this)?;
+    file.get_mut().flush()?;
+    Ok(Object::Nil)
+}
+
+// close() -> Nil
+//
+// Closing a closed file does nothing, so that `close` inside `with` is harmless.
+fn file_close(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    let file = this.borrow_mut::<FileHandle>()?.0.take();
+    if let Some(mut file) = file {
+        file.get_mut().flush()?;
+    }
+    Ok(Object::Nil)
+}
+
+// fs.write_atomic(path: String, content: String | Bytes) -> Nil
+//
+// Writes `content` to a temporary file next to `path` and renames it over `path`, so that readers
+// see either the old or the new content, never a partially written file.
+pub fn fs_write_atomic(args: &[Object]) -> Result<Object, RuntimeError> {
+    let [content, path] = args else {
+        return Err(RuntimeError::arity(2, args.len()));
+    };
+    let path = path.clone().ensure_string()?;
+    Permissions::current().check_write(path.as_str())?;
+    let content = match content {
+        Object::String(string) => string.as_str().as_bytes(),
+        Object::Bytes(bytes) => bytes,
+        content => {
+            return Err(RuntimeError::type_error(format!(
+                "Mismatched argument type: expected string or bytes, got {}",
+                content.typename()
+            )))
+        }
+    };
+    let target = Path::new(path.as_str());
+    let Some(name) = target.file_name() else {
+        return Err(format!("{}: Not a file path", path).into());
+    };
+    // The temporary file must be on the same filesystem as `path` for the rename to be atomic.
+    let temp = target.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let temp = temp.to_string_lossy();
+    let filesystem = current_filesystem();
+    let options = OpenOptions {
+        write: true,
+        create: true,
+        truncate: true,
+        ..OpenOptions::default()
+    };
+    let result = filesystem
+        .open(&temp, &options)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync()
+        })
+        .and_then(|()| filesystem.rename(&temp, path.as_str()));
+    if let Err(err) = result {
+        let _ = filesystem.remove_file(&temp);
+        return Err(RuntimeError::io(&path, err));
+    }
+    Ok(Object::Nil)
+}
+
+// fs.lock(path: String) -> Lock
+//
+// Takes an exclusive advisory lock on `path`, creating the file if needed, and waits while another
+// process holds it. The lock is released by `unlock`, at the end of `with`, or when the script ends.
+pub fn fs_lock(args: &[Object]) -> Result<Object, RuntimeError> {
+    let path = extract_argument!(args, [String]);
+    Permissions::current().check_write(path.as_str())?;
+    let lock = current_filesystem()
+        .lock(path.as_str())
+        .map_err(|e| RuntimeError::io(&path, e))?;
+    let userdata = UserdataObject::with_methods("lock", Some(lock), LOCK_METHODS);
+    Ok(Object::Userdata(Rc::new(userdata)))
+}
+
+const LOCK_METHODS: &[(&str, UserdataMethod)] = &[("unlock", lock_unlock), ("close", lock_unlock)];
+
+// unlock() -> Nil
+//
+// Unlocking a released lock does nothing. `close` is the same, so that the lock works with `with`.
+fn lock_unlock(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    let lock = this.borrow_mut::<Option<Box<dyn FileLock>>>()?.take();
+    if let Some(lock) = lock {
+        lock.unlock()?;
+    }
+    Ok(Object::Nil)
+}
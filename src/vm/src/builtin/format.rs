@@ -0,0 +1,195 @@
+use super::*;
+
+// format(format: String, ...values: Any) -> String
+//
+// Writes `values` into `format` in place of its conversions, as printf does. A conversion is
+// `%[flags][width][.precision]kind`:
+//   - kind: `d` for an Int, `x` for an Int in hex, `f` for a Float or an Int, `s` for any value,
+//     which is written as `print` does, and `%%` for `%` itself.
+//   - flags: `-` to pad on the right, `0` to pad numbers with zeros, `+` to sign positive numbers.
+//   - precision: the digits after the point for `f` (6 by default), and the maximum number of
+//     characters for `s`.
+// Neither the width nor the precision may be over `MAX_WIDTH`.
+pub fn format(args: &[Object]) -> Result<Object, RuntimeError> {
+    let Some((format, values)) = args.split_last() else {
+        return Err(RuntimeError::arity("at least 1", 0));
+    };
+    let format = format.clone().ensure_string()?;
+    let pieces = parse(format.as_str())?;
+    let conversions = pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Conversion(_)))
+        .count();
+    if values.len() != conversions {
+        return Err(RuntimeError::arity(conversions + 1, args.len()));
+    }
+
+    let mut values = values.iter().rev();
+    let mut buf = String::with_capacity(format.as_str().len());
+    for piece in pieces {
+        match piece {
+            Piece::Literal(literal) => buf.push_str(literal),
+            Piece::Conversion(spec) => {
+                let value = values
+                    .next()
+                    .ok_or_else(|| RuntimeError::arity(conversions + 1, args.len()))?;
+                spec.write(value, &mut buf)?;
+            }
+        }
+    }
+    Ok(Object::new_string(buf))
+}
+
+const MAX_WIDTH: usize = u16::MAX as usize;
+
+enum Piece<'a> {
+    Literal(&'a str),
+    Conversion(Spec),
+}
+
+struct Spec {
+    left: bool,
+    zero: bool,
+    plus: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: char,
+}
+
+fn parse(format: &str) -> Result<Vec<Piece<'_>>, RuntimeError> {
+    let mut pieces = Vec::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('%') {
+        if start > 0 {
+            pieces.push(Piece::Literal(&rest[..start]));
+        }
+        let mut chars = rest[start + 1..].char_indices().peekable();
+        let mut spec = Spec {
+            left: false,
+            zero: false,
+            plus: false,
+            width: 0,
+            precision: None,
+            kind: '%',
+        };
+        while let Some((_, flag @ ('-' | '0' | '+'))) = chars.peek().copied() {
+            match flag {
+                '-' => spec.left = true,
+                '0' => spec.zero = true,
+                _ => spec.plus = true,
+            }
+            chars.next();
+        }
+        let number = |chars: &mut std::iter::Peekable<std::str::CharIndices>| {
+            let mut number = 0usize;
+            while let Some((_, digit @ '0'..='9')) = chars.peek().copied() {
+                number = number
+                    .saturating_mul(10)
+                    .saturating_add(digit as usize - '0' as usize);
+                chars.next();
+            }
+            number
+        };
+        spec.width = number(&mut chars);
+        if let Some((_, '.')) = chars.peek() {
+            chars.next();
+            spec.precision = Some(number(&mut chars));
+        }
+        if spec.width > MAX_WIDTH || spec.precision.is_some_and(|p| p > MAX_WIDTH) {
+            return Err(format!(
+                "Width and precision in the format should be at most {}",
+                MAX_WIDTH
+            )
+            .into());
+        }
+        let Some((offset, kind)) = chars.next() else {
+            return Err("Incomplete conversion at the end of the format".into());
+        };
+        let end = start + 1 + offset + kind.len_utf8();
+        spec.kind = kind;
+        match kind {
+            '%' => pieces.push(Piece::Literal("%")),
+            'd' | 'x' | 'f' | 's' => pieces.push(Piece::Conversion(spec)),
+            _ => {
+                return Err(
+                    format!("Unknown conversion `{}` in the format", &rest[start..end]).into(),
+                )
+            }
+        }
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Literal(rest));
+    }
+    Ok(pieces)
+}
+
+impl Spec {
+    fn write(&self, value: &Object, buf: &mut String) -> Result<(), RuntimeError> {
+        let mismatch = |expected: &str| {
+            RuntimeError::type_error(format!(
+                "Expected {} for `%{}`, but got {}",
+                expected,
+                self.kind,
+                value.typename()
+            ))
+        };
+        // The sign is apart from the digits, for the zeros to go between them.
+        let (sign, body) = match (self.kind, value) {
+            ('d', Object::Int(int)) => (self.sign(*int < 0), int.unsigned_abs().to_string()),
+            ('x', Object::Int(int)) => (self.sign(*int < 0), format!("{:x}", int.unsigned_abs())),
+            ('f', Object::Int(_) | Object::Float(_)) => {
+                let float = match value {
+                    Object::Int(int) => *int as f64,
+                    Object::Float(float) => *float,
+                    _ => unreachable!("`%f` should be matched with an Int or a Float"),
+                };
+                let precision = self.precision.unwrap_or(6);
+                let sign = self.sign(float.is_sign_negative() && float != 0.0);
+                (sign, format!("{:.*}", precision, float.abs()))
+            }
+            ('d' | 'x', _) => return Err(mismatch("Int")),
+            ('f', _) => return Err(mismatch("Float or Int")),
+            (_, value) => {
+                let mut string = match value {
+                    Object::String(string) => string.as_str().to_string(),
+                    value => {
+                        let mut inspector = Inspector::new(8, true);
+                        inspector.write(value, 0);
+                        inspector.buf
+                    }
+                };
+                if let Some(precision) = self.precision {
+                    if let Some((index, _)) = string.char_indices().nth(precision) {
+                        string.truncate(index);
+                    }
+                }
+                ("", string)
+            }
+        };
+
+        let len = sign.len() + body.chars().count();
+        let padding = self.width.saturating_sub(len);
+        let numeric = self.kind != 's';
+        if self.left {
+            buf.extend([sign, &body]);
+            buf.extend(std::iter::repeat_n(' ', padding));
+        } else if self.zero && numeric {
+            buf.push_str(sign);
+            buf.extend(std::iter::repeat_n('0', padding));
+            buf.push_str(&body);
+        } else {
+            buf.extend(std::iter::repeat_n(' ', padding));
+            buf.extend([sign, &body]);
+        }
+        Ok(())
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.plus) {
+            (true, _) => "-",
+            (false, true) => "+",
+            (false, false) => "",
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::*;
+
+// glob_match(pattern: String, path: String) -> Bool
+pub fn glob_match(args: &[Object]) -> Result<Object, RuntimeError> {
+    let [path, pattern] = args else {
+        return Err(RuntimeError::arity(2, args.len()));
+    };
+    let pattern = pattern.clone().ensure_string()?;
+    let path = path.clone().ensure_string()?;
+    Ok(Object::Bool(path_matches(pattern.as_str(), path.as_str())))
+}
+
+// fs.glob(pattern: String) -> Array<String>
+pub fn fs_glob(args: &[Object]) -> Result<Object, RuntimeError> {
+    let [pattern] = args else {
+        return Err(RuntimeError::arity(1, args.len()));
+    };
+    let pattern = pattern.clone().ensure_string()?;
+    let pattern = pattern.as_str();
+
+    // The leading segments without any wildcard are used as the directory to start walking.
+    let absolute = pattern.starts_with('/');
+    let segments = split_segments(pattern);
+    let literal_len = segments
+        .iter()
+        .take_while(|s| !has_wildcard(s))
+        .count()
+        .min(segments.len().saturating_sub(1));
+    let (base, rest) = segments.split_at(literal_len);
+    let base = match (absolute, base.join("/")) {
+        (true, base) => format!("/{}", base),
+        (false, base) => base,
+    };
+    Permissions::current().check_read(if base.is_empty() { "." } else { &base })?;
+
+    let filesystem = current_filesystem();
+    let mut matched = Vec::new();
+    if rest.is_empty() {
+        if filesystem.metadata(&base).is_ok() {
+            matched.push(base);
+        }
+    } else {
+        let max_depth = match rest.contains(&"**") {
+            true => usize::MAX,
+            false => rest.len(),
+        };
+        let mut relative = Vec::new();
+        walk(
+            &*filesystem,
+            &base,
+            &mut relative,
+            max_depth,
+            rest,
+            &mut matched,
+        )?;
+    }
+    matched.sort();
+    Ok(matched.into())
+}
+
+fn walk(
+    filesystem: &dyn FileSystem,
+    dir: &str,
+    relative: &mut Vec<String>,
+    max_depth: usize,
+    pattern: &[&str],
+    matched: &mut Vec<String>,
+) -> Result<(), RuntimeError> {
+    if relative.len() >= max_depth {
+        return Ok(());
+    }
+    let names = match filesystem.read_dir(if dir.is_empty() { "." } else { dir }) {
+        Ok(names) => names,
+        // The starting directory may not exist, which simply means nothing matched.
+        Err(_) if relative.is_empty() => return Ok(()),
+        Err(e) => return Err(RuntimeError::io(dir, e)),
+    };
+    for name in names {
+        let path = match dir {
+            "" => name.clone(),
+            "/" => format!("/{}", name),
+            dir => format!("{}/{}", dir, name),
+        };
+        let is_dir = filesystem
+            .metadata(&path)
+            .map_err(|e| RuntimeError::io(&path, e))?
+            .is_dir;
+        relative.push(name);
+        let segments = relative.iter().map(String::as_str).collect::<Vec<_>>();
+        if match_segments(pattern, &segments) {
+            matched.push(path.clone());
+        }
+        if is_dir {
+            walk(filesystem, &path, relative, max_depth, pattern, matched)?;
+        }
+        relative.pop();
+    }
+    Ok(())
+}
+
+/// Whether `path` matches the glob `pattern`, as `glob_match` does.
+pub(super) fn path_matches(pattern: &str, path: &str) -> bool {
+    match_segments(&split_segments(pattern), &split_segments(path))
+}
+
+fn split_segments(s: &str) -> Vec<&str> {
+    s.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+fn has_wildcard(segment: &str) -> bool {
+    segment.contains(['*', '?', '['])
+}
+
+// `**` matches zero or more whole segments, the rest is matched segment by segment.
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                let first = first.chars().collect::<Vec<_>>();
+                let name = name.chars().collect::<Vec<_>>();
+                match_segment(&first, &name) && match_segments(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+// Supports `*`, `?`, `[abc]`, `[a-z]` and `[!abc]`.
+fn match_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| match_segment(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && match_segment(rest, &name[1..]),
+        Some(('[', rest)) => {
+            let Some(close) = rest.iter().skip(1).position(|c| *c == ']').map(|i| i + 1) else {
+                // An unclosed `[` is matched literally.
+                return name.first() == Some(&'[') && match_segment(rest, &name[1..]);
+            };
+            let Some((c, name)) = name.split_first() else {
+                return false;
+            };
+            let (negated, class) = match rest[..close].split_first() {
+                Some(('!', class)) => (true, class),
+                _ => (false, &rest[..close]),
+            };
+            let mut hit = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    hit |= (class[i]..=class[i + 2]).contains(c);
+                    i += 3;
+                } else {
+                    hit |= class[i] == *c;
+                    i += 1;
+                }
+            }
+            hit != negated && match_segment(&rest[close + 1..], name)
+        }
+        Some((p, rest)) => name.first() == Some(p) && match_segment(rest, &name[1..]),
+    }
+}
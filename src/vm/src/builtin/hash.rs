@@ -0,0 +1,72 @@
+use super::*;
+use sha2::Digest;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x00000100000001b3;
+
+enum Format {
+    Hex,
+    Bytes,
+}
+
+// Extracts `(data: String | Bytes, format?: "hex" | "bytes")`.
+fn extract_hash_args(args: &[Object]) -> Result<(Vec<u8>, Format), RuntimeError> {
+    let (data, format) = match args {
+        [data] => (data, None),
+        [format, data] => (data, Some(format.clone().ensure_string()?)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let data = match data {
+        Object::String(x) => x.as_str().as_bytes().to_vec(),
+        Object::Bytes(x) => x.to_vec(),
+        x => return Err(format!("Expected String or Bytes, but got {}", x.typename()).into()),
+    };
+    let format = match format.as_ref().map(|x| x.as_str()) {
+        None | Some("hex") => Format::Hex,
+        Some("bytes") => Format::Bytes,
+        Some(x) => return Err(format!("Unknown format: {}, expected hex or bytes", x).into()),
+    };
+    Ok((data, format))
+}
+
+fn hash_with(args: &[Object], f: impl FnOnce(&[u8]) -> Vec<u8>) -> Result<Object, RuntimeError> {
+    let (data, format) = extract_hash_args(args)?;
+    let digest = f(&data);
+    Ok(match format {
+        Format::Hex => Object::new_string(to_hex(&digest)),
+        Format::Bytes => Object::new_bytes(digest),
+    })
+}
+
+// hash.sha256(data: String | Bytes, format?: String) -> String | Bytes
+pub fn sha256(args: &[Object]) -> Result<Object, RuntimeError> {
+    hash_with(args, |data| sha2::Sha256::digest(data).to_vec())
+}
+
+// hash.sha1(data: String | Bytes, format?: String) -> String | Bytes
+pub fn sha1(args: &[Object]) -> Result<Object, RuntimeError> {
+    hash_with(args, |data| sha1::Sha1::digest(data).to_vec())
+}
+
+// hash.md5(data: String | Bytes, format?: String) -> String | Bytes
+pub fn md5(args: &[Object]) -> Result<Object, RuntimeError> {
+    hash_with(args, |data| md5::Md5::digest(data).to_vec())
+}
+
+// hash.crc32(data: String | Bytes, format?: String) -> String | Bytes
+pub fn crc32(args: &[Object]) -> Result<Object, RuntimeError> {
+    hash_with(args, |data| crc32fast::hash(data).to_be_bytes().to_vec())
+}
+
+// hash.fnv(data: String | Bytes, format?: String) -> String | Bytes
+// 64-bit FNV-1a.
+pub fn fnv(args: &[Object]) -> Result<Object, RuntimeError> {
+    hash_with(args, |data| {
+        data.iter()
+            .fold(FNV_OFFSET_BASIS, |hash, b| {
+                (hash ^ *b as u64).wrapping_mul(FNV_PRIME)
+            })
+            .to_be_bytes()
+            .to_vec()
+    })
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+type NativeIterator = Box<dyn Iterator<Item = Result<Object, RuntimeError>>>;
+
+/// Makes an iterator for `for-in` that pulls the items from `iter` one at a time, so that they
+/// don't have to be collected up front.
+pub fn make_iterator(iter: impl Iterator<Item = Result<Object, RuntimeError>> + 'static) -> Object {
+    let iteration = Iteration::Native(Box::new(iter));
+    Object::Userdata(Rc::new(UserdataObject::new("iterator", iteration)))
+}
+
+/// Where a `for-in` loop over a value that iterates natively is, kept in the loop's hidden local.
+///
+/// Arrays yield their items, strings their characters, and tables `[key, value]` pairs. Tables
+/// with a `__next` method are iterated by calling it instead, so they never get here.
+pub(crate) enum Iteration {
+    Array {
+        array: Rc<RefCell<ArrayObject>>,
+        version: u64,
+        index: usize,
+    },
+    String {
+        string: StringObject,
+        offset: usize,
+    },
+    /// The pairs are taken when the loop starts, so the table may be changed in the loop.
+    Table {
+        pairs: std::vec::IntoIter<(TableKey, Object)>,
+    },
+    Native(NativeIterator),
+}
+
+impl Iteration {
+    /// Starts iterating over `iterable`, as an `iterator` userdata to keep in the loop's local.
+    pub fn start(iterable: Object) -> Result<Object, RuntimeError> {
+        let iteration = match iterable {
+            Object::Array(array) => {
+                let version = array.borrow().version();
+                Iteration::Array {
+                    array,
+                    version,
+                    index: 0,
+                }
+            }
+            Object::String(string) => Iteration::String { string, offset: 0 },
+            Object::Table(table) => {
+                let pairs = (table.borrow().iter())
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<Vec<_>>();
+                Iteration::Table {
+                    pairs: pairs.into_iter(),
+                }
+            }
+            Object::Userdata(userdata) if userdata.name() == "iterator" => {
+                return Ok(Object::Userdata(userdata));
+            }
+            Object::Userdata(userdata) => {
+                return Err(RuntimeError::type_error(format!(
+                    "{} is not iterable",
+                    userdata.name()
+                )));
+            }
+            other => {
+                return Err(RuntimeError::type_error(format!(
+                    "{} is not iterable",
+                    other.typename()
+                )));
+            }
+        };
+        Ok(Object::Userdata(Rc::new(UserdataObject::new(
+            "iterator", iteration,
+        ))))
+    }
+
+    /// The next item, or `None` when the iteration is over.
+    pub fn next(&mut self) -> Result<Option<Object>, RuntimeError> {
+        match self {
+            Iteration::Array {
+                array,
+                version,
+                index,
+            } => {
+                let array = array.borrow();
+                if array.version() != *version {
+                    return Err("array modified during iteration".into());
+                }
+                let item = array.get(*index).cloned();
+                *index += 1;
+                Ok(item)
+            }
+            Iteration::String { string, offset } => {
+                let Some(char) = string.as_str()[*offset..].chars().next() else {
+                    return Ok(None);
+                };
+                *offset += char.len_utf8();
+                Ok(Some(Object::new_string(char.to_string())))
+            }
+            Iteration::Table { pairs } => Ok(pairs.next().map(|(key, value)| {
+                Object::new_array(ArrayObject::new(vec![key.to_object(), value]))
+            })),
+            Iteration::Native(iter) => iter.next().transpose(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+use std::io::{BufRead, BufReader, Stdin};
+
+// io.lines() -> Iterator<String>
+//
+// Iterates over the lines of stdin, reading one line per step.
+pub fn io_lines(args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    Ok(make_iterator(LineSource::Stdin(std::io::stdin())))
+}
+
+// fs.lines(path: String) -> Iterator<String>
+//
+// Iterates over the lines of a file, reading one line per step, so that the whole file is never
+// loaded at once.
+pub fn fs_lines(args: &[Object]) -> Result<Object, RuntimeError> {
+    let path = extract_argument!(args, [String]);
+    Permissions::current().check_read(path.as_str())?;
+    let options = OpenOptions {
+        read: true,
+        ..OpenOptions::default()
+    };
+    let file = current_filesystem()
+        .open(path.as_str(), &options)
+        .map_err(|e| RuntimeError::io(&path, e))?;
+    Ok(make_iterator(LineSource::Reader(Box::new(BufReader::new(
+        file,
+    )))))
+}
+
+pub(super) enum LineSource {
+    // Stdin is locked only while reading a line, so that `read_line` can be used in between.
+    Stdin(Stdin),
+    Reader(Box<dyn BufRead>),
+}
+
+impl Iterator for LineSource {
+    type Item = Result<Object, RuntimeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        let len = match self {
+            LineSource::Stdin(stdin) => stdin.read_line(&mut line),
+            LineSource::Reader(reader) => reader.read_line(&mut line),
+        };
+        match len {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(Object::new_string(line)))
+            }
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
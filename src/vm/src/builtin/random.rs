@@ -0,0 +1,37 @@
+use super::*;
+use rand::Rng;
+
+const DEFAULT_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// uuid.v4() -> String
+pub fn uuid_v4(args: &[Object]) -> Result<Object, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::arity(0, args.len()));
+    }
+    Ok(Object::new_string(uuid::Uuid::new_v4().to_string()))
+}
+
+// random_id(len: Int, alphabet?: String) -> String
+pub fn random_id(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (len, alphabet) = match args {
+        [len] => (len, None),
+        [alphabet, len] => (len, Some(alphabet.clone().ensure_string()?)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let len = len.clone().ensure_int()?;
+    if len < 0 {
+        return Err(format!("Length must be non-negative, got {}", len).into());
+    }
+    let alphabet = match &alphabet {
+        Some(alphabet) => alphabet.as_str().chars().collect::<Vec<_>>(),
+        None => DEFAULT_ALPHABET.chars().collect(),
+    };
+    if alphabet.is_empty() {
+        return Err("Alphabet must not be empty".into());
+    }
+    let mut rng = rand::thread_rng();
+    let id = (0..len)
+        .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+        .collect();
+    Ok(Object::new_string(id))
+}
@@ -0,0 +1,205 @@
+use super::*;
+use hashbrown::HashMap;
+use std::{
+    io::{BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+// subprocess.spawn(program: String, args?: Array<String>, options?: Table) -> Process
+//
+// Starts `program` without waiting for it. The returned table is
+//   { pid = Int, stdin = Pipe | Nil, stdout = Iterator<String> | Nil, stderr = Iterator<String> | Nil }
+// with the methods `wait(timeout?)` and `kill()`. `stdout` and `stderr` yield the output line by
+// line as the child writes it, and `stdin` has the methods `write(data)` and `close()`.
+//
+// options:
+//   cwd: String                           -- the working directory of the child
+//   stdin, stdout, stderr: String         -- "pipe" (default), "inherit", or "null"
+//
+// A pipe the script doesn't read can fill up and block the child, so set the streams the script
+// doesn't need to "inherit" or "null".
+pub fn subprocess_spawn(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (program, program_args, options) = match args {
+        [program] => (program, None, None),
+        [program_args, program] => (program, Some(program_args), None),
+        [options, program_args, program] => (program, Some(program_args), Some(options)),
+        _ => return Err(RuntimeError::arity("1 to 3", args.len())),
+    };
+    let program = program.clone().ensure_string()?;
+    Permissions::current().check_spawn(program.as_str())?;
+    let mut command = Command::new(program.as_str());
+    if let Some(program_args) = program_args {
+        for arg in program_args.clone().ensure_array()?.borrow().iter() {
+            command.arg(arg.clone().ensure_string()?.as_str());
+        }
+    }
+    let mut stdio = [Stdio::piped, Stdio::piped, Stdio::piped].map(|piped| piped());
+    if let Some(options) = options {
+        let options = options.clone().ensure_table()?;
+        let options = options.borrow();
+        if let Some(cwd) = options.get("cwd") {
+            command.current_dir(cwd.clone().ensure_string()?.as_str());
+        }
+        for (stdio, name) in stdio.iter_mut().zip(["stdin", "stdout", "stderr"]) {
+            if let Some(mode) = options.get(name) {
+                *stdio = match mode.clone().ensure_string()?.as_str() {
+                    "pipe" => Stdio::piped(),
+                    "inherit" => Stdio::inherit(),
+                    "null" => Stdio::null(),
+                    mode => return Err(format!("Invalid {} mode: {}", name, mode).into()),
+                };
+            }
+        }
+    }
+    let [stdin, stdout, stderr] = stdio;
+    let mut child = command
+        .stdin(stdin)
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .map_err(|e| RuntimeError::io(&program, e))?;
+
+    let mut table = TableObject::new(HashMap::default());
+    table.insert("pid".into(), Object::Int(child.id() as i64));
+    let stdin = child.stdin.take().map(|stdin| {
+        let pipe = UserdataObject::with_methods("pipe", Some(stdin), PIPE_METHODS);
+        Object::Userdata(Rc::new(pipe))
+    });
+    table.insert("stdin".into(), stdin.into());
+    let stdout = child
+        .stdout
+        .take()
+        .map(|stdout| make_iterator(LineSource::Reader(Box::new(BufReader::new(stdout)))));
+    table.insert("stdout".into(), stdout.into());
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| make_iterator(LineSource::Reader(Box::new(BufReader::new(stderr)))));
+    table.insert("stderr".into(), stderr.into());
+    let child = UserdataObject::new("process", child);
+    table.insert("__child".into(), Object::Userdata(Rc::new(child)));
+    table.add_method(
+        "wait", // wait(timeout?: Int | Float) -> Int | Nil
+        TableMethod::Builtin(process_wait),
+    );
+    table.add_method(
+        "kill", // kill() -> Nil
+        TableMethod::Builtin(process_kill),
+    );
+    Ok(Object::new_table(table))
+}
+
+fn get_child(process: &Rc<RefCell<TableObject>>) -> Rc<UserdataObject> {
+    let Some(Object::Userdata(child)) = process.borrow().get("__child").cloned() else {
+        unreachable!("process should have `__child`")
+    };
+    child
+}
+
+// Waits for the child to exit and returns its exit code, or nil if `timeout` seconds pass first.
+// A child killed by a signal exits with 128 plus the signal number, as in shells.
+fn process_wait(
+    process: Rc<RefCell<TableObject>>,
+    args: &[Object],
+) -> Result<Object, RuntimeError> {
+    let timeout = match args {
+        [] => None,
+        [timeout] => {
+            let seconds = match timeout {
+                Object::Int(x) => *x as f64,
+                Object::Float(x) => *x,
+                x => Err(RuntimeError::type_error(format!(
+                    "Expected `int` or `float` as timeout, got `{}`",
+                    x.typename()
+                )))?,
+            };
+            let timeout = Duration::try_from_secs_f64(seconds.max(0.0))
+                .map_err(|_| format!("Invalid timeout: {}", seconds))?;
+            Some(timeout)
+        }
+        _ => return Err(RuntimeError::arity("0 or 1", args.len())),
+    };
+    let child = get_child(&process);
+    let mut child = child.borrow_mut::<Child>()?;
+    let status = match timeout {
+        None => child.wait()?,
+        Some(timeout) => {
+            const STEP: Duration = Duration::from_millis(10);
+            let deadline = Instant::now() + timeout;
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(Object::Nil);
+                }
+                std::thread::sleep(STEP.min(deadline - now));
+            }
+        }
+    };
+    Ok(Object::Int(exit_code(status) as i64))
+}
+
+#[cfg(unix)]
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(-1)
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    status.code().unwrap_or(-1)
+}
+
+// Killing a child that has already exited does nothing.
+fn process_kill(
+    process: Rc<RefCell<TableObject>>,
+    args: &[Object],
+) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    let child = get_child(&process);
+    let mut child = child.borrow_mut::<Child>()?;
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+    }
+    Ok(Object::Nil)
+}
+
+const PIPE_METHODS: &[(&str, UserdataMethod)] = &[("write", pipe_write), ("close", pipe_close)];
+
+// write(data: String | Bytes) -> Nil
+fn pipe_write(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    let [data] = args else {
+        return Err(RuntimeError::arity(1, args.len()));
+    };
+    let mut pipe = this.borrow_mut::<Option<ChildStdin>>()?;
+    let Some(pipe) = pipe.as_mut() else {
+        return Err("Pipe is already closed".into());
+    };
+    let result = match data {
+        Object::String(string) => pipe.write_all(string.as_str().as_bytes()),
+        Object::Bytes(bytes) => pipe.write_all(bytes),
+        data => {
+            return Err(RuntimeError::type_error(format!(
+                "Mismatched argument type: expected string or bytes, got {}",
+                data.typename()
+            )))
+        }
+    };
+    result?;
+    Ok(Object::Nil)
+}
+
+// close() -> Nil
+//
+// Closing tells the child that there is no more input. Closing a closed pipe does nothing.
+fn pipe_close(this: &UserdataObject, args: &[Object]) -> Result<Object, RuntimeError> {
+    extract_argument!(args, []);
+    this.borrow_mut::<Option<ChildStdin>>()?.take();
+    Ok(Object::Nil)
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+// term.color(text: Any, fg: String, bg?: String) -> String
+//
+// Wraps `text` in the ANSI escape codes for the foreground color `fg` and the background color
+// `bg`. A color is one of "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+// optionally prefixed with "bright_", or nil to keep the current color.
+pub fn term_color(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (text, fg, bg) = match args {
+        [fg, text] => (text, fg, &Object::Nil),
+        [bg, fg, text] => (text, fg, bg),
+        _ => return Err(RuntimeError::arity("2 or 3", args.len())),
+    };
+    let mut codes = Vec::new();
+    for (color, base) in [(fg, 30), (bg, 40)] {
+        if let Object::Nil = color {
+            continue;
+        }
+        let color = color.clone().ensure_string()?;
+        let (name, offset) = match color.as_str().strip_prefix("bright_") {
+            Some(name) => (name, base + 60),
+            None => (color.as_str(), base),
+        };
+        let index = match name {
+            "black" => 0,
+            "red" => 1,
+            "green" => 2,
+            "yellow" => 3,
+            "blue" => 4,
+            "magenta" => 5,
+            "cyan" => 6,
+            "white" => 7,
+            _ => return Err(format!("Unknown color: {}", color).into()),
+        };
+        codes.push((offset + index).to_string());
+    }
+    if codes.is_empty() {
+        return Ok(Object::new_string(text.to_string()));
+    }
+    Ok(Object::new_string(format!(
+        "\x1b[{}m{}\x1b[0m",
+        codes.join(";"),
+        text
+    )))
+}
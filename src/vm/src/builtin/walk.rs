@@ -0,0 +1,117 @@
+use super::*;
+use hashbrown::HashMap;
+use std::path::{Path, PathBuf};
+
+// fs.walk(root: String, options?: Table) -> Iterator<Table>
+//
+// Iterates over the entries under `root` in depth-first order, reading each directory only when
+// the walk reaches it. Entries of a directory come in name order. Each entry is a table
+//   { path = String, is_dir = Bool, size = Int, depth = Int }
+// where `depth` is 1 for the direct children of `root`.
+//
+// options:
+//   max_depth: Int     -- do not descend deeper than this (default: unlimited)
+//   pattern: String    -- only yield entries whose path relative to `root` matches this glob
+//   dirs: Bool         -- whether to yield directories (default: true)
+pub fn fs_walk(args: &[Object]) -> Result<Object, RuntimeError> {
+    let (root, options) = match args {
+        [root] => (root, None),
+        [options, root] => (root, Some(options.clone().ensure_table()?)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let root = root.clone().ensure_string()?;
+    Permissions::current().check_read(root.as_str())?;
+    let root = PathBuf::from(root.as_str());
+    let mut walk = Walk {
+        filesystem: current_filesystem(),
+        pending: Vec::new(),
+        root: root.clone(),
+        max_depth: usize::MAX,
+        pattern: None,
+        dirs: true,
+    };
+    if let Some(options) = options {
+        let options = options.borrow();
+        if let Some(max_depth) = options.get("max_depth") {
+            walk.max_depth = max_depth.clone().ensure_int()?.max(0) as usize;
+        }
+        if let Some(pattern) = options.get("pattern") {
+            walk.pattern = Some(pattern.clone().ensure_string()?.as_str().to_string());
+        }
+        if let Some(dirs) = options.get("dirs") {
+            walk.dirs = dirs.clone().ensure_bool()?;
+        }
+    }
+    if walk.max_depth > 0 {
+        walk.push_children(&root, 1)?;
+    }
+    Ok(make_iterator(walk))
+}
+
+struct Walk {
+    // The filesystem of the runtime that started the walk.
+    filesystem: Rc<dyn FileSystem>,
+    // Entries to visit, the next one at the end.
+    pending: Vec<(PathBuf, usize)>,
+    root: PathBuf,
+    max_depth: usize,
+    pattern: Option<String>,
+    dirs: bool,
+}
+
+impl Walk {
+    fn push_children(&mut self, dir: &Path, depth: usize) -> Result<(), RuntimeError> {
+        let names = self
+            .filesystem
+            .read_dir(&dir.to_string_lossy())
+            .map_err(|e| RuntimeError::io(dir.display(), e))?;
+        let mut children = (names.into_iter())
+            .map(|name| (dir.join(name), depth))
+            .collect::<Vec<_>>();
+        children.sort_by(|(lhs, _), (rhs, _)| rhs.cmp(lhs));
+        self.pending.extend(children);
+        Ok(())
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Object>, RuntimeError> {
+        while let Some((path, depth)) = self.pending.pop() {
+            // Symbolic links are not followed, so that a link to an ancestor doesn't loop forever.
+            let metadata = self
+                .filesystem
+                .metadata(&path.to_string_lossy())
+                .map_err(|e| RuntimeError::io(path.display(), e))?;
+            let is_dir = metadata.is_dir;
+            if is_dir && depth < self.max_depth {
+                self.push_children(&path, depth + 1)?;
+            }
+            if is_dir && !self.dirs {
+                continue;
+            }
+            if let Some(pattern) = &self.pattern {
+                let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+                if !path_matches(pattern, &relative.to_string_lossy()) {
+                    continue;
+                }
+            }
+            let entry = TableObject::new(HashMap::from_iter([
+                (
+                    "path".into(),
+                    Object::new_string(path.to_string_lossy().into_owned()),
+                ),
+                ("is_dir".into(), Object::Bool(is_dir)),
+                ("size".into(), Object::Int(metadata.len as i64)),
+                ("depth".into(), Object::Int(depth as i64)),
+            ]));
+            return Ok(Some(Object::new_table(entry)));
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<Object, RuntimeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
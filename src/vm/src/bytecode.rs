@@ -0,0 +1,1012 @@
+//! Compiled code as bytes, so that a program can be cached and run later without compiling it.
+//!
+//! A file starts with a header
+//!   magic: b"LICO" | format version: u16 | version: u8 length + UTF-8 | features: u32
+//! followed by the strings the code uses, the source it was compiled from, the code itself and a
+//! checksum of all the bytes before it
+//!   strings: u32 count + (u32 length + UTF-8)* | source: u32 length + UTF-8 | code: block |
+//!   checksum: u32
+//! all in little endian. A block, the code of the script or of a function, is its instructions
+//! followed by the source span of each of them, so that runtime errors can be located
+//!   block: u32 count + instruction* | spans: u32 count + (0u8 | 1u8 + u32 start + u32 end)*
+//! where the spans are either as many as the instructions or none. An instruction is a tag byte
+//! followed by its operands, where strings are indices into the string table. Rust functions are
+//! stored by name, as their addresses change from build to build. Bytecode is only guaranteed to
+//! run on the exact version of Lico that compiled it, with every feature whose builtins it may use.
+
+use super::*;
+use std::{collections::HashMap, fmt, rc::Rc};
+
+pub const MAGIC: [u8; 4] = *b"LICO";
+
+/// The version of the file layout, bumped whenever the encoding changes.
+pub const FORMAT_VERSION: u16 = 3;
+
+/// How deeply functions may be nested in loaded code, so that the recursion over them can't
+/// overflow the stack.
+pub const MAX_NESTING: usize = 64;
+
+/// The Cargo features that add builtins, and so change the instruction set.
+pub mod features {
+    pub const RANDOM: u32 = 1 << 0;
+    pub const HASH: u32 = 1 << 1;
+    pub const COMPRESS: u32 = 1 << 2;
+    pub const TERM: u32 = 1 << 3;
+    pub const PLUGIN: u32 = 1 << 4;
+    pub const FFI: u32 = 1 << 5;
+
+    pub(super) const NAMES: [(u32, &str); 6] = [
+        (RANDOM, "random"),
+        (HASH, "hash"),
+        (COMPRESS, "compress"),
+        (TERM, "term"),
+        (PLUGIN, "plugin"),
+        (FFI, "ffi"),
+    ];
+
+    /// The features this build of the VM has.
+    pub const fn enabled() -> u32 {
+        (cfg!(feature = "random") as u32 * RANDOM)
+            | (cfg!(feature = "hash") as u32 * HASH)
+            | (cfg!(feature = "compress") as u32 * COMPRESS)
+            | (cfg!(feature = "term") as u32 * TERM)
+            | (cfg!(feature = "plugin") as u32 * PLUGIN)
+            | (cfg!(feature = "ffi") as u32 * FFI)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub format_version: u16,
+    /// The version of Lico that compiled the bytecode.
+    pub version: String,
+    /// The features the compiler had, as bits of [`features`].
+    pub features: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The file doesn't start with [`MAGIC`].
+    NotBytecode,
+    /// The file ended in the middle of the header or the code.
+    Truncated,
+    UnsupportedFormat {
+        found: u16,
+        expected: u16,
+    },
+    VersionMismatch {
+        found: String,
+        expected: String,
+    },
+    /// The bytecode was compiled with features this build doesn't have.
+    MissingFeatures(Vec<&'static str>),
+    /// The bytes after the header are not valid code.
+    Malformed(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a Lico bytecode file"),
+            LoadError::Truncated => write!(f, "bytecode file is truncated"),
+            LoadError::UnsupportedFormat { found, expected } => write!(
+                f,
+                "unsupported bytecode format {} (this build reads format {})",
+                found, expected
+            ),
+            LoadError::VersionMismatch { found, expected } => write!(
+                f,
+                "bytecode was compiled by Lico {}, but this is Lico {}; recompile the source",
+                found, expected
+            ),
+            LoadError::MissingFeatures(names) => write!(
+                f,
+                "bytecode needs features this build lacks: {}",
+                names.join(", ")
+            ),
+            LoadError::Malformed(reason) => write!(f, "malformed bytecode: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Header {
+    /// The header for bytecode compiled by this build.
+    pub fn current() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: features::enabled(),
+        }
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&self.format_version.to_le_bytes());
+        let version = &self.version.as_bytes()[..self.version.len().min(u8::MAX as usize)];
+        out.push(version.len() as u8);
+        out.extend_from_slice(version);
+        out.extend_from_slice(&self.features.to_le_bytes());
+    }
+
+    /// Reads a header, returning it with the bytes that follow it.
+    pub fn read(bytes: &[u8]) -> Result<(Self, &[u8]), LoadError> {
+        let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+            return Err(LoadError::NotBytecode);
+        };
+        let (format_version, rest) = split_array::<2>(rest)?;
+        let format_version = u16::from_le_bytes(format_version);
+        // Later fields may be laid out differently in other formats.
+        if format_version != FORMAT_VERSION {
+            return Err(LoadError::UnsupportedFormat {
+                found: format_version,
+                expected: FORMAT_VERSION,
+            });
+        }
+        let ([len], rest) = split_array::<1>(rest)?;
+        if rest.len() < len as usize {
+            return Err(LoadError::Truncated);
+        }
+        let (version, rest) = rest.split_at(len as usize);
+        let version = String::from_utf8_lossy(version).into_owned();
+        let (features, rest) = split_array::<4>(rest)?;
+        let header = Self {
+            format_version,
+            version,
+            features: u32::from_le_bytes(features),
+        };
+        Ok((header, rest))
+    }
+
+    /// Checks that bytecode with this header can run on this build.
+    pub fn check(&self) -> Result<(), LoadError> {
+        let current = Self::current();
+        if self.format_version != current.format_version {
+            return Err(LoadError::UnsupportedFormat {
+                found: self.format_version,
+                expected: current.format_version,
+            });
+        }
+        if self.version != current.version {
+            return Err(LoadError::VersionMismatch {
+                found: self.version.clone(),
+                expected: current.version,
+            });
+        }
+        let missing = self.features & !current.features;
+        if missing != 0 {
+            let names = features::NAMES
+                .iter()
+                .filter(|(bit, _)| missing & bit != 0)
+                .map(|(_, name)| *name)
+                .collect();
+            return Err(LoadError::MissingFeatures(names));
+        }
+        Ok(())
+    }
+}
+
+/// The checksum that ends bytecode, of the bytes before it: 32-bit FNV-1a, which tells apart any
+/// two inputs that differ in a single byte.
+pub fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn split_array<const N: usize>(bytes: &[u8]) -> Result<([u8; N], &[u8]), LoadError> {
+    if bytes.len() < N {
+        return Err(LoadError::Truncated);
+    }
+    let (head, rest) = bytes.split_at(N);
+    Ok((head.try_into().unwrap(), rest))
+}
+
+/// The code of a script as bytecode stores it, with the source it was compiled from so that its
+/// runtime errors can be located and shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    pub code: Vec<Code>,
+    /// Where each instruction of `code` came from in `source`, by index, or empty if unknown.
+    pub spans: Vec<Option<TextSpan>>,
+    pub source: String,
+}
+
+impl Program {
+    /// Encodes the program with the header of this build.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code loads a Rust function that isn't a builtin, as bytecode refers to them
+    /// by name.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut body = Writer::default();
+        body.block(&self.code, &self.spans);
+        let mut out = Vec::new();
+        Header::current().write(&mut out);
+        write_u32(&mut out, body.strings.len());
+        for string in &body.strings {
+            write_u32(&mut out, string.len());
+            out.extend_from_slice(string.as_bytes());
+        }
+        write_u32(&mut out, self.source.len());
+        out.extend_from_slice(self.source.as_bytes());
+        out.extend_from_slice(&body.out);
+        let checksum = checksum(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    /// Decodes bytecode made by [`Program::serialize`], after checking that this build can run
+    /// it.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, LoadError> {
+        let (header, rest) = Header::read(bytes)?;
+        header.check()?;
+        let Some((rest, expected)) = rest.split_last_chunk::<4>() else {
+            return Err(LoadError::Truncated);
+        };
+        if checksum(&bytes[..bytes.len() - 4]) != u32::from_le_bytes(*expected) {
+            return Err(malformed("checksum doesn't match, so the file is corrupt"));
+        }
+        let mut reader = Reader {
+            bytes: rest,
+            strings: Vec::new(),
+            objects: HashMap::new(),
+            source: "",
+            depth: 0,
+        };
+        let count = reader.u32()?;
+        for _ in 0..count {
+            let string = reader.utf8("string")?;
+            reader.strings.push(string.into());
+        }
+        reader.source = reader.utf8("source")?;
+        let (code, spans) = reader.block()?;
+        if !reader.bytes.is_empty() {
+            return Err(LoadError::Malformed(
+                "trailing bytes after the code".to_string(),
+            ));
+        }
+        Ok(Self {
+            code,
+            spans,
+            source: reader.source.to_string(),
+        })
+    }
+}
+
+impl Code {
+    /// Encodes `code` with the header of this build, without a source to locate it in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code loads a Rust function that isn't a builtin, as bytecode refers to them
+    /// by name.
+    pub fn serialize(code: &[Code]) -> Vec<u8> {
+        let program = Program {
+            code: code.to_vec(),
+            spans: Vec::new(),
+            source: String::new(),
+        };
+        program.serialize()
+    }
+
+    /// Decodes the code of bytecode made by [`Code::serialize`] or [`Program::serialize`], after
+    /// checking that this build can run it.
+    pub fn deserialize(bytes: &[u8]) -> Result<Vec<Code>, LoadError> {
+        Program::deserialize(bytes).map(|program| program.code)
+    }
+}
+
+type RustFunction = fn(&[Object]) -> Result<Object, RuntimeError>;
+
+macro_rules! rust_functions {
+    ($($(#[$attr:meta])* $path:path => $name:literal,)*) => {
+        /// The name a Rust function the compiler may load is stored with.
+        fn rust_function_name(func: RustFunction) -> Option<&'static str> {
+            $(
+                $(#[$attr])*
+                if std::ptr::fn_addr_eq(func, $path as RustFunction) {
+                    return Some($name);
+                }
+            )*
+            None
+        }
+
+        fn rust_function(name: &str) -> Option<RustFunction> {
+            match name {
+                $($(#[$attr])* $name => Some($path),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+rust_functions! {
+    builtin::clock => "clock",
+    builtin::compose => "compose",
+    builtin::env_load => "env_load",
+    builtin::format => "format",
+    builtin::format_values => "format_values",
+    builtin::fs_glob => "fs_glob",
+    builtin::fs_lines => "fs_lines",
+    builtin::fs_lock => "fs_lock",
+    builtin::fs_open => "fs_open",
+    builtin::fs_walk => "fs_walk",
+    builtin::fs_write_atomic => "fs_write_atomic",
+    builtin::glob_match => "glob_match",
+    builtin::inspect => "inspect",
+    builtin::io_lines => "io_lines",
+    builtin::memoize => "memoize",
+    builtin::monotonic => "monotonic",
+    builtin::partial => "partial",
+    builtin::subprocess_spawn => "subprocess_spawn",
+    builtin::term_color => "term_color",
+    #[cfg(feature = "compress")]
+    builtin::compress => "compress",
+    #[cfg(feature = "compress")]
+    builtin::decompress => "decompress",
+    #[cfg(feature = "ffi")]
+    builtin::ffi_open => "ffi_open",
+    #[cfg(feature = "hash")]
+    builtin::crc32 => "crc32",
+    #[cfg(feature = "hash")]
+    builtin::fnv => "fnv",
+    #[cfg(feature = "hash")]
+    builtin::md5 => "md5",
+    #[cfg(feature = "hash")]
+    builtin::sha1 => "sha1",
+    #[cfg(feature = "hash")]
+    builtin::sha256 => "sha256",
+    #[cfg(feature = "plugin")]
+    plugin::plugin_load => "plugin_load",
+    #[cfg(feature = "random")]
+    builtin::random_id => "random_id",
+    #[cfg(feature = "random")]
+    builtin::uuid_v4 => "uuid_v4",
+}
+
+macro_rules! builtin_instrs {
+    ($($(#[$attr:meta])* $variant:ident => $tag:literal,)*) => {
+        fn builtin_instr_tag(instr: BuiltinInstr) -> u8 {
+            match instr {
+                $($(#[$attr])* BuiltinInstr::$variant => $tag,)*
+            }
+        }
+
+        fn builtin_instr_from_tag(tag: u8) -> Option<BuiltinInstr> {
+            match tag {
+                $($(#[$attr])* $tag => Some(BuiltinInstr::$variant),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+builtin_instrs! {
+    Write => 0,
+    Flush => 1,
+    WriteError => 2,
+    FlushError => 3,
+    ReadLine => 4,
+    ReadAll => 5,
+    Read => 6,
+    IsTerminal => 7,
+    TerminalSize => 8,
+    ClearScreen => 9,
+    MoveCursor => 10,
+    ShowCursor => 11,
+    #[cfg(feature = "term")]
+    ReadKey => 12,
+    ReadFile => 13,
+    WriteFile => 14,
+    ReadBytes => 15,
+    WriteBytes => 16,
+    TypeName => 17,
+    Sleep => 18,
+    Log => 19,
+    Error => 20,
+    Args => 21,
+    Exit => 22,
+    OnInterrupt => 23,
+    Resume => 24,
+    LogEnabled => 25,
+}
+
+// The operators of the register instructions, indexed by their tag.
+const BINARY_OPS: [BinaryOp; 14] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::Mod,
+    BinaryOp::Eq,
+    BinaryOp::NotEq,
+    BinaryOp::Less,
+    BinaryOp::LessEq,
+    BinaryOp::Greater,
+    BinaryOp::GreaterEq,
+    BinaryOp::Concat,
+    BinaryOp::Pow,
+    BinaryOp::FloorDiv,
+];
+
+fn binary_op_tag(op: BinaryOp) -> u8 {
+    BINARY_OPS.iter().position(|x| *x == op).unwrap() as u8
+}
+
+fn write_u32(out: &mut Vec<u8>, value: usize) {
+    let value = u32::try_from(value).expect("Bytecode sizes and indices must fit in u32.");
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[derive(Default)]
+struct Writer {
+    out: Vec<u8>,
+    // Each string is stored once, and referred to by its index.
+    strings: Vec<Rc<str>>,
+    indices: HashMap<Rc<str>, usize>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.out.push(value);
+    }
+
+    fn u32(&mut self, value: usize) {
+        write_u32(&mut self.out, value);
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, string: &str) {
+        let index = match self.indices.get(string) {
+            Some(&index) => index,
+            None => {
+                let string: Rc<str> = string.into();
+                self.strings.push(Rc::clone(&string));
+                self.indices.insert(string, self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+        self.u32(index);
+    }
+
+    fn operand(&mut self, operand: Operand) {
+        match operand {
+            Operand::Stack => self.u8(0),
+            Operand::Local(id) => {
+                self.u8(1);
+                self.u32(id.0);
+            }
+            Operand::Int(x) => {
+                self.u8(2);
+                self.i64(x);
+            }
+        }
+    }
+
+    fn block(&mut self, code: &[Code], spans: &[Option<TextSpan>]) {
+        self.u32(code.len());
+        for code in code {
+            self.code(code);
+        }
+        self.u32(spans.len());
+        for span in spans {
+            match span {
+                Some(span) => {
+                    self.u8(1);
+                    self.u32(span.start() as usize);
+                    self.u32(span.end() as usize);
+                }
+                None => self.u8(0),
+            }
+        }
+    }
+
+    fn code(&mut self, code: &Code) {
+        match code {
+            Code::LoadInt(x) => {
+                self.u8(0);
+                self.i64(*x);
+            }
+            Code::LoadFloat(x) => {
+                self.u8(1);
+                self.i64(x.to_bits() as i64);
+            }
+            Code::LoadBool(x) => {
+                self.u8(2);
+                self.u8(*x as u8);
+            }
+            Code::LoadString(x) => {
+                self.u8(3);
+                self.string(x.as_str());
+            }
+            Code::LoadNil => self.u8(4),
+            Code::LoadLocal(id) => {
+                self.u8(5);
+                self.u32(id.0);
+            }
+            Code::LoadRustFunction(func) => {
+                let Some(name) = rust_function_name(*func) else {
+                    panic!("Only builtin Rust functions can be serialized.");
+                };
+                self.u8(6);
+                self.string(name);
+            }
+            Code::UnloadTop => self.u8(7),
+            Code::SetLocal(id) => {
+                self.u8(8);
+                self.u32(id.0);
+            }
+            Code::MakeLocal => self.u8(9),
+            Code::MakeArray(len) => {
+                self.u8(10);
+                self.u32(*len as usize);
+            }
+            Code::MakeNamed => self.u8(11),
+            Code::MakeTable(len) => {
+                self.u8(12);
+                self.u32(*len as usize);
+            }
+            Code::DropLocal(count) => {
+                self.u8(13);
+                self.u32(*count);
+            }
+            Code::Jump(offset) => {
+                self.u8(14);
+                self.i64(*offset as i64);
+            }
+            Code::JumpIfTrue(offset) => {
+                self.u8(15);
+                self.i64(*offset as i64);
+            }
+            Code::JumpIfFalse(offset) => {
+                self.u8(16);
+                self.i64(*offset as i64);
+            }
+            Code::CallMethod(site, args_len) => {
+                self.u8(17);
+                self.string(&site.name);
+                self.u8(*args_len);
+            }
+            // By name, so that adding a method doesn't change the encoding of the others.
+            Code::CallBuiltinMethod(method, args_len) => {
+                self.u8(18);
+                self.string(method.name());
+                self.u8(*args_len);
+            }
+            Code::Call(args_len) => {
+                self.u8(19);
+                self.u8(*args_len);
+            }
+            Code::TailCall(args_len) => {
+                self.u8(20);
+                self.u8(*args_len);
+            }
+            Code::SetItem => self.u8(21),
+            Code::GetItem => self.u8(22),
+            Code::Add => self.u8(23),
+            Code::Sub => self.u8(24),
+            Code::Mul => self.u8(25),
+            Code::Div => self.u8(26),
+            Code::Mod => self.u8(27),
+            Code::Pow => self.u8(28),
+            Code::Unm => self.u8(29),
+            Code::Eq => self.u8(30),
+            Code::NotEq => self.u8(31),
+            Code::Less => self.u8(32),
+            Code::LessEq => self.u8(33),
+            Code::Greater => self.u8(34),
+            Code::GreaterEq => self.u8(35),
+            Code::Concat => self.u8(36),
+            Code::BitAnd => self.u8(37),
+            Code::BitOr => self.u8(38),
+            Code::BitXor => self.u8(39),
+            Code::BitNot => self.u8(40),
+            Code::ShiftL => self.u8(41),
+            Code::ShiftR => self.u8(42),
+            Code::Builtin(instr, args_len) => {
+                self.u8(43);
+                self.u8(builtin_instr_tag(*instr));
+                self.u8(*args_len);
+            }
+            Code::MakeClosure(proto) => {
+                self.u8(44);
+                match &proto.name {
+                    Some(name) => {
+                        self.u8(1);
+                        self.string(name);
+                    }
+                    None => self.u8(0),
+                }
+                self.u32(proto.captures.len());
+                for capture in &proto.captures {
+                    self.u32(capture.0);
+                }
+                self.u32(proto.args.len());
+                for arg in &proto.args {
+                    self.u8(match arg {
+                        ArgumentKind::Copy => 0,
+                        ArgumentKind::Ref => 1,
+                        ArgumentKind::Auto => 2,
+                        ArgumentKind::Rest => 3,
+                    });
+                }
+                self.block(&proto.code, &proto.spans);
+            }
+            Code::EnterWith => self.u8(45),
+            Code::ExitWith => self.u8(46),
+            Code::Try(offset) => {
+                self.u8(60);
+                self.i64(*offset as i64);
+            }
+            Code::EndTry => self.u8(61),
+            Code::Yield => self.u8(62),
+            Code::Import => self.u8(63),
+            Code::FloorDiv => self.u8(66),
+            Code::JumpIfNil(offset) => {
+                self.u8(67);
+                self.i64(*offset as i64);
+            }
+            Code::JumpIfNotNil(offset) => {
+                self.u8(68);
+                self.i64(*offset as i64);
+            }
+            Code::JumpIfTrueOrPop(offset) => {
+                self.u8(69);
+                self.i64(*offset as i64);
+            }
+            Code::JumpIfFalseOrPop(offset) => {
+                self.u8(70);
+                self.i64(*offset as i64);
+            }
+            Code::Not => self.u8(71),
+            Code::MakeArraySpread(count) => {
+                self.u8(72);
+                self.u32(*count as usize);
+            }
+            Code::CallSpread => self.u8(73),
+            Code::CallMethodSpread(site) => {
+                self.u8(74);
+                self.string(&site.name);
+            }
+            Code::GetSlice => self.u8(75),
+            Code::In => self.u8(76),
+            Code::LoadGlobal(name) => {
+                self.u8(64);
+                self.string(name.as_str());
+            }
+            Code::SetGlobal(name) => {
+                self.u8(65);
+                self.string(name.as_str());
+            }
+            Code::Nop => self.u8(47),
+            Code::Return => self.u8(48),
+            Code::Exit => self.u8(49),
+            Code::Binary(op, lhs, rhs) => {
+                self.u8(50);
+                self.u8(binary_op_tag(*op));
+                self.operand(*lhs);
+                self.operand(*rhs);
+            }
+            Code::BinaryTo(dst, op, lhs, rhs) => {
+                self.u8(51);
+                self.u32(dst.0);
+                self.u8(binary_op_tag(*op));
+                self.operand(*lhs);
+                self.operand(*rhs);
+            }
+            Code::Move(dst, src) => {
+                self.u8(52);
+                self.u32(dst.0);
+                self.operand(*src);
+            }
+            Code::IterStart => self.u8(53),
+            Code::IterNext(id, offset) => {
+                self.u8(54);
+                self.u32(id.0);
+                self.i64(*offset as i64);
+            }
+            Code::IterEnd(offset) => {
+                self.u8(55);
+                self.i64(*offset as i64);
+            }
+            Code::ForPrep(id, offset) => {
+                self.u8(56);
+                self.u32(id.0);
+                self.i64(*offset as i64);
+            }
+            Code::ForLoop(id, offset) => {
+                self.u8(57);
+                self.u32(id.0);
+                self.i64(*offset as i64);
+            }
+            Code::Unpack(count) => {
+                self.u8(58);
+                self.u8(*count);
+            }
+            Code::ReturnMany(count) => {
+                self.u8(59);
+                self.u8(*count);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    strings: Vec<Rc<str>>,
+    // The loaded string constants by index, shared like the compiler shares them.
+    objects: HashMap<usize, StringObject>,
+    // The source the spans point into, which they are checked against.
+    source: &'a str,
+    // How many functions the code being read is nested in.
+    depth: usize,
+}
+
+fn malformed(reason: impl fmt::Display) -> LoadError {
+    LoadError::Malformed(reason.to_string())
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if self.bytes.len() < len {
+            return Err(LoadError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        let (bytes, rest) = split_array::<1>(self.bytes)?;
+        self.bytes = rest;
+        Ok(bytes[0])
+    }
+
+    fn u32(&mut self) -> Result<usize, LoadError> {
+        let (bytes, rest) = split_array::<4>(self.bytes)?;
+        self.bytes = rest;
+        Ok(u32::from_le_bytes(bytes) as usize)
+    }
+
+    fn i64(&mut self) -> Result<i64, LoadError> {
+        let (bytes, rest) = split_array::<8>(self.bytes)?;
+        self.bytes = rest;
+        Ok(i64::from_le_bytes(bytes))
+    }
+
+    fn offset(&mut self) -> Result<isize, LoadError> {
+        let offset = self.i64()?;
+        isize::try_from(offset).map_err(|_| malformed(format!("jump offset {} is too far", offset)))
+    }
+
+    fn utf8(&mut self, what: &str) -> Result<&'a str, LoadError> {
+        let len = self.u32()?;
+        std::str::from_utf8(self.take(len)?)
+            .map_err(|_| malformed(format!("{} is not valid UTF-8", what)))
+    }
+
+    fn string(&mut self) -> Result<Rc<str>, LoadError> {
+        let index = self.u32()?;
+        self.strings
+            .get(index)
+            .cloned()
+            .ok_or_else(|| malformed(format!("string {} is not in the string table", index)))
+    }
+
+    fn string_object(&mut self) -> Result<StringObject, LoadError> {
+        let index = self.u32()?;
+        let Some(string) = self.strings.get(index) else {
+            return Err(malformed(format!(
+                "string {} is not in the string table",
+                index
+            )));
+        };
+        let object = self
+            .objects
+            .entry(index)
+            .or_insert_with(|| StringObject::from(&**string));
+        Ok(object.clone())
+    }
+
+    fn operand(&mut self) -> Result<Operand, LoadError> {
+        match self.u8()? {
+            0 => Ok(Operand::Stack),
+            1 => Ok(Operand::Local(LocalId(self.u32()?))),
+            2 => Ok(Operand::Int(self.i64()?)),
+            tag => Err(malformed(format!("unknown operand {}", tag))),
+        }
+    }
+
+    fn binary_op(&mut self) -> Result<BinaryOp, LoadError> {
+        let tag = self.u8()?;
+        BINARY_OPS
+            .get(tag as usize)
+            .copied()
+            .ok_or_else(|| malformed(format!("unknown operator {}", tag)))
+    }
+
+    fn block(&mut self) -> Result<(Vec<Code>, Vec<Option<TextSpan>>), LoadError> {
+        let len = self.u32()?;
+        // Every instruction takes at least a byte, so a bogus length fails before allocating.
+        if len > self.bytes.len() {
+            return Err(LoadError::Truncated);
+        }
+        let mut code = Vec::with_capacity(len);
+        for _ in 0..len {
+            code.push(self.code()?);
+        }
+        let len = self.u32()?;
+        if len != 0 && len != code.len() {
+            return Err(malformed(format!(
+                "{} spans for {} instructions",
+                len,
+                code.len()
+            )));
+        }
+        let spans = (0..len).map(|_| self.span()).collect::<Result<_, _>>()?;
+        Ok((code, spans))
+    }
+
+    fn span(&mut self) -> Result<Option<TextSpan>, LoadError> {
+        if self.u8()? == 0 {
+            return Ok(None);
+        }
+        let (start, end) = (self.u32()?, self.u32()?);
+        // Spans are sliced out of the source to show errors, so they must fall on characters.
+        if start > end || !self.source.is_char_boundary(start) || !self.source.is_char_boundary(end)
+        {
+            return Err(malformed(format!(
+                "span {}..{} is not in the source",
+                start, end
+            )));
+        }
+        Ok(Some(TextSpan::new(start as u32, end as u32)))
+    }
+
+    fn code(&mut self) -> Result<Code, LoadError> {
+        let code = match self.u8()? {
+            0 => Code::LoadInt(self.i64()?),
+            1 => Code::LoadFloat(f64::from_bits(self.i64()? as u64)),
+            2 => Code::LoadBool(self.u8()? != 0),
+            3 => Code::LoadString(self.string_object()?),
+            4 => Code::LoadNil,
+            5 => Code::LoadLocal(LocalId(self.u32()?)),
+            6 => {
+                let name = self.string()?;
+                let Some(func) = rust_function(&name) else {
+                    return Err(malformed(format!("unknown Rust function `{}`", name)));
+                };
+                Code::LoadRustFunction(func)
+            }
+            7 => Code::UnloadTop,
+            8 => Code::SetLocal(LocalId(self.u32()?)),
+            9 => Code::MakeLocal,
+            10 => Code::MakeArray(self.u32()? as u32),
+            11 => Code::MakeNamed,
+            12 => Code::MakeTable(self.u32()? as u32),
+            13 => Code::DropLocal(self.u32()?),
+            14 => Code::Jump(self.offset()?),
+            15 => Code::JumpIfTrue(self.offset()?),
+            16 => Code::JumpIfFalse(self.offset()?),
+            17 => Code::CallMethod(MethodSite::new(self.string()?), self.u8()?),
+            18 => {
+                let name = self.string()?;
+                let Some(method) = BuiltinMethod::from_name(&name) else {
+                    return Err(malformed(format!("unknown builtin method `{}`", name)));
+                };
+                Code::CallBuiltinMethod(method, self.u8()?)
+            }
+            19 => Code::Call(self.u8()?),
+            20 => Code::TailCall(self.u8()?),
+            21 => Code::SetItem,
+            22 => Code::GetItem,
+            23 => Code::Add,
+            24 => Code::Sub,
+            25 => Code::Mul,
+            26 => Code::Div,
+            27 => Code::Mod,
+            28 => Code::Pow,
+            29 => Code::Unm,
+            30 => Code::Eq,
+            31 => Code::NotEq,
+            32 => Code::Less,
+            33 => Code::LessEq,
+            34 => Code::Greater,
+            35 => Code::GreaterEq,
+            36 => Code::Concat,
+            37 => Code::BitAnd,
+            38 => Code::BitOr,
+            39 => Code::BitXor,
+            40 => Code::BitNot,
+            41 => Code::ShiftL,
+            42 => Code::ShiftR,
+            43 => {
+                let tag = self.u8()?;
+                let Some(instr) = builtin_instr_from_tag(tag) else {
+                    return Err(malformed(format!("unknown builtin instruction {}", tag)));
+                };
+                Code::Builtin(instr, self.u8()?)
+            }
+            44 => {
+                let name = match self.u8()? {
+                    0 => None,
+                    _ => Some(self.string()?.to_string()),
+                };
+                let len = self.u32()?;
+                let captures = (0..len)
+                    .map(|_| self.u32().map(LocalId))
+                    .collect::<Result<_, _>>()?;
+                let len = self.u32()?;
+                let args = (0..len)
+                    .map(|_| match self.u8()? {
+                        0 => Ok(ArgumentKind::Copy),
+                        1 => Ok(ArgumentKind::Ref),
+                        2 => Ok(ArgumentKind::Auto),
+                        3 => Ok(ArgumentKind::Rest),
+                        kind => Err(malformed(format!("unknown argument kind {}", kind))),
+                    })
+                    .collect::<Result<_, _>>()?;
+                if self.depth >= MAX_NESTING {
+                    return Err(malformed(format!(
+                        "functions are nested deeper than {}",
+                        MAX_NESTING
+                    )));
+                }
+                self.depth += 1;
+                let (code, spans) = self.block()?;
+                self.depth -= 1;
+                Code::MakeClosure(Rc::new(FunctionPrototype {
+                    name,
+                    captures,
+                    args,
+                    code: code.into(),
+                    spans: spans.into(),
+                    file: None,
+                }))
+            }
+            45 => Code::EnterWith,
+            46 => Code::ExitWith,
+            47 => Code::Nop,
+            48 => Code::Return,
+            49 => Code::Exit,
+            50 => Code::Binary(self.binary_op()?, self.operand()?, self.operand()?),
+            51 => {
+                let dst = LocalId(self.u32()?);
+                Code::BinaryTo(dst, self.binary_op()?, self.operand()?, self.operand()?)
+            }
+            52 => Code::Move(LocalId(self.u32()?), self.operand()?),
+            53 => Code::IterStart,
+            54 => Code::IterNext(LocalId(self.u32()?), self.offset()?),
+            55 => Code::IterEnd(self.offset()?),
+            56 => Code::ForPrep(LocalId(self.u32()?), self.offset()?),
+            57 => Code::ForLoop(LocalId(self.u32()?), self.offset()?),
+            58 => Code::Unpack(self.u8()?),
+            59 => Code::ReturnMany(self.u8()?),
+            60 => Code::Try(self.offset()?),
+            61 => Code::EndTry,
+            62 => Code::Yield,
+            63 => Code::Import,
+            64 => Code::LoadGlobal(self.string_object()?),
+            65 => Code::SetGlobal(self.string_object()?),
+            66 => Code::FloorDiv,
+            67 => Code::JumpIfNil(self.offset()?),
+            68 => Code::JumpIfNotNil(self.offset()?),
+            69 => Code::JumpIfTrueOrPop(self.offset()?),
+            70 => Code::JumpIfFalseOrPop(self.offset()?),
+            71 => Code::Not,
+            72 => Code::MakeArraySpread(self.u32()? as u32),
+            73 => Code::CallSpread,
+            74 => Code::CallMethodSpread(MethodSite::new(self.string()?)),
+            75 => Code::GetSlice,
+            76 => Code::In,
+            tag => return Err(malformed(format!("unknown instruction {}", tag))),
+        };
+        Ok(code)
+    }
+}
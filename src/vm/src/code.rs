@@ -1,5 +1,6 @@
 use super::*;
-use std::{borrow::Cow, rc::Rc};
+use foundation::TextSpan;
+use std::{cell::RefCell, fmt, rc::Rc};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LocalId(pub usize);
@@ -9,47 +10,113 @@ pub enum ArgumentKind {
     Copy,
     Ref,
     Auto,
+    /// Collects this and all the following arguments into an array. Must be the last one.
+    Rest,
 }
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Code {
     LoadInt(i64),
     LoadFloat(f64),
     LoadBool(bool),
-    LoadString(Rc<String>),
+    LoadString(StringObject),
     LoadNil,
     LoadLocal(LocalId),
-    LoadRustFunction(fn(&[Object]) -> Result<Object, String>),
+    /// Pushes the global of the name, or nil if it was never set.
+    LoadGlobal(StringObject),
+    LoadRustFunction(fn(&[Object]) -> Result<Object, RuntimeError>),
     UnloadTop,
 
     SetLocal(LocalId),
+    /// Pops a value and sets the global of the name to it.
+    SetGlobal(StringObject),
     MakeLocal,
     MakeArray(u32),
+    /// Pops the number of arrays and pushes an array of all their items, e.g. for `[a, ...b]`
+    /// with `[a]` and `b` pushed.
+    MakeArraySpread(u32),
     MakeNamed,
     MakeTable(u32),
     DropLocal(usize),
 
     Jump(isize),
+    /// Pops a value and jumps if it is truthy, that is neither nil nor false.
     JumpIfTrue(isize),
+    /// Pops a value and jumps if it is falsy, that is nil or false.
     JumpIfFalse(isize),
+    /// Jumps if the top value is truthy, keeping it pushed, and pops it otherwise, e.g. to skip
+    /// the right-hand side of `a or b`.
+    JumpIfTrueOrPop(isize),
+    /// Jumps if the top value is falsy, keeping it pushed, and pops it otherwise, e.g. to skip
+    /// the right-hand side of `a and b`.
+    JumpIfFalseOrPop(isize),
+    /// Jumps if the top value is nil, which stays pushed either way, e.g. to skip the rest of
+    /// `a?.b.c` with nil as its result.
+    JumpIfNil(isize),
+    /// Jumps if the top value is not nil, keeping it pushed, and pops it otherwise, e.g. to skip
+    /// the right-hand side of `a ?? b`.
+    JumpIfNotNil(isize),
 
-    CallMethod(Cow<'static, str>, u8),
+    // A `for-in` loop keeps its iterator in a local, and runs
+    //   IterStart, MakeLocal, ..., IterNext(local, to end), IterEnd(to end), [body], Jump(back)
+    /// Pops the iterable and pushes its iterator, which is the result of its `__iter` method if
+    /// it is a table with one, or the iterable itself otherwise.
+    IterStart,
+    /// Pushes the next item of the iterator in the local and skips the `IterEnd` after it, or
+    /// jumps by the offset if there are no more items. An iterator that is a table with a
+    /// `__next` method is called instead, and the `IterEnd` checks the result.
+    IterNext(LocalId, isize),
+    /// Pops the result of a `__next` method and jumps by the offset if it is nil, which ends the
+    /// iteration, or leaves it for the loop otherwise.
+    IterEnd(isize),
+
+    // A numeric `for` keeps its counter, its end and its step in three locals in a row, which
+    // the variable of the loop follows.
+    /// Checks that the counter, the end and the step in the locals from the id are ints and the
+    /// step isn't 0, and jumps by the offset if the loop doesn't run at all. Otherwise sets the
+    /// variable to the counter.
+    ForPrep(LocalId, isize),
+    /// Adds the step to the counter in the locals from the id, and jumps by the offset (back to
+    /// the body) with the variable set to it unless it is past the end.
+    ForLoop(LocalId, isize),
+
+    CallMethod(MethodSite, u8),
+    CallBuiltinMethod(BuiltinMethod, u8),
     Call(u8),
+    /// Pops an array of arguments and then the callee, and calls it with them, e.g. for
+    /// `f(...args)`.
+    CallSpread,
+    /// Pops an array of arguments and then the receiver, and calls the method with them.
+    CallMethodSpread(MethodSite),
+    /// A `Call` whose result is returned right away, which runs the callee in place of the
+    /// current function when nothing is left to do there. Always followed by `Return`.
+    TailCall(u8),
+    /// Follows a call whose result is spread over this many values. The callee's first value is
+    /// already pushed, and this pushes nil for the rest. A callee returning several values with
+    /// `ReturnMany` pushes them all in place of this instead.
+    Unpack(u8),
     SetItem,
     GetItem,
+    /// Pops the end and the start, each an Int or nil for none, and then the array or string, and
+    /// pushes the part of it between them. Negative bounds count from the end.
+    GetSlice,
     Add,       // +
     Sub,       // -
     Mul,       // *
     Div,       // /
     Mod,       // %
-    Pow,       // *
+    Pow,       // **
+    FloorDiv,  // //
     Unm,       // - (unary)
+    Not,       // not
     Eq,        // ==
     NotEq,     // !=
     Less,      // <
     LessEq,    // <=
     Greater,   // >
     GreaterEq, // >=
+    In,        // in
     Concat,    // ..
     BitAnd,    // &
     BitOr,     // |
@@ -60,15 +127,163 @@ pub enum Code {
 
     Builtin(BuiltinInstr, u8),
 
-    BeginFuncCreation,
-    AddCapture(LocalId),
-    AddArgument(ArgumentKind),
-    EndFuncCreation,
+    MakeClosure(Rc<FunctionPrototype>),
+
+    EnterWith,
+    ExitWith,
+
+    /// Starts a `try` block, whose `catch` block is at the offset. An error raised until the
+    /// matching `EndTry`, also in the functions called there, jumps to it with the error pushed,
+    /// after dropping what the block pushed and the locals it defined, and closing its `with`s.
+    Try(isize),
+    /// Ends the innermost `try` block, which catches no more errors.
+    EndTry,
+
+    /// Pops a value and suspends the coroutine running the code, which `resume` returns the
+    /// value from. Resuming it pushes the value passed to `resume` and goes on after this.
+    Yield,
+
+    /// Pops the name of a module and pushes its exports, running its file the first time.
+    Import,
 
     Nop,
     Return,
+    /// Pops this many values and returns them all, to an `Unpack` after the call or on through a
+    /// `TailCall` of it. Otherwise, and when a memoized function caches them, only the first one
+    /// is the result of the call.
+    ReturnMany(u8),
 
     Exit,
+
+    // Register instructions, which read their operands from locals instead of the stack. Only
+    // code compiled with `compiler::Options::registers` has them.
+    /// Applies the operator and pushes the result. Operands on the stack are popped, the right
+    /// one first.
+    Binary(BinaryOp, Operand, Operand),
+    /// A `Binary` whose result is stored in the local instead of being pushed.
+    BinaryTo(LocalId, BinaryOp, Operand, Operand),
+    /// Stores the operand in the local.
+    Move(LocalId, Operand),
+}
+
+/// The operators of the register instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    FloorDiv,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Concat,
+}
+
+/// Where a register instruction takes a value from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operand {
+    /// The value on top of the stack, which is popped.
+    Stack,
+    Local(LocalId),
+    Int(i64),
+}
+
+/// The method name of a `CallMethod`, with an inline cache of the method it found on the last
+/// table it was called on. A call site usually sees tables of the same shape over and over (e.g.
+/// in a loop), which then skip the lookup by name.
+pub struct MethodSite {
+    pub name: Rc<str>,
+    cache: RefCell<Option<(u64, TableMethod)>>,
+}
+
+impl MethodSite {
+    pub fn new(name: Rc<str>) -> Self {
+        Self {
+            name,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// Finds the method of `table`, through the cache if the table has the shape it was filled
+    /// with. Builtin methods of tables (e.g. `len`) are not found here.
+    pub fn lookup(&self, table: &TableObject, stats: &mut Stats) -> Option<TableMethod> {
+        let shape = table.shape();
+        if shape == 0 {
+            return None;
+        }
+        if let Some((cached, method)) = &*self.cache.borrow() {
+            if *cached == shape {
+                stats.method_cache_hits += 1;
+                return Some(method.clone());
+            }
+        }
+        stats.method_cache_misses += 1;
+        let method = table.get_method(&self.name)?;
+        *self.cache.borrow_mut() = Some((shape, method.clone()));
+        Some(method)
+    }
+}
+
+impl From<&str> for MethodSite {
+    fn from(name: &str) -> Self {
+        Self::new(name.into())
+    }
+}
+
+// A copy starts with an empty cache, and the cache is left out of comparisons and debug output, as
+// it doesn't change what the code does.
+impl Clone for MethodSite {
+    fn clone(&self) -> Self {
+        Self::new(Rc::clone(&self.name))
+    }
+}
+
+impl PartialEq for MethodSite {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for MethodSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.name)
+    }
+}
+
+/// A compiled function body, shared by every closure created from the same definition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionPrototype {
+    /// The name the function was defined with, e.g. by `func name()`, shown in tracebacks.
+    pub name: Option<String>,
+    pub captures: Vec<LocalId>,
+    pub args: Vec<ArgumentKind>,
+    pub code: Rc<[Code]>,
+    /// Where each instruction of `code` came from in the source, by index, which locates runtime
+    /// errors. Empty if unknown, e.g. for code built by hand.
+    pub spans: Rc<[Option<TextSpan>]>,
+    /// The file `spans` point into, if it isn't the script being run, e.g. a module loaded by
+    /// `import`.
+    pub file: Option<Rc<SourceFile>>,
+}
+
+/// A file that code was compiled from, to locate the spans of that code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: String,
+    pub source: String,
+}
+
+impl SourceFile {
+    /// The line and column of the byte `offset` in the file, counting from 1.
+    pub fn line_column(&self, offset: u32) -> (usize, usize) {
+        crate::error::line_column(&self.source, offset)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -97,15 +312,65 @@ pub enum BuiltinInstr {
     /// return: none
     FlushError,
 
-    /// Read a line from stdin.
+    /// Read a line from stdin without its line ending, after writing `prompt` to stdout if given.
+    ///
+    /// args: 1 (prompt: String | Nil)
+    /// return: 1 (String, or Nil at EOF)
+    ReadLine,
+
+    /// Read everything remaining in stdin.
     ///
     /// args: 0
     /// return: 1 (String)
-    ReadLine,
+    ReadAll,
+
+    /// Read up to `count` characters from stdin.
+    ///
+    /// args: 1 (count: Int)
+    /// return: 1 (String, or Nil at EOF)
+    Read,
 
-    /// Read the entire contents of a file.
+    /// Check whether stdout is a terminal.
     ///
-    /// args: 1
+    /// args: 0
+    /// return: 1 (Bool)
+    IsTerminal,
+
+    /// Get the size of the terminal as `{ cols, rows }`.
+    ///
+    /// args: 0
+    /// return: 1 (Table, or Nil if stdout is not a terminal)
+    TerminalSize,
+
+    /// Clear the terminal and move the cursor to the top left.
+    ///
+    /// args: 0
+    /// return: none
+    ClearScreen,
+
+    /// Move the cursor to the given position, starting at 1.
+    ///
+    /// args: 2 (column: Int, row: Int)
+    /// return: none
+    MoveCursor,
+
+    /// Show or hide the cursor.
+    ///
+    /// args: 1 (visible: Bool)
+    /// return: none
+    ShowCursor,
+
+    /// Read a single key press from stdin in raw mode, e.g. "a", "enter", "up", "ctrl-c".
+    ///
+    /// args: 0
+    /// return: 1 (String, or Nil at EOF)
+    #[cfg(feature = "term")]
+    ReadKey,
+
+    /// Read the entire contents of a file as a string.
+    /// Invalid UTF-8 is an error, or replaced with U+FFFD if `lossy` is true.
+    ///
+    /// args: 2 (filename: String, lossy: Bool | Nil)
     /// return: 1 (String)
     ReadFile,
 
@@ -115,4 +380,200 @@ pub enum BuiltinInstr {
     /// args: 2 (filename: String, contents: String)
     /// return: none
     WriteFile,
+
+    /// Read the entire contents of a file as bytes.
+    ///
+    /// args: 1 (filename: String)
+    /// return: 1 (Bytes)
+    ReadBytes,
+
+    /// Write bytes to a file.
+    /// If the file does not exist, it will be created.
+    ///
+    /// args: 2 (filename: String, contents: Bytes)
+    /// return: none
+    WriteBytes,
+
+    /// Get the type name of an object, e.g. "int", "table".
+    ///
+    /// args: 1 (object: Any)
+    /// return: 1 (String)
+    TypeName,
+
+    /// Sleep for the given seconds. Stops early if the runtime is interrupted.
+    ///
+    /// args: 1 (seconds: Int | Float)
+    /// return: none
+    Sleep,
+
+    /// Log a message through the runtime logger if `level` passes its filter.
+    /// level: 0 (debug), 1 (info), 2 (warn), 3 (error)
+    ///
+    /// args: 3 (level: Int, message: Any, fields: Table | Nil)
+    /// return: none
+    Log,
+
+    /// Raise a runtime error with the given message, or raise a caught error again.
+    ///
+    /// args: 1 (message: String | Error)
+    /// return: none
+    Error,
+
+    /// Get the arguments passed to the script, e.g. by `lico run script.lico arg1 arg2`.
+    ///
+    /// args: 0
+    /// return: 1 (Array of String)
+    Args,
+
+    /// Stop the whole execution with the given exit code, recording it in `Runtime::exit_code`.
+    ///
+    /// args: 1 (code: Int | Nil), where Nil means 0
+    /// return: none
+    Exit,
+
+    /// Register a function to be called when the runtime is interrupted, instead of raising an
+    /// error. Nil removes the handler.
+    ///
+    /// args: 1 (handler: Function | Nil)
+    /// return: none
+    OnInterrupt,
+
+    /// Run a coroutine until it yields or returns, passing `value` as the result of the `yield`
+    /// it is suspended at, or as the argument of its function when it starts.
+    ///
+    /// args: 2 (coroutine: Coroutine, value: Any)
+    /// return: 1 (Any, the yielded or returned value)
+    Resume,
+
+    /// Whether a message at `level` passes the filter of the runtime logger.
+    ///
+    /// args: 1 (level: String, one of "debug", "info", "warn" and "error")
+    /// return: 1 (Bool)
+    LogEnabled,
+}
+
+impl BuiltinInstr {
+    /// The number of arguments the instruction takes, or `None` if it takes any number.
+    pub fn args_len(self) -> Option<u8> {
+        use BuiltinInstr::*;
+        match self {
+            Write | WriteError => None,
+            Flush | FlushError | ReadAll | IsTerminal | TerminalSize | ClearScreen | Args => {
+                Some(0)
+            }
+            #[cfg(feature = "term")]
+            ReadKey => Some(0),
+            ReadLine | Read | ShowCursor | ReadBytes | TypeName | Sleep | Error | Exit
+            | OnInterrupt | LogEnabled => Some(1),
+            MoveCursor | ReadFile | WriteFile | WriteBytes | Resume => Some(2),
+            Log => Some(3),
+        }
+    }
+
+    /// Whether the instruction pushes a value to the stack.
+    pub fn returns_value(self) -> bool {
+        use BuiltinInstr::*;
+        match self {
+            ReadLine | ReadAll | Read | IsTerminal | TerminalSize | ReadFile | ReadBytes
+            | TypeName | Args | Resume | LogEnabled => true,
+            #[cfg(feature = "term")]
+            ReadKey => true,
+            Write | Flush | WriteError | FlushError | ClearScreen | MoveCursor | ShowCursor
+            | WriteFile | WriteBytes | Sleep | Log | Error | Exit | OnInterrupt => false,
+        }
+    }
+}
+
+macro_rules! builtin_methods {
+    ($($variant:ident => $name:literal,)*) => {
+        /// A method name known to the VM, resolved at compile time so that `CallBuiltinMethod`
+        /// dispatches on an integer instead of comparing strings.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        pub enum BuiltinMethod {
+            $($variant,)*
+        }
+
+        impl BuiltinMethod {
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+builtin_methods! {
+    Abs => "abs",
+    Acos => "acos",
+    Acosh => "acosh",
+    Asin => "asin",
+    Asinh => "asinh",
+    Atan => "atan",
+    Atan2 => "atan2",
+    Atanh => "atanh",
+    Bytes => "bytes",
+    Cbar => "cbar",
+    Ceil => "ceil",
+    Chars => "chars",
+    Clamp => "clamp",
+    Contains => "contains",
+    Cos => "cos",
+    Cosh => "cosh",
+    Downto => "downto",
+    EndsWith => "ends_with",
+    Exp => "exp",
+    Exp2 => "exp2",
+    Find => "find",
+    Floor => "floor",
+    Fract => "fract",
+    Keys => "keys",
+    Len => "len",
+    Ln => "ln",
+    Log => "log",
+    Log10 => "log10",
+    Log2 => "log2",
+    Lower => "lower",
+    Lshift => "lshift",
+    PadEnd => "pad_end",
+    PadStart => "pad_start",
+    Pop => "pop",
+    Pow => "pow",
+    Push => "push",
+    Recip => "recip",
+    Remove => "remove",
+    Repeat => "repeat",
+    Replace => "replace",
+    Round => "round",
+    Rshift => "rshift",
+    Sin => "sin",
+    Sinh => "sinh",
+    Slice => "slice",
+    Split => "split",
+    Sqrt => "sqrt",
+    StartsWith => "starts_with",
+    Tan => "tan",
+    Tanh => "tanh",
+    ToArray => "to_array",
+    ToBytes => "to_bytes",
+    ToDegrees => "to_degrees",
+    ToHex => "to_hex",
+    ToRadians => "to_radians",
+    ToString => "to_string",
+    Trim => "trim",
+    TrimEnd => "trim_end",
+    TrimStart => "trim_start",
+    Trunc => "trunc",
+    Upper => "upper",
+    Upto => "upto",
+    Values => "values",
+    Xor => "xor",
 }
@@ -0,0 +1,253 @@
+use crate::code::SourceFile;
+use foundation::TextSpan;
+use std::{fmt, rc::Rc};
+
+/// An error raised while running a script.
+///
+/// `Display` gives only the message, as the VM reported errors before they had a kind. The
+/// alternate form (`{:#}`) adds the traceback.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// The index of the failing instruction in the code of the innermost function, when the error
+    /// was raised by an instruction.
+    pub pc: Option<usize>,
+    /// The frames the error unwound through, innermost first. A frame that follows itself, as in
+    /// recursion, is kept once, and at most [`RuntimeError::MAX_TRACEBACK_LEN`] frames are kept.
+    pub traceback: Vec<TraceFrame>,
+    /// The number of outer frames left out of `traceback` because it was full.
+    pub omitted_frames: usize,
+}
+
+/// A function the error unwound through, and where it was: the failing instruction in the
+/// innermost frame, the call in the others.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceFrame {
+    /// The name of the function, `<main>` for the top level or `<anonymous>`.
+    pub function: String,
+    pub pc: usize,
+    /// Where the instruction at `pc` came from in the source, if known.
+    pub span: Option<TextSpan>,
+    /// The file `span` points into, if it isn't the script being run.
+    pub file: Option<Rc<SourceFile>>,
+    /// How many more times the frame came right after itself, e.g. in a recursive function.
+    pub repeated: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A value has the wrong type for an operation or an argument, e.g. `1 + "a"`.
+    Type,
+    /// A function or method got the wrong number of arguments.
+    Arity,
+    /// An integer was divided by zero.
+    DivisionByZero,
+    /// An item was stored at an index outside of an array, e.g. `[1, 2][5] = 3`.
+    IndexOutOfRange,
+    /// The result of integer arithmetic doesn't fit in an Int, e.g. `9223372036854775807 + 1`.
+    Overflow,
+    /// Reading or writing a file, a stream or a process failed.
+    Io,
+    /// The script touched a file, or did something else, that `Runtime::set_permissions` doesn't
+    /// allow.
+    PermissionDenied,
+    /// The script raised the error itself, with `error` or a failed `assert`.
+    User,
+    /// Calls nested deeper than `Runtime::max_call_depth`, e.g. by endless recursion.
+    StackOverflow,
+    /// The script ran out of the fuel given by `Runtime::with_fuel`.
+    FuelExhausted,
+    /// The script was interrupted, e.g. by Ctrl-C, and had no handler.
+    Interrupted,
+    /// The script called `exit`, which unwinds like an error.
+    Exit,
+    /// The code given to `execute` failed verification, e.g. corrupted bytecode.
+    InvalidCode,
+    /// Anything else, e.g. an invalid argument value.
+    Other,
+}
+
+impl ErrorKind {
+    /// The name of the kind, as scripts see it in a caught error, e.g. "division_by_zero".
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Type => "type",
+            ErrorKind::Arity => "arity",
+            ErrorKind::DivisionByZero => "division_by_zero",
+            ErrorKind::IndexOutOfRange => "index_out_of_range",
+            ErrorKind::Overflow => "overflow",
+            ErrorKind::Io => "io",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::User => "user",
+            ErrorKind::StackOverflow => "stack_overflow",
+            ErrorKind::FuelExhausted => "fuel_exhausted",
+            ErrorKind::Interrupted => "interrupted",
+            ErrorKind::Exit => "exit",
+            ErrorKind::InvalidCode => "invalid_code",
+            ErrorKind::Other => "other",
+        }
+    }
+
+    /// Whether `try` may catch the error. Those that stop the script whatever it does, such as
+    /// `exit` or running out of fuel, are not.
+    pub fn is_catchable(self) -> bool {
+        !matches!(
+            self,
+            ErrorKind::FuelExhausted
+                | ErrorKind::Interrupted
+                | ErrorKind::Exit
+                | ErrorKind::InvalidCode
+        )
+    }
+}
+
+impl RuntimeError {
+    pub const MAX_TRACEBACK_LEN: usize = 100;
+
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            pc: None,
+            traceback: Vec::new(),
+            omitted_frames: 0,
+        }
+    }
+
+    pub fn type_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Type, message)
+    }
+
+    pub fn arity(expected: impl fmt::Display, got: usize) -> Self {
+        Self::new(
+            ErrorKind::Arity,
+            format!(
+                "Wrong number of arguments: expected {}, got {}",
+                expected, got
+            ),
+        )
+    }
+
+    /// Records that the error unwound through `frame`, outside of the ones already in the
+    /// traceback.
+    pub fn push_frame(&mut self, frame: TraceFrame) {
+        if let Some(last) = self.traceback.last_mut() {
+            if self.omitted_frames == 0
+                && (&last.function, last.pc, last.span, &last.file)
+                    == (&frame.function, frame.pc, frame.span, &frame.file)
+            {
+                last.repeated += 1 + frame.repeated;
+                return;
+            }
+        }
+        if self.traceback.len() < Self::MAX_TRACEBACK_LEN {
+            self.traceback.push(frame);
+        } else {
+            self.omitted_frames += 1 + frame.repeated;
+        }
+    }
+
+    /// An I/O error about `subject`, usually a path.
+    pub fn io(subject: impl fmt::Display, err: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Io, format!("{}: {}", subject, err))
+    }
+
+    /// Like the alternate form of `Display`, with the frames located as `file:line:column` in
+    /// `source`, which `file` holds. Lines and columns count from 1.
+    pub fn display_in<'a>(&'a self, file: &'a str, source: &'a str) -> impl fmt::Display + 'a {
+        DisplayIn {
+            error: self,
+            file,
+            source,
+        }
+    }
+
+    fn write_traceback(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        location: impl Fn(&TraceFrame, &mut fmt::Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if self.traceback.is_empty() {
+            return Ok(());
+        }
+        write!(f, "\nTraceback (most recent call last):")?;
+        if self.omitted_frames > 0 {
+            write!(f, "\n  [{} more frames]", self.omitted_frames)?;
+        }
+        for frame in self.traceback.iter().rev() {
+            write!(f, "\n  {} at ", frame.function)?;
+            location(frame, f)?;
+            if frame.repeated > 0 {
+                write!(
+                    f,
+                    "\n  [previous frame repeated {} more times]",
+                    frame.repeated
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return write!(f, "{}", self.message);
+        }
+        self.write_traceback(f, |frame, f| write!(f, "pc {}", frame.pc))
+    }
+}
+
+struct DisplayIn<'a> {
+    error: &'a RuntimeError,
+    file: &'a str,
+    source: &'a str,
+}
+
+impl fmt::Display for DisplayIn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error
+            .write_traceback(f, |frame, f| match (frame.span, &frame.file) {
+                (Some(span), Some(file)) => {
+                    let (line, column) = file.line_column(span.start());
+                    write!(f, "{}:{}:{}", file.path, line, column)
+                }
+                (Some(span), None) => {
+                    let (line, column) = line_column(self.source, span.start());
+                    write!(f, "{}:{}:{}", self.file, line, column)
+                }
+                (None, _) => write!(f, "pc {}", frame.pc),
+            })
+    }
+}
+
+/// The line and column of the byte `offset` in `source`, counting from 1.
+pub(crate) fn line_column(source: &str, offset: u32) -> (usize, usize) {
+    let before = source.get(..offset as usize).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+impl std::error::Error for RuntimeError {}
+
+// Errors built from a bare message, e.g. `Err(format!(..))?`, have no particular kind.
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+impl From<std::io::Error> for RuntimeError {
+    fn from(err: std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, err.to_string())
+    }
+}
+
+impl From<&str> for RuntimeError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
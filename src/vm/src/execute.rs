@@ -1,791 +1,2662 @@
 use super::*;
+use foundation::TextSpan;
 use smallvec::SmallVec;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-pub fn execute(code: &[Code], runtime: &mut Runtime) -> Result<Object, String> {
+/// Runs `code`, whose instructions came from `spans` in the source (see
+/// [`FunctionPrototype::spans`]).
+pub fn execute(
+    code: &[Code],
+    spans: &[Option<TextSpan>],
+    runtime: &mut Runtime,
+) -> Result<Object, RuntimeError> {
+    if runtime.native_depth >= runtime.max_native_depth {
+        return Err(RuntimeError::new(
+            ErrorKind::StackOverflow,
+            "Stack overflow: too many nested native calls",
+        ));
+    }
+    runtime.native_depth += 1;
+    let _permissions = runtime.enter_permissions();
+    let _filesystem = runtime.enter_filesystem();
+    let resources_base = runtime.resources.len();
+    let handlers_base = runtime.handlers.len();
+    let result = run_frames(
+        code,
+        spans,
+        runtime,
+        Thread::default(),
+        handlers_base,
+        false,
+    );
+    runtime.native_depth -= 1;
+    let mut result = result.map(|stop| match stop {
+        Stop::Return(value) => value,
+        Stop::Yield(..) => unreachable!("only the code of a coroutine stops at a `yield`"),
+    });
+
+    // Resources entered by `with` in this frame are closed even if an error propagates.
+    close_resources(runtime, resources_base, &mut result);
+
+    // The top level has finished, so keep only the memory the run actually needed.
+    if runtime.variable_table.depth() == 1 {
+        runtime.shrink();
+    }
+    result
+}
+
+/// A script function called from the code run by [`execute`]. Frames are kept on the heap
+/// instead of the native stack, so that the depth of recursion is bounded only by
+/// `Runtime::max_call_depth`.
+struct Frame {
+    proto: Rc<FunctionPrototype>,
+    pc: usize,
+    /// The number of resources entered by `with` before the call.
+    resources_base: usize,
+    /// Where to cache the result, for a call through a memoized function.
+    memoize: Option<Memoize>,
+}
+
+type Memoize = (Rc<MemoizedFunctionObject>, Vec<Option<TableKey>>);
+
+/// Where the code run by [`run_frames`] is, which a coroutine keeps while it is suspended.
+#[derive(Default)]
+pub(crate) struct Thread {
+    root_pc: usize,
+    frames: Vec<Frame>,
+}
+
+impl Thread {
+    /// Goes on after the `yield` the thread stopped at, with `value` as its result.
+    fn resume_with(&mut self, value: Object, runtime: &mut Runtime) {
+        runtime.stack.push(value.into());
+        match self.frames.last_mut() {
+            Some(frame) => frame.pc += 1,
+            None => self.root_pc += 1,
+        }
+    }
+}
+
+/// How [`run_frames`] stopped, unless by an error.
+enum Stop {
+    /// The root code returned.
+    Return(Object),
+    /// A coroutine yielded the value, and may go on from the thread.
+    Yield(Object, Thread),
+}
+
+/// A coroutine suspended at a `yield`: where it is, and what it had on the runtime above its
+/// caller, taken out until it is resumed.
+pub(crate) struct Suspension {
+    func: Rc<FunctionObject>,
+    thread: Thread,
+    stack: Vec<StackValue>,
+    scopes: Scopes,
+    call_stack: Vec<Rc<FunctionPrototype>>,
+    resources: Vec<Object>,
+    /// The `try` blocks being run, whose depths and lengths are relative to the caller.
+    handlers: Vec<Handler>,
+}
+
+/// How much of the runtime the caller of `resume` has, below what the coroutine has.
+struct Bases {
+    stack: usize,
+    scopes: usize,
+    calls: usize,
+    resources: usize,
+    handlers: usize,
+}
+
+impl Bases {
+    fn of(runtime: &Runtime) -> Self {
+        Self {
+            stack: runtime.stack.len(),
+            scopes: runtime.variable_table.depth(),
+            calls: runtime.call_stack.len(),
+            resources: runtime.resources.len(),
+            handlers: runtime.handlers.len(),
+        }
+    }
+}
+
+impl Suspension {
+    fn take(
+        func: Rc<FunctionObject>,
+        thread: Thread,
+        bases: &Bases,
+        runtime: &mut Runtime,
+    ) -> Self {
+        let handlers = runtime.handlers.split_off(bases.handlers);
+        Self {
+            func,
+            thread,
+            stack: runtime.stack.split_off(bases.stack),
+            scopes: runtime.variable_table.take_scopes(bases.scopes),
+            call_stack: runtime.call_stack.split_off(bases.calls),
+            resources: runtime.resources.split_off(bases.resources),
+            handlers: (handlers.into_iter())
+                .map(|handler| Handler {
+                    depth: handler.depth - bases.calls,
+                    stack_len: handler.stack_len - bases.stack,
+                    resources: handler.resources - bases.resources,
+                    ..handler
+                })
+                .collect(),
+        }
+    }
+
+    fn restore(self, bases: &Bases, runtime: &mut Runtime) -> (Rc<FunctionObject>, Thread) {
+        runtime.stack.extend(self.stack);
+        runtime.variable_table.restore_scopes(self.scopes);
+        runtime.call_stack.extend(self.call_stack);
+        runtime.resources.extend(self.resources);
+        runtime
+            .handlers
+            .extend(self.handlers.into_iter().map(|handler| Handler {
+                depth: handler.depth + bases.calls,
+                stack_len: handler.stack_len + bases.stack,
+                resources: handler.resources + bases.resources,
+                ..handler
+            }));
+        (self.func, self.thread)
+    }
+}
+
+/// Runs `coroutine` until it yields or returns, with `value` as the result of the `yield` it is
+/// suspended at, or as the argument of its function if it hasn't started yet.
+pub(crate) fn resume(
+    coroutine: &UserdataObject,
+    value: Object,
+    runtime: &mut Runtime,
+) -> Result<Object, RuntimeError> {
+    if runtime.native_depth >= runtime.max_native_depth {
+        return Err(RuntimeError::new(
+            ErrorKind::StackOverflow,
+            "Stack overflow: too many nested native calls",
+        ));
+    }
+    let state = std::mem::replace(
+        &mut *coroutine.borrow_mut::<builtin::Coroutine>()?,
+        builtin::Coroutine::Running,
+    );
+    let set_state = |state| -> Result<(), RuntimeError> {
+        *coroutine.borrow_mut::<builtin::Coroutine>()? = state;
+        Ok(())
+    };
+    let bases = Bases::of(runtime);
+    let (func, thread) = match state {
+        builtin::Coroutine::Created(func) => {
+            if let Err(err) = shared_proc::enter_func(&func, &[value], runtime) {
+                set_state(builtin::Coroutine::Dead)?;
+                return Err(err);
+            }
+            (func, Thread::default())
+        }
+        builtin::Coroutine::Suspended(suspension) => {
+            let (func, mut thread) = suspension.restore(&bases, runtime);
+            thread.resume_with(value, runtime);
+            (func, thread)
+        }
+        state => {
+            let status = state.status();
+            set_state(state)?;
+            return Err(RuntimeError::new(
+                ErrorKind::Other,
+                format!("Cannot resume a {} coroutine", status),
+            ));
+        }
+    };
+
+    runtime.native_depth += 1;
+    let _permissions = runtime.enter_permissions();
+    let _filesystem = runtime.enter_filesystem();
+    let result = run_frames(
+        &func.proto.code,
+        &func.proto.spans,
+        runtime,
+        thread,
+        bases.handlers,
+        true,
+    );
+    runtime.native_depth -= 1;
+    let mut result = match result {
+        Ok(Stop::Yield(value, thread)) => {
+            let suspension = Suspension::take(func, thread, &bases, runtime);
+            set_state(builtin::Coroutine::Suspended(Box::new(suspension)))?;
+            return Ok(value);
+        }
+        Ok(Stop::Return(value)) => Ok(value),
+        Err(err) => Err(err),
+    };
+    close_resources(runtime, bases.resources, &mut result);
+    shared_proc::leave_func(runtime);
+    set_state(builtin::Coroutine::Dead)?;
+    result
+}
+
+/// Why [`execute_code`] stopped running the code of a frame.
+enum Control {
+    /// The frame returned.
+    Return(Object),
+    /// The frame returned several values, the first of which is the result of the call unless an
+    /// `Unpack` after it takes them all.
+    ReturnMany(Vec<Object>),
+    /// The frame called a script function, which is to be run in a new frame. The program counter
+    /// is left on the call until the callee returns.
+    Call(code_impl::Call),
+    /// Like `Call`, but the callee's result is returned right away, so the callee may take the
+    /// place of the frame.
+    TailCall(code_impl::Call),
+    /// The frame yielded the value, suspending the coroutine it runs in. The program counter is
+    /// left on the `yield` until the coroutine is resumed.
+    Yield(Object),
+}
+
+/// Runs `code` from where `thread` is. The `try` blocks entered before `handlers_base` are left
+/// to the caller. Only the code of a coroutine may yield, which stops it with where it was.
+fn run_frames(
+    code: &[Code],
+    spans: &[Option<TextSpan>],
+    runtime: &mut Runtime,
+    thread: Thread,
+    handlers_base: usize,
+    coroutine: bool,
+) -> Result<Stop, RuntimeError> {
+    let Thread {
+        mut root_pc,
+        mut frames,
+    } = thread;
+    // The values of a frame returning right after its callee, which are returned in turn.
+    let mut returning = None;
+
+    // Goes on in the `catch` block of the innermost `try` block run here, or stops with the error.
+    macro_rules! raise {
+        ($err:expr) => {
+            match catch(
+                $err,
+                &mut frames,
+                &mut root_pc,
+                spans,
+                handlers_base,
+                runtime,
+            ) {
+                Ok(()) => continue,
+                Err(err) => break Err(err),
+            }
+        };
+    }
+
+    let result = loop {
+        let (frame_code, pc) = match frames.last_mut() {
+            Some(frame) => (&frame.proto.code[..], &mut frame.pc),
+            None => (code, &mut root_pc),
+        };
+        let control = match returning.take() {
+            Some(values) => Ok(Control::ReturnMany(values)),
+            None => execute_code(frame_code, runtime, pc),
+        };
+        let (value, rest) = match control {
+            Ok(Control::TailCall(call)) => {
+                // The frame can go only if nothing is left to do after the callee returns.
+                let replaceable = frames.last().is_some_and(|frame| {
+                    frame.memoize.is_none()
+                        && runtime.resources.len() == frame.resources_base
+                        && !in_try_block(runtime)
+                });
+                if replaceable {
+                    frames.pop();
+                    shared_proc::leave_func(runtime);
+                }
+                if let Err(err) = shared_proc::enter_func(&call.func, &call.args, runtime) {
+                    raise!(err);
+                }
+                frames.push(Frame {
+                    proto: Rc::clone(&call.func.proto),
+                    pc: 0,
+                    resources_base: runtime.resources.len(),
+                    memoize: call.memoize,
+                });
+                continue;
+            }
+            Ok(Control::Call(call)) => {
+                if let Err(err) = shared_proc::enter_func(&call.func, &call.args, runtime) {
+                    raise!(err);
+                }
+                frames.push(Frame {
+                    proto: Rc::clone(&call.func.proto),
+                    pc: 0,
+                    resources_base: runtime.resources.len(),
+                    memoize: call.memoize,
+                });
+                continue;
+            }
+            Ok(Control::Yield(value)) => {
+                if !coroutine {
+                    raise!(RuntimeError::new(
+                        ErrorKind::Other,
+                        "Cannot yield outside a coroutine, or across a native call",
+                    ));
+                }
+                let thread = Thread {
+                    root_pc,
+                    frames: std::mem::take(&mut frames),
+                };
+                return Ok(Stop::Yield(value, thread));
+            }
+            Ok(Control::Return(value)) => (value, Vec::new()),
+            Ok(Control::ReturnMany(mut values)) => {
+                let rest = values.split_off(values.len().min(1));
+                (values.pop().unwrap_or(Object::Nil), rest)
+            }
+            Err(err) => raise!(err),
+        };
+        let Some(frame) = frames.last() else {
+            break Ok(Stop::Return(value));
+        };
+        // A `return` in a `try` block leaves it, so the errors closing its `with`s are the
+        // caller's.
+        while in_try_block(runtime) {
+            runtime.handlers.pop();
+        }
+        let mut result = Ok(value);
+        close_resources(runtime, frame.resources_base, &mut result);
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => raise!(err),
+        };
+        let frame = frames.pop().unwrap();
+        shared_proc::leave_func(runtime);
+        if let Some((memoized, key)) = frame.memoize {
+            memoized.insert(key, value.clone());
+        }
+        let (caller_code, pc) = match frames.last_mut() {
+            Some(caller) => (&caller.proto.code[..], &mut caller.pc),
+            None => (code, &mut root_pc),
+        };
+        // The other values are kept only if the caller spreads the result or returns it.
+        match (&caller_code[*pc], caller_code.get(*pc + 1)) {
+            (Code::TailCall(_), Some(Code::Return)) if !rest.is_empty() => {
+                *pc += 1;
+                returning = Some(std::iter::once(value).chain(rest).collect());
+            }
+            (_, Some(Code::Unpack(count))) => {
+                let values = rest.into_iter().chain(std::iter::repeat(Object::Nil));
+                runtime.stack.push(value.into());
+                for value in values.take((*count as usize).saturating_sub(1)) {
+                    runtime.stack.push(value.into());
+                }
+                *pc += 2;
+            }
+            _ => {
+                runtime.stack.push(value.into());
+                *pc += 1;
+            }
+        }
+    };
+    // The `try` blocks left by an error that can't be caught.
+    runtime.handlers.truncate(handlers_base);
+    let Err(mut err) = result else {
+        return result;
+    };
+
+    // Unwind the frames, innermost first, as the native stack would have been.
+    err.pc
+        .get_or_insert(frames.last().map_or(root_pc, |frame| frame.pc));
+    while let Some(frame) = frames.pop() {
+        push_trace_frame(&mut err, runtime, frame.pc, &frame.proto.spans);
+        let mut result = Err(err);
+        close_resources(runtime, frame.resources_base, &mut result);
+        shared_proc::leave_func(runtime);
+        err = result.unwrap_err();
+    }
+    push_trace_frame(&mut err, runtime, root_pc, spans);
+    Err(err)
+}
+
+/// Whether the innermost `try` block being run is in the function at the top of the call stack.
+fn in_try_block(runtime: &Runtime) -> bool {
+    runtime
+        .handlers
+        .last()
+        .is_some_and(|handler| handler.depth == runtime.call_stack.len())
+}
+
+/// Unwinds the frames to the innermost `try` block entered since `handlers_base`, recording them
+/// in the traceback of `err`, and goes on in its `catch` block with the error pushed. The error
+/// is given back if there is no such block, or if it can't be caught.
+fn catch(
+    mut err: RuntimeError,
+    frames: &mut Vec<Frame>,
+    root_pc: &mut usize,
+    spans: &[Option<TextSpan>],
+    handlers_base: usize,
+    runtime: &mut Runtime,
+) -> Result<(), RuntimeError> {
+    if runtime.handlers.len() <= handlers_base || !err.kind.is_catchable() {
+        return Err(err);
+    }
+    let handler = runtime.handlers.pop().unwrap();
+    err.pc
+        .get_or_insert(frames.last().map_or(*root_pc, |frame| frame.pc));
+    while runtime.call_stack.len() > handler.depth {
+        let frame = frames
+            .pop()
+            .expect("[BUG] The frame of a `try` block is gone.");
+        push_trace_frame(&mut err, runtime, frame.pc, &frame.proto.spans);
+        let mut result = Err(err);
+        close_resources(runtime, frame.resources_base, &mut result);
+        shared_proc::leave_func(runtime);
+        err = result.unwrap_err();
+    }
+    let (frame_spans, pc) = match frames.last_mut() {
+        Some(frame) => (&frame.proto.spans[..], &mut frame.pc),
+        None => (spans, root_pc),
+    };
+    push_trace_frame(&mut err, runtime, *pc, frame_spans);
+    let mut result = Err(err);
+    close_resources(runtime, handler.resources, &mut result);
+    let err = result.unwrap_err();
+    runtime.stack.truncate(handler.stack_len);
+    let locals = runtime.variable_table.scope_len() - handler.locals;
+    runtime.variable_table.drop(locals);
+    runtime.stack.push(Object::new_error(err).into());
+    *pc = handler.pc;
+    Ok(())
+}
+
+/// Records the function at the top of the call stack, at `pc`, in the traceback of `err`.
+fn push_trace_frame(
+    err: &mut RuntimeError,
+    runtime: &Runtime,
+    pc: usize,
+    spans: &[Option<TextSpan>],
+) {
+    let (function, file) = match runtime.call_stack.last() {
+        Some(proto) => (
+            proto.name.as_deref().unwrap_or("<anonymous>"),
+            proto.file.clone(),
+        ),
+        None => ("<main>", None),
+    };
+    err.push_frame(TraceFrame {
+        function: function.to_string(),
+        pc,
+        span: spans.get(pc).copied().flatten(),
+        file,
+        repeated: 0,
+    });
+}
+
+/// Closes the resources entered by `with` after the first `base` ones. An error while closing
+/// replaces `result` unless it is already an error.
+fn close_resources(runtime: &mut Runtime, base: usize, result: &mut Result<Object, RuntimeError>) {
+    while runtime.resources.len() > base {
+        let resource = runtime.resources.pop().unwrap();
+        let closed = code_impl::close_resource(resource, runtime);
+        if let (Ok(_), Err(err)) = (&*result, closed) {
+            *result = Err(err);
+        }
+    }
+}
+
+// Pops `$args_len` arguments (in reverse order) and then the receiver, and evaluates `$body`
+// with them. Arguments live on the native stack, so that calls don't allocate.
+macro_rules! with_call_args {
+    ($runtime:ident, $args_len:expr, |$receiver:ident, $args:ident| $body:expr) => {
+        match $args_len {
+            0 => {
+                let $receiver = $runtime.stack.pop();
+                let $args: &[Object] = &[];
+                $body
+            }
+            1 => {
+                let arg = $runtime.stack.pop().ensure_object();
+                let $receiver = $runtime.stack.pop();
+                let $args: &[Object] = &[arg];
+                $body
+            }
+            2 => {
+                let arg2 = $runtime.stack.pop().ensure_object();
+                let arg1 = $runtime.stack.pop().ensure_object();
+                let $receiver = $runtime.stack.pop();
+                let $args: &[Object] = &[arg2, arg1];
+                $body
+            }
+            3 => {
+                let arg3 = $runtime.stack.pop().ensure_object();
+                let arg2 = $runtime.stack.pop().ensure_object();
+                let arg1 = $runtime.stack.pop().ensure_object();
+                let $receiver = $runtime.stack.pop();
+                let $args: &[Object] = &[arg3, arg2, arg1];
+                $body
+            }
+            args_len => {
+                let args = (0..args_len)
+                    .map(|_| $runtime.stack.pop().ensure_object())
+                    .collect::<SmallVec<[Object; 8]>>();
+                let $receiver = $runtime.stack.pop();
+                let $args: &[Object] = &args;
+                $body
+            }
+        }
+    };
+}
+
+// Applies a binary operator to the two topmost values, and moves on to the next instruction
+// unless a metamethod is to be called first. The optional closure is a fast path for
+// `Int op Int`, which skips the generic implementation and writes over the left operand, or
+// returns `None` to leave the operands to it, e.g. on overflow.
+macro_rules! binary_op {
+    ($runtime:ident, $pc:ident, $op:expr) => {{
+        let rhs = $runtime.stack.pop().ensure_object();
+        let lhs = $runtime.stack.pop().ensure_object();
+        match code_impl::resolve_binary($op, lhs, rhs, $runtime)? {
+            code_impl::Resolved::Done(res) => {
+                $runtime.stack.push(res.into());
+                *$pc += 1;
+            }
+            code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+        }
+    }};
+    ($runtime:ident, $pc:ident, $op:expr, |$lhs:ident, $rhs:ident| $int_op:expr) => {{
+        let rhs = $runtime.stack.pop().ensure_object();
+        let top = $runtime.stack.top_mut();
+        let fast = match (&*top, &rhs) {
+            (StackValue::Object(Object::Int($lhs)), Object::Int($rhs)) => {
+                let ($lhs, $rhs) = (*$lhs, *$rhs);
+                $int_op
+            }
+            _ => None,
+        };
+        match fast {
+            Some(res) => {
+                *top = StackValue::Object(res);
+                *$pc += 1;
+            }
+            None => {
+                let lhs = $runtime.stack.pop().ensure_object();
+                match code_impl::resolve_binary($op, lhs, rhs, $runtime)? {
+                    code_impl::Resolved::Done(res) => {
+                        $runtime.stack.push(res.into());
+                        *$pc += 1;
+                    }
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+        }
+    }};
+}
+
+fn execute_code(
+    code: &[Code],
+    runtime: &mut Runtime,
+    pc: &mut usize,
+) -> Result<Control, RuntimeError> {
     use Code::*;
 
-    let mut pc = 0;
     loop {
-        // println!("code: {:?}", code[pc]);
+        runtime.consume_fuel()?;
+        runtime.stats.instructions += 1;
+        // println!("code: {:?}", code[*pc]);
         // runtime.dump();
         // println!();
 
-        match &code[pc] {
+        match &code[*pc] {
             LoadInt(x) => {
                 runtime.stack.push(Object::Int(*x).into());
-                pc += 1;
+                *pc += 1;
             }
             LoadFloat(x) => {
                 runtime.stack.push(Object::Float(*x).into());
-                pc += 1;
+                *pc += 1;
             }
             LoadBool(x) => {
                 runtime.stack.push(Object::Bool(*x).into());
-                pc += 1;
+                *pc += 1;
             }
             LoadString(x) => {
-                let x = StringObject::new(Rc::clone(x));
-                runtime.stack.push(Object::String(x).into());
-                pc += 1;
+                runtime.stack.push(Object::String(x.clone()).into());
+                *pc += 1;
             }
             LoadNil => {
                 runtime.stack.push(Object::Nil.into());
-                pc += 1;
+                *pc += 1;
             }
             LoadLocal(id) => {
                 let object = runtime.variable_table.get(*id);
                 runtime.stack.push(object.into());
-                pc += 1;
+                *pc += 1;
+            }
+            LoadGlobal(name) => {
+                let object = runtime.global.get(name.as_str());
+                runtime.stack.push(object.into());
+                *pc += 1;
             }
             LoadRustFunction(x) => {
                 runtime.stack.push(Object::RustFunction(*x).into());
-                pc += 1;
+                *pc += 1;
             }
             UnloadTop => {
                 runtime.stack.pop();
-                pc += 1;
+                *pc += 1;
             }
             SetLocal(id) => {
                 let object = runtime.stack.pop().ensure_object();
                 runtime.variable_table.edit(*id, object);
-                pc += 1;
+                *pc += 1;
+            }
+            SetGlobal(name) => {
+                let object = runtime.stack.pop().ensure_object();
+                runtime.global.set(name.as_str(), object);
+                *pc += 1;
             }
             MakeLocal => {
                 let object = runtime.stack.pop().ensure_object();
                 runtime.variable_table.push(object);
-                pc += 1;
+                *pc += 1;
             }
             MakeArray(count) => {
-                let mut array = Vec::with_capacity(*count as usize);
-                for _ in 0..*count {
-                    array.push(runtime.stack.pop().ensure_object());
-                }
-                array.reverse();
+                let array = runtime.stack.pop_objects(*count as usize);
                 runtime.stack.push(array.into());
-                pc += 1;
+                *pc += 1;
+            }
+            MakeArraySpread(count) => {
+                let mut items = Vec::new();
+                for array in runtime.stack.pop_objects(*count as usize) {
+                    items.extend(code_impl::spread(array)?);
+                }
+                runtime.stack.push(items.into());
+                *pc += 1;
             }
             MakeNamed => {
-                let name = runtime.stack.pop().ensure_object().ensure_string()?;
+                let name = TableKey::from_object(runtime.stack.pop().ensure_object())?;
                 let object = runtime.stack.pop().ensure_object();
                 runtime.stack.push((name, object).into());
-                pc += 1;
+                *pc += 1;
             }
             MakeTable(count) => {
-                let mut hash_map = HashMap::with_capacity(*count as usize);
-                for _ in 0..*count {
-                    let (name, value) = runtime.stack.pop().ensure_named();
-                    let name = name.to_string();
-                    hash_map.insert(name.into(), value);
-                }
+                let hash_map = (0..*count)
+                    .map(|_| runtime.stack.pop().ensure_named())
+                    .collect();
                 let table = TableObject::new(hash_map);
                 runtime.stack.push(Object::new_table(table).into());
-                pc += 1;
+                *pc += 1;
             }
             DropLocal(count) => {
                 runtime.variable_table.drop(*count);
-                pc += 1;
+                *pc += 1;
             }
             Jump(offset) => {
-                if offset.is_positive() {
-                    pc += *offset as usize;
-                } else {
-                    pc -= offset.unsigned_abs();
+                // Every loop jumps backward, so checking here is enough to stop an endless loop.
+                if *offset <= 0 {
+                    code_impl::check_interrupt(runtime)?;
                 }
+                *pc = pc.wrapping_add_signed(*offset);
             }
             JumpIfTrue(offset) => {
-                let boolean = runtime.stack.pop().ensure_object().ensure_bool()?;
-                if boolean {
-                    if offset.is_positive() {
-                        pc += *offset as usize;
-                    } else {
-                        pc -= offset.unsigned_abs();
-                    }
-                } else {
-                    pc += 1;
+                let truthy = runtime.stack.pop().ensure_object().is_truthy();
+                if truthy && *offset <= 0 {
+                    code_impl::check_interrupt(runtime)?;
                 }
+                *pc = pc.wrapping_add_signed(if truthy { *offset } else { 1 });
             }
             JumpIfFalse(offset) => {
-                let boolean = runtime.stack.pop().ensure_object().ensure_bool()?;
-                if !boolean {
-                    if offset.is_positive() {
-                        pc += *offset as usize;
-                    } else {
-                        pc -= offset.unsigned_abs();
+                let truthy = runtime.stack.pop().ensure_object().is_truthy();
+                if !truthy && *offset <= 0 {
+                    code_impl::check_interrupt(runtime)?;
+                }
+                *pc = pc.wrapping_add_signed(if truthy { 1 } else { *offset });
+            }
+            JumpIfTrueOrPop(offset) | JumpIfFalseOrPop(offset) => {
+                let truthy = match runtime.stack.top_mut() {
+                    StackValue::Object(object) => object.is_truthy(),
+                    _ => true,
+                };
+                if truthy == matches!(code[*pc], JumpIfTrueOrPop(_)) {
+                    if *offset <= 0 {
+                        code_impl::check_interrupt(runtime)?;
                     }
+                    *pc = pc.wrapping_add_signed(*offset);
                 } else {
-                    pc += 1;
+                    runtime.stack.pop();
+                    *pc += 1;
                 }
             }
-            CallMethod(name, args_len) => {
-                let res = match args_len {
-                    0 => {
-                        let self_obj = runtime.stack.pop().ensure_object();
-                        code_impl::call_method(self_obj, name, &[], runtime)?
+            JumpIfNil(offset) => {
+                let nil = matches!(runtime.stack.top_mut(), StackValue::Object(Object::Nil));
+                if nil && *offset <= 0 {
+                    code_impl::check_interrupt(runtime)?;
+                }
+                *pc = pc.wrapping_add_signed(if nil { *offset } else { 1 });
+            }
+            JumpIfNotNil(offset) => {
+                if matches!(runtime.stack.top_mut(), StackValue::Object(Object::Nil)) {
+                    runtime.stack.pop();
+                    *pc += 1;
+                } else {
+                    if *offset <= 0 {
+                        code_impl::check_interrupt(runtime)?;
                     }
-                    1 => {
-                        let arg = runtime.stack.pop().ensure_object();
-                        let self_obj = runtime.stack.pop().ensure_object();
-                        code_impl::call_method(self_obj, name, &[arg], runtime)?
+                    *pc = pc.wrapping_add_signed(*offset);
+                }
+            }
+            ForPrep(id, offset) => {
+                let (index, end, step) = code_impl::numeric_for(*id, runtime)?;
+                if step == 0 {
+                    return Err("The step of a numeric for must not be 0".into());
+                }
+                if (step > 0 && index <= end) || (step < 0 && index >= end) {
+                    runtime
+                        .variable_table
+                        .edit(LocalId(id.0 + 3), Object::Int(index));
+                    *pc += 1;
+                } else {
+                    *pc = pc.wrapping_add_signed(*offset);
+                }
+            }
+            ForLoop(id, offset) => {
+                let (index, end, step) = code_impl::numeric_for(*id, runtime)?;
+                // Counting past the end of the integers ends the loop.
+                match index.checked_add(step) {
+                    Some(index) if (step > 0 && index <= end) || (step < 0 && index >= end) => {
+                        code_impl::check_interrupt(runtime)?;
+                        runtime.variable_table.edit(*id, Object::Int(index));
+                        runtime
+                            .variable_table
+                            .edit(LocalId(id.0 + 3), Object::Int(index));
+                        *pc = pc.wrapping_add_signed(*offset);
                     }
-                    2 => {
-                        let arg2 = runtime.stack.pop().ensure_object();
-                        let arg1 = runtime.stack.pop().ensure_object();
-                        let self_obj = runtime.stack.pop().ensure_object();
-                        code_impl::call_method(self_obj, name, &[arg2, arg1], runtime)?
+                    _ => *pc += 1,
+                }
+            }
+            IterStart => {
+                let iterable = runtime.stack.pop().ensure_object();
+                match code_impl::resolve_iter_method(&iterable, "__iter", runtime)? {
+                    Some(code_impl::Resolved::Enter(call)) => return Ok(Control::Call(call)),
+                    Some(code_impl::Resolved::Done(iterator)) => {
+                        runtime.stack.push(iterator.into());
                     }
-                    3 => {
-                        let arg3 = runtime.stack.pop().ensure_object();
-                        let arg2 = runtime.stack.pop().ensure_object();
-                        let arg1 = runtime.stack.pop().ensure_object();
-                        let self_obj = runtime.stack.pop().ensure_object();
-                        code_impl::call_method(self_obj, name, &[arg3, arg2, arg1], runtime)?
+                    None => runtime.stack.push(iterable.into()),
+                }
+                *pc += 1;
+            }
+            IterNext(id, offset) => {
+                let iterator = runtime.variable_table.get(*id);
+                match code_impl::resolve_iter_method(&iterator, "__next", runtime)? {
+                    Some(code_impl::Resolved::Enter(call)) => return Ok(Control::Call(call)),
+                    Some(code_impl::Resolved::Done(item)) => {
+                        runtime.stack.push(item.into());
+                        *pc += 1;
                     }
-                    _ => {
-                        let mut args = Vec::with_capacity(*args_len as usize);
-                        for _ in 0..*args_len {
-                            args.push(runtime.stack.pop().ensure_object());
+                    None => match code_impl::next_item(iterator, *id, runtime)? {
+                        Some(item) => {
+                            runtime.stack.push(item.into());
+                            *pc += 2;
                         }
-                        let self_obj = runtime.stack.pop().ensure_object();
-                        code_impl::call_method(self_obj, name, &args, runtime)?
+                        None => *pc = pc.wrapping_add_signed(*offset),
+                    },
+                }
+            }
+            IterEnd(offset) => {
+                if let StackValue::Object(Object::Nil) = runtime.stack.top_mut() {
+                    runtime.stack.pop();
+                    *pc = pc.wrapping_add_signed(*offset);
+                } else {
+                    *pc += 1;
+                }
+            }
+            CallMethod(site, args_len) => {
+                let call = with_call_args!(runtime, *args_len, |self_obj, args| {
+                    code_impl::resolve_method_call(self_obj.ensure_object(), site, args, runtime)?
+                });
+                match call {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                };
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+            CallBuiltinMethod(method, args_len) => {
+                let res = with_call_args!(runtime, *args_len, |self_obj, args| {
+                    let self_obj = self_obj.ensure_object();
+                    code_impl::call_builtin_method(self_obj, *method, args, runtime)?
+                });
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             Call(args_len) => {
-                let res = match args_len {
-                    0 => {
-                        let callee = runtime.stack.pop();
-                        code_impl::call(callee, &[], runtime)?
-                    }
-                    1 => {
-                        let arg = runtime.stack.pop().ensure_object();
-                        let callee = runtime.stack.pop();
-                        code_impl::call(callee, &[arg], runtime)?
+                let call = with_call_args!(runtime, *args_len, |callee, args| {
+                    code_impl::resolve_call(callee, args, runtime)?
+                });
+                match call {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                    2 => {
-                        let arg2 = runtime.stack.pop().ensure_object();
-                        let arg1 = runtime.stack.pop().ensure_object();
-                        let callee = runtime.stack.pop();
-                        code_impl::call(callee, &[arg2, arg1], runtime)?
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+            CallSpread => {
+                let args = code_impl::spread_args(runtime.stack.pop().ensure_object())?;
+                let callee = runtime.stack.pop();
+                match code_impl::resolve_call(callee, &args, runtime)? {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                    3 => {
-                        let arg3 = runtime.stack.pop().ensure_object();
-                        let arg2 = runtime.stack.pop().ensure_object();
-                        let arg1 = runtime.stack.pop().ensure_object();
-                        let callee = runtime.stack.pop();
-                        code_impl::call(callee, &[arg3, arg2, arg1], runtime)?
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+            CallMethodSpread(site) => {
+                let args = code_impl::spread_args(runtime.stack.pop().ensure_object())?;
+                let self_obj = runtime.stack.pop().ensure_object();
+                match code_impl::resolve_method_call(self_obj, site, &args, runtime)? {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                    _ => {
-                        let mut args = Vec::with_capacity(*args_len as usize);
-                        for _ in 0..*args_len {
-                            args.push(runtime.stack.pop().ensure_object());
-                        }
-                        let callee = runtime.stack.pop();
-                        code_impl::call(callee, &args, runtime)?
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+            TailCall(args_len) => {
+                let call = with_call_args!(runtime, *args_len, |callee, args| {
+                    code_impl::resolve_call(callee, args, runtime)?
+                });
+                match call {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                };
-                runtime.stack.push(res.into());
-                pc += 1;
+                    code_impl::Resolved::Enter(call) => return Ok(Control::TailCall(call)),
+                }
+            }
+            Unpack(count) => {
+                for _ in 1..*count {
+                    runtime.stack.push(Object::Nil.into());
+                }
+                *pc += 1;
             }
             SetItem => {
                 let accesser = runtime.stack.pop().ensure_object();
                 let target = runtime.stack.pop();
                 let value = runtime.stack.pop().ensure_object();
-                code_impl::set_item(target, accesser, value)?;
-                pc += 1;
+                code_impl::set_item(target, accesser, value, runtime)?;
+                *pc += 1;
             }
             GetItem => {
                 let accesser = runtime.stack.pop().ensure_object();
                 let target = runtime.stack.pop();
-                let item = code_impl::get_item(target, accesser)?;
-                runtime.stack.push(item.into());
-                pc += 1;
-            }
-            Add => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::add(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Sub => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::sub(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Mul => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::mul(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Div => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::div(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Mod => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::r#mod(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Pow => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                match (lhs, rhs) {
-                    (Object::Int(_lhs), Object::Int(_rhs)) => {
-                        unimplemented!("Int.pow(Int) is not implemented.");
-                    }
-                    (Object::Int(lhs), Object::Float(rhs)) => {
-                        let pow = (lhs as f64).powf(rhs);
-                        runtime.stack.push(Object::Float(pow).into());
+                match code_impl::resolve_get_item(target, accesser, runtime)? {
+                    code_impl::Resolved::Done(item) => {
+                        runtime.stack.push(item.into());
+                        *pc += 1;
                     }
-                    (Object::Float(lhs), Object::Int(rhs)) => {
-                        let pow = if rhs > i32::MAX as i64 {
-                            lhs.powf(rhs as f64)
-                        } else {
-                            lhs.powi(rhs as i32)
-                        };
-                        runtime.stack.push(Object::Float(pow).into());
-                    }
-                    (Object::Float(lhs), Object::Float(rhs)) => {
-                        let pow = lhs.powf(rhs);
-                        runtime.stack.push(Object::Float(pow).into());
-                    }
-                    (lhs, rhs) => Err(format!(
-                        "Expected Int or Float, but got {:?} and {:?}",
-                        lhs, rhs
-                    ))?,
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
                 }
-                pc += 1;
             }
+            GetSlice => {
+                let end = runtime.stack.pop().ensure_object();
+                let start = runtime.stack.pop().ensure_object();
+                let target = runtime.stack.pop().ensure_object();
+                runtime
+                    .stack
+                    .push(code_impl::get_slice(target, start, end)?.into());
+                *pc += 1;
+            }
+            Add => binary_op!(runtime, pc, BinaryOp::Add, |lhs, rhs| lhs
+                .checked_add(rhs)
+                .map(Object::Int)),
+            Sub => binary_op!(runtime, pc, BinaryOp::Sub, |lhs, rhs| lhs
+                .checked_sub(rhs)
+                .map(Object::Int)),
+            Mul => binary_op!(runtime, pc, BinaryOp::Mul, |lhs, rhs| lhs
+                .checked_mul(rhs)
+                .map(Object::Int)),
+            Div => binary_op!(runtime, pc, BinaryOp::Div),
+            Mod => binary_op!(runtime, pc, BinaryOp::Mod),
+            Pow => binary_op!(runtime, pc, BinaryOp::Pow),
+            FloorDiv => binary_op!(runtime, pc, BinaryOp::FloorDiv),
             Unm => {
                 let obj = runtime.stack.pop().ensure_object();
-                let res = code_impl::unm(obj)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Eq => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                runtime.stack.push(Object::Bool(lhs == rhs).into());
-                pc += 1;
-            }
-            NotEq => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                runtime.stack.push(Object::Bool(lhs != rhs).into());
-                pc += 1;
-            }
-            Less => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::less(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            LessEq => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::less_eq(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
-            }
-            Greater => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::greater(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
+                match code_impl::resolve_unm(obj, runtime)? {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
+                    }
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
             }
-            GreaterEq => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::greater_eq(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
+            Not => {
+                let obj = runtime.stack.pop().ensure_object();
+                runtime.stack.push(Object::Bool(!obj.is_truthy()).into());
+                *pc += 1;
             }
-            Concat => {
-                let rhs = runtime.stack.pop().ensure_object();
-                let lhs = runtime.stack.pop().ensure_object();
-                let res = code_impl::concat(lhs, rhs)?;
-                runtime.stack.push(res.into());
-                pc += 1;
+            Eq => binary_op!(runtime, pc, BinaryOp::Eq),
+            NotEq => binary_op!(runtime, pc, BinaryOp::NotEq),
+            Less => binary_op!(runtime, pc, BinaryOp::Less, |lhs, rhs| Some(Object::Bool(
+                lhs < rhs
+            ))),
+            LessEq => binary_op!(runtime, pc, BinaryOp::LessEq, |lhs, rhs| Some(
+                Object::Bool(lhs <= rhs)
+            )),
+            Greater => binary_op!(runtime, pc, BinaryOp::Greater, |lhs, rhs| Some(
+                Object::Bool(lhs > rhs)
+            )),
+            GreaterEq => binary_op!(runtime, pc, BinaryOp::GreaterEq, |lhs, rhs| Some(
+                Object::Bool(lhs >= rhs)
+            )),
+            In => {
+                let container = runtime.stack.pop().ensure_object();
+                let item = runtime.stack.pop().ensure_object();
+                runtime
+                    .stack
+                    .push(code_impl::contains(container, item)?.into());
+                *pc += 1;
             }
+            Concat => binary_op!(runtime, pc, BinaryOp::Concat),
             BitAnd => {
                 let rhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let lhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let res = Object::Int(lhs & rhs);
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             BitOr => {
                 let rhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let lhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let res = Object::Int(lhs | rhs);
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             BitXor => {
                 let rhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let lhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let res = Object::Int(lhs ^ rhs);
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             BitNot => {
                 let obj = runtime.stack.pop().ensure_object().ensure_int()?;
                 let res = Object::Int(!obj);
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             ShiftL => {
                 let rhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let lhs = runtime.stack.pop().ensure_object().ensure_int()?;
-                let res = Object::Int(lhs << rhs);
+                let res = code_impl::shift_left(lhs, rhs)?;
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             ShiftR => {
                 let rhs = runtime.stack.pop().ensure_object().ensure_int()?;
                 let lhs = runtime.stack.pop().ensure_object().ensure_int()?;
-                let res = Object::Int(lhs >> rhs);
+                let res = code_impl::shift_right(lhs, rhs)?;
                 runtime.stack.push(res.into());
-                pc += 1;
+                *pc += 1;
             }
             Builtin(instr, args_len) => {
-                let mut args = SmallVec::<[_; 2]>::with_capacity(*args_len as usize);
-                for _ in 0..*args_len {
-                    args.push(runtime.stack.pop().ensure_object());
-                }
-                match instr {
-                    BuiltinInstr::Write => {
-                        for arg in args.iter().rev() {
-                            runtime.stdio.write(format!("{}", arg));
-                        }
-                    }
-                    BuiltinInstr::Flush => {
-                        assert!(*args_len == 0, "Builtin::Flush takes no arguments.");
-                        runtime.stdio.flush();
-                    }
-                    BuiltinInstr::WriteError => {
-                        for arg in args.iter().rev() {
-                            runtime.stdio.write_err(format!("{}", arg));
-                        }
+                execute_builtin(*instr, *args_len, runtime)?;
+                *pc += 1;
+            }
+            MakeClosure(proto) => {
+                let env = proto
+                    .captures
+                    .iter()
+                    .map(|id| runtime.variable_table.get_ref(*id))
+                    .collect();
+                let func = FunctionObject {
+                    proto: Rc::clone(proto),
+                    env,
+                };
+                runtime.stack.push(Object::new_function(func).into());
+                *pc += 1;
+            }
+            EnterWith => {
+                let resource = runtime.stack.pop().ensure_object();
+                code_impl::ensure_closable(&resource)?;
+                runtime.resources.push(resource);
+                *pc += 1;
+            }
+            ExitWith => {
+                let resource = runtime
+                    .resources
+                    .pop()
+                    .expect("[BUG] ExitWith is called without EnterWith.");
+                code_impl::close_resource(resource, runtime)?;
+                *pc += 1;
+            }
+            Try(offset) => {
+                runtime.handlers.push(Handler {
+                    depth: runtime.call_stack.len(),
+                    pc: pc.wrapping_add_signed(*offset),
+                    stack_len: runtime.stack.len(),
+                    locals: runtime.variable_table.scope_len(),
+                    resources: runtime.resources.len(),
+                });
+                *pc += 1;
+            }
+            EndTry => {
+                runtime
+                    .handlers
+                    .pop()
+                    .expect("[BUG] EndTry is called without Try.");
+                *pc += 1;
+            }
+            Yield => {
+                return Ok(Control::Yield(runtime.stack.pop().ensure_object()));
+            }
+            Import => {
+                let name = runtime.stack.pop().ensure_object().ensure_string()?;
+                let exports = code_impl::import(name.as_str(), runtime)?;
+                runtime.stack.push(exports.into());
+                *pc += 1;
+            }
+            Nop => {
+                *pc += 1;
+            }
+            Return => {
+                return Ok(Control::Return(runtime.stack.pop().ensure_object()));
+            }
+            ReturnMany(count) => {
+                let mut values = vec![Object::Nil; *count as usize];
+                for value in values.iter_mut().rev() {
+                    *value = runtime.stack.pop().ensure_object();
+                }
+                return Ok(Control::ReturnMany(values));
+            }
+            Exit => {
+                return Ok(Control::Return(Object::Nil));
+            }
+            Binary(op, lhs, rhs) => {
+                let rhs = operand(*rhs, runtime);
+                let lhs = operand(*lhs, runtime);
+                match code_impl::resolve_binary(*op, lhs, rhs, runtime)? {
+                    code_impl::Resolved::Done(res) => {
+                        runtime.stack.push(res.into());
+                        *pc += 1;
                     }
-                    BuiltinInstr::FlushError => {
-                        assert!(*args_len == 0, "Builtin::FlushError takes no arguments.");
+                    code_impl::Resolved::Enter(call) => return Ok(Control::Call(call)),
+                }
+            }
+            BinaryTo(dst, op, lhs, rhs) => {
+                let rhs = operand(*rhs, runtime);
+                let lhs = operand(*lhs, runtime);
+                let res = code_impl::resolve_binary(*op, lhs, rhs, runtime)?.finish(runtime)?;
+                runtime.variable_table.edit(*dst, res);
+                *pc += 1;
+            }
+            Move(dst, src) => {
+                let object = operand(*src, runtime);
+                runtime.variable_table.edit(*dst, object);
+                *pc += 1;
+            }
+        }
+    }
+}
+
+fn operand(operand: Operand, runtime: &mut Runtime) -> Object {
+    match operand {
+        Operand::Stack => runtime.stack.pop().ensure_object(),
+        Operand::Local(id) => runtime.variable_table.get(id),
+        Operand::Int(x) => Object::Int(x),
+    }
+}
+
+impl BinaryOp {
+    /// Applies the operator as the stack instruction of the same name does, e.g. to evaluate
+    /// constant expressions at compile time.
+    pub fn apply(self, lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
+        if let (Object::Int(lhs), Object::Int(rhs)) = (&lhs, &rhs) {
+            let (lhs, rhs) = (*lhs, *rhs);
+            match self {
+                BinaryOp::Less => return Ok(Object::Bool(lhs < rhs)),
+                BinaryOp::LessEq => return Ok(Object::Bool(lhs <= rhs)),
+                BinaryOp::Greater => return Ok(Object::Bool(lhs > rhs)),
+                BinaryOp::GreaterEq => return Ok(Object::Bool(lhs >= rhs)),
+                _ => {}
+            }
+        }
+        match self {
+            BinaryOp::Add => code_impl::add(lhs, rhs),
+            BinaryOp::Sub => code_impl::sub(lhs, rhs),
+            BinaryOp::Mul => code_impl::mul(lhs, rhs),
+            BinaryOp::Div => code_impl::div(lhs, rhs),
+            BinaryOp::Mod => code_impl::r#mod(lhs, rhs),
+            BinaryOp::Pow => code_impl::pow(lhs, rhs),
+            BinaryOp::FloorDiv => code_impl::floor_div(lhs, rhs),
+            BinaryOp::Eq => Ok(Object::Bool(lhs == rhs)),
+            BinaryOp::NotEq => Ok(Object::Bool(lhs != rhs)),
+            BinaryOp::Less => code_impl::less(lhs, rhs),
+            BinaryOp::LessEq => code_impl::less_eq(lhs, rhs),
+            BinaryOp::Greater => code_impl::greater(lhs, rhs),
+            BinaryOp::GreaterEq => code_impl::greater_eq(lhs, rhs),
+            BinaryOp::Concat => code_impl::concat(lhs, rhs),
+        }
+    }
+}
+
+fn execute_builtin(
+    instr: BuiltinInstr,
+    args_len: u8,
+    runtime: &mut Runtime,
+) -> Result<(), RuntimeError> {
+    let args = (0..args_len)
+        .map(|_| runtime.stack.pop().ensure_object())
+        .collect::<SmallVec<[Object; 4]>>();
+    match instr {
+        BuiltinInstr::Write => {
+            for arg in args.iter().rev() {
+                runtime.stdio.write(format!("{}", arg));
+            }
+        }
+        BuiltinInstr::Flush => {
+            assert!(args_len == 0, "Builtin::Flush takes no arguments.");
+            runtime.stdio.flush();
+        }
+        BuiltinInstr::WriteError => {
+            for arg in args.iter().rev() {
+                runtime.stdio.write_err(format!("{}", arg));
+            }
+        }
+        BuiltinInstr::FlushError => {
+            assert!(args_len == 0, "Builtin::FlushError takes no arguments.");
+            runtime.stdio.flush_err();
+        }
+        BuiltinInstr::ReadLine => {
+            assert!(args_len == 1, "Builtin::ReadLine takes 1 argument.");
+            match &args[0] {
+                Object::Nil => {}
+                prompt => {
+                    runtime
+                        .stdio
+                        .write(prompt.clone().ensure_string()?.as_str());
+                    runtime.stdio.flush();
+                }
+            }
+            let line = runtime.stdio.read_line()?;
+            runtime.stack.push(Object::from(line).into());
+        }
+        BuiltinInstr::ReadAll => {
+            assert!(args_len == 0, "Builtin::ReadAll takes no arguments.");
+            let all = runtime.stdio.read_all()?;
+            runtime.stack.push(Object::new_string(all).into());
+        }
+        BuiltinInstr::Read => {
+            assert!(args_len == 1, "Builtin::Read takes 1 argument.");
+            let count = args[0].clone().ensure_int()?;
+            if count < 0 {
+                return Err(format!("Count must not be negative, got {}", count).into());
+            }
+            let chars = runtime.stdio.read_chars(count as usize)?;
+            runtime.stack.push(Object::from(chars).into());
+        }
+        BuiltinInstr::IsTerminal => {
+            assert!(args_len == 0, "Builtin::IsTerminal takes no arguments.");
+            let is_terminal = runtime.stdio.is_terminal();
+            runtime.stack.push(Object::Bool(is_terminal).into());
+        }
+        BuiltinInstr::TerminalSize => {
+            assert!(args_len == 0, "Builtin::TerminalSize takes no arguments.");
+            let size = runtime.stdio.terminal_size().map(|(cols, rows)| {
+                let mut size = TableObject::new(Default::default());
+                size.insert("cols".into(), Object::Int(cols as i64));
+                size.insert("rows".into(), Object::Int(rows as i64));
+                Object::new_table(size)
+            });
+            runtime.stack.push(Object::from(size).into());
+        }
+        BuiltinInstr::ClearScreen => {
+            assert!(args_len == 0, "Builtin::ClearScreen takes no arguments.");
+            runtime.stdio.clear_screen();
+        }
+        BuiltinInstr::MoveCursor => {
+            assert!(args_len == 2, "Builtin::MoveCursor takes 2 arguments.");
+            let position = |arg: &Object| {
+                let value = arg.clone().ensure_int()?;
+                u16::try_from(value)
+                    .ok()
+                    .filter(|value| *value > 0)
+                    .ok_or_else(|| {
+                        RuntimeError::from(format!("Invalid cursor position: {}", value))
+                    })
+            };
+            let row = position(&args[0])?;
+            let column = position(&args[1])?;
+            runtime.stdio.move_cursor(column, row);
+        }
+        BuiltinInstr::ShowCursor => {
+            assert!(args_len == 1, "Builtin::ShowCursor takes 1 argument.");
+            let visible = args[0].clone().ensure_bool()?;
+            runtime.stdio.show_cursor(visible);
+        }
+        #[cfg(feature = "term")]
+        BuiltinInstr::ReadKey => {
+            assert!(args_len == 0, "Builtin::ReadKey takes no arguments.");
+            let key = runtime.stdio.read_key()?;
+            runtime.stack.push(Object::from(key).into());
+        }
+        BuiltinInstr::ReadFile => {
+            assert!(args_len == 2, "Builtin::ReadFile takes 2 arguments.");
+            let lossy = match &args[0] {
+                Object::Nil => false,
+                lossy => lossy.clone().ensure_bool()?,
+            };
+            let path = args[1].clone().ensure_string()?;
+            let content = code_impl::read_file(path.as_str(), runtime)?;
+            let string = match lossy {
+                true => String::from_utf8_lossy(&content).into_owned(),
+                false => String::from_utf8(content).map_err(|e| RuntimeError::io(path, e))?,
+            };
+            runtime.stack.push(Object::new_string(string).into());
+        }
+        BuiltinInstr::WriteFile => {
+            assert!(args_len == 2, "Builtin::WriteFile takes 2 arguments.");
+            let content = args[0].clone().ensure_string()?;
+            let path = args[1].clone().ensure_string()?;
+            code_impl::write_file(path.as_str(), content.as_str().as_bytes(), runtime)?;
+        }
+        BuiltinInstr::ReadBytes => {
+            assert!(args_len == 1, "Builtin::ReadBytes takes 1 argument.");
+            let path = args[0].clone().ensure_string()?;
+            let content = code_impl::read_file(path.as_str(), runtime)?;
+            runtime.stack.push(Object::new_bytes(content).into());
+        }
+        BuiltinInstr::WriteBytes => {
+            assert!(args_len == 2, "Builtin::WriteBytes takes 2 arguments.");
+            let content = args[0].clone().ensure_bytes()?;
+            let path = args[1].clone().ensure_string()?;
+            code_impl::write_file(path.as_str(), &content, runtime)?;
+        }
+        BuiltinInstr::TypeName => {
+            assert!(args_len == 1, "Builtin::TypeName takes 1 argument.");
+            let typename = args[0].typename();
+            runtime
+                .stack
+                .push(Object::new_string(typename.to_string()).into());
+        }
+        BuiltinInstr::Sleep => {
+            assert!(args_len == 1, "Builtin::Sleep takes 1 argument.");
+            let seconds = match args.into_iter().next().unwrap() {
+                Object::Int(x) => x as f64,
+                Object::Float(x) => x,
+                x => Err(format!(
+                    "Expected `int` or `float` as seconds, got `{}`",
+                    x.typename()
+                ))?,
+            };
+            runtime.permissions().check_sleep()?;
+            code_impl::sleep(seconds, &runtime.interrupt)?;
+            code_impl::check_interrupt(runtime)?;
+        }
+        BuiltinInstr::Log => {
+            assert!(args_len == 3, "Builtin::Log takes 3 arguments.");
+            let mut args = args.into_iter().rev();
+            let level = args.next().unwrap().ensure_int()?;
+            let level =
+                LogLevel::from_int(level).ok_or_else(|| format!("Invalid log level: {}", level))?;
+            let message = args.next().unwrap();
+            let fields = args.next().unwrap();
+            if runtime.logger.enabled(level) {
+                let record = code_impl::make_log_record(level, message, fields)?;
+                match runtime.logger.sink() {
+                    Some(sink) => sink.log(&record),
+                    None => {
+                        runtime.stdio.write_err(format!("{}\n", record));
                         runtime.stdio.flush_err();
                     }
-                    BuiltinInstr::ReadLine => {
-                        assert!(*args_len == 0, "Builtin::ReadLine takes no arguments.");
-                        let line = runtime.stdio.read_line();
-                        runtime.stack.push(Object::new_string(line).into());
-                    }
-                    BuiltinInstr::ReadFile => {
-                        assert!(*args_len == 1, "Builtin::ReadFile takes 1 argument.");
-                        let path = args.into_iter().next().unwrap().ensure_string()?;
-                        let content = std::fs::read(path.as_str()).map_err(|e| e.to_string())?;
-                        let string = String::from_utf8(content).map_err(|e| e.to_string())?;
-                        runtime.stack.push(Object::new_string(string).into());
-                    }
-                    BuiltinInstr::WriteFile => {
-                        assert!(*args_len == 2, "Builtin::WriteFile takes 2 arguments.");
-                        let mut args = args.into_iter();
-                        let path = args.next().unwrap().ensure_string()?;
-                        let content = args.next().unwrap().ensure_string()?;
-                        std::fs::write(path.as_str(), content.as_str())
-                            .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        BuiltinInstr::LogEnabled => {
+            assert!(args_len == 1, "Builtin::LogEnabled takes 1 argument.");
+            let level = args.into_iter().next().unwrap().ensure_string()?;
+            let level = LogLevel::from_name(level.as_str())
+                .ok_or_else(|| format!("Invalid log level: {:?}", level.as_str()))?;
+            runtime
+                .stack
+                .push(Object::Bool(runtime.logger.enabled(level)).into());
+        }
+        BuiltinInstr::Error => {
+            assert!(args_len == 1, "Builtin::Error takes 1 argument.");
+            match args.into_iter().next().unwrap() {
+                // A caught error is raised again as it was, but from here.
+                Object::Error(error) => {
+                    let error = error.error();
+                    return Err(RuntimeError::new(error.kind, error.message.clone()));
+                }
+                message => {
+                    let message = message.ensure_string()?;
+                    return Err(RuntimeError::new(ErrorKind::User, message.to_string()));
+                }
+            }
+        }
+        BuiltinInstr::Args => {
+            assert!(args_len == 0, "Builtin::Args takes no arguments.");
+            let args = runtime.args.iter().map(String::as_str).collect::<Vec<_>>();
+            runtime.stack.push(Object::from(args).into());
+        }
+        BuiltinInstr::Exit => {
+            assert!(args_len == 1, "Builtin::Exit takes 1 argument.");
+            let code = match &args[0] {
+                Object::Nil => 0,
+                code => code.clone().ensure_int()?,
+            };
+            let code =
+                i32::try_from(code).map_err(|_| format!("Exit code out of range: {}", code))?;
+            // Unwinds like an error, so that every frame is left and every `with` closed.
+            runtime.exit_code = Some(code);
+            return Err(RuntimeError::new(
+                ErrorKind::Exit,
+                format!("Exited with code {}", code),
+            ));
+        }
+        BuiltinInstr::Resume => {
+            assert!(args_len == 2, "Builtin::Resume takes 2 arguments.");
+            let coroutine = builtin::ensure_coroutine(&args[1])?;
+            let value = resume(&coroutine, args[0].clone(), runtime)?;
+            runtime.stack.push(value.into());
+        }
+        BuiltinInstr::OnInterrupt => {
+            assert!(args_len == 1, "Builtin::OnInterrupt takes 1 argument.");
+            runtime.interrupt_handler = match args.into_iter().next().unwrap() {
+                Object::Nil => None,
+                handler => {
+                    builtin::ensure_callable(&handler)?;
+                    Some(handler)
+                }
+            };
+        }
+    }
+    Ok(())
+}
+
+mod shared_proc {
+    use super::*;
+
+    pub fn execute_func(
+        func: &FunctionObject,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Object, RuntimeError> {
+        enter_func(func, args, runtime)?;
+        let ret = execute(func.code(), &func.proto.spans, runtime);
+        leave_func(runtime);
+        ret
+    }
+
+    /// Makes a scope with the captures and the arguments of `func`, ready to run its code.
+    pub fn enter_func(
+        func: &FunctionObject,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<(), RuntimeError> {
+        // Recursion may run forever without looping, so calls check for interrupts too.
+        code_impl::check_interrupt(runtime)?;
+        if runtime.call_stack.len() >= runtime.max_call_depth {
+            return Err(RuntimeError::new(
+                ErrorKind::StackOverflow,
+                format!(
+                    "Stack overflow: calls nested deeper than {}",
+                    runtime.max_call_depth
+                ),
+            ));
+        }
+        runtime.variable_table.push_scope();
+        for value in func.env.iter() {
+            runtime.variable_table.push_ref(Rc::clone(value));
+        }
+        let args_len = args.len();
+        for (i, attr) in func.args().iter().enumerate() {
+            if *attr == ArgumentKind::Rest {
+                // `args` is in reverse order, so the surplus arguments are the leading ones.
+                let rest = args[..args_len.saturating_sub(i)].iter().rev().cloned();
+                runtime
+                    .variable_table
+                    .push(Object::new_array(ArrayObject::new(rest.collect())));
+                break;
+            }
+            let value = args_len
+                .checked_sub(i + 1)
+                .map(|i| &args[i])
+                .map(|arg| match attr {
+                    ArgumentKind::Copy => arg.deep_clone(),
+                    ArgumentKind::Ref => todo!("ref argument"),
+                    ArgumentKind::Auto => arg.clone(),
+                    ArgumentKind::Rest => unreachable!(),
+                })
+                .unwrap_or(Object::Nil);
+            runtime.variable_table.push(value);
+        }
+        runtime.call_stack.push(Rc::clone(&func.proto));
+        Ok(())
+    }
+
+    pub fn leave_func(runtime: &mut Runtime) {
+        runtime.call_stack.pop();
+        runtime.variable_table.pop_scope();
+    }
+
+    pub fn exec_userdata_method(
+        userdata: &UserdataObject,
+        name: &str,
+        args: &[Object],
+    ) -> Result<Object, RuntimeError> {
+        match userdata.get_method(name) {
+            Some(func) => func(userdata, args),
+            None => Err(RuntimeError::type_error(format!(
+                "{} is not a method of {}",
+                name,
+                userdata.name()
+            ))),
+        }
+    }
+
+    /// Calls the method `name` defined on `table`, or the function in its field `name` (also
+    /// inherited through `__index`), falling back to the builtin table method `default` when the
+    /// table has neither.
+    pub fn exec_table_method(
+        table: Rc<RefCell<TableObject>>,
+        name: &str,
+        default: Option<BuiltinMethod>,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Object, RuntimeError> {
+        let method = table.borrow().get_method(name);
+        match method {
+            Some(TableMethod::Builtin(func)) => func(table, args),
+            Some(TableMethod::Custom(func)) => {
+                let args = args
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(Object::Table(table)))
+                    .collect::<SmallVec<[Object; 3]>>();
+                execute_func(&func, &args, runtime)
+            }
+            Some(TableMethod::CustomNoSelf(func)) => execute_func(&func, args, runtime),
+            None => match code_impl::find_field_method(&table, name)? {
+                Some(func) => {
+                    let args = args
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::once(Object::Table(table)))
+                        .collect::<SmallVec<[Object; 3]>>();
+                    code_impl::call(func.into(), &args, runtime)
+                }
+                None => match default {
+                    Some(default) => run_table_default_method(table, default, args),
+                    None => Err(RuntimeError::type_error(format!(
+                        "table has no method {}",
+                        name
+                    ))),
+                },
+            },
+        }
+    }
+}
+
+mod code_impl {
+    use super::*;
+
+    /// How many tables a lookup may go through by `__index` or `__newindex`, which keeps a cycle
+    /// of them from hanging the program.
+    const MAX_INDEX_CHAIN: usize = 100;
+
+    pub fn call_method(
+        self_obj: Object,
+        name: &str,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Object, RuntimeError> {
+        if let Some(method) = BuiltinMethod::from_name(name) {
+            return call_builtin_method(self_obj, method, args, runtime);
+        }
+        match self_obj {
+            Object::Int(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of int",
+                name
+            ))),
+            Object::Float(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of float",
+                name
+            ))),
+            Object::String(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of string",
+                name
+            ))),
+            Object::Bool(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of bool",
+                name
+            ))),
+            Object::Nil => Err(RuntimeError::type_error(format!(
+                "{} is not a method of nil",
+                name
+            ))),
+            Object::Array(_) => Err(RuntimeError::type_error(format!(
+                "array has no method {}",
+                name
+            ))),
+            Object::Table(table) => {
+                shared_proc::exec_table_method(table, name, None, args, runtime)
+            }
+            Object::Bytes(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of bytes",
+                name
+            ))),
+            Object::Error(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of error",
+                name
+            ))),
+            Object::Userdata(userdata) => shared_proc::exec_userdata_method(&userdata, name, args),
+            Object::Function(_)
+            | Object::BoundFunction(_)
+            | Object::MemoizedFunction(_)
+            | Object::RustFunction(_) => {
+                Err(RuntimeError::type_error("Function does not have methods."))?
+            }
+        }
+    }
+
+    pub fn call_builtin_method(
+        self_obj: Object,
+        method: BuiltinMethod,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Object, RuntimeError> {
+        match self_obj {
+            Object::Int(int) => run_int_method(int, method, args, runtime.int_overflow),
+            Object::Float(float) => run_float_method(float, method, args),
+            Object::String(string) => run_string_method(string, method, args),
+            Object::Bool(boolean) => run_bool_method(boolean, method, args),
+            Object::Nil => run_nil_method(method, args),
+            Object::Array(array) => run_array_method(array, method, args),
+            Object::Table(table) => {
+                shared_proc::exec_table_method(table, method.name(), Some(method), args, runtime)
+            }
+            Object::Bytes(bytes) => run_bytes_method(bytes, method, args),
+            Object::Error(_) => Err(RuntimeError::type_error(format!(
+                "{} is not a method of error",
+                method.name()
+            ))),
+            Object::Userdata(userdata) => {
+                shared_proc::exec_userdata_method(&userdata, method.name(), args)
+            }
+            Object::Function(_)
+            | Object::BoundFunction(_)
+            | Object::MemoizedFunction(_)
+            | Object::RustFunction(_) => {
+                Err(RuntimeError::type_error("Function does not have methods."))
+            }
+        }
+    }
+
+    /// A script function to call in a new frame, with its arguments (in reverse order).
+    pub struct Call {
+        pub func: Rc<FunctionObject>,
+        pub args: SmallVec<[Object; 4]>,
+        pub memoize: Option<Memoize>,
+    }
+
+    pub enum Resolved {
+        Enter(Call),
+        /// The callee was native, or its result was cached, so it has been called already.
+        Done(Object),
+    }
+
+    impl Resolved {
+        /// Runs the call on the native stack, where the result can't be left to a new frame.
+        pub fn finish(self, runtime: &mut Runtime) -> Result<Object, RuntimeError> {
+            match self {
+                Resolved::Done(result) => Ok(result),
+                Resolved::Enter(call) => {
+                    let result = shared_proc::execute_func(&call.func, &call.args, runtime)?;
+                    if let Some((memoized, key)) = call.memoize {
+                        memoized.insert(key, result.clone());
                     }
+                    Ok(result)
                 }
-                pc += 1;
-            }
-            BeginFuncCreation => {
-                let id = (pc, 0u8);
-                pc += 1;
-                let env = {
-                    let mut env = Vec::new();
-                    while let AddCapture(name) = code[pc] {
-                        let obj = runtime.variable_table.get_ref(name);
-                        env.push(obj);
-                        pc += 1;
-                    }
-                    env
-                };
-                let args = {
-                    let mut args = Vec::new();
-                    while let AddArgument(name) = code[pc] {
-                        args.push(name);
-                        pc += 1;
-                    }
-                    args
-                };
-                let code = {
-                    let mut func_code = Vec::new();
-                    let mut inner_count = 0;
-                    loop {
-                        if let BeginFuncCreation = code[pc] {
-                            inner_count += 1;
-                        } else if let EndFuncCreation = code[pc] {
-                            inner_count -= 1;
-                        }
-                        if inner_count < 0 {
-                            break;
-                        }
-                        func_code.push(code[pc].clone());
-                        pc += 1;
-                    }
-                    func_code
-                };
-                runtime.stack.push(
-                    Object::new_function(FunctionObject {
-                        id,
-                        env,
-                        args,
-                        code,
-                    })
-                    .into(),
-                );
-                pc += 1;
             }
-            AddCapture(_) => panic!("[BUG] AddCapture is not allowed here."),
-            AddArgument(_) => panic!("[BUG] AddArgument is not allowed here."),
-            EndFuncCreation => panic!("[BUG] EndFuncCreation is not allowed here."),
-            Nop => {
-                pc += 1;
+        }
+    }
+
+    /// Like [`call`], but leaves calls that end up in a script function to the caller, so that
+    /// they don't recurse on the native stack.
+    pub fn resolve_call(
+        callee: StackValue,
+        args: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Resolved, RuntimeError> {
+        match callee {
+            StackValue::Object(Object::Function(func)) => Ok(Resolved::Enter(Call {
+                func,
+                args: SmallVec::from(args),
+                memoize: None,
+            })),
+            StackValue::Object(Object::Table(table)) => {
+                let method = table.borrow().get_method("__call");
+                resolve_table_method(table, method, "__call", args, runtime)
             }
-            Return => {
-                return Ok(runtime.stack.pop().ensure_object());
+            StackValue::Object(Object::BoundFunction(bound)) => {
+                // `args` is in reverse order, so the bound arguments (the leading ones) come last.
+                let args = args
+                    .iter()
+                    .chain(bound.args.iter().rev())
+                    .cloned()
+                    .collect::<SmallVec<[Object; 4]>>();
+                resolve_call(bound.func.clone().into(), &args, runtime)
             }
-            Exit => {
-                return Ok(Object::Nil);
+            StackValue::Object(Object::MemoizedFunction(memoized)) => {
+                let key = MemoizedFunctionObject::make_key(args)?;
+                if let Some(cached) = memoized.get(&key) {
+                    return Ok(Resolved::Done(cached));
+                }
+                match resolve_call(memoized.func.clone().into(), args, runtime)? {
+                    Resolved::Enter(call) if call.memoize.is_none() => Ok(Resolved::Enter(Call {
+                        memoize: Some((memoized, key)),
+                        ..call
+                    })),
+                    // A memoized function wrapping another one is rare enough to run natively.
+                    resolved @ Resolved::Enter(_) => {
+                        let result = resolved.finish(runtime)?;
+                        memoized.insert(key, result.clone());
+                        Ok(Resolved::Done(result))
+                    }
+                    Resolved::Done(result) => {
+                        memoized.insert(key, result.clone());
+                        Ok(Resolved::Done(result))
+                    }
+                }
             }
+            callee => call(callee, args, runtime).map(Resolved::Done),
         }
     }
-}
 
-mod shared_proc {
-    use super::*;
+    /// The items of `array`, for `...array` in an array literal.
+    pub fn spread(array: Object) -> Result<Vec<Object>, RuntimeError> {
+        match array {
+            Object::Array(array) => Ok(array.borrow().to_vec()),
+            other => Err(RuntimeError::type_error(format!(
+                "Expected array to spread, got {}",
+                other.typename()
+            ))),
+        }
+    }
 
-    pub fn execute_func(
-        func: &FunctionObject,
+    /// The arguments of a call from the array of them, in the order a call takes them, which is
+    /// the last one first.
+    pub fn spread_args(args: Object) -> Result<Vec<Object>, RuntimeError> {
+        let mut args = spread(args)?;
+        args.reverse();
+        Ok(args)
+    }
+
+    /// Like [`call_method`], but leaves methods defined by a script on a table to the caller.
+    pub fn resolve_method_call(
+        self_obj: Object,
+        site: &MethodSite,
         args: &[Object],
         runtime: &mut Runtime,
-    ) -> Result<Object, String> {
-        runtime.variable_table.push_scope();
-        for value in func.env.iter() {
-            runtime.variable_table.push_ref(Rc::clone(value));
-        }
-        let args_len = func.args.len();
-        for (i, attr) in func.args.iter().enumerate() {
-            let value = args
-                .get(args_len - i - 1)
-                .map(|arg| match attr {
-                    ArgumentKind::Copy => arg.deep_clone(),
-                    ArgumentKind::Ref => todo!("ref argument"),
-                    ArgumentKind::Auto => arg.clone(),
-                })
-                .unwrap_or(Object::Nil);
-            runtime.variable_table.push(value);
-        }
-        let ret = execute(&func.code, runtime)?;
-        runtime.variable_table.pop_scope();
-        Ok(ret)
+    ) -> Result<Resolved, RuntimeError> {
+        let Object::Table(table) = self_obj else {
+            return call_method(self_obj, &site.name, args, runtime).map(Resolved::Done);
+        };
+        let method = site.lookup(&table.borrow(), &mut runtime.stats);
+        resolve_table_method(table, method, &site.name, args, runtime)
     }
 
-    pub fn exec_table_method(
+    fn resolve_table_method(
         table: Rc<RefCell<TableObject>>,
+        method: Option<TableMethod>,
         name: &str,
         args: &[Object],
         runtime: &mut Runtime,
-    ) -> Result<Object, String> {
-        let method = table.borrow().get_method(name);
+    ) -> Result<Resolved, RuntimeError> {
         match method {
-            Some(TableMethod::Builtin(func)) => func(table, args),
             Some(TableMethod::Custom(func)) => {
                 let args = args
                     .iter()
                     .cloned()
                     .chain(std::iter::once(Object::Table(table)))
-                    .collect::<SmallVec<[Object; 3]>>();
-                execute_func(&func, &args, runtime)
+                    .collect();
+                Ok(Resolved::Enter(Call {
+                    func,
+                    args,
+                    memoize: None,
+                }))
             }
-            Some(TableMethod::CustomNoSelf(func)) => execute_func(&func, args, runtime),
-            None => run_table_default_method(table, name, args),
+            Some(TableMethod::CustomNoSelf(func)) => Ok(Resolved::Enter(Call {
+                func,
+                args: SmallVec::from(args),
+                memoize: None,
+            })),
+            Some(TableMethod::Builtin(func)) => func(table, args).map(Resolved::Done),
+            None => match find_field_method(&table, name)? {
+                Some(func) => {
+                    let args = args
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::once(Object::Table(table)))
+                        .collect::<SmallVec<[Object; 3]>>();
+                    resolve_call(func.into(), &args, runtime)
+                }
+                None => call_method(Object::Table(table), name, args, runtime).map(Resolved::Done),
+            },
         }
     }
-}
-
-mod code_impl {
-    use super::*;
-    use std::borrow::Cow;
 
-    pub fn call_method(
-        self_obj: Object,
+    /// The field `name` of `table`, or of the first table up its `__index` chain that has it.
+    /// Unlike [`resolve_get_item`], a function in `__index` is not called.
+    pub fn find_field(
+        table: &Rc<RefCell<TableObject>>,
         name: &str,
-        args: &[Object],
-        runtime: &mut Runtime,
-    ) -> Result<Object, String> {
-        match self_obj {
-            Object::Int(int) => run_int_method(int, name, args),
-            Object::Float(float) => run_float_method(float, name, args),
-            Object::String(string) => run_string_method(string, name, args),
-            Object::Bool(boolean) => run_bool_method(boolean, name, args),
-            Object::Nil => run_nil_method(name, args),
-            Object::Array(array) => run_array_method(array, name, args),
-            Object::Table(table) => shared_proc::exec_table_method(table, name, args, runtime),
-            Object::Function(_) | Object::RustFunction(_) => {
-                Err("Function does not have methods.".to_string())?
+    ) -> Result<Option<Object>, RuntimeError> {
+        let mut table = Rc::clone(table);
+        for _ in 0..MAX_INDEX_CHAIN {
+            let index = {
+                let borrowed = table.borrow();
+                if let Some(value) = borrowed.get(name) {
+                    return Ok(Some(value.clone()));
+                }
+                borrowed.get("__index").cloned()
+            };
+            match index {
+                Some(Object::Table(next)) => table = next,
+                _ => return Ok(None),
             }
         }
+        Err("`__index` chain is too long".into())
+    }
+
+    /// Like [`find_field`], but only finds a function, which a table without the method `name`
+    /// calls as the method, e.g. one defined in a class.
+    pub fn find_field_method(
+        table: &Rc<RefCell<TableObject>>,
+        name: &str,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let field = find_field(table, name)?;
+        Ok(field.filter(|field| builtin::ensure_callable(field).is_ok()))
     }
 
     pub fn call(
         callee: StackValue,
         args: &[Object],
         runtime: &mut Runtime,
-    ) -> Result<Object, String> {
+    ) -> Result<Object, RuntimeError> {
         match callee {
             StackValue::Object(Object::Function(func)) => {
                 shared_proc::execute_func(&func, args, runtime)
             }
             StackValue::Object(Object::Table(table)) => {
-                shared_proc::exec_table_method(table, &Cow::from("__call"), args, runtime)
+                shared_proc::exec_table_method(table, "__call", None, args, runtime)
+            }
+            StackValue::Object(Object::BoundFunction(bound)) => {
+                // `args` is in reverse order, so the bound arguments (the leading ones) come last.
+                let args = args
+                    .iter()
+                    .chain(bound.args.iter().rev())
+                    .cloned()
+                    .collect::<SmallVec<[Object; 4]>>();
+                call(bound.func.clone().into(), &args, runtime)
+            }
+            StackValue::Object(Object::MemoizedFunction(memoized)) => {
+                let key = MemoizedFunctionObject::make_key(args)?;
+                if let Some(cached) = memoized.get(&key) {
+                    return Ok(cached);
+                }
+                let result = call(memoized.func.clone().into(), args, runtime)?;
+                memoized.insert(key, result.clone());
+                Ok(result)
             }
             StackValue::Object(Object::RustFunction(func)) => func(args),
-            x => Err(format!("Expected Callable Object, but got {:?}", x))?,
+            x => Err(RuntimeError::type_error(format!(
+                "Expected Callable Object, but got {:?}",
+                x
+            ))),
+        }
+    }
+
+    /// The counter, the end and the step of the numeric `for` whose locals start at `id`.
+    pub fn numeric_for(id: LocalId, runtime: &Runtime) -> Result<(i64, i64, i64), RuntimeError> {
+        let local = |offset: usize| {
+            let object = runtime.variable_table.get(LocalId(id.0 + offset));
+            match object {
+                Object::Int(int) => Ok(int),
+                object => Err(RuntimeError::type_error(format!(
+                    "Expected Int as the bounds and the step of a numeric for, but got {:?}",
+                    object
+                ))),
+            }
+        };
+        Ok((local(0)?, local(1)?, local(2)?))
+    }
+
+    /// Calls the iteration method `name` (`__iter` or `__next`) of `iterable`, if it is a table
+    /// that has one. Like `close` for `with`, the method may also be a function in a field, which
+    /// is called with the table.
+    pub fn resolve_iter_method(
+        iterable: &Object,
+        name: &str,
+        runtime: &mut Runtime,
+    ) -> Result<Option<Resolved>, RuntimeError> {
+        let Object::Table(table) = iterable else {
+            return Ok(None);
+        };
+        let method = table.borrow().get_method(name);
+        if method.is_some() {
+            return resolve_table_method(Rc::clone(table), method, name, &[], runtime).map(Some);
+        }
+        let func = table.borrow().get(name).cloned();
+        match func {
+            Some(func) => {
+                resolve_call(func.into(), &[Object::Table(Rc::clone(table))], runtime).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Applies `op` to `lhs` and `rhs`. An operator on a table may be overloaded by a function in
+    /// a field of it, like a metamethod in Lua: `__add`, `__sub`, `__mul`, `__div`, `__mod`,
+    /// `__pow`, `__idiv`, `__concat`, `__lt` and `__le` (called with the operands swapped for `>`
+    /// and `>=`), and `__eq` if both operands are tables, whose result `!=` negates.
+    pub fn resolve_binary(
+        op: BinaryOp,
+        lhs: Object,
+        rhs: Object,
+        runtime: &mut Runtime,
+    ) -> Result<Resolved, RuntimeError> {
+        let is_table = |obj: &Object| matches!(obj, Object::Table(_));
+        let overloadable = match op {
+            BinaryOp::Eq | BinaryOp::NotEq => is_table(&lhs) && is_table(&rhs),
+            _ => is_table(&lhs) || is_table(&rhs),
+        };
+        if overloadable {
+            let (name, swap) = match op {
+                BinaryOp::Add => ("__add", false),
+                BinaryOp::Sub => ("__sub", false),
+                BinaryOp::Mul => ("__mul", false),
+                BinaryOp::Div => ("__div", false),
+                BinaryOp::Mod => ("__mod", false),
+                BinaryOp::Pow => ("__pow", false),
+                BinaryOp::FloorDiv => ("__idiv", false),
+                BinaryOp::Eq | BinaryOp::NotEq => ("__eq", false),
+                BinaryOp::Less => ("__lt", false),
+                BinaryOp::LessEq => ("__le", false),
+                BinaryOp::Greater => ("__lt", true),
+                BinaryOp::GreaterEq => ("__le", true),
+                BinaryOp::Concat => ("__concat", false),
+            };
+            let operands = if swap {
+                [rhs.clone(), lhs.clone()]
+            } else {
+                [lhs.clone(), rhs.clone()]
+            };
+            if let Some(resolved) = resolve_metamethod(name, &operands, runtime)? {
+                if op == BinaryOp::NotEq {
+                    let equal = resolved.finish(runtime)?.ensure_bool()?;
+                    return Ok(Resolved::Done(Object::Bool(!equal)));
+                }
+                return Ok(resolved);
+            }
+        }
+        let ints = match (&lhs, &rhs) {
+            (Object::Int(lhs), Object::Int(rhs)) => Some((*lhs, *rhs)),
+            _ => None,
+        };
+        match (op.apply(lhs, rhs), ints) {
+            (Err(err), Some((lhs, rhs)))
+                if err.kind == ErrorKind::Overflow
+                    && runtime.int_overflow == IntOverflow::Float =>
+            {
+                op.apply(Object::Float(lhs as f64), Object::Float(rhs as f64))
+                    .map(Resolved::Done)
+            }
+            (res, _) => res.map(Resolved::Done),
+        }
+    }
+
+    /// Negates `obj`, or calls its `__unm` metamethod if it is a table (see [`resolve_binary`]).
+    pub fn resolve_unm(obj: Object, runtime: &mut Runtime) -> Result<Resolved, RuntimeError> {
+        if let Object::Table(_) = obj {
+            if let Some(resolved) =
+                resolve_metamethod("__unm", std::slice::from_ref(&obj), runtime)?
+            {
+                return Ok(resolved);
+            }
+        }
+        match obj {
+            Object::Int(x) if runtime.int_overflow == IntOverflow::Float => {
+                let res = x
+                    .checked_neg()
+                    .map_or(Object::Float(-(x as f64)), Object::Int);
+                Ok(Resolved::Done(res))
+            }
+            obj => unm(obj).map(Resolved::Done),
+        }
+    }
+
+    /// Calls the function in the field `name` of the first table among `operands` that has one,
+    /// also inherited through `__index`, with the operands.
+    fn resolve_metamethod(
+        name: &str,
+        operands: &[Object],
+        runtime: &mut Runtime,
+    ) -> Result<Option<Resolved>, RuntimeError> {
+        let mut func = None;
+        for operand in operands {
+            if let Object::Table(table) = operand {
+                func = find_field(table, name)?;
+                if func.is_some() {
+                    break;
+                }
+            }
+        }
+        let Some(func) = func else {
+            return Ok(None);
+        };
+        // `resolve_call` takes the arguments in reverse order.
+        let args = operands
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<SmallVec<[Object; 2]>>();
+        resolve_call(func.into(), &args, runtime).map(Some)
+    }
+
+    /// The next item of `iterator`, which iterates natively. Before the first item, the iterable
+    /// in the local `id` is replaced with where the iteration is.
+    pub fn next_item(
+        iterator: Object,
+        id: LocalId,
+        runtime: &mut Runtime,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let iterator = match iterator {
+            Object::Userdata(userdata) if userdata.name() == "iterator" => userdata,
+            // The values a coroutine yields, until it returns.
+            Object::Userdata(userdata) if userdata.name() == "coroutine" => {
+                let item = resume(&userdata, Object::Nil, runtime)?;
+                let state = userdata.borrow_mut::<builtin::Coroutine>()?;
+                return Ok((!matches!(*state, builtin::Coroutine::Dead)).then_some(item));
+            }
+            iterable => {
+                let iterator = builtin::Iteration::start(iterable)?;
+                runtime.variable_table.edit(id, iterator.clone());
+                let Object::Userdata(userdata) = iterator else {
+                    unreachable!("an iteration should be an `iterator` userdata")
+                };
+                userdata
+            }
+        };
+        let item = iterator.borrow_mut::<builtin::Iteration>()?.next();
+        item
+    }
+
+    /// The exports of the module `name`, running its file if that hasn't been done yet. A file
+    /// exports what it returns, or an empty table if it returns nothing.
+    pub fn import(name: &str, runtime: &mut Runtime) -> Result<Object, RuntimeError> {
+        let path = match runtime.modules.find(name, &*runtime.filesystem)? {
+            Found::Loaded(exports) => return Ok(exports),
+            Found::File(path) => path,
+        };
+        let Some(compile) = runtime.modules.compiler() else {
+            return Err(RuntimeError::new(
+                ErrorKind::Other,
+                format!("Cannot import {}: no compiler is set to load files", name),
+            ));
+        };
+        let display = path.display().to_string();
+        let source = read_file(&display, runtime)?;
+        let source = String::from_utf8(source).map_err(|e| RuntimeError::io(&display, e))?;
+        let module = compile(&display, &source).map_err(|message| {
+            RuntimeError::new(
+                ErrorKind::Other,
+                format!("Failed to compile module {}\n{}", display, message),
+            )
+        })?;
+        if !module.warnings.is_empty() {
+            runtime.stdio.write_err(&module.warnings);
+            runtime.stdio.flush_err();
+        }
+        let file = SourceFile {
+            path: display,
+            source,
+        };
+        let func = Modules::function(file, module);
+        runtime.modules.start(path);
+        let result = match shared_proc::execute_func(&func, &[], runtime) {
+            Ok(Object::Nil) => Ok(Object::new_table(TableObject::new(Default::default()))),
+            result => result,
+        };
+        runtime.modules.finish(result.as_ref().ok().cloned());
+        result
+    }
+
+    pub fn read_file(path: &str, runtime: &mut Runtime) -> Result<Vec<u8>, RuntimeError> {
+        runtime.permissions().check_read(path)?;
+        runtime
+            .filesystem
+            .read(path)
+            .map_err(|e| RuntimeError::io(path, e))
+    }
+
+    pub fn write_file(
+        path: &str,
+        content: &[u8],
+        runtime: &mut Runtime,
+    ) -> Result<(), RuntimeError> {
+        runtime.permissions().check_write(path)?;
+        runtime
+            .filesystem
+            .write(path, content)
+            .map_err(|e| RuntimeError::io(path, e))
+    }
+
+    /// Runs the handler registered by `on_interrupt` if the runtime has been interrupted, or
+    /// raises an error if there is none.
+    pub fn check_interrupt(runtime: &mut Runtime) -> Result<(), RuntimeError> {
+        if !runtime.interrupt.is_interrupted() {
+            return Ok(());
+        }
+        runtime.interrupt.reset();
+        match runtime.interrupt_handler.clone() {
+            Some(handler) => call(handler.into(), &[], runtime).map(|_| ()),
+            None => Err(RuntimeError::new(ErrorKind::Interrupted, "Interrupted")),
+        }
+    }
+
+    pub fn sleep(seconds: f64, interrupt: &InterruptHandle) -> Result<(), RuntimeError> {
+        use std::time::{Duration, Instant};
+
+        // Sleep in short steps so that an interrupt is noticed soon.
+        const STEP: Duration = Duration::from_millis(10);
+        let duration = Duration::try_from_secs_f64(seconds.max(0.0)).map_err(|e| e.to_string())?;
+        let deadline = Instant::now() + duration;
+        loop {
+            if interrupt.is_interrupted() {
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            std::thread::sleep(STEP.min(deadline - now));
+        }
+    }
+
+    pub fn make_log_record(
+        level: LogLevel,
+        message: Object,
+        fields: Object,
+    ) -> Result<LogRecord, RuntimeError> {
+        let fields = match fields {
+            Object::Nil => Vec::new(),
+            Object::Table(table) => {
+                let mut fields = table
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect::<Vec<_>>();
+                fields.sort();
+                fields
+            }
+            x => Err(format!(
+                "Expected `table` or `nil` as log fields, got `{}`",
+                x.typename()
+            ))?,
+        };
+        Ok(LogRecord {
+            level,
+            message: message.to_string(),
+            fields,
+        })
+    }
+
+    fn find_close_method(table: &TableObject) -> Option<&'static str> {
+        ["__close", "close"]
+            .into_iter()
+            .find(|name| table.get_method(name).is_some() || table.contains_key(*name))
+    }
+
+    pub fn ensure_closable(resource: &Object) -> Result<(), RuntimeError> {
+        match resource {
+            Object::Table(table) if find_close_method(&table.borrow()).is_some() => Ok(()),
+            Object::Userdata(userdata) if userdata.get_method("close").is_some() => Ok(()),
+            x => Err(RuntimeError::type_error(format!(
+                "`{}` can not be used in `with`, expected a `close` or `__close` method",
+                x.typename()
+            ))),
+        }
+    }
+
+    pub fn close_resource(resource: Object, runtime: &mut Runtime) -> Result<(), RuntimeError> {
+        ensure_closable(&resource)?;
+        let table = match resource {
+            Object::Table(table) => table,
+            Object::Userdata(userdata) => {
+                shared_proc::exec_userdata_method(&userdata, "close", &[])?;
+                return Ok(());
+            }
+            _ => unreachable!("[BUG] `ensure_closable` accepts only tables and userdata."),
+        };
+        let name = find_close_method(&table.borrow()).unwrap();
+        if table.borrow().get_method(name).is_some() {
+            shared_proc::exec_table_method(table, name, None, &[], runtime)?;
+        } else {
+            let func = table.borrow().get(name).cloned().unwrap_or(Object::Nil);
+            call(func.into(), &[Object::Table(table)], runtime)?;
         }
+        Ok(())
     }
 
-    pub fn set_item(target: StackValue, accesser: Object, value: Object) -> Result<(), String> {
-        // TODO: array bounds check
+    pub fn set_item(
+        target: StackValue,
+        accesser: Object,
+        value: Object,
+        runtime: &mut Runtime,
+    ) -> Result<(), RuntimeError> {
         match target {
             StackValue::RawArray(mut array) => {
-                let index = accesser.ensure_int()?;
-                array[index as usize] = value;
+                let index = store_index(accesser.ensure_int()?, array.len())?;
+                array[index] = value;
             }
             StackValue::Object(Object::Array(array)) => {
-                let index = accesser.ensure_int()?;
-                array.borrow_mut()[index as usize] = value;
+                let mut array = array.borrow_mut();
+                let index = store_index(accesser.ensure_int()?, array.len())?;
+                array[index] = value;
             }
             StackValue::Object(Object::Table(table)) => {
-                let index = accesser.ensure_string()?;
-                let mut table = table.borrow_mut();
-                if let Some(t) = table.get_mut(index.as_str()) {
-                    *t = value;
-                } else {
-                    let index = index.to_string();
-                    table.insert(index.into(), value);
-                }
+                set_table_item(table, accesser, value, runtime)?;
             }
             x => Err(format!("Expected Array or Table, but got {:?}", x))?,
         };
         Ok(())
     }
 
-    pub fn get_item(target: StackValue, accesser: Object) -> Result<Object, String> {
+    /// Sets a key missing from `table` through the `__newindex` field of the table, as in Lua: in
+    /// the table there, or by calling the function there with the table, the key and the value.
+    fn set_table_item(
+        mut table: Rc<RefCell<TableObject>>,
+        accesser: Object,
+        value: Object,
+        runtime: &mut Runtime,
+    ) -> Result<(), RuntimeError> {
+        for _ in 0..MAX_INDEX_CHAIN {
+            let newindex = {
+                let mut borrowed = table.borrow_mut();
+                let slot = match &accesser {
+                    Object::String(index) => borrowed.get_mut(index.as_str()),
+                    accesser => borrowed.get_mut(&TableKey::from_object(accesser.clone())?),
+                };
+                if let Some(slot) = slot {
+                    *slot = value;
+                    return Ok(());
+                }
+                borrowed.get("__newindex").cloned()
+            };
+            match newindex {
+                None => {
+                    let key = TableKey::from_object(accesser)?;
+                    table.borrow_mut().insert(key, value);
+                    return Ok(());
+                }
+                Some(Object::Table(next)) => table = next,
+                // `SetItem` has no result to wait for in a new frame.
+                Some(func) => {
+                    call(
+                        func.into(),
+                        &[value, accesser, Object::Table(table)],
+                        runtime,
+                    )?;
+                    return Ok(());
+                }
+            }
+        }
+        Err("`__newindex` chain is too long".into())
+    }
+
+    pub fn get_item(target: StackValue, accesser: Object) -> Result<Object, RuntimeError> {
         let res = match target {
             StackValue::RawArray(array) => {
-                let index = accesser.ensure_int()?;
-                match array.get(index as usize) {
+                let index = item_index(accesser.ensure_int()?, array.len());
+                match index.and_then(|index| array.get(index)) {
                     Some(x) => x.clone(),
                     None => Object::Nil,
                 }
             }
             StackValue::Object(Object::String(string)) => {
-                let string = string.get_chars();
-                let index = {
-                    let i = accesser.ensure_int()?;
-                    if i >= 0 {
-                        string.len() as i64 + i
-                    } else {
-                        i
-                    }
-                };
-                match string.get(index as usize) {
+                let index = item_index(accesser.ensure_int()?, string.char_len());
+                match index.and_then(|index| string.char_at(index)) {
                     Some(x) => Object::new_string(x.to_string()),
                     None => Object::Nil,
                 }
             }
             StackValue::Object(Object::Array(array)) => {
-                let index = accesser.ensure_int()?;
-                match array.borrow().get(index as usize) {
+                let array = array.borrow();
+                let index = item_index(accesser.ensure_int()?, array.len());
+                match index.and_then(|index| array.get(index)) {
                     Some(x) => x.clone(),
                     None => Object::Nil,
                 }
             }
+            StackValue::Object(Object::Bytes(bytes)) => {
+                let index = item_index(accesser.ensure_int()?, bytes.len());
+                match index.and_then(|index| bytes.get(index)) {
+                    Some(x) => Object::Int(*x as i64),
+                    None => Object::Nil,
+                }
+            }
             StackValue::Object(Object::Table(table)) => {
-                let index = accesser.ensure_string()?;
-                match table.borrow().get(index.as_str()) {
+                let table = table.borrow();
+                match get_table_item(&table, &accesser)? {
                     Some(x) => x.clone(),
                     None => Object::Nil,
                 }
             }
+            StackValue::Object(Object::Error(error)) => {
+                let field = accesser.ensure_string()?;
+                error.get(field.as_str()).unwrap_or(Object::Nil)
+            }
             x => Err(format!("Expected Array or Table, but got {:?}", x))?,
         };
         Ok(res)
     }
 
-    pub fn add(lhs: Object, rhs: Object) -> Result<Object, String> {
-        // TODO: overflow/underflow check
+    fn get_table_item<'a>(
+        table: &'a TableObject,
+        accesser: &Object,
+    ) -> Result<Option<&'a Object>, RuntimeError> {
+        match accesser {
+            Object::String(index) => Ok(table.get(index.as_str())),
+            accesser => Ok(table.get(&TableKey::from_object(accesser.clone())?)),
+        }
+    }
+
+    // The position of the item at `index`, where a negative one counts from the end.
+    fn item_index(index: i64, len: usize) -> Option<usize> {
+        let index = if index < 0 {
+            index.checked_add(len as i64)?
+        } else {
+            index
+        };
+        usize::try_from(index).ok()
+    }
+
+    // Like `item_index`, for storing an item, which must go in the array.
+    fn store_index(index: i64, len: usize) -> Result<usize, RuntimeError> {
+        match item_index(index, len) {
+            Some(position) if position < len => Ok(position),
+            _ => Err(RuntimeError::new(
+                ErrorKind::IndexOutOfRange,
+                format!(
+                    "Index {} is out of range for an array of length {}",
+                    index, len
+                ),
+            )),
+        }
+    }
+
+    // The range between the bounds of a slice, nil for the start or the end, clamped to `len`.
+    fn slice_range(
+        start: Object,
+        end: Object,
+        len: usize,
+    ) -> Result<std::ops::Range<usize>, RuntimeError> {
+        let bound = |bound: Object, default: usize| match bound {
+            Object::Nil => Ok(default),
+            Object::Int(index) if index < 0 => Ok(index.saturating_add(len as i64).max(0) as usize),
+            Object::Int(index) => Ok((index as u64).min(len as u64) as usize),
+            x => Err(RuntimeError::type_error(format!(
+                "Expected Int or Nil for a slice bound, but got {:?}",
+                x
+            ))),
+        };
+        let (start, end) = (bound(start, 0)?, bound(end, len)?);
+        Ok(start..end.max(start))
+    }
+
+    /// Whether `item` is an item of the array, a key of the table, or a substring of the string.
+    pub fn contains(container: Object, item: Object) -> Result<Object, RuntimeError> {
+        let res = match container {
+            Object::Array(array) => array.borrow().contains(&item),
+            Object::Table(table) => get_table_item(&table.borrow(), &item)?.is_some(),
+            Object::String(string) => match item {
+                Object::String(item) => string.as_str().contains(item.as_str()),
+                x => Err(RuntimeError::type_error(format!(
+                    "Expected String to search a string for, but got {:?}",
+                    x
+                )))?,
+            },
+            x => Err(RuntimeError::type_error(format!(
+                "Expected Array, Table or String on the right of `in`, but got {:?}",
+                x
+            )))?,
+        };
+        Ok(Object::Bool(res))
+    }
+
+    pub fn get_slice(target: Object, start: Object, end: Object) -> Result<Object, RuntimeError> {
+        let res = match target {
+            Object::Array(array) => {
+                let array = array.borrow();
+                let items = &array[slice_range(start, end, array.len())?];
+                Object::new_array(ArrayObject::new(items.to_vec()))
+            }
+            Object::String(string) => {
+                let range = slice_range(start, end, string.char_len())?;
+                Object::new_string(string.char_slice(range))
+            }
+            Object::Bytes(bytes) => {
+                let range = slice_range(start, end, bytes.len())?;
+                Object::new_bytes(bytes[range].to_vec())
+            }
+            x => Err(RuntimeError::type_error(format!(
+                "Expected Array, String or Bytes to slice, but got {:?}",
+                x
+            )))?,
+        };
+        Ok(res)
+    }
+
+    /// Like [`get_item`], but looks a key missing from a table up through the `__index` field of
+    /// the table, as in Lua: in the table there, or by calling the function there with the table
+    /// and the key.
+    pub fn resolve_get_item(
+        target: StackValue,
+        accesser: Object,
+        runtime: &mut Runtime,
+    ) -> Result<Resolved, RuntimeError> {
+        let StackValue::Object(Object::Table(mut table)) = target else {
+            return get_item(target, accesser).map(Resolved::Done);
+        };
+        for _ in 0..MAX_INDEX_CHAIN {
+            let index = {
+                let borrowed = table.borrow();
+                if let Some(value) = get_table_item(&borrowed, &accesser)? {
+                    return Ok(Resolved::Done(value.clone()));
+                }
+                borrowed.get("__index").cloned()
+            };
+            match index {
+                None => return Ok(Resolved::Done(Object::Nil)),
+                Some(Object::Table(next)) => table = next,
+                Some(func) => {
+                    return resolve_call(func.into(), &[accesser, Object::Table(table)], runtime)
+                }
+            }
+        }
+        Err("`__index` chain is too long".into())
+    }
+
+    pub fn add(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let res = match (lhs, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Object::Int(lhs + rhs),
+            (Object::Int(lhs), Object::Int(rhs)) => match lhs.checked_add(rhs) {
+                Some(res) => Object::Int(res),
+                None => Err(overflow(lhs, "+", rhs))?,
+            },
             (Object::Int(lhs), Object::Float(rhs)) => Object::Float(lhs as f64 + rhs),
             (Object::Float(lhs), Object::Int(rhs)) => Object::Float(lhs + rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => Object::Float(lhs + rhs),
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(res)
     }
 
-    pub fn sub(lhs: Object, rhs: Object) -> Result<Object, String> {
-        // TODO: underflow/overflow check
+    pub fn sub(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let res = match (lhs, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Object::Int(lhs - rhs),
+            (Object::Int(lhs), Object::Int(rhs)) => match lhs.checked_sub(rhs) {
+                Some(res) => Object::Int(res),
+                None => Err(overflow(lhs, "-", rhs))?,
+            },
             (Object::Int(lhs), Object::Float(rhs)) => Object::Float(lhs as f64 - rhs),
             (Object::Float(lhs), Object::Int(rhs)) => Object::Float(lhs - rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => Object::Float(lhs - rhs),
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(res)
     }
 
-    pub fn mul(lhs: Object, rhs: Object) -> Result<Object, String> {
-        // TODO: overflow check
+    pub fn mul(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let res = match (lhs, rhs) {
-            (Object::Int(lhs), Object::Int(rhs)) => Object::Int(lhs * rhs),
+            (Object::Int(lhs), Object::Int(rhs)) => match lhs.checked_mul(rhs) {
+                Some(res) => Object::Int(res),
+                None => Err(overflow(lhs, "*", rhs))?,
+            },
             (Object::Int(lhs), Object::Float(rhs)) => Object::Float(lhs as f64 * rhs),
             (Object::Float(lhs), Object::Int(rhs)) => Object::Float(lhs * rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => Object::Float(lhs * rhs),
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(res)
     }
 
-    pub fn div(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn div(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => {
                 if rhs == 0 {
-                    Err("Divided by zero.".to_string())?
+                    Err(RuntimeError::new(
+                        ErrorKind::DivisionByZero,
+                        "Divided by zero.",
+                    ))?
+                }
+                match lhs.checked_div(rhs) {
+                    Some(res) => Ok(Object::Int(res)),
+                    None => Err(overflow(lhs, "/", rhs)),
                 }
-                Ok(Object::Int(lhs / rhs))
             }
             (Object::Int(lhs), Object::Float(rhs)) => Ok(Object::Float(lhs as f64 / rhs)),
             (Object::Float(lhs), Object::Int(rhs)) => Ok(Object::Float(lhs / rhs as f64)),
             (Object::Float(lhs), Object::Float(rhs)) => Ok(Object::Float(lhs / rhs)),
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
+        }
+    }
+
+    /// Divides and rounds down, unlike `/`, which rounds Ints toward zero.
+    pub fn floor_div(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
+        match (lhs, rhs) {
+            (Object::Int(lhs), Object::Int(rhs)) => {
+                if rhs == 0 {
+                    Err(RuntimeError::new(
+                        ErrorKind::DivisionByZero,
+                        "Divided by zero.",
+                    ))?
+                }
+                let Some(quotient) = lhs.checked_div(rhs) else {
+                    return Err(overflow(lhs, "//", rhs));
+                };
+                if lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+                    Ok(Object::Int(quotient - 1))
+                } else {
+                    Ok(Object::Int(quotient))
+                }
+            }
+            (Object::Int(lhs), Object::Float(rhs)) => Ok(Object::Float((lhs as f64 / rhs).floor())),
+            (Object::Float(lhs), Object::Int(rhs)) => Ok(Object::Float((lhs / rhs as f64).floor())),
+            (Object::Float(lhs), Object::Float(rhs)) => Ok(Object::Float((lhs / rhs).floor())),
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
+                "Expected Int or Float, but got {:?} and {:?}",
+                lhs, rhs
+            )))?,
+        }
+    }
+
+    /// Raises `lhs` to the power of `rhs`. An Int to a negative Int power is a Float, e.g.
+    /// `2 ** -1` is 0.5.
+    pub fn pow(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
+        let res = match (lhs, rhs) {
+            (Object::Int(lhs), Object::Int(rhs)) if rhs < 0 => {
+                Object::Float((lhs as f64).powf(rhs as f64))
+            }
+            (Object::Int(lhs), Object::Int(rhs)) => match int_pow(lhs, rhs) {
+                Some(res) => Object::Int(res),
+                None => Err(overflow(lhs, "**", rhs))?,
+            },
+            (Object::Int(lhs), Object::Float(rhs)) => Object::Float((lhs as f64).powf(rhs)),
+            (Object::Float(lhs), Object::Int(rhs)) => match i32::try_from(rhs) {
+                Ok(rhs) => Object::Float(lhs.powi(rhs)),
+                Err(_) => Object::Float(lhs.powf(rhs as f64)),
+            },
+            (Object::Float(lhs), Object::Float(rhs)) => Object::Float(lhs.powf(rhs)),
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
+                "Expected Int or Float, but got {:?} and {:?}",
+                lhs, rhs
+            )))?,
+        };
+        Ok(res)
+    }
+
+    /// `base` to the power of `exp`, which is not negative, or `None` on overflow.
+    fn int_pow(base: i64, exp: i64) -> Option<i64> {
+        match u32::try_from(exp) {
+            Ok(exp) => base.checked_pow(exp),
+            // Only these bases don't overflow with such a large exponent.
+            Err(_) => match base {
+                0 | 1 => Some(base),
+                -1 => Some(if exp % 2 == 0 { 1 } else { -1 }),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn shift_left(lhs: i64, rhs: i64) -> Result<Object, RuntimeError> {
+        match u32::try_from(rhs) {
+            Ok(bits @ 0..64) => Ok(Object::Int(lhs << bits)),
+            _ => Err(shift_out_of_range(rhs)),
+        }
+    }
+
+    /// Shifts arithmetically, keeping the sign of `lhs`.
+    pub fn shift_right(lhs: i64, rhs: i64) -> Result<Object, RuntimeError> {
+        match u32::try_from(rhs) {
+            Ok(bits @ 0..64) => Ok(Object::Int(lhs >> bits)),
+            _ => Err(shift_out_of_range(rhs)),
         }
     }
 
-    pub fn r#mod(lhs: Object, rhs: Object) -> Result<Object, String> {
+    fn overflow(lhs: i64, op: &str, rhs: i64) -> RuntimeError {
+        RuntimeError::new(
+            ErrorKind::Overflow,
+            format!("Integer overflow: {} {} {}", lhs, op, rhs),
+        )
+    }
+
+    fn shift_out_of_range(bits: i64) -> RuntimeError {
+        RuntimeError::new(
+            ErrorKind::Other,
+            format!("Expected a shift amount in 0..64, but got {}", bits),
+        )
+    }
+
+    pub fn r#mod(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let res = match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => {
                 if rhs == 0 {
-                    Err("Divided by zero.".to_string())?
+                    Err(RuntimeError::new(
+                        ErrorKind::DivisionByZero,
+                        "Divided by zero.",
+                    ))?
+                }
+                match lhs.checked_rem(rhs) {
+                    Some(res) => Object::Int(res),
+                    None => Err(overflow(lhs, "%", rhs))?,
                 }
-                Object::Int(lhs % rhs)
             }
             (Object::Int(lhs), Object::Float(rhs)) => Object::Float(lhs as f64 % rhs),
             (Object::Float(lhs), Object::Int(rhs)) => Object::Float(lhs % rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => Object::Float(lhs % rhs),
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(res)
     }
 
-    pub fn unm(obj: Object) -> Result<Object, String> {
-        // TODO: underflow/overflow check
+    pub fn unm(obj: Object) -> Result<Object, RuntimeError> {
         let res = match obj {
-            Object::Int(x) => Object::Int(-x),
+            Object::Int(x) => match x.checked_neg() {
+                Some(res) => Object::Int(res),
+                None => Err(RuntimeError::new(
+                    ErrorKind::Overflow,
+                    format!("Integer overflow: -({})", x),
+                ))?,
+            },
             Object::Float(x) => Object::Float(-x),
             x => Err(format!("Expected Int or Float, but got {:?}", x))?,
         };
         Ok(res)
     }
 
-    pub fn less(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn less(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let boolean = match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => lhs < rhs,
             (Object::Int(lhs), Object::Float(rhs)) => (lhs as f64) < rhs,
             (Object::Float(lhs), Object::Int(rhs)) => lhs < (rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => lhs < rhs,
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(Object::Bool(boolean))
     }
 
-    pub fn less_eq(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn less_eq(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let boolean = match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => lhs <= rhs,
             (Object::Int(lhs), Object::Float(rhs)) => (lhs as f64) <= rhs,
             (Object::Float(lhs), Object::Int(rhs)) => lhs <= (rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => lhs <= rhs,
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(Object::Bool(boolean))
     }
 
-    pub fn greater(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn greater(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let boolean = match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => lhs > rhs,
             (Object::Int(lhs), Object::Float(rhs)) => (lhs as f64) > rhs,
             (Object::Float(lhs), Object::Int(rhs)) => lhs > (rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => lhs > rhs,
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(Object::Bool(boolean))
     }
 
-    pub fn greater_eq(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn greater_eq(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         let boolean = match (lhs, rhs) {
             (Object::Int(lhs), Object::Int(rhs)) => lhs >= rhs,
             (Object::Int(lhs), Object::Float(rhs)) => (lhs as f64) >= rhs,
             (Object::Float(lhs), Object::Int(rhs)) => lhs >= (rhs as f64),
             (Object::Float(lhs), Object::Float(rhs)) => lhs >= rhs,
-            (lhs, rhs) => Err(format!(
+            (lhs, rhs) => Err(RuntimeError::type_error(format!(
                 "Expected Int or Float, but got {:?} and {:?}",
                 lhs, rhs
-            ))?,
+            )))?,
         };
         Ok(Object::Bool(boolean))
     }
 
-    pub fn concat(lhs: Object, rhs: Object) -> Result<Object, String> {
+    pub fn concat(lhs: Object, rhs: Object) -> Result<Object, RuntimeError> {
         // TODO: Improve performance when lhs or rhs is Object::String.
-        fn to_string(obj: Object) -> Result<String, String> {
+        fn to_string(obj: Object) -> Result<String, RuntimeError> {
             match obj {
                 Object::Int(x) => Ok(x.to_string()),
                 Object::Float(x) => Ok(x.to_string()),
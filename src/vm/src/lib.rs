@@ -1,9 +1,43 @@
+use foundation::TextSpan;
+
 mod execute;
 
+mod error;
+pub use error::{ErrorKind, RuntimeError, TraceFrame};
+
 pub mod code;
 use code::*;
 
 pub mod runtime;
 use runtime::*;
 
-pub use execute::execute;
+pub mod builtin;
+
+pub mod bytecode;
+
+#[cfg(feature = "plugin")]
+pub mod plugin;
+
+mod utf8;
+
+mod verify;
+pub use verify::{verify, verify_with_locals, VerifyError, VerifyErrorKind};
+
+/// Runs `code` on `runtime`, after checking it with [`verify`] so that malformed code fails with
+/// [`ErrorKind::InvalidCode`] instead of panicking.
+pub fn execute(code: &[Code], runtime: &mut Runtime) -> Result<Object, RuntimeError> {
+    execute_with_spans(code, &[], runtime)
+}
+
+/// Like [`execute`], for `code` whose instructions came from `spans` in the source, by index, so
+/// that the tracebacks of errors say where they were.
+pub fn execute_with_spans(
+    code: &[Code],
+    spans: &[Option<TextSpan>],
+    runtime: &mut Runtime,
+) -> Result<Object, RuntimeError> {
+    verify_with_locals(code, runtime.variable_table.scope_len()).map_err(|err| {
+        RuntimeError::new(ErrorKind::InvalidCode, format!("Invalid code: {}", err))
+    })?;
+    execute::execute(code, spans, runtime)
+}
@@ -0,0 +1,110 @@
+//! Native plugins: dynamic libraries that add modules implemented in Rust.
+//!
+//! A plugin is a `cdylib` depending on this crate that exports the register function:
+//!
+//! ```ignore
+//! use vm::{plugin::Registry, runtime::Object};
+//!
+//! #[no_mangle]
+//! pub fn lic_plugin_register(registry: &mut Registry) {
+//!     registry
+//!         .module("greet")
+//!         .function("hello", |_args| Ok(Object::new_string("hello".to_string())));
+//! }
+//! ```
+//!
+//! Scripts load it with `plugin.load(path)`, which returns a table of the registered modules,
+//! e.g. `plugin.load("./libgreet.so").greet.hello()`. The register function is called through the
+//! Rust ABI, so a plugin must be built with the same compiler and the same version of this crate.
+
+use super::*;
+use hashbrown::HashMap;
+
+/// The name of the function every plugin exports.
+pub const REGISTER_SYMBOL: &str = "lic_plugin_register";
+
+/// The signature of [`REGISTER_SYMBOL`].
+pub type RegisterFn = fn(&mut Registry);
+
+/// A function a plugin provides, called with its arguments in reverse order.
+pub type PluginFunction = fn(&[Object]) -> Result<Object, RuntimeError>;
+
+/// The modules a plugin registers.
+#[derive(Debug, Default)]
+pub struct Registry {
+    modules: Vec<Module>,
+}
+
+#[derive(Debug)]
+pub struct Module {
+    name: String,
+    functions: Vec<(String, PluginFunction)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the module `name`, adding it if it isn't registered yet.
+    pub fn module(&mut self, name: &str) -> &mut Module {
+        let index = match self.modules.iter().position(|module| module.name == name) {
+            Some(index) => index,
+            None => {
+                self.modules.push(Module {
+                    name: name.to_string(),
+                    functions: Vec::new(),
+                });
+                self.modules.len() - 1
+            }
+        };
+        &mut self.modules[index]
+    }
+
+    /// Makes the table `{ <module> = { <function> = <rust_function>, .. }, .. }`.
+    pub fn into_object(self) -> Object {
+        let modules = self.modules.into_iter().map(|module| {
+            let functions = module
+                .functions
+                .into_iter()
+                .map(|(name, func)| (name.into(), Object::RustFunction(func)));
+            let table = TableObject::new(HashMap::from_iter(functions));
+            (module.name.into(), Object::new_table(table))
+        });
+        Object::new_table(TableObject::new(HashMap::from_iter(modules)))
+    }
+}
+
+impl Module {
+    pub fn function(&mut self, name: &str, func: PluginFunction) -> &mut Self {
+        self.functions.push((name.to_string(), func));
+        self
+    }
+}
+
+/// Loads the plugin at `path` and calls its register function.
+///
+/// The library is never unloaded, because the functions it registered may be referenced anywhere.
+pub fn load(path: &str) -> Result<Registry, RuntimeError> {
+    // SAFETY: Loading a library runs its initializers, and calling the register function trusts
+    // that it has the signature of `RegisterFn`. Both are what loading a plugin means.
+    unsafe {
+        let library = libloading::Library::new(path).map_err(|e| e.to_string())?;
+        let register = *library
+            .get::<RegisterFn>(REGISTER_SYMBOL.as_bytes())
+            .map_err(|e| RuntimeError::io(path, e))?;
+        let mut registry = Registry::new();
+        register(&mut registry);
+        std::mem::forget(library);
+        Ok(registry)
+    }
+}
+
+// plugin.load(path: String) -> Table
+pub fn plugin_load(args: &[Object]) -> Result<Object, RuntimeError> {
+    let path = extract_argument!(args, [String]);
+    let permissions = Permissions::current();
+    permissions.check_native_code(path.as_str())?;
+    permissions.check_read(path.as_str())?;
+    Ok(load(path.as_str())?.into_object())
+}
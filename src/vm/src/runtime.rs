@@ -16,27 +16,191 @@ pub use global::Global;
 mod stdio;
 pub use stdio::Stdio;
 
-#[derive(Debug, Default)]
+mod filesystem;
+pub use filesystem::*;
+
+mod logger;
+pub use logger::*;
+
+mod stats;
+pub use stats::Stats;
+
+mod interrupt;
+pub use interrupt::InterruptHandle;
+
+mod permissions;
+pub use permissions::{FileAccess, Permissions};
+
+mod module;
+pub(crate) use module::Found;
+pub use module::{CompiledModule, ModuleCompiler, Modules};
+
+#[derive(Debug)]
 pub struct Runtime {
     pub stack: Stack,
     pub variable_table: VariableTable,
     pub global: Global,
     pub stdio: Stdio,
+    pub filesystem: Rc<dyn FileSystem>,
+    pub logger: Logger,
+    pub interrupt: InterruptHandle,
+    /// Called instead of raising an error when the script is interrupted, set by `on_interrupt`.
+    pub interrupt_handler: Option<Object>,
+    pub resources: Vec<Object>,
+    /// The `try` blocks being run, innermost last.
+    pub(crate) handlers: Vec<Handler>,
+    /// The modules `import` loads, and where it searches for them.
+    pub modules: Modules,
+    /// The arguments passed to the script, exposed as `args`.
+    pub args: Vec<String>,
+    /// Set when the script calls `exit(code)`.
+    pub exit_code: Option<i32>,
+    /// The functions being run, innermost last, so that errors can tell where they were raised.
+    pub call_stack: Vec<Rc<FunctionPrototype>>,
+    /// How deep calls may nest before the script fails with a stack overflow error.
+    pub max_call_depth: usize,
+    /// What integer arithmetic does when the result doesn't fit in an Int.
+    pub int_overflow: IntOverflow,
+    /// How many `execute` calls may be nested. Script functions calling each other share one
+    /// `execute`, but calls made from native code (e.g. a `__close` method, or a memoized
+    /// function wrapping another one) start a new one on the native stack, which this keeps from
+    /// overflowing. The default fits a 2 MiB thread stack in debug builds; a host running the VM
+    /// on a larger stack may raise it.
+    pub max_native_depth: usize,
+    /// The number of nested `execute` calls on the native stack.
+    pub(crate) native_depth: usize,
+    /// Counters of what the VM did, e.g. to measure the method caches.
+    pub stats: Stats,
+    /// The number of instructions left to run, or `None` for no limit.
+    fuel: Option<u64>,
+    permissions: Rc<Permissions>,
+}
+
+/// What `+`, `-`, `*`, `/`, `%` and negation do when the result for Int operands is out of range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntOverflow {
+    /// Fail with [`ErrorKind::Overflow`].
+    #[default]
+    Error,
+    /// Compute the result as a Float instead.
+    Float,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Runtime {
+    pub const DEFAULT_MAX_CALL_DEPTH: usize = 100_000;
+    pub const DEFAULT_MAX_NATIVE_DEPTH: usize = 24;
+
     pub fn new() -> Self {
         Self {
-            stack: Stack::new(),
+            stack: Stack::with_capacity(Stack::RETAINED_CAPACITY),
             variable_table: VariableTable::new(),
             global: Global::new(),
             stdio: Stdio::new(),
+            filesystem: Rc::new(StdFileSystem),
+            logger: Logger::new(),
+            interrupt: InterruptHandle::new(),
+            interrupt_handler: None,
+            resources: Vec::new(),
+            handlers: Vec::new(),
+            modules: Modules::new(),
+            args: Vec::new(),
+            exit_code: None,
+            call_stack: Vec::new(),
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
+            int_overflow: IntOverflow::default(),
+            max_native_depth: Self::DEFAULT_MAX_NATIVE_DEPTH,
+            native_depth: 0,
+            stats: Stats::default(),
+            fuel: None,
+            permissions: Rc::default(),
         }
     }
 
+    /// Makes a runtime that runs at most `fuel` instructions, then fails with
+    /// [`ErrorKind::FuelExhausted`], e.g. to stop untrusted scripts that loop forever.
+    pub fn with_fuel(fuel: u64) -> Self {
+        Self {
+            fuel: Some(fuel),
+            ..Self::new()
+        }
+    }
+
+    /// The number of instructions left to run, or `None` if there is no limit.
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Sets the number of instructions left to run, e.g. to refuel between evaluations.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Takes the fuel for one instruction.
+    #[inline]
+    pub(crate) fn consume_fuel(&mut self) -> Result<(), RuntimeError> {
+        match &mut self.fuel {
+            None => Ok(()),
+            Some(0) => Err(RuntimeError::new(
+                ErrorKind::FuelExhausted,
+                "Fuel exhausted",
+            )),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// What the script may do beyond computing, e.g. the files it may touch or whether it may
+    /// sleep.
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Restricts (or widens) what the script may do, e.g. with [`Permissions::no_files`] to keep
+    /// an untrusted script off the filesystem.
+    pub fn set_permissions(&mut self, permissions: Permissions) {
+        self.permissions = Rc::new(permissions);
+    }
+
+    /// Makes the permissions of this runtime visible to the builtins until the guard is dropped.
+    pub(crate) fn enter_permissions(&self) -> permissions::PermissionsGuard {
+        Permissions::enter(Rc::clone(&self.permissions))
+    }
+
+    /// Makes the filesystem of this runtime visible to the builtins until the guard is dropped.
+    pub(crate) fn enter_filesystem(&self) -> filesystem::FileSystemGuard {
+        filesystem::enter_filesystem(Rc::clone(&self.filesystem))
+    }
+
+    /// Releases memory that grew beyond what recent execution needed.
+    pub fn shrink(&mut self) {
+        self.stack.shrink();
+        self.variable_table.shrink();
+    }
+
     pub fn dump(&self) {
         println!("[Runtime]");
         self.stack.dump(2);
         self.variable_table.dump(2);
     }
 }
+
+/// A `try` block being run: where its `catch` block is, and what to restore before running it.
+#[derive(Clone, Debug)]
+pub(crate) struct Handler {
+    /// The length of `Runtime::call_stack` in the function of the block.
+    pub depth: usize,
+    /// Where the `catch` block starts in the code of the function.
+    pub pc: usize,
+    pub stack_len: usize,
+    /// The number of locals in the scope of the function.
+    pub locals: usize,
+    pub resources: usize,
+}
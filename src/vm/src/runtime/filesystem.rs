@@ -0,0 +1,408 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+/// Where the builtins that touch files, such as `fs.read`, `fs.open`, `fs.walk`, `env.load` and
+/// `import`, read and write them.
+///
+/// The default, [`StdFileSystem`], is the real filesystem. Tests and hosts without one (e.g. WASM)
+/// can use [`MemoryFileSystem`] or their own implementation. Only `read` and `write` are required:
+/// the other methods fail with [`io::ErrorKind::Unsupported`] unless implemented, and so do the
+/// builtins that need them.
+pub trait FileSystem: fmt::Debug {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &str, content: &[u8]) -> io::Result<()>;
+
+    /// Opens the file at `path` to read or write it in place, e.g. for `fs.open`.
+    fn open(&self, path: &str, options: &OpenOptions) -> io::Result<Box<dyn OpenFile>> {
+        let _ = (path, options);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// The names of the entries of the directory at `path`, in any order.
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// What is at `path`. A symbolic link is not followed, so that walking the directories can't
+    /// loop forever.
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// The path that `path` and any other path to the same file resolve to, e.g. to import a
+    /// module only once.
+    fn canonicalize(&self, path: &str) -> io::Result<PathBuf> {
+        self.metadata(path).map(|_| PathBuf::from(path))
+    }
+
+    /// Moves the file at `from` to `to`, replacing what is there.
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let _ = (from, to);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn remove_file(&self, path: &str) -> io::Result<()> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Takes an exclusive lock on the file at `path`, creating it if needed, and waits while
+    /// someone else holds it.
+    fn lock(&self, path: &str) -> io::Result<Box<dyn FileLock>> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// How [`FileSystem::open`] opens a file, as [`std::fs::OpenOptions`] does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    pub read: bool,
+    pub write: bool,
+    /// Every write goes to the end of the file.
+    pub append: bool,
+    pub create: bool,
+    pub truncate: bool,
+}
+
+/// A file opened by [`FileSystem::open`].
+pub trait OpenFile: Read + Write + Seek {
+    /// Makes sure that what was written is stored, e.g. on the disk, before the file is renamed.
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+/// What [`FileSystem::metadata`] tells about a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    /// The size of a file in bytes.
+    pub len: u64,
+}
+
+/// A lock taken by [`FileSystem::lock`], released by `unlock` or when dropped.
+pub trait FileLock {
+    fn unlock(self: Box<Self>) -> io::Result<()>;
+}
+
+thread_local! {
+    // The filesystem of the runtime being executed on this thread, for the builtins that are
+    // plain Rust functions and can't see the runtime.
+    static CURRENT: RefCell<Rc<dyn FileSystem>> = RefCell::new(Rc::new(StdFileSystem));
+}
+
+/// The filesystem of the runtime being executed.
+pub fn current_filesystem() -> Rc<dyn FileSystem> {
+    CURRENT.with(|current| Rc::clone(&current.borrow()))
+}
+
+/// Makes `filesystem` the current one until the guard is dropped.
+pub(crate) fn enter_filesystem(filesystem: Rc<dyn FileSystem>) -> FileSystemGuard {
+    FileSystemGuard(CURRENT.with(|current| current.replace(filesystem)))
+}
+
+/// Restores what [`enter_filesystem`] replaced.
+pub(crate) struct FileSystemGuard(Rc<dyn FileSystem>);
+
+impl Drop for FileSystemGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.replace(Rc::clone(&self.0)));
+    }
+}
+
+/// The filesystem of the process, through `std::fs`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn write(&self, path: &str, content: &[u8]) -> io::Result<()> {
+        std::fs::write(path, content)
+    }
+
+    fn open(&self, path: &str, options: &OpenOptions) -> io::Result<Box<dyn OpenFile>> {
+        let file = std::fs::OpenOptions::new()
+            .read(options.read)
+            .write(options.write)
+            .append(options.append)
+            .create(options.create)
+            .truncate(options.truncate)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+        })
+    }
+
+    fn canonicalize(&self, path: &str) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &str) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn lock(&self, path: &str) -> io::Result<Box<dyn FileLock>> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.lock()?;
+        Ok(Box::new(file))
+    }
+}
+
+impl OpenFile for std::fs::File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+impl FileLock for std::fs::File {
+    fn unlock(self: Box<Self>) -> io::Result<()> {
+        std::fs::File::unlock(&self)
+    }
+}
+
+/// Files kept in memory by their path as written in the script, without `.` segments, so that
+/// `./a.txt` and `a.txt` are the same file. Directories are not stored: a path is a directory if
+/// some file is under it, and `.` or an empty path is the one all relative paths are under.
+///
+/// Clones share the same files, so that the embedder can keep one to fill it before the script
+/// runs and to look at what the script wrote after.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFileSystem {
+    files: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+    locked: Rc<RefCell<HashSet<String>>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path: impl Into<String>, content: impl Into<Vec<u8>>) {
+        let path = Self::key(&path.into());
+        self.files.borrow_mut().insert(path, content.into());
+    }
+
+    pub fn get(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.borrow().get(&Self::key(path)).cloned()
+    }
+
+    pub fn remove(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.borrow_mut().remove(&Self::key(path))
+    }
+
+    // The path the file at `path` is stored by.
+    fn key(path: &str) -> String {
+        let absolute = path.starts_with('/');
+        let segments = (path.split('/'))
+            .filter(|segment| !matches!(*segment, "" | "."))
+            .collect::<Vec<_>>();
+        match absolute {
+            true => format!("/{}", segments.join("/")),
+            false => segments.join("/"),
+        }
+    }
+
+    // The prefix of the paths of the files under the directory `path`.
+    fn dir_prefix(path: &str) -> String {
+        match Self::key(path) {
+            dir if dir.is_empty() || dir.ends_with('/') => dir,
+            dir => format!("{}/", dir),
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        self.get(path)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn write(&self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.insert(path, content);
+        Ok(())
+    }
+
+    fn open(&self, path: &str, options: &OpenOptions) -> io::Result<Box<dyn OpenFile>> {
+        let content = match self.get(path) {
+            Some(_) if options.truncate => Vec::new(),
+            Some(content) => content,
+            None if options.create => Vec::new(),
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+        let writable = options.write || options.append;
+        if writable {
+            self.insert(path, content.clone());
+        }
+        Ok(Box::new(MemoryFile {
+            files: Rc::clone(&self.files),
+            path: Self::key(path),
+            content: Cursor::new(content),
+            writable,
+            append: options.append,
+        }))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let prefix = Self::dir_prefix(path);
+        let mut names = (self.files.borrow().keys())
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .filter(|rest| !rest.starts_with('/'))
+            .map(|rest| rest.split('/').next().unwrap_or(rest).to_string())
+            .collect::<Vec<_>>();
+        if names.is_empty() && !prefix.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        if let Some(content) = self.files.borrow().get(&Self::key(path)) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: content.len() as u64,
+            });
+        }
+        let prefix = Self::dir_prefix(path);
+        match prefix.is_empty()
+            || self
+                .files
+                .borrow()
+                .keys()
+                .any(|file| file.starts_with(&prefix))
+        {
+            true => Ok(Metadata {
+                is_dir: true,
+                len: 0,
+            }),
+            false => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let content = self
+            .remove(from)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        self.insert(to, content);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &str) -> io::Result<()> {
+        self.remove(path)
+            .map(|_| ())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    // Nothing else shares the files to wait for, so taking a lock held already would wait forever.
+    fn lock(&self, path: &str) -> io::Result<Box<dyn FileLock>> {
+        let path = Self::key(path);
+        if !self.locked.borrow_mut().insert(path.clone()) {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the file is locked already",
+            ));
+        }
+        self.files.borrow_mut().entry(path.clone()).or_default();
+        Ok(Box::new(MemoryLock {
+            locked: Rc::clone(&self.locked),
+            path,
+        }))
+    }
+}
+
+// A file of a `MemoryFileSystem` being read or written, whose content is stored back on every
+// write.
+struct MemoryFile {
+    files: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+    path: String,
+    content: Cursor<Vec<u8>>,
+    writable: bool,
+    append: bool,
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.content.read(buf)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the file is not open for writing",
+            ));
+        }
+        if self.append {
+            self.content.seek(SeekFrom::End(0))?;
+        }
+        let len = self.content.write(buf)?;
+        let content = self.content.get_ref().clone();
+        self.files.borrow_mut().insert(self.path.clone(), content);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.content.seek(pos)
+    }
+}
+
+impl OpenFile for MemoryFile {}
+
+struct MemoryLock {
+    locked: Rc<RefCell<HashSet<String>>>,
+    path: String,
+}
+
+impl FileLock for MemoryLock {
+    fn unlock(self: Box<Self>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MemoryLock {
+    fn drop(&mut self) {
+        self.locked.borrow_mut().remove(&self.path);
+    }
+}
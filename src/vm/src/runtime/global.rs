@@ -1,8 +1,37 @@
-#[derive(Default, Debug, PartialEq)]
-pub struct Global {}
+use super::*;
+
+/// The variables declared with `global`, shared by all the functions and all the code run on the
+/// runtime.
+#[derive(Debug)]
+pub struct Global {
+    table: TableObject,
+}
+
+impl Default for Global {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Global {
-    pub const fn new() -> Self {
-        Self {}
+    pub fn new() -> Self {
+        Self {
+            table: TableObject::new(Default::default()),
+        }
+    }
+
+    /// The value of the global `name`, or nil if it was never set.
+    pub fn get(&self, name: &str) -> Object {
+        self.table.get(name).cloned().unwrap_or(Object::Nil)
+    }
+
+    /// Sets the global `name`, e.g. to pass a value to the scripts run later.
+    pub fn set(&mut self, name: impl Into<String>, value: Object) {
+        self.table.insert(TableKey::from(name.into()), value);
+    }
+
+    /// The globals set so far.
+    pub fn table(&self) -> &TableObject {
+        &self.table
     }
 }
@@ -0,0 +1,109 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn from_int(level: i64) -> Option<Self> {
+        match level {
+            0 => Some(LogLevel::Debug),
+            1 => Some(LogLevel::Info),
+            2 => Some(LogLevel::Warn),
+            3 => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "DEBUG"),
+            LogLevel::Info => write!(f, "INFO"),
+            LogLevel::Warn => write!(f, "WARN"),
+            LogLevel::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.level, self.message)?;
+        for (key, value) in self.fields.iter() {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the records logged by `log.debug/info/warn/error`.
+/// Hosts implement this to route script logs into their own logging.
+pub trait LogSink {
+    fn log(&mut self, record: &LogRecord);
+}
+
+pub struct Logger {
+    pub level: LogLevel,
+    sink: Option<Box<dyn LogSink>>,
+}
+
+impl Logger {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            level: LogLevel::Info,
+            sink: None,
+        }
+    }
+
+    /// Replaces the sink. If no sink is set, records are written to stderr.
+    pub fn set_sink(&mut self, sink: impl LogSink + 'static) {
+        self.sink = Some(Box::new(sink));
+    }
+
+    #[inline]
+    pub fn enabled(&self, level: LogLevel) -> bool {
+        level >= self.level
+    }
+
+    pub fn sink(&mut self) -> Option<&mut (dyn LogSink + 'static)> {
+        self.sink.as_deref_mut()
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("level", &self.level)
+            .field("sink", &self.sink.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
@@ -0,0 +1,155 @@
+use super::*;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Compiles the source of a module, read from the file at the path. The error is the message to
+/// fail the `import` with.
+pub type ModuleCompiler = fn(path: &str, source: &str) -> Result<CompiledModule, String>;
+
+/// What a [`ModuleCompiler`] makes of a module.
+#[derive(Clone, Debug, Default)]
+pub struct CompiledModule {
+    pub code: Vec<Code>,
+    /// Where each instruction of `code` came from in the source, as for
+    /// [`FunctionPrototype::spans`].
+    pub spans: Vec<Option<TextSpan>>,
+    /// The warnings about the source, rendered as for a script, shown on stderr when the module
+    /// is imported. Empty if there are none.
+    pub warnings: String,
+}
+
+/// The modules `import` loads: the native ones registered by the embedder, and the files it has
+/// run, each only once.
+#[derive(Debug, Default)]
+pub struct Modules {
+    /// The directories searched, in order, for a module that isn't a path relative to the
+    /// importing file (`./name` or `../name`).
+    pub search_path: Vec<PathBuf>,
+    /// What relative paths are resolved against outside any module, e.g. the directory of the
+    /// script being run. Defaults to the working directory.
+    pub root: Option<PathBuf>,
+    native: HashMap<String, Object>,
+    compiler: Option<ModuleCompiler>,
+    /// The exports of the files run, by canonical path.
+    cache: HashMap<PathBuf, Object>,
+    /// The files being run, innermost last.
+    loading: Vec<PathBuf>,
+}
+
+/// Where `import` finds a module.
+pub(crate) enum Found {
+    Loaded(Object),
+    File(PathBuf),
+}
+
+impl Modules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `import "name"` give `exports`, e.g. a table of Rust functions, before the files
+    /// are searched.
+    pub fn register(&mut self, name: impl Into<String>, exports: Object) {
+        self.native.insert(name.into(), exports);
+    }
+
+    /// Sets how the files imported are compiled. Without it, only native modules can be imported.
+    pub fn set_compiler(&mut self, compiler: ModuleCompiler) {
+        self.compiler = Some(compiler);
+    }
+
+    pub(crate) fn compiler(&self) -> Option<ModuleCompiler> {
+        self.compiler
+    }
+
+    /// Finds the module `name`: a native one, one run already, or the file to run in
+    /// `filesystem`. The extension `.lico` may be left out.
+    pub(crate) fn find(
+        &self,
+        name: &str,
+        filesystem: &dyn FileSystem,
+    ) -> Result<Found, RuntimeError> {
+        if let Some(exports) = self.native.get(name) {
+            return Ok(Found::Loaded(exports.clone()));
+        }
+        let file = match Path::new(name).extension() {
+            Some(_) => PathBuf::from(name),
+            None => PathBuf::from(format!("{}.lico", name)),
+        };
+        let candidates = if file.is_absolute() {
+            vec![file]
+        } else if name.starts_with("./") || name.starts_with("../") {
+            let dir = match self.loading.last() {
+                Some(importer) => importer.parent().map(Path::to_path_buf),
+                None => self.root.clone(),
+            };
+            vec![dir.unwrap_or_default().join(file)]
+        } else {
+            self.search_path.iter().map(|dir| dir.join(&file)).collect()
+        };
+        let path = candidates
+            .iter()
+            .find_map(|candidate| filesystem.canonicalize(&candidate.to_string_lossy()).ok())
+            .ok_or_else(|| {
+                RuntimeError::new(ErrorKind::Io, format!("Module not found: {}", name))
+            })?;
+        match self.cache.get(&path) {
+            Some(exports) => Ok(Found::Loaded(exports.clone())),
+            None if self.loading.contains(&path) => Err(RuntimeError::new(
+                ErrorKind::Other,
+                format!("Circular import of {}", path.display()),
+            )),
+            None => Ok(Found::File(path)),
+        }
+    }
+
+    /// Makes the function that runs `module`, compiled from `file`, named after the file in
+    /// tracebacks.
+    ///
+    /// The functions it defines are marked as coming from `file` too, so that errors in them are
+    /// located in the module rather than in the script.
+    pub(crate) fn function(file: SourceFile, module: CompiledModule) -> FunctionObject {
+        fn in_file(code: &[Code], file: &Rc<SourceFile>) -> Rc<[Code]> {
+            (code.iter())
+                .map(|code| match code {
+                    Code::MakeClosure(proto) => Code::MakeClosure(Rc::new(FunctionPrototype {
+                        code: in_file(&proto.code, file),
+                        file: Some(Rc::clone(file)),
+                        ..FunctionPrototype::clone(proto)
+                    })),
+                    code => code.clone(),
+                })
+                .collect()
+        }
+        let file = Rc::new(file);
+        FunctionObject {
+            proto: Rc::new(FunctionPrototype {
+                name: Some(file.path.clone()),
+                captures: Vec::new(),
+                args: Vec::new(),
+                code: in_file(&module.code, &file),
+                spans: module.spans.into(),
+                file: Some(file),
+            }),
+            env: Vec::new(),
+        }
+    }
+
+    /// Marks the file at `path` as being run, until [`Self::finish`].
+    pub(crate) fn start(&mut self, path: PathBuf) {
+        self.loading.push(path);
+    }
+
+    /// Marks the innermost file being run as done, keeping its exports unless it failed.
+    pub(crate) fn finish(&mut self, exports: Option<Object>) {
+        let path = self
+            .loading
+            .pop()
+            .expect("[BUG] No module is being loaded.");
+        if let Some(exports) = exports {
+            self.cache.insert(path, exports);
+        }
+    }
+}
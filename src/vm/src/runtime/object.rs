@@ -10,9 +10,24 @@ pub use array::*;
 mod function;
 pub use function::*;
 
+mod bound_function;
+pub use bound_function::*;
+
+mod memoized_function;
+pub use memoized_function::*;
+
+mod bytes;
+pub use bytes::*;
+
+mod error;
+pub use error::*;
+
 mod table;
 pub use table::*;
 
+mod userdata;
+pub use userdata::*;
+
 mod string;
 pub use string::*;
 
@@ -25,6 +40,7 @@ pub use int::*;
 mod primitive;
 pub use primitive::*;
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Object {
     Int(i64),
@@ -33,21 +49,29 @@ pub enum Object {
     Bool(bool),
     Nil,
     Function(Rc<FunctionObject>),
+    BoundFunction(Rc<BoundFunctionObject>),
+    MemoizedFunction(Rc<MemoizedFunctionObject>),
     Array(Rc<RefCell<ArrayObject>>),
     Table(Rc<RefCell<TableObject>>),
-    RustFunction(fn(&[Object]) -> Result<Object, String>),
+    Bytes(Rc<BytesObject>),
+    Error(Rc<ErrorObject>),
+    Userdata(Rc<UserdataObject>),
+    RustFunction(fn(&[Object]) -> Result<Object, RuntimeError>),
 }
 
+// Every payload is at most one word, so that moving an object around is cheap.
+const _: () = assert!(std::mem::size_of::<Object>() == 16);
+
 macro_rules! ensure_fn {
     ($name:ident -> $inner_type:ty, $pattern:pat => $result:expr) => {
-        pub fn $name(self) -> Result<$inner_type, String> {
+        pub fn $name(self) -> Result<$inner_type, RuntimeError> {
             match self {
                 $pattern => $result,
-                _ => Err(format!(
+                _ => Err(RuntimeError::type_error(format!(
                     "Expected `{}`, got `{}`",
                     stringify!($name)[7..].to_string(), // remove "ensure_"
                     self.typename()
-                )),
+                ))),
             }
         }
     };
@@ -55,13 +79,21 @@ macro_rules! ensure_fn {
 
 impl Object {
     pub fn new_string(string: String) -> Self {
-        Self::String(StringObject::new(Rc::new(string)))
+        Self::String(StringObject::new(string))
     }
 
     pub fn new_function(func: FunctionObject) -> Self {
         Self::Function(Rc::new(func))
     }
 
+    pub fn new_bound_function(func: BoundFunctionObject) -> Self {
+        Self::BoundFunction(Rc::new(func))
+    }
+
+    pub fn new_memoized_function(func: MemoizedFunctionObject) -> Self {
+        Self::MemoizedFunction(Rc::new(func))
+    }
+
     pub fn new_array(array: ArrayObject) -> Self {
         Self::Array(Rc::new(RefCell::new(array)))
     }
@@ -70,6 +102,14 @@ impl Object {
         Self::Table(Rc::new(RefCell::new(table)))
     }
 
+    pub fn new_bytes(bytes: Vec<u8>) -> Self {
+        Self::Bytes(Rc::new(BytesObject::new(bytes)))
+    }
+
+    pub fn new_error(error: RuntimeError) -> Self {
+        Self::Error(Rc::new(ErrorObject::new(error)))
+    }
+
     pub fn typename(&self) -> &'static str {
         match self {
             Object::Int(_) => "int",
@@ -78,12 +118,22 @@ impl Object {
             Object::Bool(_) => "bool",
             Object::Nil => "nil",
             Object::Function(_) => "function",
+            Object::BoundFunction(_) => "function",
+            Object::MemoizedFunction(_) => "function",
             Object::Array(_) => "array",
             Object::Table(_) => "table",
+            Object::Bytes(_) => "bytes",
+            Object::Error(_) => "error",
+            Object::Userdata(_) => "userdata",
             Object::RustFunction(_) => "rust_function",
         }
     }
 
+    /// Whether the object counts as true in a condition, which is unless it is nil or false.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Nil | Object::Bool(false))
+    }
+
     pub fn deep_clone(&self) -> Self {
         match self {
             Object::Int(x) => Object::Int(*x),
@@ -92,8 +142,13 @@ impl Object {
             Object::Bool(x) => Object::Bool(*x),
             Object::Nil => Object::Nil,
             Object::Function(x) => Object::Function(Rc::clone(x)), // It is ok because FunctionObject is immutable
+            Object::BoundFunction(x) => Object::BoundFunction(Rc::clone(x)),
+            Object::MemoizedFunction(x) => Object::MemoizedFunction(Rc::clone(x)),
             Object::Array(x) => Object::new_array(x.borrow().deep_clone()),
             Object::Table(x) => Object::new_table(x.borrow().deep_clone()),
+            Object::Bytes(x) => Object::Bytes(Rc::clone(x)), // It is ok because BytesObject is immutable
+            Object::Error(x) => Object::Error(Rc::clone(x)), // It is ok because ErrorObject is immutable
+            Object::Userdata(x) => Object::Userdata(Rc::clone(x)), // Native values can't be copied
             Object::RustFunction(x) => Object::RustFunction(*x),
         }
     }
@@ -122,6 +177,10 @@ impl Object {
         ensure_array -> Rc<RefCell<ArrayObject>>,
         Object::Array(x) => Ok(x)
     );
+    ensure_fn!(
+        ensure_bytes -> Rc<BytesObject>,
+        Object::Bytes(x) => Ok(x)
+    );
     ensure_fn!(
         ensure_table -> Rc<RefCell<TableObject>>,
         Object::Table(x) => Ok(x)
@@ -137,30 +196,22 @@ impl std::fmt::Display for Object {
             Object::Bool(x) => write!(f, "{}", if *x { "true" } else { "false" }),
             Object::Nil => write!(f, "nil"),
             Object::Function(x) => {
-                write!(f, "<Function:{}-{} ({})>", x.id.0, x.id.1, x.args.len())
+                write!(
+                    f,
+                    "<Function:{:p} ({})>",
+                    Rc::as_ptr(&x.proto),
+                    x.args().len()
+                )
             }
+            Object::BoundFunction(x) => write!(f, "<BoundFunction:{} ({})>", x.func, x.args.len()),
+            Object::MemoizedFunction(x) => write!(f, "<MemoizedFunction:{}>", x.func),
             Object::Array(x) => write!(f, "[{}]", {
                 let array = x.borrow();
                 let content = array
                     .iter()
                     .take(10)
                     .map(|x| match x {
-                        Object::String(x) => {
-                            let x = x
-                                .to_string()
-                                .replace('\\', "\\\\")
-                                .replace('\n', "\\n")
-                                .replace('\r', "\\r")
-                                .replace('\t', "\\t")
-                                .replace('\0', "\\0");
-                            let has_single_quote = x.contains('\'');
-                            let has_double_quote = x.contains('"');
-                            match (has_single_quote, has_double_quote) {
-                                (true, true) => format!("\"{}\"", x.replace('\"', "\\\"")),
-                                (_, false) => format!("\"{}\"", x),
-                                (false, _) => format!("'{}'", x),
-                            }
-                        }
+                        Object::String(x) => quote_string(x.as_str()),
                         _ => format!("{}", x),
                     })
                     .collect::<Vec<_>>()
@@ -172,7 +223,76 @@ impl std::fmt::Display for Object {
                 }
             }),
             Object::Table(x) => write!(f, "<Table ({} fields)>", x.borrow().len(),),
+            Object::Bytes(x) => write!(f, "<Bytes ({} bytes)>", x.len()),
+            Object::Error(x) => write!(f, "{}", x.error()),
+            Object::Userdata(x) => write!(f, "<Userdata:{}>", x.name()),
             Object::RustFunction(x) => write!(f, "<RustFunction:{:?}>", x),
         }
     }
 }
+
+// Conversions for the values returned by Rust functions, e.g. `Ok((path, size).into())`.
+// Lico has no multiple return values, so a `Vec` or a tuple becomes an array the script can index.
+
+macro_rules! impl_from {
+    ($($type:ty => |$value:ident| $object:expr),* $(,)?) => {
+        $(impl From<$type> for Object {
+            #[inline]
+            fn from($value: $type) -> Self {
+                $object
+            }
+        })*
+    };
+}
+impl_from! {
+    () => |_value| Object::Nil,
+    i64 => |value| Object::Int(value),
+    f64 => |value| Object::Float(value),
+    bool => |value| Object::Bool(value),
+    String => |value| Object::new_string(value),
+    &str => |value| Object::new_string(value.to_string()),
+}
+
+impl<T: Into<Object>> From<Option<T>> for Object {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Object::Nil, Into::into)
+    }
+}
+
+impl<T: Into<Object>> From<Vec<T>> for Object {
+    fn from(value: Vec<T>) -> Self {
+        let array = value.into_iter().map(Into::into).collect();
+        Object::new_array(ArrayObject::new(array))
+    }
+}
+
+macro_rules! impl_from_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Into<Object>),*> From<($($name,)*)> for Object {
+            #[allow(non_snake_case)]
+            fn from(($($name,)*): ($($name,)*)) -> Self {
+                Object::new_array(ArrayObject::new(vec![$($name.into()),*]))
+            }
+        }
+    };
+}
+impl_from_tuple!(A, B);
+impl_from_tuple!(A, B, C);
+impl_from_tuple!(A, B, C, D);
+
+/// Quotes `string` as a string literal, escaping special characters.
+pub fn quote_string(string: &str) -> String {
+    let x = string
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+        .replace('\0', "\\0");
+    let has_single_quote = x.contains('\'');
+    let has_double_quote = x.contains('"');
+    match (has_single_quote, has_double_quote) {
+        (true, true) => format!("\"{}\"", x.replace('\"', "\\\"")),
+        (_, false) => format!("\"{}\"", x),
+        (false, _) => format!("'{}'", x),
+    }
+}
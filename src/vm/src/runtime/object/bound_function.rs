@@ -0,0 +1,8 @@
+use super::*;
+
+/// A function with some leading arguments already bound, created by `partial(func, args...)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundFunctionObject {
+    pub func: Object,
+    pub args: Vec<Object>,
+}
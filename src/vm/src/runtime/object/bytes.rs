@@ -0,0 +1,75 @@
+use super::*;
+use std::ops::Deref;
+
+/// An immutable byte sequence, used for binary data such as hash digests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytesObject(Vec<u8>);
+
+impl BytesObject {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+}
+
+impl Deref for BytesObject {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Encodes `bytes` as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, b| {
+            // Writing to a String never fails.
+            let _ = write!(hex, "{:02x}", b);
+            hex
+        })
+}
+
+pub fn run_bytes_method(
+    bytes: Rc<BytesObject>,
+    method: BuiltinMethod,
+    args: &[Object],
+) -> Result<Object, RuntimeError> {
+    match method {
+        // len() -> Int
+        BuiltinMethod::Len => {
+            extract_argument!(args, []);
+            Ok(Object::Int(bytes.len() as i64))
+        }
+
+        // to_hex() -> String
+        BuiltinMethod::ToHex => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(bytes.to_hex()))
+        }
+
+        // to_array() -> Array
+        BuiltinMethod::ToArray => {
+            extract_argument!(args, []);
+            let array = bytes.iter().map(|b| Object::Int(*b as i64)).collect();
+            Ok(Object::new_array(ArrayObject::new(array)))
+        }
+
+        // to_string() -> String
+        BuiltinMethod::ToString => {
+            extract_argument!(args, []);
+            let string = String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())?;
+            Ok(Object::new_string(string))
+        }
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of bytes",
+            method.name()
+        ))),
+    }
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+/// An error caught by `try`, which its `catch` block gets. Scripts read what happened from the
+/// fields `message`, `kind` and `traceback`, and can raise it again with `error`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorObject(RuntimeError);
+
+impl ErrorObject {
+    pub fn new(error: RuntimeError) -> Self {
+        Self(error)
+    }
+
+    pub fn error(&self) -> &RuntimeError {
+        &self.0
+    }
+
+    /// The field `name`, or `None` if there is no such field.
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match name {
+            "message" => Some(Object::new_string(self.0.message.clone())),
+            "kind" => Some(Object::new_string(self.0.kind.name().to_string())),
+            // The functions the error unwound through, innermost last as in a printed traceback. A
+            // function that called itself from the same place is listed once.
+            "traceback" => {
+                let functions = self
+                    .0
+                    .traceback
+                    .iter()
+                    .rev()
+                    .map(|frame| Object::new_string(frame.function.clone()))
+                    .collect();
+                Some(Object::new_array(ArrayObject::new(functions)))
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,141 +1,148 @@
 use super::*;
 
-pub fn run_float_method(float: f64, name: &str, args: &[Object]) -> Result<Object, String> {
-    match name {
+pub fn run_float_method(
+    float: f64,
+    method: BuiltinMethod,
+    args: &[Object],
+) -> Result<Object, RuntimeError> {
+    match method {
         // abs() -> Float
-        "abs" => {
+        BuiltinMethod::Abs => {
             extract_argument!(args, []);
             Ok(Object::Float(float.abs()))
         }
 
         // acos() -> Float
-        "acos" => {
+        BuiltinMethod::Acos => {
             extract_argument!(args, []);
             Ok(Object::Float(float.acos()))
         }
 
         // acosh() -> Float
-        "acosh" => {
+        BuiltinMethod::Acosh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.acosh()))
         }
 
         // asin() -> Float
-        "asin" => {
+        BuiltinMethod::Asin => {
             extract_argument!(args, []);
             Ok(Object::Float(float.asin()))
         }
 
         // asinh() -> Float
-        "asinh" => {
+        BuiltinMethod::Asinh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.asinh()))
         }
 
         // atan() -> Float
-        "atan" => {
+        BuiltinMethod::Atan => {
             extract_argument!(args, []);
             Ok(Object::Float(float.atan()))
         }
 
         // atan2(float) -> Float
-        "atan2" => {
+        BuiltinMethod::Atan2 => {
             let other = extract_argument!(args, [Float]);
             Ok(Object::Float(float.atan2(other)))
         }
 
         // atanh() -> Float
-        "atanh" => {
+        BuiltinMethod::Atanh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.atanh()))
         }
 
         // cbrt() -> Float
-        "cbar" => {
+        BuiltinMethod::Cbar => {
             extract_argument!(args, []);
             Ok(Object::Float(float.cbrt()))
         }
 
         // ceil() -> Float
-        "ceil" => {
+        BuiltinMethod::Ceil => {
             extract_argument!(args, []);
             Ok(Object::Float(float.ceil()))
         }
 
         // clamp(min: Float, max: Float) -> Float
-        "clamp" => {
+        BuiltinMethod::Clamp => {
             let (min, max) = extract_argument!(args, [Float, Float]);
             Ok(Object::Float(float.clamp(min, max)))
         }
 
         // cos() -> Float
-        "cos" => {
+        BuiltinMethod::Cos => {
             extract_argument!(args, []);
             Ok(Object::Float(float.cos()))
         }
 
         // cosh() -> Float
-        "cosh" => {
+        BuiltinMethod::Cosh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.cosh()))
         }
 
         // exp() -> Float
-        "exp" => {
+        BuiltinMethod::Exp => {
             extract_argument!(args, []);
             Ok(Object::Float(float.exp()))
         }
 
         // exp2() -> Float
-        "exp2" => {
+        BuiltinMethod::Exp2 => {
             extract_argument!(args, []);
             Ok(Object::Float(float.exp2()))
         }
 
         // floor() -> Float
-        "floor" => {
+        BuiltinMethod::Floor => {
             extract_argument!(args, []);
             Ok(Object::Float(float.floor()))
         }
 
         // fract() -> Float
-        "fract" => {
+        BuiltinMethod::Fract => {
             extract_argument!(args, []);
             Ok(Object::Float(float.fract()))
         }
 
         // ln() -> Float
-        "ln" => {
+        BuiltinMethod::Ln => {
             extract_argument!(args, []);
             Ok(Object::Float(float.ln()))
         }
 
         // log(base: Float|Int) -> Float
-        "log" => {
+        BuiltinMethod::Log => {
             let base = extract_argument!(args, [
                 {
                     Object::Float(base) => *base,
                     Object::Int(base) => *base as f64,
-                    _ => return Err(format!("{} takes an float", name)),
+                    _ => return Err(RuntimeError::type_error(format!(
+                        "{} takes an float",
+                        method.name()
+                    ))),
                 }
             ]);
             Ok(Object::Float(float.log(base)))
         }
 
         // log10() -> Float
-        "log10" => {
+        BuiltinMethod::Log10 => {
             extract_argument!(args, []);
             Ok(Object::Float(float.log10()))
         }
 
         // log2() -> Float
-        "log2" => {
+        BuiltinMethod::Log2 => {
             extract_argument!(args, []);
             Ok(Object::Float(float.log2()))
         }
 
         // pow(exp: Float|Int) -> Float
-        "pow" => {
+        BuiltinMethod::Pow => {
             ensure_argument_length!(args, 1);
             match args[0] {
                 Object::Float(exp) => Ok(Object::Float(float.powf(exp))),
@@ -148,77 +155,83 @@ pub fn run_float_method(float: f64, name: &str, args: &[Object]) -> Result<Objec
                         Ok(Object::Float(float.powf(exp as f64)))
                     }
                 }
-                _ => Err(format!("{} takes an float", name)),
+                _ => Err(RuntimeError::type_error(format!(
+                    "{} takes an float",
+                    method.name()
+                ))),
             }
         }
 
         // recip() -> Float
-        "recip" => {
+        BuiltinMethod::Recip => {
             extract_argument!(args, []);
             Ok(Object::Float(float.recip()))
         }
 
         // round() -> Float
-        "round" => {
+        BuiltinMethod::Round => {
             extract_argument!(args, []);
             Ok(Object::Float(float.round()))
         }
 
         // sin() -> Float
-        "sin" => {
+        BuiltinMethod::Sin => {
             extract_argument!(args, []);
             Ok(Object::Float(float.sin()))
         }
 
         // sinh() -> Float
-        "sinh" => {
+        BuiltinMethod::Sinh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.sinh()))
         }
 
         // sqrt() -> Float
-        "sqrt" => {
+        BuiltinMethod::Sqrt => {
             extract_argument!(args, []);
             Ok(Object::Float(float.sqrt()))
         }
 
         // tan() -> Float
-        "tan" => {
+        BuiltinMethod::Tan => {
             extract_argument!(args, []);
             Ok(Object::Float(float.tan()))
         }
 
         // tanh() -> Float
-        "tanh" => {
+        BuiltinMethod::Tanh => {
             extract_argument!(args, []);
             Ok(Object::Float(float.tanh()))
         }
 
         // to_degrees() -> Float
-        "to_degrees" => {
+        BuiltinMethod::ToDegrees => {
             extract_argument!(args, []);
             Ok(Object::Float(float.to_degrees()))
         }
 
         // to_radians() -> Float
-        "to_string" => {
+        BuiltinMethod::ToString => {
             extract_argument!(args, []);
             let string = float.to_string();
             Ok(Object::new_string(string))
         }
 
         // to_radians() -> Float
-        "to_radians" => {
+        BuiltinMethod::ToRadians => {
             extract_argument!(args, []);
             Ok(Object::Float(float.to_radians()))
         }
 
         // trunc() -> Float
-        "trunc" => {
+        BuiltinMethod::Trunc => {
             extract_argument!(args, []);
             Ok(Object::Float(float.trunc()))
         }
 
-        _ => Err(format!("{} is not a method of float", name)),
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of float",
+            method.name()
+        ))),
     }
 }
@@ -2,15 +2,25 @@ use super::*;
 
 #[derive(Clone, Debug)]
 pub struct FunctionObject {
-    pub id: (usize, u8),
+    pub proto: Rc<FunctionPrototype>,
     pub env: Vec<Rc<RefCell<Object>>>,
-    pub args: Vec<ArgumentKind>,
-    pub code: Vec<Code>,
+}
+
+impl FunctionObject {
+    #[inline]
+    pub fn args(&self) -> &[ArgumentKind] {
+        &self.proto.args
+    }
+
+    #[inline]
+    pub fn code(&self) -> &Rc<[Code]> {
+        &self.proto.code
+    }
 }
 
 impl PartialEq for FunctionObject {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        Rc::ptr_eq(&self.proto, &other.proto)
     }
 }
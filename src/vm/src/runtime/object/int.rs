@@ -1,69 +1,81 @@
 use super::*;
 
-pub fn run_int_method(int: i64, name: &str, args: &[Object]) -> Result<Object, String> {
-    match name {
+pub fn run_int_method(
+    int: i64,
+    method: BuiltinMethod,
+    args: &[Object],
+    overflow: IntOverflow,
+) -> Result<Object, RuntimeError> {
+    match method {
         // abs() -> Int
-        "abs" => {
+        BuiltinMethod::Abs => {
             extract_argument!(args, []);
-            Ok(Object::Int(int.abs()))
+            match int.checked_abs() {
+                Some(abs) => Ok(Object::Int(abs)),
+                None if overflow == IntOverflow::Float => Ok(Object::Float((int as f64).abs())),
+                None => Err(RuntimeError::new(
+                    ErrorKind::Overflow,
+                    format!("Integer overflow: ({})->abs()", int),
+                )),
+            }
         }
 
         // acos() -> Float
-        "acos" => {
+        BuiltinMethod::Acos => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).acos()))
         }
 
         // acosh() -> Float
-        "acosh" => {
+        BuiltinMethod::Acosh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).acosh()))
         }
 
         // asin() -> Float
-        "asin" => {
+        BuiltinMethod::Asin => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).asin()))
         }
 
         // asinh() -> Float
-        "asinh" => {
+        BuiltinMethod::Asinh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).asinh()))
         }
 
         // atan() -> Float
-        "atan" => {
+        BuiltinMethod::Atan => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).atan()))
         }
 
         // atan2(other: Float) -> Float
-        "atan2" => {
+        BuiltinMethod::Atan2 => {
             let other = extract_argument!(args, [Float]);
             Ok(Object::Float((int as f64).atan2(other)))
         }
 
         // atanh() -> Float
-        "atanh" => {
+        BuiltinMethod::Atanh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).atanh()))
         }
 
         // cbrt() -> Float
-        "cbar" => {
+        BuiltinMethod::Cbar => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).cbrt()))
         }
 
         // ceil() -> Float
-        "ceil" => {
+        BuiltinMethod::Ceil => {
             extract_argument!(args, []);
             Ok(Object::Int(int))
         }
 
         // clamp(min: Int|Float, max: Int|Float) -> Int
-        "clamp" => {
+        BuiltinMethod::Clamp => {
             ensure_argument_length!(args, 2);
             let a0 = &args[0];
             let a1 = &args[1];
@@ -72,330 +84,240 @@ pub fn run_int_method(int: i64, name: &str, args: &[Object]) -> Result<Object, S
                 (Object::Int(_min), Object::Float(_max)) => unimplemented!("int clamp"),
                 (Object::Float(_min), Object::Int(_max)) => unimplemented!("int clamp"),
                 (Object::Float(_min), Object::Float(_max)) => unimplemented!("int clamp"),
-                _ => Err(format!("{} takes an int", name)),
+                _ => Err(RuntimeError::type_error(format!(
+                    "{} takes an int",
+                    method.name()
+                ))),
             }
         }
 
         // cos() -> Float
-        "cos" => {
+        BuiltinMethod::Cos => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).cos()))
         }
 
         // cosh() -> Float
-        "cosh" => {
+        BuiltinMethod::Cosh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).cosh()))
         }
 
-        // div(int) -> Int
-        "downto" => {
+        // downto(to: Int) -> Table
+        BuiltinMethod::Downto => {
             ensure_argument_length!(args, 1);
             let Object::Int(to) = args[0] else {
-                return Err(format!("{} takes an int", name));
+                return Err(RuntimeError::type_error(format!(
+                    "{} takes an int",
+                    method.name()
+                )));
             };
-            let mut range_tbl = TableObject::new(
-                [
-                    ("start".into(), Object::Int(int)),
-                    ("end".into(), Object::Int(to)),
-                    ("step".into(), Object::Int(-1)),
-                    ("__current".into(), Object::Nil),
-                ]
-                .into_iter()
-                .collect(),
-            );
-            range_tbl.add_method(
-                "step",
-                TableMethod::Builtin(|range, args| {
-                    ensure_argument_length!(args, 1);
-                    let Object::Int(step) = args[0] else {
-                        return Err(format!("expected int, got {:?}", args[0]));
-                    };
-                    if step >= 0 {
-                        return Err(format!("step should be negative, got {}", step));
-                    }
-                    range.borrow_mut().insert("step".into(), Object::Int(step));
-                    Ok(Object::Nil)
-                }),
-            );
-            range_tbl.add_method(
-                "__get_iterator",
-                TableMethod::Builtin(|range, args| {
-                    extract_argument!(args, []);
-                    Ok(Object::Table(range))
-                }),
-            );
-            range_tbl.add_method(
-                "__move_next",
-                TableMethod::Builtin(|range, args| {
-                    extract_argument!(args, []);
-                    let Some(current) = range.borrow().get("__current").cloned() else {
-                        unreachable!("range should have `__current`")
-                    };
-                    if let Object::Int(current) = current {
-                        let (end, step) = table_extract_values!(range, {
-                            end: Int, step: Int,
-                        });
-                        if current + step >= end {
-                            range
-                                .borrow_mut()
-                                .insert("__current".into(), Object::Int(current - 1));
-                            Ok(Object::Bool(true))
-                        } else {
-                            range.borrow_mut().insert("__current".into(), Object::Nil);
-                            Ok(Object::Bool(false))
-                        }
-                    } else {
-                        let Some(Object::Int(start)) = range.borrow().get("start").cloned() else {
-                            unreachable!("range should have `start` field (int)")
-                        };
-                        range
-                            .borrow_mut()
-                            .insert("__current".into(), Object::Int(start));
-                        Ok(Object::Bool(true))
-                    }
-                }),
-            );
-            range_tbl.add_method(
-                "__current",
-                TableMethod::Builtin(|range, args| {
-                    extract_argument!(args, []);
-                    let current = range.borrow().get("__current").cloned();
-                    Ok(current.unwrap_or(Object::Nil))
-                }),
-            );
-            Ok(Object::new_table(range_tbl))
+            Ok(make_range(int, to, -1))
         }
 
         // exp() -> Float
-        "exp" => {
+        BuiltinMethod::Exp => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).exp()))
         }
 
         // exp2() -> Float
-        "exp2" => {
+        BuiltinMethod::Exp2 => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).exp2()))
         }
 
         // floor() -> Int
-        "floor" => {
+        BuiltinMethod::Floor => {
             extract_argument!(args, []);
             Ok(Object::Int(int))
         }
 
         // fract() -> Int
-        "fract" => {
+        BuiltinMethod::Fract => {
             extract_argument!(args, []);
             Ok(Object::Int(0))
         }
 
         // hypot(float) -> Float
-        "ln" => {
+        BuiltinMethod::Ln => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).ln()))
         }
 
         // log(base: Float|Int) -> Float
-        "log" => {
+        BuiltinMethod::Log => {
             let base = extract_argument!(args, [
                 {
                     Object::Float(base) => *base,
                     Object::Int(base) => *base as f64,
-                    _ => return Err(format!("{} takes an float", name)),
+                    _ => return Err(RuntimeError::type_error(format!(
+                        "{} takes an float",
+                        method.name()
+                    ))),
                 }
             ]);
             Ok(Object::Float((int as f64).log(base)))
         }
 
         // log10() -> Float
-        "log10" => {
+        BuiltinMethod::Log10 => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).log10()))
         }
 
         // log2() -> Float
-        "log2" => {
+        BuiltinMethod::Log2 => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).log2()))
         }
 
         // max(amount: Int) -> Int
-        "lshift" => {
+        BuiltinMethod::Lshift => {
             let amount = extract_argument!(args, [Int]);
             Ok(Object::Int(int << amount))
         }
 
-        // min(other: Int) -> Int
-        "pow" => {
+        // pow(exp: Int|Float) -> Int|Float
+        BuiltinMethod::Pow => {
             ensure_argument_length!(args, 1);
-            unimplemented!("int pow")
+            BinaryOp::Pow.apply(Object::Int(int), args[0].clone())
         }
 
         // round() -> Int
-        "recip" => {
+        BuiltinMethod::Recip => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).recip()))
         }
 
         // round() -> Int
-        "round" => {
+        BuiltinMethod::Round => {
             extract_argument!(args, []);
             Ok(Object::Int(int))
         }
 
         // rshift(amount: Int) -> Int
-        "rshift" => {
+        BuiltinMethod::Rshift => {
             let amount = extract_argument!(args, [Int]);
             Ok(Object::Int(int >> amount))
         }
 
         // sin() -> Float
-        "sin" => {
+        BuiltinMethod::Sin => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).sin()))
         }
 
         // sinh() -> Float
-        "sinh" => {
+        BuiltinMethod::Sinh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).sinh()))
         }
 
         // sqrt() -> Float
-        "sqrt" => {
+        BuiltinMethod::Sqrt => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).sqrt()))
         }
 
         // tan() -> Float
-        "tan" => {
+        BuiltinMethod::Tan => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).tan()))
         }
 
         // tanh() -> Float
-        "tanh" => {
+        BuiltinMethod::Tanh => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).tanh()))
         }
 
         // trunc() -> Float
-        "to_degrees" => {
+        BuiltinMethod::ToDegrees => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).to_degrees()))
         }
 
         // trunc() -> String
-        "to_string" => {
+        BuiltinMethod::ToString => {
             extract_argument!(args, []);
             let string = int.to_string();
             Ok(Object::new_string(string))
         }
 
         // trunc() -> Float
-        "to_radians" => {
+        BuiltinMethod::ToRadians => {
             extract_argument!(args, []);
             Ok(Object::Float((int as f64).to_radians()))
         }
 
         // trunc() -> Int
-        "trunc" => {
+        BuiltinMethod::Trunc => {
             extract_argument!(args, []);
             Ok(Object::Int(int))
         }
 
         // upto(to: Int) -> Table
-        "upto" => {
+        BuiltinMethod::Upto => {
             let to = extract_argument!(args, [Int]);
-            let mut range_tbl = TableObject::new(
-                [
-                    ("start".into(), Object::Int(int)),
-                    ("end".into(), Object::Int(to)),
-                    ("step".into(), Object::Int(1)),
-                    ("__current".into(), Object::Nil),
-                ]
-                .into_iter()
-                .collect(),
-            );
-            range_tbl.add_method(
-                "step", // step(step: Int) -> Nil
-                TableMethod::Builtin(|range, args| {
-                    let step = extract_argument!(args, [Int]);
-                    if step <= 0 {
-                        return Err(format!("step should be positive, got {}", step));
-                    }
-                    range.borrow_mut().insert("step".into(), Object::Int(step));
-                    Ok(Object::Nil)
-                }),
-            );
-            range_tbl.add_method(
-                "__get_iterator", // __get_iterator() -> Table
-                TableMethod::Builtin(|range, args| {
-                    extract_argument!(args, []);
-                    Ok(Object::Table(Rc::clone(&range)))
-                }),
-            );
-            if int <= to {
-                range_tbl.add_method(
-                    "__move_next", // __move_next() -> Bool
-                    TableMethod::Builtin(|range, args| {
-                        extract_argument!(args, []);
-                        let Some(current) = range.borrow().get("__current").cloned() else {
-                            unreachable!("range should have `__current`")
-                        };
-                        if let Object::Int(current) = current {
-                            let (end, step) = table_extract_values!(range, {
-                                end: Int, step: Int,
-                            });
-                            if current + step <= end {
-                                range
-                                    .borrow_mut()
-                                    .insert("__current".into(), Object::Int(current + 1));
-                                Ok(Object::Bool(true))
-                            } else {
-                                range.borrow_mut().insert("__current".into(), Object::Nil);
-                                Ok(Object::Bool(false))
-                            }
-                        } else {
-                            let Some(Object::Int(start)) = range.borrow().get("start").cloned()
-                            else {
-                                unreachable!("range should have `start` field (int)")
-                            };
-                            range
-                                .borrow_mut()
-                                .insert("__current".into(), Object::Int(start));
-                            Ok(Object::Bool(true))
-                        }
-                    }),
-                );
-            } else {
-                range_tbl.add_method(
-                    "__move_next", // __move_next() -> Bool
-                    TableMethod::Builtin(|_, args| {
-                        extract_argument!(args, []);
-                        Ok(Object::Bool(false))
-                    }),
-                );
-            }
-            range_tbl.add_method(
-                "__current", // __current() -> Int|Nil
-                TableMethod::Builtin(|range, args| {
-                    extract_argument!(args, []);
-                    let current = range.borrow().get("__current").cloned();
-                    Ok(current.unwrap_or(Object::Nil))
-                }),
-            );
-            Ok(Object::new_table(range_tbl))
+            Ok(make_range(int, to, 1))
         }
 
         // xor(other: Int) -> Int
-        "xor" => {
+        BuiltinMethod::Xor => {
             let other = extract_argument!(args, [Int]);
             Ok(Object::Int(int ^ other))
         }
 
-        _ => Err(format!("{} is not a method of int", name)),
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of int",
+            method.name()
+        ))),
     }
 }
+
+/// The range from `start` to `end` (inclusive) that `upto` and `downto` make, which counts by
+/// `step` when iterated:
+///   { start = start, end = end, step = step, step(step: Int), __iter() }
+fn make_range(start: i64, end: i64, step: i64) -> Object {
+    let mut range_tbl = TableObject::new(
+        [
+            ("start".into(), Object::Int(start)),
+            ("end".into(), Object::Int(end)),
+            ("step".into(), Object::Int(step)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    range_tbl.add_method(
+        "step", // step(step: Int) -> Nil
+        TableMethod::Builtin(|range, args| {
+            let step = extract_argument!(args, [Int]);
+            let current = table_extract_values!(range, { step: Int });
+            // The range counts in the direction it was made with.
+            if current > 0 && step <= 0 {
+                return Err(format!("step should be positive, got {}", step).into());
+            }
+            if current < 0 && step >= 0 {
+                return Err(format!("step should be negative, got {}", step).into());
+            }
+            range.borrow_mut().insert("step".into(), Object::Int(step));
+            Ok(Object::Nil)
+        }),
+    );
+    range_tbl.add_method(
+        "__iter", // __iter() -> Iterator
+        TableMethod::Builtin(|range, args| {
+            extract_argument!(args, []);
+            let (start, end, step) = table_extract_values!(range, {
+                start: Int, end: Int, step: Int,
+            });
+            let in_range = move |&int: &i64| if step > 0 { int <= end } else { int >= end };
+            let ints = std::iter::successors(Some(start).filter(in_range), move |int| {
+                int.checked_add(step).filter(in_range)
+            });
+            Ok(builtin::make_iterator(ints.map(|int| Ok(Object::Int(int)))))
+        }),
+    );
+    Object::new_table(range_tbl)
+}
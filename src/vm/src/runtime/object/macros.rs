@@ -2,19 +2,12 @@
 macro_rules! ensure_argument_length {
     ($args:expr, 0) => {
         if !$args.is_empty() {
-            return Err(format!(
-                "Wrong number of arguments: expected 0, got {}",
-                $args.len()
-            ));
+            return Err($crate::RuntimeError::arity(0, $args.len()));
         }
     };
     ($args:expr, $len:expr) => {
         if $args.len() != $len {
-            return Err(format!(
-                "Wrong number of arguments: expected {}, got {}",
-                $len,
-                $args.len()
-            ));
+            return Err($crate::RuntimeError::arity($len, $args.len()));
         }
     };
 }
@@ -44,11 +37,11 @@ macro_rules! extract_argument {
                 $({
                     let next = unsafe { iter.next().unwrap_unchecked() };
                     let Object::$type(x) = next else {
-                        return Err(format!(
+                        return Err($crate::RuntimeError::type_error(format!(
                             "Mismatched argument type: expected {}, got {}",
                             stringify!($type).to_lowercase(),
                             next.typename()
-                        ));
+                        )));
                     };
                     x.clone()
                 },)*
@@ -0,0 +1,43 @@
+use super::*;
+use hashbrown::HashMap;
+use rustc_hash::FxBuildHasher;
+
+/// A function whose results are cached by argument values, created by `memoize(func)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoizedFunctionObject {
+    pub func: Object,
+    cache: RefCell<HashMap<Vec<Option<TableKey>>, Object, FxBuildHasher>>,
+}
+
+impl MemoizedFunctionObject {
+    pub fn new(func: Object) -> Self {
+        Self {
+            func,
+            cache: RefCell::new(HashMap::default()),
+        }
+    }
+
+    /// Makes a cache key from `args`. `nil` and the types usable as table keys are hashable.
+    pub fn make_key(args: &[Object]) -> Result<Vec<Option<TableKey>>, RuntimeError> {
+        args.iter()
+            .map(|arg| match arg {
+                Object::Nil => Ok(None),
+                Object::Int(_) | Object::String(_) | Object::Bool(_) => {
+                    TableKey::from_object(arg.clone()).map(Some)
+                }
+                x => Err(RuntimeError::type_error(format!(
+                    "Memoized function can not take `{}` as an argument, expected hashable value",
+                    x.typename()
+                ))),
+            })
+            .collect()
+    }
+
+    pub fn get(&self, key: &[Option<TableKey>]) -> Option<Object> {
+        self.cache.borrow().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: Vec<Option<TableKey>>, value: Object) {
+        self.cache.borrow_mut().insert(key, value);
+    }
+}
@@ -1,25 +1,35 @@
 use super::*;
 
-pub fn run_bool_method(bool: bool, name: &str, args: &[Object]) -> Result<Object, String> {
-    match name {
+pub fn run_bool_method(
+    bool: bool,
+    method: BuiltinMethod,
+    args: &[Object],
+) -> Result<Object, RuntimeError> {
+    match method {
         // to_string() -> String
-        "to_string" => {
+        BuiltinMethod::ToString => {
             extract_argument!(args, []);
             let string = bool.to_string();
             Ok(Object::new_string(string))
         }
-        _ => Err(format!("{} is not a method of bool", name)),
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of bool",
+            method.name()
+        ))),
     }
 }
 
-pub fn run_nil_method(name: &str, args: &[Object]) -> Result<Object, String> {
-    match name {
+pub fn run_nil_method(method: BuiltinMethod, args: &[Object]) -> Result<Object, RuntimeError> {
+    match method {
         // to_string() -> String
-        "to_string" => {
+        BuiltinMethod::ToString => {
             extract_argument!(args, []);
             let string = "nil".to_string();
             Ok(Object::new_string(string))
         }
-        _ => Err(format!("{} is not a method of nil", name)),
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of nil",
+            method.name()
+        ))),
     }
 }
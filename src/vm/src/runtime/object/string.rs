@@ -1,51 +1,104 @@
 use super::*;
+use std::cell::OnceCell;
 
-#[derive(Clone, Debug, Eq)]
+#[derive(Clone)]
 pub struct StringObject {
-    value: Rc<String>,
-    chars: Option<Rc<Vec<char>>>,
+    data: Rc<StringData>,
+}
+
+#[derive(Debug)]
+struct StringData {
+    value: String,
+    // The decoded characters, computed on the first character-wise access and shared by every
+    // clone of the string. `None` means the string is ASCII, so bytes and characters coincide.
+    chars: OnceCell<Option<Box<[char]>>>,
 }
 
 impl StringObject {
     #[inline]
-    pub fn new(value: Rc<String>) -> Self {
-        Self { value, chars: None }
+    pub fn new(value: String) -> Self {
+        Self {
+            data: Rc::new(StringData {
+                value,
+                chars: OnceCell::new(),
+            }),
+        }
     }
 
     // NOTE: Do not impl `Deref` for `StringObject`.
     //       It causes unexpected behavior due to the fact that the String is a wrapper of Vec<u8>.
     //       e.g. String::len() returns the length of the Vec<u8>, not the length of unicode characters.
     #[inline]
-    pub fn inner(&self) -> &Rc<String> {
-        &self.value
+    pub fn as_str(&self) -> &str {
+        self.data.value.as_str()
+    }
+
+    fn chars(&self) -> Option<&[char]> {
+        self.data
+            .chars
+            .get_or_init(|| {
+                let value = &self.data.value;
+                (!value.is_ascii()).then(|| value.chars().collect())
+            })
+            .as_deref()
+    }
+
+    /// The number of unicode characters.
+    pub fn char_len(&self) -> usize {
+        match self.chars() {
+            Some(chars) => chars.len(),
+            None => self.data.value.len(),
+        }
     }
 
-    pub fn get_chars(&self) -> Rc<Vec<char>> {
-        self.chars
-            .clone()
-            .unwrap_or_else(|| Rc::new(self.value.chars().collect()))
+    /// The `index`-th unicode character, in O(1) after the first call.
+    pub fn char_at(&self, index: usize) -> Option<char> {
+        match self.chars() {
+            Some(chars) => chars.get(index).copied(),
+            None => self.data.value.as_bytes().get(index).map(|&b| b as char),
+        }
+    }
+
+    /// The unicode characters in `range`, which must be within `char_len()`.
+    pub fn char_slice(&self, range: std::ops::Range<usize>) -> String {
+        match self.chars() {
+            Some(chars) => chars[range].iter().collect(),
+            None => self.data.value[range].to_string(),
+        }
     }
 
+    /// Whether both are the same string, not only equal ones, e.g. two loads of one constant.
     #[inline]
-    pub fn as_str(&self) -> &str {
-        self.value.as_str()
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
     }
 
     pub fn deep_clone(&self) -> Self {
-        Self {
-            value: Rc::new(self.value.as_str().to_string()),
-            chars: None,
-        }
+        Self::new(self.as_str().to_string())
+    }
+}
+
+impl From<&str> for StringObject {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl std::fmt::Debug for StringObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("StringObject").field(&self.as_str()).finish()
     }
 }
 
 impl PartialEq for StringObject {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.value.eq(&other.value)
+        self.ptr_eq(other) || self.as_str().eq(other.as_str())
     }
 }
 
+impl Eq for StringObject {}
+
 impl PartialOrd for StringObject {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
@@ -56,34 +109,208 @@ impl PartialOrd for StringObject {
 impl Ord for StringObject {
     #[inline]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.value.cmp(&other.value)
+        self.as_str().cmp(other.as_str())
     }
 }
 
 impl std::fmt::Display for StringObject {
     #[inline]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        f.write_str(self.as_str())
     }
 }
 
 pub fn run_string_method(
     string: StringObject,
-    name: &str,
+    method: BuiltinMethod,
     args: &[Object],
-) -> Result<Object, String> {
-    match name {
+) -> Result<Object, RuntimeError> {
+    match method {
         // len() -> Int
-        "len" => {
+        BuiltinMethod::Len => {
             extract_argument!(args, []);
-            Ok(Object::Int(string.get_chars().len() as i64))
+            Ok(Object::Int(string.char_len() as i64))
         }
 
         // to_string() -> String
-        "to_string" => {
+        BuiltinMethod::ToString => {
             extract_argument!(args, []);
             Ok(Object::String(string))
         }
-        _ => Err(format!("{} is not a method of string", name)),
+
+        // to_bytes() -> Bytes
+        BuiltinMethod::ToBytes => {
+            extract_argument!(args, []);
+            Ok(Object::new_bytes(string.as_str().as_bytes().to_vec()))
+        }
+
+        // split(sep: String) -> Array
+        BuiltinMethod::Split => {
+            let sep = extract_argument!(args, [String]);
+            if sep.as_str().is_empty() {
+                return Err("Separator must not be empty".into());
+            }
+            let parts = string
+                .as_str()
+                .split(sep.as_str())
+                .map(|part| Object::new_string(part.to_string()))
+                .collect();
+            Ok(Object::new_array(ArrayObject::new(parts)))
+        }
+
+        // trim() -> String
+        BuiltinMethod::Trim => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(string.as_str().trim().to_string()))
+        }
+
+        // trim_start() -> String
+        BuiltinMethod::TrimStart => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(string.as_str().trim_start().to_string()))
+        }
+
+        // trim_end() -> String
+        BuiltinMethod::TrimEnd => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(string.as_str().trim_end().to_string()))
+        }
+
+        // replace(from: String, to: String) -> String
+        BuiltinMethod::Replace => {
+            let (from, to) = extract_argument!(args, [String, String]);
+            let replaced = string.as_str().replace(from.as_str(), to.as_str());
+            Ok(Object::new_string(replaced))
+        }
+
+        // find(sub: String) -> Int | Nil
+        // The index is in characters, as for indexing the string.
+        BuiltinMethod::Find => {
+            let sub = extract_argument!(args, [String]);
+            let string = string.as_str();
+            Ok(match string.find(sub.as_str()) {
+                Some(byte_index) => Object::Int(string[..byte_index].chars().count() as i64),
+                None => Object::Nil,
+            })
+        }
+
+        // contains(sub: String) -> Bool
+        BuiltinMethod::Contains => {
+            let sub = extract_argument!(args, [String]);
+            Ok(Object::Bool(string.as_str().contains(sub.as_str())))
+        }
+
+        // starts_with(prefix: String) -> Bool
+        BuiltinMethod::StartsWith => {
+            let prefix = extract_argument!(args, [String]);
+            Ok(Object::Bool(string.as_str().starts_with(prefix.as_str())))
+        }
+
+        // ends_with(suffix: String) -> Bool
+        BuiltinMethod::EndsWith => {
+            let suffix = extract_argument!(args, [String]);
+            Ok(Object::Bool(string.as_str().ends_with(suffix.as_str())))
+        }
+
+        // repeat(n: Int) -> String
+        BuiltinMethod::Repeat => {
+            let n = extract_argument!(args, [Int]);
+            let Ok(n) = usize::try_from(n) else {
+                return Err(format!("Count must not be negative, got {}", n).into());
+            };
+            let too_long = || format!("repeating the string {} times is too long", n);
+            let Some(len) = string.as_str().len().checked_mul(n) else {
+                return Err(too_long().into());
+            };
+            let mut repeated = String::new();
+            if repeated.try_reserve_exact(len).is_err() {
+                return Err(too_long().into());
+            }
+            repeated.extend(std::iter::repeat_n(string.as_str(), n));
+            Ok(Object::new_string(repeated))
+        }
+
+        // pad_start(width: Int, fill?: String) -> String
+        BuiltinMethod::PadStart => {
+            let (padding, fill) = padding(&string, args)?;
+            let mut padded = String::with_capacity(string.as_str().len() + padding);
+            padded.extend(std::iter::repeat_n(fill, padding));
+            padded.push_str(string.as_str());
+            Ok(Object::new_string(padded))
+        }
+
+        // pad_end(width: Int, fill?: String) -> String
+        BuiltinMethod::PadEnd => {
+            let (padding, fill) = padding(&string, args)?;
+            let mut padded = String::with_capacity(string.as_str().len() + padding);
+            padded.push_str(string.as_str());
+            padded.extend(std::iter::repeat_n(fill, padding));
+            Ok(Object::new_string(padded))
+        }
+
+        // lower() -> String
+        BuiltinMethod::Lower => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(string.as_str().to_lowercase()))
+        }
+
+        // upper() -> String
+        BuiltinMethod::Upper => {
+            extract_argument!(args, []);
+            Ok(Object::new_string(string.as_str().to_uppercase()))
+        }
+
+        // chars() -> Array
+        BuiltinMethod::Chars => {
+            extract_argument!(args, []);
+            let chars = string
+                .as_str()
+                .chars()
+                .map(|c| Object::new_string(c.to_string()))
+                .collect();
+            Ok(Object::new_array(ArrayObject::new(chars)))
+        }
+
+        // bytes() -> Array
+        BuiltinMethod::Bytes => {
+            extract_argument!(args, []);
+            let bytes = string
+                .as_str()
+                .bytes()
+                .map(|b| Object::Int(b as i64))
+                .collect();
+            Ok(Object::new_array(ArrayObject::new(bytes)))
+        }
+        _ => Err(RuntimeError::type_error(format!(
+            "{} is not a method of string",
+            method.name()
+        ))),
+    }
+}
+
+// The number of `fill` characters to pad `string` with, up to `width` characters in total.
+// `fill` is a space when it is left out.
+fn padding(string: &StringObject, args: &[Object]) -> Result<(usize, char), RuntimeError> {
+    let (width, fill) = match args {
+        [width] => (width, None),
+        [fill, width] => (width, Some(fill)),
+        _ => return Err(RuntimeError::arity("1 or 2", args.len())),
+    };
+    let width = width.clone().ensure_int()?;
+    let fill = match fill {
+        None => ' ',
+        Some(fill) => {
+            let fill = fill.clone().ensure_string()?;
+            let mut chars = fill.as_str().chars();
+            match (chars.next(), chars.next()) {
+                (Some(fill), None) => fill,
+                _ => return Err("Fill must be exactly one character".into()),
+            }
+        }
+    };
+    let width = usize::try_from(width).unwrap_or(0);
+    if width > u16::MAX as usize {
+        return Err(format!("width should be at most {}, got {}", u16::MAX, width).into());
     }
+    Ok((width.saturating_sub(string.char_len()), fill))
 }
@@ -1,29 +1,46 @@
 use super::*;
+use hashbrown::{Equivalent, HashMap};
+use rustc_hash::FxBuildHasher;
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
     ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct TableObject {
-    value: HashMap<Cow<'static, str>, Object>,
-    methods: Option<HashMap<Cow<'static, str>, TableMethod>>,
+    value: HashMap<TableKey, Object, FxBuildHasher>,
+    methods: Option<HashMap<Cow<'static, str>, TableMethod, FxBuildHasher>>,
+    shape: u64,
+}
+
+// Shapes are never reused, so that a cached shape can't match a different set of methods.
+static NEXT_SHAPE: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableKey {
+    String(Cow<'static, str>),
+    Int(i64),
+    Bool(bool),
 }
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum TableMethod {
     #[allow(clippy::type_complexity)]
-    Builtin(fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, String>),
+    Builtin(fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, RuntimeError>),
     Custom(Rc<FunctionObject>),
     CustomNoSelf(Rc<FunctionObject>),
 }
 
 impl TableObject {
-    pub fn new(value: HashMap<Cow<'static, str>, Object>) -> Self {
+    pub fn new(value: HashMap<TableKey, Object, FxBuildHasher>) -> Self {
         Self {
             value,
             methods: None,
+            shape: 0,
         }
     }
 
@@ -39,17 +56,29 @@ impl TableObject {
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect()
         });
-        Self { value, methods }
+        Self {
+            value,
+            methods,
+            shape: self.shape,
+        }
     }
 
     pub fn add_method(&mut self, name: impl Into<Cow<'static, str>>, func: impl Into<TableMethod>) {
         if let Some(methods) = &mut self.methods {
             methods.insert(name.into(), func.into());
         } else {
-            let mut methods = HashMap::new();
+            let mut methods = HashMap::default();
             methods.insert(name.into(), func.into());
             self.methods = Some(methods);
         }
+        self.shape = NEXT_SHAPE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Identifies the methods of the table: tables of the same shape have the same methods, and
+    /// adding a method gives the table a new shape. A table without methods has shape 0.
+    #[inline]
+    pub fn shape(&self) -> u64 {
+        self.shape
     }
 
     pub fn get_method(&self, name: &str) -> Option<TableMethod> {
@@ -65,20 +94,101 @@ impl TableObject {
     }
 }
 
+// The shape only caches what the methods are, so it doesn't take part in equality.
+impl PartialEq for TableObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.methods == other.methods
+    }
+}
+
+impl TableKey {
+    pub fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::String(string) => Ok(TableKey::String(string.to_string().into())),
+            Object::Int(int) => Ok(TableKey::Int(int)),
+            Object::Bool(boolean) => Ok(TableKey::Bool(boolean)),
+            x => Err(RuntimeError::type_error(format!(
+                "Expected `string`, `int` or `bool` as table key, got `{}`",
+                x.typename()
+            ))),
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            TableKey::String(string) => Object::new_string(string.to_string()),
+            TableKey::Int(int) => Object::Int(*int),
+            TableKey::Bool(boolean) => Object::Bool(*boolean),
+        }
+    }
+}
+
+// `str` and `TableKey::String` must produce the same hash so that a table can be looked up by
+// `&str` without allocating a `TableKey`.
+impl Hash for TableKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            TableKey::String(string) => string.as_ref().hash(state),
+            TableKey::Int(int) => int.hash(state),
+            TableKey::Bool(boolean) => boolean.hash(state),
+        }
+    }
+}
+
+impl Equivalent<TableKey> for str {
+    fn equivalent(&self, key: &TableKey) -> bool {
+        matches!(key, TableKey::String(string) if string == self)
+    }
+}
+
+impl From<&'static str> for TableKey {
+    fn from(value: &'static str) -> Self {
+        TableKey::String(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for TableKey {
+    fn from(value: String) -> Self {
+        TableKey::String(Cow::Owned(value))
+    }
+}
+
+impl From<i64> for TableKey {
+    fn from(value: i64) -> Self {
+        TableKey::Int(value)
+    }
+}
+
+impl From<bool> for TableKey {
+    fn from(value: bool) -> Self {
+        TableKey::Bool(value)
+    }
+}
+
+impl fmt::Display for TableKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableKey::String(string) => write!(f, "{}", string),
+            TableKey::Int(int) => write!(f, "{}", int),
+            TableKey::Bool(boolean) => write!(f, "{}", boolean),
+        }
+    }
+}
+
 impl From<FunctionObject> for TableMethod {
     fn from(func: FunctionObject) -> Self {
         Self::Custom(Rc::new(func))
     }
 }
 
-impl From<fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, String>> for TableMethod {
-    fn from(func: fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, String>) -> Self {
+impl From<fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, RuntimeError>> for TableMethod {
+    fn from(func: fn(Rc<RefCell<TableObject>>, &[Object]) -> Result<Object, RuntimeError>) -> Self {
         Self::Builtin(func)
     }
 }
 
 impl Deref for TableObject {
-    type Target = HashMap<Cow<'static, str>, Object>;
+    type Target = HashMap<TableKey, Object, FxBuildHasher>;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
@@ -95,25 +205,20 @@ impl DerefMut for TableObject {
 
 pub fn run_table_default_method(
     table: Rc<RefCell<TableObject>>,
-    name: &str,
+    method: BuiltinMethod,
     args: &[Object],
-) -> Result<Object, String> {
-    match name {
+) -> Result<Object, RuntimeError> {
+    match method {
         // keys() -> Array
-        "keys" => {
+        BuiltinMethod::Keys => {
             extract_argument!(args, []);
-            let keys = table
-                .borrow()
-                .keys()
-                .cloned()
-                .map(|s| Object::new_string(s.to_string()))
-                .collect();
+            let keys = table.borrow().keys().map(TableKey::to_object).collect();
             let array = ArrayObject::new(keys);
             Ok(Object::new_array(array))
         }
 
         // values() -> Array
-        "values" => {
+        BuiltinMethod::Values => {
             extract_argument!(args, []);
             let values = table.borrow().values().cloned().collect();
             let array = ArrayObject::new(values);
@@ -121,25 +226,27 @@ pub fn run_table_default_method(
         }
 
         // len() -> Int
-        "len" => {
+        BuiltinMethod::Len => {
             extract_argument!(args, []);
             Ok(Object::Int(table.borrow().len() as i64))
         }
 
-        // contains(key: String) -> Bool
-        "contains" => {
-            let key = extract_argument!(args, [String]);
-            Ok(Object::Bool(table.borrow().contains_key(key.as_str())))
+        // contains(key: String | Int | Bool) -> Bool
+        BuiltinMethod::Contains => {
+            ensure_argument_length!(args, 1);
+            let key = TableKey::from_object(args[0].clone())?;
+            Ok(Object::Bool(table.borrow().contains_key(&key)))
         }
 
-        // remove(key: String) -> Any
-        "remove" => {
-            let key = extract_argument!(args, [String]);
-            Ok(table
-                .borrow_mut()
-                .remove(key.as_str())
-                .unwrap_or(Object::Nil))
+        // remove(key: String | Int | Bool) -> Any
+        BuiltinMethod::Remove => {
+            ensure_argument_length!(args, 1);
+            let key = TableKey::from_object(args[0].clone())?;
+            Ok(table.borrow_mut().remove(&key).unwrap_or(Object::Nil))
         }
-        _ => Err(format!("table has no method {}", name)),
+        _ => Err(RuntimeError::type_error(format!(
+            "table has no method {}",
+            method.name()
+        ))),
     }
 }
@@ -0,0 +1,64 @@
+use super::*;
+use std::{any::Any, cell::RefMut};
+
+/// A native value such as an open file, which scripts can only hold, pass to builtins, and call
+/// the methods given on creation.
+pub struct UserdataObject {
+    name: &'static str,
+    value: RefCell<Box<dyn Any>>,
+    methods: &'static [(&'static str, UserdataMethod)],
+}
+
+pub type UserdataMethod = fn(&UserdataObject, &[Object]) -> Result<Object, RuntimeError>;
+
+impl UserdataObject {
+    pub fn new<T: Any>(name: &'static str, value: T) -> Self {
+        Self::with_methods(name, value, &[])
+    }
+
+    pub fn with_methods<T: Any>(
+        name: &'static str,
+        value: T,
+        methods: &'static [(&'static str, UserdataMethod)],
+    ) -> Self {
+        Self {
+            name,
+            value: RefCell::new(Box::new(value)),
+            methods,
+        }
+    }
+
+    pub fn get_method(&self, name: &str) -> Option<UserdataMethod> {
+        self.methods
+            .iter()
+            .find(|(method, _)| *method == name)
+            .map(|(_, func)| *func)
+    }
+
+    /// The kind of the native value, e.g. "file".
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn borrow_mut<T: Any>(&self) -> Result<RefMut<'_, T>, RuntimeError> {
+        let value = self
+            .value
+            .try_borrow_mut()
+            .map_err(|_| format!("{} is already in use", self.name))?;
+        RefMut::filter_map(value, |value| value.downcast_mut::<T>()).map_err(|_| {
+            RuntimeError::type_error(format!("Expected {}, got another userdata", self.name))
+        })
+    }
+}
+
+impl PartialEq for UserdataObject {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl std::fmt::Debug for UserdataObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UserdataObject").field(&self.name).finish()
+    }
+}
@@ -0,0 +1,144 @@
+use super::*;
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// What a script may do through the builtins beyond computing: touching files, starting
+/// processes and loading native code. Everything is allowed by default; embedders running
+/// untrusted scripts restrict it, so that the builtins fail with [`ErrorKind::PermissionDenied`]
+/// instead of reaching the system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permissions {
+    /// Reading files and listing directories, e.g. `fs.read`, `fs.lines` and `fs.walk`.
+    pub read: FileAccess,
+    /// Creating and writing files, e.g. `fs.write`, `fs.open(path, "w")` and `fs.lock`.
+    pub write: FileAccess,
+    /// Starting processes with `subprocess.spawn`, which can do anything the host can.
+    pub spawn: bool,
+    /// Loading native libraries with `ffi.open` and `plugin.load`, whose code runs unchecked.
+    pub native_code: bool,
+    /// Pausing with `time.sleep`, e.g. denied to scripts that should use no more than their
+    /// running time.
+    pub sleep: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            read: FileAccess::All,
+            write: FileAccess::All,
+            spawn: true,
+            native_code: true,
+            sleep: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FileAccess {
+    #[default]
+    All,
+    Nothing,
+    /// Only the listed files and the files under the listed directories. Paths are compared after
+    /// resolving `..` and symbolic links, so that a script cannot get out of them.
+    Only(Vec<PathBuf>),
+}
+
+thread_local! {
+    // The permissions of the runtime being executed on this thread, for the builtins that are
+    // plain Rust functions and can't see the runtime.
+    static CURRENT: RefCell<Rc<Permissions>> = RefCell::default();
+}
+
+impl Permissions {
+    /// Denies reading and writing any file, and starting processes or loading native code, which
+    /// could do it anyway.
+    pub fn no_files() -> Self {
+        Self {
+            read: FileAccess::Nothing,
+            write: FileAccess::Nothing,
+            spawn: false,
+            native_code: false,
+            sleep: true,
+        }
+    }
+
+    /// The permissions of the runtime being executed on this thread, or the default ones outside
+    /// of [`execute`](crate::execute).
+    pub fn current() -> Rc<Permissions> {
+        CURRENT.with(|current| Rc::clone(&current.borrow()))
+    }
+
+    /// Makes `permissions` the current ones until the guard is dropped.
+    pub(crate) fn enter(permissions: Rc<Permissions>) -> PermissionsGuard {
+        PermissionsGuard(CURRENT.with(|current| current.replace(permissions)))
+    }
+
+    pub fn check_read(&self, path: &str) -> Result<(), RuntimeError> {
+        check(&self.read, "read", path)
+    }
+
+    pub fn check_write(&self, path: &str) -> Result<(), RuntimeError> {
+        check(&self.write, "write", path)
+    }
+
+    pub fn check_spawn(&self, program: &str) -> Result<(), RuntimeError> {
+        allow(self.spawn, "spawn a process", program)
+    }
+
+    pub fn check_native_code(&self, path: &str) -> Result<(), RuntimeError> {
+        allow(self.native_code, "load native code", path)
+    }
+
+    pub fn check_sleep(&self) -> Result<(), RuntimeError> {
+        allow(self.sleep, "sleep", "time.sleep")
+    }
+}
+
+/// Restores the permissions that were current before [`Permissions::enter`].
+pub(crate) struct PermissionsGuard(Rc<Permissions>);
+
+impl Drop for PermissionsGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.replace(Rc::clone(&self.0)));
+    }
+}
+
+fn check(access: &FileAccess, action: &str, path: &str) -> Result<(), RuntimeError> {
+    let allowed = match access {
+        FileAccess::All => true,
+        FileAccess::Nothing => false,
+        FileAccess::Only(roots) => resolve(Path::new(path)).is_some_and(|path| {
+            roots
+                .iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .any(|root| path.starts_with(root))
+        }),
+    };
+    allow(allowed, action, path)
+}
+
+fn allow(allowed: bool, action: &str, subject: &str) -> Result<(), RuntimeError> {
+    match allowed {
+        true => Ok(()),
+        false => Err(RuntimeError::new(
+            ErrorKind::PermissionDenied,
+            format!("{}: Not permitted to {}", subject, action),
+        )),
+    }
+}
+
+// A path that doesn't exist yet, e.g. a file to create, is resolved through its parent.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+    let name = path.file_name()?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(name))
+}
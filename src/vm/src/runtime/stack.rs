@@ -6,16 +6,80 @@ pub struct Stack {
 }
 
 impl Stack {
+    /// The capacity kept even after shrinking, enough for most scripts to never reallocate.
+    pub const RETAINED_CAPACITY: usize = 256;
+
     #[inline]
     pub const fn new() -> Self {
         Self { vec: Vec::new() }
     }
 
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            vec: Vec::with_capacity(capacity),
+        }
+    }
+
     #[inline]
     pub fn push(&mut self, value: StackValue) {
         self.vec.push(value);
     }
 
+    #[inline]
+    pub fn top_mut(&mut self) -> &mut StackValue {
+        self.vec
+            .last_mut()
+            .expect("[BUG] Stack must have at least one value at top_mut.")
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Releases the memory grown beyond [`Self::RETAINED_CAPACITY`], e.g. by deep recursion.
+    ///
+    /// The capacity itself serves as the high-water mark, so that `push` doesn't need to track it.
+    pub fn shrink(&mut self) {
+        if self.vec.capacity() > Self::RETAINED_CAPACITY {
+            self.vec
+                .shrink_to(self.vec.len().max(Self::RETAINED_CAPACITY));
+        }
+    }
+
+    /// Discards the values above the first `len`, e.g. those left behind by an error.
+    pub fn truncate(&mut self, len: usize) {
+        self.vec.truncate(len);
+    }
+
+    /// Takes out the values above the first `len`, e.g. those of a suspended coroutine.
+    pub fn split_off(&mut self, len: usize) -> Vec<StackValue> {
+        self.vec.split_off(len)
+    }
+
+    /// Pushes `values` in order, e.g. those taken by [`Self::split_off`].
+    pub fn extend(&mut self, values: Vec<StackValue>) {
+        self.vec.extend(values);
+    }
+
+    /// Pops the topmost `count` values as objects, keeping the order in which they were pushed.
+    pub fn pop_objects(&mut self, count: usize) -> Vec<Object> {
+        let start = self
+            .vec
+            .len()
+            .checked_sub(count)
+            .expect("[BUG] Stack must have enough values at pop_objects.");
+        self.vec
+            .drain(start..)
+            .map(StackValue::ensure_object)
+            .collect()
+    }
+
     pub fn pop(&mut self) -> StackValue {
         self.vec
             .pop()
@@ -32,23 +96,26 @@ impl Stack {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum StackValue {
-    RawArray(Vec<Object>),
+    RawArray(Box<Vec<Object>>),
     Object(Object),
-    Named(Rc<String>, Object),
+    Named(Box<(TableKey, Object)>),
 }
 
+// The rare variants are boxed so that a push or pop moves no more than an `Object`.
+const _: () = assert!(std::mem::size_of::<StackValue>() == std::mem::size_of::<Object>());
+
 impl StackValue {
     pub fn ensure_object(self) -> Object {
         match self {
-            StackValue::RawArray(array) => Object::new_array(ArrayObject::new(array)),
+            StackValue::RawArray(array) => Object::new_array(ArrayObject::new(*array)),
             StackValue::Object(obj) => obj,
             x => panic!("[BUG] Expected Object, but got {:?}", x),
         }
     }
 
-    pub fn ensure_named(self) -> (Rc<String>, Object) {
+    pub fn ensure_named(self) -> (TableKey, Object) {
         match self {
-            StackValue::Named(name, obj) => (name, obj),
+            StackValue::Named(named) => *named,
             x => panic!("[BUG] Expected Named, but got {:?}", x),
         }
     }
@@ -63,15 +130,14 @@ macro_rules! impl_from {
         }
     };
 }
-impl_from!(Vec<Object> => RawArray);
 impl_from!(Object => Object);
-impl From<(Rc<String>, Object)> for StackValue {
-    fn from(value: (Rc<String>, Object)) -> Self {
-        Self::Named(value.0, value.1)
+impl From<Vec<Object>> for StackValue {
+    fn from(value: Vec<Object>) -> Self {
+        Self::RawArray(Box::new(value))
     }
 }
-impl From<(StringObject, Object)> for StackValue {
-    fn from(value: (StringObject, Object)) -> Self {
-        Self::Named(Rc::clone(value.0.inner()), value.1)
+impl From<(TableKey, Object)> for StackValue {
+    fn from(value: (TableKey, Object)) -> Self {
+        Self::Named(Box::new(value))
     }
 }
@@ -0,0 +1,11 @@
+/// Counters of what the VM did, to measure the effect of its optimizations. They are never reset
+/// by the VM.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Instructions dispatched, which the register instructions bring down.
+    pub instructions: u64,
+    /// Method calls on a table that found the method in the cache of their call site.
+    pub method_cache_hits: u64,
+    /// Method calls on a table with methods that had to look the method up by name.
+    pub method_cache_misses: u64,
+}
@@ -1,4 +1,5 @@
-use std::io::{Stderr, Stdin, Stdout, Write};
+use crate::{utf8::read_char, ErrorKind, RuntimeError};
+use std::io::{IsTerminal, Read, Stderr, Stdin, Stdout, Write};
 
 #[derive(Debug, Default)]
 pub struct Stdio {
@@ -7,6 +8,10 @@ pub struct Stdio {
     pub stderr: Option<Stderr>,
 }
 
+fn stdin_error(err: std::io::Error) -> RuntimeError {
+    RuntimeError::new(ErrorKind::Io, format!("Failed to read from stdin: {}", err))
+}
+
 impl Stdio {
     #[inline]
     pub const fn new() -> Self {
@@ -45,12 +50,201 @@ impl Stdio {
             .expect("Failed to flush stderr.");
     }
 
-    pub fn read_line(&mut self) -> String {
+    /// Reads a line without its line ending, or `None` at the end of input.
+    pub fn read_line(&mut self) -> Result<Option<String>, RuntimeError> {
         let mut buf = String::new();
-        self.stdin
+        let len = self
+            .stdin
             .get_or_insert_with(std::io::stdin)
             .read_line(&mut buf)
-            .expect("Failed to read from stdin.");
-        buf
+            .map_err(stdin_error)?;
+        if len == 0 {
+            return Ok(None);
+        }
+        if buf.ends_with('\n') {
+            buf.pop();
+            if buf.ends_with('\r') {
+                buf.pop();
+            }
+        }
+        Ok(Some(buf))
+    }
+
+    /// Reads everything until the end of input.
+    pub fn read_all(&mut self) -> Result<String, RuntimeError> {
+        let mut buf = String::new();
+        self.stdin
+            .get_or_insert_with(std::io::stdin)
+            .read_to_string(&mut buf)
+            .map_err(stdin_error)?;
+        Ok(buf)
+    }
+
+    /// Reads up to `count` characters, or `None` if the input has already ended.
+    pub fn read_chars(&mut self, count: usize) -> Result<Option<String>, RuntimeError> {
+        let stdin = self.stdin.get_or_insert_with(std::io::stdin).lock();
+        let mut bytes = stdin.bytes();
+        let mut next_byte = || bytes.next().transpose().map_err(stdin_error);
+        let mut string = String::new();
+        for _ in 0..count {
+            match read_char(&mut next_byte)? {
+                Some(char) => string.push(char),
+                None => break,
+            }
+        }
+        if string.is_empty() && count > 0 {
+            return Ok(None);
+        }
+        Ok(Some(string))
+    }
+
+    /// Whether stdout is a terminal rather than a file or a pipe.
+    pub fn is_terminal(&mut self) -> bool {
+        self.stdout
+            .get_or_insert_with(std::io::stdout)
+            .is_terminal()
+    }
+
+    /// The size of the terminal as (columns, rows), or `None` if stdout is not a terminal.
+    #[cfg(unix)]
+    pub fn terminal_size(&mut self) -> Option<(u16, u16)> {
+        use std::os::fd::AsRawFd;
+
+        let stdout = self.stdout.get_or_insert_with(std::io::stdout);
+        let mut size = unsafe { std::mem::zeroed::<libc::winsize>() };
+        let res = unsafe { libc::ioctl(stdout.as_raw_fd(), libc::TIOCGWINSZ, &mut size) };
+        (res == 0 && size.ws_col > 0).then_some((size.ws_col, size.ws_row))
+    }
+
+    #[cfg(not(unix))]
+    pub fn terminal_size(&mut self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// Clears the screen and moves the cursor to the top left.
+    pub fn clear_screen(&mut self) {
+        self.write("\x1b[2J\x1b[H");
+        self.flush();
+    }
+
+    /// Moves the cursor to `column` and `row`, both starting at 1.
+    pub fn move_cursor(&mut self, column: u16, row: u16) {
+        self.write(format!("\x1b[{};{}H", row, column));
+        self.flush();
+    }
+
+    pub fn show_cursor(&mut self, visible: bool) {
+        self.write(if visible { "\x1b[?25h" } else { "\x1b[?25l" });
+        self.flush();
+    }
+
+    /// Reads a single key press without waiting for Enter, or `None` at the end of input.
+    ///
+    /// Printable keys are returned as themselves, and the others by name: "enter", "tab",
+    /// "backspace", "escape", "up", "down", "left", "right", "home", "end", "delete", and
+    /// "ctrl-a" to "ctrl-z". When stdin is not a terminal, this reads a character instead.
+    #[cfg(feature = "term")]
+    pub fn read_key(&mut self) -> Result<Option<String>, RuntimeError> {
+        let stdin = self.stdin.get_or_insert_with(std::io::stdin);
+        if !stdin.is_terminal() {
+            let key = self.read_chars(1)?;
+            return Ok(key.map(|key| match key.as_str() {
+                "\n" => "enter".to_string(),
+                _ => key,
+            }));
+        }
+        let _raw = RawMode::enable(stdin)?;
+        let mut stdin = stdin.lock();
+        let mut read_byte = || -> Result<Option<u8>, RuntimeError> {
+            let mut byte = [0];
+            match stdin.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(e) => Err(stdin_error(e)),
+            }
+        };
+        // Raw mode reads time out, so wait for the first byte, and take a timeout after it as the
+        // end of the key (e.g. a lone Escape rather than the start of an arrow key).
+        let lead = loop {
+            if let Some(byte) = read_byte()? {
+                break byte;
+            }
+        };
+        let key = match lead {
+            b'\r' | b'\n' => "enter".to_string(),
+            b'\t' => "tab".to_string(),
+            0x7f | 0x08 => "backspace".to_string(),
+            0x1b => match (read_byte()?, read_byte()?) {
+                (Some(b'[' | b'O'), Some(b'A')) => "up".to_string(),
+                (Some(b'[' | b'O'), Some(b'B')) => "down".to_string(),
+                (Some(b'[' | b'O'), Some(b'C')) => "right".to_string(),
+                (Some(b'[' | b'O'), Some(b'D')) => "left".to_string(),
+                (Some(b'[' | b'O'), Some(b'H')) => "home".to_string(),
+                (Some(b'[' | b'O'), Some(b'F')) => "end".to_string(),
+                (Some(b'['), Some(b'3')) if read_byte()? == Some(b'~') => "delete".to_string(),
+                _ => "escape".to_string(),
+            },
+            ctrl @ 0x01..=0x1a => format!("ctrl-{}", (b'a' + ctrl - 1) as char),
+            lead => {
+                let mut lead = Some(lead);
+                let next_byte = || match lead.take() {
+                    Some(lead) => Ok(Some(lead)),
+                    None => read_byte(),
+                };
+                read_char(next_byte)?.map_or_else(String::new, String::from)
+            }
+        };
+        Ok(Some(key))
+    }
+}
+
+/// Puts the terminal into raw mode until dropped, so that the settings are restored even if
+/// reading fails.
+#[cfg(feature = "term")]
+struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+#[cfg(all(feature = "term", unix))]
+impl RawMode {
+    fn enable(stdin: &Stdin) -> Result<Self, RuntimeError> {
+        use std::os::fd::AsRawFd;
+
+        let fd = stdin.as_raw_fd();
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+            let err = std::io::Error::last_os_error();
+            return Err(RuntimeError::new(ErrorKind::Io, err.to_string()));
+        }
+        let mut raw = original;
+        // Keys are read one by one without echo, and Ctrl-C is read as a key instead of a signal.
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+        // A read waits at most 0.1 seconds, and returns as soon as a byte is available.
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 1;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            let err = std::io::Error::last_os_error();
+            return Err(RuntimeError::new(ErrorKind::Io, err.to_string()));
+        }
+        Ok(Self { original })
+    }
+}
+
+#[cfg(all(feature = "term", unix))]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        use std::os::fd::AsRawFd;
+
+        let fd = std::io::stdin().as_raw_fd();
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &self.original) };
+    }
+}
+
+#[cfg(all(feature = "term", not(unix)))]
+impl RawMode {
+    fn enable(_stdin: &Stdin) -> Result<Self, RuntimeError> {
+        Err("Reading keys is not supported on this platform".into())
     }
 }
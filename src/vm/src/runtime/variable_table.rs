@@ -1,175 +1,187 @@
 use super::*;
 
+/// Local variables of all active frames, stored in one flat vector.
+///
+/// Each frame owns the entities from its base offset to the end (or to the next frame's base), so
+/// `LocalId(n)` of the current frame is simply `entities[base + n]`.
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct VariableTable {
-    scopes: Vec<internal::Scope>,
+    entities: Vec<Entity>,
+    frame_bases: Vec<usize>,
+    base: usize,
+}
+
+/// Scopes taken out of a [`VariableTable`], with their bases relative to the first one.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Scopes {
+    entities: Vec<Entity>,
+    bases: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Entity {
+    Value(Object),
+    Shared(Rc<RefCell<Object>>),
 }
 
 impl VariableTable {
+    /// The capacity kept even after shrinking.
+    const RETAINED_CAPACITY: usize = 256;
+
     #[inline]
     pub fn new() -> Self {
         Self {
-            scopes: vec![internal::Scope::new()],
+            entities: Vec::with_capacity(Self::RETAINED_CAPACITY),
+            frame_bases: Vec::new(),
+            base: 0,
         }
     }
 
     #[inline]
     pub fn push_scope(&mut self) {
-        self.scopes.push(internal::Scope::new());
+        self.frame_bases.push(self.base);
+        self.base = self.entities.len();
     }
 
     pub fn pop_scope(&mut self) {
-        self.scopes
+        let base = self
+            .frame_bases
             .pop()
             .expect("[BUG] This should be called in at least one scope.");
+        self.entities.truncate(self.base);
+        self.base = base;
     }
 
-    pub fn push(&mut self, object: Object) {
-        self.scopes
-            .last_mut()
-            .expect("[BUG] This should be called in at least one scope.")
-            .push(internal::Entity::Value(object));
-    }
-
-    pub fn push_ref(&mut self, ref_object: Rc<RefCell<Object>>) {
-        self.scopes
-            .last_mut()
-            .expect("[BUG] This should be called in at least one scope.")
-            .push(internal::Entity::Shared(ref_object));
-    }
-
-    pub fn drop(&mut self, count: usize) {
-        self.scopes
-            .last_mut()
-            .expect("[BUG] This should be called in at least one scope.")
-            .drop(count);
+    /// Returns the number of scopes, which is 1 at the top level.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.frame_bases.len() + 1
     }
 
-    pub fn edit(&mut self, id: LocalId, object: Object) {
-        self.scopes
-            .last_mut()
-            .expect("[BUG] This should be called in at least one scope.")
-            .edit(id, object)
+    #[inline]
+    pub fn push(&mut self, object: Object) {
+        self.entities.push(Entity::Value(object));
     }
 
-    pub fn get(&self, id: LocalId) -> Object {
-        self.scopes
-            .last()
-            .expect("[BUG] This should be called in at least one scope.")
-            .get(id)
+    #[inline]
+    pub fn push_ref(&mut self, ref_object: Rc<RefCell<Object>>) {
+        self.entities.push(Entity::Shared(ref_object));
     }
 
-    pub fn get_ref(&mut self, id: LocalId) -> Rc<RefCell<Object>> {
-        self.scopes
-            .last_mut()
-            .expect("[BUG] This should be called in at least one scope.")
-            .get_ref(id)
+    /// Returns the number of variables in the current scope.
+    #[inline]
+    pub fn scope_len(&self) -> usize {
+        self.entities.len() - self.base
     }
 
-    pub fn dump(&self, indent: usize) {
-        println!("{}[VariableTable]", " ".repeat(indent));
-        for scope in self.scopes.iter() {
-            scope.dump(indent + 2);
+    /// Takes out the scopes after the first `depth` ones, e.g. those of a suspended coroutine, to
+    /// be put back by [`Self::restore_scopes`].
+    pub fn take_scopes(&mut self, depth: usize) -> Scopes {
+        assert!(
+            0 < depth && depth <= self.depth(),
+            "[BUG] Cannot take the scopes after {} of {}.",
+            depth,
+            self.depth()
+        );
+        let mut bases = self.frame_bases.split_off(depth - 1);
+        bases.push(self.base);
+        self.base = bases.remove(0);
+        let start = bases.first().copied().unwrap_or(self.entities.len());
+        Scopes {
+            entities: self.entities.split_off(start),
+            bases: bases.into_iter().map(|base| base - start).collect(),
         }
     }
-}
-
-mod internal {
-    use super::*;
 
-    #[derive(Clone, Debug, PartialEq)]
-    pub enum Entity {
-        Value(Object),
-        Shared(Rc<RefCell<Object>>),
+    /// Puts back the scopes taken by [`Self::take_scopes`] on top of the current ones.
+    pub fn restore_scopes(&mut self, scopes: Scopes) {
+        let start = self.entities.len();
+        for base in scopes.bases {
+            self.frame_bases.push(self.base);
+            self.base = start + base;
+        }
+        self.entities.extend(scopes.entities);
     }
 
-    #[derive(Default, Clone, Debug, PartialEq)]
-    pub struct Scope {
-        entities: Vec<Entity>,
+    pub fn drop(&mut self, count: usize) {
+        let len = self.entities.len() - self.base;
+        if count > len {
+            panic!(
+                "[BUG] Cannot drop {} variables because there are only {} variables in scope.",
+                count, len
+            );
+        }
+        self.entities.truncate(self.entities.len() - count);
     }
 
-    impl Scope {
-        #[inline]
-        pub const fn new() -> Self {
-            Self {
-                entities: Vec::new(),
-            }
+    pub fn edit(&mut self, id: LocalId, object: Object) {
+        match self.entity_mut(id) {
+            Entity::Value(value) => *value = object,
+            Entity::Shared(value) => *value.borrow_mut() = object,
         }
+    }
 
-        #[inline]
-        pub fn push(&mut self, entity: Entity) {
-            self.entities.push(entity);
+    pub fn get(&self, id: LocalId) -> Object {
+        match self.entities.get(self.base + id.0) {
+            Some(Entity::Value(object)) => object.clone(),
+            Some(Entity::Shared(object)) => object.borrow().clone(),
+            None => self.out_of_range(id),
         }
+    }
 
-        pub fn drop(&mut self, count: usize) {
-            if count > self.entities.len() {
-                panic!(
-                    "[BUG] Cannot drop {} variables because there are only {} variables in scope.",
-                    count,
-                    self.entities.len()
-                );
+    pub fn get_ref(&mut self, id: LocalId) -> Rc<RefCell<Object>> {
+        let entity = self.entity_mut(id);
+        match entity {
+            Entity::Value(object) => {
+                let res = Rc::new(RefCell::new(std::mem::replace(object, Object::Nil)));
+                *entity = Entity::Shared(Rc::clone(&res));
+                res
             }
-            self.entities.truncate(self.entities.len() - count);
+            Entity::Shared(object) => Rc::clone(object),
         }
+    }
 
-        pub fn get(&self, id: LocalId) -> Object {
-            if let Some(entity) = self.entities.get(id.0) {
-                match entity {
-                    Entity::Value(object) => object.clone(),
-                    Entity::Shared(object) => object.borrow().clone(),
-                }
-            } else {
-                panic!(
-                    "[BUG] LocalId out of range. Expected 0..{}, but got {}.",
-                    self.entities.len(),
-                    id.0
-                );
-            }
+    /// Releases the memory grown beyond [`Self::RETAINED_CAPACITY`], e.g. by deep recursion.
+    pub fn shrink(&mut self) {
+        if self.entities.capacity() > Self::RETAINED_CAPACITY {
+            self.entities
+                .shrink_to(self.entities.len().max(Self::RETAINED_CAPACITY));
         }
+        self.frame_bases.shrink_to_fit();
+    }
 
-        pub fn get_ref(&mut self, id: LocalId) -> Rc<RefCell<Object>> {
-            if let Some(entity) = self.entities.get(id.0) {
-                match entity {
-                    Entity::Value(object) => {
-                        let res = Rc::new(RefCell::new(object.clone()));
-                        self.entities[id.0] = Entity::Shared(Rc::clone(&res));
-                        res
-                    }
-                    Entity::Shared(object) => Rc::clone(object),
-                }
-            } else {
-                panic!(
-                    "[BUG] LocalId out of range. Expected 0..{}, but got {}.",
-                    self.entities.len(),
-                    id.0
-                );
+    pub fn dump(&self, indent: usize) {
+        println!("{}[VariableTable]", " ".repeat(indent));
+        let bases = self
+            .frame_bases
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.base))
+            .collect::<Vec<_>>();
+        for (i, base) in bases.iter().enumerate() {
+            let end = bases.get(i + 1).copied().unwrap_or(self.entities.len());
+            println!("{}[Scope]", " ".repeat(indent + 2));
+            for (idx, entity) in self.entities[*base..end].iter().enumerate() {
+                println!("{}{idx}: {entity:?}", " ".repeat(indent + 4));
             }
         }
+    }
 
-        pub fn edit(&mut self, id: LocalId, object: Object) {
-            if let Some(entity) = self.entities.get(id.0) {
-                match entity {
-                    Entity::Value(_) => {
-                        self.entities[id.0] = Entity::Value(object);
-                    }
-                    Entity::Shared(entity) => {
-                        *(entity.borrow_mut()) = object;
-                    }
-                }
-            } else {
-                panic!(
-                    "[BUG] LocalId out of range. Expected 0..{}, but got {}.",
-                    self.entities.len(),
-                    id.0
-                );
-            }
+    #[inline]
+    fn entity_mut(&mut self, id: LocalId) -> &mut Entity {
+        let index = self.base + id.0;
+        if index >= self.entities.len() {
+            self.out_of_range(id);
         }
+        &mut self.entities[index]
+    }
 
-        pub fn dump(&self, indent: usize) {
-            println!("{}[Scope]", " ".repeat(indent));
-            for (idx, entity) in self.entities.iter().enumerate() {
-                println!("{}{idx}: {entity:?}", " ".repeat(indent + 2));
-            }
-        }
+    #[cold]
+    fn out_of_range(&self, id: LocalId) -> ! {
+        panic!(
+            "[BUG] LocalId out of range. Expected 0..{}, but got {}.",
+            self.entities.len() - self.base,
+            id.0
+        );
     }
 }
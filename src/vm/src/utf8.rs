@@ -0,0 +1,40 @@
+use crate::{ErrorKind, RuntimeError};
+
+/// Reads one UTF-8 encoded character from `next_byte`, which gives `None` at the end of the input.
+/// Returns `None` if the input ended before the character.
+///
+/// The lead byte tells how many continuation bytes follow. A byte that can't lead a character (a
+/// stray continuation byte, or 0xF8 to 0xFF), a character cut short, or one that is not valid
+/// UTF-8 anyway (e.g. an overlong encoding) is a decode error.
+pub(crate) fn read_char(
+    mut next_byte: impl FnMut() -> Result<Option<u8>, RuntimeError>,
+) -> Result<Option<char>, RuntimeError> {
+    let Some(lead) = next_byte()? else {
+        return Ok(None);
+    };
+    let width = match lead {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return Err(invalid(&[lead])),
+    };
+    let mut bytes = vec![lead];
+    while bytes.len() < width {
+        match next_byte()? {
+            Some(byte) => bytes.push(byte),
+            None => return Err(invalid(&bytes)),
+        }
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(char) => Ok(char.chars().next()),
+        Err(_) => Err(invalid(&bytes)),
+    }
+}
+
+fn invalid(bytes: &[u8]) -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::Io,
+        format!("Invalid UTF-8 sequence: {:02x?}", bytes),
+    )
+}
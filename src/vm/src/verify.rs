@@ -0,0 +1,436 @@
+use super::*;
+use std::fmt;
+
+/// Why [`verify`] rejected some code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyError {
+    pub kind: VerifyErrorKind,
+    /// The index of the offending instruction in the code of its function.
+    pub pc: usize,
+    /// Where the function is: the index of each `MakeClosure` leading to it, from the top level,
+    /// which is an empty path.
+    pub path: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyErrorKind {
+    /// A jump lands outside of the code.
+    JumpOutOfBounds { target: isize },
+    /// An instruction pops more values than the stack has.
+    StackUnderflow,
+    /// Two paths reach the instruction with different numbers of values on the stack.
+    StackMismatch { expected: usize, found: usize },
+    /// `ExitWith` runs without an open `with`, or two paths reach the instruction with different
+    /// numbers of them.
+    UnbalancedWith,
+    /// `EndTry` runs without an open `try`, or two paths reach the instruction with different
+    /// numbers of them.
+    UnbalancedTry,
+    /// Execution runs past the last instruction, which must be a return, an `Exit` or a jump.
+    FallsOffEnd,
+    /// A `Builtin` instruction is given the wrong number of arguments.
+    BuiltinArity { expected: u8, found: u8 },
+    /// A local variable is used that isn't in scope, of the `locals` that are.
+    LocalOutOfRange { id: usize, locals: usize },
+    /// `DropLocal` drops more local variables than are in scope, or than the open `try` made.
+    LocalUnderflow,
+    /// Two paths reach the instruction with different numbers of local variables.
+    LocalsMismatch { expected: usize, found: usize },
+    /// A value made by `MakeNamed` is used as an object, `MakeTable` is given a value that isn't
+    /// one, or two paths reach the instruction with them at different places on the stack.
+    NamedMismatch,
+    /// A function takes a `ref` argument, which the VM can't pass yet.
+    RefArgument,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            VerifyErrorKind::JumpOutOfBounds { target } => {
+                write!(f, "jump to {} is out of bounds", target)?
+            }
+            VerifyErrorKind::StackUnderflow => write!(f, "stack underflow")?,
+            VerifyErrorKind::StackMismatch { expected, found } => write!(
+                f,
+                "stack has {} values on one path and {} on another",
+                expected, found
+            )?,
+            VerifyErrorKind::UnbalancedWith => write!(f, "unbalanced `with`")?,
+            VerifyErrorKind::UnbalancedTry => write!(f, "unbalanced `try`")?,
+            VerifyErrorKind::FallsOffEnd => write!(f, "execution runs past the end of the code")?,
+            VerifyErrorKind::BuiltinArity { expected, found } => write!(
+                f,
+                "builtin takes {} arguments, but is given {}",
+                expected, found
+            )?,
+            VerifyErrorKind::LocalOutOfRange { id, locals } => write!(
+                f,
+                "local {} is out of range, as there are {} in scope",
+                id, locals
+            )?,
+            VerifyErrorKind::LocalUnderflow => write!(f, "local variable underflow")?,
+            VerifyErrorKind::LocalsMismatch { expected, found } => write!(
+                f,
+                "{} local variables are in scope on one path and {} on another",
+                expected, found
+            )?,
+            VerifyErrorKind::NamedMismatch => {
+                write!(f, "named value used as an object or the reverse")?
+            }
+            VerifyErrorKind::RefArgument => write!(f, "`ref` arguments are not supported")?,
+        }
+        write!(f, " at pc {}", self.pc)?;
+        if !self.path.is_empty() {
+            let path = self
+                .path
+                .iter()
+                .map(|pc| pc.to_string())
+                .collect::<Vec<_>>();
+            write!(f, " in the function created at pc {}", path.join(" > "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Checks that `code` can run without breaking the invariants of the VM: jumps stay in the code,
+/// every path leaves the stack, the local variables and the open `with`s and `try`s the same at
+/// each instruction and never pops more than it pushed, local variables are used only while in
+/// scope, the values made by `MakeNamed` go only to `MakeTable`, and nothing runs past the end.
+/// Functions created by `MakeClosure` are checked too.
+///
+/// The compiler always emits valid code, so this is for code from elsewhere, e.g. bytecode loaded
+/// from a file.
+pub fn verify(code: &[Code]) -> Result<(), VerifyError> {
+    verify_with_locals(code, 0)
+}
+
+/// Like [`verify`], for `code` run in a scope that already has `locals` local variables, e.g. ones
+/// the host pushed before running it.
+pub fn verify_with_locals(code: &[Code], locals: usize) -> Result<(), VerifyError> {
+    let mut path = Vec::new();
+    verify_function(code, locals, &mut path)
+}
+
+/// What is in scope before an instruction.
+#[derive(Clone, PartialEq, Eq)]
+struct State {
+    /// The number of values on the stack.
+    stack: usize,
+    /// The places on the stack of the values made by `MakeNamed`, from the bottom, in order.
+    named: Vec<usize>,
+    /// The number of local variables, including the captures and the arguments.
+    locals: usize,
+    with: usize,
+    /// The numbers of values on the stack and of local variables when each open `try` started,
+    /// which an error goes back to.
+    handlers: Vec<(usize, usize)>,
+}
+
+fn stack_operands<const N: usize>(operands: [&Operand; N]) -> usize {
+    operands
+        .into_iter()
+        .filter(|operand| matches!(operand, Operand::Stack))
+        .count()
+}
+
+fn local_operands<const N: usize>(operands: [&Operand; N]) -> impl Iterator<Item = LocalId> + '_ {
+    operands.into_iter().filter_map(|operand| match operand {
+        Operand::Local(id) => Some(*id),
+        _ => None,
+    })
+}
+
+fn verify_function(code: &[Code], locals: usize, path: &mut Vec<usize>) -> Result<(), VerifyError> {
+    let error = |kind, pc, path: &[usize]| VerifyError {
+        kind,
+        pc,
+        path: path.to_vec(),
+    };
+    let mut states: Vec<Option<State>> = vec![None; code.len()];
+    let mut pending = vec![(
+        0,
+        State {
+            stack: 0,
+            named: Vec::new(),
+            locals,
+            with: 0,
+            handlers: Vec::new(),
+        },
+    )];
+    while let Some((pc, state)) = pending.pop() {
+        let Some(instr) = code.get(pc) else {
+            return Err(error(VerifyErrorKind::FallsOffEnd, pc, path));
+        };
+        match &states[pc] {
+            Some(known) if known.stack != state.stack => {
+                let kind = VerifyErrorKind::StackMismatch {
+                    expected: known.stack,
+                    found: state.stack,
+                };
+                return Err(error(kind, pc, path));
+            }
+            Some(known) if known.locals != state.locals => {
+                let kind = VerifyErrorKind::LocalsMismatch {
+                    expected: known.locals,
+                    found: state.locals,
+                };
+                return Err(error(kind, pc, path));
+            }
+            Some(known) if known.named != state.named => {
+                return Err(error(VerifyErrorKind::NamedMismatch, pc, path));
+            }
+            Some(known) if known.with != state.with => {
+                return Err(error(VerifyErrorKind::UnbalancedWith, pc, path));
+            }
+            Some(known) if known.handlers != state.handlers => {
+                return Err(error(VerifyErrorKind::UnbalancedTry, pc, path));
+            }
+            Some(_) => continue,
+            None => states[pc] = Some(state.clone()),
+        }
+
+        // The local variables the instruction uses, which must be in scope.
+        let used: Vec<LocalId> = match instr {
+            Code::LoadLocal(id) | Code::SetLocal(id) | Code::IterNext(id, _) => vec![*id],
+            // A numeric for keeps the index, the end and the step, and the loop variable after them.
+            Code::ForPrep(id, _) | Code::ForLoop(id, _) => vec![LocalId(id.0.saturating_add(3))],
+            Code::Binary(_, lhs, rhs) => local_operands([lhs, rhs]).collect(),
+            Code::BinaryTo(dst, _, lhs, rhs) => local_operands([lhs, rhs]).chain([*dst]).collect(),
+            Code::Move(dst, src) => local_operands([src]).chain([*dst]).collect(),
+            Code::MakeClosure(proto) => proto.captures.clone(),
+            _ => Vec::new(),
+        };
+        if let Some(id) = used.into_iter().find(|id| id.0 >= state.locals) {
+            let kind = VerifyErrorKind::LocalOutOfRange {
+                id: id.0,
+                locals: state.locals,
+            };
+            return Err(error(kind, pc, path));
+        }
+
+        let (pops, pushes) = match instr {
+            Code::LoadInt(_)
+            | Code::LoadFloat(_)
+            | Code::LoadBool(_)
+            | Code::LoadString(_)
+            | Code::LoadNil
+            | Code::LoadLocal(_)
+            | Code::LoadGlobal(_)
+            | Code::LoadRustFunction(_) => (0, 1),
+            Code::UnloadTop | Code::SetLocal(_) | Code::SetGlobal(_) | Code::MakeLocal => (1, 0),
+            Code::MakeArray(len) | Code::MakeTable(len) | Code::MakeArraySpread(len) => {
+                (*len as usize, 1)
+            }
+            Code::MakeNamed => (2, 1),
+            Code::DropLocal(_) | Code::Nop => (0, 0),
+            Code::Jump(_) => (0, 0),
+            Code::JumpIfTrue(_) | Code::JumpIfFalse(_) => (1, 0),
+            Code::JumpIfNil(_) => (0, 0),
+            // The value is popped only when not jumping.
+            Code::JumpIfNotNil(_) | Code::JumpIfTrueOrPop(_) | Code::JumpIfFalseOrPop(_) => (1, 0),
+            Code::ForPrep(..) | Code::ForLoop(..) => (0, 0),
+            Code::IterStart => (1, 1),
+            // The item is pushed unless the iteration is over, which is handled with the jump.
+            Code::IterNext(..) => (0, 1),
+            // Likewise, the result is popped only when jumping.
+            Code::IterEnd(_) => (1, 1),
+            Code::CallMethod(_, args_len)
+            | Code::CallBuiltinMethod(_, args_len)
+            | Code::Call(args_len)
+            | Code::TailCall(args_len) => (*args_len as usize + 1, 1),
+            Code::CallSpread | Code::CallMethodSpread(_) => (2, 1),
+            Code::Unpack(count) => (1, (*count).max(1) as usize),
+            Code::SetItem => (3, 0),
+            Code::GetSlice => (3, 1),
+            Code::GetItem
+            | Code::Add
+            | Code::Sub
+            | Code::Mul
+            | Code::Div
+            | Code::Mod
+            | Code::Pow
+            | Code::FloorDiv
+            | Code::Eq
+            | Code::NotEq
+            | Code::Less
+            | Code::LessEq
+            | Code::Greater
+            | Code::GreaterEq
+            | Code::In
+            | Code::Concat
+            | Code::BitAnd
+            | Code::BitOr
+            | Code::BitXor
+            | Code::ShiftL
+            | Code::ShiftR => (2, 1),
+            Code::Unm | Code::Not | Code::BitNot => (1, 1),
+            Code::Builtin(instr, args_len) => {
+                if let Some(expected) = instr.args_len() {
+                    if expected != *args_len {
+                        let kind = VerifyErrorKind::BuiltinArity {
+                            expected,
+                            found: *args_len,
+                        };
+                        return Err(error(kind, pc, path));
+                    }
+                }
+                (*args_len as usize, instr.returns_value() as usize)
+            }
+            Code::MakeClosure(proto) => {
+                // The scope of a function starts with its captures and arguments, up to the
+                // rest argument that collects the others.
+                let mut locals = proto.captures.len();
+                for arg in proto.args.iter() {
+                    locals += 1;
+                    match arg {
+                        ArgumentKind::Ref => {
+                            return Err(error(VerifyErrorKind::RefArgument, pc, path));
+                        }
+                        ArgumentKind::Rest => break,
+                        ArgumentKind::Copy | ArgumentKind::Auto => {}
+                    }
+                }
+                path.push(pc);
+                verify_function(&proto.code, locals, path)?;
+                path.pop();
+                (0, 1)
+            }
+            Code::EnterWith => (1, 0),
+            Code::ExitWith => {
+                if state.with == 0 {
+                    return Err(error(VerifyErrorKind::UnbalancedWith, pc, path));
+                }
+                (0, 0)
+            }
+            Code::Try(_) => (0, 0),
+            Code::EndTry => {
+                if state.handlers.is_empty() {
+                    return Err(error(VerifyErrorKind::UnbalancedTry, pc, path));
+                }
+                (0, 0)
+            }
+            Code::Yield | Code::Import => (1, 1),
+            Code::Return => (1, 0),
+            Code::ReturnMany(count) => (*count as usize, 0),
+            Code::Exit => (0, 0),
+            Code::Binary(_, lhs, rhs) => (stack_operands([lhs, rhs]), 1),
+            Code::BinaryTo(_, _, lhs, rhs) => (stack_operands([lhs, rhs]), 0),
+            Code::Move(_, src) => (stack_operands([src]), 0),
+        };
+        // An error in a `try` block goes back to the values and the local variables it started
+        // with, so the block can't pop or drop those.
+        let (stack_floor, locals_floor) = state.handlers.last().copied().unwrap_or((0, 0));
+        let stack = match state.stack.checked_sub(pops) {
+            Some(stack) if stack >= stack_floor => stack,
+            _ => return Err(error(VerifyErrorKind::StackUnderflow, pc, path)),
+        };
+        let popped_named = state.named.iter().filter(|at| **at >= stack).count();
+        let named_ok = match instr {
+            Code::MakeTable(_) => popped_named == pops,
+            Code::UnloadTop => true,
+            _ => popped_named == 0,
+        };
+        if !named_ok {
+            return Err(error(VerifyErrorKind::NamedMismatch, pc, path));
+        }
+        let locals = match instr {
+            Code::MakeLocal => state.locals + 1,
+            Code::DropLocal(count) => match state.locals.checked_sub(*count) {
+                Some(locals) if locals >= locals_floor => locals,
+                _ => return Err(error(VerifyErrorKind::LocalUnderflow, pc, path)),
+            },
+            _ => state.locals,
+        };
+        let mut named = state.named.clone();
+        named.retain(|at| *at < stack);
+        if let Code::MakeNamed = instr {
+            named.push(stack);
+        }
+        let mut handlers = state.handlers.clone();
+        match instr {
+            Code::Try(_) => handlers.push((state.stack, state.locals)),
+            Code::EndTry => {
+                handlers.pop();
+            }
+            _ => {}
+        }
+        let next = State {
+            stack: stack + pushes,
+            named,
+            locals,
+            with: match instr {
+                Code::EnterWith => state.with + 1,
+                Code::ExitWith => state.with - 1,
+                _ => state.with,
+            },
+            handlers,
+        };
+
+        let mut jump = |offset: isize, state: State| match pc.checked_add_signed(offset) {
+            Some(target) if target < code.len() => {
+                pending.push((target, state));
+                Ok(())
+            }
+            _ => {
+                let target = (pc as isize).saturating_add(offset);
+                Err(error(VerifyErrorKind::JumpOutOfBounds { target }, pc, path))
+            }
+        };
+        match instr {
+            Code::Return | Code::ReturnMany(_) | Code::Exit => {}
+            Code::Jump(offset) => jump(*offset, next)?,
+            Code::JumpIfTrue(offset)
+            | Code::JumpIfFalse(offset)
+            | Code::JumpIfNil(offset)
+            | Code::ForPrep(_, offset)
+            | Code::ForLoop(_, offset) => {
+                jump(*offset, next.clone())?;
+                pending.push((pc + 1, next));
+            }
+            // An item goes to the `IterEnd` after it when it comes from a `__next` method, and
+            // past it otherwise.
+            Code::IterNext(_, offset) => {
+                jump(*offset, state)?;
+                pending.push((pc + 1, next.clone()));
+                pending.push((pc + 2, next));
+            }
+            Code::IterEnd(offset) => {
+                jump(
+                    *offset,
+                    State {
+                        stack,
+                        ..next.clone()
+                    },
+                )?;
+                pending.push((pc + 1, next));
+            }
+            Code::JumpIfNotNil(offset)
+            | Code::JumpIfTrueOrPop(offset)
+            | Code::JumpIfFalseOrPop(offset) => {
+                jump(
+                    *offset,
+                    State {
+                        stack: state.stack,
+                        ..next.clone()
+                    },
+                )?;
+                pending.push((pc + 1, next));
+            }
+            // The `catch` block starts with the error pushed, and the `try` closed.
+            Code::Try(offset) => {
+                jump(
+                    *offset,
+                    State {
+                        stack: stack + 1,
+                        ..state
+                    },
+                )?;
+                pending.push((pc + 1, next));
+            }
+            _ => pending.push((pc + 1, next)),
+        }
+    }
+    Ok(())
+}
@@ -2,6 +2,7 @@ use std::rc::Rc;
 use vm::{
     code::{ArgumentKind, Code::*, LocalId},
     runtime::{Object, Runtime},
+    ErrorKind, RuntimeError, TraceFrame,
 };
 
 #[test]
@@ -9,7 +10,7 @@ use vm::{
 fn load() {
     let mut runtime = Runtime::new();
     vm::execute(&[
-        LoadInt(37), LoadFloat(42.0), LoadBool(true), LoadString(Rc::new("a b".to_string())), LoadString(Rc::new("c".to_string())), LoadNil,
+        LoadInt(37), LoadFloat(42.0), LoadBool(true), LoadString("a b".into()), LoadString("c".into()), LoadNil,
         Exit,
     ], &mut runtime).unwrap();
     assert_eq!(runtime.stack.pop().ensure_object(), Object::Nil);
@@ -47,12 +48,31 @@ fn load_rust_function() {
     assert_eq!(res.unwrap(), Object::Int(2));
 }
 
+#[test]
+fn rust_function_returns_tuple() {
+    let mut runtime = Runtime::new();
+    let res = vm::execute(
+        &[
+            LoadRustFunction(|_| Ok((1, "one", Some(1.5), None::<bool>).into())),
+            Call(0),
+            LoadInt(1),
+            GetItem,
+            Return,
+        ],
+        &mut runtime,
+    );
+    assert_eq!(res.unwrap(), Object::new_string("one".to_string()));
+    assert_eq!(
+        Object::from(vec![(1, true), (2, false)]).to_string(),
+        "[[1, true], [2, false]]"
+    );
+}
+
 #[test]
 #[should_panic(expected = "[BUG] Stack must have at least one value at pop.")]
 fn unload() {
     let mut runtime = Runtime::new();
-    runtime.stack.push(Object::Int(0).into());
-    vm::execute(&[UnloadTop, Exit], &mut runtime).unwrap();
+    vm::execute(&[LoadInt(0), UnloadTop, Exit], &mut runtime).unwrap();
     runtime.stack.pop(); // panic
 }
 
@@ -95,7 +115,7 @@ fn make_array() {
     .unwrap();
     assert_eq!(
         runtime.stack.pop(),
-        StackValue::RawArray(vec![Object::Int(1), Object::Int(2), Object::Int(3)])
+        StackValue::from(vec![Object::Int(1), Object::Int(2), Object::Int(3)])
     );
 }
 
@@ -103,18 +123,43 @@ fn make_array() {
 fn make_named() {
     let mut runtime = Runtime::new();
     vm::execute(
-        &[
-            LoadNil,
-            LoadString(Rc::new("NILL".to_string())),
-            MakeNamed,
-            Exit,
-        ],
+        &[LoadNil, LoadString("NILL".into()), MakeNamed, Exit],
         &mut runtime,
     )
     .unwrap();
     assert_eq!(
         runtime.stack.pop().ensure_named(),
-        (Rc::new("NILL".to_string()), Object::Nil)
+        ("NILL".into(), Object::Nil)
+    );
+}
+
+#[test]
+fn make_named_with_int_key() {
+    use vm::runtime::TableKey;
+
+    let mut runtime = Runtime::new();
+    vm::execute(&[LoadNil, LoadInt(1), MakeNamed, Exit], &mut runtime).unwrap();
+    assert_eq!(
+        runtime.stack.pop().ensure_named(),
+        (TableKey::Int(1), Object::Nil)
+    );
+
+    let err = vm::execute(&[LoadNil, LoadFloat(1.0), MakeNamed, Exit], &mut runtime);
+    assert_eq!(
+        err,
+        Err(RuntimeError {
+            kind: ErrorKind::Type,
+            message: "Expected `string`, `int` or `bool` as table key, got `float`".to_string(),
+            pc: Some(2),
+            traceback: vec![TraceFrame {
+                function: "<main>".to_string(),
+                pc: 2,
+                span: None,
+                file: None,
+                repeated: 0,
+            }],
+            omitted_frames: 0,
+        })
     );
 }
 
@@ -124,14 +169,14 @@ fn make_table() {
     use vm::runtime::TableObject;
 
     let mut runtime = Runtime::new();
-    for (key, value) in [
-        ("Key1".to_string(), Object::Int(1)),
-        ("Key2".to_string(), Object::Bool(true)),
-        ("Key3".to_string(), Object::new_string("a".to_string())),
-    ] {
-        runtime.stack.push((Rc::new(key), value).into());
-    }
-    vm::execute(&[MakeTable(2), Exit], &mut runtime).unwrap();
+    #[rustfmt::skip]
+    vm::execute(&[
+        LoadInt(1), LoadString("Key1".into()), MakeNamed,
+        LoadBool(true), LoadString("Key2".into()), MakeNamed,
+        LoadString("a".into()), LoadString("Key3".into()), MakeNamed,
+        MakeTable(2),
+        Exit,
+    ], &mut runtime).unwrap();
 
     assert_eq!(
         runtime.stack.pop().ensure_object(),
@@ -146,7 +191,7 @@ fn make_table() {
     );
     assert_eq!(
         runtime.stack.pop().ensure_named(),
-        (Rc::new("Key1".to_string()), Object::Int(1))
+        ("Key1".into(), Object::Int(1))
     );
 }
 
@@ -246,7 +291,10 @@ fn jump_if_false() {
 
 #[test]
 fn custom_method() {
-    use vm::runtime::{FunctionObject, TableObject};
+    use vm::{
+        code::FunctionPrototype,
+        runtime::{FunctionObject, TableObject},
+    };
 
     // var table = {
     //     key = "value"
@@ -263,17 +311,22 @@ fn custom_method() {
         table.add_method(
             "testMethod",
             FunctionObject {
-                id: (0, 0),
+                proto: Rc::new(FunctionPrototype {
+                    name: None,
+                    captures: vec![],
+                    args: vec![ArgumentKind::Auto, ArgumentKind::Copy], // self, new_value
+                    code: Rc::new([
+                        LoadLocal(LocalId(1)),
+                        LoadLocal(LocalId(0)),
+                        LoadString("key".into()),
+                        SetItem,
+                        LoadNil,
+                        Return,
+                    ]),
+                    spans: Rc::new([]),
+                    file: None,
+                }),
                 env: vec![],
-                args: vec![ArgumentKind::Auto, ArgumentKind::Copy], // self, new_value
-                code: vec![
-                    LoadLocal(LocalId(1)),
-                    LoadLocal(LocalId(0)),
-                    LoadString(Rc::new("key".to_string())),
-                    SetItem,
-                    LoadNil,
-                    Return,
-                ],
             },
         );
         table
@@ -299,9 +352,97 @@ fn custom_method() {
     }
 }
 
+#[test]
+#[rustfmt::skip]
+fn method_cache() {
+    use std::cell::RefCell;
+    use vm::runtime::{Stats, TableObject};
+
+    fn one(_: Rc<RefCell<TableObject>>, _: &[Object]) -> Result<Object, RuntimeError> {
+        Ok(Object::Int(1))
+    }
+    fn two(_: Rc<RefCell<TableObject>>, _: &[Object]) -> Result<Object, RuntimeError> {
+        Ok(Object::Int(2))
+    }
+
+    // var result = nil
+    // for i = 0, 9 do result = table->get() end
+    // return result
+    let code = [
+        LoadLocal(LocalId(0)), CallMethod("get".into(), 0), SetLocal(LocalId(2)),
+        LoadLocal(LocalId(1)), LoadInt(1), Add, SetLocal(LocalId(1)),
+        LoadLocal(LocalId(1)), LoadInt(10), Less, JumpIfTrue(-10),
+        LoadLocal(LocalId(2)), Return,
+    ];
+    let mut table = TableObject::new(Default::default());
+    table.add_method("get", one as fn(_, &_) -> _);
+    let table = Object::new_table(table);
+    let mut runtime = Runtime::new();
+    let run = |runtime: &mut Runtime| {
+        runtime.variable_table.push(table.clone());
+        runtime.variable_table.push(Object::Int(0));
+        runtime.variable_table.push(Object::Nil);
+        let res = vm::execute(&code, runtime);
+        runtime.variable_table.drop(3);
+        res.unwrap()
+    };
+
+    assert_eq!(run(&mut runtime), Object::Int(1));
+    let Stats { method_cache_hits, method_cache_misses, .. } = runtime.stats;
+    assert_eq!((method_cache_hits, method_cache_misses), (9, 1));
+
+    // Replacing the method changes the shape of the table, so the cache is refilled.
+    let Object::Table(inner) = &table else { unreachable!() };
+    inner.borrow_mut().add_method("get", two as fn(_, &_) -> _);
+    assert_eq!(run(&mut runtime), Object::Int(2));
+    let Stats { method_cache_hits, method_cache_misses, .. } = runtime.stats;
+    assert_eq!((method_cache_hits, method_cache_misses), (18, 2));
+}
+
+#[test]
+fn call_builtin_method() {
+    use vm::code::BuiltinMethod;
+
+    let mut runtime = Runtime::new();
+    let res = vm::execute(
+        &[
+            LoadInt(-3),
+            CallBuiltinMethod(BuiltinMethod::Abs, 0),
+            LoadString("len".into()),
+            CallBuiltinMethod(BuiltinMethod::Len, 0),
+            Add,
+            Return,
+        ],
+        &mut runtime,
+    )
+    .unwrap();
+    assert_eq!(res, Object::Int(6));
+
+    // A table's own method takes precedence over the builtin one of the same name.
+    let mut table = vm::runtime::TableObject::new(Default::default());
+    table.add_method(
+        "len",
+        vm::runtime::TableMethod::Builtin(|_, _| Ok(Object::Int(42))),
+    );
+    runtime.variable_table.push(Object::new_table(table));
+    let res = vm::execute(
+        &[
+            LoadLocal(LocalId(0)),
+            CallBuiltinMethod(BuiltinMethod::Len, 0),
+            Return,
+        ],
+        &mut runtime,
+    )
+    .unwrap();
+    assert_eq!(res, Object::Int(42));
+}
+
 #[test]
 fn call() {
-    use vm::runtime::{FunctionObject, TableObject};
+    use vm::{
+        code::FunctionPrototype,
+        runtime::{FunctionObject, TableObject},
+    };
 
     let table_obj_0 = Object::new_table(TableObject::new(
         [("key".into(), Object::Int(1))].into_iter().collect(),
@@ -310,26 +451,36 @@ fn call() {
         [("key".into(), Object::Int(2))].into_iter().collect(),
     ));
     let (func_obj_0, func_obj_1) = {
-        let code = [
+        let code: Rc<[_]> = Rc::new([
             LoadInt(100),
             LoadLocal(LocalId(0)),
-            LoadString(Rc::new("key".to_string())),
+            LoadString("key".into()),
             SetItem,
             LoadNil,
             Return,
-        ];
+        ]);
         (
             Object::new_function(FunctionObject {
-                id: (0, 0),
+                proto: Rc::new(FunctionPrototype {
+                    name: None,
+                    captures: vec![],
+                    args: vec![ArgumentKind::Copy],
+                    code: Rc::clone(&code),
+                    spans: Rc::new([]),
+                    file: None,
+                }),
                 env: vec![],
-                args: vec![ArgumentKind::Copy],
-                code: code.to_vec(),
             }),
             Object::new_function(FunctionObject {
-                id: (0, 0),
+                proto: Rc::new(FunctionPrototype {
+                    name: None,
+                    captures: vec![],
+                    args: vec![ArgumentKind::Auto],
+                    code: Rc::clone(&code),
+                    spans: Rc::new([]),
+                    file: None,
+                }),
                 env: vec![],
-                args: vec![ArgumentKind::Auto],
-                code: code.to_vec(),
             }),
         )
     };
@@ -365,6 +516,43 @@ fn call() {
     }
 }
 
+#[test]
+fn call_with_rest_argument() {
+    use vm::{code::FunctionPrototype, runtime::FunctionObject};
+
+    // func(first, ...rest) return rest end
+    let func = Object::new_function(FunctionObject {
+        proto: Rc::new(FunctionPrototype {
+            name: None,
+            captures: vec![],
+            args: vec![ArgumentKind::Auto, ArgumentKind::Rest],
+            code: Rc::new([LoadLocal(LocalId(1)), Return]),
+            spans: Rc::new([]),
+            file: None,
+        }),
+        env: vec![],
+    });
+    let mut runtime = Runtime::new();
+    runtime.variable_table.push(func);
+    let res = vm::execute(
+        &[
+            LoadLocal(LocalId(0)),
+            LoadInt(1),
+            LoadInt(2),
+            LoadInt(3),
+            Call(3),
+            LoadLocal(LocalId(0)),
+            LoadInt(1),
+            Call(1),
+            MakeArray(2),
+            Return,
+        ],
+        &mut runtime,
+    )
+    .unwrap();
+    assert_eq!(res.to_string(), "[[2, 3], []]");
+}
+
 #[test]
 fn set_item() {
     use vm::runtime::TableObject;
@@ -379,11 +567,11 @@ fn set_item() {
         &[
             LoadInt(2),
             LoadLocal(LocalId(0)),
-            LoadString(Rc::new("a".to_string())),
+            LoadString("a".into()),
             SetItem,
             LoadBool(true),
             LoadLocal(LocalId(0)),
-            LoadString(Rc::new("b".to_string())),
+            LoadString("b".into()),
             SetItem,
             Exit,
         ],
@@ -398,3 +586,388 @@ fn set_item() {
         unreachable!()
     }
 }
+
+#[test]
+fn set_and_get_item_with_non_string_key() {
+    use vm::runtime::{TableKey, TableObject};
+
+    let table_obj = Object::new_table(TableObject::new(
+        [("1".into(), Object::Int(1))].into_iter().collect(),
+    ));
+
+    let mut runtime = Runtime::new();
+    runtime.variable_table.push(table_obj);
+    vm::execute(
+        &[
+            LoadInt(2),
+            LoadLocal(LocalId(0)),
+            LoadInt(1),
+            SetItem,
+            LoadInt(3),
+            LoadLocal(LocalId(0)),
+            LoadBool(true),
+            SetItem,
+            LoadLocal(LocalId(0)),
+            LoadInt(1),
+            GetItem,
+            Exit,
+        ],
+        &mut runtime,
+    )
+    .unwrap();
+
+    assert_eq!(runtime.stack.pop().ensure_object(), Object::Int(2));
+    if let Object::Table(table) = runtime.variable_table.get(LocalId(0)) {
+        assert_eq!(table.borrow().get("1"), Some(&Object::Int(1)));
+        assert_eq!(table.borrow().get(&TableKey::Int(1)), Some(&Object::Int(2)));
+        assert_eq!(
+            table.borrow().get(&TableKey::Bool(true)),
+            Some(&Object::Int(3))
+        );
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn string_chars() {
+    use vm::runtime::StringObject;
+
+    let ascii = StringObject::from("abc");
+    assert_eq!(ascii.char_len(), 3);
+    assert_eq!(ascii.char_at(2), Some('c'));
+    assert_eq!(ascii.char_at(3), None);
+
+    let unicode = StringObject::from("aあ🍣");
+    let clone = unicode.clone();
+    assert_eq!(unicode.char_len(), 3);
+    assert_eq!(clone.char_at(1), Some('あ'));
+    assert_eq!(clone.char_at(2), Some('🍣'));
+    assert_eq!(unicode.char_at(3), None);
+}
+
+#[test]
+fn string_methods() {
+    use vm::code::{BuiltinMethod, Code};
+
+    fn call(receiver: &str, method: BuiltinMethod, args: &[Code]) -> Result<Object, RuntimeError> {
+        let mut code = vec![LoadString(receiver.into())];
+        code.extend_from_slice(args);
+        code.extend([CallBuiltinMethod(method, args.len() as u8), Return]);
+        vm::execute(&code, &mut Runtime::new())
+    }
+    let string = |s: &str| Object::new_string(s.to_string());
+    let strings = |items: &[&str]| {
+        let items = items.iter().map(|s| string(s)).collect();
+        Object::new_array(vm::runtime::ArrayObject::new(items))
+    };
+
+    let comma = || LoadString(",".into());
+    assert_eq!(
+        call("a,b,,c", BuiltinMethod::Split, &[comma()]),
+        Ok(strings(&["a", "b", "", "c"]))
+    );
+    assert_eq!(
+        call("", BuiltinMethod::Split, &[comma()]),
+        Ok(strings(&[""]))
+    );
+    let res = call("abc", BuiltinMethod::Split, &[LoadString("".into())]);
+    assert_eq!(res.unwrap_err().message, "Separator must not be empty");
+
+    assert_eq!(
+        call(" \tab c\n", BuiltinMethod::Trim, &[]),
+        Ok(string("ab c"))
+    );
+    assert_eq!(
+        call("  ab  ", BuiltinMethod::TrimStart, &[]),
+        Ok(string("ab  "))
+    );
+    assert_eq!(
+        call("  ab  ", BuiltinMethod::TrimEnd, &[]),
+        Ok(string("  ab"))
+    );
+
+    let (from, to) = (LoadString("o".into()), LoadString("0".into()));
+    assert_eq!(
+        call("foo boo", BuiltinMethod::Replace, &[from, to]),
+        Ok(string("f00 b00"))
+    );
+
+    let sub = || LoadString("ló".into());
+    assert_eq!(
+        call("héllóló", BuiltinMethod::Find, &[sub()]),
+        Ok(Object::Int(3))
+    );
+    assert_eq!(
+        call("hello", BuiltinMethod::Find, &[sub()]),
+        Ok(Object::Nil)
+    );
+    assert_eq!(
+        call("héllóló", BuiltinMethod::Contains, &[sub()]),
+        Ok(Object::Bool(true))
+    );
+    let affix = || LoadString("ab".into());
+    assert_eq!(
+        call("abc", BuiltinMethod::StartsWith, &[affix()]),
+        Ok(Object::Bool(true))
+    );
+    assert_eq!(
+        call("abc", BuiltinMethod::EndsWith, &[affix()]),
+        Ok(Object::Bool(false))
+    );
+
+    assert_eq!(
+        call("ab", BuiltinMethod::Repeat, &[LoadInt(3)]),
+        Ok(string("ababab"))
+    );
+    assert_eq!(
+        call("ab", BuiltinMethod::Repeat, &[LoadInt(0)]),
+        Ok(string(""))
+    );
+    let res = call("ab", BuiltinMethod::Repeat, &[LoadInt(-1)]);
+    assert_eq!(
+        res.unwrap_err().message,
+        "Count must not be negative, got -1"
+    );
+    assert!(call("ab", BuiltinMethod::Repeat, &[LoadInt(i64::MAX)]).is_err());
+
+    assert_eq!(
+        call("é1", BuiltinMethod::PadStart, &[LoadInt(4)]),
+        Ok(string("  é1"))
+    );
+    assert_eq!(
+        call(
+            "7",
+            BuiltinMethod::PadStart,
+            &[LoadInt(3), LoadString("0".into())]
+        ),
+        Ok(string("007"))
+    );
+    assert_eq!(
+        call(
+            "ab",
+            BuiltinMethod::PadEnd,
+            &[LoadInt(4), LoadString(".".into())]
+        ),
+        Ok(string("ab.."))
+    );
+    assert_eq!(
+        call("abc", BuiltinMethod::PadEnd, &[LoadInt(-1)]),
+        Ok(string("abc"))
+    );
+    let res = call(
+        "a",
+        BuiltinMethod::PadEnd,
+        &[LoadInt(3), LoadString("ab".into())],
+    );
+    assert_eq!(
+        res.unwrap_err().message,
+        "Fill must be exactly one character"
+    );
+
+    assert_eq!(call("ÀbC", BuiltinMethod::Lower, &[]), Ok(string("àbc")));
+    assert_eq!(call("àbC", BuiltinMethod::Upper, &[]), Ok(string("ÀBC")));
+    assert_eq!(
+        call("aé", BuiltinMethod::Chars, &[]),
+        Ok(strings(&["a", "é"]))
+    );
+    let bytes = [97, 195, 169].map(Object::Int).to_vec();
+    assert_eq!(
+        call("aé", BuiltinMethod::Bytes, &[]),
+        Ok(Object::new_array(vm::runtime::ArrayObject::new(bytes)))
+    );
+
+    let res = call("abc", BuiltinMethod::Split, &[LoadInt(1)]);
+    assert_eq!(res.unwrap_err().kind, ErrorKind::Type);
+}
+
+#[test]
+fn log_with_host_sink() {
+    use std::cell::RefCell;
+    use vm::{
+        code::BuiltinInstr,
+        runtime::{LogLevel, LogRecord, LogSink},
+    };
+
+    struct Collect(Rc<RefCell<Vec<LogRecord>>>);
+    impl LogSink for Collect {
+        fn log(&mut self, record: &LogRecord) {
+            self.0.borrow_mut().push(record.clone());
+        }
+    }
+
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut runtime = Runtime::new();
+    runtime.logger.level = LogLevel::Warn;
+    runtime.logger.set_sink(Collect(Rc::clone(&records)));
+    let enabled = vm::execute(
+        &[
+            LoadInt(1),
+            LoadString("filtered".into()),
+            LoadNil,
+            Builtin(BuiltinInstr::Log, 3),
+            LoadInt(3),
+            LoadString("failed".into()),
+            LoadInt(500),
+            LoadString("status".into()),
+            MakeNamed,
+            MakeTable(1),
+            Builtin(BuiltinInstr::Log, 3),
+            LoadString("info".into()),
+            Builtin(BuiltinInstr::LogEnabled, 1),
+            LoadString("warn".into()),
+            Builtin(BuiltinInstr::LogEnabled, 1),
+            MakeArray(2),
+            Return,
+        ],
+        &mut runtime,
+    )
+    .unwrap();
+    assert_eq!(enabled.to_string(), "[false, true]");
+
+    assert_eq!(
+        *records.borrow(),
+        vec![LogRecord {
+            level: LogLevel::Error,
+            message: "failed".to_string(),
+            fields: vec![("status".to_string(), "500".to_string())],
+        }]
+    );
+}
+
+#[test]
+fn sleep_is_interruptible() {
+    use vm::code::BuiltinInstr;
+
+    let mut runtime = Runtime::new();
+    runtime.interrupt.interrupt();
+    let start = std::time::Instant::now();
+    let res = vm::execute(
+        &[LoadInt(60), Builtin(BuiltinInstr::Sleep, 1), Exit],
+        &mut runtime,
+    );
+    assert_eq!(
+        res,
+        Err(RuntimeError {
+            kind: ErrorKind::Interrupted,
+            message: "Interrupted".to_string(),
+            pc: Some(1),
+            traceback: vec![TraceFrame {
+                function: "<main>".to_string(),
+                pc: 1,
+                span: None,
+                file: None,
+                repeated: 0,
+            }],
+            omitted_frames: 0,
+        })
+    );
+    assert!(start.elapsed().as_secs() < 1);
+}
+
+#[test]
+fn fs_glob() {
+    let dir = std::env::temp_dir().join(format!("lico-fs-glob-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub/deep")).unwrap();
+    for file in ["a.txt", "b.md", "sub/c.txt", "sub/deep/d.txt"] {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+    let root = dir.to_str().unwrap();
+    let glob = |pattern: &str| {
+        let pattern = Object::new_string(format!("{}/{}", root, pattern));
+        let res = vm::builtin::fs_glob(&[pattern])
+            .unwrap()
+            .ensure_array()
+            .unwrap();
+        let res = res.borrow();
+        res.iter()
+            .map(|path| path.to_string().strip_prefix(root).unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        glob("**/*.txt"),
+        ["/a.txt", "/sub/c.txt", "/sub/deep/d.txt"]
+    );
+    assert_eq!(glob("*"), ["/a.txt", "/b.md", "/sub"]);
+    assert_eq!(glob("sub/*/?.txt"), ["/sub/deep/d.txt"]);
+    assert_eq!(glob("missing/*"), Vec::<String>::new());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parse_dotenv() {
+    let source = r#"
+# comment
+export HOST=localhost
+PORT = 8080 # inline comment
+URL=http://${HOST}:$PORT/#top
+SINGLE='raw $HOST \n'
+DOUBLE="line1\nline2 \"$HOST\""
+MULTI="a
+b"
+EMPTY=
+PORT=9090
+"#;
+    let vars = vm::builtin::parse_dotenv(source).unwrap();
+    let vars = vars
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vars,
+        [
+            ("HOST", "localhost"),
+            ("PORT", "9090"),
+            ("URL", "http://localhost:8080/#top"),
+            ("SINGLE", "raw $HOST \\n"),
+            ("DOUBLE", "line1\nline2 \"localhost\""),
+            ("MULTI", "a\nb"),
+            ("EMPTY", ""),
+        ]
+    );
+
+    assert_eq!(
+        vm::builtin::parse_dotenv("A=1\nB=\"open\n"),
+        Err("line 2: unterminated `\"`".to_string())
+    );
+    assert_eq!(
+        vm::builtin::parse_dotenv("A='x' y"),
+        Err("line 1: unexpected `y` after value".to_string())
+    );
+}
+
+#[cfg(feature = "plugin")]
+#[test]
+fn plugin_registry() {
+    use vm::plugin::Registry;
+
+    let mut registry = Registry::new();
+    registry
+        .module("greet")
+        .function("hello", |_| Ok(Object::new_string("hello".to_string())));
+    registry
+        .module("math")
+        .function("two", |_| Ok(Object::Int(2)));
+    registry
+        .module("greet")
+        .function("bye", |_| Ok(Object::new_string("bye".to_string())));
+
+    let mut runtime = Runtime::new();
+    runtime.variable_table.push(registry.into_object());
+    let res = vm::execute(
+        &[
+            LoadLocal(LocalId(0)),
+            LoadString("greet".into()),
+            GetItem,
+            LoadString("bye".into()),
+            GetItem,
+            Call(0),
+            Return,
+        ],
+        &mut runtime,
+    );
+    assert_eq!(res, Ok(Object::new_string("bye".to_string())));
+
+    assert!(vm::plugin::load("/lico-no-such-plugin.so").is_err());
+}
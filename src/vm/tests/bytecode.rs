@@ -0,0 +1,259 @@
+use foundation::TextSpan;
+use std::rc::Rc;
+use vm::{
+    builtin,
+    bytecode::{checksum, features, Header, LoadError, Program, FORMAT_VERSION, MAX_NESTING},
+    code::{
+        ArgumentKind, BinaryOp, BuiltinInstr, BuiltinMethod, Code, Code::*, FunctionPrototype,
+        LocalId, Operand,
+    },
+};
+
+#[test]
+fn header_round_trip() {
+    let mut bytes = Vec::new();
+    Header::current().write(&mut bytes);
+    bytes.extend_from_slice(b"code");
+    let (header, rest) = Header::read(&bytes).unwrap();
+    assert_eq!(header, Header::current());
+    assert_eq!(rest, b"code");
+    assert_eq!(header.check(), Ok(()));
+}
+
+#[test]
+fn header_rejects_incompatible() {
+    assert_eq!(
+        Header::read(b"#!/usr/bin/env lico"),
+        Err(LoadError::NotBytecode)
+    );
+
+    let mut bytes = Vec::new();
+    Header::current().write(&mut bytes);
+    assert_eq!(Header::read(&bytes[..7]), Err(LoadError::Truncated));
+
+    let mut bytes = Vec::new();
+    Header {
+        format_version: FORMAT_VERSION + 1,
+        ..Header::current()
+    }
+    .write(&mut bytes);
+    assert!(matches!(
+        Header::read(&bytes),
+        Err(LoadError::UnsupportedFormat { .. })
+    ));
+
+    let old = Header {
+        version: "0.0.0".to_string(),
+        ..Header::current()
+    };
+    assert!(matches!(
+        old.check(),
+        Err(LoadError::VersionMismatch { .. })
+    ));
+
+    let all = features::RANDOM
+        | features::HASH
+        | features::COMPRESS
+        | features::TERM
+        | features::PLUGIN
+        | features::FFI;
+    let header = Header {
+        features: all,
+        ..Header::current()
+    };
+    if features::enabled() == all {
+        assert_eq!(header.check(), Ok(()));
+    } else {
+        let Err(LoadError::MissingFeatures(names)) = header.check() else {
+            panic!("expected missing features");
+        };
+        assert!(!names.is_empty());
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn code_round_trip() {
+    let proto = FunctionPrototype {
+        name: Some("f".to_string()),
+        captures: vec![LocalId(3)],
+        args: vec![ArgumentKind::Copy, ArgumentKind::Ref, ArgumentKind::Auto, ArgumentKind::Rest],
+        code: Rc::new([LoadString("héllo".into()), TailCall(1), Return]),
+        spans: Rc::new([]),
+        file: None,
+    };
+    let code = vec![
+        LoadInt(-37), LoadFloat(0.5), LoadBool(true), LoadString("héllo".into()), LoadNil,
+        LoadLocal(LocalId(1)), LoadRustFunction(builtin::partial), UnloadTop, SetLocal(LocalId(2)),
+        MakeLocal, MakeArray(3), MakeArraySpread(2), MakeNamed, MakeTable(4), DropLocal(5),
+        Jump(-6), JumpIfTrue(7), JumpIfFalse(8), JumpIfNil(9), JumpIfNotNil(10),
+        JumpIfTrueOrPop(11), JumpIfFalseOrPop(12),
+        IterStart, IterNext(LocalId(6), 9), IterEnd(8),
+        ForPrep(LocalId(7), 10), ForLoop(LocalId(7), -11),
+        CallMethod("push".into(), 1), CallBuiltinMethod(BuiltinMethod::Len, 0), Call(2), TailCall(3),
+        CallSpread, CallMethodSpread("push".into()),
+        Unpack(2),
+        SetItem, GetItem, GetSlice, Add, Sub, Mul, Div, Mod, Pow, FloorDiv, Unm, Not, Eq, NotEq, Less,
+        LessEq, Greater, GreaterEq, In, Concat, BitAnd, BitOr, BitXor, BitNot, ShiftL, ShiftR,
+        Builtin(BuiltinInstr::Write, 2), Builtin(BuiltinInstr::OnInterrupt, 1),
+        Builtin(BuiltinInstr::LogEnabled, 1),
+        MakeClosure(Rc::new(proto)), MakeClosure(Rc::new(FunctionPrototype {
+            name: None,
+            captures: vec![],
+            args: vec![],
+            code: Rc::new([]),
+            spans: Rc::new([]),
+            file: None,
+        })),
+        EnterWith, ExitWith, Try(4), EndTry, Yield, Import, Nop, Return, ReturnMany(3), Exit,
+        LoadGlobal("a".into()), SetGlobal("b".into()),
+        Binary(BinaryOp::Less, Operand::Local(LocalId(1)), Operand::Int(-2)),
+        BinaryTo(LocalId(3), BinaryOp::GreaterEq, Operand::Stack, Operand::Local(LocalId(4))),
+        Binary(BinaryOp::Concat, Operand::Stack, Operand::Stack),
+        Move(LocalId(5), Operand::Int(6)),
+    ];
+    let bytes = Code::serialize(&code);
+    assert_eq!(Header::read(&bytes).unwrap().0, Header::current());
+    assert_eq!(Code::deserialize(&bytes), Ok(code));
+}
+
+/// Changes the bytes before the checksum of `bytes` by `edit`, and fixes the checksum up.
+fn edit_body(bytes: &[u8], edit: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+    let mut body = bytes[..bytes.len() - 4].to_vec();
+    edit(&mut body);
+    let checksum = checksum(&body);
+    body.extend_from_slice(&checksum.to_le_bytes());
+    body
+}
+
+#[test]
+fn code_rejects_malformed() {
+    let bytes = Code::serialize(&[LoadString("a".into()), Return]);
+    for len in 0..bytes.len() {
+        assert!(Code::deserialize(&bytes[..len]).is_err(), "{} bytes", len);
+    }
+
+    let trailing = edit_body(&bytes, |body| body.push(0));
+    assert_eq!(
+        Code::deserialize(&trailing),
+        Err(LoadError::Malformed(
+            "trailing bytes after the code".to_string()
+        ))
+    );
+
+    // The tag of `Return` is followed by the empty span table.
+    let unknown = edit_body(&bytes, |body| {
+        let at = body.len() - 5;
+        body[at] = u8::MAX;
+    });
+    assert_eq!(
+        Code::deserialize(&unknown),
+        Err(LoadError::Malformed("unknown instruction 255".to_string()))
+    );
+
+    // Any change to a single byte is caught by the checksum.
+    for i in 4..bytes.len() {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 1 << (i % 8);
+        assert!(Code::deserialize(&corrupt).is_err(), "byte {}", i);
+    }
+
+    let mut nested = Rc::new(FunctionPrototype {
+        name: None,
+        captures: vec![],
+        args: vec![],
+        code: Rc::new([Exit]),
+        spans: Rc::new([]),
+        file: None,
+    });
+    let nest = |proto: Rc<FunctionPrototype>| {
+        Rc::new(FunctionPrototype {
+            code: Rc::new([MakeClosure(proto.clone()), Return]),
+            ..(*proto).clone()
+        })
+    };
+    for _ in 1..MAX_NESTING {
+        nested = nest(nested);
+    }
+    let code = [MakeClosure(nested.clone()), Return];
+    assert_eq!(
+        Code::deserialize(&Code::serialize(&code)),
+        Ok(code.to_vec())
+    );
+    let code = [MakeClosure(nest(nested)), Return];
+    assert_eq!(
+        Code::deserialize(&Code::serialize(&code)),
+        Err(LoadError::Malformed(format!(
+            "functions are nested deeper than {}",
+            MAX_NESTING
+        )))
+    );
+
+    let old = Header {
+        version: "0.0.0".to_string(),
+        ..Header::current()
+    };
+    let mut bytes = Vec::new();
+    old.write(&mut bytes);
+    assert!(matches!(
+        Code::deserialize(&bytes),
+        Err(LoadError::VersionMismatch { .. })
+    ));
+}
+
+#[test]
+fn program_round_trip() {
+    let span = |start, end| Some(TextSpan::new(start, end));
+    let proto = FunctionPrototype {
+        name: Some("f".to_string()),
+        captures: vec![],
+        args: vec![],
+        code: Rc::new([LoadInt(1), Return]),
+        spans: Rc::new([span(16, 17), None]),
+        file: None,
+    };
+    let program = Program {
+        code: vec![MakeClosure(Rc::new(proto)), Return],
+        spans: vec![span(0, 21), span(22, 24)],
+        source: "func f() return 1 end
+é"
+        .to_string(),
+    };
+    assert_eq!(
+        Program::deserialize(&program.serialize()),
+        Ok(program.clone())
+    );
+
+    // The spans are either as many as the instructions or none.
+    let bytes = program.serialize();
+    let missing = edit_body(&bytes, |body| {
+        let at = body.len() - 4 - 2 * 9;
+        body.truncate(at);
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(&[0]);
+    });
+    assert_eq!(
+        Program::deserialize(&missing),
+        Err(LoadError::Malformed(
+            "1 spans for 2 instructions".to_string()
+        ))
+    );
+
+    // A span must lie on characters of the source, which ends in the two bytes of `é`.
+    let program = Program {
+        spans: vec![None, span(23, 24)],
+        ..program
+    };
+    assert_eq!(
+        Program::deserialize(&program.serialize()),
+        Err(LoadError::Malformed(
+            "span 23..24 is not in the source".to_string()
+        ))
+    );
+}
+
+#[test]
+#[should_panic]
+fn code_rejects_unknown_rust_function() {
+    Code::serialize(&[LoadRustFunction(|_| Ok(vm::runtime::Object::Nil))]);
+}
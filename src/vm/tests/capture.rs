@@ -1,9 +1,20 @@
 use std::rc::Rc;
 use vm::{
-    code::{ArgumentKind, Code::*, LocalId},
+    code::{ArgumentKind, Code, Code::*, FunctionPrototype, LocalId},
     runtime::{Object, Runtime},
 };
 
+fn closure(captures: Vec<LocalId>, args: Vec<ArgumentKind>, code: Vec<Code>) -> Code {
+    MakeClosure(Rc::new(FunctionPrototype {
+        name: None,
+        captures,
+        args,
+        code: code.into(),
+        spans: Rc::new([]),
+        file: None,
+    }))
+}
+
 #[test]
 fn case1() {
     // var a = 1
@@ -19,11 +30,10 @@ fn case1() {
             // f: 1
             LoadInt(1), MakeLocal,
 
-            BeginFuncCreation,
-              AddCapture(LocalId(0)),
+            closure(vec![LocalId(0)], vec![], vec![
               LoadLocal(LocalId(0)), LoadInt(10), Add, SetLocal(LocalId(0)),
               LoadNil, Return,
-            EndFuncCreation,
+            ]),
             MakeLocal,
 
             LoadLocal(LocalId(1)), Call(0), UnloadTop,
@@ -39,8 +49,9 @@ fn case1() {
             .get(LocalId(1))
             .ensure_function()
             .unwrap()
-            .id,
-        (2, 0)
+            .proto
+            .captures,
+        vec![LocalId(0)]
     )
 }
 
@@ -67,12 +78,10 @@ fn case2() {
         )));
     #[rustfmt::skip]
     vm::execute(&[
-        BeginFuncCreation,
-          AddCapture(LocalId(0)),
-          AddArgument(ArgumentKind::Copy),
-          LoadLocal(LocalId(1)), LoadLocal(LocalId(0)), LoadString(Rc::new("key".to_string())), SetItem,
+        closure(vec![LocalId(0)], vec![ArgumentKind::Copy], vec![
+          LoadLocal(LocalId(1)), LoadLocal(LocalId(0)), LoadString("key".into()), SetItem,
           LoadNil, Return,
-        EndFuncCreation,
+        ]),
         MakeLocal,
         LoadLocal(LocalId(1)), LoadFloat(1.23), Call(1),
         Exit,
@@ -106,24 +115,27 @@ fn case3() {
     runtime
         .variable_table
         .push(Object::new_function(vm::runtime::FunctionObject {
-            id: (0, 0),
+            proto: Rc::new(FunctionPrototype {
+                name: None,
+                captures: vec![],
+                args: vec![ArgumentKind::Copy],
+                code: Rc::new([LoadLocal(LocalId(0)), Return]),
+                spans: Rc::new([]),
+                file: None,
+            }),
             env: vec![],
-            args: vec![ArgumentKind::Copy],
-            code: vec![LoadLocal(LocalId(0)), Return],
         }));
     #[rustfmt::skip]
     let res = vm::execute(
         &[
-            BeginFuncCreation,
-              AddCapture(LocalId(0)),
-              BeginFuncCreation,
-                AddArgument(ArgumentKind::Copy),
+            closure(vec![LocalId(0)], vec![], vec![
+              closure(vec![], vec![ArgumentKind::Copy], vec![
                 LoadLocal(LocalId(0)), LoadInt(100), Add,
                 Return,
-              EndFuncCreation,
+              ]),
               SetLocal(LocalId(0)),
               LoadInt(10), Return,
-            EndFuncCreation,
+            ]),
             MakeLocal,
 
             LoadLocal(LocalId(0)),
@@ -156,12 +168,10 @@ fn case4() {
     runtime.variable_table.push(Object::Int(7));
     #[rustfmt::skip]
     let res = vm::execute(&[
-        BeginFuncCreation,
-          AddCapture(LocalId(0)),
-          AddArgument(ArgumentKind::Copy),
+        closure(vec![LocalId(0)], vec![ArgumentKind::Copy], vec![
           LoadLocal(LocalId(0)), LoadLocal(LocalId(1)), Add,
           Return,
-        EndFuncCreation,
+        ]),
         MakeLocal,
         LoadLocal(LocalId(1)), LoadInt(13), Call(1),
         Return,
@@ -169,3 +179,41 @@ fn case4() {
 
     assert_eq!(res, Ok(Object::Int(20)));
 }
+
+#[test]
+fn closures_share_prototype() {
+    // var a = 1
+    // var f = func() return a end
+    // var g = func() return a end   (the same definition executed again)
+
+    let mut runtime = Runtime::new();
+
+    // a: 0
+    // f: 1
+    // g: 2
+    runtime.variable_table.push(Object::Int(1));
+    let make = closure(
+        vec![LocalId(0)],
+        vec![],
+        vec![LoadLocal(LocalId(0)), Return],
+    );
+    #[rustfmt::skip]
+    vm::execute(&[
+        make.clone(), MakeLocal,
+        make, MakeLocal,
+        Exit,
+    ], &mut runtime).unwrap();
+
+    let f = runtime
+        .variable_table
+        .get(LocalId(1))
+        .ensure_function()
+        .unwrap();
+    let g = runtime
+        .variable_table
+        .get(LocalId(2))
+        .ensure_function()
+        .unwrap();
+    assert!(Rc::ptr_eq(&f.proto, &g.proto));
+    assert_eq!(f.env.len(), 1);
+}
@@ -0,0 +1,195 @@
+use std::rc::Rc;
+use vm::{
+    code::{ArgumentKind, BuiltinInstr, Code, Code::*, FunctionPrototype, LocalId},
+    runtime::Runtime,
+    verify, verify_with_locals, ErrorKind, VerifyError, VerifyErrorKind,
+};
+
+fn kind(code: &[Code]) -> Option<VerifyErrorKind> {
+    verify(code).err().map(|err| err.kind)
+}
+
+fn closure(code: Vec<Code>) -> Code {
+    function(vec![], vec![], code)
+}
+
+fn function(captures: Vec<LocalId>, args: Vec<ArgumentKind>, code: Vec<Code>) -> Code {
+    MakeClosure(Rc::new(FunctionPrototype {
+        name: None,
+        captures,
+        args,
+        code: code.into(),
+        spans: Rc::new([]),
+        file: None,
+    }))
+}
+
+#[test]
+#[rustfmt::skip]
+fn accepts_valid_code() {
+    // if true then 1 else 2, with a loop and a `with` around it.
+    assert_eq!(verify(&[
+        LoadNil, EnterWith,
+        LoadBool(true), JumpIfFalse(3), LoadInt(1), Jump(2), LoadInt(2),
+        UnloadTop, LoadBool(false), JumpIfTrue(-7),
+        ExitWith,
+        function(vec![], vec![ArgumentKind::Auto], vec![LoadLocal(LocalId(0)), Return]),
+        Call(0), Return,
+    ]), Ok(()));
+    // var a = 1 return func(b, ...c) return [a, b, c] end
+    assert_eq!(verify(&[
+        LoadInt(1), MakeLocal,
+        function(vec![LocalId(0)], vec![ArgumentKind::Auto, ArgumentKind::Rest], vec![
+            LoadLocal(LocalId(0)), LoadLocal(LocalId(1)), LoadLocal(LocalId(2)), MakeArray(3), Return,
+        ]),
+        Return,
+    ]), Ok(()));
+    // return { a = 1 }
+    assert_eq!(verify(&[LoadInt(1), LoadString("a".into()), MakeNamed, MakeTable(1), Return]), Ok(()));
+    // for x in [] do end
+    assert_eq!(verify(&[
+        MakeArray(0), IterStart, MakeLocal, LoadNil, MakeLocal,
+        IterNext(LocalId(0), 5), IterEnd(4), SetLocal(LocalId(1)), Jump(-3),
+        DropLocal(2), Exit,
+    ]), Ok(()));
+    // var a, b = f() return b, a
+    assert_eq!(verify(&[
+        closure(vec![LoadInt(1), LoadInt(2), ReturnMany(2)]), Call(0), Unpack(2), MakeLocal, MakeLocal,
+        LoadLocal(LocalId(0)), LoadLocal(LocalId(1)), ReturnMany(2),
+    ]), Ok(()));
+    // try error("x") catch e end
+    assert_eq!(verify(&[
+        Try(5), LoadString("x".into()), Builtin(BuiltinInstr::Error, 1), EndTry, Jump(3),
+        MakeLocal, DropLocal(1), Exit,
+    ]), Ok(()));
+    // var x = yield 1
+    assert_eq!(verify(&[LoadInt(1), Yield, MakeLocal, Exit]), Ok(()));
+    assert_eq!(verify(&[Exit]), Ok(()));
+    // The host may have made local variables before running the code.
+    assert_eq!(verify_with_locals(&[LoadLocal(LocalId(1)), Return], 2), Ok(()));
+}
+
+#[test]
+#[rustfmt::skip]
+fn rejects_invalid_code() {
+    assert_eq!(kind(&[Jump(5), Exit]), Some(VerifyErrorKind::JumpOutOfBounds { target: 5 }));
+    assert_eq!(kind(&[LoadBool(true), JumpIfTrue(-2), Exit]), Some(VerifyErrorKind::JumpOutOfBounds { target: -1 }));
+    assert_eq!(kind(&[UnloadTop, Exit]), Some(VerifyErrorKind::StackUnderflow));
+    assert_eq!(kind(&[LoadInt(1), Call(1), Return]), Some(VerifyErrorKind::StackUnderflow));
+    assert_eq!(kind(&[Return]), Some(VerifyErrorKind::StackUnderflow));
+    assert_eq!(kind(&[LoadInt(1), ReturnMany(2)]), Some(VerifyErrorKind::StackUnderflow));
+    assert_eq!(
+        kind(&[LoadBool(true), JumpIfTrue(2), LoadInt(1), Exit]),
+        Some(VerifyErrorKind::StackMismatch { expected: 1, found: 0 }),
+    );
+    assert_eq!(kind(&[ExitWith, Exit]), Some(VerifyErrorKind::UnbalancedWith));
+    assert_eq!(
+        kind(&[LoadBool(true), JumpIfTrue(3), LoadNil, EnterWith, Exit]),
+        Some(VerifyErrorKind::UnbalancedWith),
+    );
+    assert_eq!(kind(&[EndTry, Exit]), Some(VerifyErrorKind::UnbalancedTry));
+    // The `catch` block starts with the error on the stack.
+    assert_eq!(
+        kind(&[Try(3), EndTry, Jump(1), Exit]),
+        Some(VerifyErrorKind::StackMismatch { expected: 0, found: 1 }),
+    );
+    // The loop ends where the item is still on the stack.
+    assert_eq!(
+        kind(&[LoadNil, MakeLocal, IterNext(LocalId(0), 2), IterEnd(1), Exit]),
+        Some(VerifyErrorKind::StackMismatch { expected: 1, found: 0 }),
+    );
+    assert_eq!(kind(&[]), Some(VerifyErrorKind::FallsOffEnd));
+    assert_eq!(kind(&[LoadInt(1)]), Some(VerifyErrorKind::FallsOffEnd));
+    assert_eq!(
+        kind(&[LoadNil, Builtin(BuiltinInstr::Log, 1), Exit]),
+        Some(VerifyErrorKind::BuiltinArity { expected: 3, found: 1 }),
+    );
+    assert_eq!(
+        kind(&[LoadLocal(LocalId(0)), Return]),
+        Some(VerifyErrorKind::LocalOutOfRange { id: 0, locals: 0 }),
+    );
+    assert_eq!(
+        kind(&[LoadNil, MakeLocal, LoadNil, DropLocal(1), SetLocal(LocalId(0)), Exit]),
+        Some(VerifyErrorKind::LocalOutOfRange { id: 0, locals: 0 }),
+    );
+    // A numeric for uses the three locals after its first one too.
+    assert_eq!(
+        kind(&[LoadInt(1), MakeLocal, ForPrep(LocalId(0), 1), Exit]),
+        Some(VerifyErrorKind::LocalOutOfRange { id: 3, locals: 1 }),
+    );
+    assert_eq!(
+        kind(&[function(vec![LocalId(0)], vec![], vec![Exit]), Return]),
+        Some(VerifyErrorKind::LocalOutOfRange { id: 0, locals: 0 }),
+    );
+    // Arguments after the rest one are not in scope.
+    assert_eq!(
+        kind(&[
+            function(vec![], vec![ArgumentKind::Rest, ArgumentKind::Auto], vec![LoadLocal(LocalId(1)), Return]),
+            Return,
+        ]),
+        Some(VerifyErrorKind::LocalOutOfRange { id: 1, locals: 1 }),
+    );
+    assert_eq!(
+        kind(&[function(vec![], vec![ArgumentKind::Ref], vec![Exit]), Return]),
+        Some(VerifyErrorKind::RefArgument),
+    );
+    assert_eq!(kind(&[LoadNil, MakeLocal, DropLocal(2), Exit]), Some(VerifyErrorKind::LocalUnderflow));
+    assert_eq!(
+        kind(&[LoadBool(true), JumpIfTrue(3), LoadNil, MakeLocal, Exit]),
+        Some(VerifyErrorKind::LocalsMismatch { expected: 1, found: 0 }),
+    );
+    // An error in the `try` block goes back to the local variables it started with.
+    assert_eq!(
+        kind(&[LoadNil, MakeLocal, Try(4), DropLocal(1), EndTry, Exit, UnloadTop, Exit]),
+        Some(VerifyErrorKind::LocalUnderflow),
+    );
+    assert_eq!(
+        kind(&[LoadNil, Try(4), UnloadTop, EndTry, Exit, UnloadTop, Exit]),
+        Some(VerifyErrorKind::StackUnderflow),
+    );
+    assert_eq!(
+        kind(&[LoadNil, LoadString("a".into()), MakeNamed, Return]),
+        Some(VerifyErrorKind::NamedMismatch),
+    );
+    assert_eq!(kind(&[LoadInt(1), MakeTable(1), Return]), Some(VerifyErrorKind::NamedMismatch));
+    assert_eq!(
+        kind(&[
+            LoadNil, LoadString("a".into()), LoadBool(true), JumpIfTrue(3), MakeNamed, LoadNil,
+            MakeTable(1), Return,
+        ]),
+        Some(VerifyErrorKind::NamedMismatch),
+    );
+}
+
+#[test]
+fn reports_where_in_nested_functions() {
+    let code = [
+        LoadNil,
+        closure(vec![closure(vec![UnloadTop]), Return]),
+        Exit,
+    ];
+    let err = verify(&code).unwrap_err();
+    assert_eq!(
+        err,
+        VerifyError {
+            kind: VerifyErrorKind::StackUnderflow,
+            pc: 0,
+            path: vec![1, 0],
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "stack underflow at pc 0 in the function created at pc 1 > 0"
+    );
+}
+
+#[test]
+fn execute_rejects_invalid_code() {
+    let mut runtime = Runtime::new();
+    let err = vm::execute(&[Jump(-1), Exit], &mut runtime).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidCode);
+    assert_eq!(
+        err.message,
+        "Invalid code: jump to -1 is out of bounds at pc 0"
+    );
+}